@@ -0,0 +1,299 @@
+//! `#[derive(C12Config)]` for [c12-parser](https://docs.rs/c12-parser).
+//! Use it through c12-parser's `derive` feature, which re-exports it next
+//! to the `C12Config` trait it implements.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::meta::ParseNestedMeta;
+use syn::{Data, DeriveInput, Expr, ExprLit, ExprPath, Fields, Lit, LitStr, Type, parenthesized};
+
+/// Implements `c12_parser::C12Config` for a struct with named fields.
+///
+/// On the struct, `#[c12(...)]` takes:
+///
+/// - `name = "app"`: the config name, `CARGO_PKG_NAME` by default;
+/// - `env_prefix = "APP_"`: read environment variables with this prefix;
+/// - `schema = "schema.json"`: a JSON Schema file, relative to the crate
+///   root, checked at compile time and embedded;
+/// - `validate = "path::to::fn"`: a `fn(&Self) -> Result<(), E>` with
+///   `E: Display`, run on the loaded config.
+///
+/// On a field, `#[c12(default = <expr>)]` gives the value used when no
+/// layer sets it. String literals are converted into the field's type.
+#[proc_macro_derive(C12Config, attributes(c12))]
+pub fn derive_c12_config(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct StructAttrs {
+    name: Option<LitStr>,
+    env_prefix: Option<LitStr>,
+    schema: Option<LitStr>,
+    validate: Option<ExprPath>,
+    rename_all: Option<LitStr>,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "C12Config can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "C12Config needs a struct with named fields",
+        ));
+    };
+
+    let mut attrs = StructAttrs::default();
+    for attr in &input.attrs {
+        if attr.path().is_ident("c12") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    attrs.name = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("env_prefix") {
+                    attrs.env_prefix = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("schema") {
+                    attrs.schema = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("validate") {
+                    attrs.validate = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                } else {
+                    return Err(meta.error(
+                        "unknown c12 attribute, expected `name`, `env_prefix`, `schema` \
+                         or `validate`",
+                    ));
+                }
+                Ok(())
+            })?;
+        } else if attr.path().is_ident("serde") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename_all") && meta.input.peek(syn::Token![=]) {
+                    attrs.rename_all = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else {
+                    skip_meta(&meta)
+                }
+            })?;
+        }
+    }
+
+    let mut defaults = Vec::new();
+    for field in &fields.named {
+        let mut default = None;
+        let mut rename = None;
+        for attr in &field.attrs {
+            if attr.path().is_ident("c12") {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("default") {
+                        default = Some(meta.value()?.parse::<Expr>()?);
+                        Ok(())
+                    } else {
+                        Err(meta.error("unknown c12 field attribute, expected `default`"))
+                    }
+                })?;
+            } else if attr.path().is_ident("serde") {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("rename") && meta.input.peek(syn::Token![=]) {
+                        rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                        Ok(())
+                    } else {
+                        skip_meta(&meta)
+                    }
+                })?;
+            }
+        }
+        let Some(default) = default else { continue };
+        let ident = field.ident.as_ref().expect("named field");
+        let key = match (rename, &attrs.rename_all) {
+            (Some(key), _) => key,
+            (None, Some(rule)) => rename_field(&ident.to_string(), rule)?,
+            (None, None) => ident.to_string().trim_start_matches("r#").to_string(),
+        };
+        defaults.push(default_entry(&key, &field.ty, &default));
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let name = match &attrs.name {
+        Some(name) => quote!(#name),
+        None => quote!(env!("CARGO_PKG_NAME")),
+    };
+    let env_prefix = match &attrs.env_prefix {
+        Some(prefix) => quote!(::core::option::Option::Some(#prefix.into())),
+        None => quote!(::core::option::Option::None),
+    };
+    let schema = attrs.schema.as_ref().map(schema_fn).transpose()?;
+    let validate = attrs.validate.as_ref().map(|path| {
+        quote! {
+            fn validate(&self) -> ::core::result::Result<(), ::std::string::String> {
+                #path(self).map_err(|e| ::std::string::ToString::to_string(&e))
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::c12_parser::C12Config for #ident #ty_generics #where_clause {
+            fn load_options() -> ::c12_parser::LoadOptions {
+                #[allow(unused_mut)]
+                let mut defaults = ::c12_parser::__private::Map::new();
+                #(#defaults)*
+                ::c12_parser::LoadOptions {
+                    name: #name.into(),
+                    env_prefix: #env_prefix,
+                    defaults: (!defaults.is_empty())
+                        .then(|| ::c12_parser::__private::Value::Object(defaults)),
+                    ..::core::default::Default::default()
+                }
+            }
+            #schema
+            #validate
+        }
+    })
+}
+
+/// Skips a `serde` attribute entry this macro has no use for.
+fn skip_meta(meta: &ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(syn::Token![=]) {
+        meta.value()?.parse::<Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        let _content;
+        parenthesized!(_content in meta.input);
+    }
+    Ok(())
+}
+
+/// Inserts the field's default under `key`, typed as the field so the
+/// value serializes as the field would.
+fn default_entry(key: &str, ty: &Type, default: &Expr) -> TokenStream2 {
+    let value = match default {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(_), ..
+        }) => quote!(::core::convert::Into::into(#default)),
+        _ => quote!(#default),
+    };
+    let message = format!("the default of `{key}` cannot be serialized");
+    quote! {
+        let value: #ty = #value;
+        defaults.insert(
+            #key.into(),
+            ::c12_parser::__private::to_value(value).expect(#message),
+        );
+    }
+}
+
+/// The `schema()` method embedding the file, after checking it is JSON.
+fn schema_fn(path: &LitStr) -> syn::Result<TokenStream2> {
+    let dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let file = std::path::Path::new(&dir).join(path.value());
+    let text = std::fs::read_to_string(&file).map_err(|e| {
+        syn::Error::new(path.span(), format!("cannot read {}: {e}", file.display()))
+    })?;
+    serde_json::from_str::<serde_json::Value>(&text).map_err(|e| {
+        syn::Error::new(path.span(), format!("{} is not JSON: {e}", file.display()))
+    })?;
+    Ok(quote! {
+        fn schema() -> ::core::option::Option<::c12_parser::Schema> {
+            let text = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/", #path));
+            let schema = ::c12_parser::__private::from_str(text).expect("the schema is JSON");
+            ::core::option::Option::Some(::c12_parser::Schema::new(schema))
+        }
+    })
+}
+
+/// A snake_case field name as serde's `rename_all = "<rule>"` renames it.
+fn rename_field(field: &str, rule: &LitStr) -> syn::Result<String> {
+    let field = field.trim_start_matches("r#");
+    let words = field.split('_').filter(|w| !w.is_empty());
+    let capitalized = |w: &str| {
+        let mut chars = w.chars();
+        chars
+            .next()
+            .map_or_else(String::new, |c| c.to_uppercase().chain(chars).collect())
+    };
+    Ok(match rule.value().as_str() {
+        "lowercase" => field.to_lowercase(),
+        "UPPERCASE" => field.to_uppercase(),
+        "snake_case" => field.to_string(),
+        "SCREAMING_SNAKE_CASE" => field.to_uppercase(),
+        "kebab-case" => field.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => field.replace('_', "-").to_uppercase(),
+        "PascalCase" => words.map(capitalized).collect(),
+        "camelCase" => words
+            .enumerate()
+            .map(|(i, w)| {
+                if i == 0 {
+                    w.to_string()
+                } else {
+                    capitalized(w)
+                }
+            })
+            .collect(),
+        other => {
+            return Err(syn::Error::new(
+                rule.span(),
+                format!("unknown rename_all rule `{other}`"),
+            ));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    #[test]
+    fn keys_defaults_as_serde_names_them() {
+        let tokens = expand(parse_quote! {
+            #[c12(name = "app", env_prefix = "APP_")]
+            #[serde(rename_all = "camelCase", deny_unknown_fields)]
+            struct Config {
+                #[c12(default = 8080)]
+                listen_port: u16,
+                #[serde(rename = "host-name", default)]
+                #[c12(default = "localhost")]
+                host: String,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                tls: Option<bool>,
+            }
+        })
+        .unwrap()
+        .to_string();
+        assert!(tokens.contains("\"listenPort\""), "{tokens}");
+        assert!(tokens.contains("\"host-name\""), "{tokens}");
+        assert!(!tokens.contains("\"tls\""), "{tokens}");
+        assert!(tokens.contains("Some (\"APP_\" . into ())"), "{tokens}");
+    }
+
+    #[test]
+    fn rejects_what_it_cannot_derive() {
+        let error = |input: DeriveInput| expand(input).unwrap_err().to_string();
+        assert_eq!(
+            error(parse_quote! { enum Mode { Dev, Prod } }),
+            "C12Config can only be derived for structs"
+        );
+        assert_eq!(
+            error(parse_quote! {
+                struct Config {
+                    #[c12(defualt = 1)]
+                    port: u16,
+                }
+            }),
+            "unknown c12 field attribute, expected `default`"
+        );
+        assert!(
+            error(parse_quote! {
+                #[c12(schema = "missing.schema.json")]
+                struct Config { port: u16 }
+            })
+            .starts_with("cannot read ")
+        );
+    }
+}
@@ -0,0 +1,261 @@
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::diagnostic::{Code, Diagnostic, MessageArgs, as_diagnostic};
+use crate::doctor::Severity;
+
+/// A diagnostic recorded against one item of a batch, detached from the
+/// error type that produced it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ItemDiagnostic {
+    pub severity: Severity,
+    /// `None` for errors from outside this crate, such as I/O errors.
+    pub code: Option<Code>,
+    /// The English message.
+    pub message: String,
+    /// The values behind `message`, see [`Diagnostic::args`].
+    pub args: MessageArgs,
+}
+
+impl ItemDiagnostic {
+    /// Records `diagnostic` at its own severity.
+    pub fn new(diagnostic: &dyn Diagnostic) -> Self {
+        Self {
+            severity: diagnostic.severity(),
+            code: Some(diagnostic.code()),
+            message: diagnostic.to_string(),
+            args: diagnostic.args(),
+        }
+    }
+
+    /// Records `diagnostic` as a warning, for problems that did not stop
+    /// the item, such as data a conversion dropped.
+    pub fn warning(diagnostic: &dyn Diagnostic) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::new(diagnostic)
+        }
+    }
+
+    /// An error without a code.
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            code: None,
+            message: message.into(),
+            args: Vec::new(),
+        }
+    }
+
+    /// Records `error` as an error, with its code when it is one of this
+    /// crate's errors.
+    pub fn from_error(error: &(dyn Error + 'static)) -> Self {
+        match as_diagnostic(error) {
+            Some(diagnostic) => Self {
+                severity: Severity::Error,
+                ..Self::new(diagnostic)
+            },
+            None => Self::error(error.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for ItemDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        };
+        match self.code {
+            Some(code) => write!(f, "{severity}[{code}]: {}", self.message),
+            None => write!(f, "{severity}: {}", self.message),
+        }
+    }
+}
+
+/// What happened to one item.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ItemOutcome<T> {
+    /// Done, possibly with warnings.
+    Succeeded {
+        value: T,
+        warnings: Vec<ItemDiagnostic>,
+    },
+    /// Left alone, with the reason.
+    Skipped(String),
+    /// Not done; at least one diagnostic says why.
+    Failed(Vec<ItemDiagnostic>),
+}
+
+/// One file a batch operation visited.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BatchItem<T> {
+    /// The file, relative to the directory the batch ran over.
+    pub source: PathBuf,
+    pub outcome: ItemOutcome<T>,
+}
+
+/// Counts over a [`BatchReport`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BatchStats {
+    pub total: usize,
+    pub succeeded: usize,
+    /// Succeeded items with at least one warning.
+    pub with_warnings: usize,
+    pub skipped: usize,
+    pub failed: usize,
+}
+
+impl fmt::Display for BatchStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} succeeded", self.succeeded)?;
+        if self.with_warnings > 0 {
+            write!(f, " ({} with warnings)", self.with_warnings)?;
+        }
+        write!(f, ", {} failed, {} skipped", self.failed, self.skipped)
+    }
+}
+
+/// The per-item results of a batch operation, in path order. A bad item
+/// is recorded and the batch goes on, so one report covers every item.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BatchReport<T> {
+    pub items: Vec<BatchItem<T>>,
+}
+
+impl<T> Default for BatchReport<T> {
+    fn default() -> Self {
+        Self { items: Vec::new() }
+    }
+}
+
+impl<T> BatchReport<T> {
+    pub fn stats(&self) -> BatchStats {
+        let mut stats = BatchStats {
+            total: self.items.len(),
+            ..BatchStats::default()
+        };
+        for item in &self.items {
+            match &item.outcome {
+                ItemOutcome::Succeeded { warnings, .. } => {
+                    stats.succeeded += 1;
+                    stats.with_warnings += usize::from(!warnings.is_empty());
+                }
+                ItemOutcome::Skipped(_) => stats.skipped += 1,
+                ItemOutcome::Failed(_) => stats.failed += 1,
+            }
+        }
+        stats
+    }
+
+    /// Whether no item failed; warnings and skips are allowed.
+    pub fn is_success(&self) -> bool {
+        self.stats().failed == 0
+    }
+
+    /// The failed items with their diagnostics.
+    pub fn failures(&self) -> impl Iterator<Item = (&PathBuf, &[ItemDiagnostic])> {
+        self.items.iter().filter_map(|item| match &item.outcome {
+            ItemOutcome::Failed(diagnostics) => Some((&item.source, diagnostics.as_slice())),
+            _ => None,
+        })
+    }
+
+    /// The values of the succeeded items.
+    pub fn values(&self) -> impl Iterator<Item = (&PathBuf, &T)> {
+        self.items.iter().filter_map(|item| match &item.outcome {
+            ItemOutcome::Succeeded { value, .. } => Some((&item.source, value)),
+            _ => None,
+        })
+    }
+}
+
+/// One line per item, with its diagnostics indented below it, then the
+/// [stats](BatchReport::stats).
+impl<T: fmt::Display> fmt::Display for BatchReport<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for item in &self.items {
+            let source = item.source.display();
+            let diagnostics = match &item.outcome {
+                ItemOutcome::Succeeded { value, warnings } => {
+                    writeln!(f, "{source}: {value}")?;
+                    warnings.as_slice()
+                }
+                ItemOutcome::Skipped(reason) => {
+                    writeln!(f, "{source}: skipped, {reason}")?;
+                    &[]
+                }
+                ItemOutcome::Failed(diagnostics) => {
+                    writeln!(f, "{source}: failed")?;
+                    diagnostics.as_slice()
+                }
+            };
+            for diagnostic in diagnostics {
+                writeln!(f, "  {diagnostic}")?;
+            }
+        }
+        write!(f, "{}", self.stats())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::C12Error;
+    use crate::format::Format;
+    use crate::loss::{Loss, LossKind};
+
+    #[test]
+    fn diagnostics_keep_codes_of_crate_errors() {
+        let parse = C12Error::at_offset(Format::Toml, "a = \n", 4, "expected a value".into());
+        let diagnostic = ItemDiagnostic::from_error(&parse);
+        assert_eq!(diagnostic.code, Some(Code::Parse));
+        assert_eq!(
+            diagnostic.to_string(),
+            "error[C12E021]: toml error at line 1, column 5: expected a value\n  | a = \n  |     ^"
+        );
+        let io = std::io::Error::new(std::io::ErrorKind::NotFound, "gone");
+        assert_eq!(ItemDiagnostic::from_error(&io).to_string(), "error: gone");
+    }
+
+    #[test]
+    fn reports_aggregate_every_item() {
+        let loss = Loss {
+            path: crate::path::Path::root().key("a"),
+            line: None,
+            kind: LossKind::Null,
+        };
+        let report = BatchReport {
+            items: vec![
+                BatchItem {
+                    source: "a.json".into(),
+                    outcome: ItemOutcome::Succeeded {
+                        value: "ok",
+                        warnings: vec![ItemDiagnostic::warning(&loss)],
+                    },
+                },
+                BatchItem {
+                    source: "b.json".into(),
+                    outcome: ItemOutcome::Failed(vec![ItemDiagnostic::error("denied")]),
+                },
+                BatchItem {
+                    source: "c.md".into(),
+                    outcome: ItemOutcome::Skipped("not config".into()),
+                },
+            ],
+        };
+        assert!(!report.is_success());
+        assert_eq!(report.failures().count(), 1);
+        assert_eq!(
+            report.values().collect::<Vec<_>>(),
+            [(&"a.json".into(), &"ok")]
+        );
+        assert_eq!(
+            report.to_string(),
+            "a.json: ok\n  warning[C12E001]: null at `a`\nb.json: failed\n  error: denied\n\
+             c.md: skipped, not config\n1 succeeded (1 with warnings), 1 failed, 1 skipped"
+        );
+    }
+}
@@ -0,0 +1,389 @@
+use std::error::Error;
+
+use regex::Regex;
+
+use crate::emit::{Dialect, EmitStyle, is_identifier, write_string};
+use crate::format::{Format, FormatOptions, StyleProfile};
+use crate::minify::{LexError, Lexeme, lex};
+use crate::normalize::glob_regex;
+use crate::path::{join_index, join_key};
+
+/// Line width used when [`FormatOptions::line_width`] is not set.
+const DEFAULT_LINE_WIDTH: usize = 80;
+
+/// How a container is laid out by [`beautify`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layout {
+    /// On a single line, whatever its width.
+    Inline,
+    /// One element per line.
+    Expanded,
+}
+
+/// Extra options for [`beautify`].
+#[derive(Clone, Debug, Default)]
+pub struct BeautifyOptions {
+    /// Layouts forced per path glob (see
+    /// [`Normalizers`](crate::Normalizers) for the glob syntax), e.g.
+    /// `("matrix", Layout::Inline)`. The first matching glob wins.
+    pub overrides: Vec<(String, Layout)>,
+}
+
+/// Lays out minified JSON, JSONC or JSON5 text for reading. Key order and
+/// number spellings are kept; comments are dropped.
+///
+/// Objects get one entry per line. Arrays are kept on one line when they
+/// only hold scalars (or arrays of scalars) and fit within the line width
+/// (`line_width`, default 80), otherwise they get one element per line.
+/// Indentation defaults to two spaces. For JSON5 the
+/// [`StyleProfile::Native`] profile adds trailing commas to expanded
+/// containers, like [`stringify_json5`](crate::stringify_json5).
+///
+/// ```
+/// use c12_parser::{Format, beautify};
+///
+/// let out = beautify(r#"{"z":1,"tags":["a","b"],"db":{"port":5432}}"#, Format::Json, None, None)?;
+/// assert_eq!(out, "{\n  \"z\": 1,\n  \"tags\": [\"a\", \"b\"],\n  \"db\": {\n    \"port\": 5432\n  }\n}\n");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn beautify(
+    text: &str,
+    format: Format,
+    fmt_options: Option<FormatOptions>,
+    options: Option<BeautifyOptions>,
+) -> Result<String, Box<dyn Error>> {
+    let fmt_opts = fmt_options.unwrap_or_default();
+    let opts = options.unwrap_or_default();
+    let dialect = match format {
+        Format::Json | Format::Jsonc => Dialect::Json,
+        Format::Json5 => Dialect::Json5,
+        other => return Err(format!("cannot beautify {other}, only JSON-like formats").into()),
+    };
+
+    let lexemes: Vec<_> = lex(text)?
+        .into_iter()
+        .filter(|(_, l)| !l.is_comment())
+        .collect();
+    let mut reader = Reader {
+        lexemes: &lexemes,
+        pos: 0,
+        len: text.len(),
+    };
+    let root = reader.node()?;
+    if reader.pos < lexemes.len() {
+        return Err(reader.error("trailing content").into());
+    }
+
+    let mut printer = Printer {
+        style: EmitStyle {
+            dialect,
+            indent: " ".repeat(fmt_opts.indent.unwrap_or(2)),
+            line_width: Some(fmt_opts.line_width.unwrap_or(DEFAULT_LINE_WIDTH)),
+            escaping: fmt_opts.escaping,
+            js_compat: fmt_opts.profile == StyleProfile::Confbox,
+            trailing_commas: dialect == Dialect::Json5
+                && fmt_opts
+                    .trailing_commas
+                    .unwrap_or(fmt_opts.profile != StyleProfile::Confbox),
+        },
+        overrides: opts
+            .overrides
+            .iter()
+            .map(|(glob, layout)| (glob_regex(glob), *layout))
+            .collect(),
+        out: String::new(),
+    };
+    printer.node(&root, "", 0);
+    printer.out.push('\n');
+    Ok(printer.out)
+}
+
+/// A parsed value that keeps entry order and scalar spellings.
+enum Node<'a> {
+    Str(&'a str),
+    Atom(&'a str),
+    Array(Vec<Node<'a>>),
+    Object(Vec<(&'a str, Node<'a>)>),
+}
+
+impl Node<'_> {
+    fn is_inlinable(&self) -> bool {
+        match self {
+            Node::Object(_) => false,
+            Node::Array(items) => items.iter().all(Node::is_inlinable),
+            _ => true,
+        }
+    }
+}
+
+struct Reader<'a> {
+    lexemes: &'a [(usize, Lexeme<'a>)],
+    pos: usize,
+    /// Offset reported for errors at the end of the input.
+    len: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// An error at the lexeme at `pos`, or at the end of the input.
+    fn error_at(&self, pos: usize, message: &'static str) -> LexError {
+        let offset = self
+            .lexemes
+            .get(pos)
+            .map_or(self.len, |(offset, _)| *offset);
+        LexError { offset, message }
+    }
+
+    /// An error at the next lexeme.
+    fn error(&self, message: &'static str) -> LexError {
+        self.error_at(self.pos, message)
+    }
+
+    /// An error at the lexeme just read.
+    fn error_before(&self, message: &'static str) -> LexError {
+        self.error_at(self.pos - 1, message)
+    }
+
+    fn next(&mut self) -> Option<&'a Lexeme<'a>> {
+        let (_, lexeme) = self.lexemes.get(self.pos)?;
+        self.pos += 1;
+        Some(lexeme)
+    }
+
+    fn eat(&mut self, punct: char) -> bool {
+        let found = self.lexemes.get(self.pos).map(|(_, l)| l) == Some(&Lexeme::Punct(punct));
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn node(&mut self) -> Result<Node<'a>, LexError> {
+        let lexeme = self.next().ok_or(self.error("unexpected end of input"))?;
+        match lexeme {
+            Lexeme::Str(s) => Ok(Node::Str(s)),
+            Lexeme::Atom(raw) => Ok(Node::Atom(raw)),
+            Lexeme::Punct('[') => {
+                let mut items = Vec::new();
+                while !self.eat(']') {
+                    items.push(self.node()?);
+                    if self.eat(']') {
+                        break;
+                    } else if !self.eat(',') {
+                        return Err(self.error("expected `,` or `]`"));
+                    }
+                }
+                Ok(Node::Array(items))
+            }
+            Lexeme::Punct('{') => {
+                let mut entries = Vec::new();
+                while !self.eat('}') {
+                    let key = match self.next() {
+                        Some(Lexeme::Str(s)) => s.as_str(),
+                        Some(Lexeme::Atom(raw)) => raw,
+                        _ => return Err(self.error_before("expected a key")),
+                    };
+                    if !self.eat(':') {
+                        return Err(self.error("expected `:`"));
+                    }
+                    entries.push((key, self.node()?));
+                    if self.eat('}') {
+                        break;
+                    } else if !self.eat(',') {
+                        return Err(self.error("expected `,` or `}`"));
+                    }
+                }
+                Ok(Node::Object(entries))
+            }
+            _ => Err(self.error_before("unexpected token")),
+        }
+    }
+}
+
+struct Printer {
+    style: EmitStyle,
+    overrides: Vec<(Regex, Layout)>,
+    out: String,
+}
+
+impl Printer {
+    fn node(&mut self, node: &Node<'_>, path: &str, depth: usize) {
+        match node {
+            Node::Str(s) => write_string(&mut self.out, s, &self.style),
+            Node::Atom(raw) => self.out.push_str(raw),
+            Node::Array(items) if items.is_empty() => self.out.push_str("[]"),
+            Node::Object(entries) if entries.is_empty() => self.out.push_str("{}"),
+            Node::Array(items) => {
+                let layout = self.layout(path).unwrap_or_else(|| {
+                    let fits = node.is_inlinable() && {
+                        let mut inline = String::new();
+                        Printer::inline(&mut inline, node, &self.style);
+                        // Leave room for a trailing comma.
+                        self.column() + inline.chars().count() < self.style.line_width.unwrap()
+                    };
+                    if fits {
+                        Layout::Inline
+                    } else {
+                        Layout::Expanded
+                    }
+                });
+                if layout == Layout::Inline {
+                    Printer::inline(&mut self.out, node, &self.style);
+                    return;
+                }
+                self.out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    self.separator(i, depth + 1);
+                    self.node(item, &join_index(path, i), depth + 1);
+                }
+                self.close(']', depth);
+            }
+            Node::Object(entries) => {
+                if self.layout(path) == Some(Layout::Inline) {
+                    Printer::inline(&mut self.out, node, &self.style);
+                    return;
+                }
+                self.out.push('{');
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    self.separator(i, depth + 1);
+                    self.key(key);
+                    self.out.push(' ');
+                    self.node(value, &join_key(path, key), depth + 1);
+                }
+                self.close('}', depth);
+            }
+        }
+    }
+
+    fn layout(&self, path: &str) -> Option<Layout> {
+        self.overrides
+            .iter()
+            .find(|(glob, _)| glob.is_match(path))
+            .map(|(_, layout)| *layout)
+    }
+
+    fn key(&mut self, key: &str) {
+        write_key(&mut self.out, key, &self.style);
+    }
+
+    fn separator(&mut self, index: usize, depth: usize) {
+        if index > 0 {
+            self.out.push(',');
+        }
+        self.newline(depth);
+    }
+
+    fn close(&mut self, bracket: char, depth: usize) {
+        if self.style.trailing_commas {
+            self.out.push(',');
+        }
+        self.newline(depth);
+        self.out.push(bracket);
+    }
+
+    fn newline(&mut self, depth: usize) {
+        self.out.push('\n');
+        for _ in 0..depth {
+            self.out.push_str(&self.style.indent);
+        }
+    }
+
+    fn column(&self) -> usize {
+        let line_start = self.out.rfind('\n').map_or(0, |pos| pos + 1);
+        self.out[line_start..].chars().count()
+    }
+
+    fn inline(out: &mut String, node: &Node<'_>, style: &EmitStyle) {
+        match node {
+            Node::Str(s) => write_string(out, s, style),
+            Node::Atom(raw) => out.push_str(raw),
+            Node::Array(items) => {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    Printer::inline(out, item, style);
+                }
+                out.push(']');
+            }
+            Node::Object(entries) => {
+                out.push('{');
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    out.push_str(if i > 0 { ", " } else { " " });
+                    write_key(out, key, style);
+                    out.push(' ');
+                    Printer::inline(out, value, style);
+                }
+                out.push_str(if entries.is_empty() { "}" } else { " }" });
+            }
+        }
+    }
+}
+
+fn write_key(out: &mut String, key: &str, style: &EmitStyle) {
+    if style.dialect == Dialect::Json5 && is_identifier(key) {
+        out.push_str(key);
+    } else {
+        write_string(out, key, style);
+    }
+    out.push(':');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_arrays_by_line_width() {
+        let text =
+            r#"{"short":[1,2,3],"long":["aaaaaaaaaa","bbbbbbbbbb","cccccccccc"],"rows":[{"a":1}]}"#;
+        let opts = FormatOptions {
+            indent: Some(4),
+            line_width: Some(30),
+            ..Default::default()
+        };
+        assert_eq!(
+            beautify(text, Format::Json, Some(opts), None).unwrap(),
+            r#"{
+    "short": [1, 2, 3],
+    "long": [
+        "aaaaaaaaaa",
+        "bbbbbbbbbb",
+        "cccccccccc"
+    ],
+    "rows": [
+        {
+            "a": 1
+        }
+    ]
+}
+"#
+        );
+    }
+
+    #[test]
+    fn applies_layout_overrides_and_json5_style() {
+        let text = "{point:{x:1,y:2},tags:['a','b'],'a b':0x1F}";
+        let options = BeautifyOptions {
+            overrides: vec![
+                ("point".into(), Layout::Inline),
+                ("tags".into(), Layout::Expanded),
+            ],
+        };
+        assert_eq!(
+            beautify(text, Format::Json5, None, Some(options)).unwrap(),
+            "{\n  point: { x: 1, y: 2 },\n  tags: [\n    \"a\",\n    \"b\",\n  ],\n  \"a b\": 0x1F,\n}\n"
+        );
+    }
+
+    #[test]
+    fn beautify_inverts_minify() {
+        let text = "{\n  \"b\": [true, null],\n  \"a\": {\n    \"x\": \"y\"\n  }\n}\n";
+        let minified = crate::minify(text, Format::Json, false).unwrap();
+        assert_eq!(beautify(&minified, Format::Json, None, None).unwrap(), text);
+        let err = beautify("{\"a\":1", Format::Json, None, None).unwrap_err();
+        assert_eq!(err.to_string(), "expected `,` or `}` at offset 6");
+        let err = beautify("[1] 2", Format::Json, None, None).unwrap_err();
+        assert_eq!(err.to_string(), "trailing content at offset 4");
+    }
+}
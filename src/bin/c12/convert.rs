@@ -0,0 +1,154 @@
+//! `c12 convert`: one-shot conversion between formats, from a file or
+//! stdin to stdout or a file, or of every config under a directory.
+
+use std::error::Error;
+use std::io::Read as _;
+use std::path::Path as FsPath;
+use std::process::ExitCode;
+
+use c12_parser::{
+    ConvertFilters, ConvertOptions, Format, FormatOptions, convert, convert_tree, sniff_format,
+};
+
+const USAGE: &str = "usage: c12 convert [<file>|-] --to <format> [--from <format>] \
+                     [--indent <n>] [--sort-keys] [--out <file>]\n       \
+                     c12 convert -r <dir> --to <format> [--from <format>] [--indent <n>] \
+                     [--out <dir>]";
+
+pub fn main(args: &[String]) -> Result<ExitCode, Box<dyn Error>> {
+    let args: Vec<String> = args
+        .iter()
+        .map(|arg| match arg.as_str() {
+            "-r" => "--recursive".to_string(),
+            _ => arg.clone(),
+        })
+        .collect();
+    let args = crate::Args::parse_with_flags(
+        &args,
+        &["to", "from", "indent", "out"],
+        &["sort-keys", "recursive"],
+    )?;
+    let to = format_option(args.option("to").ok_or(USAGE)?)?;
+    let indent = args
+        .option("indent")
+        .map(|indent| {
+            indent
+                .parse()
+                .map_err(|_| format!("`{indent}` is not an indent"))
+        })
+        .transpose()?;
+    let mut format = FormatOptions::default();
+    format.indent = indent;
+
+    if args.flag("recursive") {
+        let [dir] = args.positional[..] else {
+            return Err(USAGE.into());
+        };
+        if args.flag("sort-keys") {
+            return Err("`--sort-keys` cannot be combined with `--recursive`".into());
+        }
+        let filters = ConvertFilters {
+            formats: args
+                .option("from")
+                .map(format_option)
+                .transpose()?
+                .into_iter()
+                .collect(),
+            options: format,
+            ..ConvertFilters::default()
+        };
+        let out = args.option("out").unwrap_or(dir);
+        let (report, converted) = convert_dir(FsPath::new(dir), FsPath::new(out), to, &filters)?;
+        print!("{report}");
+        return Ok(if converted {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::from(1)
+        });
+    }
+
+    let input = match args.positional[..] {
+        [] | ["-"] => None,
+        [file] => Some(file),
+        _ => return Err(USAGE.into()),
+    };
+    let (text, from) = match (input, args.option("from")) {
+        (Some(file), None) => crate::read_config(file)?,
+        (Some(file), Some(from)) => (std::fs::read_to_string(file)?, format_option(from)?),
+        (None, from) => {
+            let mut text = String::new();
+            std::io::stdin().read_to_string(&mut text)?;
+            let from = match from {
+                Some(from) => format_option(from)?,
+                None => sniff_format(&text)
+                    .ok_or("cannot tell the format of stdin, pass `--from <format>`")?,
+            };
+            (text, from)
+        }
+    };
+    let options = ConvertOptions {
+        format,
+        sort_keys: args.flag("sort-keys"),
+        ..ConvertOptions::default()
+    };
+
+    let out = convert(&text, from, to, options)?;
+    match args.option("out") {
+        Some(file) => std::fs::write(file, out)?,
+        None if out.ends_with('\n') => print!("{out}"),
+        None => println!("{out}"),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Converts every config under `dir` into `out` with
+/// [`convert_tree`], returning the report and whether no file failed.
+fn convert_dir(
+    dir: &FsPath,
+    out: &FsPath,
+    to: Format,
+    filters: &ConvertFilters,
+) -> Result<(String, bool), Box<dyn Error>> {
+    let report = convert_tree(dir, out, to, filters)?;
+    Ok((report.to_string(), report.is_success()))
+}
+
+fn format_option(name: &str) -> Result<Format, String> {
+    Format::from_name(name).ok_or_else(|| format!("unknown format `{name}`"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_a_directory_recursively() {
+        let dir = std::env::temp_dir().join(format!("c12-cli-convert-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("src/nested")).unwrap();
+        std::fs::write(dir.join("src/app.json"), r#"{ "port": 80 }"#).unwrap();
+        std::fs::write(dir.join("src/nested/db.toml"), "url = \"x\"").unwrap();
+        std::fs::write(dir.join("src/nested/bad.json"), "{").unwrap();
+
+        let filters = ConvertFilters {
+            formats: vec![Format::Toml],
+            ..ConvertFilters::default()
+        };
+        let (report, converted) =
+            convert_dir(&dir.join("src"), &dir.join("out"), Format::Yaml, &filters).unwrap();
+        assert!(converted, "{report}");
+        assert_eq!(
+            std::fs::read_to_string(dir.join("out/nested/db.yaml")).unwrap(),
+            "url: x\n"
+        );
+        assert!(!dir.join("out/app.yaml").exists());
+
+        let all = ConvertFilters::default();
+        let (report, converted) =
+            convert_dir(&dir.join("src"), &dir.join("out"), Format::Yaml, &all).unwrap();
+        assert!(!converted);
+        assert!(report.contains("nested/bad.json: failed"), "{report}");
+        assert!(dir.join("out/app.yaml").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,72 @@
+//! `c12 diff`: compare two configs by value, whatever their formats.
+
+use std::error::Error;
+use std::process::ExitCode;
+
+use c12_parser::{Change, ConvertOptions, Format, convert, diff_values};
+use serde_json::Value as JsonValue;
+
+/// Prints one line per added, removed or changed key and exits with 1 when
+/// there are any, 0 when the configs hold the same values, as `diff` does.
+pub fn main(args: &[String]) -> Result<ExitCode, Box<dyn Error>> {
+    let args = crate::Args::parse(args, &[])?;
+    let [a, b] = args.positional[..] else {
+        return Err("usage: c12 diff <file> <file>".into());
+    };
+    let changes = changes(&crate::read_config(a)?, &crate::read_config(b)?)?;
+    for change in &changes {
+        println!("{change}");
+    }
+    Ok(if changes.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(1)
+    })
+}
+
+fn changes(a: &(String, Format), b: &(String, Format)) -> Result<Vec<Change>, Box<dyn Error>> {
+    Ok(diff_values(&value(a)?, &value(b)?))
+}
+
+/// The config's value as JSON reads it, so TOML datetimes compare equal
+/// to the same dates written as strings elsewhere.
+fn value((text, format): &(String, Format)) -> Result<JsonValue, Box<dyn Error>> {
+    let json = convert(text, *format, Format::Json, ConvertOptions::default())?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_values_across_formats() {
+        let yaml = (
+            "# web\nserver:\n  port: 80\n  since: 2024-05-01\n".to_string(),
+            Format::Yaml,
+        );
+        let toml = (
+            "[server]\nsince = 2024-05-01\nport = 80\n".to_string(),
+            Format::Toml,
+        );
+        assert!(changes(&yaml, &toml).unwrap().is_empty());
+
+        let json = (
+            r#"{ "server": { "port": 8080 }, "tls": true }"#.to_string(),
+            Format::Json,
+        );
+        let lines: Vec<_> = changes(&toml, &json)
+            .unwrap()
+            .iter()
+            .map(Change::to_string)
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "- server.since = \"2024-05-01\"",
+                "~ server.port: 80 -> 8080",
+                "+ tls = true"
+            ]
+        );
+    }
+}
@@ -0,0 +1,118 @@
+//! `c12 doctor`: lint a config's keys and, given a JSON Schema, check it
+//! for schema problems, unknown, deprecated and expired keys.
+
+use std::error::Error;
+use std::process::ExitCode;
+
+use c12_parser::{
+    C12Context, Format, Location, Severity, doctor_with_context, locate, parse_format,
+};
+use serde_json::Value as JsonValue;
+
+const USAGE: &str = "usage: c12 doctor <file> [--schema <file>]";
+
+/// Prints a `file:line:column: severity[code]: message` line per parse
+/// error or finding, exiting with 1 when any is an error.
+pub fn main(args: &[String]) -> Result<ExitCode, Box<dyn Error>> {
+    let args = crate::Args::parse(args, &["schema"])?;
+    let [file] = args.positional[..] else {
+        return Err(USAGE.into());
+    };
+    let schema = args
+        .option("schema")
+        .map(|schema| C12Context::new().load(schema))
+        .transpose()?
+        .map(|schema| schema.value);
+    let (text, format) = crate::read_config(file)?;
+    let (lines, healthy) = report(file, &text, format, schema.as_ref());
+    for line in lines {
+        println!("{line}");
+    }
+    Ok(if healthy {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(1)
+    })
+}
+
+/// The report lines for `text`, and whether it is free of errors.
+pub fn report(
+    file: &str,
+    text: &str,
+    format: Format,
+    schema: Option<&JsonValue>,
+) -> (Vec<String>, bool) {
+    let parsed = match parse_format::<JsonValue>(text, format, None) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            let line = format!("{file}:{}:{}: error: {}", e.line, e.column, e.message);
+            return (vec![line], false);
+        }
+    };
+    let report = doctor_with_context(&C12Context::new(), &parsed, schema);
+    let lines = report
+        .findings
+        .iter()
+        .map(|finding| {
+            let at = Some(&finding.path)
+                .filter(|path| !path.is_root())
+                .and_then(|path| locate(text, format, path))
+                .unwrap_or(Location::of_offset(text, 0));
+            format!(
+                "{file}:{}:{}: {}[{}]: {finding}",
+                at.line,
+                at.column,
+                severity_name(finding.severity),
+                finding.code.id()
+            )
+        })
+        .collect();
+    (lines, !report.has_errors())
+}
+
+pub fn severity_name(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info => "info",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reports_lints_and_schema_findings_at_their_lines() {
+        let (lines, healthy) = report("app.yaml", "name: web\nno: 1\n", Format::Yaml, None);
+        assert_eq!(
+            lines,
+            [
+                "app.yaml:2:1: warning[C12W004]: `no`: the key `no` reads as a YAML boolean, null or number unless quoted, consider `'no'`"
+            ]
+        );
+        assert!(healthy);
+
+        let schema = json!({
+            "properties": {
+                "host": { "deprecated": true, "x-replaced-by": "hostname" },
+                "port": { "type": "integer" }
+            }
+        });
+        let (lines, healthy) = report(
+            "app.toml",
+            "host = \"a\"\nport = \"80\"\n",
+            Format::Toml,
+            Some(&schema),
+        );
+        assert_eq!(
+            lines,
+            [
+                "app.toml:2:1: error[C12E014]: `port`: expected integer, found string",
+                "app.toml:1:1: warning[C12W007]: `host`: deprecated, use `hostname` instead"
+            ]
+        );
+        assert!(!healthy);
+    }
+}
@@ -0,0 +1,651 @@
+//! `c12 edit`: browse a config as a tree, edit values in place, and save
+//! after previewing the changes and validating the result.
+//!
+//! [`Editor`] holds all state and reacts to [`Key`]s without touching the
+//! terminal, which only [`main`] does.
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::io::{self, Write as _};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use c12_parser::{
+    C12Context, C12Error, DocumentHistory, FileSystem, Format, FormatOptions, Formatted,
+    OsFileSystem, Path, diff_values, doctor, locate, parse_document,
+};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, execute, queue};
+use serde_json::Value as JsonValue;
+
+const HELP: &str = "↑↓ move  ←→ fold  enter edit  d delete  u undo  r redo  s save  q quit";
+
+pub fn main(args: &[String]) -> Result<ExitCode, Box<dyn Error>> {
+    let args = crate::Args::parse(args, &["schema"])?;
+    let [file] = args.positional[..] else {
+        return Err("usage: c12 edit <file> [--schema <file>]".into());
+    };
+    let file = PathBuf::from(file);
+    let format = file
+        .extension()
+        .and_then(|ext| Format::from_name(&ext.to_string_lossy()))
+        .ok_or_else(|| format!("cannot tell the format of {}", file.display()))?;
+    let schema = match args.option("schema") {
+        Some(schema) => Some(C12Context::new().load(schema)?.value),
+        None => None,
+    };
+    let text = std::fs::read_to_string(&file)?;
+    let mut editor = Editor::new(file, text, format, schema)?;
+
+    let mut stdout = io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+    let result = run(&mut editor, &mut stdout);
+    execute!(stdout, cursor::Show, terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    result?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run(editor: &mut Editor, stdout: &mut io::Stdout) -> io::Result<()> {
+    loop {
+        let (width, height) = terminal::size()?;
+        queue!(stdout, terminal::Clear(ClearType::All))?;
+        for (row, line) in editor.view(height.into()).into_iter().enumerate() {
+            let text: String = line.text.chars().take(width.into()).collect();
+            let attribute = match line.style {
+                Style::Normal => Attribute::Reset,
+                Style::Selected => Attribute::Reverse,
+                Style::Header => Attribute::Bold,
+                Style::Dim => Attribute::Dim,
+            };
+            queue!(
+                stdout,
+                cursor::MoveTo(0, row as u16),
+                SetAttribute(attribute),
+                Print(text),
+                SetAttribute(Attribute::Reset)
+            )?;
+        }
+        stdout.flush()?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let key = match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+            KeyCode::Char(c) => Key::Char(c),
+            KeyCode::Up => Key::Up,
+            KeyCode::Down => Key::Down,
+            KeyCode::Left => Key::Left,
+            KeyCode::Right => Key::Right,
+            KeyCode::Enter => Key::Enter,
+            KeyCode::Backspace => Key::Backspace,
+            KeyCode::Esc => Key::Esc,
+            _ => continue,
+        };
+        match editor.handle(key) {
+            Action::None => {}
+            Action::Save => editor.save(&OsFileSystem),
+            Action::Quit => return Ok(()),
+        }
+    }
+}
+
+/// The keys the editor understands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Backspace,
+    Esc,
+    Char(char),
+}
+
+/// What the caller of [`Editor::handle`] should do next.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    None,
+    Save,
+    Quit,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Style {
+    Normal,
+    Selected,
+    Header,
+    Dim,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Line {
+    pub text: String,
+    pub style: Style,
+}
+
+impl Line {
+    fn new(text: impl Into<String>, style: Style) -> Self {
+        Self {
+            text: text.into(),
+            style,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Mode {
+    Browse,
+    /// Editing the value of the selected row.
+    Edit(String),
+    /// Showing what saving would change; `blocked` when validation failed.
+    Preview {
+        lines: Vec<String>,
+        blocked: bool,
+    },
+}
+
+/// One visible line of the tree.
+#[derive(Clone, Debug, PartialEq)]
+struct Row {
+    path: Path,
+    depth: usize,
+    label: String,
+    container: bool,
+    /// The scalar value, or the size of an object or array.
+    summary: String,
+}
+
+pub struct Editor {
+    file: PathBuf,
+    format: Format,
+    /// The text on disk, to tell whether there is anything to save.
+    saved: String,
+    history: DocumentHistory,
+    schema: Option<JsonValue>,
+    expanded: HashSet<Path>,
+    cursor: usize,
+    scroll: usize,
+    mode: Mode,
+    status: String,
+    confirm_quit: bool,
+}
+
+impl Editor {
+    pub fn new(
+        file: PathBuf,
+        text: String,
+        format: Format,
+        schema: Option<JsonValue>,
+    ) -> Result<Self, C12Error> {
+        let document = parse_document(&text, format)?;
+        Ok(Self {
+            file,
+            format,
+            saved: text,
+            history: DocumentHistory::new(document),
+            schema,
+            expanded: HashSet::new(),
+            cursor: 0,
+            scroll: 0,
+            mode: Mode::Browse,
+            status: String::new(),
+            confirm_quit: false,
+        })
+    }
+
+    fn text(&self) -> String {
+        self.history.to_string()
+    }
+
+    fn is_modified(&self) -> bool {
+        self.text() != self.saved
+    }
+
+    fn rows(&self) -> Vec<Row> {
+        let mut rows = Vec::new();
+        self.collect_rows(
+            &self.history.document().to_value(),
+            Path::root(),
+            0,
+            &mut rows,
+        );
+        rows
+    }
+
+    fn collect_rows(&self, value: &JsonValue, path: Path, depth: usize, rows: &mut Vec<Row>) {
+        let children: Vec<(Path, String, &JsonValue)> = match value {
+            JsonValue::Object(map) => map
+                .iter()
+                .map(|(key, child)| (path.clone().key(key.as_str()), key.clone(), child))
+                .collect(),
+            JsonValue::Array(items) => items
+                .iter()
+                .enumerate()
+                .map(|(i, child)| (path.clone().index(i), format!("[{i}]"), child))
+                .collect(),
+            _ => return,
+        };
+        for (path, label, child) in children {
+            let (container, summary) = match child {
+                JsonValue::Object(map) => (true, format!("{{{}}}", map.len())),
+                JsonValue::Array(items) => (true, format!("[{}]", items.len())),
+                scalar => (false, scalar.to_string()),
+            };
+            rows.push(Row {
+                path: path.clone(),
+                depth,
+                label,
+                container,
+                summary,
+            });
+            if self.expanded.contains(&path) {
+                self.collect_rows(child, path, depth + 1, rows);
+            }
+        }
+    }
+
+    fn selected(&self) -> Option<Row> {
+        self.rows().into_iter().nth(self.cursor)
+    }
+
+    /// Reacts to `key`, returning what the caller should do next.
+    pub fn handle(&mut self, key: Key) -> Action {
+        let quitting = std::mem::take(&mut self.confirm_quit);
+        match std::mem::replace(&mut self.mode, Mode::Browse) {
+            Mode::Edit(buffer) => {
+                self.edit(buffer, key);
+                Action::None
+            }
+            Mode::Preview { blocked, lines } => match key {
+                Key::Char('y') if blocked => {
+                    self.status = "fix the errors before saving".into();
+                    self.mode = Mode::Preview { lines, blocked };
+                    Action::None
+                }
+                Key::Char('y') => Action::Save,
+                _ => Action::None,
+            },
+            Mode::Browse => self.browse(key, quitting),
+        }
+    }
+
+    fn browse(&mut self, key: Key, quitting: bool) -> Action {
+        self.status.clear();
+        let rows = self.rows();
+        let selected = rows.get(self.cursor);
+        match key {
+            Key::Up | Key::Char('k') => self.cursor = self.cursor.saturating_sub(1),
+            Key::Down | Key::Char('j') => {
+                self.cursor = (self.cursor + 1).min(rows.len().saturating_sub(1));
+            }
+            Key::Right | Key::Char('l') => {
+                if let Some(row) = selected
+                    && row.container
+                {
+                    self.expanded.insert(row.path.clone());
+                }
+            }
+            Key::Left | Key::Char('h') => {
+                if let Some(row) = selected
+                    && !self.expanded.remove(&row.path)
+                {
+                    let parent = row.path.parent();
+                    if let Some(i) = rows.iter().position(|r| Some(&r.path) == parent.as_ref()) {
+                        self.cursor = i;
+                    }
+                }
+            }
+            Key::Enter | Key::Char('e') => match selected {
+                Some(row) if row.container => self.toggle(&row.path),
+                Some(row) => {
+                    let buffer = match self.history.document().get(&row.path) {
+                        Some(JsonValue::String(s)) => s,
+                        Some(other) => other.to_string(),
+                        None => String::new(),
+                    };
+                    self.mode = Mode::Edit(buffer);
+                }
+                None => {}
+            },
+            Key::Char('d') => {
+                if let Some(row) = selected {
+                    let path = row.path.clone();
+                    self.history.remove(&path);
+                    self.status = format!("removed {path}");
+                }
+            }
+            Key::Char('u') => {
+                self.status = match self.history.undo() {
+                    Some(edit) => format!("undid {edit}"),
+                    None => "nothing to undo".into(),
+                };
+            }
+            Key::Char('r') => {
+                self.status = match self.history.redo() {
+                    Some(edit) => format!("redid {edit}"),
+                    None => "nothing to redo".into(),
+                };
+            }
+            Key::Char('s') => self.preview(),
+            Key::Char('q') | Key::Esc => {
+                if !self.is_modified() || quitting {
+                    return Action::Quit;
+                }
+                self.status = "unsaved changes, press q again to discard them".into();
+                self.confirm_quit = true;
+            }
+            _ => {}
+        }
+        let len = self.rows().len();
+        self.cursor = self.cursor.min(len.saturating_sub(1));
+        Action::None
+    }
+
+    fn toggle(&mut self, path: &Path) {
+        if !self.expanded.remove(path) {
+            self.expanded.insert(path.clone());
+        }
+    }
+
+    /// Edits the buffer; Enter sets the value, read as JSON when it parses
+    /// and as a string otherwise, and Esc cancels.
+    fn edit(&mut self, mut buffer: String, key: Key) {
+        match key {
+            Key::Char(c) => buffer.push(c),
+            Key::Backspace => {
+                buffer.pop();
+            }
+            Key::Esc => return,
+            Key::Enter => {
+                let Some(row) = self.selected() else {
+                    return;
+                };
+                let value = serde_json::from_str(&buffer).unwrap_or(JsonValue::String(buffer));
+                if let Err(e) = self.history.set(&row.path, value) {
+                    self.status = e.to_string();
+                }
+                return;
+            }
+            _ => {}
+        }
+        self.mode = Mode::Edit(buffer);
+    }
+
+    /// Switches to the save preview: what changed since the last save and
+    /// what validation found in the result.
+    fn preview(&mut self) {
+        if !self.is_modified() {
+            self.status = "nothing to save".into();
+            return;
+        }
+        let text = self.text();
+        let before = parse_document(&self.saved, self.format)
+            .map(|doc| doc.to_value())
+            .unwrap_or(JsonValue::Null);
+        let after = self.history.document().to_value();
+        let mut lines: Vec<String> = diff_values(&before, &after)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let config = Formatted::new(&text, after, &FormatOptions::default());
+        let report = doctor(&config, self.schema.as_ref());
+        if !report.findings.is_empty() {
+            lines.push(String::new());
+        }
+        lines.extend(
+            report
+                .findings
+                .iter()
+                .map(|finding| format!("{:?}: {finding}", finding.severity)),
+        );
+        self.mode = Mode::Preview {
+            lines,
+            blocked: report.has_errors(),
+        };
+    }
+
+    /// Writes the document to its file atomically.
+    pub fn save(&mut self, fs: &dyn FileSystem) {
+        let text = self.text();
+        match fs.write_atomic(&self.file, &text) {
+            Ok(()) => {
+                self.status = format!("saved {}", self.file.display());
+                self.saved = text;
+            }
+            Err(e) => self.status = format!("saving failed: {e}"),
+        }
+    }
+
+    /// The screen as `height` lines: a header, the tree or the save
+    /// preview, then the comments of the selected key and a status line.
+    pub fn view(&mut self, height: usize) -> Vec<Line> {
+        let body = height.saturating_sub(4).max(1);
+        let modified = if self.is_modified() {
+            " [modified]"
+        } else {
+            ""
+        };
+        let mut lines = vec![Line::new(
+            format!("{} ({}){modified}", self.file.display(), self.format),
+            Style::Header,
+        )];
+
+        let footer = match &self.mode {
+            Mode::Preview {
+                lines: changes,
+                blocked,
+            } => {
+                lines.extend(
+                    changes
+                        .iter()
+                        .take(body)
+                        .map(|change| Line::new(change.as_str(), Style::Normal)),
+                );
+                if *blocked {
+                    "validation failed, any key to go back"
+                } else {
+                    "save these changes? y to save, any other key to go back"
+                }
+                .to_string()
+            }
+            mode => {
+                let rows = self.rows();
+                if self.cursor < self.scroll {
+                    self.scroll = self.cursor;
+                } else if self.cursor >= self.scroll + body {
+                    self.scroll = self.cursor + 1 - body;
+                }
+                for (i, row) in rows.iter().enumerate().skip(self.scroll).take(body) {
+                    let indent = "  ".repeat(row.depth);
+                    let marker = match (row.container, self.expanded.contains(&row.path)) {
+                        (false, _) => " ",
+                        (true, false) => "▸",
+                        (true, true) => "▾",
+                    };
+                    let value = match mode {
+                        Mode::Edit(buffer) if i == self.cursor => format!("{buffer}▏"),
+                        _ => row.summary.clone(),
+                    };
+                    let style = if i == self.cursor {
+                        Style::Selected
+                    } else {
+                        Style::Normal
+                    };
+                    lines.push(Line::new(
+                        format!("{indent}{marker} {}: {value}", row.label),
+                        style,
+                    ));
+                }
+                let comments = rows
+                    .get(self.cursor)
+                    .map(|row| comments(&self.text(), self.format, &row.path))
+                    .unwrap_or_default();
+                lines.resize(body + 1, Line::new("", Style::Normal));
+                lines.push(Line::new(
+                    comments
+                        .iter()
+                        .map(|c| format!("# {c}"))
+                        .collect::<Vec<_>>()
+                        .join("  "),
+                    Style::Dim,
+                ));
+                HELP.to_string()
+            }
+        };
+        lines.resize(
+            height.saturating_sub(1).max(lines.len()),
+            Line::new("", Style::Normal),
+        );
+        let status = if self.status.is_empty() {
+            footer
+        } else {
+            self.status.clone()
+        };
+        lines.push(Line::new(status, Style::Dim));
+        lines
+    }
+}
+
+/// The comments attached to the key at `path`: whole-line comments right
+/// above it and a comment after it on the same line.
+fn comments(text: &str, format: Format, path: &Path) -> Vec<String> {
+    let Some(location) = locate(text, format, path) else {
+        return Vec::new();
+    };
+    let marker = match format {
+        Format::Json | Format::Jsonc | Format::Json5 => "//",
+        Format::Toml | Format::Yaml => "#",
+        Format::Ini => ";",
+    };
+    let lines: Vec<&str> = text.lines().collect();
+    let mut found: Vec<String> = lines[..location.line - 1]
+        .iter()
+        .rev()
+        .map_while(|line| line.trim().strip_prefix(marker))
+        .map(|comment| comment.trim().to_string())
+        .collect();
+    found.reverse();
+    if let Some(line) = lines.get(location.line - 1)
+        && let Some(i) = line.find(&format!(" {marker}"))
+    {
+        found.push(line[i + 1 + marker.len()..].trim().to_string());
+    }
+    found
+}
+
+impl std::fmt::Debug for Editor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Editor")
+            .field("file", &self.file)
+            .field("format", &self.format)
+            .field("mode", &self.mode)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use c12_parser::MemoryFileSystem;
+
+    const YAML: &str = "\
+# listener
+server:
+  # public port
+  port: 8080 # http
+  host: localhost
+debug: true
+";
+
+    fn editor() -> Editor {
+        Editor::new("/app.yaml".into(), YAML.into(), Format::Yaml, None).unwrap()
+    }
+
+    fn press(editor: &mut Editor, keys: &str) -> Action {
+        keys.chars()
+            .map(|c| match c {
+                '↑' => Key::Up,
+                '↓' => Key::Down,
+                '→' => Key::Right,
+                '←' => Key::Left,
+                '⏎' => Key::Enter,
+                '⌫' => Key::Backspace,
+                '⎋' => Key::Esc,
+                c => Key::Char(c),
+            })
+            .map(|key| editor.handle(key))
+            .last()
+            .unwrap_or(Action::None)
+    }
+
+    fn body(editor: &mut Editor) -> Vec<String> {
+        editor.view(10).into_iter().map(|line| line.text).collect()
+    }
+
+    #[test]
+    fn navigates_the_tree_and_shows_comments() {
+        let mut editor = editor();
+        let lines = body(&mut editor);
+        assert_eq!(lines[0], "/app.yaml (yaml)");
+        assert_eq!(lines[1..3], ["▸ server: {2}", "  debug: true"]);
+        assert_eq!(lines[7], "# listener");
+        assert_eq!(lines[9], HELP);
+
+        press(&mut editor, "→↓");
+        let lines = body(&mut editor);
+        assert_eq!(
+            lines[1..4],
+            ["▾ server: {2}", "    port: 8080", "    host: \"localhost\""]
+        );
+        assert_eq!(lines[7], "# public port  # http");
+        assert_eq!(editor.view(10)[2].style, Style::Selected);
+
+        press(&mut editor, "←");
+        assert_eq!(editor.cursor, 0);
+    }
+
+    #[test]
+    fn edits_undo_and_save_after_preview() {
+        let mut editor = editor();
+        press(&mut editor, "→↓⏎⌫⌫⌫⌫9090⏎");
+        assert!(editor.text().contains("port: 9090 # http"));
+        press(&mut editor, "↓⏎⌫⌫⌫⌫⌫⌫⌫⌫⌫\"8080\"⏎u");
+        assert_eq!(editor.status, "undid set server.host = \"8080\"");
+
+        assert_eq!(press(&mut editor, "q"), Action::None);
+        assert_eq!(press(&mut editor, "s"), Action::None);
+        assert_eq!(body(&mut editor)[1], "~ server.port: 8080 -> 9090");
+        assert_eq!(press(&mut editor, "y"), Action::Save);
+
+        let fs = MemoryFileSystem::new();
+        editor.save(&fs);
+        let saved = fs.read_to_string("/app.yaml".as_ref()).unwrap();
+        assert_eq!(saved, YAML.replace("8080", "9090"));
+        assert_eq!(press(&mut editor, "q"), Action::Quit);
+    }
+
+    #[test]
+    fn validation_errors_block_saving() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": { "debug": { "type": "boolean" } }
+        });
+        let mut editor =
+            Editor::new("/app.yaml".into(), YAML.into(), Format::Yaml, Some(schema)).unwrap();
+        press(&mut editor, "↓⏎⌫⌫⌫⌫yes⏎s");
+        let lines = body(&mut editor);
+        assert_eq!(lines[1], "~ debug: true -> \"yes\"");
+        assert!(lines[3].starts_with("Error: `debug`"), "{}", lines[3]);
+        assert_eq!(press(&mut editor, "y"), Action::None);
+        assert_eq!(editor.status, "fix the errors before saving");
+    }
+}
@@ -0,0 +1,142 @@
+//! `c12 fmt`: rewrite configs in the layout the crate writes, keeping the
+//! indentation and outer whitespace each file already uses.
+
+use std::error::Error;
+use std::process::ExitCode;
+
+use c12_parser::{
+    C12Context, Format, FormatOptions, StyleProfile, find_ini_comments, find_toml_comments,
+    find_yaml_comments, minify,
+};
+
+const USAGE: &str = "usage: c12 fmt [--check] <file>...";
+
+/// Formats each file in place, or with `--check` only lists the files that
+/// would change, exiting with 1 when there are any. Files that cannot be
+/// formatted are reported and exit with 2.
+pub fn main(args: &[String]) -> Result<ExitCode, Box<dyn Error>> {
+    let args = crate::Args::parse_with_flags(args, &[], &["check"])?;
+    if args.positional.is_empty() {
+        return Err(USAGE.into());
+    }
+    let check = args.flag("check");
+    let context = context();
+    let (mut unformatted, mut failed) = (false, false);
+    for file in &args.positional {
+        let result = crate::read_config(file).and_then(|(text, format)| {
+            let Some(out) = reformat(&context, &text, format)? else {
+                return Ok(false);
+            };
+            if !check {
+                std::fs::write(file, out)?;
+            }
+            Ok(true)
+        });
+        match result {
+            Ok(true) if check => {
+                println!("{file}");
+                unformatted = true;
+            }
+            Ok(true) => println!("formatted {file}"),
+            Ok(false) => {}
+            Err(e) => {
+                eprintln!("c12: {file}: {e}");
+                failed = true;
+            }
+        }
+    }
+    Ok(match (failed, unformatted) {
+        (true, _) => ExitCode::from(2),
+        (false, true) => ExitCode::from(1),
+        (false, false) => ExitCode::SUCCESS,
+    })
+}
+
+/// serde_yaml always indents by two spaces, so YAML goes through the
+/// crate's own emitter, which follows the file's indent.
+fn context() -> C12Context {
+    let mut yaml = FormatOptions::default();
+    yaml.profile = StyleProfile::Confbox;
+    C12Context::new().format_defaults(Format::Yaml, yaml)
+}
+
+/// `text` in canonical layout, `None` when it already is. Files with
+/// comments are refused rather than stripped of them.
+fn reformat(
+    context: &C12Context,
+    text: &str,
+    format: Format,
+) -> Result<Option<String>, Box<dyn Error>> {
+    if has_comments(text, format)? {
+        return Err("has comments, which formatting would drop".into());
+    }
+    let mut parsed = context.parse(format.name(), text)?;
+    // `parse_yaml` leaves indentation undetected, as confbox does.
+    parsed.format.sample.get_or_insert_with(|| text.to_string());
+    let out = context.stringify(format.name(), &parsed)?;
+    // Some emitters end with a newline of their own on top of the one kept
+    // from the file, so the file's trailing whitespace is put back alone.
+    let end = &text[text.trim_end().len()..];
+    let out = format!("{}{end}", out.trim_end());
+    Ok((out != text).then_some(out))
+}
+
+/// Whether `text` holds a comment, as the format's parser reads it.
+fn has_comments(text: &str, format: Format) -> Result<bool, Box<dyn Error>> {
+    Ok(match format {
+        Format::Json => false,
+        Format::Json5 | Format::Jsonc => {
+            minify(text, format, true)? != minify(text, format, false)?
+        }
+        Format::Ini => !find_ini_comments(text).is_empty(),
+        Format::Toml => !find_toml_comments(text).is_empty(),
+        Format::Yaml => !find_yaml_comments(text).is_empty(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_each_files_indentation() {
+        let context = context();
+        let json = "{\n    \"a\": [\n        1,\n        2\n    ],\n    \"b\": {}\n}\n";
+        let out = reformat(
+            &context,
+            "{\n    \"a\": [1,\n2], \"b\": {}}\n",
+            Format::Json,
+        );
+        assert_eq!(out.unwrap().as_deref(), Some(json));
+        assert_eq!(reformat(&context, json, Format::Json).unwrap(), None);
+
+        let toml = "name = \"app\"\n\n[server]\nport = 80\n";
+        let out = reformat(&context, "name='app'\n[server]\nport=80\n", Format::Toml);
+        assert_eq!(out.unwrap().as_deref(), Some(toml));
+        assert_eq!(reformat(&context, toml, Format::Toml).unwrap(), None);
+
+        let yaml = "a:\n    b: 1\n    c:\n        -   x\n";
+        let out = reformat(&context, "a:\n    b:   1\n    c: [x]\n", Format::Yaml);
+        assert_eq!(out.unwrap().as_deref(), Some(yaml));
+        assert_eq!(reformat(&context, yaml, Format::Yaml).unwrap(), None);
+    }
+
+    #[test]
+    fn refuses_files_with_comments() {
+        let cases = [
+            ("{ /* port */ \"a\": 1 }", Format::Jsonc, true),
+            ("{ \"url\": \"http://x\" }", Format::Json5, false),
+            ("a = \"#1\" # note\n", Format::Toml, true),
+            ("a = \"#1\"\nb = 'it''s'\n", Format::Toml, false),
+            ("a = '''\n# not\n'''\n", Format::Toml, false),
+            ("color: '#fff'\nurl: a#b\n", Format::Yaml, false),
+            ("it: it's # here\n", Format::Yaml, true),
+            ("[a]\n; note\nb=1\n", Format::Ini, true),
+        ];
+        for (text, format, expected) in cases {
+            assert_eq!(has_comments(text, format).unwrap(), expected, "{text}");
+        }
+        let err = reformat(&context(), "# note\na: 1\n", Format::Yaml).unwrap_err();
+        assert_eq!(err.to_string(), "has comments, which formatting would drop");
+    }
+}
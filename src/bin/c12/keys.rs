@@ -0,0 +1,87 @@
+//! `c12 get` and `c12 set`: read or change a single value, leaving the
+//! rest of the file as it is.
+
+use std::error::Error;
+use std::process::ExitCode;
+
+use c12_parser::{C12Context, Format, Path, parse_document};
+use serde_json::Value as JsonValue;
+
+/// Prints the value at a path: strings as they are, anything else as JSON.
+pub fn get(args: &[String]) -> Result<ExitCode, Box<dyn Error>> {
+    let args = crate::Args::parse(args, &[])?;
+    let [file, path] = args.positional[..] else {
+        return Err("usage: c12 get <file> <path>".into());
+    };
+    let (text, format) = crate::read_config(file)?;
+    println!("{}", lookup(&text, format, &path.parse()?)?);
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Sets the value at a path and writes the file back. The value is read
+/// as JSON when it parses as JSON, and as a string otherwise.
+pub fn set(args: &[String]) -> Result<ExitCode, Box<dyn Error>> {
+    let args = crate::Args::parse(args, &[])?;
+    let [file, path, value] = args.positional[..] else {
+        return Err("usage: c12 set <file> <path> <value>".into());
+    };
+    let (text, format) = crate::read_config(file)?;
+    std::fs::write(file, assign(&text, format, &path.parse()?, value)?)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn lookup(text: &str, format: Format, path: &Path) -> Result<String, Box<dyn Error>> {
+    let config = C12Context::new().parse(format.name(), text)?;
+    Ok(match path.lookup(&config.value) {
+        Some(JsonValue::String(text)) => text.clone(),
+        Some(value @ (JsonValue::Array(_) | JsonValue::Object(_))) => {
+            serde_json::to_string_pretty(value)?
+        }
+        Some(value) => value.to_string(),
+        None => return Err(format!("`{path}` is not set").into()),
+    })
+}
+
+fn assign(text: &str, format: Format, path: &Path, value: &str) -> Result<String, Box<dyn Error>> {
+    let value = serde_json::from_str(value).unwrap_or_else(|_| JsonValue::String(value.into()));
+    let mut doc = parse_document(text, format)?;
+    doc.set(path, value)?;
+    Ok(doc.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_single_values() {
+        let yaml = "server:\n  host: example.com\n  port: 80\n  tags: [a]\n";
+        let get = |path: &str| lookup(yaml, Format::Yaml, &path.parse().unwrap());
+        assert_eq!(get("server.host").unwrap(), "example.com");
+        assert_eq!(get("server.port").unwrap(), "80");
+        assert_eq!(get("server.tags").unwrap(), "[\n  \"a\"\n]");
+        assert_eq!(
+            get("server.tls").unwrap_err().to_string(),
+            "`server.tls` is not set"
+        );
+        assert_eq!(
+            lookup("[a]\nb=1\n", Format::Ini, &"a.b".parse().unwrap()).unwrap(),
+            "1"
+        );
+    }
+
+    #[test]
+    fn edits_in_place() {
+        let toml = "# deploy\n[server]\nport = 80 # public\nhost = \"a\"\n";
+        let set = |path: &str, value| assign(toml, Format::Toml, &path.parse().unwrap(), value);
+        assert_eq!(
+            set("server.port", "8080").unwrap(),
+            "# deploy\n[server]\nport = 8080 # public\nhost = \"a\"\n"
+        );
+        assert_eq!(
+            set("server.host", "b.dev").unwrap(),
+            "# deploy\n[server]\nport = 80 # public\nhost = \"b.dev\"\n"
+        );
+        assert!(assign("[a]\n", Format::Ini, &"a.b".parse().unwrap(), "1").is_err());
+    }
+}
@@ -0,0 +1,637 @@
+//! `c12 lsp`: a language server over stdio for every built-in format.
+//!
+//! It publishes parse errors and [`doctor`] findings as diagnostics,
+//! shows the path, value and schema docs of a key on hover, formats whole
+//! documents and renames keys. Documents are synced in full on every
+//! change. Columns are counted in characters, which matches the
+//! protocol's UTF-16 columns outside the astral planes.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{self, BufRead, Write};
+use std::process::ExitCode;
+
+use c12_parser::{
+    C12Context, Format, Location, OutlineItem, Path, PathSegment, Severity, beautify,
+    completion_model, doctor, find_ini_comments, find_jsonc_comments, find_toml_comments,
+    find_yaml_comments, locate, outline, parse_format,
+};
+use serde_json::{Value as JsonValue, json};
+
+/// JSON-RPC's "method not found".
+const METHOD_NOT_FOUND: i64 = -32601;
+/// The protocol's "request failed".
+const REQUEST_FAILED: i64 = -32803;
+
+pub fn main(args: &[String]) -> Result<ExitCode, Box<dyn Error>> {
+    let args = crate::Args::parse(args, &["schema"])?;
+    if !args.positional.is_empty() {
+        return Err("usage: c12 lsp [--schema <file>]".into());
+    }
+    let schema = match args.option("schema") {
+        Some(schema) => Some(C12Context::new().load(schema)?.value),
+        None => None,
+    };
+    let mut server = Server::new(schema);
+    let mut input = io::stdin().lock();
+    let mut output = io::stdout().lock();
+    while !server.exited
+        && let Some(message) = read_message(&mut input)?
+    {
+        for reply in server.handle(message) {
+            write_message(&mut output, &reply)?;
+        }
+    }
+    // As the protocol asks, exiting without a shutdown request is an error.
+    Ok(if server.shut_down {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+/// Reads one `Content-Length`-framed message, `None` at the end of input.
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<JsonValue>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let length = length.ok_or_else(|| invalid_data("missing Content-Length header"))?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| invalid_data(&e.to_string()))
+}
+
+fn write_message(output: &mut impl Write, message: &JsonValue) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+    output.flush()
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+struct OpenDocument {
+    text: String,
+    format: Format,
+}
+
+/// The server state, reacting to decoded messages without doing any I/O.
+pub struct Server {
+    schema: Option<JsonValue>,
+    documents: HashMap<String, OpenDocument>,
+    shut_down: bool,
+    exited: bool,
+}
+
+impl Server {
+    pub fn new(schema: Option<JsonValue>) -> Self {
+        Self {
+            schema,
+            documents: HashMap::new(),
+            shut_down: false,
+            exited: false,
+        }
+    }
+
+    /// Handles one request or notification, returning the messages to
+    /// send back.
+    pub fn handle(&mut self, message: JsonValue) -> Vec<JsonValue> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        let result = match method {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "hoverProvider": true,
+                    "documentFormattingProvider": true,
+                    "renameProvider": true
+                },
+                "serverInfo": { "name": "c12", "version": env!("CARGO_PKG_VERSION") }
+            })),
+            "shutdown" => {
+                self.shut_down = true;
+                Ok(JsonValue::Null)
+            }
+            "exit" => {
+                self.exited = true;
+                return Vec::new();
+            }
+            "textDocument/didOpen" => {
+                let document = &params["textDocument"];
+                let format = format_of(&uri, document["languageId"].as_str());
+                let text = document["text"].as_str().unwrap_or_default();
+                return self.update(uri, format, text.to_string());
+            }
+            "textDocument/didChange" => {
+                let Some(format) = self.documents.get(&uri).map(|doc| doc.format) else {
+                    return Vec::new();
+                };
+                let changes = params["contentChanges"].as_array();
+                let Some(text) = changes.and_then(|c| c.last()?["text"].as_str()) else {
+                    return Vec::new();
+                };
+                return self.update(uri, Some(format), text.to_string());
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                return vec![publish(&uri, Vec::new())];
+            }
+            "textDocument/hover" => Ok(self.hover(&uri, &params["position"])),
+            "textDocument/formatting" => self.format(&uri),
+            "textDocument/rename" => {
+                let new_name = params["newName"].as_str().unwrap_or_default();
+                self.rename(&uri, &params["position"], new_name)
+            }
+            _ if message.get("id").is_none() => return Vec::new(),
+            _ => Err((METHOD_NOT_FOUND, format!("unsupported method `{method}`"))),
+        };
+        let Some(id) = message.get("id") else {
+            return Vec::new();
+        };
+        vec![match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message }
+            }),
+        }]
+    }
+
+    fn update(&mut self, uri: String, format: Option<Format>, text: String) -> Vec<JsonValue> {
+        let Some(format) = format else {
+            return Vec::new();
+        };
+        let document = OpenDocument { text, format };
+        let diagnostics = self.diagnostics(&document);
+        self.documents.insert(uri.clone(), document);
+        vec![publish(&uri, diagnostics)]
+    }
+
+    /// Parse errors, or the findings of [`doctor`] against the schema.
+    fn diagnostics(&self, document: &OpenDocument) -> Vec<JsonValue> {
+        let text = &document.text;
+        let parsed = match parse_format::<JsonValue>(text, document.format, None) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                let at = Location::of_offset(text, e.offset);
+                return vec![diagnostic(text, at, 1, None, &e.message)];
+            }
+        };
+        doctor(&parsed, self.schema.as_ref())
+            .findings
+            .iter()
+            .map(|finding| {
+                let at = locate(text, document.format, &finding.path)
+                    .unwrap_or(Location::of_offset(text, 0));
+                let severity = match finding.severity {
+                    Severity::Error => 1,
+                    Severity::Warning => 2,
+                    Severity::Info => 3,
+                };
+                diagnostic(
+                    text,
+                    at,
+                    severity,
+                    Some(finding.code.id()),
+                    &finding.to_string(),
+                )
+            })
+            .collect()
+    }
+
+    fn hover(&self, uri: &str, position: &JsonValue) -> JsonValue {
+        let Some(document) = self.documents.get(uri) else {
+            return JsonValue::Null;
+        };
+        let Some((path, value)) = key_at(document, position) else {
+            return JsonValue::Null;
+        };
+        let mut text = format!("`{path}` = `{value}`");
+        if let Some(schema) = &self.schema
+            && let Some(PathSegment::Key(key)) = path.segments().last()
+        {
+            let model = completion_model(schema);
+            let parent = schema_path(&path.parent().unwrap_or_else(Path::root));
+            if let Some(doc) = model.keys_at(&parent).iter().find(|c| &c.key == key) {
+                if !doc.types.is_empty() {
+                    text.push_str(&format!(" ({})", doc.types.join(" | ")));
+                }
+                if let Some(description) = &doc.doc {
+                    text.push_str(&format!("\n\n{description}"));
+                }
+                if let Some(default) = &doc.default {
+                    text.push_str(&format!("\n\nDefault: `{default}`"));
+                }
+                if doc.deprecated {
+                    text.push_str("\n\n**Deprecated**");
+                    if let Some(replacement) = &doc.replaced_by {
+                        text.push_str(&format!(", use `{replacement}`"));
+                    }
+                }
+            }
+        }
+        json!({ "contents": { "kind": "markdown", "value": text } })
+    }
+
+    /// Reformats the whole document, refusing when that would drop
+    /// comments.
+    fn format(&self, uri: &str) -> Result<JsonValue, (i64, String)> {
+        let document = self.documents.get(uri).ok_or(not_open())?;
+        let failed = |message: String| (REQUEST_FAILED, message);
+        let text = &document.text;
+        let comments = match document.format {
+            Format::Json | Format::Jsonc | Format::Json5 => find_jsonc_comments(text),
+            Format::Toml => find_toml_comments(text),
+            Format::Yaml => find_yaml_comments(text),
+            Format::Ini => find_ini_comments(text),
+        };
+        if !comments.is_empty() {
+            return Err(failed("formatting would drop comments".into()));
+        }
+        let formatted = match document.format {
+            Format::Json | Format::Jsonc | Format::Json5 => {
+                beautify(text, document.format, None, None).map_err(|e| failed(e.to_string()))?
+            }
+            Format::Toml | Format::Yaml | Format::Ini => {
+                let ctx = C12Context::new();
+                let name = document.format.name();
+                let parsed = ctx.parse(name, text).map_err(|e| failed(e.to_string()))?;
+                let out = ctx
+                    .stringify(name, &parsed)
+                    .map_err(|e| failed(e.to_string()))?;
+                // The emitter ends with a newline before the source's own
+                // trailing whitespace; keep just one.
+                format!("{}\n", out.trim_end())
+            }
+        };
+        if formatted == *text {
+            return Ok(json!([]));
+        }
+        let end = Location::of_offset(text, text.len());
+        Ok(json!([{
+            "range": { "start": to_position(Location::of_offset(text, 0)), "end": to_position(end) },
+            "newText": formatted
+        }]))
+    }
+
+    /// Renames the key under the cursor, editing only its spelling.
+    fn rename(
+        &self,
+        uri: &str,
+        position: &JsonValue,
+        new_name: &str,
+    ) -> Result<JsonValue, (i64, String)> {
+        let failed = |message: &str| (REQUEST_FAILED, message.to_string());
+        let document = self.documents.get(uri).ok_or(not_open())?;
+        let (path, _) = key_at(document, position).ok_or(failed("no key here"))?;
+        let Some(PathSegment::Key(old_name)) = path.segments().last() else {
+            return Err(failed("only object keys can be renamed"));
+        };
+        let parsed = parse_format::<JsonValue>(&document.text, document.format, None)
+            .map_err(|e| failed(&e.to_string()))?;
+        let parent = path.parent().unwrap_or_else(Path::root);
+        if parent
+            .lookup(&parsed.value)
+            .and_then(JsonValue::as_object)
+            .is_some_and(|siblings| siblings.contains_key(new_name))
+        {
+            return Err(failed(&format!("`{new_name}` already exists here")));
+        }
+
+        let at = locate(&document.text, document.format, &path).ok_or(failed("no key here"))?;
+        let rest = &document.text[at.offset..];
+        let (token, spelled) = key_token(rest).ok_or(failed("cannot find the key's text"))?;
+        if spelled != *old_name {
+            return Err(failed("cannot find the key's text"));
+        }
+        let quote = rest.chars().next().filter(|c| matches!(c, '"' | '\''));
+        let new_text = match quote {
+            _ if document.format == Format::Ini => new_name.to_string(),
+            None if is_bare(new_name) && document.format != Format::Json => new_name.to_string(),
+            Some('\'') if !new_name.contains('\'') => format!("'{new_name}'"),
+            _ => JsonValue::from(new_name).to_string(),
+        };
+        let end = Location::of_offset(&document.text, at.offset + token.len());
+        Ok(json!({
+            "changes": {
+                uri: [{
+                    "range": { "start": to_position(at), "end": to_position(end) },
+                    "newText": new_text
+                }]
+            }
+        }))
+    }
+}
+
+fn not_open() -> (i64, String) {
+    (REQUEST_FAILED, "the document is not open".to_string())
+}
+
+fn to_position(at: Location) -> JsonValue {
+    json!({ "line": at.line - 1, "character": at.column - 1 })
+}
+
+fn publish(uri: &str, diagnostics: Vec<JsonValue>) -> JsonValue {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics }
+    })
+}
+
+/// A diagnostic from `at` to the end of its line.
+fn diagnostic(
+    text: &str,
+    at: Location,
+    severity: u8,
+    code: Option<&str>,
+    message: &str,
+) -> JsonValue {
+    let line_end = text[at.offset..]
+        .find('\n')
+        .map_or(text.len(), |i| at.offset + i);
+    let end = Location::of_offset(text, line_end.max(at.offset));
+    let mut diagnostic = json!({
+        "range": { "start": to_position(at), "end": to_position(end) },
+        "severity": severity,
+        "source": "c12",
+        "message": message
+    });
+    if let Some(code) = code {
+        diagnostic["code"] = code.into();
+    }
+    diagnostic
+}
+
+/// The format of a document, from its file extension or else the
+/// client's language id.
+fn format_of(uri: &str, language: Option<&str>) -> Option<Format> {
+    let name = uri.rsplit('/').next().unwrap_or(uri);
+    name.rsplit_once('.')
+        .and_then(|(_, ext)| Format::from_name(ext))
+        .or_else(|| language.and_then(Format::from_name))
+}
+
+/// The key or item written on the cursor's line at or before the cursor,
+/// with its value. Every entry is placed in one pass over the text.
+fn key_at(document: &OpenDocument, position: &JsonValue) -> Option<(Path, JsonValue)> {
+    let line = usize::try_from(position["line"].as_u64()?).ok()? + 1;
+    let column = usize::try_from(position["character"].as_u64()?).ok()? + 1;
+    let value = parse_format::<JsonValue>(&document.text, document.format, None)
+        .ok()?
+        .value;
+    let items = outline(&document.text, document.format).ok()?;
+    let mut best: Option<&OutlineItem> = None;
+    let mut pending: Vec<&OutlineItem> = items.iter().collect();
+    while let Some(item) = pending.pop() {
+        pending.extend(&item.children);
+        let at = item.start;
+        let closer = best.is_none_or(|best| at.column > best.start.column);
+        if at.line == line && at.column <= column && closer {
+            best = Some(item);
+        }
+    }
+    let path = best?.path.clone();
+    let value = path.lookup(&value)?.clone();
+    Some((path, value))
+}
+
+/// `path` as a [`completion_model`] key: array indices become `[*]`.
+fn schema_path(path: &Path) -> String {
+    let mut out = String::new();
+    for segment in path.segments() {
+        match segment {
+            PathSegment::Key(key) => {
+                let key = Path::root().key(key.as_str()).to_string();
+                if !out.is_empty() && !key.starts_with('[') {
+                    out.push('.');
+                }
+                out.push_str(&key);
+            }
+            PathSegment::Index(_) => out.push_str("[*]"),
+        }
+    }
+    out
+}
+
+/// The key token at the start of `text` and the key it spells.
+fn key_token(text: &str) -> Option<(&str, String)> {
+    match text.chars().next()? {
+        quote @ ('"' | '\'') => {
+            let mut escaped = false;
+            let end = text[1..].char_indices().find_map(|(i, c)| {
+                let closes = c == quote && !escaped;
+                escaped = quote == '"' && c == '\\' && !escaped;
+                closes.then_some(i + 2)
+            })?;
+            let token = &text[..end];
+            let spelled = if quote == '"' {
+                serde_json::from_str(token).ok()?
+            } else {
+                token[1..end - 1].to_string()
+            };
+            Some((token, spelled))
+        }
+        _ => {
+            let end = text
+                .find(|c: char| c.is_whitespace() || matches!(c, ':' | '=' | '.' | ']'))
+                .unwrap_or(text.len());
+            let token = &text[..end];
+            Some((token, token.to_string()))
+        }
+    }
+}
+
+fn is_bare(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open(server: &mut Server, uri: &str, text: &str) -> Vec<JsonValue> {
+        server.handle(json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didOpen",
+            "params": { "textDocument": { "uri": uri, "languageId": "", "version": 1, "text": text } }
+        }))
+    }
+
+    fn request(server: &mut Server, method: &str, params: JsonValue) -> JsonValue {
+        let mut replies =
+            server.handle(json!({ "jsonrpc": "2.0", "id": 7, "method": method, "params": params }));
+        assert_eq!(replies.len(), 1);
+        replies.remove(0)
+    }
+
+    fn at(uri: &str, line: u64, character: u64) -> JsonValue {
+        json!({ "textDocument": { "uri": uri }, "position": { "line": line, "character": character } })
+    }
+
+    #[test]
+    fn frames_messages_and_publishes_diagnostics() {
+        let message = json!({ "jsonrpc": "2.0", "id": 1, "method": "shutdown" });
+        let mut framed = Vec::new();
+        write_message(&mut framed, &message).unwrap();
+        let mut input = io::Cursor::new(framed);
+        assert_eq!(read_message(&mut input).unwrap(), Some(message.clone()));
+        assert_eq!(read_message(&mut input).unwrap(), None);
+
+        let schema = json!({ "properties": { "port": { "type": "integer" } } });
+        let mut server = Server::new(Some(schema));
+        let published = open(&mut server, "file:///app.toml", "name = 1\nport = \"x\"\n");
+        let diagnostics = &published[0]["params"]["diagnostics"];
+        assert_eq!(
+            diagnostics[0]["range"]["start"],
+            json!({ "line": 1, "character": 0 })
+        );
+        assert_eq!(diagnostics[0]["severity"], 1);
+        assert_eq!(
+            diagnostics[1]["range"]["start"],
+            json!({ "line": 0, "character": 0 })
+        );
+        assert_eq!(diagnostics[1]["severity"], 2);
+
+        let published = open(&mut server, "file:///app.yaml", "a: [1,\n");
+        let diagnostics = &published[0]["params"]["diagnostics"];
+        assert_eq!(diagnostics[0]["range"]["start"]["line"], 1);
+
+        let reply = request(&mut server, "shutdown", JsonValue::Null);
+        assert_eq!(reply["result"], JsonValue::Null);
+        assert!(
+            server
+                .handle(json!({ "jsonrpc": "2.0", "method": "exit" }))
+                .is_empty()
+        );
+        assert!(server.exited && server.shut_down);
+    }
+
+    #[test]
+    fn hovers_with_schema_docs_and_renames_keys() {
+        let schema = json!({
+            "properties": {
+                "server": {
+                    "properties": {
+                        "port": { "type": "integer", "description": "Listen port", "default": 80 }
+                    }
+                }
+            }
+        });
+        let mut server = Server::new(Some(schema));
+        let uri = "file:///app.json";
+        open(&mut server, uri, "{\n  \"server\": { \"port\": 8080 }\n}\n");
+
+        let hover = request(&mut server, "textDocument/hover", at(uri, 1, 17));
+        assert_eq!(
+            hover["result"]["contents"]["value"],
+            "`server.port` = `8080` (integer)\n\nListen port\n\nDefault: `80`"
+        );
+
+        let mut params = at(uri, 1, 4);
+        params["newName"] = "listener".into();
+        let reply = request(&mut server, "textDocument/rename", params);
+        assert_eq!(
+            reply["result"]["changes"][uri],
+            json!([{
+                "range": {
+                    "start": { "line": 1, "character": 2 },
+                    "end": { "line": 1, "character": 10 }
+                },
+                "newText": "\"listener\""
+            }])
+        );
+
+        let mut params = at(uri, 1, 17);
+        params["newName"] = "port".into();
+        let reply = request(&mut server, "textDocument/rename", params);
+        assert_eq!(reply["error"]["message"], "`port` already exists here");
+    }
+
+    #[test]
+    fn hovers_in_large_documents() {
+        let mut server = Server::new(None);
+        let uri = "file:///big.json";
+        let keys: Vec<_> = (0..5000).map(|i| format!("  \"k{i}\": {i}")).collect();
+        open(&mut server, uri, &format!("{{\n{}\n}}\n", keys.join(",\n")));
+        let hover = request(&mut server, "textDocument/hover", at(uri, 5000, 4));
+        assert_eq!(hover["result"]["contents"]["value"], "`k4999` = `4999`");
+    }
+
+    #[test]
+    fn formats_without_dropping_comments() {
+        let mut server = Server::new(None);
+        open(
+            &mut server,
+            "file:///a.json",
+            "{\"a\":[1,2],\"b\":{\"c\":true}}",
+        );
+        let reply = request(
+            &mut server,
+            "textDocument/formatting",
+            json!({ "textDocument": { "uri": "file:///a.json" } }),
+        );
+        assert_eq!(
+            reply["result"][0]["newText"],
+            "{\n  \"a\": [1, 2],\n  \"b\": {\n    \"c\": true\n  }\n}\n"
+        );
+
+        open(&mut server, "file:///a.toml", "a=1\n[t]\n  b   =  2\n");
+        let reply = request(
+            &mut server,
+            "textDocument/formatting",
+            json!({ "textDocument": { "uri": "file:///a.toml" } }),
+        );
+        assert_eq!(reply["result"][0]["newText"], "a = 1\n\n[t]\nb = 2\n");
+
+        open(&mut server, "file:///a.yaml", "a:   1 # note\n");
+        let reply = request(
+            &mut server,
+            "textDocument/formatting",
+            json!({ "textDocument": { "uri": "file:///a.yaml" } }),
+        );
+        assert_eq!(reply["error"]["message"], "formatting would drop comments");
+
+        let cases = [
+            ("file:///b.toml", "a =   \"x # y\"\n", "a = \"x # y\"\n"),
+            ("file:///b.yaml", "a:   'x # y'\n", "a: 'x # y'\n"),
+        ];
+        for (uri, text, formatted) in cases {
+            open(&mut server, uri, text);
+            let params = json!({ "textDocument": { "uri": uri } });
+            let reply = request(&mut server, "textDocument/formatting", params);
+            assert_eq!(reply["result"][0]["newText"], formatted, "{uri}");
+        }
+        // The INI parser ends a value at `;` or `#`, even in quotes.
+        open(&mut server, "file:///b.ini", "a = \"x # y\"\n");
+        let params = json!({ "textDocument": { "uri": "file:///b.ini" } });
+        let reply = request(&mut server, "textDocument/formatting", params);
+        assert_eq!(reply["error"]["message"], "formatting would drop comments");
+        let reply = request(&mut server, "workspace/symbol", json!({}));
+        assert_eq!(reply["error"]["code"], METHOD_NOT_FOUND);
+    }
+}
@@ -0,0 +1,160 @@
+//! `c12`, a command-line tool over the `c12-parser` library.
+
+mod convert;
+mod diff;
+mod doctor;
+#[cfg(feature = "tui")]
+mod edit;
+mod fmt;
+mod keys;
+#[cfg(feature = "lsp")]
+mod lsp;
+mod validate;
+
+use std::error::Error;
+use std::path::Path as FsPath;
+use std::process::ExitCode;
+
+use c12_parser::Format;
+
+const USAGE: &str = "\
+usage: c12 <command> [arguments]
+
+commands:
+  convert [<file>|-] --to <format> [--from <format>] [--indent <n>]
+          [--sort-keys] [--out <file>]
+                                  convert a config to another format
+  convert -r <dir> --to <format> [--from <format>] [--indent <n>]
+          [--out <dir>]           convert every config under a directory
+  diff <file> <file>              compare two configs by value
+  doctor <file> [--schema <file>] lint a config and check it against a schema
+  edit <file> [--schema <file>]   edit a config in a terminal UI
+  fmt [--check] <file>...         reformat configs in place
+  get <file> <path>               print the value at a path
+  set <file> <path> <value>       change the value at a path in place
+  lsp [--schema <file>]           run a language server over stdio
+  validate <file> --schema <file> check a config against a JSON Schema
+  help                            show this message
+";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("c12: {e}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<ExitCode, Box<dyn Error>> {
+    let Some((command, rest)) = args.split_first() else {
+        eprint!("{USAGE}");
+        return Ok(ExitCode::from(2));
+    };
+    match command.as_str() {
+        "convert" => convert::main(rest),
+        "diff" => diff::main(rest),
+        "doctor" => doctor::main(rest),
+        "edit" => edit(rest),
+        "fmt" => fmt::main(rest),
+        "get" => keys::get(rest),
+        "set" => keys::set(rest),
+        "lsp" => lsp(rest),
+        "validate" => validate::main(rest),
+        "help" | "-h" | "--help" => {
+            print!("{USAGE}");
+            Ok(ExitCode::SUCCESS)
+        }
+        other => Err(format!("unknown command `{other}`, see `c12 help`").into()),
+    }
+}
+
+#[cfg(feature = "tui")]
+fn edit(args: &[String]) -> Result<ExitCode, Box<dyn Error>> {
+    edit::main(args)
+}
+
+#[cfg(not(feature = "tui"))]
+fn edit(_args: &[String]) -> Result<ExitCode, Box<dyn Error>> {
+    Err("`c12 edit` needs c12-parser built with the `tui` feature".into())
+}
+
+#[cfg(feature = "lsp")]
+fn lsp(args: &[String]) -> Result<ExitCode, Box<dyn Error>> {
+    lsp::main(args)
+}
+
+#[cfg(not(feature = "lsp"))]
+fn lsp(_args: &[String]) -> Result<ExitCode, Box<dyn Error>> {
+    Err("`c12 lsp` needs c12-parser built with the `lsp` feature".into())
+}
+
+/// The text of `file` and the format its extension names.
+fn read_config(file: &str) -> Result<(String, Format), Box<dyn Error>> {
+    let format = FsPath::new(file)
+        .extension()
+        .and_then(|ext| Format::from_name(&ext.to_string_lossy()))
+        .ok_or("cannot tell the format from the extension")?;
+    Ok((std::fs::read_to_string(file)?, format))
+}
+
+/// Command arguments split into positional ones, `--name value` options
+/// and `--name` flags.
+struct Args<'a> {
+    positional: Vec<&'a str>,
+    options: Vec<(&'a str, &'a str)>,
+    flags: Vec<&'a str>,
+}
+
+impl<'a> Args<'a> {
+    /// Splits `args`, accepting only the named `options`.
+    #[cfg_attr(not(any(feature = "tui", feature = "lsp")), allow(dead_code))]
+    fn parse(args: &'a [String], options: &[&str]) -> Result<Self, String> {
+        Self::parse_with_flags(args, options, &[])
+    }
+
+    /// Splits `args`, accepting only the named `options` and `flags`.
+    fn parse_with_flags(
+        args: &'a [String],
+        options: &[&str],
+        flags: &[&str],
+    ) -> Result<Self, String> {
+        let mut parsed = Args {
+            positional: Vec::new(),
+            options: Vec::new(),
+            flags: Vec::new(),
+        };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.strip_prefix("--") {
+                Some(name) if flags.contains(&name) => parsed.flags.push(name),
+                Some(name) if options.contains(&name) => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| format!("`--{name}` needs a value"))?;
+                    parsed.options.push((name, value.as_str()));
+                }
+                Some(name) => return Err(format!("unknown option `--{name}`")),
+                None => parsed.positional.push(arg.as_str()),
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Whether the `--name` flag was given.
+    fn flag(&self, name: &str) -> bool {
+        self.flags.contains(&name)
+    }
+
+    /// The value of the last `--name` given.
+    #[cfg_attr(not(any(feature = "tui", feature = "lsp")), allow(dead_code))]
+    fn option(&self, name: &str) -> Option<&'a str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(option, _)| *option == name)
+            .map(|(_, value)| *value)
+    }
+}
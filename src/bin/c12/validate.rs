@@ -0,0 +1,102 @@
+//! `c12 validate`: check a config against a JSON Schema and report each
+//! problem at its position in the file, as compilers do.
+
+use std::error::Error;
+use std::process::ExitCode;
+
+use c12_parser::{C12Context, Diagnostic, Format, Location, Schema, validate_text};
+
+const USAGE: &str = "usage: c12 validate <file> --schema <file>";
+
+/// Prints a `file:line:column: severity[code]: message` line per parse
+/// error or schema [`Violation`](c12_parser::Violation), exiting with 1
+/// when there is any.
+pub fn main(args: &[String]) -> Result<ExitCode, Box<dyn Error>> {
+    let args = crate::Args::parse(args, &["schema"])?;
+    let ([file], Some(schema)) = (&args.positional[..], args.option("schema")) else {
+        return Err(USAGE.into());
+    };
+    let schema = Schema::new(C12Context::new().load(schema)?.value);
+    let (text, format) = crate::read_config(file)?;
+    let (lines, valid) = validate(file, &text, format, &schema);
+    for line in lines {
+        println!("{line}");
+    }
+    Ok(if valid {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(1)
+    })
+}
+
+/// The report lines for `text`, and whether it is valid.
+fn validate(file: &str, text: &str, format: Format, schema: &Schema) -> (Vec<String>, bool) {
+    let violations = match validate_text(text, format, schema) {
+        Ok(violations) => violations,
+        Err(e) => {
+            let line = format!("{file}:{}:{}: error: {}", e.line, e.column, e.message);
+            return (vec![line], false);
+        }
+    };
+    let lines: Vec<_> = violations
+        .iter()
+        .map(|violation| {
+            let offset = violation.span.as_ref().map_or(0, |span| span.start);
+            let at = Location::of_offset(text, offset);
+            format!(
+                "{file}:{}:{}: {}[{}]: {violation}",
+                at.line,
+                at.column,
+                crate::doctor::severity_name(violation.severity()),
+                violation.code().id()
+            )
+        })
+        .collect();
+    let valid = lines.is_empty();
+    (lines, valid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reports_problems_at_their_lines() {
+        let schema = Schema::new(json!({
+            "type": "object",
+            "required": ["name"],
+            "properties": {
+                "name": { "type": "string", "pattern": "^[a-z]+$" },
+                "port": { "type": "integer", "maximum": 65535 }
+            }
+        }));
+        let (lines, valid) = validate("app.yaml", "name: web\nport: 80\n", Format::Yaml, &schema);
+        assert_eq!((lines.len(), valid), (0, true));
+
+        let (lines, valid) = validate("app.yaml", "# app\nport: high\n", Format::Yaml, &schema);
+        assert_eq!(
+            lines,
+            [
+                "app.yaml:1:1: error[C12E016]: missing required key `name`",
+                "app.yaml:2:1: error[C12E014]: `/port`: expected integer, found string"
+            ]
+        );
+        assert!(!valid);
+
+        let text = "name: Web\nport: 80000\n";
+        let (lines, valid) = validate("app.yaml", text, Format::Yaml, &schema);
+        assert_eq!(
+            lines,
+            [
+                "app.yaml:1:1: error[C12E024]: `/name`: \"Web\" does not match the pattern `^[a-z]+$`",
+                "app.yaml:2:1: error[C12E024]: `/port`: expected at most 65535, found 80000"
+            ]
+        );
+        assert!(!valid);
+
+        let (lines, valid) = validate("app.toml", "port = \n", Format::Toml, &schema);
+        assert!(!valid);
+        assert!(lines[0].starts_with("app.toml:1:"), "{}", lines[0]);
+    }
+}
@@ -0,0 +1,54 @@
+// Format-agnostic entry points shared by the language bindings. Values
+// cross the boundary as `serde_json::Value`, formats are named by their
+// confbox names and errors are plain strings, so a binding layer only has
+// to convert plain data.
+
+use serde_json::Value as JsonValue;
+
+use crate::format::{Format, FormatOptions, Formatted};
+use crate::registry::{parse_as, stringify_as};
+
+fn format_named(name: &str) -> Result<Format, String> {
+    Format::from_name(name).ok_or_else(|| format!("unsupported format `{name}`"))
+}
+
+/// Parses `text` written in the named format.
+pub(crate) fn parse(
+    format: &str,
+    text: &str,
+    options: FormatOptions,
+) -> Result<Formatted<JsonValue>, String> {
+    parse_as(format_named(format)?, text, options).map_err(|e| e.to_string())
+}
+
+/// Stringifies a value into the named format.
+pub(crate) fn stringify(
+    format: &str,
+    formatted: &Formatted<JsonValue>,
+    options: FormatOptions,
+) -> Result<String, String> {
+    stringify_as(format_named(format)?, formatted, options).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn round_trips_every_format_by_name() {
+        let value = json!({ "section": { "key": "value" } });
+        for format in ["json", "json5", "jsonc", "yaml", "toml", "ini"] {
+            let formatted = Formatted::new("", value.clone(), &FormatOptions::default());
+            let text = stringify(format, &formatted, FormatOptions::default()).unwrap();
+            let parsed = parse(format, &text, FormatOptions::default()).unwrap();
+            assert_eq!(parsed.value, value, "{format}");
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_formats() {
+        let err = parse("xml", "<a/>", FormatOptions::default()).unwrap_err();
+        assert_eq!(err, "unsupported format `xml`");
+    }
+}
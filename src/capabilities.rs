@@ -0,0 +1,140 @@
+use serde::Serialize;
+
+use crate::format::Format;
+
+/// What a format can hold and how it round-trips, from [`formats`] or
+/// [`FormatRegistry::formats`](crate::FormatRegistry::formats). Serializes
+/// as a plain object, for help screens and generated documentation.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct FormatCapabilities {
+    /// The name the registry parses and stringifies it by.
+    pub name: String,
+    /// The built-in format, `None` for custom ones.
+    pub format: Option<Format>,
+    /// File extensions read as this format, without the dot.
+    pub extensions: Vec<String>,
+    /// The syntax has comments. Parsing into a value drops them; editing
+    /// through a [`Document`](crate::Document) keeps them.
+    pub comments: bool,
+    /// Stringifying writes keys in the order they were read or inserted.
+    pub preserves_order: bool,
+    /// Files can be edited in place with
+    /// [`parse_document`](crate::parse_document).
+    pub editable: bool,
+    /// What a value loses when written in this format.
+    pub lossy: Vec<String>,
+}
+
+/// The built-in formats and their capabilities, in [`Format::ALL`]
+/// order. Custom formats are listed by their registry's
+/// [`formats`](crate::FormatRegistry::formats).
+///
+/// ```
+/// use c12_parser::formats;
+///
+/// let with_comments: Vec<_> = formats()
+///     .into_iter()
+///     .filter(|f| f.comments)
+///     .map(|f| f.name)
+///     .collect();
+/// assert_eq!(with_comments, ["json5", "jsonc", "toml", "yaml", "ini"]);
+/// ```
+pub fn formats() -> Vec<FormatCapabilities> {
+    Format::ALL.into_iter().map(capabilities).collect()
+}
+
+fn capabilities(format: Format) -> FormatCapabilities {
+    let (extensions, comments, preserves_order, editable, lossy): (&[&str], _, _, _, &[&str]) =
+        match format {
+            Format::Json => (&["json"], false, true, true, &[]),
+            Format::Json5 => (&["json5"], true, true, true, &[]),
+            Format::Jsonc => (&["jsonc"], true, true, true, &[]),
+            Format::Toml => (
+                &["toml"],
+                true,
+                false,
+                true,
+                &[
+                    "null values, which TOML cannot hold",
+                    "key order: tables are written after plain values",
+                ],
+            ),
+            Format::Yaml => (&["yaml", "yml"], true, true, true, &[]),
+            Format::Ini => (
+                &["ini"],
+                true,
+                true,
+                false,
+                &[
+                    "types: every value reads back as a string",
+                    "arrays and objects inside sections, written as JSON strings",
+                ],
+            ),
+        };
+    FormatCapabilities {
+        name: format.name().to_string(),
+        format: Some(format),
+        extensions: extensions.iter().map(|ext| ext.to_string()).collect(),
+        comments,
+        preserves_order,
+        editable,
+        lossy: lossy.iter().map(|loss| loss.to_string()).collect(),
+    }
+}
+
+/// What is known of a custom format: its name, taken as its extension.
+pub(crate) fn custom_capabilities(name: &str) -> FormatCapabilities {
+    FormatCapabilities {
+        name: name.to_string(),
+        format: None,
+        extensions: vec![name.to_string()],
+        comments: false,
+        preserves_order: false,
+        editable: false,
+        lossy: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FormatRegistry, parse_document};
+
+    #[test]
+    fn match_what_the_crate_does() {
+        for capabilities in formats() {
+            let format = capabilities.format.unwrap();
+            for ext in &capabilities.extensions {
+                assert_eq!(Format::from_name(ext), Some(format));
+            }
+            let text = match format {
+                Format::Yaml => "a: 1\n",
+                Format::Toml | Format::Ini => "a = 1\n",
+                _ => "{ \"a\": 1 }",
+            };
+            assert_eq!(
+                parse_document(text, format).is_ok(),
+                capabilities.editable,
+                "{format}"
+            );
+        }
+
+        let mut registry = FormatRegistry::new();
+        registry
+            .register(
+                "props",
+                |_| Ok(serde_json::Value::Null),
+                |_| Ok(String::new()),
+            )
+            .unwrap();
+        let names: Vec<_> = registry.formats().into_iter().map(|f| f.name).collect();
+        assert_eq!(
+            names,
+            ["json", "json5", "jsonc", "toml", "yaml", "ini", "props"]
+        );
+        assert_eq!(
+            serde_json::to_value(&registry.formats()[6]).unwrap()["format"],
+            serde_json::Value::Null
+        );
+    }
+}
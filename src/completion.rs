@@ -0,0 +1,189 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as JsonValue};
+
+use crate::path::join_key;
+use crate::schema_diff::{required, types};
+
+/// A key an editor can offer at some path.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Completion {
+    pub key: String,
+    /// The schema's `type`s, sorted; empty when unconstrained.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub types: Vec<String>,
+    /// The `description`, or the `title` when there is none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
+    /// The values to suggest: the `enum` or `const`, or both booleans.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<JsonValue>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<JsonValue>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub required: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub deprecated: bool,
+    /// The schema's `x-replaced-by` for deprecated keys.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replaced_by: Option<String>,
+}
+
+fn is_false(b: &bool) -> bool {
+    !b
+}
+
+/// Completion data for editor plugins: the keys a schema declares under
+/// each object path, with `[*]` standing for array items as in
+/// [`diff_schemas`](crate::diff_schemas). Serializes as a JSON object from
+/// path to key list, the root being `""`.
+///
+/// ```
+/// use c12_parser::completion_model;
+/// use serde_json::json;
+///
+/// let schema = json!({
+///     "properties": {
+///         "log": {
+///             "description": "Logging settings",
+///             "properties": { "level": { "enum": ["debug", "info"] } }
+///         }
+///     }
+/// });
+/// let model = completion_model(&schema);
+/// assert_eq!(model.keys_at("log")[0].values, [json!("debug"), json!("info")]);
+/// assert_eq!(
+///     serde_json::to_value(&model)?,
+///     json!({
+///         "": [{ "key": "log", "doc": "Logging settings" }],
+///         "log": [{ "key": "level", "values": ["debug", "info"] }]
+///     })
+/// );
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CompletionModel {
+    pub paths: BTreeMap<String, Vec<Completion>>,
+}
+
+impl CompletionModel {
+    /// The keys to offer inside the object at `path`, in schema order.
+    pub fn keys_at(&self, path: &str) -> &[Completion] {
+        self.paths.get(path).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Builds the [`CompletionModel`] of a JSON Schema, following
+/// `properties` and `items`. A schema written in YAML or TOML works the
+/// same once parsed.
+pub fn completion_model(schema: &JsonValue) -> CompletionModel {
+    let mut model = CompletionModel::default();
+    collect(schema, "", &mut model);
+    model
+}
+
+fn collect(schema: &JsonValue, path: &str, model: &mut CompletionModel) {
+    if let Some(properties) = schema.get("properties").and_then(JsonValue::as_object) {
+        let required = required(schema);
+        let keys = properties
+            .iter()
+            .map(|(key, property)| Completion {
+                required: required.contains(key),
+                ..completion(key, property)
+            })
+            .collect();
+        model.paths.insert(path.to_string(), keys);
+        for (key, property) in properties {
+            collect(property, &join_key(path, key), model);
+        }
+    }
+    if let Some(items) = schema.get("items") {
+        collect(items, &format!("{path}[*]"), model);
+    }
+}
+
+fn completion(key: &str, property: &JsonValue) -> Completion {
+    let empty = Map::new();
+    let schema = property.as_object().unwrap_or(&empty);
+    let types: Vec<String> = types(property).map_or_else(Vec::new, |t| t.into_iter().collect());
+    let text = |name: &str| {
+        schema
+            .get(name)
+            .and_then(JsonValue::as_str)
+            .map(str::to_string)
+    };
+    let values = match (schema.get("enum"), schema.get("const")) {
+        (Some(JsonValue::Array(values)), _) => values.clone(),
+        (_, Some(value)) => vec![value.clone()],
+        _ if types == ["boolean"] => vec![true.into(), false.into()],
+        _ => Vec::new(),
+    };
+    Completion {
+        key: key.to_string(),
+        doc: text("description").or_else(|| text("title")),
+        values,
+        default: schema.get("default").cloned(),
+        required: false,
+        deprecated: schema.get("deprecated") == Some(&JsonValue::Bool(true)),
+        replaced_by: text("x-replaced-by"),
+        types,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn collects_keys_per_path() {
+        let schema = json!({
+            "required": ["port"],
+            "properties": {
+                "port": { "type": "integer", "default": 8080, "title": "Port" },
+                "tls": { "type": "boolean" },
+                "host": { "deprecated": true, "x-replaced-by": "hostname" },
+                "routes": {
+                    "type": "array",
+                    "items": { "properties": { "mode": { "const": "proxy" } } }
+                }
+            }
+        });
+        let model = completion_model(&schema);
+        assert_eq!(model.paths.keys().collect::<Vec<_>>(), ["", "routes[*]"]);
+        let root = model.keys_at("");
+        assert_eq!(
+            root[0],
+            Completion {
+                key: "port".into(),
+                types: vec!["integer".into()],
+                doc: Some("Port".into()),
+                default: Some(json!(8080)),
+                required: true,
+                ..Completion::default()
+            }
+        );
+        assert_eq!(root[1].values, [json!(true), json!(false)]);
+        assert!(root[2].deprecated);
+        assert_eq!(root[2].replaced_by.as_deref(), Some("hostname"));
+        assert_eq!(model.keys_at("routes[*]")[0].values, [json!("proxy")]);
+        assert!(model.keys_at("missing").is_empty());
+    }
+
+    #[test]
+    fn the_json_form_reads_back() {
+        let schema = json!({ "properties": { "a b": { "properties": { "c": {} } } } });
+        let model = completion_model(&schema);
+        let json = serde_json::to_value(&model).unwrap();
+        assert_eq!(
+            json,
+            json!({ "": [{ "key": "a b" }], "[\"a b\"]": [{ "key": "c" }] })
+        );
+        assert_eq!(
+            serde_json::from_value::<CompletionModel>(json).unwrap(),
+            model
+        );
+    }
+}
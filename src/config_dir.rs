@@ -0,0 +1,151 @@
+use std::path::PathBuf;
+
+use crate::context::C12Context;
+
+/// Whose configuration a path belongs to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ConfigScope {
+    /// Settings of the current user.
+    #[default]
+    User,
+    /// Settings shared by every user on the machine.
+    Machine,
+}
+
+/// Returns the conventional per-user config directory for `app`:
+///
+/// - Linux and other Unix: `$XDG_CONFIG_HOME/<app>`, falling back to
+///   `~/.config/<app>`
+/// - macOS: `~/Library/Application Support/<app>`
+/// - Windows: `%APPDATA%\<app>`
+///
+/// Returns `None` when the base directory cannot be determined, e.g. with
+/// no home directory set. [`C12Context::config_path`] reads the variables
+/// from a context instead of the process environment.
+pub fn default_config_path(app: &str) -> Option<PathBuf> {
+    scoped_config_path(app, ConfigScope::User)
+}
+
+/// Like [`default_config_path`], with a choice of scope. Machine-wide
+/// configs live in the first entry of `$XDG_CONFIG_DIRS` (default
+/// `/etc/xdg`), `/Library/Application Support` or `%PROGRAMDATA%`.
+pub fn scoped_config_path(app: &str, scope: ConfigScope) -> Option<PathBuf> {
+    C12Context::new().config_path(app, scope)
+}
+
+/// Like [`scoped_config_path`], reading variables through `env`.
+pub(crate) fn config_path_with(
+    app: &str,
+    scope: ConfigScope,
+    env: impl Fn(&str) -> Option<String>,
+) -> Option<PathBuf> {
+    let os = if cfg!(windows) {
+        Os::Windows
+    } else if cfg!(target_os = "macos") {
+        Os::Mac
+    } else {
+        Os::Unix
+    };
+    resolve(os, scope, env).map(|base| base.join(app))
+}
+
+#[derive(Clone, Copy)]
+enum Os {
+    Unix,
+    Mac,
+    Windows,
+}
+
+fn resolve(os: Os, scope: ConfigScope, env: impl Fn(&str) -> Option<String>) -> Option<PathBuf> {
+    // Empty variables are treated as unset, as the XDG spec requires.
+    let var = |name: &str| env(name).filter(|v| !v.is_empty()).map(PathBuf::from);
+    match (os, scope) {
+        (Os::Unix, ConfigScope::User) => {
+            var("XDG_CONFIG_HOME").or_else(|| Some(var("HOME")?.join(".config")))
+        }
+        (Os::Unix, ConfigScope::Machine) => {
+            let dirs = env("XDG_CONFIG_DIRS").unwrap_or_default();
+            let first = dirs
+                .split(':')
+                .find(|d| !d.is_empty())
+                .unwrap_or("/etc/xdg");
+            Some(PathBuf::from(first))
+        }
+        (Os::Mac, ConfigScope::User) => {
+            Some(var("HOME")?.join("Library").join("Application Support"))
+        }
+        (Os::Mac, ConfigScope::Machine) => Some(PathBuf::from("/Library/Application Support")),
+        (Os::Windows, ConfigScope::User) => var("APPDATA"),
+        (Os::Windows, ConfigScope::Machine) => var("PROGRAMDATA"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| v.to_string())
+        }
+    }
+
+    #[test]
+    fn unix_follows_xdg() {
+        let home = [("HOME", "/home/ada")];
+        assert_eq!(
+            resolve(Os::Unix, ConfigScope::User, env(&home)),
+            Some(PathBuf::from("/home/ada/.config"))
+        );
+        let xdg = [("HOME", "/home/ada"), ("XDG_CONFIG_HOME", "/cfg")];
+        assert_eq!(
+            resolve(Os::Unix, ConfigScope::User, env(&xdg)),
+            Some(PathBuf::from("/cfg"))
+        );
+        let empty = [("HOME", "/home/ada"), ("XDG_CONFIG_HOME", "")];
+        assert_eq!(
+            resolve(Os::Unix, ConfigScope::User, env(&empty)),
+            Some(PathBuf::from("/home/ada/.config"))
+        );
+        assert_eq!(
+            resolve(Os::Unix, ConfigScope::Machine, env(&[])),
+            Some(PathBuf::from("/etc/xdg"))
+        );
+        let dirs = [("XDG_CONFIG_DIRS", "/opt/xdg:/etc/xdg")];
+        assert_eq!(
+            resolve(Os::Unix, ConfigScope::Machine, env(&dirs)),
+            Some(PathBuf::from("/opt/xdg"))
+        );
+        assert_eq!(resolve(Os::Unix, ConfigScope::User, env(&[])), None);
+    }
+
+    #[test]
+    fn macos_and_windows_use_platform_dirs() {
+        let home = [("HOME", "/Users/ada")];
+        assert_eq!(
+            resolve(Os::Mac, ConfigScope::User, env(&home)),
+            Some(PathBuf::from("/Users/ada/Library/Application Support"))
+        );
+        let win = [
+            ("APPDATA", r"C:\Users\ada\AppData\Roaming"),
+            ("PROGRAMDATA", r"C:\ProgramData"),
+        ];
+        assert_eq!(
+            resolve(Os::Windows, ConfigScope::User, env(&win)),
+            Some(PathBuf::from(r"C:\Users\ada\AppData\Roaming"))
+        );
+        assert_eq!(
+            resolve(Os::Windows, ConfigScope::Machine, env(&win)),
+            Some(PathBuf::from(r"C:\ProgramData"))
+        );
+    }
+
+    #[test]
+    fn appends_the_app_name() {
+        if let Some(path) = default_config_path("myapp") {
+            assert!(path.ends_with("myapp"));
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use serde_json::Value as JsonValue;
+
+use crate::empty::EmptyDocument;
+use crate::error::{C12Error, parse_format};
+use crate::format::{Format, FormatOptions};
+use crate::path::Path;
+use crate::value_diff::diff_values;
+
+/// A config file with git conflict markers, read by [`parse_conflicts`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConflictedConfig {
+    pub format: Format,
+    pub regions: Vec<ConflictRegion>,
+    /// The file with every region resolved to our side.
+    pub ours_text: String,
+    /// The file with every region resolved to their side.
+    pub theirs_text: String,
+    pub ours: JsonValue,
+    pub theirs: JsonValue,
+    /// Every value the two sides disagree on, in the order of ours
+    /// followed by keys only theirs has.
+    pub conflicts: Vec<KeyConflict>,
+}
+
+/// One `<<<<<<<` ... `>>>>>>>` region of a conflicted file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConflictRegion {
+    /// 1-based line of the `<<<<<<<` marker.
+    pub start_line: usize,
+    /// 1-based line of the `>>>>>>>` marker.
+    pub end_line: usize,
+    /// The text after `<<<<<<<`, usually `HEAD`.
+    pub ours_label: String,
+    /// The text after `>>>>>>>`, usually a branch or commit.
+    pub theirs_label: String,
+    /// Our lines, each with its line break.
+    pub ours: String,
+    /// The common ancestor's lines, in the diff3 style with a `|||||||`
+    /// section.
+    pub base: Option<String>,
+    pub theirs: String,
+}
+
+/// A value that differs between the two sides of a conflicted config.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyConflict {
+    pub path: Path,
+    /// Our value, `None` when only their side has the key.
+    pub ours: Option<JsonValue>,
+    /// Their value, `None` when only our side has the key.
+    pub theirs: Option<JsonValue>,
+}
+
+/// Which section of a conflict region a line belongs to.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Ours,
+    Base,
+    Theirs,
+}
+
+/// Reads a config file left with git conflict markers, instead of failing
+/// with a syntax error at the first `<<<<<<<`.
+///
+/// Both sides are rebuilt as whole files, each region resolved one way,
+/// and parsed, so a resolution tool can show the keys in conflict with
+/// both values. Regions may carry a diff3 `|||||||` base section. Returns
+/// `None` when `text` has no conflict markers.
+///
+/// Errors point at `text` itself: an unclosed region at its `<<<<<<<`
+/// line, and a side that does not parse at the offending line, with the
+/// side named in the message.
+///
+/// ```
+/// use c12_parser::{Format, parse_conflicts};
+/// use serde_json::json;
+///
+/// let text = "name: app\n<<<<<<< HEAD\nport: 80\n=======\nport: 8080\ndebug: true\n>>>>>>> feature\n";
+/// let conflicted = parse_conflicts(text, Format::Yaml)?.unwrap();
+/// assert_eq!(conflicted.regions[0].theirs_label, "feature");
+/// let keys: Vec<_> = conflicted.conflicts.iter().map(|c| c.path.to_string()).collect();
+/// assert_eq!(keys, ["port", "debug"]);
+/// assert_eq!(conflicted.conflicts[0].ours, Some(json!(80)));
+/// assert_eq!(conflicted.theirs_text, "name: app\nport: 8080\ndebug: true\n");
+/// # Ok::<(), c12_parser::C12Error>(())
+/// ```
+pub fn parse_conflicts(text: &str, format: Format) -> Result<Option<ConflictedConfig>, C12Error> {
+    let mut regions = Vec::new();
+    // Each side's text, with the 0-based line of `text` each of its lines
+    // came from.
+    let mut ours = (String::new(), Vec::new());
+    let mut theirs = (String::new(), Vec::new());
+    let mut open: Option<(ConflictRegion, Section)> = None;
+
+    for (index, line) in text.split_inclusive('\n').enumerate() {
+        let content = line.trim_end_matches(['\n', '\r']);
+        let marker = |prefix: &str| {
+            content
+                .strip_prefix(prefix)
+                .filter(|rest| rest.is_empty() || rest.starts_with(' '))
+                .map(|rest| rest.trim().to_string())
+        };
+        let Some((region, section)) = open.as_mut() else {
+            if let Some(label) = marker("<<<<<<<") {
+                let region = ConflictRegion {
+                    start_line: index + 1,
+                    end_line: 0,
+                    ours_label: label,
+                    theirs_label: String::new(),
+                    ours: String::new(),
+                    base: None,
+                    theirs: String::new(),
+                };
+                open = Some((region, Section::Ours));
+            } else {
+                for side in [&mut ours, &mut theirs] {
+                    side.0.push_str(line);
+                    side.1.push(index);
+                }
+            }
+            continue;
+        };
+        if *section != Section::Theirs && marker("=======").is_some() {
+            *section = Section::Theirs;
+        } else if *section == Section::Ours && marker("|||||||").is_some() {
+            region.base = Some(String::new());
+            *section = Section::Base;
+        } else if *section == Section::Theirs
+            && let Some(label) = marker(">>>>>>>")
+        {
+            region.end_line = index + 1;
+            region.theirs_label = label;
+            regions.extend(open.take().map(|(region, _)| region));
+        } else {
+            let (lines, side) = match section {
+                Section::Ours => (&mut region.ours, Some(&mut ours)),
+                Section::Base => (region.base.get_or_insert_default(), None),
+                Section::Theirs => (&mut region.theirs, Some(&mut theirs)),
+            };
+            lines.push_str(line);
+            if let Some(side) = side {
+                side.0.push_str(line);
+                side.1.push(index);
+            }
+        }
+    }
+    if let Some((region, _)) = open {
+        return Err(C12Error::at_line_column(
+            format,
+            text,
+            region.start_line,
+            1,
+            "conflict region is never closed".into(),
+        ));
+    }
+    if regions.is_empty() {
+        return Ok(None);
+    }
+
+    let parse = |(side, lines): &(String, Vec<usize>), name: &str| {
+        let opts = FormatOptions {
+            on_empty: EmptyDocument::Null,
+            ..FormatOptions::default()
+        };
+        parse_format::<JsonValue>(side, format, Some(opts))
+            .map(|parsed| parsed.value)
+            .map_err(|e| {
+                let line = lines
+                    .get(e.line - 1)
+                    .map_or(text.lines().count(), |l| l + 1);
+                let message = format!("{name} side: {}", e.message);
+                C12Error::at_line_column(format, text, line, e.column, message)
+            })
+    };
+    let ours_value = parse(&ours, "our")?;
+    let theirs_value = parse(&theirs, "their")?;
+    let conflicts = diff_values(&ours_value, &theirs_value)
+        .into_iter()
+        .map(|change| KeyConflict {
+            path: change.path,
+            ours: change.old,
+            theirs: change.new,
+        })
+        .collect();
+    Ok(Some(ConflictedConfig {
+        format,
+        regions,
+        ours_text: ours.0,
+        theirs_text: theirs.0,
+        ours: ours_value,
+        theirs: theirs_value,
+        conflicts,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reads_diff3_regions_and_keyed_conflicts() {
+        let text = "\
+{
+<<<<<<< HEAD
+  \"port\": 80,
+  \"host\": \"a\",
+||||||| base
+  \"port\": 70,
+=======
+  \"port\": 8080,
+>>>>>>> topic
+  \"name\": \"app\"
+}
+";
+        let conflicted = parse_conflicts(text, Format::Json).unwrap().unwrap();
+        let region = &conflicted.regions[0];
+        assert_eq!((region.start_line, region.end_line), (2, 9));
+        assert_eq!(
+            (region.ours_label.as_str(), region.theirs_label.as_str()),
+            ("HEAD", "topic")
+        );
+        assert_eq!(region.base.as_deref(), Some("  \"port\": 70,\n"));
+        assert_eq!(
+            conflicted.conflicts,
+            [
+                KeyConflict {
+                    path: Path::root().key("port"),
+                    ours: Some(json!(80)),
+                    theirs: Some(json!(8080)),
+                },
+                KeyConflict {
+                    path: Path::root().key("host"),
+                    ours: Some(json!("a")),
+                    theirs: None,
+                },
+            ]
+        );
+        assert!(parse_conflicts("a = 1\n", Format::Toml).unwrap().is_none());
+    }
+
+    #[test]
+    fn locates_unclosed_regions_and_broken_sides() {
+        let err = parse_conflicts("a = 1\n<<<<<<< HEAD\nb = 2\n", Format::Toml).unwrap_err();
+        assert_eq!(
+            (err.line, err.message.as_str()),
+            (2, "conflict region is never closed")
+        );
+
+        let text = "a = 1\n<<<<<<< HEAD\nb = 2\n=======\nb = \n>>>>>>> x\n";
+        let err = parse_conflicts(text, Format::Toml).unwrap_err();
+        assert_eq!(err.line, 5);
+        assert!(err.message.starts_with("their side: "), "{err}");
+    }
+}
@@ -0,0 +1,746 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path as FsPath, PathBuf};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+use serde_json::Value as JsonValue;
+
+use crate::config_dir::{ConfigScope, config_path_with};
+use crate::format::{Format, FormatOptions, Formatted};
+use crate::hooks::{HookResult, Hooks};
+use crate::interpolate::process_env;
+use crate::io::{Clock, Fetcher, FileSystem, OsFileSystem, SystemClock, default_fetcher};
+use crate::limits::Limits;
+use crate::ownership::{ForeignEdit, Ownership, OwnershipError, OwnershipPolicy};
+use crate::registry::{FormatRegistry, ReservedFormatName};
+use crate::value_diff::diff_values;
+
+/// A source of nondeterminism that a pipeline may consult while resolving
+/// a config.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Nondeterminism {
+    /// Reading an environment variable.
+    EnvVar(String),
+    /// Reading the current date or time.
+    Clock,
+    /// Fetching a remote source.
+    Remote(String),
+}
+
+impl fmt::Display for Nondeterminism {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Nondeterminism::EnvVar(name) => write!(f, "environment variable `{name}`"),
+            Nondeterminism::Clock => write!(f, "the system clock"),
+            Nondeterminism::Remote(url) => write!(f, "remote source `{url}`"),
+        }
+    }
+}
+
+/// Returned when deterministic mode is violated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeterminismError {
+    /// A nondeterministic source was requested.
+    Disallowed(Nondeterminism),
+    /// Resolving twice produced different results.
+    Unstable,
+}
+
+impl fmt::Display for DeterminismError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeterminismError::Disallowed(source) => {
+                write!(f, "deterministic mode forbids reading {source}")
+            }
+            DeterminismError::Unstable => {
+                write!(f, "resolving twice produced different results")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DeterminismError {}
+
+/// Settings shared by the loading pipeline.
+///
+/// Everything that reads the environment, the clock, files or the network
+/// goes through the context, so one flag can switch nondeterminism off and
+/// tests can swap in a [`MemoryFileSystem`](crate::MemoryFileSystem),
+/// [`FixedClock`](crate::FixedClock) or
+/// [`MemoryFetcher`](crate::MemoryFetcher). The context also carries the hooks
+/// run by [`parse_with`](Self::parse_with) and
+/// [`stringify_with`](Self::stringify_with), and its own
+/// [`FormatRegistry`], which holds its value limits and is used by
+/// [`parse`](Self::parse), [`stringify`](Self::stringify),
+/// [`load`](Self::load) and [`save`](Self::save). Nothing is global:
+/// separate contexts never see each other's settings.
+///
+/// The per-format functions such as [`parse_json`](crate::parse_json)
+/// take no context. They see only their [`FormatOptions`], and
+/// [`interpolate_env`](FormatOptions::interpolate_env) reads the process
+/// environment there. Parse through a context to apply its hooks, limits
+/// and environment.
+#[derive(Clone, Debug)]
+pub struct C12Context {
+    deterministic: bool,
+    hooks: Hooks,
+    registry: FormatRegistry,
+    ownership: Option<Ownership>,
+    require_acknowledgment: bool,
+    fs: Arc<dyn FileSystem>,
+    clock: Arc<dyn Clock>,
+    fetcher: Arc<dyn Fetcher>,
+    env: Option<Arc<BTreeMap<String, String>>>,
+}
+
+impl Default for C12Context {
+    fn default() -> Self {
+        Self {
+            deterministic: false,
+            hooks: Hooks::default(),
+            registry: FormatRegistry::default(),
+            ownership: None,
+            require_acknowledgment: false,
+            fs: Arc::new(OsFileSystem),
+            clock: Arc::new(SystemClock),
+            fetcher: default_fetcher(),
+            env: None,
+        }
+    }
+}
+
+impl C12Context {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Disables every nondeterministic input (env vars, the clock and
+    /// remote sources), for pipelines that hash resolved configs.
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    pub fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    /// Fails if `source` may not be consulted under this context.
+    pub fn allow(&self, source: Nondeterminism) -> Result<(), DeterminismError> {
+        if self.deterministic {
+            Err(DeterminismError::Disallowed(source))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Reads an environment variable, or `None` in deterministic mode.
+    pub fn env_var(&self, name: &str) -> Option<String> {
+        if self.deterministic {
+            None
+        } else if let Some(env) = &self.env {
+            env.get(name).cloned()
+        } else {
+            process_env(name)
+        }
+    }
+
+    /// Every environment variable with a UTF-8 name and value, sorted by
+    /// name, or none in deterministic mode.
+    pub fn env_vars(&self) -> Vec<(String, String)> {
+        if self.deterministic {
+            return Vec::new();
+        }
+        if let Some(env) = &self.env {
+            return env.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        }
+        let mut vars: Vec<_> = std::env::vars_os()
+            .filter_map(|(k, v)| Some((k.into_string().ok()?, v.into_string().ok()?)))
+            .collect();
+        vars.sort();
+        vars
+    }
+
+    /// The conventional config directory for `app`, as
+    /// [`scoped_config_path`](crate::scoped_config_path) finds it, reading
+    /// the variables it depends on through [`env_var`](Self::env_var).
+    /// `None` in deterministic mode unless the base directory needs no
+    /// variable.
+    ///
+    /// ```
+    /// use c12_parser::{C12Context, ConfigScope};
+    ///
+    /// let ctx = C12Context::new().env([
+    ///     ("HOME", "/home/ada"),
+    ///     ("XDG_CONFIG_HOME", "/cfg"),
+    ///     ("APPDATA", r"C:\Users\ada"),
+    /// ]);
+    /// let dir = ctx.config_path("app", ConfigScope::User).unwrap();
+    /// assert!(dir.ends_with("app"));
+    /// ```
+    pub fn config_path(&self, app: &str, scope: ConfigScope) -> Option<PathBuf> {
+        config_path_with(app, scope, |name| self.env_var(name))
+    }
+
+    /// Today's UTC date as `YYYY-MM-DD` by the context's clock, or `None`
+    /// in deterministic mode.
+    pub fn today(&self) -> Option<String> {
+        if self.deterministic {
+            return None;
+        }
+        let secs = self
+            .clock
+            .now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        Some(civil_date(secs / 86_400))
+    }
+
+    /// Runs `resolve` twice and returns its result, failing if the two runs
+    /// disagree. A no-op check outside deterministic mode.
+    pub fn assert_deterministic<T: PartialEq>(
+        &self,
+        mut resolve: impl FnMut(&Self) -> T,
+    ) -> Result<T, DeterminismError> {
+        let first = resolve(self);
+        if self.deterministic && resolve(self) != first {
+            return Err(DeterminismError::Unstable);
+        }
+        Ok(first)
+    }
+
+    /// Adds a filter run on the raw text before parsing, after any
+    /// previously added ones.
+    pub fn pre_parse(
+        mut self,
+        hook: impl Fn(&str) -> HookResult<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.hooks.pre_parse.push(Arc::new(hook));
+        self
+    }
+
+    /// Adds a transform run on the parsed value.
+    pub fn post_parse(
+        mut self,
+        hook: impl Fn(JsonValue) -> HookResult<JsonValue> + Send + Sync + 'static,
+    ) -> Self {
+        self.hooks.post_parse.push(Arc::new(hook));
+        self
+    }
+
+    /// Adds a filter run on the stringified text before it is returned
+    /// for saving.
+    pub fn pre_save(
+        mut self,
+        hook: impl Fn(&str) -> HookResult<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.hooks.pre_save.push(Arc::new(hook));
+        self
+    }
+
+    /// Replaces the file system used by [`load`](Self::load) and
+    /// [`save`](Self::save).
+    pub fn file_system(mut self, fs: impl FileSystem + 'static) -> Self {
+        self.fs = Arc::new(fs);
+        self
+    }
+
+    /// Replaces the process environment read by
+    /// [`env_var`](Self::env_var) and [`env_vars`](Self::env_vars) with a
+    /// fixed set of variables.
+    pub fn env<K, V>(mut self, vars: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let vars = vars.into_iter().map(|(k, v)| (k.into(), v.into()));
+        self.env = Some(Arc::new(vars.collect()));
+        self
+    }
+
+    /// Replaces the fetcher used by [`fetch`](Self::fetch):
+    /// `HttpFetcher` by default with the `http` feature. Without it nothing
+    /// is fetched unless a fetcher such as [`CurlFetcher`](crate::CurlFetcher)
+    /// is passed here.
+    pub fn fetcher(mut self, fetcher: impl Fetcher + 'static) -> Self {
+        self.fetcher = Arc::new(fetcher);
+        self
+    }
+
+    /// Fetches `url` with the context's fetcher. Fails in deterministic
+    /// mode.
+    pub fn fetch(&self, url: &str) -> HookResult<String> {
+        self.allow(Nondeterminism::Remote(url.to_string()))?;
+        Ok(self.fetcher.fetch(url)?)
+    }
+
+    /// Replaces the clock used by [`today`](Self::today).
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Bounds every value parsed through this context, whatever its format,
+    /// by setting [`FormatOptions::limits`] in its registry; see
+    /// [`FormatRegistry::set_limits`]. Options set afterwards with
+    /// [`style`](Self::style) or [`format_defaults`](Self::format_defaults)
+    /// bring their own limits.
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.registry.set_limits(limits);
+        self
+    }
+
+    /// Checks saves against `policy` on behalf of `actor`, the editing user
+    /// and the teams they belong to; see
+    /// [`foreign_edits`](Self::foreign_edits).
+    pub fn ownership(
+        mut self,
+        policy: OwnershipPolicy,
+        actor: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.ownership = Some(Ownership {
+            policy,
+            actor: actor.into_iter().map(Into::into).collect(),
+        });
+        self
+    }
+
+    /// Makes [`save`](Self::save) fail with an [`OwnershipError`] when it
+    /// would edit keys owned by other teams; such edits then go through
+    /// [`save_acknowledged`](Self::save_acknowledged). Without this, they
+    /// are only reported by [`foreign_edits`](Self::foreign_edits).
+    pub fn require_acknowledgment(mut self, require: bool) -> Self {
+        self.require_acknowledgment = require;
+        self
+    }
+
+    /// Sets the style options used for every format without its own
+    /// [`format_defaults`](Self::format_defaults).
+    pub fn style(mut self, options: FormatOptions) -> Self {
+        self.registry.set_base(options);
+        self
+    }
+
+    /// Sets the options this context uses for `format`.
+    pub fn format_defaults(mut self, format: Format, options: FormatOptions) -> Self {
+        self.registry.set_defaults(format, options);
+        self
+    }
+
+    /// Registers a custom format for this context only. See
+    /// [`FormatRegistry::register`].
+    pub fn register_format(
+        mut self,
+        name: &str,
+        parse: impl Fn(&str) -> HookResult<JsonValue> + Send + Sync + 'static,
+        stringify: impl Fn(&JsonValue) -> HookResult<String> + Send + Sync + 'static,
+    ) -> Result<Self, ReservedFormatName> {
+        self.registry.register(name, parse, stringify)?;
+        Ok(self)
+    }
+
+    pub fn registry(&self) -> &FormatRegistry {
+        &self.registry
+    }
+
+    pub(crate) fn fs(&self) -> &dyn FileSystem {
+        &*self.fs
+    }
+
+    /// Parses `text` in the named format (built-in or registered) with this
+    /// context's defaults and hooks. With
+    /// [`interpolate_env`](FormatOptions::interpolate_env) set, variables
+    /// are read through [`env_var`](Self::env_var).
+    pub fn parse(&self, format: &str, text: &str) -> HookResult<Formatted<JsonValue>> {
+        let text = self.hooks.run_pre_parse(text)?;
+        let mut formatted = self
+            .registry
+            .parse_in(format, &text, &|name| self.env_var(name))?;
+        formatted.value = self.hooks.run_post_parse(formatted.value)?;
+        Ok(formatted)
+    }
+
+    /// Reads and parses the file at `path`, picking the format from its
+    /// extension.
+    pub fn load(&self, path: impl AsRef<FsPath>) -> HookResult<Formatted<JsonValue>> {
+        let path = path.as_ref();
+        let format = extension(path)?;
+        self.parse(format, &self.fs.read_to_string(path)?)
+    }
+
+    /// Stringifies `formatted` in the format named by the extension of
+    /// `path` and writes it there.
+    pub fn save(
+        &self,
+        path: impl AsRef<FsPath>,
+        formatted: &Formatted<JsonValue>,
+    ) -> HookResult<()> {
+        self.save_acknowledged(path, formatted, &[])
+    }
+
+    /// Like [`save`](Self::save), allowing the listed edits to keys owned
+    /// by other teams, as returned by [`foreign_edits`](Self::foreign_edits)
+    /// and confirmed by the user.
+    ///
+    /// ```
+    /// use c12_parser::{C12Context, MemoryFileSystem, OwnershipError};
+    ///
+    /// let fs = MemoryFileSystem::new().with_file("db.json", r#"{ "pool": 4 }"#);
+    /// let ctx = C12Context::new()
+    ///     .file_system(fs)
+    ///     .ownership("*.json  @org/dba".parse()?, ["@bob", "@org/web"])
+    ///     .require_acknowledgment(true);
+    ///
+    /// let mut config = ctx.load("db.json")?;
+    /// config.value["pool"] = 8.into();
+    /// let err = ctx.save("db.json", &config).unwrap_err();
+    /// assert!(err.is::<OwnershipError>());
+    ///
+    /// let edits = ctx.foreign_edits("db.json", &config)?;
+    /// assert_eq!(edits[0].to_string(), "`pool` in db.json is owned by @org/dba");
+    /// ctx.save_acknowledged("db.json", &config, &edits)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn save_acknowledged(
+        &self,
+        path: impl AsRef<FsPath>,
+        formatted: &Formatted<JsonValue>,
+        acknowledged: &[ForeignEdit],
+    ) -> HookResult<()> {
+        let path = path.as_ref();
+        self.check_ownership(path, formatted, acknowledged)?;
+        let text = self.stringify(extension(path)?, formatted)?;
+        Ok(self.fs.write(path, &text)?)
+    }
+
+    /// Like [`save`](Self::save), through
+    /// [`FileSystem::write_atomic`] so readers of `path` never see a
+    /// partially written file.
+    pub fn save_atomic(
+        &self,
+        path: impl AsRef<FsPath>,
+        formatted: &Formatted<JsonValue>,
+    ) -> HookResult<()> {
+        let path = path.as_ref();
+        self.check_ownership(path, formatted, &[])?;
+        let text = self.stringify(extension(path)?, formatted)?;
+        Ok(self.fs.write_atomic(path, &text)?)
+    }
+
+    /// Fails when acknowledgment is required and saving `formatted` would
+    /// make foreign edits missing from `acknowledged`.
+    fn check_ownership(
+        &self,
+        path: &FsPath,
+        formatted: &Formatted<JsonValue>,
+        acknowledged: &[ForeignEdit],
+    ) -> HookResult<()> {
+        if !self.require_acknowledgment {
+            return Ok(());
+        }
+        let edits: Vec<_> = self
+            .foreign_edits(path, formatted)?
+            .into_iter()
+            .filter(|edit| !acknowledged.contains(edit))
+            .collect();
+        if edits.is_empty() {
+            Ok(())
+        } else {
+            Err(OwnershipError { edits }.into())
+        }
+    }
+
+    /// The edits saving `formatted` to `path` would make to keys owned by
+    /// other teams under the [`ownership`](Self::ownership) policy, for
+    /// warning the user before saving. A file that does not exist yet or no
+    /// longer parses counts as replaced whole.
+    pub fn foreign_edits(
+        &self,
+        path: impl AsRef<FsPath>,
+        formatted: &Formatted<JsonValue>,
+    ) -> HookResult<Vec<ForeignEdit>> {
+        let path = path.as_ref();
+        let Some(ownership) = &self.ownership else {
+            return Ok(Vec::new());
+        };
+        let old = if self.fs.exists(path) {
+            self.load(path).map_or(JsonValue::Null, |old| old.value)
+        } else {
+            JsonValue::Null
+        };
+        let changed = diff_values(&old, &formatted.value)
+            .into_iter()
+            .map(|change| change.path);
+        Ok(ownership
+            .policy
+            .foreign_edits(path, &ownership.actor, changed))
+    }
+
+    /// Stringifies `formatted` in the named format with this context's
+    /// defaults and hooks.
+    pub fn stringify(&self, format: &str, formatted: &Formatted<JsonValue>) -> HookResult<String> {
+        let text = self.registry.stringify(format, formatted)?;
+        self.hooks.run_pre_save(&text)
+    }
+
+    /// Runs the `pre_parse` filters, `parse`, then the `post_parse`
+    /// transforms.
+    ///
+    /// ```
+    /// use c12_parser::{C12Context, parse_json};
+    ///
+    /// let ctx = C12Context::new().pre_parse(|text| Ok(text.replace("%PORT%", "8080")));
+    /// let config = ctx.parse_with(r#"{ "port": %PORT% }"#, |text| parse_json(text, None))?;
+    /// assert_eq!(config.value["port"], 8080);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn parse_with<E: std::error::Error + 'static>(
+        &self,
+        text: &str,
+        parse: impl FnOnce(&str) -> Result<Formatted<JsonValue>, E>,
+    ) -> HookResult<Formatted<JsonValue>> {
+        let text = self.hooks.run_pre_parse(text)?;
+        let mut formatted = parse(&text)?;
+        formatted.value = self.hooks.run_post_parse(formatted.value)?;
+        Ok(formatted)
+    }
+
+    /// Runs `stringify`, then the `pre_save` filters.
+    pub fn stringify_with<E: std::error::Error + 'static>(
+        &self,
+        formatted: &Formatted<JsonValue>,
+        stringify: impl FnOnce(&Formatted<JsonValue>) -> Result<String, E>,
+    ) -> HookResult<String> {
+        let text = stringify(formatted)?;
+        self.hooks.run_pre_save(&text)
+    }
+}
+
+fn extension(path: &FsPath) -> HookResult<&str> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .ok_or_else(|| format!("cannot tell the format of {}", path.display()).into())
+}
+
+/// Converts days since the Unix epoch into a `YYYY-MM-DD` date.
+pub(crate) fn civil_date(days: u64) -> String {
+    // Howard Hinnant's `civil_from_days`, restricted to dates after 1970.
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deterministic_mode_blocks_env_and_clock() {
+        let ctx = C12Context::new().deterministic(true);
+        assert_eq!(ctx.env_var("PATH"), None);
+        assert_eq!(ctx.today(), None);
+        assert_eq!(
+            ctx.allow(Nondeterminism::Clock),
+            Err(DeterminismError::Disallowed(Nondeterminism::Clock))
+        );
+
+        let ctx = C12Context::new();
+        assert!(ctx.today().is_some());
+        assert!(ctx.allow(Nondeterminism::Clock).is_ok());
+    }
+
+    #[test]
+    fn assert_deterministic_detects_unstable_results() {
+        let ctx = C12Context::new().deterministic(true);
+        assert_eq!(ctx.assert_deterministic(|_| "same"), Ok("same"));
+
+        let mut calls = 0;
+        let unstable = ctx.assert_deterministic(|_| {
+            calls += 1;
+            calls
+        });
+        assert_eq!(unstable, Err(DeterminismError::Unstable));
+    }
+
+    #[test]
+    fn hooks_run_in_order_around_parse_and_save() {
+        let ctx = C12Context::new()
+            .pre_parse(|text| Ok(text.replace("{{name}}", "demo")))
+            .post_parse(|mut value| {
+                value["parsed"] = true.into();
+                Ok(value)
+            })
+            .post_parse(|value| Ok(serde_json::json!({ "wrapped": value })))
+            .pre_save(|text| Ok(format!("# generated\n{text}")));
+
+        let formatted = ctx
+            .parse_with("name: '{{name}}'", |text| crate::parse_yaml(text, None))
+            .unwrap();
+        assert_eq!(
+            formatted.value,
+            serde_json::json!({ "wrapped": { "name": "demo", "parsed": true } })
+        );
+
+        let text = ctx
+            .stringify_with(&formatted, |f| crate::stringify_yaml(f, None))
+            .unwrap();
+        assert!(text.starts_with("# generated\nwrapped:"));
+    }
+
+    #[test]
+    fn hook_errors_abort_the_pipeline() {
+        let ctx = C12Context::new().pre_parse(|_| Err("template error".into()));
+        let err = ctx
+            .parse_with("{}", |text| crate::parse_json(text, None))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "template error");
+    }
+
+    #[test]
+    fn contexts_do_not_share_format_settings() {
+        let compact = C12Context::new()
+            .format_defaults(
+                Format::Json,
+                FormatOptions {
+                    indent: Some(4),
+                    profile: crate::StyleProfile::Confbox,
+                    ..Default::default()
+                },
+            )
+            .register_format(
+                "upper",
+                |text| Ok(text.into()),
+                |value| Ok(value.to_string().to_uppercase()),
+            )
+            .unwrap()
+            .pre_save(|text| Ok(format!("{text}\n")));
+        let plain = C12Context::new();
+
+        let formatted = plain.parse("json", r#"{"a": "b"}"#).unwrap();
+        assert_eq!(
+            compact.stringify("json", &formatted).unwrap(),
+            "{\n    \"a\": \"b\"\n}\n"
+        );
+        assert_eq!(
+            plain.stringify("json", &formatted).unwrap(),
+            crate::stringify_json(&formatted, None).unwrap()
+        );
+        assert_eq!(
+            compact.stringify("upper", &formatted).unwrap(),
+            "{\"A\":\"B\"}\n"
+        );
+        assert!(plain.stringify("upper", &formatted).is_err());
+    }
+
+    #[test]
+    fn interpolates_from_the_context_environment() {
+        let mut options = FormatOptions::default();
+        options.interpolate_env = true;
+        let ctx = C12Context::new()
+            .style(options)
+            .env([("C12_TEST_HOST", "db")]);
+        let yaml = ctx
+            .parse("yaml", "url: http://${C12_TEST_HOST}/\n")
+            .unwrap();
+        assert_eq!(yaml.value["url"], "http://db/");
+        let ini = ctx
+            .parse("ini", "url = http://${C12_TEST_HOST}/\n")
+            .unwrap();
+        assert_eq!(ini.value["default"]["url"], "http://db/");
+        let err = ctx
+            .deterministic(true)
+            .parse("yaml", "url: ${C12_TEST_HOST}\n");
+        assert!(err.unwrap_err().is::<crate::InterpolationError>());
+    }
+
+    #[test]
+    fn loads_and_saves_through_the_context_file_system() {
+        use crate::io::{FixedClock, MemoryFileSystem};
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let ctx = C12Context::new()
+            .file_system(MemoryFileSystem::new().with_file("/app/config.yaml", "tags: [a, b, c]\n"))
+            .clock(FixedClock(
+                UNIX_EPOCH + Duration::from_secs(20_000 * 86_400),
+            ))
+            .style(FormatOptions {
+                profile: crate::StyleProfile::Confbox,
+                indent: Some(4),
+                ..Default::default()
+            });
+        assert_eq!(ctx.today().as_deref(), Some("2024-10-04"));
+
+        let config = ctx.load("/app/config.yaml").unwrap();
+        ctx.save("/app/config.json", &config).unwrap();
+        let saved = ctx.load("/app/config.json").unwrap();
+        assert_eq!(saved.value, config.value);
+        assert!(ctx.load("/app/missing.toml").is_err());
+        assert!(ctx.load("/app/config").is_err());
+
+        let limited = ctx.clone().limits(Limits {
+            max_array_len: Some(2),
+            ..Default::default()
+        });
+        let err = limited.load("/app/config.yaml").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "array at `tags` has 3 elements, over the limit of 2"
+        );
+        assert_eq!(
+            err.downcast_ref::<crate::LimitExceeded>().unwrap().actual,
+            3
+        );
+        assert_eq!(
+            limited
+                .registry()
+                .defaults(Format::Yaml)
+                .limits
+                .max_array_len,
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn foreign_edits_warn_unless_acknowledgment_is_required() {
+        use crate::io::MemoryFileSystem;
+
+        let fs = MemoryFileSystem::new().with_file("/repo/app.yaml", "db: 1\nname: a\n");
+        let policy: OwnershipPolicy = "*.yaml @org/web\n*.yaml:db @org/dba\n".parse().unwrap();
+        let ctx = C12Context::new()
+            .file_system(fs)
+            .ownership(policy.root("/repo"), ["@org/web"]);
+        let mut config = ctx.load("/repo/app.yaml").unwrap();
+        config.value["name"] = "b".into();
+        assert!(
+            ctx.foreign_edits("/repo/app.yaml", &config)
+                .unwrap()
+                .is_empty()
+        );
+        config.value["db"] = 2.into();
+        let edits = ctx.foreign_edits("/repo/app.yaml", &config).unwrap();
+        assert_eq!(edits[0].owners, ["@org/dba"]);
+        ctx.save("/repo/app.yaml", &config).unwrap();
+
+        config.value["db"] = 3.into();
+        let strict = ctx.require_acknowledgment(true);
+        let err = strict.save("/repo/app.yaml", &config).unwrap_err();
+        let diagnostic = crate::diagnostic::as_diagnostic(&*err).unwrap();
+        assert_eq!(diagnostic.code().id(), "C12E023");
+        assert!(strict.save("/repo/new.yaml", &config).is_err());
+    }
+
+    #[test]
+    fn civil_date_converts_epoch_days() {
+        assert_eq!(civil_date(0), "1970-01-01");
+        assert_eq!(civil_date(11_016), "2000-02-29");
+        assert_eq!(civil_date(20_000), "2024-10-04");
+    }
+}
@@ -0,0 +1,615 @@
+use serde_json::{Map, Value as JsonValue};
+use toml::Value as TomlValue;
+use toml::value::{Datetime, Offset};
+
+use crate::error::C12Error;
+use crate::flatten::{DottedKeys, nest_dotted_keys};
+use crate::format::{Format, FormatOptions, Formatted};
+use crate::hooks::HookResult;
+use crate::loss::{DataLoss, find_non_finite_numbers, find_toml_nulls};
+use crate::mixed_arrays::{MixedArray, locate_mixed_arrays, normalize_mixed_arrays};
+use crate::path::Path;
+use crate::registry::{parse_as, stringify_as};
+use crate::toml_format::stringify_toml;
+
+/// The key the `toml` crate wraps datetimes in when they are read into
+/// another data model.
+const TOML_DATETIME: &str = "$__toml_private_datetime";
+
+/// What [`convert`] does with `null`s when the target is TOML, which has
+/// no null.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NullPolicy {
+    /// Fail with a [`DataLoss`] listing every `null`.
+    #[default]
+    Error,
+    /// Leave out keys and array items that are `null`.
+    Drop,
+    /// Write `null`s as empty strings.
+    EmptyString,
+}
+
+/// How [`convert`] treats dates and times.
+///
+/// TOML is the only format with datetime values. Converting from TOML
+/// always turns them into RFC 3339 strings, which is what the other
+/// formats' parsers hand back for the same text, and TOML to TOML keeps
+/// them as they are. This policy decides the other direction.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DatetimePolicy {
+    /// Strings stay strings in TOML output, however they look.
+    #[default]
+    Strings,
+    /// Strings that are valid TOML dates, times or datetimes, such as
+    /// `2024-05-01` or `2024-05-01T09:30:00Z`, become TOML datetimes.
+    Native,
+}
+
+/// How [`convert`] writes dates and datetimes, both TOML datetimes and
+/// strings that read as one, so every target agrees on them.
+///
+/// Times without a date are not points in time and are left alone.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DateNormalization {
+    /// Keep each value as written. TOML datetimes headed for another
+    /// format become their RFC 3339 text.
+    #[default]
+    Original,
+    /// RFC 3339 strings in UTC, such as `2024-05-01T07:30:00Z`. A date
+    /// alone is its midnight, and a datetime without an offset is taken
+    /// as UTC.
+    Rfc3339Utc,
+    /// Seconds since the Unix epoch: an integer, or a float when there
+    /// are fractions of a second. Dates and offsets read as for
+    /// [`Rfc3339Utc`](Self::Rfc3339Utc).
+    EpochSeconds,
+}
+
+/// Options for [`convert`].
+#[derive(Clone, Debug, Default)]
+pub struct ConvertOptions {
+    /// Options for parsing the source and stringifying the result; e.g.
+    /// `mixed_arrays` decides what happens to arrays TOML cannot hold.
+    pub format: FormatOptions,
+    pub nulls: NullPolicy,
+    pub datetimes: DatetimePolicy,
+    pub dates: DateNormalization,
+    /// Write the keys of every object in lexicographic order rather than
+    /// the order they were read in, for stable output across sources.
+    pub sort_keys: bool,
+    /// How dots in the source's keys are read. [`DottedKeys::Literal`],
+    /// the default, keeps `{"a.b": 1}` as one key, quoted in TOML;
+    /// [`DottedKeys::Nested`] makes it `{"a": {"b": 1}}`, with `["a.b"]`
+    /// for a key whose dot belongs to it.
+    pub dotted_keys: DottedKeys,
+}
+
+/// A date or datetime [`convert_with_report`] rewrote.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DateConversion {
+    pub path: Path,
+    /// The value as read, in RFC 3339 text for TOML datetimes.
+    pub original: String,
+    /// The value written, before any [`DatetimePolicy`] applies.
+    pub converted: JsonValue,
+}
+
+/// The output of [`convert_with_report`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConversionReport {
+    pub output: String,
+    /// Every date or datetime that changed representation, including TOML
+    /// datetimes that became strings.
+    pub dates: Vec<DateConversion>,
+    /// Every array whose elements mix TOML types, with its span in the
+    /// source, when converting to TOML. [`FormatOptions::mixed_arrays`]
+    /// decides whether they were rewritten.
+    pub mixed_arrays: Vec<MixedArray>,
+}
+
+/// Parses `text` as `from` and stringifies it as `to`, e.g. JSON5 to TOML.
+///
+/// Values the target cannot represent follow [`ConvertOptions`]: `null`s
+/// headed for TOML by [`NullPolicy`], dates and times by
+/// [`DatetimePolicy`], and mixed arrays by
+/// [`FormatOptions::mixed_arrays`]. Comments and YAML tags are dropped;
+/// see [`find_jsonc_comments`](crate::find_jsonc_comments) and
+/// [`find_yaml_tags`](crate::find_yaml_tags) to report them. NaN and
+/// infinite floats, which the JSON data model cannot hold, fail with a
+/// [`DataLoss`]. Parse errors are [`C12Error`]s located in `text`.
+///
+/// ```
+/// use c12_parser::{ConvertOptions, DatetimePolicy, Format, NullPolicy, convert};
+///
+/// let json5 = "{ name: 'web', since: '2024-05-01', proxy: null }";
+/// let options = ConvertOptions {
+///     nulls: NullPolicy::Drop,
+///     datetimes: DatetimePolicy::Native,
+///     ..ConvertOptions::default()
+/// };
+/// let toml = convert(json5, Format::Json5, Format::Toml, options)?;
+/// assert_eq!(toml, "name = \"web\"\nsince = 2024-05-01\n");
+///
+/// assert!(convert(json5, Format::Json5, Format::Toml, ConvertOptions::default()).is_err());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn convert(
+    text: &str,
+    from: Format,
+    to: Format,
+    options: ConvertOptions,
+) -> HookResult<String> {
+    Ok(convert_with_report(text, from, to, options)?.output)
+}
+
+/// [`convert`], also listing the dates and datetimes it rewrote, so a
+/// migration can show where TOML datetimes turned into strings or where
+/// [`DateNormalization`] changed a value.
+///
+/// ```
+/// use c12_parser::{ConvertOptions, DateNormalization, Format, convert_with_report};
+///
+/// let toml = "released = 2024-05-01T09:30:00+02:00\nday = 2024-05-01\n";
+/// let options = ConvertOptions {
+///     dates: DateNormalization::Rfc3339Utc,
+///     ..ConvertOptions::default()
+/// };
+/// let report = convert_with_report(toml, Format::Toml, Format::Json, options)?;
+/// assert_eq!(
+///     report.output,
+///     "{\n  \"released\": \"2024-05-01T07:30:00Z\",\n  \"day\": \"2024-05-01T00:00:00Z\"\n}\n"
+/// );
+/// assert_eq!(report.dates[0].original, "2024-05-01T09:30:00+02:00");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn convert_with_report(
+    text: &str,
+    from: Format,
+    to: Format,
+    options: ConvertOptions,
+) -> HookResult<ConversionReport> {
+    let parsed =
+        parse_as(from, text, options.format.clone()).map_err(|e| C12Error::new(from, text, &*e))?;
+    // NaN and infinities are `null` by now; fail rather than write that.
+    DataLoss::check(find_non_finite_numbers(text, from))?;
+    let Formatted { mut value, format } = parsed;
+    if options.dotted_keys == DottedKeys::Nested {
+        nest_dotted_keys(&mut value)?;
+    }
+    let mut dates = Vec::new();
+    normalize_dates(&mut value, Path::root(), options.dates, to, &mut dates);
+    if options.sort_keys {
+        sort_keys(&mut value);
+    }
+    if to != Format::Toml {
+        unwrap_datetimes(&mut value);
+        let output = stringify_as(to, &Formatted { value, format }, options.format)?;
+        return Ok(ConversionReport {
+            output,
+            dates,
+            mixed_arrays: Vec::new(),
+        });
+    }
+
+    match options.nulls {
+        NullPolicy::Error => DataLoss::check(find_toml_nulls(&value))?,
+        NullPolicy::Drop => drop_nulls(&mut value),
+        NullPolicy::EmptyString => replace_nulls(&mut value),
+    }
+    let mut value = to_toml(value, options.datetimes);
+    let mut mixed_arrays = normalize_mixed_arrays(&mut value, options.format.mixed_arrays)
+        .map_err(|mut e| {
+            locate_mixed_arrays(&mut e.arrays, text, from);
+            e
+        })?;
+    locate_mixed_arrays(&mut mixed_arrays, text, from);
+    let output = stringify_toml(&Formatted { value, format }, Some(options.format))?;
+    Ok(ConversionReport {
+        output,
+        dates,
+        mixed_arrays,
+    })
+}
+
+/// Rewrites the dates and datetimes in `value` by `mode`, recording each
+/// change.
+fn normalize_dates(
+    value: &mut JsonValue,
+    path: Path,
+    mode: DateNormalization,
+    to: Format,
+    report: &mut Vec<DateConversion>,
+) {
+    let native = datetime_text(value).map(str::to_string);
+    if let Some(text) = native.as_deref().or(value.as_str()) {
+        let converted = match mode {
+            DateNormalization::Original => None,
+            _ => point_in_time(text, mode),
+        };
+        let converted = match converted {
+            Some(converted) if native.is_some() || converted.as_str() != Some(text) => {
+                Some(converted)
+            }
+            None if native.is_some() && to != Format::Toml => {
+                Some(JsonValue::String(text.to_string()))
+            }
+            _ => None,
+        };
+        if let Some(converted) = converted {
+            report.push(DateConversion {
+                path,
+                original: text.to_string(),
+                converted: converted.clone(),
+            });
+            *value = converted;
+        }
+        return;
+    }
+    match value {
+        JsonValue::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                normalize_dates(item, path.clone().index(i), mode, to, report);
+            }
+        }
+        JsonValue::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                normalize_dates(item, path.clone().key(key.as_str()), mode, to, report);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// `text` as a point in time under `mode`, if it is a date or datetime.
+fn point_in_time(text: &str, mode: DateNormalization) -> Option<JsonValue> {
+    let datetime = text.parse::<Datetime>().ok()?;
+    let date = datetime.date?;
+    let time = datetime.time;
+    let offset = match datetime.offset {
+        Some(Offset::Custom { minutes }) => i64::from(minutes),
+        Some(Offset::Z) | None => 0,
+    };
+    let seconds = days_from_civil(i64::from(date.year), date.month.into(), date.day.into())
+        * 86_400
+        + time.map_or(0, |t| {
+            i64::from(t.hour) * 3600 + i64::from(t.minute) * 60 + i64::from(t.second.unwrap_or(0))
+        })
+        - offset * 60;
+    let nanos = time.and_then(|t| t.nanosecond).unwrap_or(0);
+    Some(match mode {
+        DateNormalization::EpochSeconds if nanos == 0 => seconds.into(),
+        DateNormalization::EpochSeconds => (seconds as f64 + f64::from(nanos) / 1e9).into(),
+        _ => {
+            let (days, rest) = (seconds.div_euclid(86_400), seconds.rem_euclid(86_400));
+            let (year, month, day) = civil_from_days(days);
+            let fraction = if nanos == 0 {
+                String::new()
+            } else {
+                format!(".{nanos:09}").trim_end_matches('0').to_string()
+            };
+            JsonValue::String(format!(
+                "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}{fraction}Z",
+                rest / 3600,
+                rest % 3600 / 60,
+                rest % 60
+            ))
+        }
+    })
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date, after Howard
+/// Hinnant's `days_from_civil`.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// The inverse of [`days_from_civil`].
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + i64::from(month <= 2), month, day)
+}
+
+/// Replaces TOML's datetime wrappers with their RFC 3339 text.
+fn unwrap_datetimes(value: &mut JsonValue) {
+    if let Some(text) = datetime_text(value) {
+        *value = JsonValue::String(text.to_string());
+        return;
+    }
+    match value {
+        JsonValue::Array(items) => items.iter_mut().for_each(unwrap_datetimes),
+        JsonValue::Object(map) => map.values_mut().for_each(unwrap_datetimes),
+        _ => {}
+    }
+}
+
+fn datetime_text(value: &JsonValue) -> Option<&str> {
+    match value.as_object()? {
+        map if map.len() == 1 => map.get(TOML_DATETIME)?.as_str(),
+        _ => None,
+    }
+}
+
+fn sort_keys(value: &mut JsonValue) {
+    match value {
+        JsonValue::Array(items) => items.iter_mut().for_each(sort_keys),
+        JsonValue::Object(map) => {
+            map.sort_keys();
+            map.values_mut().for_each(sort_keys);
+        }
+        _ => {}
+    }
+}
+
+fn drop_nulls(value: &mut JsonValue) {
+    match value {
+        JsonValue::Array(items) => {
+            items.retain(|item| !item.is_null());
+            items.iter_mut().for_each(drop_nulls);
+        }
+        JsonValue::Object(map) => {
+            map.retain(|_, item| !item.is_null());
+            map.values_mut().for_each(drop_nulls);
+        }
+        _ => {}
+    }
+}
+
+fn replace_nulls(value: &mut JsonValue) {
+    match value {
+        JsonValue::Null => *value = JsonValue::String(String::new()),
+        JsonValue::Array(items) => items.iter_mut().for_each(replace_nulls),
+        JsonValue::Object(map) => map.values_mut().for_each(replace_nulls),
+        _ => {}
+    }
+}
+
+/// Builds the TOML value directly rather than through serde, which would
+/// write datetime wrappers out as tables. `null`s are gone by now.
+fn to_toml(value: JsonValue, datetimes: DatetimePolicy) -> TomlValue {
+    if let Some(datetime) = datetime_text(&value).and_then(|text| text.parse::<Datetime>().ok()) {
+        return TomlValue::Datetime(datetime);
+    }
+    match value {
+        JsonValue::Null => TomlValue::String(String::new()),
+        JsonValue::Bool(b) => TomlValue::Boolean(b),
+        JsonValue::Number(n) => match n.as_i64() {
+            Some(i) => TomlValue::Integer(i),
+            None => TomlValue::Float(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        JsonValue::String(s) => match datetimes {
+            DatetimePolicy::Native => s
+                .parse::<Datetime>()
+                .map_or(TomlValue::String(s), TomlValue::Datetime),
+            DatetimePolicy::Strings => TomlValue::String(s),
+        },
+        JsonValue::Array(items) => TomlValue::Array(
+            items
+                .into_iter()
+                .map(|item| to_toml(item, datetimes))
+                .collect(),
+        ),
+        JsonValue::Object(map) => TomlValue::Table(table(map, datetimes)),
+    }
+}
+
+fn table(map: Map<String, JsonValue>, datetimes: DatetimePolicy) -> toml::Table {
+    map.into_iter()
+        .map(|(key, value)| (key, to_toml(value, datetimes)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to(text: &str, from: Format, to: Format, options: ConvertOptions) -> String {
+        convert(text, from, to, options).unwrap()
+    }
+
+    #[test]
+    fn nulls_follow_the_policy() {
+        let json = r#"{ "a": null, "b": [1, null], "c": { "d": null, "e": true } }"#;
+        let err = convert(json, Format::Json, Format::Toml, ConvertOptions::default()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "operation would lose data: null at `a`; null at `b[1]`; null at `c.d`;"
+        );
+        let drop = ConvertOptions {
+            nulls: NullPolicy::Drop,
+            ..ConvertOptions::default()
+        };
+        assert_eq!(
+            to(json, Format::Json, Format::Toml, drop),
+            "b = [1]\n\n[c]\ne = true\n"
+        );
+        let empty = ConvertOptions {
+            nulls: NullPolicy::EmptyString,
+            ..ConvertOptions::default()
+        };
+        assert_eq!(
+            to(json, Format::Json, Format::Toml, empty),
+            "a = \"\"\nb = [1, \"\"]\n\n[c]\nd = \"\"\ne = true\n"
+        );
+        // Other targets keep them.
+        assert_eq!(
+            to(json, Format::Json, Format::Yaml, ConvertOptions::default()),
+            "a: null\nb:\n- 1\n- null\nc:\n  d: null\n  e: true\n"
+        );
+    }
+
+    #[test]
+    fn datetimes_follow_the_policy() {
+        let toml = "at = 1979-05-27T07:32:00Z\nday = 1979-05-27\n";
+        assert_eq!(
+            to(toml, Format::Toml, Format::Json, ConvertOptions::default()),
+            "{\n  \"at\": \"1979-05-27T07:32:00Z\",\n  \"day\": \"1979-05-27\"\n}\n"
+        );
+        // Outer whitespace is carried over after the emitter's own newline.
+        assert_eq!(
+            to(toml, Format::Toml, Format::Toml, ConvertOptions::default()).trim_end(),
+            toml.trim_end()
+        );
+
+        let yaml = "at: 1979-05-27T07:32:00Z\nname: '07:32:00'";
+        assert_eq!(
+            to(yaml, Format::Yaml, Format::Toml, ConvertOptions::default()),
+            "at = \"1979-05-27T07:32:00Z\"\nname = \"07:32:00\"\n"
+        );
+        let native = ConvertOptions {
+            datetimes: DatetimePolicy::Native,
+            ..ConvertOptions::default()
+        };
+        assert_eq!(
+            to(yaml, Format::Yaml, Format::Toml, native),
+            "at = 1979-05-27T07:32:00Z\nname = 07:32:00\n"
+        );
+    }
+
+    #[test]
+    fn sorts_keys_at_every_depth() {
+        let sorted = ConvertOptions {
+            sort_keys: true,
+            ..ConvertOptions::default()
+        };
+        assert_eq!(
+            to(
+                "b: 1\na:\n  - { z: 1, y: 2 }",
+                Format::Yaml,
+                Format::Json5,
+                sorted
+            ),
+            "{\n  a: [\n    {\n      y: 2,\n      z: 1,\n    },\n  ],\n  b: 1,\n}"
+        );
+    }
+
+    #[test]
+    fn reports_mixed_arrays_with_their_spans() {
+        use crate::mixed_arrays::{MixedArrayError, MixedArrayStrategy};
+
+        let yaml = "name: web\nports: [80, \"https\", { alt: 8443 }]";
+        let mut options = ConvertOptions::default();
+        options.format.mixed_arrays = MixedArrayStrategy::Stringify;
+        let report = convert_with_report(yaml, Format::Yaml, Format::Toml, options).unwrap();
+        assert_eq!(
+            report.output,
+            "name = \"web\"\nports = [\"80\", \"https\", '{\"alt\":8443}']\n"
+        );
+        let span = report.mixed_arrays[0].span.clone().unwrap();
+        assert_eq!(&yaml[span], "ports: [80, \"https\", { alt: 8443 }]");
+
+        let mut options = ConvertOptions::default();
+        options.format.mixed_arrays = MixedArrayStrategy::Error;
+        let err = convert(yaml, Format::Yaml, Format::Toml, options).unwrap_err();
+        let err = err.downcast::<MixedArrayError>().unwrap();
+        assert_eq!(err.arrays[0].path.to_string(), "ports");
+        assert!(err.arrays[0].span.is_some());
+    }
+
+    #[test]
+    fn dotted_keys_follow_the_option() {
+        let json = r#"{ "a.b": 1, "[\"c.d\"]": { "e": true } }"#;
+        assert_eq!(
+            to(json, Format::Json, Format::Toml, ConvertOptions::default()),
+            "\"a.b\" = 1\n\n['[\"c.d\"]']\ne = true\n"
+        );
+        let options = ConvertOptions {
+            dotted_keys: DottedKeys::Nested,
+            ..ConvertOptions::default()
+        };
+        assert_eq!(
+            to(json, Format::Json, Format::Toml, options),
+            "[a]\nb = 1\n\n[\"c.d\"]\ne = true\n"
+        );
+    }
+
+    #[test]
+    fn non_finite_numbers_are_reported_as_loss() {
+        let toml = "ratio = nan\nlimits = [1.5, -inf]\n";
+        let err = convert(toml, Format::Toml, Format::Json, ConvertOptions::default()).unwrap_err();
+        let err = err.downcast::<DataLoss>().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "operation would lose data: number `NaN` at `ratio`; number `-inf` at `limits[1]`;"
+        );
+        assert!(
+            convert(
+                "info: 1\n",
+                Format::Yaml,
+                Format::Toml,
+                ConvertOptions::default()
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn parse_errors_are_located() {
+        let err = convert(
+            "a: [\n",
+            Format::Yaml,
+            Format::Json,
+            ConvertOptions::default(),
+        )
+        .unwrap_err();
+        let err = err.downcast::<C12Error>().unwrap();
+        assert_eq!((err.format, err.line), (Format::Yaml, 2));
+    }
+
+    #[test]
+    fn normalizes_dates_and_reports_each_change() {
+        let yaml = "at: 1969-12-31T23:00:00-02:00\nday: '2024-02-29'\nutc: 2024-01-01T00:00:00Z\ntime: '09:30:00'\nlist: [2000-01-01T00:00:00.5Z]\n";
+        let epoch = ConvertOptions {
+            dates: DateNormalization::EpochSeconds,
+            ..ConvertOptions::default()
+        };
+        let report = convert_with_report(yaml, Format::Yaml, Format::Json, epoch).unwrap();
+        let value: JsonValue = serde_json::from_str(&report.output).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "at": 3600,
+                "day": 1_709_164_800,
+                "utc": 1_704_067_200,
+                "time": "09:30:00",
+                "list": [946_684_800.5]
+            })
+        );
+        let paths: Vec<_> = report.dates.iter().map(|d| d.path.to_string()).collect();
+        assert_eq!(paths, ["at", "day", "utc", "list[0]"]);
+
+        let utc = ConvertOptions {
+            dates: DateNormalization::Rfc3339Utc,
+            datetimes: DatetimePolicy::Native,
+            ..ConvertOptions::default()
+        };
+        let report = convert_with_report(yaml, Format::Yaml, Format::Toml, utc).unwrap();
+        assert!(
+            report.output.starts_with("at = 1970-01-01T01:00:00Z\nday = 2024-02-29T00:00:00Z\nutc = 2024-01-01T00:00:00Z\n"),
+            "{}",
+            report.output
+        );
+        // Strings already in UTC are not reported.
+        let paths: Vec<_> = report.dates.iter().map(|d| d.path.to_string()).collect();
+        assert_eq!(paths, ["at", "day"]);
+
+        let toml = "day = 2024-05-01";
+        let report =
+            convert_with_report(toml, Format::Toml, Format::Yaml, ConvertOptions::default())
+                .unwrap();
+        assert_eq!(report.output, "day: 2024-05-01\n");
+        assert_eq!(report.dates[0].converted, serde_json::json!("2024-05-01"));
+    }
+}
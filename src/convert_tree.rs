@@ -0,0 +1,300 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path as FsPath, PathBuf};
+
+use regex::Regex;
+
+use crate::batch::{BatchItem, BatchReport, ItemDiagnostic, ItemOutcome};
+use crate::convert::{ConvertOptions, convert};
+use crate::format::{Format, FormatOptions};
+use crate::loss::{Loss, find_jsonc_comments, find_yaml_comments, find_yaml_tags};
+use crate::progress::Progress;
+use crate::walk::{Walker, file_glob, slash_path};
+
+/// Which files [`convert_tree`] picks up.
+///
+/// Globs match paths relative to the source directory, with `/` as the
+/// separator: `*` matches within one path component, `**/` any number of
+/// directories and `?` one character, e.g. `**/*.yaml` or `fixtures/*`.
+#[derive(Clone, Debug, Default)]
+pub struct ConvertFilters {
+    /// Only convert files matching one of these globs; all when empty.
+    pub include: Vec<String>,
+
+    /// Skip files matching any of these globs, and directories whose own
+    /// path matches.
+    pub exclude: Vec<String>,
+
+    /// Also convert files excluded by `.gitignore` or `.c12ignore`; see
+    /// [`Walker`].
+    pub include_ignored: bool,
+
+    /// Only convert files in these formats; all recognized formats when
+    /// empty.
+    pub formats: Vec<Format>,
+
+    /// Options for parsing and stringifying each file.
+    pub options: FormatOptions,
+}
+
+/// A file [`convert_tree`] wrote.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Converted {
+    /// Where the converted file went, relative to the destination
+    /// directory.
+    pub target: PathBuf,
+}
+
+impl fmt::Display for Converted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "converted to {}", self.target.display())
+    }
+}
+
+/// The per-file results of [`convert_tree`], in path order.
+pub type ConvertReport = BatchReport<Converted>;
+
+/// Converts every config file under `src_dir` to `to_format`, writing the
+/// results under `dst_dir` at the same relative paths with the new
+/// extension.
+///
+/// Files ignored by `.gitignore` or `.c12ignore` are left out unless
+/// [`ConvertFilters::include_ignored`] is set. Files are recognized by
+/// extension (see [`Format::from_name`]). A file that fails to convert is
+/// reported and the walk goes on; only failing to read `src_dir` itself
+/// is an error. Each file goes through [`convert`](crate::convert) with
+/// the default policies, so a `null` headed for TOML fails its file.
+/// Converted files are reported with a warning for each
+/// comment or YAML tag the conversion dropped. Files already in
+/// `to_format` are reformatted, and when two sources map to the same
+/// target (`a.json` and `a.yaml`), the later one in path order fails
+/// instead of overwriting.
+pub fn convert_tree(
+    src_dir: &FsPath,
+    dst_dir: &FsPath,
+    to_format: Format,
+    filters: &ConvertFilters,
+) -> io::Result<ConvertReport> {
+    convert_tree_with_progress(src_dir, dst_dir, to_format, filters, &())
+}
+
+/// Like [`convert_tree`], reporting each file selected by the include
+/// globs to `progress` as it is handled. Failures are reported as
+/// [errors](Progress::error), skipped files only as processed.
+pub fn convert_tree_with_progress(
+    src_dir: &FsPath,
+    dst_dir: &FsPath,
+    to_format: Format,
+    filters: &ConvertFilters,
+    progress: &dyn Progress,
+) -> io::Result<ConvertReport> {
+    let include: Vec<Regex> = filters.include.iter().map(|g| file_glob(g)).collect();
+    let walker = filters
+        .exclude
+        .iter()
+        .fold(Walker::new(), |walker, glob| walker.exclude(glob))
+        .include_ignored(filters.include_ignored);
+    let mut files = walker.files(src_dir)?;
+    if !include.is_empty() {
+        files.retain(|source| include.iter().any(|g| g.is_match(&slash_path(source))));
+    }
+    progress.discovered(files.len());
+
+    let mut report = ConvertReport::default();
+    let mut targets: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for source in files {
+        let outcome = convert_entry(src_dir, dst_dir, to_format, filters, &mut targets, &source);
+        if let ItemOutcome::Failed(diagnostics) = &outcome {
+            let message = diagnostics.first().map_or("", |d| d.message.as_str());
+            progress.error(&source, message);
+        }
+        progress.processed(&source);
+        report.items.push(BatchItem { source, outcome });
+    }
+    Ok(report)
+}
+
+fn convert_entry(
+    src_dir: &FsPath,
+    dst_dir: &FsPath,
+    to_format: Format,
+    filters: &ConvertFilters,
+    targets: &mut Vec<(PathBuf, PathBuf)>,
+    source: &FsPath,
+) -> ItemOutcome<Converted> {
+    let format = source
+        .extension()
+        .and_then(|ext| Format::from_name(&ext.to_string_lossy()));
+    let Some(format) = format else {
+        return ItemOutcome::Skipped("not a recognized config format".into());
+    };
+    if !filters.formats.is_empty() && !filters.formats.contains(&format) {
+        return ItemOutcome::Skipped(format!("{format} is filtered out"));
+    }
+    let target = source.with_extension(to_format.name());
+    if let Some((_, first)) = targets.iter().find(|(t, _)| *t == target) {
+        return ItemOutcome::Failed(vec![ItemDiagnostic::error(format!(
+            "`{}` is also the target of `{}`",
+            slash_path(&target),
+            slash_path(first)
+        ))]);
+    }
+    targets.push((target.clone(), source.to_path_buf()));
+    match convert_file(
+        &src_dir.join(source),
+        &dst_dir.join(&target),
+        format,
+        to_format,
+        &filters.options,
+    ) {
+        Ok(losses) => ItemOutcome::Succeeded {
+            value: Converted { target },
+            warnings: losses.iter().map(|l| ItemDiagnostic::warning(l)).collect(),
+        },
+        Err(diagnostic) => ItemOutcome::Failed(vec![diagnostic]),
+    }
+}
+
+/// Converts one file, returning what the conversion dropped.
+fn convert_file(
+    source: &FsPath,
+    target: &FsPath,
+    from: Format,
+    to: Format,
+    options: &FormatOptions,
+) -> Result<Vec<Loss>, ItemDiagnostic> {
+    let io = |e: io::Error| ItemDiagnostic::from_error(&e);
+    let text = fs::read_to_string(source).map_err(io)?;
+    let options = ConvertOptions {
+        format: options.clone(),
+        ..ConvertOptions::default()
+    };
+    let out = convert(&text, from, to, options).map_err(|e| ItemDiagnostic::from_error(&*e))?;
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(io)?;
+    }
+    fs::write(target, out).map_err(io)?;
+
+    Ok(match from {
+        Format::Jsonc | Format::Json5 => find_jsonc_comments(&text),
+        Format::Yaml => {
+            let mut losses = find_yaml_comments(&text);
+            if to != Format::Yaml {
+                losses.extend(find_yaml_tags(&text).unwrap_or_default());
+            }
+            losses
+        }
+        _ => Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::ProgressCounter;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("c12-convert-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn write(root: &FsPath, path: &str, text: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, text).unwrap();
+    }
+
+    #[test]
+    fn mirrors_the_tree_and_reports_each_file() {
+        let root = scratch("mirror");
+        let (src, dst) = (root.join("src"), root.join("dst"));
+        write(&src, "app.jsonc", "{\n  // public\n  \"port\": 8080\n}");
+        write(&src, "nested/db.yaml", "url: postgres://db\n");
+        write(&src, "nested/broken.toml", "port = \n");
+        write(&src, "README.md", "# docs\n");
+
+        let counter = ProgressCounter::new();
+        let report = convert_tree_with_progress(
+            &src,
+            &dst,
+            Format::Toml,
+            &ConvertFilters::default(),
+            &counter,
+        )
+        .unwrap();
+        assert_eq!(counter.to_string(), "4/4 processed, 1 error");
+        let summary = report.to_string();
+        let lines: Vec<_> = summary.lines().collect();
+        assert_eq!(
+            lines[..5],
+            [
+                "README.md: skipped, not a recognized config format",
+                "app.jsonc: converted to app.toml",
+                "  warning[C12E001]: comment `public` on line 2",
+                "nested/broken.toml: failed",
+                "  error[C12E021]: toml error at line 1, column 8: string values must be quoted, expected literal string",
+            ]
+        );
+        assert!(summary.ends_with(
+            "nested/db.yaml: converted to nested/db.toml\n\
+             2 succeeded (1 with warnings), 1 failed, 1 skipped"
+        ));
+        assert_eq!(
+            fs::read_to_string(dst.join("nested/db.toml"))
+                .unwrap()
+                .trim_end(),
+            "url = \"postgres://db\""
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn filters_select_files_and_collisions_fail() {
+        let root = scratch("filters");
+        let (src, dst) = (root.join("src"), root.join("dst"));
+        write(&src, "a.json", "{}");
+        write(&src, "a.json5", "{}");
+        write(&src, "b.ini", "x = 1\n");
+        write(&src, "vendor/c.json", "{}");
+
+        let filters = ConvertFilters {
+            exclude: vec!["vendor".into()],
+            formats: vec![Format::Json, Format::Json5],
+            ..ConvertFilters::default()
+        };
+        let report = convert_tree(&src, &dst, Format::Yaml, &filters).unwrap();
+        let outcomes: Vec<_> = report
+            .items
+            .iter()
+            .map(|e| (slash_path(&e.source), e.outcome.clone()))
+            .collect();
+        assert_eq!(
+            outcomes,
+            [
+                (
+                    "a.json".to_string(),
+                    ItemOutcome::Succeeded {
+                        value: Converted {
+                            target: "a.yaml".into()
+                        },
+                        warnings: Vec::new()
+                    }
+                ),
+                (
+                    "a.json5".to_string(),
+                    ItemOutcome::Failed(vec![ItemDiagnostic::error(
+                        "`a.yaml` is also the target of `a.json`"
+                    )])
+                ),
+                (
+                    "b.ini".to_string(),
+                    ItemOutcome::Skipped("ini is filtered out".into())
+                ),
+            ]
+        );
+        assert!(!dst.join("vendor").exists());
+        fs::remove_dir_all(root).unwrap();
+    }
+}
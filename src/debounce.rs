@@ -0,0 +1,322 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use serde_json::Value as JsonValue;
+
+use crate::batch::ItemDiagnostic;
+use crate::context::C12Context;
+use crate::format::Formatted;
+
+/// Returned by [`DebouncedSaver::flush`] when writes failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SaveError {
+    /// Each file that could not be written, in the order it failed.
+    pub failures: Vec<(PathBuf, ItemDiagnostic)>,
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "saving failed:")?;
+        for (path, diagnostic) in &self.failures {
+            write!(f, " {}: {diagnostic};", path.display())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+/// Coalesces rapid saves of the same file, as an editor saving on every
+/// keystroke produces, into one write once the file has been quiet for a
+/// while.
+///
+/// Writes go through [`C12Context::save_atomic`] on a background thread,
+/// so they use the context's file system, formats and hooks, and a reader
+/// never sees half a file. Only the latest value of each file is written.
+/// Dropping the saver writes whatever is still pending; call
+/// [`flush`](Self::flush) first to learn about failures.
+///
+/// ```
+/// use std::time::Duration;
+/// use c12_parser::{C12Context, DebouncedSaver, MemoryFileSystem};
+///
+/// let ctx = C12Context::new().file_system(MemoryFileSystem::new());
+/// let saver = DebouncedSaver::new(ctx.clone(), Duration::from_millis(200));
+/// for port in 8080..8090 {
+///     saver.save("/app.json", ctx.parse("json", &format!(r#"{{ "port": {port} }}"#))?);
+/// }
+/// assert_eq!(saver.pending(), 1);
+/// saver.flush()?;
+/// assert_eq!(ctx.load("/app.json")?.value["port"], 8089);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug)]
+pub struct DebouncedSaver {
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    ctx: C12Context,
+    quiet: Duration,
+    state: Mutex<State>,
+    wake: Condvar,
+    /// Held while writing, so a flush cannot overtake an older write of the
+    /// same file still in progress on the worker.
+    writing: Mutex<()>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    pending: BTreeMap<PathBuf, Pending>,
+    failures: Vec<(PathBuf, ItemDiagnostic)>,
+    max_delay: Option<Duration>,
+    closed: bool,
+}
+
+#[derive(Debug)]
+struct Pending {
+    formatted: Formatted<JsonValue>,
+    first: Instant,
+    last: Instant,
+}
+
+impl DebouncedSaver {
+    /// Writes each file once no save has been requested for it for
+    /// `quiet`.
+    pub fn new(ctx: C12Context, quiet: Duration) -> Self {
+        let shared = Arc::new(Shared {
+            ctx,
+            quiet,
+            state: Mutex::default(),
+            wake: Condvar::new(),
+            writing: Mutex::new(()),
+        });
+        let worker = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || shared.run())
+        };
+        Self {
+            shared,
+            worker: Some(worker),
+        }
+    }
+
+    /// Also writes a file `max` after its first unwritten save even if
+    /// saves keep coming, bounding how stale the file on disk gets.
+    pub fn max_delay(self, max: Duration) -> Self {
+        self.shared.lock().max_delay = Some(max);
+        self.shared.wake.notify_one();
+        self
+    }
+
+    /// Schedules `formatted` to be written to `path`, replacing any value
+    /// still pending for it.
+    pub fn save(&self, path: impl Into<PathBuf>, formatted: Formatted<JsonValue>) {
+        let path = path.into();
+        let now = Instant::now();
+        let mut state = self.shared.lock();
+        let first = state
+            .pending
+            .get(&path)
+            .map_or(now, |pending| pending.first);
+        state.pending.insert(
+            path,
+            Pending {
+                formatted,
+                first,
+                last: now,
+            },
+        );
+        drop(state);
+        self.shared.wake.notify_one();
+    }
+
+    /// The number of files waiting to be written.
+    pub fn pending(&self) -> usize {
+        self.shared.lock().pending.len()
+    }
+
+    /// Writes everything pending now and reports the writes that failed
+    /// since the last flush, in the background or here.
+    pub fn flush(&self) -> Result<(), SaveError> {
+        let _writing = self.shared.writing();
+        let due = std::mem::take(&mut self.shared.lock().pending);
+        self.shared.write(due);
+        let failures = std::mem::take(&mut self.shared.lock().failures);
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(SaveError { failures })
+        }
+    }
+}
+
+impl Drop for DebouncedSaver {
+    fn drop(&mut self) {
+        self.shared.lock().closed = true;
+        self.shared.wake.notify_one();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // Writes happen outside the lock, so a panic cannot leave the
+        // state half-updated.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn writing(&self) -> MutexGuard<'_, ()> {
+        self.writing.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// When `pending` is due: after the quiet period, or the maximum
+    /// delay if one is set.
+    fn deadline(&self, pending: &Pending, max_delay: Option<Duration>) -> Instant {
+        let quiet = pending.last + self.quiet;
+        max_delay.map_or(quiet, |max| quiet.min(pending.first + max))
+    }
+
+    fn run(&self) {
+        let mut state = self.lock();
+        loop {
+            let now = Instant::now();
+            let closed = state.closed;
+            let max_delay = state.max_delay;
+            if state
+                .pending
+                .values()
+                .any(|p| closed || self.deadline(p, max_delay) <= now)
+            {
+                drop(state);
+                let writing = self.writing();
+                let mut locked = self.lock();
+                let (due, rest) = std::mem::take(&mut locked.pending)
+                    .into_iter()
+                    .partition(|(_, p)| closed || self.deadline(p, max_delay) <= now);
+                locked.pending = rest;
+                drop(locked);
+                self.write(due);
+                drop(writing);
+                state = self.lock();
+                continue;
+            }
+            if closed {
+                return;
+            }
+            let next = state
+                .pending
+                .values()
+                .map(|p| self.deadline(p, max_delay))
+                .min();
+            state = match next {
+                Some(next) => {
+                    let timeout = next.saturating_duration_since(now);
+                    self.wake
+                        .wait_timeout(state, timeout)
+                        .unwrap_or_else(|e| e.into_inner())
+                        .0
+                }
+                None => self.wake.wait(state).unwrap_or_else(|e| e.into_inner()),
+            };
+        }
+    }
+
+    /// Writes `due`; the caller holds the `writing` lock.
+    fn write(&self, due: BTreeMap<PathBuf, Pending>) {
+        for (path, pending) in due {
+            if let Err(e) = self.ctx.save_atomic(&path, &pending.formatted) {
+                let diagnostic = ItemDiagnostic::from_error(&*e);
+                self.lock().failures.push((path, diagnostic));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{FileSystem, MemoryFileSystem};
+    use std::io;
+    use std::path::Path as FsPath;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Debug, Default)]
+    struct CountingFs {
+        files: MemoryFileSystem,
+        writes: AtomicUsize,
+    }
+
+    impl FileSystem for Arc<CountingFs> {
+        fn read_to_string(&self, path: &FsPath) -> io::Result<String> {
+            self.files.read_to_string(path)
+        }
+
+        fn write(&self, path: &FsPath, contents: &str) -> io::Result<()> {
+            self.writes.fetch_add(1, Ordering::SeqCst);
+            self.files.write(path, contents)
+        }
+
+        fn exists(&self, path: &FsPath) -> bool {
+            self.files.exists(path)
+        }
+    }
+
+    fn config(port: u16) -> Formatted<JsonValue> {
+        crate::parse_json(&format!(r#"{{ "port": {port} }}"#), None).unwrap()
+    }
+
+    #[test]
+    fn coalesces_saves_after_the_quiet_period() {
+        let fs = Arc::new(CountingFs::default());
+        let ctx = C12Context::new().file_system(Arc::clone(&fs));
+        let saver = DebouncedSaver::new(ctx.clone(), Duration::from_millis(50));
+        for port in 0..5 {
+            saver.save("/a.json", config(port));
+            saver.save("/b.yaml", config(port));
+        }
+        thread::sleep(Duration::from_millis(400));
+        assert_eq!(saver.pending(), 0);
+        assert_eq!(fs.writes.load(Ordering::SeqCst), 2);
+        assert_eq!(ctx.load("/b.yaml").unwrap().value["port"], 4);
+        saver.flush().unwrap();
+        assert_eq!(fs.writes.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn drop_writes_what_is_pending() {
+        let fs = Arc::new(CountingFs::default());
+        let ctx = C12Context::new().file_system(Arc::clone(&fs));
+        let saver = DebouncedSaver::new(ctx.clone(), Duration::from_secs(60))
+            .max_delay(Duration::from_secs(120));
+        saver.save("/a.json", config(1));
+        saver.save("/a.json", config(2));
+        drop(saver);
+        assert_eq!(fs.writes.load(Ordering::SeqCst), 1);
+        assert_eq!(ctx.load("/a.json").unwrap().value["port"], 2);
+    }
+
+    #[test]
+    fn flush_reports_failed_writes() {
+        let saver = DebouncedSaver::new(
+            C12Context::new().file_system(MemoryFileSystem::new()),
+            Duration::from_secs(60),
+        );
+        saver.save("/no-extension", config(1));
+        saver.save("/ok.toml", config(1));
+        let err = saver.flush().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "saving failed: /no-extension: error: cannot tell the format of /no-extension;"
+        );
+        assert!(saver.flush().is_ok());
+    }
+}
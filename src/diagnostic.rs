@@ -0,0 +1,864 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::context::DeterminismError;
+use crate::doctor::{Finding, Severity};
+use crate::document::DocumentEditError;
+use crate::encryption::EncryptionError;
+use crate::error::{C12Error, C12ErrorKind};
+use crate::flatten::FlattenConflict;
+use crate::json_document::JsonEditError;
+use crate::limits::{LimitExceeded, LimitKind};
+use crate::lint::{KeyIssue, KeyLint};
+use crate::loss::{DataLoss, Loss, LossKind};
+use crate::mixed_arrays::{MixedArray, MixedArrayError};
+use crate::ownership::{ForeignEdit, OwnershipError};
+use crate::patch::PatchError;
+use crate::path::{PathError, PathParseError};
+use crate::roundtrip::RoundTripMismatch;
+use crate::schema::Violation;
+use crate::toml_document::TomlEditError;
+use crate::version::BumpError;
+use crate::yaml_aliases::{AliasIssue, AliasIssueKind, YamlAliasError};
+use crate::yaml_document::YamlEditError;
+
+/// A stable identifier for every error and warning this crate reports.
+///
+/// Ids never change meaning once released (`C12E…` for errors, `C12W…`
+/// for warnings), so tools can suppress or translate specific diagnostics
+/// and documentation can link to an explanation. [`Code::ALL`] is the
+/// full catalog.
+///
+/// ```
+/// use c12_parser::{Code, Diagnostic, lint_keys};
+///
+/// let lints = lint_keys(&serde_json::json!({ "no": 1 }));
+/// assert_eq!(lints[0].code(), Code::AmbiguousYamlKey);
+/// assert_eq!(lints[0].code().id(), "C12W004");
+/// assert_eq!(Code::from_id("C12W004"), Some(Code::AmbiguousYamlKey));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum Code {
+    DataLoss,
+    RoundTripMismatch,
+    LimitExceeded,
+    DuplicateAnchor,
+    RecursiveAlias,
+    ExcessiveAliasReuse,
+    AliasExpansionTooLarge,
+    MixedArray,
+    Encryption,
+    InvalidPath,
+    TomlEdit,
+    FlattenConflict,
+    Nondeterminism,
+    TypeMismatch,
+    ValueNotAllowed,
+    MissingRequiredKey,
+    UnknownKeyNotAllowed,
+    YamlEdit,
+    JsonEdit,
+    PathConflict,
+    Parse,
+    Patch,
+    UnacknowledgedEdit,
+    EmptyKey,
+    WhitespaceInKey,
+    ControlCharacterInKey,
+    AmbiguousYamlKey,
+    KeyCaseCollision,
+    UnknownKey,
+    DeprecatedKey,
+    ExpiredKey,
+    ForeignEdit,
+    SchemaViolation,
+}
+
+impl Code {
+    pub const ALL: &[Code] = &[
+        Code::DataLoss,
+        Code::RoundTripMismatch,
+        Code::LimitExceeded,
+        Code::DuplicateAnchor,
+        Code::RecursiveAlias,
+        Code::ExcessiveAliasReuse,
+        Code::AliasExpansionTooLarge,
+        Code::MixedArray,
+        Code::Encryption,
+        Code::InvalidPath,
+        Code::TomlEdit,
+        Code::FlattenConflict,
+        Code::Nondeterminism,
+        Code::TypeMismatch,
+        Code::ValueNotAllowed,
+        Code::MissingRequiredKey,
+        Code::UnknownKeyNotAllowed,
+        Code::YamlEdit,
+        Code::JsonEdit,
+        Code::PathConflict,
+        Code::Parse,
+        Code::Patch,
+        Code::UnacknowledgedEdit,
+        Code::EmptyKey,
+        Code::WhitespaceInKey,
+        Code::ControlCharacterInKey,
+        Code::AmbiguousYamlKey,
+        Code::KeyCaseCollision,
+        Code::UnknownKey,
+        Code::DeprecatedKey,
+        Code::ExpiredKey,
+        Code::ForeignEdit,
+        Code::SchemaViolation,
+    ];
+
+    /// The stable id, e.g. `C12E001`.
+    pub fn id(self) -> &'static str {
+        self.entry().0
+    }
+
+    /// How serious the diagnostic is unless the reporter says otherwise.
+    pub fn severity(self) -> Severity {
+        if self.id().starts_with("C12E") {
+            Severity::Error
+        } else {
+            Severity::Warning
+        }
+    }
+
+    /// A one-line explanation, suitable for a catalog page.
+    pub fn summary(self) -> &'static str {
+        self.entry().1
+    }
+
+    /// Looks a code up by id, case-insensitively.
+    pub fn from_id(id: &str) -> Option<Code> {
+        Code::ALL
+            .iter()
+            .copied()
+            .find(|code| code.id().eq_ignore_ascii_case(id))
+    }
+
+    /// The names of the [arguments](Diagnostic::args) diagnostics with this
+    /// code supply, for writing translations. Errors that aggregate
+    /// several problems supply the first problem's arguments plus `count`.
+    pub fn args(self) -> &'static [&'static str] {
+        match self {
+            Code::DataLoss => &["path", "line", "kind", "text"],
+            Code::RoundTripMismatch => &["path", "expected", "found"],
+            Code::LimitExceeded => &["path", "kind", "actual", "limit"],
+            Code::DuplicateAnchor | Code::RecursiveAlias | Code::ExcessiveAliasReuse => {
+                &["line", "column", "name"]
+            }
+            Code::AliasExpansionTooLarge => &["line", "column", "nodes"],
+            Code::MixedArray => &["path", "kinds"],
+            Code::Encryption
+            | Code::TomlEdit
+            | Code::YamlEdit
+            | Code::JsonEdit
+            | Code::PathConflict => &["path", "message"],
+            Code::InvalidPath => &["offset", "message"],
+            Code::Parse => &["format", "line", "column", "message"],
+            Code::Patch => &["index", "path", "message"],
+            Code::UnacknowledgedEdit => &["file", "paths", "owners"],
+            Code::ForeignEdit => &["file", "path", "owners"],
+            Code::FlattenConflict => &["key"],
+            Code::Nondeterminism => &["source"],
+            Code::TypeMismatch => &["path", "expected", "found"],
+            Code::ValueNotAllowed => &["path", "value"],
+            Code::MissingRequiredKey | Code::UnknownKeyNotAllowed | Code::UnknownKey => {
+                &["path", "key"]
+            }
+            Code::DeprecatedKey => &["path", "replacement"],
+            Code::ExpiredKey => &["path", "expires"],
+            Code::SchemaViolation => &["path", "keyword", "message"],
+            Code::EmptyKey
+            | Code::WhitespaceInKey
+            | Code::ControlCharacterInKey
+            | Code::AmbiguousYamlKey
+            | Code::KeyCaseCollision => &["path", "key", "suggestion"],
+        }
+    }
+
+    fn entry(self) -> (&'static str, &'static str) {
+        match self {
+            Code::DataLoss => (
+                "C12E001",
+                "the operation would drop comments, nulls or tags",
+            ),
+            Code::RoundTripMismatch => (
+                "C12E002",
+                "stringified output does not parse back to the same value",
+            ),
+            Code::LimitExceeded => ("C12E003", "a string or array is over the configured limit"),
+            Code::DuplicateAnchor => ("C12E004", "a YAML anchor is defined twice"),
+            Code::RecursiveAlias => ("C12E005", "a YAML alias refers to a node containing it"),
+            Code::ExcessiveAliasReuse => ("C12E006", "a YAML anchor is referenced too many times"),
+            Code::AliasExpansionTooLarge => (
+                "C12E007",
+                "a YAML document expands to too many nodes through aliases",
+            ),
+            Code::MixedArray => ("C12E008", "an array mixes element types TOML cannot mix"),
+            Code::Encryption => ("C12E009", "an encrypted value cannot be processed"),
+            Code::InvalidPath => ("C12E010", "a path expression is malformed"),
+            Code::TomlEdit => ("C12E011", "a TOML document edit cannot be applied"),
+            Code::FlattenConflict => ("C12E012", "two flat keys disagree about the tree's shape"),
+            Code::Nondeterminism => (
+                "C12E013",
+                "deterministic mode forbids a source or results differ",
+            ),
+            Code::TypeMismatch => ("C12E014", "a value does not have the schema's type"),
+            Code::ValueNotAllowed => ("C12E015", "a value is not one of the schema's enum values"),
+            Code::MissingRequiredKey => ("C12E016", "a key the schema requires is missing"),
+            Code::UnknownKeyNotAllowed => (
+                "C12E017",
+                "a key is not declared and the schema forbids extra keys",
+            ),
+            Code::YamlEdit => ("C12E018", "a YAML document edit cannot be applied"),
+            Code::JsonEdit => ("C12E019", "a JSON document edit cannot be applied"),
+            Code::PathConflict => (
+                "C12E020",
+                "a path runs through a scalar or past the end of an array",
+            ),
+            Code::Parse => ("C12E021", "a text is not valid in its format"),
+            Code::Patch => ("C12E022", "a JSON Patch operation cannot be applied"),
+            Code::UnacknowledgedEdit => (
+                "C12E023",
+                "a save edits keys owned by other teams without acknowledgment",
+            ),
+            Code::EmptyKey => ("C12W001", "a key is empty"),
+            Code::WhitespaceInKey => ("C12W002", "a key contains whitespace"),
+            Code::ControlCharacterInKey => ("C12W003", "a key contains control characters"),
+            Code::AmbiguousYamlKey => (
+                "C12W004",
+                "a key reads as a YAML 1.1 boolean, null or number when unquoted",
+            ),
+            Code::KeyCaseCollision => ("C12W005", "two keys differ only in case"),
+            Code::UnknownKey => ("C12W006", "a key is not declared by the schema"),
+            Code::DeprecatedKey => ("C12W007", "a key is deprecated by the schema"),
+            Code::ExpiredKey => ("C12W008", "a key is past its schema expiry date"),
+            Code::ForeignEdit => ("C12W009", "an edit changes a key owned by another team"),
+            Code::SchemaViolation => (
+                "C12E024",
+                "a value breaks a schema constraint such as `maximum` or `pattern`",
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+/// Named values a diagnostic message is built from, e.g.
+/// `("path", "server.port")`. Values that do not apply are empty.
+pub type MessageArgs = Vec<(&'static str, String)>;
+
+/// Anything reported with a [`Code`]. `Display` gives the English
+/// message.
+pub trait Diagnostic: fmt::Display {
+    fn code(&self) -> Code;
+
+    fn severity(&self) -> Severity {
+        self.code().severity()
+    }
+
+    /// The values a translated message can refer to, named as listed by
+    /// [`Code::args`].
+    fn args(&self) -> MessageArgs;
+
+    /// The message rendered by `formatter`, or the English message when
+    /// it has no translation for this code.
+    fn localized(&self, formatter: &dyn MessageFormatter) -> String {
+        formatter
+            .format(self.code(), &self.args())
+            .unwrap_or_else(|| self.to_string())
+    }
+}
+
+/// Renders diagnostic messages for hosts that translate them.
+///
+/// Maps from code ids (or codes) to templates implement this, filling
+/// `{name}` placeholders through [`fill_template`]:
+///
+/// ```
+/// use std::collections::HashMap;
+/// use c12_parser::{Diagnostic, lint_keys};
+///
+/// let messages = HashMap::from([(
+///     "C12W004".to_string(),
+///     "clé ambiguë `{key}`, essayez {suggestion}".to_string(),
+/// )]);
+/// let lints = lint_keys(&serde_json::json!({ "no": 1 }));
+/// assert_eq!(lints[0].localized(&messages), "clé ambiguë `no`, essayez 'no'");
+/// assert_eq!(
+///     lints[0].to_string(),
+///     "the key `no` reads as a YAML boolean, null or number unless quoted, consider `'no'`"
+/// );
+/// ```
+pub trait MessageFormatter {
+    /// The message for `code`, or `None` to fall back to English.
+    fn format(&self, code: Code, args: &[(&'static str, String)]) -> Option<String>;
+}
+
+/// Keyed by code id, e.g. `C12E001`, as loaded from a translation file.
+impl MessageFormatter for HashMap<String, String> {
+    fn format(&self, code: Code, args: &[(&'static str, String)]) -> Option<String> {
+        self.get(code.id())
+            .map(|template| fill_template(template, args))
+    }
+}
+
+impl MessageFormatter for HashMap<Code, String> {
+    fn format(&self, code: Code, args: &[(&'static str, String)]) -> Option<String> {
+        self.get(&code)
+            .map(|template| fill_template(template, args))
+    }
+}
+
+/// Replaces each `{name}` in `template` with the value of argument
+/// `name`. Unknown placeholders are left as written.
+pub fn fill_template(template: &str, args: &[(&'static str, String)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let value = after.find('}').and_then(|close| {
+            let name = &after[..close];
+            args.iter()
+                .find(|(arg, _)| *arg == name)
+                .map(|(_, value)| (value, close))
+        });
+        match value {
+            Some((value, close)) => {
+                out.push_str(value);
+                rest = &after[close + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Adds `count` to the arguments of the first of several problems.
+fn aggregate<T: Diagnostic>(items: &[T]) -> MessageArgs {
+    let mut args = items.first().map(Diagnostic::args).unwrap_or_default();
+    args.push(("count", items.len().to_string()));
+    args
+}
+
+fn or_empty(value: Option<impl ToString>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+impl Diagnostic for Finding {
+    fn code(&self) -> Code {
+        self.code
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn args(&self) -> MessageArgs {
+        self.args.clone()
+    }
+}
+
+impl Diagnostic for Violation {
+    fn code(&self) -> Code {
+        match self.keyword.as_str() {
+            "type" => Code::TypeMismatch,
+            "enum" => Code::ValueNotAllowed,
+            "required" => Code::MissingRequiredKey,
+            "additionalProperties" => Code::UnknownKeyNotAllowed,
+            _ => Code::SchemaViolation,
+        }
+    }
+
+    fn args(&self) -> MessageArgs {
+        let mut args = vec![("path", self.path.to_string())];
+        match self.code() {
+            Code::SchemaViolation => {
+                args.push(("keyword", self.keyword.clone()));
+                args.push(("message", self.message.clone()));
+            }
+            _ => args.extend(self.args.iter().cloned()),
+        }
+        args
+    }
+}
+
+impl Diagnostic for KeyLint {
+    fn code(&self) -> Code {
+        match self.issue {
+            KeyIssue::Empty => Code::EmptyKey,
+            KeyIssue::Whitespace => Code::WhitespaceInKey,
+            KeyIssue::ControlCharacter => Code::ControlCharacterInKey,
+            KeyIssue::AmbiguousYamlScalar => Code::AmbiguousYamlKey,
+            KeyIssue::CaseCollision { .. } => Code::KeyCaseCollision,
+        }
+    }
+
+    fn args(&self) -> MessageArgs {
+        vec![
+            ("path", self.path.to_string()),
+            ("key", self.key.clone()),
+            ("suggestion", or_empty(self.suggestion.as_ref())),
+        ]
+    }
+}
+
+impl Diagnostic for AliasIssue {
+    fn code(&self) -> Code {
+        match self.kind {
+            AliasIssueKind::DuplicateAnchor(_) => Code::DuplicateAnchor,
+            AliasIssueKind::RecursiveAlias(_) => Code::RecursiveAlias,
+            AliasIssueKind::ExcessiveReuse(_) => Code::ExcessiveAliasReuse,
+            AliasIssueKind::ExpansionTooLarge { .. } => Code::AliasExpansionTooLarge,
+        }
+    }
+
+    fn args(&self) -> MessageArgs {
+        let mut args = vec![
+            ("line", self.line.to_string()),
+            ("column", self.column.to_string()),
+        ];
+        args.push(match &self.kind {
+            AliasIssueKind::DuplicateAnchor(name)
+            | AliasIssueKind::RecursiveAlias(name)
+            | AliasIssueKind::ExcessiveReuse(name) => ("name", name.clone()),
+            AliasIssueKind::ExpansionTooLarge { nodes } => ("nodes", nodes.to_string()),
+        });
+        args
+    }
+}
+
+/// Reports the code of the first issue; callers needing each one can
+/// iterate `issues`.
+impl Diagnostic for YamlAliasError {
+    fn code(&self) -> Code {
+        self.issues
+            .first()
+            .map_or(Code::RecursiveAlias, Diagnostic::code)
+    }
+
+    fn args(&self) -> MessageArgs {
+        aggregate(&self.issues)
+    }
+}
+
+impl Diagnostic for Loss {
+    fn code(&self) -> Code {
+        Code::DataLoss
+    }
+
+    fn args(&self) -> MessageArgs {
+        let (kind, text) = match &self.kind {
+            LossKind::Comment(text) => ("comment", text.trim().to_string()),
+            LossKind::Null => ("null", String::new()),
+            LossKind::Tag(tag) => ("tag", tag.clone()),
+            LossKind::NonFiniteNumber(number) => ("number", number.clone()),
+        };
+        vec![
+            ("path", self.path.to_string()),
+            ("line", or_empty(self.line)),
+            ("kind", kind.to_string()),
+            ("text", text),
+        ]
+    }
+}
+
+impl Diagnostic for DataLoss {
+    fn code(&self) -> Code {
+        Code::DataLoss
+    }
+
+    fn args(&self) -> MessageArgs {
+        aggregate(&self.losses)
+    }
+}
+
+impl Diagnostic for RoundTripMismatch {
+    fn code(&self) -> Code {
+        Code::RoundTripMismatch
+    }
+
+    fn args(&self) -> MessageArgs {
+        vec![
+            ("path", self.path.to_string()),
+            ("expected", or_empty(self.expected.as_ref())),
+            ("found", or_empty(self.found.as_ref())),
+        ]
+    }
+}
+
+impl Diagnostic for LimitExceeded {
+    fn code(&self) -> Code {
+        Code::LimitExceeded
+    }
+
+    fn args(&self) -> MessageArgs {
+        let kind = match self.kind {
+            LimitKind::StringLength => "string",
+            LimitKind::ArrayLength => "array",
+        };
+        vec![
+            ("path", self.path.to_string()),
+            ("kind", kind.to_string()),
+            ("actual", self.actual.to_string()),
+            ("limit", self.limit.to_string()),
+        ]
+    }
+}
+
+impl Diagnostic for MixedArray {
+    fn code(&self) -> Code {
+        Code::MixedArray
+    }
+
+    fn args(&self) -> MessageArgs {
+        vec![
+            ("path", self.path.to_string()),
+            ("kinds", self.kinds.join(", ")),
+        ]
+    }
+}
+
+impl Diagnostic for MixedArrayError {
+    fn code(&self) -> Code {
+        Code::MixedArray
+    }
+
+    fn args(&self) -> MessageArgs {
+        aggregate(&self.arrays)
+    }
+}
+
+impl Diagnostic for PathParseError {
+    fn code(&self) -> Code {
+        Code::InvalidPath
+    }
+
+    fn args(&self) -> MessageArgs {
+        vec![
+            ("offset", self.offset.to_string()),
+            ("message", self.message.clone()),
+        ]
+    }
+}
+
+impl Diagnostic for PathError {
+    fn code(&self) -> Code {
+        match self {
+            PathError::Parse(e) => e.code(),
+            PathError::Conflict { .. } => Code::PathConflict,
+        }
+    }
+
+    fn args(&self) -> MessageArgs {
+        match self {
+            PathError::Parse(e) => e.args(),
+            PathError::Conflict { path, message } => {
+                vec![("path", path.to_string()), ("message", message.clone())]
+            }
+        }
+    }
+}
+
+impl Diagnostic for C12Error {
+    fn code(&self) -> Code {
+        match &self.kind {
+            C12ErrorKind::Syntax => Code::Parse,
+            C12ErrorKind::DataLoss(e) => e.code(),
+            C12ErrorKind::LimitExceeded(e) => e.code(),
+        }
+    }
+
+    fn args(&self) -> MessageArgs {
+        match &self.kind {
+            C12ErrorKind::Syntax => {}
+            C12ErrorKind::DataLoss(e) => return e.args(),
+            C12ErrorKind::LimitExceeded(e) => return e.args(),
+        }
+        vec![
+            ("format", self.format.to_string()),
+            ("line", self.line.to_string()),
+            ("column", self.column.to_string()),
+            ("message", self.message.clone()),
+        ]
+    }
+}
+
+impl Diagnostic for PatchError {
+    fn code(&self) -> Code {
+        Code::Patch
+    }
+
+    fn args(&self) -> MessageArgs {
+        vec![
+            ("index", self.index.to_string()),
+            ("path", self.pointer.clone()),
+            ("message", self.message.clone()),
+        ]
+    }
+}
+
+impl Diagnostic for ForeignEdit {
+    fn code(&self) -> Code {
+        Code::ForeignEdit
+    }
+
+    fn args(&self) -> MessageArgs {
+        vec![
+            ("file", self.file.display().to_string()),
+            ("path", self.path.to_string()),
+            ("owners", self.owners.join(", ")),
+        ]
+    }
+}
+
+impl Diagnostic for OwnershipError {
+    fn code(&self) -> Code {
+        Code::UnacknowledgedEdit
+    }
+
+    fn args(&self) -> MessageArgs {
+        let mut owners: Vec<&str> = Vec::new();
+        for owner in self.edits.iter().flat_map(|edit| &edit.owners) {
+            if !owners.contains(&owner.as_str()) {
+                owners.push(owner);
+            }
+        }
+        let paths: Vec<_> = self
+            .edits
+            .iter()
+            .map(|edit| edit.path.to_string())
+            .collect();
+        vec![
+            (
+                "file",
+                self.edits
+                    .first()
+                    .map_or_else(String::new, |edit| edit.file.display().to_string()),
+            ),
+            ("paths", paths.join(", ")),
+            ("owners", owners.join(", ")),
+        ]
+    }
+}
+
+impl Diagnostic for FlattenConflict {
+    fn code(&self) -> Code {
+        Code::FlattenConflict
+    }
+
+    fn args(&self) -> MessageArgs {
+        vec![("key", self.key.clone())]
+    }
+}
+
+impl Diagnostic for DocumentEditError {
+    fn code(&self) -> Code {
+        match self {
+            DocumentEditError::Json(e) => e.code(),
+            DocumentEditError::Toml(e) => e.code(),
+            DocumentEditError::Yaml(e) => e.code(),
+            DocumentEditError::Value { .. } => Code::PathConflict,
+            DocumentEditError::Embedded(e) => e.code(),
+        }
+    }
+
+    fn args(&self) -> MessageArgs {
+        match self {
+            DocumentEditError::Json(e) => e.args(),
+            DocumentEditError::Toml(e) => e.args(),
+            DocumentEditError::Yaml(e) => e.args(),
+            DocumentEditError::Value { path, message } => {
+                vec![("path", path.to_string()), ("message", message.clone())]
+            }
+            DocumentEditError::Embedded(e) => e.args(),
+        }
+    }
+}
+
+impl Diagnostic for BumpError {
+    fn code(&self) -> Code {
+        match self {
+            BumpError::Parse(e) => e.code(),
+            BumpError::Edit(e) => e.code(),
+            _ => Code::PathConflict,
+        }
+    }
+
+    fn args(&self) -> MessageArgs {
+        match self {
+            BumpError::Parse(e) => e.args(),
+            BumpError::Edit(e) => e.args(),
+            _ => vec![("path", or_empty(self.path())), ("message", self.message())],
+        }
+    }
+}
+
+impl Diagnostic for DeterminismError {
+    fn code(&self) -> Code {
+        Code::Nondeterminism
+    }
+
+    fn args(&self) -> MessageArgs {
+        let source = match self {
+            DeterminismError::Disallowed(source) => source.to_string(),
+            DeterminismError::Unstable => String::new(),
+        };
+        vec![("source", source)]
+    }
+}
+
+/// The diagnostic behind `error`, when it is one of this crate's errors.
+pub(crate) fn as_diagnostic<'a>(
+    error: &'a (dyn std::error::Error + 'static),
+) -> Option<&'a dyn Diagnostic> {
+    macro_rules! try_types {
+        ($($ty:ty),*) => {
+            $(
+                if let Some(e) = error.downcast_ref::<$ty>() {
+                    return Some(e);
+                }
+            )*
+        };
+    }
+    try_types!(
+        C12Error,
+        DataLoss,
+        RoundTripMismatch,
+        LimitExceeded,
+        MixedArrayError,
+        YamlAliasError,
+        PathError,
+        PathParseError,
+        PatchError,
+        OwnershipError,
+        FlattenConflict,
+        DeterminismError,
+        EncryptionError,
+        TomlEditError,
+        YamlEditError,
+        JsonEditError,
+        DocumentEditError,
+        BumpError
+    );
+    None
+}
+
+/// Errors carrying just a path and a message.
+macro_rules! path_message {
+    ($($ty:ty => $code:ident),* $(,)?) => {
+        $(
+            impl Diagnostic for $ty {
+                fn code(&self) -> Code {
+                    Code::$code
+                }
+
+                fn args(&self) -> MessageArgs {
+                    vec![("path", self.path.to_string()), ("message", self.message.clone())]
+                }
+            }
+        )*
+    };
+}
+
+path_message! {
+    EncryptionError => Encryption,
+    TomlEditError => TomlEdit,
+    YamlEditError => YamlEdit,
+    JsonEditError => JsonEdit,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn catalog_ids_are_unique_and_round_trip() {
+        let ids: HashSet<_> = Code::ALL.iter().map(|c| c.id()).collect();
+        assert_eq!(ids.len(), Code::ALL.len());
+        for &code in Code::ALL {
+            assert_eq!(Code::from_id(code.id()), Some(code));
+            assert!(code.id().starts_with("C12") && code.id().len() == 7);
+            assert!(!code.summary().is_empty());
+        }
+        assert_eq!(Code::from_id("c12e001"), Some(Code::DataLoss));
+        assert_eq!(Code::from_id("C12E999"), None);
+        assert_eq!(Code::DataLoss.to_string(), "C12E001");
+        assert_eq!(Code::ExpiredKey.severity(), Severity::Warning);
+    }
+
+    #[test]
+    fn errors_report_their_codes() {
+        let err = crate::parse_path("a[").unwrap_err();
+        assert_eq!(err.code(), Code::InvalidPath);
+        assert_eq!(err.severity(), Severity::Error);
+
+        let issues = crate::check_yaml_aliases("a: &x [*x]\n", &Default::default()).unwrap();
+        assert_eq!(issues[0].code().id(), "C12E005");
+    }
+
+    #[test]
+    fn args_match_the_catalog() {
+        let value = serde_json::json!({ "no": 1, "extra": true });
+        let schema = serde_json::json!({ "properties": { "no": { "type": "string" } } });
+        let report = crate::doctor(
+            &crate::Formatted::new("", value, &Default::default()),
+            Some(&schema),
+        );
+        let err = crate::parse_path("a[").unwrap_err();
+        let schema = crate::Schema::new(serde_json::json!({
+            "required": ["name"],
+            "properties": { "no": { "type": "string", "enum": ["a"], "maxLength": 0 } },
+            "additionalProperties": false
+        }));
+        let config = crate::Formatted::new(
+            "",
+            serde_json::json!({ "no": "b", "x": 1 }),
+            &Default::default(),
+        );
+        let violations = crate::validate(&config, &schema);
+        let diagnostics: Vec<&dyn Diagnostic> = report
+            .findings
+            .iter()
+            .map(|f| f as &dyn Diagnostic)
+            .chain(violations.iter().map(|v| v as &dyn Diagnostic))
+            .chain([&err as &dyn Diagnostic])
+            .collect();
+        assert_eq!(diagnostics.len(), 8);
+        for diagnostic in diagnostics {
+            let names: Vec<_> = diagnostic.args().iter().map(|(name, _)| *name).collect();
+            assert_eq!(names, diagnostic.code().args(), "{}", diagnostic.code());
+        }
+    }
+
+    #[test]
+    fn formatters_fall_back_to_english() {
+        let err = crate::parse_path("a[").unwrap_err();
+        let messages = HashMap::from([(Code::TypeMismatch, "Typfehler".to_string())]);
+        assert_eq!(err.localized(&messages), err.to_string());
+
+        let messages = HashMap::from([(
+            Code::InvalidPath,
+            "Pfad ungültig bei {offset}: {message}{nope}".to_string(),
+        )]);
+        assert_eq!(
+            err.localized(&messages),
+            format!("Pfad ungültig bei 2: {}{{nope}}", err.message)
+        );
+        assert_eq!(fill_template("{a} {", &[("a", "1".into())]), "1 {");
+    }
+}
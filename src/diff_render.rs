@@ -0,0 +1,258 @@
+use crate::empty::EmptyDocument;
+use crate::format::{Format, FormatOptions};
+use crate::registry::parse_as;
+use crate::value_diff::{Change, diff_values};
+
+/// Unchanged lines shown around each change in a unified diff.
+const CONTEXT_LINES: usize = 3;
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const CYAN: &str = "\x1b[36m";
+const RESET: &str = "\x1b[0m";
+
+/// How [`render_diff`] lays out differences.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DiffStyle {
+    /// One line per added, removed or changed key, as in
+    /// [`Change`]'s display.
+    #[default]
+    Structural,
+    /// A unified diff of the lines, as `diff -u` prints it.
+    Unified,
+}
+
+/// Options for [`render_diff`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RenderDiffOptions {
+    pub style: DiffStyle,
+    /// Color additions, removals and changes with ANSI escapes, for
+    /// terminals.
+    pub color: bool,
+}
+
+/// Renders the differences between two config texts for people to read,
+/// in a terminal or a review comment.
+///
+/// The structural style compares the parsed values, so it reports the
+/// keys added, removed or changed along with their values, and nothing for
+/// edits to comments, whitespace or key order. JSON is read as JSONC. When
+/// either text fails to parse, or with [`DiffStyle::Unified`], the texts
+/// are compared line by line instead. Identical configs render as an empty
+/// string.
+///
+/// ```
+/// use c12_parser::{Format, render_diff};
+///
+/// let a = "port: 80\nhosts: [a, b]\n";
+/// let b = "# web\nport: 8080\nhosts: [a, b]\ntls: true\n";
+/// assert_eq!(
+///     render_diff(a, b, Format::Yaml, None),
+///     "~ port: 80 -> 8080\n+ tls = true\n"
+/// );
+/// ```
+pub fn render_diff(
+    text_a: &str,
+    text_b: &str,
+    format: Format,
+    options: Option<RenderDiffOptions>,
+) -> String {
+    let opts = options.unwrap_or_default();
+    let format = match format {
+        Format::Json => Format::Jsonc,
+        format => format,
+    };
+    let read = |text| {
+        let opts = FormatOptions {
+            on_empty: EmptyDocument::Null,
+            ..FormatOptions::default()
+        };
+        parse_as(format, text, opts).ok().map(|parsed| parsed.value)
+    };
+    if opts.style == DiffStyle::Structural
+        && let (Some(a), Some(b)) = (read(text_a), read(text_b))
+    {
+        return diff_values(&a, &b)
+            .iter()
+            .map(|change| structural_line(change, opts.color))
+            .collect();
+    }
+    unified(text_a, text_b, opts.color)
+}
+
+fn structural_line(change: &Change, color: bool) -> String {
+    let line = change.to_string();
+    if !color {
+        return line + "\n";
+    }
+    let paint = match (&change.old, &change.new) {
+        (None, _) => GREEN,
+        (_, None) => RED,
+        _ => YELLOW,
+    };
+    format!("{paint}{line}{RESET}\n")
+}
+
+/// A line of a line-by-line edit script.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Keep,
+    Remove,
+    Add,
+}
+
+fn unified(a: &str, b: &str, color: bool) -> String {
+    let old: Vec<&str> = a.lines().collect();
+    let new: Vec<&str> = b.lines().collect();
+    let script = edit_script(&old, &new);
+
+    // Each edit with the 0-based old and new line it sits at.
+    let mut steps = Vec::with_capacity(script.len());
+    let (mut i, mut j) = (0, 0);
+    for edit in script {
+        steps.push((edit, i, j));
+        match edit {
+            Edit::Keep => (i, j) = (i + 1, j + 1),
+            Edit::Remove => i += 1,
+            Edit::Add => j += 1,
+        }
+    }
+
+    let mut out = String::new();
+    let changed: Vec<usize> = (0..steps.len())
+        .filter(|&k| steps[k].0 != Edit::Keep)
+        .collect();
+    let mut index = 0;
+    while index < changed.len() {
+        let first = changed[index];
+        let mut last = first;
+        while index + 1 < changed.len() && changed[index + 1] - last <= 2 * CONTEXT_LINES + 1 {
+            index += 1;
+            last = changed[index];
+        }
+        index += 1;
+        let hunk = &steps
+            [first.saturating_sub(CONTEXT_LINES)..(last + CONTEXT_LINES + 1).min(steps.len())];
+        let old_count = hunk.iter().filter(|s| s.0 != Edit::Add).count();
+        let new_count = hunk.iter().filter(|s| s.0 != Edit::Remove).count();
+        let start = |line: usize, count: usize| if count == 0 { line } else { line + 1 };
+        let header = format!(
+            "@@ -{},{old_count} +{},{new_count} @@",
+            start(hunk[0].1, old_count),
+            start(hunk[0].2, new_count)
+        );
+        push_line(&mut out, &header, color.then_some(CYAN));
+        for &(edit, i, j) in hunk {
+            match edit {
+                Edit::Keep => push_line(&mut out, &format!(" {}", old[i]), None),
+                Edit::Remove => push_line(&mut out, &format!("-{}", old[i]), color.then_some(RED)),
+                Edit::Add => push_line(&mut out, &format!("+{}", new[j]), color.then_some(GREEN)),
+            }
+        }
+    }
+    out
+}
+
+fn push_line(out: &mut String, line: &str, paint: Option<&str>) {
+    match paint {
+        Some(paint) => {
+            out.push_str(paint);
+            out.push_str(line);
+            out.push_str(RESET);
+        }
+        None => out.push_str(line),
+    }
+    out.push('\n');
+}
+
+/// The shortest edit script turning `old` into `new`, from a longest
+/// common subsequence of the lines that differ between the common prefix
+/// and suffix.
+fn edit_script(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    // lcs[i][j]: the longest common subsequence of a[i..] and b[j..].
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut script = vec![Edit::Keep; prefix];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            script.push(Edit::Keep);
+            (i, j) = (i + 1, j + 1);
+        } else if j == b.len() || (i < a.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            script.push(Edit::Remove);
+            i += 1;
+        } else {
+            script.push(Edit::Add);
+            j += 1;
+        }
+    }
+    script.extend(std::iter::repeat_n(Edit::Keep, suffix));
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_keys_and_colors_them() {
+        let a = "{\n  // db\n  \"db\": { \"host\": \"a\" },\n  \"debug\": true\n}";
+        let b = "{ \"db\": { \"host\": \"b\", \"pool\": 4 } }";
+        assert_eq!(
+            render_diff(a, b, Format::Json, None),
+            "~ db.host: \"a\" -> \"b\"\n+ db.pool = 4\n- debug = true\n"
+        );
+        let colored = RenderDiffOptions {
+            color: true,
+            ..RenderDiffOptions::default()
+        };
+        assert_eq!(
+            render_diff("a = 1\n", "a = 2\nb = 3\n", Format::Toml, Some(colored)),
+            "\x1b[33m~ a: 1 -> 2\x1b[0m\n\x1b[32m+ b = 3\x1b[0m\n"
+        );
+        assert_eq!(
+            render_diff("a: 1\n", "# same\na:   1\n", Format::Yaml, None),
+            ""
+        );
+    }
+
+    #[test]
+    fn falls_back_to_a_unified_diff() {
+        let a: String = (1..=12).map(|n| format!("k{n} = {n}\n")).collect();
+        let b = a.replace("k2 = 2\n", "k2 = \n").replace("k11 = 11\n", "");
+        assert_eq!(
+            render_diff(&a, &b, Format::Toml, None),
+            "@@ -1,5 +1,5 @@\n k1 = 1\n-k2 = 2\n+k2 = \n k3 = 3\n k4 = 4\n k5 = 5\n\
+             @@ -8,5 +8,4 @@\n k8 = 8\n k9 = 9\n k10 = 10\n-k11 = 11\n k12 = 12\n"
+        );
+        let unified = RenderDiffOptions {
+            style: DiffStyle::Unified,
+            color: false,
+        };
+        assert_eq!(
+            render_diff("a = 1\n", "", Format::Toml, Some(unified)),
+            "@@ -1,1 +0,0 @@\n-a = 1\n"
+        );
+        assert_eq!(render_diff("a = \n", "a = \n", Format::Toml, None), "");
+    }
+}
@@ -0,0 +1,327 @@
+use std::fmt;
+
+use serde_json::{Map, Value as JsonValue};
+
+use crate::context::C12Context;
+use crate::diagnostic::{Code, Diagnostic, MessageArgs};
+use crate::format::Formatted;
+use crate::lint::lint_keys;
+use crate::path::Path;
+use crate::schema::{Schema, resolve, validate};
+
+/// How serious a [`Finding`] is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// The check that produced a [`Finding`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Check {
+    /// Risky key spellings, see [`lint_keys`](crate::lint_keys).
+    KeyLint,
+    /// A value breaking the schema, as [`validate`](crate::validate)
+    /// reports it.
+    Schema,
+    /// A key the schema does not declare: an error when
+    /// `additionalProperties` is `false`, a warning otherwise.
+    UnknownKey,
+    /// A key the schema marks as `deprecated`, optionally pointing at its
+    /// replacement through `x-replaced-by`.
+    Deprecated,
+    /// A key whose schema `x-expires` date (`YYYY-MM-DD`) has passed.
+    Expired,
+}
+
+/// A single problem reported by [`doctor`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub check: Check,
+    /// The stable code of this finding, see [`Code`].
+    pub code: Code,
+    pub path: Path,
+    /// The English message.
+    pub message: String,
+    /// The values behind `message`, for [localized](Diagnostic::localized)
+    /// rendering.
+    pub args: MessageArgs,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_root() {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "`{}`: {}", self.path, self.message)
+        }
+    }
+}
+
+/// The combined result of every check run by [`doctor`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DoctorReport {
+    pub findings: Vec<Finding>,
+}
+
+impl DoctorReport {
+    /// The most severe finding, if any.
+    pub fn max_severity(&self) -> Option<Severity> {
+        self.findings.iter().map(|f| f.severity).max()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.max_severity() == Some(Severity::Error)
+    }
+}
+
+/// Runs the key linter and, when a JSON Schema is given, [`validate`]
+/// plus unknown-key detection, deprecation and expiry checks, bundling
+/// everything into one report sorted by severity.
+pub fn doctor(config: &Formatted<JsonValue>, schema: Option<&JsonValue>) -> DoctorReport {
+    doctor_with_context(&C12Context::default(), config, schema)
+}
+
+/// Like [`doctor`], reading the date for expiry checks from `ctx`. Expiry
+/// checks are skipped in deterministic mode.
+pub fn doctor_with_context(
+    ctx: &C12Context,
+    config: &Formatted<JsonValue>,
+    schema: Option<&JsonValue>,
+) -> DoctorReport {
+    let mut findings: Vec<Finding> = lint_keys(&config.value)
+        .into_iter()
+        .map(|lint| Finding {
+            severity: Severity::Warning,
+            check: Check::KeyLint,
+            code: lint.code(),
+            message: lint.to_string(),
+            args: lint.args(),
+            path: lint.path,
+        })
+        .collect();
+
+    if let Some(schema) = schema {
+        let schema = Schema::new(schema.clone());
+        findings.extend(validate(config, &schema).into_iter().map(|violation| {
+            let code = violation.code();
+            Finding {
+                severity: Severity::Error,
+                check: check_of(code),
+                code,
+                path: violation.path.clone(),
+                args: violation.args(),
+                message: violation.message,
+            }
+        }));
+        let mut checker = AnnotationChecker {
+            root: schema.as_value(),
+            today: ctx.today(),
+            findings: &mut findings,
+        };
+        checker.check(&config.value, schema.as_value(), &Path::root());
+    }
+
+    findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+    DoctorReport { findings }
+}
+
+fn check_of(code: Code) -> Check {
+    match code {
+        Code::UnknownKey | Code::UnknownKeyNotAllowed => Check::UnknownKey,
+        Code::DeprecatedKey => Check::Deprecated,
+        Code::ExpiredKey => Check::Expired,
+        _ => Check::Schema,
+    }
+}
+
+/// Reports what [`validate`] leaves alone: undeclared keys the schema
+/// does not forbid, and deprecated or expired ones.
+struct AnnotationChecker<'a> {
+    root: &'a JsonValue,
+    today: Option<String>,
+    findings: &'a mut Vec<Finding>,
+}
+
+impl AnnotationChecker<'_> {
+    fn report(&mut self, code: Code, path: &Path, message: String, mut args: MessageArgs) {
+        args.insert(0, ("path", path.to_string()));
+        self.findings.push(Finding {
+            severity: Severity::Warning,
+            check: check_of(code),
+            code,
+            path: path.clone(),
+            message,
+            args,
+        });
+    }
+
+    fn check(&mut self, value: &JsonValue, schema: &JsonValue, path: &Path) {
+        let schema = resolve(self.root, schema);
+        match value {
+            JsonValue::Object(map) => self.check_object(map, schema, path),
+            JsonValue::Array(items) => {
+                if let Some(item_schema) = schema.get("items").filter(|s| s.is_object()) {
+                    for (i, item) in items.iter().enumerate() {
+                        self.check(item, item_schema, &path.clone().index(i));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn check_object(&mut self, map: &Map<String, JsonValue>, schema: &JsonValue, path: &Path) {
+        let properties = schema.get("properties").and_then(JsonValue::as_object);
+        let additional = schema.get("additionalProperties");
+
+        for (key, child) in map {
+            let child_path = path.clone().key(key.as_str());
+            match (properties.and_then(|p| p.get(key)), additional) {
+                (Some(property), _) => {
+                    self.check_annotations(resolve(self.root, property), &child_path);
+                    self.check(child, property, &child_path);
+                }
+                (None, Some(additional @ JsonValue::Object(_))) => {
+                    self.check(child, additional, &child_path);
+                }
+                (None, None) if properties.is_some() => self.report(
+                    Code::UnknownKey,
+                    &child_path,
+                    format!("unknown key `{key}`"),
+                    vec![("key", key.clone())],
+                ),
+                (None, _) => {}
+            }
+        }
+    }
+
+    fn check_annotations(&mut self, property: &JsonValue, path: &Path) {
+        if property.get("deprecated") == Some(&JsonValue::Bool(true)) {
+            let replacement = property.get("x-replaced-by").and_then(JsonValue::as_str);
+            let message = match replacement {
+                Some(replacement) => format!("deprecated, use `{replacement}` instead"),
+                None => "deprecated".to_string(),
+            };
+            let args = vec![("replacement", replacement.unwrap_or_default().to_string())];
+            self.report(Code::DeprecatedKey, path, message, args);
+        }
+
+        if let Some(expires) = property.get("x-expires").and_then(JsonValue::as_str)
+            && let Some(today) = &self.today
+            && expires < today.as_str()
+        {
+            self.report(
+                Code::ExpiredKey,
+                path,
+                format!("expired on {expires}, remove it or extend the deadline"),
+                vec![("expires", expires.to_string())],
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::FormatOptions;
+    use serde_json::json;
+
+    fn formatted(value: JsonValue) -> Formatted<JsonValue> {
+        Formatted::new("", value, &FormatOptions::default())
+    }
+
+    #[test]
+    fn reports_lints_without_schema() {
+        let report = doctor(&formatted(json!({ "on": true })), None);
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(report.findings[0].check, Check::KeyLint);
+        assert_eq!(report.findings[0].code, Code::AmbiguousYamlKey);
+        assert_eq!(report.max_severity(), Some(Severity::Warning));
+    }
+
+    #[test]
+    fn bundles_schema_checks_sorted_by_severity() {
+        let schema = json!({
+            "type": "object",
+            "required": ["name"],
+            "properties": {
+                "port": { "type": "integer" },
+                "host": { "deprecated": true, "x-replaced-by": "hostname" },
+                "beta": { "x-expires": "2000-01-01" },
+            },
+        });
+        let config = formatted(json!({ "port": "80", "host": "x", "beta": true, "extra": 1 }));
+
+        let report = doctor(&config, Some(&schema));
+        let paths: Vec<_> = report.findings.iter().map(|f| f.path.to_string()).collect();
+        let summary: Vec<_> = report
+            .findings
+            .iter()
+            .zip(&paths)
+            .map(|(f, path)| (f.severity, f.check, path.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Severity::Error, Check::Schema, ""),
+                (Severity::Error, Check::Schema, "port"),
+                (Severity::Warning, Check::Deprecated, "host"),
+                (Severity::Warning, Check::Expired, "beta"),
+                (Severity::Warning, Check::UnknownKey, "extra"),
+            ]
+        );
+        assert!(report.has_errors());
+        assert!(report.findings[2].message.contains("`hostname`"));
+        assert_eq!(report.findings[1].message, "expected integer, found string");
+    }
+
+    #[test]
+    fn checks_every_schema_keyword_through_refs() {
+        let schema = json!({
+            "$defs": { "port": { "type": "integer", "maximum": 65535, "deprecated": true } },
+            "properties": { "port": { "$ref": "#/$defs/port" } },
+            "additionalProperties": false
+        });
+        let config = formatted(json!({ "port": 80000, "extra": 1 }));
+
+        let report = doctor(&config, Some(&schema));
+        let paths: Vec<_> = report.findings.iter().map(|f| f.path.to_string()).collect();
+        let summary: Vec<_> = report
+            .findings
+            .iter()
+            .zip(&paths)
+            .map(|(f, path)| (f.code, path.as_str(), f.message.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    Code::SchemaViolation,
+                    "port",
+                    "expected at most 65535, found 80000"
+                ),
+                (
+                    Code::UnknownKeyNotAllowed,
+                    "extra",
+                    "unknown key `extra` is not allowed"
+                ),
+                (Code::DeprecatedKey, "port", "deprecated"),
+            ]
+        );
+    }
+
+    #[test]
+    fn deterministic_context_skips_expiry() {
+        let schema = json!({ "properties": { "beta": { "x-expires": "2000-01-01" } } });
+        let config = formatted(json!({ "beta": true }));
+        let ctx = C12Context::new().deterministic(true);
+        assert!(
+            doctor_with_context(&ctx, &config, Some(&schema))
+                .findings
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,465 @@
+use std::fmt;
+
+use jsonc_parser::errors::ParseError;
+use serde_json::Value as JsonValue;
+
+use crate::error::{C12Error, parse_format};
+use crate::format::{Format, FormatOptions, Formatted};
+use crate::json_document::{JsonDocument, JsonEditError};
+use crate::json5::parse_json5;
+use crate::literal::incremented;
+use crate::locate::locate;
+use crate::minify::minify;
+use crate::path::Path;
+use crate::registry::stringify_as;
+use crate::roundtrip::verify_round_trip;
+use crate::toml_document::{TomlDocument, TomlEditError};
+use crate::yaml_document::{YamlDocument, YamlEditError};
+
+/// A config file of any editable format, changed through minimal text
+/// edits so diffs stay small: untouched parts of the file, comments
+/// included, come back byte for byte.
+///
+/// Each variant is the format's own document type, for format-specific
+/// edits. INI has no editable document.
+///
+/// ```
+/// use c12_parser::{Format, Path, parse_document};
+///
+/// let mut doc = parse_document("# server\nport: 8080 # public\n", Format::Yaml)?;
+/// doc.set(&Path::root().key("port"), 9090.into())?;
+/// doc.insert_after(&Path::root().key("port"), "host", "0.0.0.0".into())?;
+/// assert_eq!(doc.to_string(), "# server\nport: 9090 # public\nhost: 0.0.0.0\n");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug)]
+pub enum Document {
+    /// JSON, JSONC or JSON5.
+    Json(JsonDocument),
+    Toml(TomlDocument),
+    Yaml(YamlDocument),
+}
+
+/// Returned when a [`Document`] edit cannot be applied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DocumentEditError {
+    Json(JsonEditError),
+    Toml(TomlEditError),
+    Yaml(YamlEditError),
+    /// The value at `path` is missing or not of the type the edit needs.
+    Value {
+        path: Path,
+        message: String,
+    },
+    /// An embedded document does not parse, or cannot be written, as its
+    /// format.
+    Embedded(C12Error),
+}
+
+impl fmt::Display for DocumentEditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DocumentEditError::Json(e) => e.fmt(f),
+            DocumentEditError::Toml(e) => e.fmt(f),
+            DocumentEditError::Yaml(e) => e.fmt(f),
+            DocumentEditError::Value { path, message } => write!(f, "`{path}` {message}"),
+            DocumentEditError::Embedded(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for DocumentEditError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DocumentEditError::Json(e) => Some(e),
+            DocumentEditError::Toml(e) => Some(e),
+            DocumentEditError::Yaml(e) => Some(e),
+            DocumentEditError::Value { .. } => None,
+            DocumentEditError::Embedded(e) => Some(e),
+        }
+    }
+}
+
+impl From<JsonEditError> for DocumentEditError {
+    fn from(e: JsonEditError) -> Self {
+        DocumentEditError::Json(e)
+    }
+}
+
+impl From<TomlEditError> for DocumentEditError {
+    fn from(e: TomlEditError) -> Self {
+        DocumentEditError::Toml(e)
+    }
+}
+
+impl From<YamlEditError> for DocumentEditError {
+    fn from(e: YamlEditError) -> Self {
+        DocumentEditError::Yaml(e)
+    }
+}
+
+/// The error for a value at `path` an edit cannot use.
+fn unusable(path: &Path, value: Option<JsonValue>, expected: &str) -> DocumentEditError {
+    let message = match value {
+        Some(_) => format!("is not {expected}"),
+        None => "is not in the document".into(),
+    };
+    DocumentEditError::Value {
+        path: path.clone(),
+        message,
+    }
+}
+
+/// Parses `text` as `format` into an editable [`Document`].
+pub fn parse_document(text: &str, format: Format) -> Result<Document, C12Error> {
+    let locate = |e: &(dyn std::error::Error + 'static)| C12Error::new(format, text, e);
+    Ok(match format {
+        Format::Json | Format::Jsonc => Document::Json(text.parse().map_err(|e| locate(&e))?),
+        Format::Json5 => Document::Json(json5_document(text)?),
+        Format::Toml => Document::Toml(text.parse().map_err(|e| locate(&e))?),
+        Format::Yaml => Document::Yaml(text.parse().map_err(|e| locate(&e))?),
+        Format::Ini => {
+            return Err(C12Error::at_offset(
+                format,
+                text,
+                0,
+                "INI documents cannot be edited in place".into(),
+            ));
+        }
+    })
+}
+
+/// JSON5 `text` as a [`JsonDocument`]. The document tree reads only part
+/// of JSON5, so text using the rest, such as `.5`, `Infinity`, `null` keys
+/// or escaped line breaks, is refused rather than misread.
+fn json5_document(text: &str) -> Result<JsonDocument, C12Error> {
+    let expected = parse_json5::<JsonValue>(text, None)
+        .map_err(|e| C12Error::new(Format::Json5, text, &e))?
+        .value;
+    let unsupported = |offset: usize| {
+        C12Error::at_offset(
+            Format::Json5,
+            text,
+            offset,
+            "this JSON5 syntax cannot be edited in place".into(),
+        )
+    };
+    let doc: JsonDocument = text
+        .parse()
+        .map_err(|e: ParseError| unsupported(e.range().start))?;
+    match verify_round_trip(&expected, &doc.to_value()) {
+        Ok(()) => Ok(doc),
+        Err(mismatch) => Err(unsupported(
+            locate(text, Format::Json5, &mismatch.path).map_or(0, |at| at.offset),
+        )),
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Document::Json(doc) => doc.fmt(f),
+            Document::Toml(doc) => doc.fmt(f),
+            Document::Yaml(doc) => doc.fmt(f),
+        }
+    }
+}
+
+impl Document {
+    /// The whole document in the shared value model.
+    pub fn to_value(&self) -> JsonValue {
+        match self {
+            Document::Json(doc) => doc.to_value(),
+            Document::Toml(doc) => doc.to_value(),
+            Document::Yaml(doc) => doc.to_value(),
+        }
+    }
+
+    /// The value at `path`, if present.
+    pub fn get(&self, path: &Path) -> Option<JsonValue> {
+        path.lookup(&self.to_value()).cloned()
+    }
+
+    /// Sets the value at `path`; see each format's `set` for what is
+    /// created on the way.
+    pub fn set(&mut self, path: &Path, value: JsonValue) -> Result<(), DocumentEditError> {
+        match self {
+            Document::Json(doc) => doc.set(path, value)?,
+            Document::Toml(doc) => doc.set(path, value)?,
+            Document::Yaml(doc) => doc.set(path, value)?,
+        }
+        Ok(())
+    }
+
+    /// Removes the value at `path`, returning it.
+    pub fn remove(&mut self, path: &Path) -> Option<JsonValue> {
+        match self {
+            Document::Json(doc) => doc.remove(path),
+            Document::Toml(doc) => doc.remove(path),
+            Document::Yaml(doc) => doc.remove(path),
+        }
+    }
+
+    /// Inserts `key` into the object holding `after`, directly after it.
+    pub fn insert_after(
+        &mut self,
+        after: &Path,
+        key: &str,
+        value: JsonValue,
+    ) -> Result<(), DocumentEditError> {
+        match self {
+            Document::Json(doc) => doc.insert_after(after, key, value)?,
+            Document::Toml(doc) => doc.insert_after(after, key, value)?,
+            Document::Yaml(doc) => doc.insert_after(after, key, value)?,
+        }
+        Ok(())
+    }
+
+    /// Adds `by` to the integer at `path`, returning the new value. The
+    /// literal keeps the way it is written: a JSON5 or TOML `0xff` becomes
+    /// `0x100`, and TOML's `1_000` becomes `1_001`. Fails, leaving the
+    /// document alone, when the value is missing or not an integer.
+    pub fn increment(&mut self, path: &Path, by: i64) -> Result<i64, DocumentEditError> {
+        match self.get(path) {
+            Some(JsonValue::Number(n)) if n.is_i64() || n.is_u64() => {}
+            other => return Err(unusable(path, other, "an integer")),
+        }
+        let mut new = 0;
+        let edit = |raw: &str| {
+            incremented(raw, by).map(|(text, value)| {
+                new = value;
+                text
+            })
+        };
+        match self {
+            Document::Json(doc) => doc.rewrite_number(path, edit)?,
+            Document::Toml(doc) => doc.rewrite_number(path, edit)?,
+            Document::Yaml(doc) => doc.rewrite_number(path, edit)?,
+        }
+        Ok(new)
+    }
+
+    /// Appends `value` to the array at `path`, in the array's own layout.
+    /// Fails when the value is missing or not an array.
+    pub fn append(&mut self, path: &Path, value: JsonValue) -> Result<(), DocumentEditError> {
+        match self.get(path) {
+            Some(JsonValue::Array(items)) => self.set(&path.clone().index(items.len()), value),
+            other => Err(unusable(path, other, "an array")),
+        }
+    }
+
+    /// Flips the boolean at `path`, returning the new value. Fails when
+    /// the value is missing or not a boolean.
+    pub fn toggle(&mut self, path: &Path) -> Result<bool, DocumentEditError> {
+        match self.get(path) {
+            Some(JsonValue::Bool(b)) => {
+                self.set(path, JsonValue::Bool(!b))?;
+                Ok(!b)
+            }
+            other => Err(unusable(path, other, "a boolean")),
+        }
+    }
+
+    /// Parses the string at `path` as a document of its own, such as a
+    /// JSON blob in a YAML field or an INI file in a JSON one. Errors in
+    /// the embedded document are located in the string.
+    pub fn parse_embedded(
+        &self,
+        path: &Path,
+        format: Format,
+    ) -> Result<Formatted<JsonValue>, DocumentEditError> {
+        match self.get(path) {
+            Some(JsonValue::String(text)) => {
+                parse_format(&text, format, None).map_err(DocumentEditError::Embedded)
+            }
+            other => Err(unusable(path, other, "a string")),
+        }
+    }
+
+    /// Writes `embedded` back into the string at `path` as `format`, with
+    /// the layout it was parsed with, for a round trip through
+    /// [`parse_embedded`](Self::parse_embedded). JSON-like documents that
+    /// were on one line stay minified on one line. The outer document
+    /// escapes the string as its format requires.
+    pub fn set_embedded(
+        &mut self,
+        path: &Path,
+        embedded: &Formatted<JsonValue>,
+        format: Format,
+        options: Option<FormatOptions>,
+    ) -> Result<(), DocumentEditError> {
+        let mut text = stringify_as(format, embedded, options.unwrap_or_default())
+            .map_err(|e| DocumentEditError::Embedded(C12Error::new(format, "", &*e)))?;
+        let one_line = self
+            .get(path)
+            .and_then(|old| old.as_str().map(|old| !old.trim().contains('\n')))
+            .unwrap_or(false);
+        if one_line && matches!(format, Format::Json | Format::Jsonc | Format::Json5) {
+            text = minify(&text, format, false)
+                .map_err(|e| DocumentEditError::Embedded(C12Error::new(format, &text, &*e)))?;
+        }
+        self.set(path, JsonValue::String(text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostic::{Code, Diagnostic};
+    use crate::fixtures;
+    use serde_json::json;
+
+    #[test]
+    fn the_same_edits_apply_to_every_format() {
+        let date = Path::root().key("types").key("date");
+        let integer = Path::root().key("types").key("integer");
+        // The JSON5 fixture has an unquoted `null` key, which JSON5
+        // documents refuse.
+        let json5 = fixtures::types(Format::Json5);
+        assert!(parse_document(json5, Format::Json5).is_err());
+        for format in [Format::Json, Format::Jsonc, Format::Toml, Format::Yaml] {
+            let text = fixtures::types(format);
+            let mut doc = parse_document(text, format).unwrap();
+            assert_eq!(doc.to_string(), text, "{format}");
+            doc.set(&integer, json!(2)).unwrap();
+            doc.insert_after(&integer, "count", json!(3)).unwrap();
+            assert_eq!(doc.remove(&date), Some(json!(fixtures::DATE)), "{format}");
+
+            let value = doc.to_value();
+            let keys: Vec<_> = value["types"].as_object().unwrap().keys().collect();
+            assert_eq!(&keys[..3], ["boolean", "integer", "count"], "{format}");
+            assert_eq!(value["types"]["integer"], json!(2), "{format}");
+            // Removing the last JSON entry also drops the comma before it.
+            let out = doc.to_string();
+            let changed = text
+                .lines()
+                .filter(|l| !out.contains(l.trim_end_matches(',')))
+                .count();
+            assert_eq!(
+                changed, 2,
+                "{format}: only the integer and date lines change"
+            );
+        }
+    }
+
+    #[test]
+    fn json5_the_document_tree_cannot_read_is_refused() {
+        let doc = parse_document("{ a: +1, b: 'x', }", Format::Json5).unwrap();
+        assert_eq!(doc.to_value(), json!({ "a": 1, "b": "x" }));
+        let cases = [
+            ("{\n  a: .5,\n}", 2),
+            ("{\n  a: 1,\n  b: Infinity,\n}", 3),
+            ("{\n  null: 1,\n}", 2),
+            ("{\n  a: 'one \\\ntwo',\n}", 2),
+        ];
+        for (text, line) in cases {
+            assert!(crate::json5::parse_json5::<JsonValue>(text, None).is_ok());
+            let err = parse_document(text, Format::Json5).unwrap_err();
+            assert_eq!(
+                (err.message.as_str(), err.line),
+                ("this JSON5 syntax cannot be edited in place", line),
+                "{text}"
+            );
+        }
+        let err = parse_document("{ a: }", Format::Json5).unwrap_err();
+        assert_ne!(err.message, "this JSON5 syntax cannot be edited in place");
+    }
+
+    #[test]
+    fn ini_and_invalid_text_are_located_errors() {
+        let err = parse_document("a = 1\n", Format::Ini).unwrap_err();
+        assert_eq!(err.message, "INI documents cannot be edited in place");
+        let err = parse_document("a = 1\nb = \n", Format::Toml).unwrap_err();
+        assert_eq!(err.line, 2);
+    }
+
+    #[test]
+    fn typed_edits_keep_literals_and_check_types() {
+        let port = Path::root().key("port");
+        let tags = Path::root().key("tags");
+        let debug = Path::root().key("debug");
+        let cases = [
+            (
+                Format::Json5,
+                "{\n  port: 0x1F, // hex\n  tags: ['a'],\n  debug: true,\n}\n",
+                "{\n  port: 0x20, // hex\n  tags: ['a', \"b\"],\n  debug: false,\n}\n",
+            ),
+            (
+                Format::Toml,
+                "port = 9_999 # max\ntags = [\"a\"]\ndebug = true\n",
+                "port = 10_000 # max\ntags = [\"a\", \"b\"]\ndebug = false\n",
+            ),
+            (
+                Format::Yaml,
+                "port: 0o17 # octal\ntags:\n  - a\ndebug: true\n",
+                "port: 0o20 # octal\ntags:\n  - a\n  - b\ndebug: false\n",
+            ),
+        ];
+        for (format, text, expected) in cases {
+            let mut doc = parse_document(text, format).unwrap();
+            assert!(doc.increment(&port, 1).is_ok(), "{format}");
+            doc.append(&tags, json!("b")).unwrap();
+            assert!(!doc.toggle(&debug).unwrap());
+            assert_eq!(doc.to_string(), expected, "{format}");
+        }
+
+        let mut doc = parse_document("{ \"v\": 1.5, \"n\": 2 }", Format::Json).unwrap();
+        assert_eq!(doc.increment(&Path::root().key("n"), 40).unwrap(), 42);
+        let message = |result: Result<(), DocumentEditError>| result.unwrap_err().to_string();
+        assert_eq!(
+            message(doc.increment(&Path::root().key("v"), 1).map(drop)),
+            "`v` is not an integer"
+        );
+        assert_eq!(
+            message(doc.append(&Path::root().key("n"), json!(1))),
+            "`n` is not an array"
+        );
+        assert_eq!(
+            message(doc.toggle(&Path::root().key("x")).map(drop)),
+            "`x` is not in the document"
+        );
+        let err = doc
+            .set(&Path::root().key("n").key("m"), json!(1))
+            .unwrap_err();
+        assert!(matches!(err, DocumentEditError::Json(_)), "{err}");
+        assert_eq!(err.code(), Code::JsonEdit);
+    }
+
+    #[test]
+    fn edits_documents_embedded_in_strings() {
+        let text = "steps:\n  - name: build\n    config: '{\"retries\": 2, \"tags\": [\"ci\"]}'\n";
+        let mut doc = parse_document(text, Format::Yaml).unwrap();
+        let path = Path::root().key("steps").index(0).key("config");
+        let mut embedded = doc.parse_embedded(&path, Format::Json).unwrap();
+        assert_eq!(embedded.value, json!({ "retries": 2, "tags": ["ci"] }));
+        embedded.value["retries"] = json!(3);
+        doc.set_embedded(&path, &embedded, Format::Json, None)
+            .unwrap();
+        assert_eq!(
+            doc.parse_embedded(&path, Format::Json).unwrap().value,
+            json!({ "retries": 3, "tags": ["ci"] })
+        );
+        assert_eq!(
+            doc.to_string(),
+            "steps:\n  - name: build\n    config: '{\"retries\":3,\"tags\":[\"ci\"]}'\n"
+        );
+
+        let mut json = parse_document("{ \"ini\": \"[db]\\nhost = a\\n\" }", Format::Json).unwrap();
+        let ini = Path::root().key("ini");
+        let mut embedded = json.parse_embedded(&ini, Format::Ini).unwrap();
+        embedded.value["db"]["host"] = json!("b \"quoted\"");
+        json.set_embedded(&ini, &embedded, Format::Ini, None)
+            .unwrap();
+        assert_eq!(json.to_value()["ini"], json!("[db]\nhost = b \"quoted\"\n"));
+
+        let name = Path::root().key("steps").index(0).key("name");
+        let err = doc.parse_embedded(&name, Format::Toml).unwrap_err();
+        assert!(matches!(err, DocumentEditError::Embedded(_)), "{err}");
+        assert_eq!(
+            doc.parse_embedded(&Path::root().key("steps"), Format::Json)
+                .unwrap_err()
+                .to_string(),
+            "`steps` is not a string"
+        );
+    }
+}
@@ -0,0 +1,374 @@
+use serde_json::Value as JsonValue;
+
+use crate::format::{EscapeOptions, FormatOptions, QuoteStyle, StyleProfile};
+
+/// The JSON flavour written by the emitter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Dialect {
+    Json,
+    /// Identifier keys are left unquoted and strings may use single
+    /// quotes.
+    Json5,
+}
+
+/// Layout settings shared by the in-crate emitters.
+#[derive(Clone, Debug)]
+pub(crate) struct EmitStyle {
+    pub dialect: Dialect,
+
+    /// The string used for one level of indentation. When empty, output
+    /// is written on a single line.
+    pub indent: String,
+
+    /// Maximum line width. Arrays whose single-line form fits within the
+    /// remaining width are collapsed, longer ones get one element per line.
+    pub line_width: Option<usize>,
+
+    pub escaping: EscapeOptions,
+
+    /// Mimic the JS `JSON.stringify`/`JSON5.stringify` output: numbers are
+    /// printed like JS numbers.
+    pub js_compat: bool,
+
+    /// End multi-line JSON5 containers with a comma.
+    pub trailing_commas: bool,
+}
+
+impl EmitStyle {
+    /// The style `stringify_json`/`stringify_json5` use for `opts`.
+    pub(crate) fn for_options(dialect: Dialect, indent: usize, opts: &FormatOptions) -> Self {
+        let js_compat = opts.profile == StyleProfile::Confbox;
+        let mut escaping = opts.escaping.clone();
+        if dialect == Dialect::Json5 && !js_compat {
+            escaping.quote_style.get_or_insert(QuoteStyle::Single);
+        }
+        Self {
+            dialect,
+            indent: " ".repeat(indent),
+            line_width: opts.line_width,
+            escaping,
+            js_compat,
+            trailing_commas: dialect == Dialect::Json5
+                && opts.trailing_commas.unwrap_or(!js_compat),
+        }
+    }
+}
+
+/// Writes a JSON value using the given style.
+pub(crate) fn to_json_string(value: &JsonValue, style: &EmitStyle) -> String {
+    let mut emitter = Emitter {
+        style,
+        out: String::new(),
+    };
+    emitter.value(value, 0);
+    emitter.out
+}
+
+struct Emitter<'a> {
+    style: &'a EmitStyle,
+    out: String,
+}
+
+impl Emitter<'_> {
+    fn value(&mut self, value: &JsonValue, depth: usize) {
+        match value {
+            JsonValue::Array(items) => self.array(items, depth),
+            JsonValue::Object(map) => self.object(map, depth),
+            scalar => write_scalar(&mut self.out, scalar, self.style),
+        }
+    }
+
+    fn array(&mut self, items: &[JsonValue], depth: usize) {
+        if items.is_empty() {
+            self.out.push_str("[]");
+            return;
+        }
+
+        if let Some(inline) = self.inline_array(items) {
+            self.out.push_str(&inline);
+            return;
+        }
+
+        self.out.push('[');
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                self.out.push(',');
+            }
+            self.newline(depth + 1);
+            self.value(item, depth + 1);
+        }
+        self.trailing_comma();
+        self.newline(depth);
+        self.out.push(']');
+    }
+
+    fn object(&mut self, map: &serde_json::Map<String, JsonValue>, depth: usize) {
+        if map.is_empty() {
+            self.out.push_str("{}");
+            return;
+        }
+
+        self.out.push('{');
+        for (i, (key, item)) in map.iter().enumerate() {
+            if i > 0 {
+                self.out.push(',');
+            }
+            self.newline(depth + 1);
+            if self.style.dialect == Dialect::Json5 && is_identifier(key) {
+                self.out.push_str(key);
+            } else {
+                write_string(&mut self.out, key, self.style);
+            }
+            self.out.push_str(if self.style.indent.is_empty() {
+                ":"
+            } else {
+                ": "
+            });
+            self.value(item, depth + 1);
+        }
+        self.trailing_comma();
+        self.newline(depth);
+        self.out.push('}');
+    }
+
+    /// Returns the single-line form of an array when a line width is set,
+    /// the array only holds scalars (or nested scalar arrays) and it fits
+    /// on the current line.
+    fn inline_array(&self, items: &[JsonValue]) -> Option<String> {
+        let width = self.style.line_width?;
+        if !items.iter().all(is_inlinable) {
+            return None;
+        }
+
+        let mut inline = String::new();
+        write_inline(&mut inline, &JsonValue::Array(items.to_vec()), self.style);
+
+        // Leave room for a trailing comma after the array.
+        let column = self.current_column();
+        (column + inline.chars().count() < width).then_some(inline)
+    }
+
+    fn current_column(&self) -> usize {
+        let line_start = self.out.rfind('\n').map_or(0, |pos| pos + 1);
+        self.out[line_start..].chars().count()
+    }
+
+    fn trailing_comma(&mut self) {
+        if self.style.trailing_commas && !self.style.indent.is_empty() {
+            self.out.push(',');
+        }
+    }
+
+    /// Starts a new line at `depth`; with an empty indent everything
+    /// stays on one line, as `JSON.stringify` does for an indent of 0.
+    fn newline(&mut self, depth: usize) {
+        if self.style.indent.is_empty() {
+            return;
+        }
+        self.out.push('\n');
+        for _ in 0..depth {
+            self.out.push_str(&self.style.indent);
+        }
+    }
+}
+
+fn is_inlinable(value: &JsonValue) -> bool {
+    match value {
+        JsonValue::Object(_) => false,
+        JsonValue::Array(items) => items.iter().all(is_inlinable),
+        _ => true,
+    }
+}
+
+pub(crate) fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+fn write_inline(out: &mut String, value: &JsonValue, style: &EmitStyle) {
+    match value {
+        JsonValue::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_inline(out, item, style);
+            }
+            out.push(']');
+        }
+        other => write_scalar(out, other, style),
+    }
+}
+
+fn write_scalar(out: &mut String, value: &JsonValue, style: &EmitStyle) {
+    match value {
+        JsonValue::String(s) => write_string(out, s, style),
+        JsonValue::Number(n) if style.js_compat => out.push_str(&js_number(n)),
+        // Numbers, booleans and null have a single canonical spelling.
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+/// Formats a number the way JavaScript's `Number.prototype.toString`
+/// does, so integral floats lose their `.0` and large or tiny values use
+/// `e+`/`e-` exponents.
+pub(crate) fn js_number(n: &serde_json::Number) -> String {
+    match n.as_f64() {
+        Some(f) if n.is_f64() => js_float(f),
+        _ => n.to_string(),
+    }
+}
+
+pub(crate) fn js_float(f: f64) -> String {
+    if f.is_nan() {
+        return "NaN".into();
+    }
+    if f.is_infinite() {
+        return if f > 0.0 { "Infinity" } else { "-Infinity" }.into();
+    }
+    let abs = f.abs();
+    if abs == 0.0 || (1e-6..1e21).contains(&abs) {
+        return f.to_string();
+    }
+    let exp = format!("{f:e}");
+    match exp.split_once('e') {
+        Some((mantissa, exponent)) if !exponent.starts_with('-') => {
+            format!("{mantissa}e+{exponent}")
+        }
+        _ => exp,
+    }
+}
+
+/// Writes a quoted string, honoring the escaping options. Single quotes
+/// are only used for the JSON5 dialect, since JSON requires double quotes.
+pub(crate) fn write_string(out: &mut String, s: &str, style: &EmitStyle) {
+    let quote = match (style.dialect, style.escaping.quote_style) {
+        (Dialect::Json5, Some(QuoteStyle::Single)) => '\'',
+        // Like JSON5.stringify: single quotes unless the string holds more
+        // single quotes than double quotes.
+        (Dialect::Json5, None) if style.js_compat => {
+            let singles = s.matches('\'').count();
+            let doubles = s.matches('"').count();
+            if singles > doubles { '"' } else { '\'' }
+        }
+        _ => '"',
+    };
+
+    out.push(quote);
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            '/' if style.escaping.escape_slash => out.push_str("\\/"),
+            c if c == quote => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if c.is_control() || (style.escaping.escape_unicode && !c.is_ascii()) => {
+                let mut units = [0u16; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    out.push_str(&format!("\\u{unit:04x}"));
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out.push(quote);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn style(line_width: Option<usize>) -> EmitStyle {
+        EmitStyle {
+            dialect: Dialect::Json,
+            indent: "  ".into(),
+            line_width,
+            escaping: EscapeOptions::default(),
+            js_compat: false,
+            trailing_commas: false,
+        }
+    }
+
+    #[test]
+    fn emits_nested_structure_per_level() {
+        let value = json!({ "a": { "b": [1, { "c": null }] } });
+        let out = to_json_string(&value, &style(None));
+        assert_eq!(
+            out,
+            "{\n  \"a\": {\n    \"b\": [\n      1,\n      {\n        \"c\": null\n      }\n    ]\n  }\n}"
+        );
+    }
+
+    #[test]
+    fn collapses_short_arrays_within_line_width() {
+        let value = json!({ "array": [1, 2, 3] });
+        let out = to_json_string(&value, &style(Some(80)));
+        assert_eq!(out, "{\n  \"array\": [1, 2, 3]\n}");
+    }
+
+    #[test]
+    fn wraps_arrays_exceeding_line_width() {
+        let value = json!({ "array": ["aaaaaaaa", "bbbbbbbb", "cccccccc"] });
+        let out = to_json_string(&value, &style(Some(20)));
+        assert_eq!(
+            out,
+            "{\n  \"array\": [\n    \"aaaaaaaa\",\n    \"bbbbbbbb\",\n    \"cccccccc\"\n  ]\n}"
+        );
+    }
+
+    #[test]
+    fn escapes_unicode_and_slashes_when_requested() {
+        let mut style = style(None);
+        style.escaping.escape_unicode = true;
+        style.escaping.escape_slash = true;
+
+        let out = to_json_string(&json!("é/😀\n"), &style);
+        assert_eq!(out, r#""\u00e9\/\ud83d\ude00\n""#);
+        assert_eq!(serde_json::from_str::<String>(&out).unwrap(), "é/😀\n");
+    }
+
+    #[test]
+    fn json5_dialect_uses_single_quotes_and_bare_keys() {
+        let mut style = style(None);
+        style.dialect = Dialect::Json5;
+        style.trailing_commas = true;
+        style.escaping.quote_style = Some(QuoteStyle::Single);
+
+        let out = to_json_string(&json!({ "key": "it's", "a b": 1 }), &style);
+        assert_eq!(out, "{\n  key: 'it\\'s',\n  'a b': 1,\n}");
+    }
+
+    #[test]
+    fn js_compat_matches_json5_stringify() {
+        let mut style = style(None);
+        style.dialect = Dialect::Json5;
+        style.js_compat = true;
+
+        let value = json!({ "a": [1.0, 2.5], "s": "it's", "t": "plain" });
+        let out = to_json_string(&value, &style);
+        assert_eq!(
+            out,
+            "{\n  a: [\n    1,\n    2.5\n  ],\n  s: \"it's\",\n  t: 'plain'\n}"
+        );
+    }
+
+    #[test]
+    fn js_float_formats_like_javascript() {
+        assert_eq!(js_float(1.0), "1");
+        assert_eq!(js_float(2.75), "2.75");
+        assert_eq!(js_float(1e21), "1e+21");
+        assert_eq!(js_float(1.5e-7), "1.5e-7");
+        assert_eq!(js_float(-0.5), "-0.5");
+    }
+}
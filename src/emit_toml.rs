@@ -0,0 +1,141 @@
+use toml::Value as TomlValue;
+
+use crate::emit::js_float;
+
+/// Writes a TOML table with the layout of smol-toml's `stringify` (the
+/// serializer behind confbox): key/values first, then `[table]` and
+/// `[[array]]` sections separated by blank lines, and inline arrays
+/// written as `[ 1, 2, 3 ]`.
+pub(crate) fn to_toml_string(table: &toml::Table) -> String {
+    stringify_table(table, "")
+}
+
+fn stringify_table(table: &toml::Table, prefix: &str) -> String {
+    let mut preamble = String::new();
+    let mut tables = String::new();
+
+    for (key, value) in table {
+        let key = format_key(key);
+        let full_key = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+
+        match value {
+            TomlValue::Array(items) if is_array_of_tables(items) => {
+                for item in items {
+                    if let TomlValue::Table(item) = item {
+                        tables.push_str(&format!("[[{full_key}]]\n"));
+                        tables.push_str(&stringify_table(item, &full_key));
+                        tables.push_str("\n\n");
+                    }
+                }
+            }
+            TomlValue::Table(child) => {
+                tables.push_str(&format!("[{full_key}]\n"));
+                tables.push_str(&stringify_table(child, &full_key));
+                tables.push_str("\n\n");
+            }
+            other => {
+                preamble.push_str(&format!("{key} = {}\n", stringify_value(other)));
+            }
+        }
+    }
+
+    if tables.is_empty() {
+        preamble.trim().to_string()
+    } else {
+        format!("{preamble}\n{tables}").trim().to_string()
+    }
+}
+
+fn is_array_of_tables(items: &[TomlValue]) -> bool {
+    !items.is_empty() && items.iter().all(TomlValue::is_table)
+}
+
+fn format_key(key: &str) -> String {
+    let bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if bare {
+        key.to_string()
+    } else {
+        format_string(key)
+    }
+}
+
+/// Strings are written the way `JSON.stringify` writes them, which is
+/// also a valid TOML basic string.
+fn format_string(s: &str) -> String {
+    serde_json::Value::from(s).to_string()
+}
+
+fn stringify_value(value: &TomlValue) -> String {
+    match value {
+        TomlValue::String(s) => format_string(s),
+        TomlValue::Integer(i) => i.to_string(),
+        TomlValue::Float(f) if f.is_nan() => "nan".into(),
+        TomlValue::Float(f) if f.is_infinite() => if *f > 0.0 { "inf" } else { "-inf" }.into(),
+        TomlValue::Float(f) => js_float(*f),
+        TomlValue::Boolean(b) => b.to_string(),
+        TomlValue::Datetime(dt) => dt.to_string(),
+        TomlValue::Array(items) if items.is_empty() => "[]".into(),
+        TomlValue::Array(items) => {
+            let items: Vec<_> = items.iter().map(stringify_value).collect();
+            format!("[ {} ]", items.join(", "))
+        }
+        TomlValue::Table(table) if table.is_empty() => "{}".into(),
+        TomlValue::Table(table) => {
+            let entries: Vec<_> = table
+                .iter()
+                .map(|(k, v)| format!("{} = {}", format_key(k), stringify_value(v)))
+                .collect();
+            format!("{{ {} }}", entries.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_smol_toml_layout() {
+        let table: toml::Table = toml::from_str(
+            r#"
+title = "demo"
+
+[types]
+array = [1, 2, 3]
+mixed = [{ a = 1 }, 2]
+float = 3.0
+
+[types.object]
+key = "value"
+
+[[servers]]
+"host name" = "a"
+
+[[servers]]
+"host name" = "b"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            to_toml_string(&table),
+            "title = \"demo\"\n\n[types]\narray = [ 1, 2, 3 ]\nmixed = [ { a = 1 }, 2 ]\nfloat = 3\n\n[types.object]\nkey = \"value\"\n\n[[servers]]\n\"host name\" = \"a\"\n\n[[servers]]\n\"host name\" = \"b\""
+        );
+    }
+
+    #[test]
+    fn output_reparses() {
+        let table: toml::Table =
+            toml::from_str("a = { b = [\"x\\ny\", 1.5] }\nc = []\n[d]\n").unwrap();
+        let out = to_toml_string(&table);
+        let reparsed: toml::Table = toml::from_str(&out).unwrap();
+        assert_eq!(reparsed, table);
+    }
+}
@@ -0,0 +1,357 @@
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::{Map, Value as JsonValue};
+
+use crate::emit::js_float;
+use crate::path::Path;
+use crate::yaml_aliases::YamlAnchor;
+
+/// Writes a value as block-style YAML following the conventions of
+/// js-yaml's `dump`: sequences are indented under their key, strings are
+/// only quoted when their plain form would be misread, and multi-line
+/// strings become literal block scalars.
+pub(crate) fn to_yaml_string(value: &JsonValue, indent: usize) -> String {
+    to_yaml_string_with_anchors(value, indent, &[])
+}
+
+/// Like [`to_yaml_string`], writing `anchors` back: each anchor still in
+/// `value` is defined again, and each alias whose value still equals the
+/// anchored one is written as `*name` once the anchor precedes it.
+pub(crate) fn to_yaml_string_with_anchors(
+    value: &JsonValue,
+    indent: usize,
+    anchors: &[YamlAnchor],
+) -> String {
+    let mut marks = HashMap::new();
+    for anchor in anchors.iter().filter(|a| !a.path.is_root()) {
+        let Some(anchored) = anchor.path.lookup(value) else {
+            continue;
+        };
+        marks.insert(anchor.path.clone(), Mark::Anchor(anchor.name.clone()));
+        for alias in &anchor.aliases {
+            if alias.path.lookup(value) == Some(anchored) {
+                let mark = Mark::Alias {
+                    name: anchor.name.clone(),
+                    anchor: anchor.path.clone(),
+                };
+                marks.insert(alias.path.clone(), mark);
+            }
+        }
+    }
+
+    // A one-space indent cannot fit the `- ` sequence marker.
+    let mut writer = YamlWriter {
+        indent: indent.max(2),
+        marks,
+        defined: HashMap::new(),
+    };
+    let mut out = String::new();
+    let root = Path::root();
+    match value {
+        JsonValue::Object(map) if !map.is_empty() => writer.mapping(&mut out, map, 0, &root),
+        JsonValue::Array(items) if !items.is_empty() => writer.sequence(&mut out, items, 0, &root),
+        scalar => writer.scalar(&mut out, scalar, 0),
+    }
+    out.push('\n');
+    out
+}
+
+enum Mark {
+    Anchor(String),
+    Alias { name: String, anchor: Path },
+}
+
+struct YamlWriter {
+    indent: usize,
+    marks: HashMap<Path, Mark>,
+    /// The node each anchor name was last defined on, so far.
+    defined: HashMap<String, Path>,
+}
+
+impl YamlWriter {
+    fn pad(&self, out: &mut String, level: usize) {
+        out.push('\n');
+        out.push_str(&" ".repeat(self.indent * level));
+    }
+
+    /// The `&name` or `*name` token for the node at `path`, if it has one,
+    /// and whether it is an alias, which stands for the whole node.
+    fn mark(&mut self, path: &Path) -> Option<(String, bool)> {
+        match self.marks.get(path)? {
+            Mark::Alias { name, anchor } if self.defined.get(name) == Some(anchor) => {
+                Some((format!("*{name}"), true))
+            }
+            Mark::Alias { .. } => None,
+            Mark::Anchor(name) => {
+                self.defined.insert(name.clone(), path.clone());
+                Some((format!("&{name}"), false))
+            }
+        }
+    }
+
+    fn mapping(
+        &mut self,
+        out: &mut String,
+        map: &Map<String, JsonValue>,
+        level: usize,
+        path: &Path,
+    ) {
+        for (i, (key, value)) in map.iter().enumerate() {
+            if i > 0 {
+                self.pad(out, level);
+            }
+            out.push_str(&quote_scalar(key));
+            out.push(':');
+            let path = path.clone().key(key.as_str());
+            if let Some((token, alias)) = self.mark(&path) {
+                out.push(' ');
+                out.push_str(&token);
+                if alias {
+                    continue;
+                }
+            }
+            match value {
+                JsonValue::Object(child) if !child.is_empty() => {
+                    self.pad(out, level + 1);
+                    self.mapping(out, child, level + 1, &path);
+                }
+                JsonValue::Array(items) if !items.is_empty() => {
+                    self.pad(out, level + 1);
+                    self.sequence(out, items, level + 1, &path);
+                }
+                scalar => {
+                    out.push(' ');
+                    self.scalar(out, scalar, level + 1);
+                }
+            }
+        }
+    }
+
+    fn sequence(&mut self, out: &mut String, items: &[JsonValue], level: usize, path: &Path) {
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                self.pad(out, level);
+            }
+            // Pad the dash to a full indent so nested block content lines
+            // up with the first entry.
+            out.push('-');
+            out.push_str(&" ".repeat(self.indent - 1));
+            let path = path.clone().index(i);
+            let mark = self.mark(&path);
+            if let Some((token, alias)) = &mark {
+                out.push_str(token);
+                if *alias {
+                    continue;
+                }
+            }
+            let anchored = mark.is_some();
+            match item {
+                JsonValue::Object(child) if !child.is_empty() => {
+                    if anchored {
+                        self.pad(out, level + 1);
+                    }
+                    self.mapping(out, child, level + 1, &path)
+                }
+                JsonValue::Array(child) if !child.is_empty() => {
+                    if anchored {
+                        self.pad(out, level + 1);
+                    }
+                    self.sequence(out, child, level + 1, &path)
+                }
+                scalar => {
+                    if anchored {
+                        out.push(' ');
+                    }
+                    self.scalar(out, scalar, level + 1)
+                }
+            }
+        }
+    }
+
+    /// Writes a scalar or an empty container. `level` is the indentation
+    /// level used for the body of block scalars.
+    fn scalar(&self, out: &mut String, value: &JsonValue, level: usize) {
+        match value {
+            JsonValue::Null => out.push_str("null"),
+            JsonValue::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+            JsonValue::Number(n) => match n.as_f64() {
+                Some(f) if n.is_f64() && f.is_nan() => out.push_str(".nan"),
+                Some(f) if n.is_f64() && f.is_infinite() => {
+                    out.push_str(if f > 0.0 { ".inf" } else { "-.inf" })
+                }
+                Some(f) if n.is_f64() => out.push_str(&js_float(f)),
+                _ => out.push_str(&n.to_string()),
+            },
+            JsonValue::String(s) if is_block_literal(s) => self.block_literal(out, s, level),
+            JsonValue::String(s) => out.push_str(&quote_scalar(s)),
+            JsonValue::Array(_) => out.push_str("[]"),
+            JsonValue::Object(_) => out.push_str("{}"),
+        }
+    }
+
+    fn block_literal(&self, out: &mut String, s: &str, level: usize) {
+        out.push('|');
+        if s.starts_with(' ') {
+            out.push_str(&self.indent.to_string());
+        }
+        let body = s.trim_end_matches('\n');
+        match s.len() - body.len() {
+            0 => out.push('-'),
+            1 => {}
+            _ => out.push('+'),
+        }
+        for line in body.split('\n') {
+            if line.is_empty() {
+                out.push('\n');
+            } else {
+                self.pad(out, level);
+                out.push_str(line);
+            }
+        }
+        for _ in 1..s.len() - body.len() {
+            out.push('\n');
+        }
+    }
+}
+
+fn is_printable(c: char) -> bool {
+    matches!(c, '\u{20}'..='\u{7e}' | '\u{a1}'..='\u{d7ff}' | '\u{e000}'..='\u{fffd}' | '\u{10000}'..)
+        && c != '\u{2028}'
+        && c != '\u{2029}'
+        && c != '\u{feff}'
+}
+
+fn is_block_literal(s: &str) -> bool {
+    s.contains('\n') && s.chars().all(|c| c == '\n' || is_printable(c))
+}
+
+/// Returns `true` when the unquoted string would resolve to a null,
+/// boolean, number or timestamp under js-yaml's default schema.
+fn is_ambiguous(s: &str) -> bool {
+    static AMBIGUOUS_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(concat!(
+            r"^(?:~|null|Null|NULL|true|True|TRUE|false|False|FALSE|<<",
+            // YAML 1.1 booleans and base-60 numbers, which js-yaml quotes
+            // for older readers.
+            r"|[yYnN]|yes|Yes|YES|no|No|NO|on|On|ON|off|Off|OFF",
+            r"|[-+]?[0-9_]+(?::[0-9_]+)+(?:\.[0-9_]*)?",
+            r"|[-+]?(?:0b[01_]+|0o[0-7_]+|0x[0-9a-fA-F_]+|[0-9][0-9_]*)",
+            r"|[-+]?(?:[0-9][0-9_]*)?(?:\.[0-9_]*)?(?:[eE][-+]?[0-9]+)?",
+            r"|[-+]?\.(?:inf|Inf|INF)|\.(?:nan|NaN|NAN)",
+            r"|[0-9]{4}-[0-9]{1,2}-[0-9]{1,2}",
+            r"(?:(?:[Tt]|[ \t]+)[0-9]{1,2}:[0-9]{2}:[0-9]{2}(?:\.[0-9]*)?",
+            r"(?:[ \t]*(?:Z|[-+][0-9]{1,2}(?::[0-9]{2})?))?)?)$",
+        ))
+        .unwrap()
+    });
+    AMBIGUOUS_RE.is_match(s)
+}
+
+fn is_plain_safe(s: &str) -> bool {
+    const FIRST_INDICATORS: &str = "-?:,[]{}#&*!|>=\'\"%@`";
+
+    let Some(first) = s.chars().next() else {
+        return false;
+    };
+    !FIRST_INDICATORS.contains(first)
+        && !first.is_whitespace()
+        && !s.ends_with(char::is_whitespace)
+        && !s.ends_with(':')
+        && !s.contains(": ")
+        && !s.contains(" #")
+        && s.chars().all(is_printable)
+}
+
+/// Quotes a single-line string (or a key) the way js-yaml would: plain
+/// when safe, single-quoted when only the plain form is problematic and
+/// double-quoted when escapes are required.
+fn quote_scalar(s: &str) -> String {
+    if s.chars().all(is_printable) {
+        if is_plain_safe(s) && !is_ambiguous(s) {
+            return s.to_string();
+        }
+        return format!("'{}'", s.replace('\'', "''"));
+    }
+
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if is_printable(c) => out.push(c),
+            c if (c as u32) < 0x100 => out.push_str(&format!("\\x{:02X}", c as u32)),
+            c => out.push_str(&format!("\\u{:04X}", c as u32)),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn writes_js_yaml_layout() {
+        let value = json!({
+            "types": {
+                "array": [1, 2],
+                "items": [{ "a": 1, "b": [] }],
+                "float": 3.0,
+                "null": null,
+            }
+        });
+        assert_eq!(
+            to_yaml_string(&value, 2),
+            "types:\n  array:\n    - 1\n    - 2\n  items:\n    - a: 1\n      b: []\n  float: 3\n  'null': null\n"
+        );
+    }
+
+    #[test]
+    fn quotes_only_ambiguous_or_unsafe_strings() {
+        assert_eq!(quote_scalar("hello world"), "hello world");
+        for word in ["yes", "No", "ON", "off", "y", "N"] {
+            assert_eq!(quote_scalar(word), format!("'{word}'"));
+        }
+        assert_eq!(quote_scalar("yEs"), "yEs");
+        assert_eq!(quote_scalar("1:30"), "'1:30'");
+        assert_eq!(quote_scalar("-190:20:30.15"), "'-190:20:30.15'");
+        assert_eq!(quote_scalar("10:30 am"), "10:30 am");
+        let value = json!({ "c": "yes", "on": 1 });
+        assert_eq!(to_yaml_string(&value, 2), "c: 'yes'\n'on': 1\n");
+        assert_eq!(quote_scalar("true"), "'true'");
+        assert_eq!(quote_scalar("0x1F"), "'0x1F'");
+        assert_eq!(
+            quote_scalar("1979-05-27T15:32:00.000Z"),
+            "'1979-05-27T15:32:00.000Z'"
+        );
+        assert_eq!(quote_scalar("- item"), "'- item'");
+        assert_eq!(quote_scalar("it's: x"), "'it''s: x'");
+        assert_eq!(quote_scalar("tab\there"), "\"tab\\there\"");
+        assert_eq!(quote_scalar(""), "''");
+    }
+
+    #[test]
+    fn writes_multi_line_strings_as_block_literals() {
+        let value = json!({ "script": "echo a\n\necho b\n", "bare": "x\ny" });
+        let out = to_yaml_string(&value, 2);
+        assert_eq!(out, "script: |\n  echo a\n\n  echo b\nbare: |-\n  x\n  y\n");
+        let reparsed: JsonValue = serde_yaml::from_str(&out).unwrap();
+        assert_eq!(reparsed, value);
+    }
+
+    #[test]
+    fn wider_indent_stays_valid() {
+        let value = json!({ "list": [{ "a": 1, "b": 2 }] });
+        let out = to_yaml_string(&value, 4);
+        assert_eq!(out, "list:\n    -   a: 1\n        b: 2\n");
+        let reparsed: JsonValue = serde_yaml::from_str(&out).unwrap();
+        assert_eq!(reparsed, value);
+    }
+}
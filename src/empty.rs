@@ -0,0 +1,121 @@
+use serde::de::{DeserializeOwned, Error};
+use serde_json::{Map, Value as JsonValue};
+
+use crate::format::Format;
+
+/// What the `parse_*` functions return for an empty document: one with
+/// nothing but whitespace and comments.
+///
+/// The backends disagree on their own (an empty TOML file is an empty
+/// table, empty JSONC is `null` and empty JSON is a syntax error), so the
+/// choice is made here, once, for every format.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EmptyDocument {
+    /// Fail with a "document is empty" error.
+    #[default]
+    Error,
+    /// Read the document as `null`, which deserializes into `Option`s and
+    /// `()`.
+    Null,
+    /// Read the document as an empty object, which deserializes into maps
+    /// and structs whose fields all have defaults.
+    Default,
+}
+
+/// Whether `text` holds nothing but whitespace and comments of `format`.
+pub(crate) fn is_empty_document(text: &str, format: Format) -> bool {
+    match format {
+        Format::Json => text.trim().is_empty(),
+        Format::Json5 | Format::Jsonc => only_comments(text, &["//"], true),
+        Format::Toml | Format::Yaml => only_comments(text, &["#"], false),
+        Format::Ini => only_comments(text, &["#", ";"], false),
+    }
+}
+
+/// Whether `text` holds nothing but whitespace, comments running from one
+/// of `line` to the end of the line and, with `block`, `/* */` comments.
+pub(crate) fn only_comments(text: &str, line: &[&str], block: bool) -> bool {
+    let mut rest = text.trim_start();
+    while !rest.is_empty() {
+        if line.iter().any(|marker| rest.starts_with(marker)) {
+            rest = rest.split_once('\n').map_or("", |(_, next)| next);
+        } else if let Some(comment) = rest.strip_prefix("/*").filter(|_| block) {
+            let Some((_, next)) = comment.split_once("*/") else {
+                return false;
+            };
+            rest = next;
+        } else {
+            return false;
+        }
+        rest = rest.trim_start();
+    }
+    true
+}
+
+/// The value of an empty document under `mode`.
+pub(crate) fn empty_value<T, E>(mode: EmptyDocument) -> Result<T, E>
+where
+    T: DeserializeOwned,
+    E: Error,
+{
+    let value = match mode {
+        EmptyDocument::Error => return Err(E::custom("document is empty")),
+        EmptyDocument::Null => JsonValue::Null,
+        EmptyDocument::Default => JsonValue::Object(Map::new()),
+    };
+    serde_json::from_value(value).map_err(E::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_documents_of_only_comments() {
+        assert!(is_empty_document(" \n\t", Format::Json));
+        assert!(!is_empty_document("// x", Format::Json));
+        assert!(is_empty_document("// a\n/* b\n c */ // d", Format::Jsonc));
+        assert!(!is_empty_document("/* open", Format::Json5));
+        assert!(is_empty_document("# a\n\n  # b", Format::Yaml));
+        assert!(is_empty_document("; a\n# b\n", Format::Ini));
+        assert!(!is_empty_document("# a\nkey = 1\n", Format::Toml));
+    }
+
+    #[test]
+    fn maps_modes_to_values() {
+        let error = empty_value::<JsonValue, serde_json::Error>(EmptyDocument::Error);
+        assert_eq!(error.unwrap_err().to_string(), "document is empty");
+        let null: Option<u8> = empty_value::<_, serde_json::Error>(EmptyDocument::Null).unwrap();
+        assert_eq!(null, None);
+        let map: std::collections::BTreeMap<String, u8> =
+            empty_value::<_, serde_json::Error>(EmptyDocument::Default).unwrap();
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn every_format_treats_empty_documents_alike() {
+        use crate::{FormatOptions, parse_format};
+        use serde_json::json;
+
+        for format in Format::ALL {
+            let text = match format {
+                Format::Json => "\n  \n",
+                Format::Json5 | Format::Jsonc => "// nothing yet\n",
+                Format::Toml | Format::Yaml | Format::Ini => "# nothing yet\n",
+            };
+            let err = parse_format::<JsonValue>(text, format, None).unwrap_err();
+            assert!(err.message.contains("document is empty"), "{format}: {err}");
+            for (on_empty, expected) in [
+                (EmptyDocument::Null, json!(null)),
+                (EmptyDocument::Default, json!({})),
+            ] {
+                let opts = FormatOptions {
+                    on_empty,
+                    ..FormatOptions::default()
+                };
+                let parsed = parse_format::<JsonValue>(text, format, Some(opts)).unwrap();
+                assert_eq!(parsed.value, expected, "{format}");
+            }
+        }
+    }
+}
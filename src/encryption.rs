@@ -0,0 +1,201 @@
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde_json::Value as JsonValue;
+
+use crate::hooks::HookResult;
+use crate::path::Path;
+
+/// Prefix marking an encrypted value: `enc:v1:<base64 ciphertext>`.
+pub const ENC_PREFIX: &str = "enc:v1:";
+
+/// Encrypts and decrypts individual config values, typically by calling
+/// out to a KMS or a local keyring. The crate only handles the markers and
+/// the encoding; the cipher is entirely up to the provider.
+pub trait KeyProvider: Send + Sync {
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>>;
+    fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>>;
+}
+
+/// A value that could not be encrypted or decrypted.
+#[derive(Debug)]
+pub struct EncryptionError {
+    /// Path of the value, e.g. `db.password`.
+    pub path: Path,
+    pub message: String,
+}
+
+impl fmt::Display for EncryptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cannot process encrypted value at `{}`: {}",
+            self.path, self.message
+        )
+    }
+}
+
+impl Error for EncryptionError {}
+
+/// Returns `true` if `value` is an `enc:` marker string.
+pub fn is_encrypted(value: &JsonValue) -> bool {
+    value.as_str().is_some_and(|s| s.starts_with(ENC_PREFIX))
+}
+
+/// Replaces every `enc:v1:` string in `value` by its decrypted value and
+/// returns the paths that were decrypted.
+///
+/// The plaintext is the JSON text of the original value, so numbers and
+/// booleans keep their type.
+pub fn decrypt_values(
+    value: &mut JsonValue,
+    provider: &dyn KeyProvider,
+) -> Result<Vec<Path>, EncryptionError> {
+    let mut decrypted = Vec::new();
+    decrypt_visit(value, provider, &Path::root(), &mut decrypted)?;
+    Ok(decrypted)
+}
+
+/// Encrypts the value at `path` in place, for use before saving. Values
+/// that are already encrypted are left alone.
+pub fn encrypt_path(
+    value: &mut JsonValue,
+    path: &Path,
+    provider: &dyn KeyProvider,
+) -> Result<(), EncryptionError> {
+    let error = |message: String| EncryptionError {
+        path: path.clone(),
+        message,
+    };
+    let target = path
+        .lookup_mut(value)
+        .ok_or_else(|| error("no value at this path".into()))?;
+    if is_encrypted(target) {
+        return Ok(());
+    }
+    let plaintext = serde_json::to_vec(target).map_err(|e| error(e.to_string()))?;
+    let ciphertext = provider
+        .encrypt(&plaintext)
+        .map_err(|e| error(e.to_string()))?;
+    *target = format!("{ENC_PREFIX}{}", BASE64.encode(ciphertext)).into();
+    Ok(())
+}
+
+/// A [`C12Context::post_parse`](crate::C12Context::post_parse) hook that
+/// decrypts `enc:` values as configs are resolved.
+pub fn decrypt_hook(
+    provider: Arc<dyn KeyProvider>,
+) -> impl Fn(JsonValue) -> HookResult<JsonValue> + Send + Sync + 'static {
+    move |mut value| {
+        decrypt_values(&mut value, provider.as_ref())?;
+        Ok(value)
+    }
+}
+
+fn decrypt_visit(
+    value: &mut JsonValue,
+    provider: &dyn KeyProvider,
+    path: &Path,
+    decrypted: &mut Vec<Path>,
+) -> Result<(), EncryptionError> {
+    match value {
+        JsonValue::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                decrypt_visit(child, provider, &path.clone().key(key.as_str()), decrypted)?;
+            }
+        }
+        JsonValue::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                decrypt_visit(item, provider, &path.clone().index(i), decrypted)?;
+            }
+        }
+        JsonValue::String(s) => {
+            if let Some(encoded) = s.strip_prefix(ENC_PREFIX) {
+                let error = |message: String| EncryptionError {
+                    path: path.clone(),
+                    message,
+                };
+                let ciphertext = BASE64
+                    .decode(encoded.trim())
+                    .map_err(|e| error(format!("invalid base64: {e}")))?;
+                let plaintext = provider
+                    .decrypt(&ciphertext)
+                    .map_err(|e| error(e.to_string()))?;
+                *value = serde_json::from_slice(&plaintext)
+                    .map_err(|e| error(format!("decrypted value is not JSON: {e}")))?;
+                decrypted.push(path.clone());
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// XORs with a fixed byte; stands in for a real KMS.
+    struct Xor;
+
+    impl KeyProvider for Xor {
+        fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+            Ok(plaintext.iter().map(|b| b ^ 0x5a).collect())
+        }
+
+        fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+            self.encrypt(ciphertext)
+        }
+    }
+
+    #[test]
+    fn encrypts_and_decrypts_single_values() {
+        let mut config = json!({ "db": { "password": "hunter2", "port": 5432 }, "name": "app" });
+        encrypt_path(&mut config, &"db.password".parse().unwrap(), &Xor).unwrap();
+        encrypt_path(&mut config, &"db.port".parse().unwrap(), &Xor).unwrap();
+        assert!(is_encrypted(&config["db"]["password"]));
+        assert_eq!(config["name"], "app");
+
+        let before = config.clone();
+        encrypt_path(&mut config, &"db.port".parse().unwrap(), &Xor).unwrap();
+        assert_eq!(config, before);
+
+        let paths = decrypt_values(&mut config, &Xor).unwrap();
+        let paths: Vec<_> = paths.iter().map(ToString::to_string).collect();
+        assert_eq!(paths, ["db.password", "db.port"]);
+        assert_eq!(
+            config,
+            json!({ "db": { "password": "hunter2", "port": 5432 }, "name": "app" })
+        );
+    }
+
+    #[test]
+    fn reports_bad_markers_by_path() {
+        let mut config = json!({ "keys": ["enc:v1:not base64!"] });
+        let err = decrypt_values(&mut config, &Xor).unwrap_err();
+        assert_eq!(err.path, Path::root().key("keys").index(0));
+
+        let err = encrypt_path(&mut json!({}), &"missing".parse().unwrap(), &Xor).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot process encrypted value at `missing`: no value at this path"
+        );
+    }
+
+    #[test]
+    fn decrypts_as_a_post_parse_hook() {
+        let mut secret = json!({ "token": "abc" });
+        encrypt_path(&mut secret, &"token".parse().unwrap(), &Xor).unwrap();
+        let text = serde_json::to_string(&secret).unwrap();
+
+        let ctx = crate::C12Context::new().post_parse(decrypt_hook(Arc::new(Xor)));
+        let config = ctx
+            .parse_with(&text, |text| crate::parse_json(text, None))
+            .unwrap();
+        assert_eq!(config.value["token"], "abc");
+    }
+}
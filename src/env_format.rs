@@ -0,0 +1,381 @@
+use std::fmt;
+
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::{Map, Value as JsonValue};
+
+use crate::format::{FormatOptions, Formatted};
+use crate::interpolate::{interpolate_env, process_env};
+use crate::limits::{LimitExceeded, enforce_limits};
+use crate::roundtrip::guard;
+
+/// Returned by [`parse_env`] for malformed lines and by [`stringify_env`]
+/// for values a `.env` file cannot hold.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EnvError {
+    /// 1-based line of the problem when parsing.
+    pub line: Option<usize>,
+    pub message: String,
+    /// The value over the [`limits`](FormatOptions::limits), when that is
+    /// the problem; also the error's [`source`](std::error::Error::source).
+    pub exceeded: Option<LimitExceeded>,
+}
+
+impl fmt::Display for EnvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {line}: {}", self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl std::error::Error for EnvError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.exceeded
+            .as_ref()
+            .map(|e| e as &(dyn std::error::Error + 'static))
+    }
+}
+
+impl From<LimitExceeded> for EnvError {
+    fn from(e: LimitExceeded) -> Self {
+        Self {
+            line: None,
+            message: e.to_string(),
+            exceeded: Some(e),
+        }
+    }
+}
+
+impl serde::de::Error for EnvError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self {
+            line: None,
+            message: msg.to_string(),
+            exceeded: None,
+        }
+    }
+}
+
+impl serde::ser::Error for EnvError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        <Self as serde::de::Error>::custom(msg)
+    }
+}
+
+/// Parses a dotenv file into an object of strings, in source order.
+///
+/// Lines are `KEY=value`, optionally prefixed with `export`. Values may be
+/// unquoted (trimmed, with a ` #` starting a comment), single-quoted or
+/// backtick-quoted (taken literally), or double-quoted, where `\n`, `\r`,
+/// `\t`, `\\`, `\"` and `\$` are escapes. Quoted values may span lines. A
+/// key given twice keeps its first position and its last value. Variables
+/// are not expanded.
+///
+/// ```
+/// use c12_parser::parse_env;
+///
+/// let env = parse_env::<serde_json::Value>(
+///     "# app\nexport PORT=8080\nNAME=\"web\\tapp\" # shown\nKEY='-----BEGIN\nabc'\n",
+///     None,
+/// )?;
+/// assert_eq!(env.value["PORT"], "8080");
+/// assert_eq!(env.value["NAME"], "web\tapp");
+/// assert_eq!(env.value["KEY"], "-----BEGIN\nabc");
+/// # Ok::<(), c12_parser::EnvError>(())
+/// ```
+pub fn parse_env<T>(text: &str, options: Option<FormatOptions>) -> Result<Formatted<T>, EnvError>
+where
+    T: DeserializeOwned,
+{
+    let mut opts = options.unwrap_or_default();
+    opts.preserve_indentation = false;
+    let mut value = JsonValue::Object(env_to_map(text)?);
+    if opts.interpolate_env {
+        interpolate_env(&mut value, process_env).map_err(<EnvError as serde::de::Error>::custom)?;
+    }
+    enforce_limits(&mut value, &opts.limits)?;
+    let value = serde_json::from_value(value).map_err(<EnvError as serde::de::Error>::custom)?;
+    Ok(Formatted::new(text, value, &opts))
+}
+
+/// Stringifies a flat object as a dotenv file, one `KEY=value` per line.
+///
+/// Strings with whitespace, quotes, `#` or `$` are quoted so they read
+/// back as themselves and are not expanded; numbers and booleans are
+/// written as they are and `null` as an empty value. The output ends with
+/// exactly one newline. Nested objects and arrays are an error, as are
+/// keys that are not valid variable names.
+pub fn stringify_env<T>(
+    formatted: &Formatted<T>,
+    options: Option<FormatOptions>,
+) -> Result<String, EnvError>
+where
+    T: Serialize,
+{
+    let opts = options.unwrap_or_default();
+    let value =
+        serde_json::to_value(&formatted.value).map_err(<EnvError as serde::ser::Error>::custom)?;
+    let Some(map) = value.as_object() else {
+        return Err(<EnvError as serde::ser::Error>::custom(
+            ".env output requires an object of variables",
+        ));
+    };
+
+    let mut body = String::new();
+    let mut written = Map::new();
+    for (key, value) in map {
+        if !is_key(key) {
+            return Err(<EnvError as serde::ser::Error>::custom(format!(
+                "`{key}` is not a valid variable name"
+            )));
+        }
+        let text = match value {
+            JsonValue::Null => String::new(),
+            JsonValue::String(s) => s.clone(),
+            JsonValue::Bool(_) | JsonValue::Number(_) => value.to_string(),
+            JsonValue::Array(_) | JsonValue::Object(_) => {
+                return Err(<EnvError as serde::ser::Error>::custom(format!(
+                    "`{key}` must be a string, number or boolean in a .env file"
+                )));
+            }
+        };
+        body.push_str(key);
+        body.push('=');
+        body.push_str(&quote(&text));
+        body.push('\n');
+        written.insert(key.clone(), JsonValue::String(text));
+    }
+
+    // Each line ends with its own newline, so the source's trailing
+    // whitespace is not added again.
+    let out = format!("{}{body}", formatted.format.whitespace_start);
+    if opts.verify_round_trip {
+        // Every value reads back as a string, so compare against those.
+        guard::<_, EnvError>(&JsonValue::Object(written), &out, |text| {
+            env_to_map(text)
+                .map(JsonValue::Object)
+                .map_err(|e| e.to_string())
+        })?;
+    }
+    Ok(out)
+}
+
+fn is_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+/// `text` as written after `=`: bare when it has no whitespace, quotes,
+/// `#` or `$`, and quoted otherwise. A `$` is kept from tools that expand
+/// variables by single quotes, which every dotenv reader takes literally,
+/// or else by a `\$` escape.
+fn quote(text: &str) -> String {
+    let bare = !text.contains(|c: char| c.is_whitespace() || "#\"'`\\$".contains(c));
+    if bare {
+        return text.to_string();
+    }
+    if text.contains('$') && !text.contains(['\'', '\n', '\r']) {
+        return format!("'{text}'");
+    }
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '$' => out.push_str("\\$"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Reads the variables of a dotenv file.
+fn env_to_map(text: &str) -> Result<Map<String, JsonValue>, EnvError> {
+    let mut map = Map::new();
+    let mut rest = text;
+    let mut line = 1;
+    while !rest.is_empty() {
+        let (current, after) = rest.split_once('\n').unwrap_or((rest, ""));
+        let error = |message: &str| EnvError {
+            line: Some(line),
+            message: message.to_string(),
+            exceeded: None,
+        };
+        let trimmed = current.trim_start();
+        if trimmed.trim_end().is_empty() || trimmed.starts_with('#') {
+            rest = after;
+            line += 1;
+            continue;
+        }
+        let assignment = trimmed
+            .strip_prefix("export")
+            .filter(|s| s.starts_with([' ', '\t']))
+            .map_or(trimmed, str::trim_start);
+        let Some((key, value)) = assignment.split_once('=') else {
+            return Err(error("expected `KEY=value`"));
+        };
+        let key = key.trim_end();
+        if !is_key(key) {
+            return Err(error(&format!("`{key}` is not a valid variable name")));
+        }
+
+        // The value may run past this line, so scan from `=` to the end
+        // of the text.
+        let start = text.len() - rest.len() + (current.len() - value.len());
+        let source = text[start..].trim_start_matches([' ', '\t']);
+        let (value, consumed) = match source.chars().next() {
+            Some(quote @ ('"' | '\'' | '`')) => quoted(source, quote)
+                .ok_or_else(|| error(&format!("unterminated {quote}-quoted value for `{key}`")))?,
+            _ => {
+                let end = source.find('\n').unwrap_or(source.len());
+                let raw = &source[..end];
+                let raw = raw.find(" #").map_or(raw, |i| &raw[..i]);
+                (raw.trim().to_string(), end)
+            }
+        };
+        let remainder = &source[consumed..];
+        let tail_end = remainder.find('\n').unwrap_or(remainder.len());
+        let tail = remainder[..tail_end].trim();
+        if !tail.is_empty() && !tail.starts_with('#') {
+            return Err(error(&format!(
+                "unexpected `{tail}` after the value of `{key}`"
+            )));
+        }
+        line += source[..consumed].matches('\n').count() + 1;
+        rest = remainder.get(tail_end + 1..).unwrap_or("");
+        map.insert(key.to_string(), JsonValue::String(value));
+    }
+    Ok(map)
+}
+
+/// A value in `quote`s at the start of `source`, unescaped, and the number
+/// of bytes it took up.
+fn quoted(source: &str, quote: char) -> Option<(String, usize)> {
+    let mut value = String::new();
+    let mut chars = source.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            c if c == quote => return Some((value, i + 1)),
+            '\\' if quote == '"' => {
+                let (_, escaped) = chars.next()?;
+                match escaped {
+                    'n' => value.push('\n'),
+                    'r' => value.push('\r'),
+                    't' => value.push('\t'),
+                    '\\' | '"' | '$' => value.push(escaped),
+                    other => {
+                        value.push('\\');
+                        value.push(other);
+                    }
+                }
+            }
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse(text: &str) -> JsonValue {
+        parse_env::<JsonValue>(text, None).unwrap().value
+    }
+
+    #[test]
+    fn reads_quotes_escapes_and_comments() {
+        let text = "\
+# database
+export DB_HOST = localhost
+DB_PASS=\"p#ss \\\"word\\\"\\n\"   # quoted
+GREETING='hello $USER \\n'
+EMPTY=
+URL=http://example.com/#anchor # trailing comment
+MULTI=\"line one
+line two\"
+DB_HOST=db.internal
+";
+        let env = parse(text);
+        assert_eq!(
+            env,
+            json!({
+                "DB_HOST": "db.internal",
+                "DB_PASS": "p#ss \"word\"\n",
+                "GREETING": "hello $USER \\n",
+                "EMPTY": "",
+                "URL": "http://example.com/#anchor",
+                "MULTI": "line one\nline two",
+            })
+        );
+        let keys: Vec<_> = env.as_object().unwrap().keys().collect();
+        assert_eq!(
+            keys,
+            ["DB_HOST", "DB_PASS", "GREETING", "EMPTY", "URL", "MULTI"]
+        );
+    }
+
+    #[test]
+    fn reports_the_line_of_errors() {
+        let err = parse_env::<JsonValue>("A=1\nB=\"open\n\n", None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2: unterminated \"-quoted value for `B`"
+        );
+        let err = parse_env::<JsonValue>("A=1\nMULTI='a\nb'\nnot a pair\n", None).unwrap_err();
+        assert_eq!(err.line, Some(4));
+        let err = parse_env::<JsonValue>("A='x' y\n", None).unwrap_err();
+        assert_eq!(err.message, "unexpected `y` after the value of `A`");
+    }
+
+    #[test]
+    fn stringify_quotes_only_when_needed() {
+        let formatted = Formatted::new(
+            "\n{}",
+            json!({
+                "PORT": 8080,
+                "DEBUG": true,
+                "NAME": "web",
+                "NOTE": " padded # not a comment",
+                "CERT": "a\nb\t\"c\"",
+                "UNSET": null,
+            }),
+            &FormatOptions::default(),
+        );
+        let options = FormatOptions {
+            verify_round_trip: true,
+            ..FormatOptions::default()
+        };
+        let out = stringify_env(&formatted, Some(options)).unwrap();
+        assert_eq!(
+            out,
+            "\nPORT=8080\nDEBUG=true\nNAME=web\nNOTE=\" padded # not a comment\"\nCERT=\"a\\nb\\t\\\"c\\\"\"\nUNSET=\n"
+        );
+        assert_eq!(parse(&out)["CERT"], "a\nb\t\"c\"");
+
+        let text = "A=1\nB='lit $x'\nC=\"$HOME/a b\"\nD=\"it's $x\"\nE=a b\n\n";
+        let once = stringify_env(&parse_env::<JsonValue>(text, None).unwrap(), None).unwrap();
+        assert_eq!(
+            once,
+            "A=1\nB='lit $x'\nC='$HOME/a b'\nD=\"it's \\$x\"\nE=\"a b\"\n"
+        );
+        let twice = stringify_env(&parse_env::<JsonValue>(&once, None).unwrap(), None).unwrap();
+        assert_eq!(twice, once);
+        assert_eq!(parse(&once), parse(text));
+
+        let nested = Formatted {
+            value: json!({ "db": { "host": "x" } }),
+            format: formatted.format,
+        };
+        assert!(stringify_env(&nested, None).is_err());
+    }
+}
@@ -0,0 +1,253 @@
+use std::fmt;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::Value as JsonValue;
+
+use crate::format::Format;
+use crate::path::{Path, shown};
+use crate::roundtrip::{RoundTripMismatch, first_difference};
+use crate::sniff::parse_auto;
+
+/// The key `toml` uses to carry a native datetime through `serde`.
+const TOML_DATETIME: &str = "$__toml_private_datetime";
+
+static DATE_TIME: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^(\d{4})-(\d{2})-(\d{2})(?:[Tt ](\d{2}):(\d{2}):(\d{2})(?:\.(\d+))?)?(?:([Zz])|([+-])(\d{2}):(\d{2}))?$",
+    )
+    .unwrap()
+});
+
+/// How loosely [`assert_equivalent_with`] compares two documents.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Tolerance {
+    /// Treat dates as equal when they denote the same instant, whatever
+    /// their spelling: TOML datetimes and strings, `Z` and `+00:00`,
+    /// different offsets, or trailing zeros in fractional seconds.
+    pub dates: bool,
+
+    /// Largest absolute difference at which two numbers still count as
+    /// equal. Numbers equal by value (`1` and `1.0`) always are.
+    pub number_epsilon: f64,
+}
+
+/// Returned when two documents are not equivalent.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EquivalenceError {
+    /// One of the documents does not parse as its format.
+    Parse { format: Format, message: String },
+    /// The first difference; `expected` is from the first document and
+    /// `found` from the second.
+    Mismatch(Box<RoundTripMismatch>),
+}
+
+impl fmt::Display for EquivalenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EquivalenceError::Parse { format, message } => {
+                write!(f, "cannot parse the {format} document: {message}")
+            }
+            EquivalenceError::Mismatch(mismatch) => {
+                let show =
+                    |v: &Option<JsonValue>| v.as_ref().map_or("nothing".into(), |v| v.to_string());
+                write!(
+                    f,
+                    "documents differ at `{}`: first has {}, second has {}",
+                    shown(&mismatch.path),
+                    show(&mismatch.expected),
+                    show(&mismatch.found)
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for EquivalenceError {}
+
+/// Checks that two documents, possibly in different formats, hold the
+/// same data under the shared value model.
+///
+/// Comparison is strict apart from numbers equal by value; see
+/// [`assert_equivalent_with`] for tolerances.
+///
+/// ```
+/// use c12_parser::{Format, assert_equivalent};
+///
+/// assert_equivalent(
+///     "[server]\nport = 8080\n",
+///     Format::Toml,
+///     "server:\n  port: 8080.0\n",
+///     Format::Yaml,
+/// )?;
+/// # Ok::<(), c12_parser::EquivalenceError>(())
+/// ```
+pub fn assert_equivalent(
+    text_a: &str,
+    format_a: Format,
+    text_b: &str,
+    format_b: Format,
+) -> Result<(), EquivalenceError> {
+    assert_equivalent_with(text_a, format_a, text_b, format_b, &Tolerance::default())
+}
+
+/// Like [`assert_equivalent`], comparing under `tolerance`.
+pub fn assert_equivalent_with(
+    text_a: &str,
+    format_a: Format,
+    text_b: &str,
+    format_b: Format,
+    tolerance: &Tolerance,
+) -> Result<(), EquivalenceError> {
+    let a = parse(text_a, format_a, tolerance)?;
+    let b = parse(text_b, format_b, tolerance)?;
+    let same = |x: &JsonValue, y: &JsonValue| match (x, y) {
+        (JsonValue::Number(x), JsonValue::Number(y)) => match (x.as_f64(), y.as_f64()) {
+            (Some(x), Some(y)) => (x - y).abs() <= tolerance.number_epsilon,
+            _ => false,
+        },
+        (JsonValue::String(x), JsonValue::String(y)) if tolerance.dates => {
+            x == y || matches!((instant(x), instant(y)), (Some(x), Some(y)) if x == y)
+        }
+        _ => x == y,
+    };
+    match first_difference(&a, &b, &Path::root(), &same) {
+        Some(mismatch) => Err(EquivalenceError::Mismatch(Box::new(mismatch))),
+        None => Ok(()),
+    }
+}
+
+fn parse(text: &str, format: Format, tolerance: &Tolerance) -> Result<JsonValue, EquivalenceError> {
+    let mut value = parse_auto::<JsonValue>(text, Some(format))
+        .map_err(|e| EquivalenceError::Parse {
+            format,
+            message: e.to_string(),
+        })?
+        .value;
+    if tolerance.dates {
+        unwrap_toml_datetimes(&mut value);
+    }
+    Ok(value)
+}
+
+fn unwrap_toml_datetimes(value: &mut JsonValue) {
+    match value {
+        JsonValue::Object(map) => {
+            if map.len() == 1
+                && let Some(JsonValue::String(text)) = map.get(TOML_DATETIME)
+            {
+                *value = JsonValue::String(text.clone());
+                return;
+            }
+            map.values_mut().for_each(unwrap_toml_datetimes);
+        }
+        JsonValue::Array(items) => items.iter_mut().for_each(unwrap_toml_datetimes),
+        _ => {}
+    }
+}
+
+/// What a date or datetime string denotes: whether it has a time and an
+/// offset, seconds since the epoch (in UTC when there is an offset) and
+/// the fractional digits without trailing zeros.
+fn instant(text: &str) -> Option<(bool, bool, i64, String)> {
+    let caps = DATE_TIME.captures(text)?;
+    let num = |i: usize| {
+        caps.get(i)
+            .map_or(0, |m| m.as_str().parse::<i64>().unwrap_or(0))
+    };
+    crate::scalar::parse_date(&text[..10])?;
+    let days = days_from_civil(num(1), num(2), num(3));
+    let mut seconds = days * 86_400 + num(4) * 3_600 + num(5) * 60 + num(6);
+    let has_offset = caps.get(8).is_some() || caps.get(9).is_some();
+    if caps.get(9).is_some_and(|sign| sign.as_str() == "+") {
+        seconds -= num(10) * 3_600 + num(11) * 60;
+    } else if caps.get(9).is_some() {
+        seconds += num(10) * 3_600 + num(11) * 60;
+    }
+    let fraction = caps
+        .get(7)
+        .map_or("", |m| m.as_str().trim_end_matches('0'))
+        .to_string();
+    Some((caps.get(4).is_some(), has_offset, seconds, fraction))
+}
+
+/// Days since 1970-01-01 in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn fixtures_agree_across_the_json_family_and_yaml() {
+        for format in [Format::Json5, Format::Jsonc, Format::Yaml] {
+            assert_eq!(
+                assert_equivalent(
+                    fixtures::types(Format::Json),
+                    Format::Json,
+                    fixtures::types(format),
+                    format
+                ),
+                Ok(())
+            );
+        }
+        let err = assert_equivalent(
+            fixtures::types(Format::Json),
+            Format::Json,
+            fixtures::types(Format::Toml),
+            Format::Toml,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "documents differ at `types.null`: first has null, second has \"null\""
+        );
+    }
+
+    #[test]
+    fn dates_compare_by_instant_when_tolerated() {
+        let toml = "a = 1979-05-27T07:32:00-08:00\nb = 1979-05-27\n";
+        let yaml = "a: 1979-05-27T15:32:00.000Z\nb: '1979-05-27'\n";
+        assert!(assert_equivalent(toml, Format::Toml, yaml, Format::Yaml).is_err());
+
+        let dates = Tolerance {
+            dates: true,
+            ..Tolerance::default()
+        };
+        assert_eq!(
+            assert_equivalent_with(toml, Format::Toml, yaml, Format::Yaml, &dates),
+            Ok(())
+        );
+        let later = "a: 1979-05-27T15:32:01Z\nb: '1979-05-27'\n";
+        assert!(assert_equivalent_with(toml, Format::Toml, later, Format::Yaml, &dates).is_err());
+    }
+
+    #[test]
+    fn numbers_compare_within_epsilon() {
+        let loose = Tolerance {
+            number_epsilon: 1e-6,
+            ..Tolerance::default()
+        };
+        let (a, b) = ("{\"pi\": 3.1415926}", "pi = 3.14159265\n");
+        assert!(assert_equivalent(a, Format::Json, b, Format::Toml).is_err());
+        assert_eq!(
+            assert_equivalent_with(a, Format::Json, b, Format::Toml, &loose),
+            Ok(())
+        );
+        assert!(matches!(
+            assert_equivalent("{", Format::Json, "", Format::Toml),
+            Err(EquivalenceError::Parse {
+                format: Format::Json,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,350 @@
+use std::error::Error;
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+
+use crate::empty::{empty_value, is_empty_document};
+use crate::format::{Format, FormatOptions, Formatted};
+use crate::json::parse_json_limited;
+use crate::json5::parse_json5_limited;
+use crate::limits::{LimitExceeded, enforce_limits};
+use crate::locate::locate;
+use crate::loss::{DataLoss, parse_losses};
+use crate::repro::minimal_repro;
+use crate::toml_format::parse_toml_limited;
+use crate::yaml_format::parse_yaml_limited;
+use crate::{parse_ini_typed, parse_jsonc};
+
+/// A parse error located in the source text, with the same shape for
+/// every format.
+///
+/// Errors a backend does not tie to a position, such as a value of the
+/// wrong type after JSONC or INI parsing, point at the start of the text.
+///
+/// ```
+/// use c12_parser::{Format, parse_format};
+///
+/// let err = parse_format::<serde_json::Value>("a = 1\nb = [1,\n", Format::Toml, None)
+///     .unwrap_err();
+/// assert_eq!((err.line, err.column, err.offset), (2, 8, 13));
+/// assert_eq!(err.snippet, "b = [1,");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct C12Error {
+    pub format: Format,
+    /// The backend's message, without its own position suffix.
+    pub message: String,
+    /// Byte offset of the error in the text.
+    pub offset: usize,
+    /// 1-based line.
+    pub line: usize,
+    /// 1-based column, counted in characters.
+    pub column: usize,
+    /// The line containing the error, without its line break.
+    pub snippet: String,
+    /// What went wrong, [`Syntax`](C12ErrorKind::Syntax) for the backend's
+    /// own errors.
+    pub kind: C12ErrorKind,
+}
+
+/// What a [`C12Error`] reports. Errors this crate raises itself keep their
+/// own type here, also returned by [`Error::source`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum C12ErrorKind {
+    /// The text is not valid in its format, or does not fit the requested
+    /// type.
+    Syntax,
+    /// [`fail_on_loss`](FormatOptions::fail_on_loss) refused to drop
+    /// data. The error points at the first loss with a line.
+    DataLoss(DataLoss),
+    /// A value is over the configured
+    /// [`limits`](FormatOptions::limits). The error points at the value
+    /// when it can be found in the text.
+    LimitExceeded(Box<LimitExceeded>),
+}
+
+impl C12Error {
+    /// Locates `error`, returned by a backend parsing `text` as `format`.
+    /// Errors from `serde_json`, `json5`, `jsonc-parser`, `toml`,
+    /// `toml_edit` and `serde_yaml` carry a position; others point at the start of the
+    /// text.
+    pub fn new(format: Format, text: &str, error: &(dyn Error + 'static)) -> Self {
+        if let Some(e) = error.downcast_ref::<C12Error>() {
+            return e.clone();
+        }
+        if let Some(e) = error.downcast_ref::<DataLoss>() {
+            let line = e.losses.iter().find_map(|loss| loss.line).unwrap_or(1);
+            return Self {
+                kind: C12ErrorKind::DataLoss(e.clone()),
+                ..Self::at_line_column(format, text, line, 1, e.to_string())
+            };
+        }
+        if let Some(e) = error.downcast_ref::<LimitExceeded>() {
+            let offset = locate(text, format, &e.path).map_or(0, |at| at.offset);
+            return Self {
+                kind: C12ErrorKind::LimitExceeded(Box::new(e.clone())),
+                ..Self::at_offset(format, text, offset, e.to_string())
+            };
+        }
+        if let Some(e) = error.downcast_ref::<serde_json::Error>() {
+            let message = strip_position(&e.to_string(), e.line(), e.column());
+            return Self::at_line_column(format, text, e.line(), e.column(), message);
+        }
+        if let Some(e) = error.downcast_ref::<json5::Error>() {
+            let message = e.to_string();
+            return match e.position() {
+                Some(p) => {
+                    let message = message
+                        .strip_suffix(&format!(" at {p}"))
+                        .unwrap_or(&message)
+                        .to_string();
+                    Self::at_line_column(format, text, p.line + 1, p.column + 1, message)
+                }
+                None => Self::at_offset(format, text, 0, message),
+            };
+        }
+        if let Some(e) = error.downcast_ref::<jsonc_parser::errors::ParseError>() {
+            return Self::at_offset(format, text, e.range().start, e.kind().to_string());
+        }
+        if let Some(e) = error.downcast_ref::<toml::de::Error>() {
+            let offset = e.span().map_or(0, |span| span.start);
+            return Self::at_offset(format, text, offset, e.message().trim_end().to_string());
+        }
+        if let Some(e) = error.downcast_ref::<toml_edit::TomlError>() {
+            let offset = e.span().map_or(0, |span| span.start);
+            return Self::at_offset(format, text, offset, e.message().trim_end().to_string());
+        }
+        if let Some(e) = error.downcast_ref::<serde_yaml::Error>() {
+            return match e.location() {
+                Some(l) => {
+                    let message = strip_position(&e.to_string(), l.line(), l.column());
+                    Self::at_line_column(format, text, l.line(), l.column(), message)
+                }
+                None => Self::at_offset(format, text, 0, e.to_string()),
+            };
+        }
+        Self::at_offset(format, text, 0, error.to_string())
+    }
+
+    /// An error at byte `offset`, clamped to the text and moved back to a
+    /// character boundary.
+    pub fn at_offset(format: Format, text: &str, offset: usize, message: String) -> Self {
+        let mut offset = offset.min(text.len());
+        while !text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = text[offset..].find('\n').map_or(text.len(), |i| offset + i);
+        Self {
+            format,
+            message,
+            offset,
+            line: text[..offset].matches('\n').count() + 1,
+            column: text[line_start..offset].chars().count() + 1,
+            snippet: text[line_start..line_end]
+                .trim_end_matches('\r')
+                .to_string(),
+            kind: C12ErrorKind::Syntax,
+        }
+    }
+
+    /// An error at a 1-based `line` and `column` (in characters). Columns
+    /// past the end of the line point at its end.
+    pub fn at_line_column(
+        format: Format,
+        text: &str,
+        line: usize,
+        column: usize,
+        message: String,
+    ) -> Self {
+        let line_start = text
+            .split_inclusive('\n')
+            .take(line.saturating_sub(1))
+            .map(str::len)
+            .sum::<usize>();
+        let rest = &text[line_start.min(text.len())..];
+        let line_len = rest.find('\n').unwrap_or(rest.len());
+        let within = rest[..line_len]
+            .char_indices()
+            .nth(column.saturating_sub(1))
+            .map_or(line_len, |(i, _)| i);
+        Self::at_offset(format, text, line_start + within, message)
+    }
+
+    /// A minimal, self-contained excerpt of `text`, the document this error
+    /// came from, that fails the same way: for diagnostics and bug reports
+    /// on large files, instead of the whole document.
+    ///
+    /// The excerpt keeps the error line, a couple of lines before it and
+    /// what encloses them: the innermost JSON object or array, with the
+    /// brackets left open closed, the TOML table or INI section header, or
+    /// the YAML keys of the enclosing blocks. The cut is line based and
+    /// best effort; a value spanning many lines may be cut in two.
+    ///
+    /// ```
+    /// use c12_parser::{Format, parse_format};
+    ///
+    /// let text = "[package]\nname = \"x\"\n\n[server]\nhost = \"a\"\nport = ?\n";
+    /// let err = parse_format::<serde_json::Value>(text, Format::Toml, None).unwrap_err();
+    /// assert_eq!(err.repro(text), "[server]\nhost = \"a\"\nport = ?\n");
+    /// ```
+    pub fn repro(&self, text: &str) -> String {
+        minimal_repro(text, self)
+    }
+}
+
+impl fmt::Display for C12Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} error at line {}, column {}: {}",
+            self.format, self.line, self.column, self.message
+        )?;
+        if !self.snippet.is_empty() {
+            let pad: String = self
+                .snippet
+                .chars()
+                .take(self.column - 1)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            write!(f, "\n  | {}\n  | {pad}^", self.snippet)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for C12Error {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            C12ErrorKind::Syntax => None,
+            C12ErrorKind::DataLoss(e) => Some(e),
+            C12ErrorKind::LimitExceeded(e) => Some(&**e),
+        }
+    }
+}
+
+/// Removes the ` at line L column C` `serde_json` and `serde_yaml` put in
+/// their messages.
+fn strip_position(message: &str, line: usize, column: usize) -> String {
+    message.replacen(&format!(" at line {line} column {column}"), "", 1)
+}
+
+/// Parses `text` as `format`, reporting failures as a [`C12Error`].
+///
+/// JSONC and INI are parsed into the shared value model first, as in
+/// [`parse_auto`](crate::parse_auto), so they can fill any `T`.
+pub fn parse_format<T>(
+    text: &str,
+    format: Format,
+    options: Option<FormatOptions>,
+) -> Result<Formatted<T>, C12Error>
+where
+    T: DeserializeOwned,
+{
+    let locate = |e: &(dyn Error + 'static)| C12Error::new(format, text, e);
+    let located = |e: Box<dyn Error>| C12Error::new(format, text, &*e);
+    // Losses are checked here, before the backend, so they keep their type.
+    if options.as_ref().is_some_and(|o| o.fail_on_loss) {
+        DataLoss::check(parse_losses(text, format)).map_err(|e| locate(&e))?;
+    }
+    let parsed = match format {
+        Format::Json => parse_json_limited(text, options).map_err(|e| located(e.into()))?,
+        Format::Json5 => parse_json5_limited(text, options).map_err(|e| located(e.into()))?,
+        Format::Toml => parse_toml_limited(text, options).map_err(|e| located(e.into()))?,
+        Format::Yaml => parse_yaml_limited(text, options).map_err(|e| located(e.into()))?,
+        Format::Jsonc => {
+            let parsed = parse_jsonc(text, options, None).map_err(located)?;
+            Formatted {
+                value: serde_json::from_value(parsed.value).map_err(|e| locate(&e))?,
+                format: parsed.format,
+            }
+        }
+        Format::Ini => {
+            let opts = options.unwrap_or_default();
+            let value = if is_empty_document(text, format) {
+                empty_value::<_, serde_json::Error>(opts.on_empty).map_err(|e| locate(&e))?
+            } else {
+                let mut value = parse_ini_typed(text, None);
+                enforce_limits(&mut value, &opts.limits).map_err(|e| locate(&e))?;
+                serde_json::from_value(value).map_err(|e| locate(&e))?
+            };
+            Formatted::new(text, value, &opts)
+        }
+    };
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value as JsonValue;
+
+    #[test]
+    fn every_format_reports_line_column_and_snippet() {
+        let cases = [
+            (Format::Json, "{\n  \"a\": 1,\n  \"b\": ?\n}", (3, 8)),
+            (Format::Json5, "{\n  a: 1,\n  b: ?\n}", (3, 6)),
+            (Format::Jsonc, "{\n  // c\n  \"b\": ?\n}", (3, 8)),
+            (Format::Toml, "a = 1\nb = ?\n", (2, 5)),
+            (Format::Yaml, "a: 1\nb: [1\n", (3, 1)),
+        ];
+        for (format, text, (line, column)) in cases {
+            let err = parse_format::<JsonValue>(text, format, None).unwrap_err();
+            assert_eq!((err.line, err.column), (line, column), "{format}: {err}");
+            assert_eq!(
+                text.lines().nth(line - 1).unwrap_or(""),
+                err.snippet,
+                "{format}"
+            );
+            let position = format!("line {line} column {column}");
+            assert!(
+                !err.message.contains(&position),
+                "{format}: {}",
+                err.message
+            );
+        }
+    }
+
+    #[test]
+    fn errors_without_positions_point_at_the_start() {
+        #[derive(serde::Deserialize, Debug)]
+        struct Config {
+            #[allow(dead_code)]
+            port: u16,
+        }
+        let err = parse_format::<Config>("; app\nport = 1\n", Format::Ini, None).unwrap_err();
+        assert_eq!((err.offset, err.line, err.column), (0, 1, 1));
+        assert_eq!(
+            err.to_string(),
+            "ini error at line 1, column 1: missing field `port`\n  | ; app\n  | ^"
+        );
+    }
+
+    #[test]
+    fn data_loss_keeps_its_type() {
+        let options = FormatOptions {
+            fail_on_loss: true,
+            ..FormatOptions::default()
+        };
+        let text = "a: 1\n# note\nb: !secret x\n";
+        let err = parse_format::<JsonValue>(text, Format::Yaml, Some(options)).unwrap_err();
+        let C12ErrorKind::DataLoss(loss) = &err.kind else {
+            panic!("{err}");
+        };
+        assert_eq!(loss.losses.len(), 2);
+        assert_eq!((err.line, err.snippet.as_str()), (2, "# note"));
+        assert!(err.source().unwrap().downcast_ref::<DataLoss>().is_some());
+        assert_eq!(crate::Diagnostic::code(&err), crate::Code::DataLoss);
+    }
+
+    #[test]
+    fn offsets_and_columns_agree_on_multibyte_text() {
+        let text = "名前 = \"x\"\nvalue = ✗\n";
+        let err = C12Error::at_line_column(Format::Toml, text, 2, 9, "bad".into());
+        assert_eq!(&text[err.offset..err.offset + 3], "✗");
+        assert_eq!(
+            err,
+            C12Error::at_offset(Format::Toml, text, err.offset + 1, "bad".into())
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::Write as _;
+use std::path::Path as FsPath;
+
+use serde_json::Value as JsonValue;
+
+use crate::format::{Format, FormatOptions, Formatted};
+use crate::registry::stringify_as;
+use crate::support_bundle::{SupportBundleOptions, export_support_bundle};
+
+/// Options for [`export_effective`].
+#[derive(Clone, Debug)]
+pub struct ExportOptions {
+    /// Redact values under secret-looking keys and URL credentials, as
+    /// [`export_support_bundle`] does. Hostnames are kept.
+    pub redact_secrets: bool,
+
+    /// Where each value came from, by path (`db.host` to
+    /// `/etc/myapp/config.toml`). Written as a comment header; formats
+    /// without comments (JSON) reject a non-empty map.
+    pub provenance: BTreeMap<String, String>,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            redact_secrets: true,
+            provenance: BTreeMap::new(),
+        }
+    }
+}
+
+/// Writes the final merged config to `path` in `format`, so operators can
+/// inspect exactly what a service runs with.
+pub fn export_effective(
+    path: impl AsRef<FsPath>,
+    config: &Formatted<JsonValue>,
+    format: Format,
+    options: Option<ExportOptions>,
+) -> Result<(), Box<dyn Error>> {
+    let text = render_effective(config, format, options)?;
+    std::fs::write(path, text)?;
+    Ok(())
+}
+
+/// The text [`export_effective`] writes.
+pub fn render_effective(
+    config: &Formatted<JsonValue>,
+    format: Format,
+    options: Option<ExportOptions>,
+) -> Result<String, Box<dyn Error>> {
+    let opts = options.unwrap_or_default();
+    let redacted;
+    let config = if opts.redact_secrets {
+        let bundle_opts = SupportBundleOptions {
+            anonymize_hosts: false,
+            ..Default::default()
+        };
+        redacted = export_support_bundle(config, Some(bundle_opts)).config;
+        &redacted
+    } else {
+        config
+    };
+
+    let mut out = String::new();
+    if !opts.provenance.is_empty() {
+        let prefix = match format {
+            Format::Json => return Err("JSON cannot carry provenance comments".into()),
+            Format::Json5 | Format::Jsonc => "//",
+            Format::Toml | Format::Yaml => "#",
+            Format::Ini => ";",
+        };
+        for (path, source) in &opts.provenance {
+            let _ = writeln!(out, "{prefix} {path}: {source}");
+        }
+    }
+    out.push_str(&stringify_as(format, config, FormatOptions::default())?);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::support_bundle::REDACTED;
+    use serde_json::json;
+
+    fn effective() -> Formatted<JsonValue> {
+        let value = json!({
+            "db": { "host": "db.internal", "password": "hunter2" },
+            "port": 8080,
+        });
+        Formatted::new("", value, &FormatOptions::default())
+    }
+
+    #[test]
+    fn redacts_and_annotates_provenance() {
+        let options = ExportOptions {
+            provenance: BTreeMap::from([
+                ("db.host".into(), "/etc/myapp/config.toml".into()),
+                ("port".into(), "env MYAPP_PORT".into()),
+            ]),
+            ..Default::default()
+        };
+        let out = render_effective(&effective(), Format::Toml, Some(options)).unwrap();
+        assert_eq!(
+            out,
+            format!(
+                "# db.host: /etc/myapp/config.toml\n# port: env MYAPP_PORT\nport = 8080\n\n[db]\nhost = \"db.internal\"\npassword = \"{REDACTED}\"\n"
+            )
+        );
+    }
+
+    #[test]
+    fn json_rejects_provenance_but_exports_plainly() {
+        let options = ExportOptions {
+            provenance: BTreeMap::from([("port".into(), "default".into())]),
+            ..Default::default()
+        };
+        assert!(render_effective(&effective(), Format::Json, Some(options)).is_err());
+
+        let options = ExportOptions {
+            redact_secrets: false,
+            ..Default::default()
+        };
+        let out = render_effective(&effective(), Format::Json, Some(options)).unwrap();
+        assert!(out.contains("hunter2"));
+    }
+
+    #[test]
+    fn writes_the_file() {
+        let path = std::env::temp_dir().join(format!("c12-effective-{}.yaml", std::process::id()));
+        export_effective(&path, &effective(), Format::Yaml, None).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(written.contains("port: 8080"));
+        assert!(!written.contains("hunter2"));
+    }
+}
@@ -0,0 +1,213 @@
+//! The canonical "types" document in every supported format.
+//!
+//! One `types` table holding a boolean, an integer, a float, a string, an
+//! array, an object, a null and a date, written the way each format
+//! spells them. Contributors adding a format extend [`types`] and
+//! [`types_value`]; users checking conversions can parse [`types`] and
+//! compare against [`types_value`].
+//!
+//! ```
+//! use c12_parser::{Format, fixtures, parse_auto};
+//!
+//! for format in Format::ALL {
+//!     let parsed = parse_auto::<serde_json::Value>(fixtures::types(format), Some(format))?;
+//!     assert_eq!(parsed.value, fixtures::types_value(format), "{format}");
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use serde_json::{Value as JsonValue, json};
+
+use crate::format::Format;
+
+/// The date sample, an RFC 3339 timestamp. The shared value model has no
+/// date type, so every format carries it as a string.
+pub const DATE: &str = "1979-05-27T15:32:00.000Z";
+
+/// The float sample, written `3.14` in every fixture.
+// A plain sample value, not an approximation of PI.
+#[allow(clippy::approx_constant)]
+pub const FLOAT: f64 = 3.14;
+
+const JSON: &str = r#"{
+  "types": {
+    "boolean": true,
+    "integer": 1,
+    "float": 3.14,
+    "string": "hello",
+    "array": [
+      1,
+      2,
+      3
+    ],
+    "object": {
+      "key": "value"
+    },
+    "null": null,
+    "date": "1979-05-27T15:32:00.000Z"
+  }
+}
+"#;
+
+const JSON5: &str = r#"{
+  types: {
+    boolean: true,
+    integer: 1,
+    float: 3.14,
+    string: 'hello',
+    array: [
+      1,
+      2,
+      3,
+    ],
+    object: {
+      key: 'value',
+    },
+    null: null,
+    date: '1979-05-27T15:32:00.000Z',
+  },
+}
+"#;
+
+const JSONC: &str = r#"{
+  // The canonical types document.
+  "types": {
+    "boolean": true,
+    "integer": 1,
+    "float": 3.14,
+    "string": "hello",
+    "array": [
+      1,
+      2,
+      3
+    ],
+    "object": {
+      "key": "value"
+    },
+    "null": null,
+    "date": "1979-05-27T15:32:00.000Z"
+  }
+}
+"#;
+
+const TOML: &str = r#"[types]
+boolean = true
+integer = 1
+float = 3.14
+string = "hello"
+array = [1, 2, 3]
+null = "null"
+date = "1979-05-27T15:32:00.000Z"
+
+[types.object]
+key = "value"
+"#;
+
+const YAML: &str = r#"types:
+  boolean: true
+  integer: 1
+  float: 3.14
+  string: hello
+  array:
+    - 1
+    - 2
+    - 3
+  object:
+    key: value
+  'null': null
+  date: '1979-05-27T15:32:00.000Z'
+"#;
+
+const INI: &str = r#"[types]
+boolean = true
+integer = 1
+float = 3.14
+string = hello
+array = 1,2,3
+object.key = value
+null = null
+date = 1979-05-27T15:32:00.000Z
+"#;
+
+/// The canonical document written in `format`.
+pub fn types(format: Format) -> &'static str {
+    match format {
+        Format::Json => JSON,
+        Format::Json5 => JSON5,
+        Format::Jsonc => JSONC,
+        Format::Toml => TOML,
+        Format::Yaml => YAML,
+        Format::Ini => INI,
+    }
+}
+
+/// What [`types`] reads back to for `format`.
+///
+/// JSON, JSON5, JSONC and YAML hold the document exactly. TOML has no
+/// null, so `null` is the string `"null"`. INI has no nesting or arrays,
+/// so the array is a comma-separated string and the object a dotted key;
+/// scalars are read as with [`parse_ini_typed`](crate::parse_ini_typed).
+pub fn types_value(format: Format) -> JsonValue {
+    let mut value = json!({
+        "types": {
+            "boolean": true,
+            "integer": 1,
+            "float": FLOAT,
+            "string": "hello",
+            "array": [1, 2, 3],
+            "object": { "key": "value" },
+            "null": null,
+            "date": DATE,
+        }
+    });
+    let types = &mut value["types"];
+    match format {
+        Format::Json | Format::Json5 | Format::Jsonc | Format::Yaml => {}
+        Format::Toml => types["null"] = json!("null"),
+        Format::Ini => {
+            let map = types.as_object_mut().expect("types is an object");
+            map.insert("array".into(), json!("1,2,3"));
+            map.shift_remove("object");
+            map.shift_remove("null");
+            map.shift_remove("date");
+            map.insert("object.key".into(), json!("value"));
+            map.insert("null".into(), JsonValue::Null);
+            map.insert("date".into(), json!(DATE));
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::{parse_as, stringify_as};
+    use crate::{FormatOptions, StyleProfile, parse_auto, sniff_format};
+
+    #[test]
+    fn every_fixture_is_sniffed_and_read_back() {
+        for format in Format::ALL {
+            assert_eq!(sniff_format(types(format)), Some(format));
+            let parsed = parse_auto::<JsonValue>(types(format), None).unwrap();
+            assert_eq!(parsed.value, types_value(format), "{format}");
+        }
+    }
+
+    #[test]
+    fn json_fixtures_are_canonical_output() {
+        let confbox = || FormatOptions {
+            profile: StyleProfile::Confbox,
+            ..FormatOptions::default()
+        };
+        let parsed = parse_as(Format::Json, JSON, confbox()).unwrap();
+        assert_eq!(
+            stringify_as(Format::Json, &parsed, confbox()).unwrap(),
+            JSON
+        );
+        for format in [Format::Json, Format::Json5] {
+            let parsed = parse_as(format, types(format), FormatOptions::default()).unwrap();
+            let out = stringify_as(format, &parsed, FormatOptions::default()).unwrap();
+            assert_eq!(out, types(format), "{format}");
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use std::fmt;
+
+use serde_json::{Map, Value as JsonValue};
+
+use crate::path::{Path, PathSegment, join_key, parse_path};
+
+/// How a `.` inside a key is interpreted.
+///
+/// `a.b = 1` in TOML or INI means nesting, while `{"a.b": 1}` in JSON is a
+/// single literal key; guessing silently corrupts configs, so callers
+/// choose explicitly.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DottedKeys {
+    /// Keys are used verbatim, dots included.
+    #[default]
+    Literal,
+    /// Keys are path expressions as read by [`parse_path`]: dots separate
+    /// nested keys and a key with a dot of its own is quoted, `["a.b"]`.
+    Nested,
+}
+
+/// Returned by [`unflatten`] when two flat keys disagree about the shape
+/// of the tree, e.g. `a = 1` and `a.b = 2`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FlattenConflict {
+    pub key: String,
+}
+
+impl fmt::Display for FlattenConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "key `{}` conflicts with an existing value", self.key)
+    }
+}
+
+impl std::error::Error for FlattenConflict {}
+
+/// A key as a one-segment path expression (see [`parse_path`]), so it
+/// survives [`split_key`] unchanged: `a.b` becomes `["a.b"]`.
+pub fn escape_key(key: &str) -> String {
+    Path::root().key(key).to_string()
+}
+
+/// Splits a flat key written as a path expression into its keys. A key
+/// that is not an expression of keys alone, e.g. `a..b` or `list[0]`, is
+/// one key as written.
+pub fn split_key(key: &str) -> Vec<String> {
+    let keys = parse_path(key).ok().and_then(|path| {
+        path.segments()
+            .iter()
+            .map(|segment| match segment {
+                PathSegment::Key(key) => Some(key.clone()),
+                PathSegment::Index(_) => None,
+            })
+            .collect::<Option<Vec<_>>>()
+    });
+    match keys {
+        Some(keys) if !keys.is_empty() => keys,
+        _ => vec![key.to_string()],
+    }
+}
+
+/// Flattens nested objects into a single-level map keyed by each leaf's
+/// path expression, e.g. `a.b` or `servers["10.0.0.1"]`. Arrays and
+/// scalars are kept as leaf values.
+pub fn flatten(value: &JsonValue) -> Map<String, JsonValue> {
+    let mut out = Map::new();
+    match value {
+        JsonValue::Object(map) => flatten_into(map, "", &mut out),
+        other => {
+            out.insert(String::new(), other.clone());
+        }
+    }
+    out
+}
+
+fn flatten_into(map: &Map<String, JsonValue>, prefix: &str, out: &mut Map<String, JsonValue>) {
+    for (key, value) in map {
+        let flat = join_key(prefix, key);
+        match value {
+            JsonValue::Object(child) if !child.is_empty() => flatten_into(child, &flat, out),
+            other => {
+                out.insert(flat, other.clone());
+            }
+        }
+    }
+}
+
+/// Rebuilds a nested object from flat keys, interpreting dots according
+/// to `dotted_keys`.
+pub fn unflatten(
+    map: &Map<String, JsonValue>,
+    dotted_keys: DottedKeys,
+) -> Result<JsonValue, FlattenConflict> {
+    let mut root = Map::new();
+    for (key, value) in map {
+        let segments = match dotted_keys {
+            DottedKeys::Literal => vec![key.clone()],
+            DottedKeys::Nested => split_key(key),
+        };
+        if !insert_nested(&mut root, &segments, value.clone()) {
+            return Err(FlattenConflict { key: key.clone() });
+        }
+    }
+    Ok(JsonValue::Object(root))
+}
+
+/// Splits the dotted keys of every object in `value` into nested objects,
+/// as [`unflatten`] with [`DottedKeys::Nested`] does for a single map.
+pub(crate) fn nest_dotted_keys(value: &mut JsonValue) -> Result<(), FlattenConflict> {
+    match value {
+        JsonValue::Array(items) => items.iter_mut().try_for_each(nest_dotted_keys),
+        JsonValue::Object(map) => {
+            map.values_mut().try_for_each(nest_dotted_keys)?;
+            if map.keys().any(|key| key.contains(['.', '['])) {
+                *value = unflatten(map, DottedKeys::Nested)?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Inserts `value` at the nested `segments`, creating intermediate
+/// objects. Returns `false` when a non-object value is in the way.
+fn insert_nested(root: &mut Map<String, JsonValue>, segments: &[String], value: JsonValue) -> bool {
+    let Some((last, parents)) = segments.split_last() else {
+        return false;
+    };
+    let mut current = root;
+    for segment in parents {
+        let entry = current
+            .entry(segment.clone())
+            .or_insert_with(|| JsonValue::Object(Map::new()));
+        match entry.as_object_mut() {
+            Some(map) => current = map,
+            None => return false,
+        }
+    }
+    match (current.get_mut(last), value) {
+        (Some(JsonValue::Object(existing)), JsonValue::Object(incoming)) => {
+            existing.extend(incoming);
+        }
+        (Some(_), _) => return false,
+        (None, value) => {
+            current.insert(last.clone(), value);
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn flatten_escapes_literal_dots() {
+        let value = json!({ "a": { "b": 1 }, "a.b": 2, "list": [1, 2] });
+        let flat = flatten(&value);
+        assert_eq!(flat["a.b"], json!(1));
+        assert_eq!(flat["[\"a.b\"]"], json!(2));
+        assert_eq!(flat["list"], json!([1, 2]));
+    }
+
+    #[test]
+    fn unflatten_round_trips_with_nested_interpretation() {
+        let value = json!({ "a": { "b": 1 }, "a.b": 2, "10.0.0.1": { "port": 80 } });
+        let flat = flatten(&value);
+        assert_eq!(unflatten(&flat, DottedKeys::Nested).unwrap(), value);
+    }
+
+    #[test]
+    fn keys_are_path_expressions() {
+        assert_eq!(escape_key("10.0.0.1"), "[\"10.0.0.1\"]");
+        assert_eq!(escape_key("port"), "port");
+        assert_eq!(split_key(&escape_key("C:\\a.b")), ["C:\\a.b"]);
+        assert_eq!(split_key("a['b.c'].d"), ["a", "b.c", "d"]);
+        for verbatim in ["a..b", "list[0]", ""] {
+            assert_eq!(split_key(verbatim), [verbatim]);
+        }
+    }
+
+    #[test]
+    fn literal_interpretation_keeps_keys_verbatim() {
+        let mut flat = Map::new();
+        flat.insert("object.key".into(), json!("value"));
+        assert_eq!(
+            unflatten(&flat, DottedKeys::Literal).unwrap(),
+            json!({ "object.key": "value" })
+        );
+        assert_eq!(
+            unflatten(&flat, DottedKeys::Nested).unwrap(),
+            json!({ "object": { "key": "value" } })
+        );
+    }
+
+    #[test]
+    fn unflatten_reports_conflicting_keys() {
+        let mut flat = Map::new();
+        flat.insert("a".into(), json!(1));
+        flat.insert("a.b".into(), json!(2));
+        let err = unflatten(&flat, DottedKeys::Nested).unwrap_err();
+        assert_eq!(err.key, "a.b");
+    }
+}
@@ -1,5 +1,63 @@
+use std::fmt;
+
 use once_cell::sync::Lazy;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::empty::EmptyDocument;
+use crate::limits::Limits;
+use crate::mixed_arrays::MixedArrayStrategy;
+use crate::yaml_aliases::{AliasLimits, YamlAnchor};
+
+/// A supported config format. Serializes as its [name](Format::name).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Json,
+    Json5,
+    Jsonc,
+    Toml,
+    Yaml,
+    Ini,
+}
+
+impl Format {
+    pub const ALL: [Format; 6] = [
+        Format::Json,
+        Format::Json5,
+        Format::Jsonc,
+        Format::Toml,
+        Format::Yaml,
+        Format::Ini,
+    ];
+
+    /// The confbox name, which is also the usual file extension.
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Json5 => "json5",
+            Format::Jsonc => "jsonc",
+            Format::Toml => "toml",
+            Format::Yaml => "yaml",
+            Format::Ini => "ini",
+        }
+    }
+
+    /// Looks a format up by name or file extension, case-insensitively
+    /// (`yml` is accepted for YAML).
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name.to_ascii_lowercase().as_str() {
+            "yml" => Some(Format::Yaml),
+            name => Format::ALL.into_iter().find(|f| f.name() == name),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
 
 /// Information about formatting (indentation and outer whitespace)
 /// captured from the original text.
@@ -8,10 +66,15 @@ pub struct FormatInfo {
     pub sample: Option<String>,
     pub whitespace_start: String,
     pub whitespace_end: String,
+    /// The anchors of a YAML document, recorded by `parse_yaml` when
+    /// [`keep_yaml_anchors`](FormatOptions::keep_yaml_anchors) is set and
+    /// written back by `stringify_yaml`.
+    pub yaml_anchors: Vec<YamlAnchor>,
 }
 
 /// Options that control how formatting is detected and preserved.
 #[derive(Clone, Debug)]
+#[non_exhaustive]
 pub struct FormatOptions {
     /// Explicit indent to use when stringifying. When `None`,
     /// indentation is auto-detected from the original text (if enabled).
@@ -21,6 +84,15 @@ pub struct FormatOptions {
     /// auto-detected, even if a sample is present.
     pub preserve_indentation: bool,
 
+    /// Character to indent output with. When `None`, output is indented
+    /// with tabs if the original text is, and with spaces otherwise. YAML
+    /// does not allow tabs in indentation and always uses spaces.
+    pub indent_style: Option<IndentStyle>,
+
+    /// Columns a tab stands for, used when detecting the indent of
+    /// tab-indented text and when converting between tabs and spaces.
+    pub tab_width: usize,
+
     /// If `false`, leading and trailing whitespace around the value
     /// will not be preserved.
     pub preserve_whitespace: bool,
@@ -28,6 +100,122 @@ pub struct FormatOptions {
     /// Number of characters to sample from the start of the text
     /// when detecting indentation.
     pub sample_size: usize,
+
+    /// Maximum line width for arrays. When set, JSON and JSON5 arrays of
+    /// scalars that fit are collapsed onto one line and arrays that would
+    /// overflow the width get one element per line; TOML arrays, always
+    /// written inline, get one element per line when the `key = [...]`
+    /// line would overflow. Strings are never broken and TOML inline
+    /// tables stay on one line, as TOML 1.0 requires. YAML and INI output
+    /// has no inline collections to wrap, so it is unaffected.
+    pub line_width: Option<usize>,
+
+    /// Controls how strings are quoted and escaped on output.
+    pub escaping: EscapeOptions,
+
+    /// How arrays mixing element types are handled when emitting TOML.
+    pub mixed_arrays: MixedArrayStrategy,
+
+    /// Which emitter conventions to follow when stringifying.
+    pub profile: StyleProfile,
+
+    /// Whether multi-line JSON5 objects and arrays end with a trailing
+    /// comma. When `None`, the [`Native`](StyleProfile::Native) profile
+    /// writes them and [`Confbox`](StyleProfile::Confbox) does not.
+    pub trailing_commas: Option<bool>,
+
+    /// Reparse the output of `stringify_*` and fail if it does not read
+    /// back as the original value. Stringifying through a
+    /// [`FormatRegistry`](crate::FormatRegistry) or
+    /// [`C12Context`](crate::C12Context) fails with a typed
+    /// [`RoundTripMismatch`](crate::RoundTripMismatch), for every built-in
+    /// format; the `stringify_*` functions report its message in their
+    /// backend's error type.
+    pub verify_round_trip: bool,
+
+    /// Fail with a [`DataLoss`](crate::DataLoss) listing what would be
+    /// dropped instead of dropping it: comments when parsing JSONC or
+    /// YAML, tags when parsing YAML and nulls when stringifying TOML.
+    /// [`parse_format`](crate::parse_format) keeps it as the
+    /// [`C12ErrorKind`](crate::C12ErrorKind) of its error, and a
+    /// [`FormatRegistry`](crate::FormatRegistry) or
+    /// [`C12Context`](crate::C12Context) returns it as is; the `parse_*`
+    /// and `stringify_*` functions report its message in their backend's
+    /// error type.
+    pub fail_on_loss: bool,
+
+    /// Caps on string and array sizes enforced by the `parse_*`
+    /// functions, for untrusted input.
+    pub limits: Limits,
+
+    /// When set, `parse_yaml` rejects recursive aliases, redefined
+    /// anchors and documents whose aliases expand past the given bounds.
+    pub yaml_aliases: Option<AliasLimits>,
+
+    /// Record the anchors and aliases of YAML input when parsing, so that
+    /// stringifying writes them back instead of repeating the shared
+    /// values. Output with anchors always uses the crate's own YAML
+    /// emitter, as serde_yaml cannot write them.
+    pub keep_yaml_anchors: bool,
+
+    /// What the `parse_*` functions return for text with nothing but
+    /// whitespace and comments.
+    pub on_empty: EmptyDocument,
+
+    /// Expand `${NAME}` and `${NAME:-default}` in string values from the
+    /// process environment when parsing, as
+    /// [`interpolate_env`](crate::interpolate_env) does. A
+    /// [`C12Context`](crate::C12Context) reads them through its
+    /// [`env_var`](crate::C12Context::env_var) instead.
+    pub interpolate_env: bool,
+}
+
+/// Output conventions used by the `stringify_*` functions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StyleProfile {
+    /// The Rust backends' own output (serde_json, serde_yaml, toml) and
+    /// this crate's JSON5 printer.
+    #[default]
+    Native,
+    /// Byte-for-byte compatible with the JS confbox/c12 packages for
+    /// shared fixtures: `JSON.stringify`, `JSON5.stringify`, js-yaml's
+    /// `dump` and smol-toml's `stringify`. Useful while a project is
+    /// migrating between the two stacks.
+    Confbox,
+}
+
+/// Whether indentation is written with spaces or tabs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndentStyle {
+    Spaces,
+    Tabs,
+}
+
+/// Quote character used for strings in formats that accept both.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuoteStyle {
+    Double,
+    Single,
+}
+
+/// Options that control string quoting and escaping on output, so
+/// emitted files can follow an organization's conventions.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EscapeOptions {
+    /// Escape non-ASCII characters as `\uXXXX` in JSON and JSON5 output
+    /// instead of writing raw UTF-8.
+    pub escape_unicode: bool,
+
+    /// Escape forward slashes as `\/` in JSON and JSON5 output.
+    pub escape_slash: bool,
+
+    /// Preferred quote character for JSON5 strings and quoted YAML
+    /// scalars. When `None`, each backend's default is kept.
+    pub quote_style: Option<QuoteStyle>,
+
+    /// Write TOML strings as literal (`'...'`) strings whenever their
+    /// content allows it.
+    pub prefer_literal_strings: bool,
 }
 
 impl Default for FormatOptions {
@@ -35,12 +223,34 @@ impl Default for FormatOptions {
         Self {
             indent: None,
             preserve_indentation: true,
+            indent_style: None,
+            tab_width: 4,
             preserve_whitespace: true,
             sample_size: 1024,
+            line_width: None,
+            escaping: EscapeOptions::default(),
+            mixed_arrays: MixedArrayStrategy::default(),
+            profile: StyleProfile::default(),
+            trailing_commas: None,
+            verify_round_trip: false,
+            fail_on_loss: false,
+            limits: Limits::default(),
+            yaml_aliases: None,
+            keep_yaml_anchors: false,
+            on_empty: EmptyDocument::default(),
+            interpolate_env: false,
         }
     }
 }
 
+impl FormatOptions {
+    /// Whether the `parse_*` functions can deserialize straight into the
+    /// target type, with no limits to check and no strings to expand.
+    pub(crate) fn parses_directly(&self) -> bool {
+        self.limits.is_unbounded() && !self.interpolate_env
+    }
+}
+
 pub(crate) fn detect_format(text: &str, opts: &FormatOptions) -> FormatInfo {
     let sample = if opts.indent.is_none() && opts.preserve_indentation {
         Some(text.chars().take(opts.sample_size).collect::<String>())
@@ -72,6 +282,7 @@ pub(crate) fn detect_format(text: &str, opts: &FormatOptions) -> FormatInfo {
         sample,
         whitespace_start,
         whitespace_end,
+        yaml_anchors: Vec::new(),
     }
 }
 
@@ -82,12 +293,16 @@ pub(crate) fn compute_indent(info: &FormatInfo, opts: &FormatOptions) -> usize {
 
     if let Some(sample) = &info.sample {
         // Naive indent detection: find the first non-empty line and
-        // count its leading spaces.
+        // count its leading spaces, or its tabs in `tab_width` columns.
         for line in sample.lines() {
             let trimmed = line.trim_start();
             if trimmed.is_empty() {
                 continue;
             }
+            let tabs = line.len() - line.trim_start_matches('\t').len();
+            if tabs > 0 {
+                return tabs * opts.tab_width;
+            }
             let indent_len = line.len() - trimmed.len();
             if indent_len > 0 {
                 return indent_len;
@@ -99,6 +314,27 @@ pub(crate) fn compute_indent(info: &FormatInfo, opts: &FormatOptions) -> usize {
     2
 }
 
+/// The indent character for output: the configured one, or tabs when
+/// no indent is set and the first indented line of the sample starts
+/// with a tab.
+pub(crate) fn indent_style(info: &FormatInfo, opts: &FormatOptions) -> IndentStyle {
+    if let Some(style) = opts.indent_style {
+        return style;
+    }
+    let tabbed = info.sample.as_deref().is_some_and(|sample| {
+        sample
+            .lines()
+            .find(|line| line.starts_with([' ', '\t']) && !line.trim().is_empty())
+            .is_some_and(|line| line.starts_with('\t'))
+    });
+    // An explicit indent asks for that many spaces.
+    if tabbed && opts.indent.is_none() {
+        IndentStyle::Tabs
+    } else {
+        IndentStyle::Spaces
+    }
+}
+
 /// A value bundled with its detected formatting information.
 #[derive(Clone, Debug)]
 pub struct Formatted<T> {
@@ -150,6 +386,7 @@ mod tests {
             sample: Some("  key: 1".into()),
             whitespace_start: String::new(),
             whitespace_end: String::new(),
+            yaml_anchors: Vec::new(),
         };
         let mut opts = FormatOptions::default();
         opts.indent = Some(4);
@@ -163,6 +400,7 @@ mod tests {
             sample: Some("  key: 1\n    child: 2".into()),
             whitespace_start: String::new(),
             whitespace_end: String::new(),
+            yaml_anchors: Vec::new(),
         };
         let opts = FormatOptions::default();
 
@@ -175,9 +413,31 @@ mod tests {
             sample: Some("\n\n".into()),
             whitespace_start: String::new(),
             whitespace_end: String::new(),
+            yaml_anchors: Vec::new(),
         };
         let opts = FormatOptions::default();
 
         assert_eq!(compute_indent(&info, &opts), 2);
     }
+
+    #[test]
+    fn tab_indented_samples_map_to_tab_width() {
+        let info = FormatInfo {
+            sample: Some("{\n\t\"a\": {\n\t\t\"b\": 1".into()),
+            whitespace_start: String::new(),
+            whitespace_end: String::new(),
+            yaml_anchors: Vec::new(),
+        };
+        let opts = FormatOptions::default();
+        assert_eq!(compute_indent(&info, &opts), 4);
+        assert_eq!(indent_style(&info, &opts), IndentStyle::Tabs);
+
+        let spaces = FormatOptions {
+            indent_style: Some(IndentStyle::Spaces),
+            tab_width: 2,
+            ..Default::default()
+        };
+        assert_eq!(compute_indent(&info, &spaces), 2);
+        assert_eq!(indent_style(&info, &spaces), IndentStyle::Spaces);
+    }
 }
@@ -0,0 +1,189 @@
+use std::error::Error;
+
+use serde_json::Value as JsonValue;
+
+use crate::empty::EmptyDocument;
+use crate::error::{C12Error, parse_format};
+use crate::format::{Format, FormatOptions, Formatted};
+use crate::registry::stringify_as;
+
+/// Front matter at the start of a Markdown or template file, found by
+/// [`parse_front_matter`].
+#[derive(Clone, Debug)]
+pub struct FrontMatter {
+    pub format: Format,
+    /// The opening delimiter line with its line break, e.g. `---\n` or
+    /// `---toml\n`. Empty for JSON front matter written as a bare object.
+    pub open: String,
+    /// The closing delimiter line with its line break, if it has one.
+    /// Empty for JSON front matter written as a bare object.
+    pub close: String,
+    pub matter: Formatted<JsonValue>,
+    /// Everything after the front matter.
+    pub body: String,
+}
+
+/// Detects and parses front matter: YAML between `---` lines, TOML
+/// between `+++` lines, or a JSON object starting the file and ending
+/// with a `}` line of its own. An opening `---` may name the format, as in
+/// `---toml` or `---json`, and then also closes with `---`.
+///
+/// Returns `None` when `text` does not start with front matter or it is
+/// never closed, as with a Markdown rule on the first line. Parse errors
+/// are located in `text` itself. Empty front matter is an empty object.
+///
+/// ```
+/// use c12_parser::{Format, parse_front_matter, stringify_front_matter};
+///
+/// let text = "+++\ntitle = \"Hello\"\ndraft = true\n+++\n# Hello\n";
+/// let mut front = parse_front_matter(text)?.unwrap();
+/// assert_eq!(front.format, Format::Toml);
+/// assert_eq!(front.matter.value["title"], "Hello");
+/// assert_eq!(front.body, "# Hello\n");
+///
+/// front.matter.value["draft"] = false.into();
+/// assert_eq!(
+///     stringify_front_matter(&front, None)?,
+///     "+++\ntitle = \"Hello\"\ndraft = false\n+++\n# Hello\n"
+/// );
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn parse_front_matter(text: &str) -> Result<Option<FrontMatter>, C12Error> {
+    let Some((format, open_len, matter_end, close_len)) = split(text) else {
+        return Ok(None);
+    };
+    let matter = &text[open_len..matter_end];
+    let opts = FormatOptions {
+        on_empty: EmptyDocument::Default,
+        ..FormatOptions::default()
+    };
+    let parsed = parse_format::<JsonValue>(matter, format, Some(opts))
+        .map_err(|e| C12Error::at_offset(format, text, open_len + e.offset, e.message))?;
+    Ok(Some(FrontMatter {
+        format,
+        open: text[..open_len].to_string(),
+        close: text[matter_end..matter_end + close_len].to_string(),
+        matter: parsed,
+        body: text[matter_end + close_len..].to_string(),
+    }))
+}
+
+/// Writes front matter back in front of its body, between the original
+/// delimiters and with the original line breaks. Comments in the front
+/// matter are not kept.
+pub fn stringify_front_matter(
+    front: &FrontMatter,
+    options: Option<FormatOptions>,
+) -> Result<String, Box<dyn Error>> {
+    let matter = stringify_as(front.format, &front.matter, options.unwrap_or_default())?;
+    let eol = if front.open.ends_with("\r\n") || front.close.ends_with("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let matter = matter.trim().replace("\r\n", "\n").replace('\n', eol);
+    let mut out = String::with_capacity(front.open.len() + matter.len() + front.body.len() + 8);
+    out.push_str(&front.open);
+    out.push_str(&matter);
+    // A bare JSON object has no closing line; the line break after its
+    // `}` starts the body.
+    if !front.close.is_empty() {
+        out.push_str(eol);
+        out.push_str(&front.close);
+    }
+    out.push_str(&front.body);
+    Ok(out)
+}
+
+/// Finds the front matter in `text`: its format, the length of the
+/// opening line, where the matter ends and the length of the closing line.
+fn split(text: &str) -> Option<(Format, usize, usize, usize)> {
+    let first = text.split_inclusive('\n').next()?;
+    let marker = first.trim_end();
+    let (format, close) = match marker {
+        "---" => (Format::Yaml, "---"),
+        "+++" => (Format::Toml, "+++"),
+        _ if marker.starts_with('{') => return json_object(text),
+        _ => {
+            let hint = marker.strip_prefix("---")?;
+            (Format::from_name(hint.trim())?, "---")
+        }
+    };
+    if !first.ends_with('\n') {
+        return None;
+    }
+    let mut offset = first.len();
+    for line in text[first.len()..].split_inclusive('\n') {
+        if line.trim_end() == close {
+            let close_len = line.len();
+            return Some((format, first.len(), offset, close_len));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// A bare JSON object, ended by the first line holding only `}`.
+fn json_object(text: &str) -> Option<(Format, usize, usize, usize)> {
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        offset += line.len();
+        if line.trim_end() == "}" {
+            let end = offset - (line.len() - line.trim_end().len());
+            return Some((Format::Json, 0, end, 0));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reads_each_delimiter_style() {
+        let yaml = "---\r\ntitle: Post\r\ntags: [a, b]\r\n---\r\nBody\r\n";
+        let front = parse_front_matter(yaml).unwrap().unwrap();
+        assert_eq!(front.format, Format::Yaml);
+        assert_eq!(
+            front.matter.value,
+            json!({ "title": "Post", "tags": ["a", "b"] })
+        );
+        assert_eq!(front.body, "Body\r\n");
+
+        let json = "{\n  \"title\": \"Post\"\n}\nBody\n";
+        let front = parse_front_matter(json).unwrap().unwrap();
+        assert_eq!(front.format, Format::Json);
+        assert_eq!(front.body, "\nBody\n");
+        assert_eq!(stringify_front_matter(&front, None).unwrap(), json);
+
+        let hinted = "---toml\nn = 1\n---\n";
+        let front = parse_front_matter(hinted).unwrap().unwrap();
+        assert_eq!(front.format, Format::Toml);
+        assert_eq!(front.matter.value, json!({ "n": 1 }));
+        assert_eq!(stringify_front_matter(&front, None).unwrap(), hinted);
+
+        let empty = parse_front_matter("---\n---\nx").unwrap().unwrap();
+        assert_eq!(empty.matter.value, json!({}));
+    }
+
+    #[test]
+    fn skips_text_without_front_matter_and_locates_errors() {
+        assert!(parse_front_matter("# Title\n---\n").unwrap().is_none());
+        assert!(
+            parse_front_matter("---\nno closing line\n")
+                .unwrap()
+                .is_none()
+        );
+        assert!(parse_front_matter("").unwrap().is_none());
+
+        let err = parse_front_matter("---\ntitle: ok\nbad: [1\n---\nBody\n").unwrap_err();
+        assert_eq!(err.format, Format::Yaml);
+        assert!(err.line >= 3, "{err}");
+
+        let yaml = "---\r\ntitle: Post\r\n---\r\nBody\r\n";
+        let front = parse_front_matter(yaml).unwrap().unwrap();
+        assert_eq!(stringify_front_matter(&front, None).unwrap(), yaml);
+    }
+}
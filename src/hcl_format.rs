@@ -0,0 +1,130 @@
+use serde::de::DeserializeOwned;
+
+use crate::empty::{empty_value, only_comments};
+use crate::format::{FormatOptions, Formatted};
+use crate::interpolate::prepared;
+
+/// Parses HCL2 text, e.g. a Terraform file, into a value, capturing outer
+/// whitespace only.
+///
+/// Attributes become keys and blocks nest under their type and then each
+/// label, so `resource "aws_instance" "web" { ... }` reads as
+/// `resource.aws_instance.web`. Expressions that are not literals, such
+/// as `var.region` or function calls, are kept as `${...}` template
+/// strings rather than evaluated.
+///
+/// ```
+/// use c12_parser::parse_hcl;
+/// use serde_json::Value;
+///
+/// let text = r#"
+/// region = "eu-west-1"
+///
+/// resource "aws_instance" "web" {
+///   count = 2
+///   ami   = var.ami
+/// }
+/// "#;
+/// let parsed = parse_hcl::<Value>(text, None)?;
+/// assert_eq!(parsed.value["region"], "eu-west-1");
+/// assert_eq!(parsed.value["resource"]["aws_instance"]["web"]["count"], 2);
+/// assert_eq!(parsed.value["resource"]["aws_instance"]["web"]["ami"], "${var.ami}");
+/// # Ok::<(), hcl::Error>(())
+/// ```
+pub fn parse_hcl<T>(text: &str, options: Option<FormatOptions>) -> Result<Formatted<T>, hcl::Error>
+where
+    T: DeserializeOwned,
+{
+    let mut opts = options.unwrap_or_default();
+    // Like TOML, HCL output is never re-indented from a sample.
+    opts.preserve_indentation = false;
+    let value = if only_comments(text, &["#", "//"], true) {
+        empty_value::<_, hcl::Error>(opts.on_empty)?
+    } else if opts.parses_directly() {
+        hcl::from_str(text)?
+    } else {
+        prepared::<_, hcl::Error>(hcl::from_str(text)?, &opts)?
+    };
+    Ok(Formatted::new(text, value, &opts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::limits::Limits;
+    use serde::Deserialize;
+    use serde_json::{Value as JsonValue, json};
+
+    const MAIN_TF: &str = r#"
+# Inputs
+variable "region" {
+  type    = string
+  default = "eu-west-1"
+}
+
+variable "zones" {
+  default = ["a", "b"]
+}
+
+provider "aws" {
+  region = var.region
+}
+
+locals {
+  name    = "web-${var.region}"
+  enabled = true
+  ratio   = 0.5
+  tags    = { team = "platform" }
+}
+"#;
+
+    #[test]
+    fn blocks_nest_under_their_labels() {
+        let parsed = parse_hcl::<JsonValue>(MAIN_TF, None).unwrap();
+        assert_eq!(
+            parsed.value["variable"],
+            json!({
+                "region": { "type": "${string}", "default": "eu-west-1" },
+                "zones": { "default": ["a", "b"] }
+            })
+        );
+        assert_eq!(parsed.value["provider"]["aws"]["region"], "${var.region}");
+        assert_eq!(
+            parsed.value["locals"],
+            json!({
+                "name": "web-${var.region}",
+                "enabled": true,
+                "ratio": 0.5,
+                "tags": { "team": "platform" }
+            })
+        );
+        assert_eq!(parsed.format.whitespace_start, "\n");
+    }
+
+    #[test]
+    fn reads_into_typed_structs_and_reports_errors() {
+        #[derive(Deserialize)]
+        struct Locals {
+            name: String,
+            enabled: bool,
+        }
+        #[derive(Deserialize)]
+        struct Config {
+            locals: Locals,
+        }
+        let config = parse_hcl::<Config>(MAIN_TF, None).unwrap().value;
+        assert_eq!(config.locals.name, "web-${var.region}");
+        assert!(config.locals.enabled);
+
+        assert!(parse_hcl::<JsonValue>("locals {\n  a = \n}\n", None).is_err());
+        let limits = FormatOptions {
+            limits: Limits {
+                max_array_len: Some(1),
+                ..Limits::default()
+            },
+            ..FormatOptions::default()
+        };
+        let err = parse_hcl::<JsonValue>(MAIN_TF, Some(limits)).unwrap_err();
+        assert!(err.to_string().contains("variable.zones.default"), "{err}");
+    }
+}
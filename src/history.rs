@@ -0,0 +1,277 @@
+use std::fmt;
+
+use serde_json::Value as JsonValue;
+
+use crate::document::{Document, DocumentEditError};
+use crate::path::Path;
+
+/// A structural edit recorded by a [`DocumentHistory`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum DocumentEdit {
+    Set {
+        path: Path,
+        value: JsonValue,
+    },
+    Remove {
+        path: Path,
+    },
+    InsertAfter {
+        after: Path,
+        key: String,
+        value: JsonValue,
+    },
+}
+
+/// `set path = value`, `remove path` or `insert key after path`, for
+/// listing the history in an editor.
+impl fmt::Display for DocumentEdit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DocumentEdit::Set { path, value } => write!(f, "set {path} = {value}"),
+            DocumentEdit::Remove { path } => write!(f, "remove {path}"),
+            DocumentEdit::InsertAfter { after, key, value } => {
+                write!(f, "insert {key} = {value} after {after}")
+            }
+        }
+    }
+}
+
+/// A [`Document`] that records its edits so they can be undone and
+/// redone, for editors built on this crate.
+///
+/// Each edit keeps the document as it was before, so undoing restores the
+/// text byte for byte, comments and layout included. That costs a copy
+/// of the document per edit, so only the latest
+/// [`DEFAULT_LIMIT`](Self::DEFAULT_LIMIT) edits are kept unless
+/// [`limit`](Self::limit) says otherwise. Edits that fail or change
+/// nothing are not recorded, and a new edit clears the redo stack.
+///
+/// ```
+/// use c12_parser::{DocumentHistory, Format, Path, parse_document};
+///
+/// let doc = parse_document("port = 8080 # public\n", Format::Toml)?;
+/// let mut history = DocumentHistory::new(doc);
+/// history.set(&Path::root().key("port"), 9090.into())?;
+/// history.remove(&Path::root().key("port"));
+/// assert_eq!(history.to_string(), "");
+///
+/// history.undo();
+/// assert_eq!(history.to_string(), "port = 9090 # public\n");
+/// history.undo();
+/// assert_eq!(history.to_string(), "port = 8080 # public\n");
+/// assert_eq!(history.redo().unwrap().to_string(), "set port = 9090");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct DocumentHistory {
+    document: Document,
+    undo: Vec<(DocumentEdit, Document)>,
+    redo: Vec<(DocumentEdit, Document)>,
+    limit: usize,
+}
+
+impl DocumentHistory {
+    /// How many edits a new history keeps to undo.
+    pub const DEFAULT_LIMIT: usize = 100;
+
+    /// Starts recording edits to `document`, keeping the latest
+    /// [`DEFAULT_LIMIT`](Self::DEFAULT_LIMIT).
+    pub fn new(document: Document) -> Self {
+        Self {
+            document,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            limit: Self::DEFAULT_LIMIT,
+        }
+    }
+
+    /// Keeps at most `edits` edits to undo, forgetting the oldest first.
+    pub fn limit(mut self, edits: usize) -> Self {
+        self.limit = edits;
+        self.trim();
+        self
+    }
+
+    /// The document as edited so far.
+    pub fn document(&self) -> &Document {
+        &self.document
+    }
+
+    /// Stops recording and returns the document.
+    pub fn into_document(self) -> Document {
+        self.document
+    }
+
+    /// The edits [`undo`](Self::undo) would take back, oldest first.
+    pub fn undo_stack(&self) -> impl Iterator<Item = &DocumentEdit> {
+        self.undo.iter().map(|(edit, _)| edit)
+    }
+
+    /// The edits [`redo`](Self::redo) would apply again, next first.
+    pub fn redo_stack(&self) -> impl Iterator<Item = &DocumentEdit> {
+        self.redo.iter().rev().map(|(edit, _)| edit)
+    }
+
+    /// Sets the value at `path`, as [`Document::set`].
+    pub fn set(&mut self, path: &Path, value: JsonValue) -> Result<(), DocumentEditError> {
+        let before = self.document.clone();
+        self.document.set(path, value.clone())?;
+        let edit = DocumentEdit::Set {
+            path: path.clone(),
+            value,
+        };
+        self.record(edit, before);
+        Ok(())
+    }
+
+    /// Removes the value at `path`, as [`Document::remove`].
+    pub fn remove(&mut self, path: &Path) -> Option<JsonValue> {
+        let before = self.document.clone();
+        let removed = self.document.remove(path)?;
+        self.record(DocumentEdit::Remove { path: path.clone() }, before);
+        Some(removed)
+    }
+
+    /// Inserts `key` after `after`, as [`Document::insert_after`].
+    pub fn insert_after(
+        &mut self,
+        after: &Path,
+        key: &str,
+        value: JsonValue,
+    ) -> Result<(), DocumentEditError> {
+        let before = self.document.clone();
+        self.document.insert_after(after, key, value.clone())?;
+        let edit = DocumentEdit::InsertAfter {
+            after: after.clone(),
+            key: key.to_string(),
+            value,
+        };
+        self.record(edit, before);
+        Ok(())
+    }
+
+    /// Takes back the latest edit, returning it, or `None` when there is
+    /// nothing to undo.
+    pub fn undo(&mut self) -> Option<&DocumentEdit> {
+        let (edit, before) = self.undo.pop()?;
+        let after = std::mem::replace(&mut self.document, before);
+        self.redo.push((edit, after));
+        self.redo.last().map(|(edit, _)| edit)
+    }
+
+    /// Applies the latest undone edit again, returning it, or `None` when
+    /// there is nothing to redo.
+    pub fn redo(&mut self) -> Option<&DocumentEdit> {
+        let (edit, after) = self.redo.pop()?;
+        let before = std::mem::replace(&mut self.document, after);
+        self.undo.push((edit, before));
+        self.undo.last().map(|(edit, _)| edit)
+    }
+
+    fn record(&mut self, edit: DocumentEdit, before: Document) {
+        if before.to_string() == self.document.to_string() {
+            return;
+        }
+        self.undo.push((edit, before));
+        self.redo.clear();
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        if self.undo.len() > self.limit {
+            self.undo.drain(..self.undo.len() - self.limit);
+        }
+    }
+}
+
+impl fmt::Display for DocumentHistory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.document.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::parse_document;
+    use crate::format::Format;
+    use serde_json::json;
+
+    const YAML: &str = "# app\nname: web\nport: 8080 # public\n";
+
+    fn history() -> DocumentHistory {
+        DocumentHistory::new(parse_document(YAML, Format::Yaml).unwrap())
+    }
+
+    #[test]
+    fn undo_and_redo_restore_the_exact_text() {
+        let mut history = history();
+        let port = Path::root().key("port");
+        history.set(&port, json!(9090)).unwrap();
+        history
+            .insert_after(&port, "host", json!("0.0.0.0"))
+            .unwrap();
+        history.remove(&Path::root().key("name"));
+        let edited = history.to_string();
+        assert_eq!(edited, "# app\nport: 9090 # public\nhost: 0.0.0.0\n");
+
+        let undone: Vec<_> =
+            std::iter::from_fn(|| history.undo().map(ToString::to_string)).collect();
+        assert_eq!(
+            undone,
+            [
+                "remove name",
+                "insert host = \"0.0.0.0\" after port",
+                "set port = 9090"
+            ]
+        );
+        assert_eq!(history.to_string(), YAML);
+        assert!(history.undo().is_none());
+
+        while history.redo().is_some() {}
+        assert_eq!(history.to_string(), edited);
+    }
+
+    #[test]
+    fn new_edits_clear_redo_and_no_ops_are_skipped() {
+        let mut history = history();
+        let port = Path::root().key("port");
+        history.set(&port, json!(1)).unwrap();
+        history.undo();
+        assert_eq!(history.redo_stack().count(), 1);
+        history.set(&port, json!(2)).unwrap();
+        assert_eq!(history.redo_stack().count(), 0);
+
+        history.set(&port, json!(2)).unwrap();
+        assert!(history.remove(&Path::root().key("missing")).is_none());
+        assert_eq!(history.undo_stack().count(), 1);
+    }
+
+    #[test]
+    fn the_limit_forgets_the_oldest_edits() {
+        let mut history = history().limit(2);
+        for port in 1..=3 {
+            history.set(&Path::root().key("port"), json!(port)).unwrap();
+        }
+        let stack: Vec<_> = history.undo_stack().map(ToString::to_string).collect();
+        assert_eq!(stack, ["set port = 2", "set port = 3"]);
+        while history.undo().is_some() {}
+        assert_eq!(history.to_string(), "# app\nname: web\nport: 1 # public\n");
+
+        let mut history = self::history();
+        for port in 0..=DocumentHistory::DEFAULT_LIMIT {
+            history.set(&Path::root().key("port"), json!(port)).unwrap();
+        }
+        assert_eq!(history.undo_stack().count(), DocumentHistory::DEFAULT_LIMIT);
+    }
+
+    #[test]
+    fn failed_edits_keep_their_type() {
+        let mut history = history();
+        let err = history
+            .set(&Path::root().key("name").key("first"), json!("a"))
+            .unwrap_err();
+        assert!(matches!(err, DocumentEditError::Yaml(_)), "{err:?}");
+        assert_eq!(history.undo_stack().count(), 0);
+    }
+}
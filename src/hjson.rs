@@ -0,0 +1,633 @@
+use std::fmt;
+
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::{Map, Number, Value as JsonValue};
+
+use crate::empty::{empty_value, only_comments};
+use crate::format::{FormatOptions, Formatted, compute_indent};
+use crate::interpolate::prepared;
+use crate::json::stringify_json;
+use crate::locate::Location;
+use crate::roundtrip::guard;
+
+/// Returned by [`parse_hjson`] for malformed text and by
+/// [`stringify_hjson`] when the value cannot be written.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HjsonError {
+    /// 1-based line of the problem when parsing.
+    pub line: Option<usize>,
+    /// 1-based column, counted in characters.
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for HjsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, "{} at {line}:{column}", self.message),
+            _ => f.write_str(&self.message),
+        }
+    }
+}
+
+impl std::error::Error for HjsonError {}
+
+impl serde::de::Error for HjsonError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self {
+            line: None,
+            column: None,
+            message: msg.to_string(),
+        }
+    }
+}
+
+impl serde::ser::Error for HjsonError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        <Self as serde::de::Error>::custom(msg)
+    }
+}
+
+/// What [`stringify_hjson`] writes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HjsonOutput {
+    /// Hjson: unquoted keys and strings where they read back the same,
+    /// `'''` blocks for multi-line strings and no commas.
+    #[default]
+    Hjson,
+    /// Plain JSON, as [`stringify_json`] writes it, for handing a
+    /// human-edited file to tools that only read JSON.
+    Json,
+}
+
+/// Parses Hjson text into a value, capturing its formatting.
+///
+/// Supports `#`, `//` and `/* */` comments, optional commas, unquoted keys,
+/// quoteless strings (running to the end of the line), single- and
+/// double-quoted strings, `'''` multi-line strings with their indentation
+/// removed and a root object without braces. As in Hjson, a quoteless
+/// value is `true`, `false`, `null` or a number only when nothing but a
+/// separator or comment follows it on its line, so `3 times` is a string.
+///
+/// ```
+/// use c12_parser::parse_hjson;
+///
+/// let text = "
+/// // server settings
+/// host: example.com
+/// port: 8080
+/// motd:
+///   '''
+///   Welcome,
+///     friend
+///   '''
+/// ports: [80, 443]
+/// ";
+/// let parsed = parse_hjson::<serde_json::Value>(text, None)?;
+/// assert_eq!(parsed.value["host"], "example.com");
+/// assert_eq!(parsed.value["port"], 8080);
+/// assert_eq!(parsed.value["motd"], "Welcome,\n  friend");
+/// assert_eq!(parsed.value["ports"], serde_json::json!([80, 443]));
+/// # Ok::<(), c12_parser::HjsonError>(())
+/// ```
+pub fn parse_hjson<T>(
+    text: &str,
+    options: Option<FormatOptions>,
+) -> Result<Formatted<T>, HjsonError>
+where
+    T: DeserializeOwned,
+{
+    let opts = options.unwrap_or_default();
+    if only_comments(text, &["#", "//"], true) {
+        return Ok(Formatted::new(text, empty_value(opts.on_empty)?, &opts));
+    }
+    let value = hjson_to_value(text)?;
+    let value = if opts.parses_directly() {
+        serde_json::from_value(value).map_err(<HjsonError as serde::de::Error>::custom)?
+    } else {
+        prepared(value, &opts)?
+    };
+    Ok(Formatted::new(text, value, &opts))
+}
+
+/// Stringifies a value as Hjson or, with [`HjsonOutput::Json`], as JSON,
+/// with preserved or configured indentation. Comments are not kept.
+///
+/// ```
+/// use c12_parser::{HjsonOutput, parse_hjson, stringify_hjson};
+///
+/// let parsed = parse_hjson::<serde_json::Value>("name: my app\nport: 80\n", None)?;
+/// assert_eq!(
+///     stringify_hjson(&parsed, None, HjsonOutput::Hjson)?,
+///     "{\n  name: my app\n  port: 80\n}\n"
+/// );
+/// assert_eq!(
+///     stringify_hjson(&parsed, None, HjsonOutput::Json)?,
+///     "{\n  \"name\": \"my app\",\n  \"port\": 80\n}\n"
+/// );
+/// # Ok::<(), c12_parser::HjsonError>(())
+/// ```
+pub fn stringify_hjson<T>(
+    formatted: &Formatted<T>,
+    options: Option<FormatOptions>,
+    output: HjsonOutput,
+) -> Result<String, HjsonError>
+where
+    T: Serialize,
+{
+    if output == HjsonOutput::Json {
+        return stringify_json(formatted, options)
+            .map_err(<HjsonError as serde::ser::Error>::custom);
+    }
+    let opts = options.unwrap_or_default();
+    let value = serde_json::to_value(&formatted.value)
+        .map_err(<HjsonError as serde::ser::Error>::custom)?;
+    let indent = " ".repeat(compute_indent(&formatted.format, &opts).max(1));
+    let mut body = String::new();
+    write_value(&mut body, &value, &indent, 0);
+
+    let out = format!(
+        "{}{}{}",
+        formatted.format.whitespace_start, body, formatted.format.whitespace_end
+    );
+    if opts.verify_round_trip {
+        guard::<_, HjsonError>(&formatted.value, &out, |text| {
+            hjson_to_value(text).map_err(|e| e.to_string())
+        })?;
+    }
+    Ok(out)
+}
+
+/// Parses Hjson text into a value; empty text is an empty object.
+fn hjson_to_value(text: &str) -> Result<JsonValue, HjsonError> {
+    let mut parser = Parser { text, pos: 0 };
+    parser.skip_space()?;
+    match parser.peek() {
+        None => Ok(JsonValue::Object(Map::new())),
+        Some('{' | '[') => parser.root_value(),
+        Some(_) => {
+            // A root object may omit its braces; anything else is a
+            // single value.
+            let start = parser.pos;
+            parser.members(None).map(JsonValue::Object).or_else(|e| {
+                parser.pos = start;
+                parser.root_value().map_err(|_| e)
+            })
+        }
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: impl Into<String>) -> HjsonError {
+        let location = Location::of_offset(self.text, self.pos);
+        HjsonError {
+            line: Some(location.line),
+            column: Some(location.column),
+            message: message.into(),
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    /// Skips whitespace, line breaks and comments.
+    fn skip_space(&mut self) -> Result<(), HjsonError> {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if trimmed.starts_with('#') || trimmed.starts_with("//") {
+                self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else if let Some(comment) = trimmed.strip_prefix("/*") {
+                let end = comment
+                    .find("*/")
+                    .ok_or_else(|| self.error("unterminated block comment"))?;
+                self.pos += end + 4;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    /// A value that must be followed only by whitespace and comments.
+    fn root_value(&mut self) -> Result<JsonValue, HjsonError> {
+        let value = self.value()?;
+        self.skip_space()?;
+        if self.pos < self.text.len() {
+            return Err(self.error("unexpected text after the value"));
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<JsonValue, HjsonError> {
+        match self.peek() {
+            Some('{') => {
+                self.pos += 1;
+                self.members(Some('}')).map(JsonValue::Object)
+            }
+            Some('[') => {
+                self.pos += 1;
+                self.elements()
+            }
+            Some('\'') if self.rest().starts_with("'''") => self.multiline().map(JsonValue::String),
+            Some(quote @ ('"' | '\'')) => {
+                self.pos += 1;
+                self.quoted(quote).map(JsonValue::String)
+            }
+            Some(c @ ('}' | ']' | ',' | ':')) => Err(self.error(format!("unexpected `{c}`"))),
+            Some(_) => Ok(self.quoteless()),
+            None => Err(self.error("expected a value")),
+        }
+    }
+
+    /// Object members up to `close`, or to the end of the text for a root
+    /// object without braces.
+    fn members(&mut self, close: Option<char>) -> Result<Map<String, JsonValue>, HjsonError> {
+        let mut map = Map::new();
+        loop {
+            self.skip_space()?;
+            match self.peek() {
+                Some(c) if Some(c) == close => {
+                    self.pos += 1;
+                    return Ok(map);
+                }
+                None if close.is_none() => return Ok(map),
+                None => return Err(self.error("unterminated object")),
+                Some(_) => {}
+            }
+            let key = self.key()?;
+            self.skip_space()?;
+            if self.peek() != Some(':') {
+                return Err(self.error(format!("expected `:` after `{key}`")));
+            }
+            self.pos += 1;
+            self.skip_space()?;
+            let value = self.value()?;
+            map.insert(key, value);
+            self.separator()?;
+        }
+    }
+
+    fn elements(&mut self) -> Result<JsonValue, HjsonError> {
+        let mut values = Vec::new();
+        loop {
+            self.skip_space()?;
+            match self.peek() {
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Array(values));
+                }
+                None => return Err(self.error("unterminated array")),
+                Some(_) => {}
+            }
+            values.push(self.value()?);
+            self.separator()?;
+        }
+    }
+
+    /// Skips an optional comma after a member or element.
+    fn separator(&mut self) -> Result<(), HjsonError> {
+        self.skip_space()?;
+        if self.peek() == Some(',') {
+            self.pos += 1;
+        }
+        Ok(())
+    }
+
+    fn key(&mut self) -> Result<String, HjsonError> {
+        if let Some(quote @ ('"' | '\'')) = self.peek() {
+            self.pos += 1;
+            return self.quoted(quote);
+        }
+        let rest = self.rest();
+        let end = rest
+            .find(|c: char| c.is_whitespace() || ",:[]{}".contains(c))
+            .unwrap_or(rest.len());
+        if end == 0 {
+            return Err(self.error("expected a key"));
+        }
+        self.pos += end;
+        Ok(rest[..end].to_string())
+    }
+
+    /// A quoted string after its opening quote, with JSON escapes.
+    fn quoted(&mut self, quote: char) -> Result<String, HjsonError> {
+        let mut out = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                c if c == quote => {
+                    self.pos += i + 1;
+                    return Ok(out);
+                }
+                '\n' | '\r' => break,
+                '\\' => {
+                    let escaped = match chars.next().map(|(_, c)| c) {
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some(c @ ('"' | '\'' | '\\' | '/')) => c,
+                        Some('u') => {
+                            let code = match hex4(&mut chars) {
+                                Some(high @ 0xd800..=0xdbff) => {
+                                    let escape = (chars.next(), chars.next());
+                                    matches!(escape, (Some((_, '\\')), Some((_, 'u'))))
+                                        .then(|| hex4(&mut chars))
+                                        .flatten()
+                                        .filter(|low| (0xdc00..=0xdfff).contains(low))
+                                        .map(|low| {
+                                            0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+                                        })
+                                }
+                                unit => unit,
+                            };
+                            match code.and_then(char::from_u32) {
+                                Some(c) => c,
+                                None => {
+                                    self.pos += i;
+                                    return Err(self.error("invalid unicode escape"));
+                                }
+                            }
+                        }
+                        _ => {
+                            self.pos += i;
+                            return Err(self.error("invalid escape"));
+                        }
+                    };
+                    out.push(escaped);
+                }
+                c => out.push(c),
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    /// A `'''` string. Whitespace after the opening quotes is dropped, as
+    /// is the indentation of each line up to the column of those quotes
+    /// and the line break before the closing ones.
+    fn multiline(&mut self) -> Result<String, HjsonError> {
+        let line_start = self.text[..self.pos].rfind('\n').map_or(0, |i| i + 1);
+        let column = self.text[line_start..self.pos].chars().count();
+        let body_start = self.pos + 3;
+        let Some(len) = self.text[body_start..].find("'''") else {
+            return Err(self.error("unterminated multiline string"));
+        };
+        self.pos = body_start + len + 3;
+
+        let body = &self.text[body_start..body_start + len];
+        let mut lines = body
+            .split('\n')
+            .map(|line| line.strip_suffix('\r').unwrap_or(line));
+        let first = lines.next().unwrap_or_default().trim_start();
+        let mut rest: Vec<&str> = lines.collect();
+        if rest.last().is_some_and(|line| line.trim().is_empty()) {
+            rest.pop();
+        }
+        let mut out: Vec<&str> = Vec::new();
+        if !first.is_empty() {
+            out.push(first);
+        }
+        for line in rest {
+            let strip = line
+                .char_indices()
+                .take(column)
+                .take_while(|(_, c)| *c == ' ' || *c == '\t')
+                .last()
+                .map_or(0, |(i, c)| i + c.len_utf8());
+            out.push(&line[strip..]);
+        }
+        Ok(out.join("\n"))
+    }
+
+    /// A value without quotes or brackets: a literal when only a
+    /// separator or comment follows it on the line, else the rest of the
+    /// line as a string.
+    fn quoteless(&mut self) -> JsonValue {
+        let rest = self.rest();
+        let line = &rest[..rest.find('\n').unwrap_or(rest.len())];
+        let token = literal_token(line);
+        if let Some(literal) = literal(token) {
+            self.pos += token.len();
+            return literal;
+        }
+        let string = line.trim_end();
+        self.pos += string.len();
+        JsonValue::String(string.to_string())
+    }
+}
+
+/// Four hex digits of a `\\u` escape.
+fn hex4(chars: &mut std::str::CharIndices) -> Option<u32> {
+    let hex: String = chars.take(4).map(|(_, c)| c).collect();
+    (hex.len() == 4)
+        .then(|| u32::from_str_radix(&hex, 16).ok())
+        .flatten()
+}
+
+/// The start of `line` up to the first separator or comment, trimmed.
+fn literal_token(line: &str) -> &str {
+    let end = [",", "]", "}", "#", "//", "/*"]
+        .iter()
+        .filter_map(|stop| line.find(stop))
+        .min()
+        .unwrap_or(line.len());
+    line[..end].trim_end()
+}
+
+fn literal(token: &str) -> Option<JsonValue> {
+    match token {
+        "true" => Some(JsonValue::Bool(true)),
+        "false" => Some(JsonValue::Bool(false)),
+        "null" => Some(JsonValue::Null),
+        _ if token.starts_with(|c: char| c == '-' || c.is_ascii_digit()) => {
+            serde_json::from_str::<Number>(token)
+                .ok()
+                .map(JsonValue::Number)
+        }
+        _ => None,
+    }
+}
+
+fn write_value(out: &mut String, value: &JsonValue, indent: &str, depth: usize) {
+    match value {
+        JsonValue::Object(map) if !map.is_empty() => {
+            out.push('{');
+            for (key, value) in map {
+                newline(out, indent, depth + 1);
+                if is_quoteless_key(key) {
+                    out.push_str(key);
+                } else {
+                    out.push_str(&JsonValue::String(key.clone()).to_string());
+                }
+                out.push(':');
+                match value {
+                    JsonValue::String(s) if is_multiline(s) => {
+                        newline(out, indent, depth + 2);
+                        write_multiline(out, s, indent, depth + 2);
+                    }
+                    _ => {
+                        out.push(' ');
+                        write_value(out, value, indent, depth + 1);
+                    }
+                }
+            }
+            newline(out, indent, depth);
+            out.push('}');
+        }
+        JsonValue::Array(values) if !values.is_empty() => {
+            out.push('[');
+            for value in values {
+                newline(out, indent, depth + 1);
+                write_value(out, value, indent, depth + 1);
+            }
+            newline(out, indent, depth);
+            out.push(']');
+        }
+        JsonValue::String(s) if is_multiline(s) => write_multiline(out, s, indent, depth),
+        JsonValue::String(s) if is_quoteless(s) => out.push_str(s),
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+fn newline(out: &mut String, indent: &str, depth: usize) {
+    out.push('\n');
+    out.push_str(&indent.repeat(depth));
+}
+
+/// Writes `s` as a `'''` block whose quotes sit at `depth`.
+fn write_multiline(out: &mut String, s: &str, indent: &str, depth: usize) {
+    out.push_str("'''");
+    for line in s.split('\n') {
+        out.push('\n');
+        if !line.is_empty() {
+            out.push_str(&indent.repeat(depth));
+            out.push_str(line);
+        }
+    }
+    newline(out, indent, depth);
+    out.push_str("'''");
+}
+
+/// Whether `s` can be written as a `'''` block and read back unchanged.
+fn is_multiline(s: &str) -> bool {
+    s.contains('\n')
+        && !s.contains("'''")
+        && !s.chars().any(|c| c.is_control() && c != '\n' && c != '\t')
+}
+
+/// Whether `s` reads back as itself without quotes.
+fn is_quoteless(s: &str) -> bool {
+    !s.is_empty()
+        && s.trim() == s
+        && !s.chars().any(char::is_control)
+        && !s.starts_with(['{', '}', '[', ']', ',', ':', '"', '\'', '#'])
+        && !s.starts_with("//")
+        && !s.starts_with("/*")
+        && literal(literal_token(s)).is_none()
+}
+
+fn is_quoteless_key(key: &str) -> bool {
+    !key.is_empty()
+        && !key.starts_with(['"', '\'', '#'])
+        && !key.starts_with("//")
+        && !key.starts_with("/*")
+        && !key
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || ",:[]{}".contains(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::empty::EmptyDocument;
+    use serde_json::json;
+
+    #[test]
+    fn parses_hjson_syntax() {
+        let text = r#"{
+  // comments of every kind
+  /* block */ name: my app # part of the name
+  "quoted key": 'single \'quoted\''
+  count: 3, ratio: 0.5
+  phrase: 3 times
+  list: [
+    1
+    true
+    null, "x"
+  ]
+  text:
+    '''
+    first
+      second
+    '''
+  empty: {}
+}"#;
+        let parsed = parse_hjson::<JsonValue>(text, None).unwrap();
+        assert_eq!(
+            parsed.value,
+            json!({
+                "name": "my app # part of the name",
+                "quoted key": "single 'quoted'",
+                "count": 3,
+                "ratio": 0.5,
+                "phrase": "3 times",
+                "list": [1, true, null, "x"],
+                "text": "first\n  second",
+                "empty": {}
+            })
+        );
+
+        assert!(parse_hjson::<JsonValue>("# nothing\n", None).is_err());
+        let opts = FormatOptions {
+            on_empty: EmptyDocument::Default,
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            parse_hjson::<JsonValue>("", Some(opts)).unwrap().value,
+            json!({})
+        );
+        assert_eq!(
+            parse_hjson::<JsonValue>("[1, 2]", None).unwrap().value,
+            json!([1, 2])
+        );
+        let err = parse_hjson::<JsonValue>("a: 1\nb c: 2\n", None).unwrap_err();
+        assert_eq!((err.line, err.column), (Some(2), Some(3)));
+        assert!(parse_hjson::<JsonValue>("{ a: 1", None).is_err());
+    }
+
+    #[test]
+    fn stringify_round_trips_tricky_values() {
+        let value = json!({
+            "plain": "hello world",
+            "looks like number": "42",
+            "leading space": " x",
+            "comment": "# hash",
+            "lines": "one\n  two\n",
+            "odd:key": [1, "true", {"nested": null}],
+            "": []
+        });
+        let formatted = Formatted::new("", value.clone(), &FormatOptions::default());
+        let opts = FormatOptions {
+            verify_round_trip: true,
+            ..FormatOptions::default()
+        };
+        let out = stringify_hjson(&formatted, Some(opts), HjsonOutput::Hjson).unwrap();
+        assert_eq!(
+            out,
+            "{\n  plain: hello world\n  \"looks like number\": \"42\"\n  \"leading space\": \" x\"\n  comment: \"# hash\"\n  lines:\n    '''\n    one\n      two\n\n    '''\n  \"odd:key\": [\n    1\n    \"true\"\n    {\n      nested: null\n    }\n  ]\n  \"\": []\n}"
+        );
+        assert_eq!(hjson_to_value(&out).unwrap(), value);
+    }
+}
@@ -0,0 +1,52 @@
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+use serde_json::Value as JsonValue;
+
+/// What a hook returns; any error aborts the pipeline.
+pub type HookResult<T> = Result<T, Box<dyn Error>>;
+
+type TextHook = Arc<dyn Fn(&str) -> HookResult<String> + Send + Sync>;
+type ValueHook = Arc<dyn Fn(JsonValue) -> HookResult<JsonValue> + Send + Sync>;
+
+/// Ordered callbacks run around parsing and saving, registered through
+/// [`C12Context`](crate::C12Context).
+#[derive(Clone, Default)]
+pub(crate) struct Hooks {
+    pub(crate) pre_parse: Vec<TextHook>,
+    pub(crate) post_parse: Vec<ValueHook>,
+    pub(crate) pre_save: Vec<TextHook>,
+}
+
+impl fmt::Debug for Hooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hooks")
+            .field("pre_parse", &self.pre_parse.len())
+            .field("post_parse", &self.post_parse.len())
+            .field("pre_save", &self.pre_save.len())
+            .finish()
+    }
+}
+
+impl Hooks {
+    pub(crate) fn run_pre_parse(&self, text: &str) -> HookResult<String> {
+        run_text(&self.pre_parse, text)
+    }
+
+    pub(crate) fn run_post_parse(&self, value: JsonValue) -> HookResult<JsonValue> {
+        self.post_parse
+            .iter()
+            .try_fold(value, |value, hook| hook(value))
+    }
+
+    pub(crate) fn run_pre_save(&self, text: &str) -> HookResult<String> {
+        run_text(&self.pre_save, text)
+    }
+}
+
+fn run_text(hooks: &[TextHook], text: &str) -> HookResult<String> {
+    hooks
+        .iter()
+        .try_fold(text.to_string(), |text, hook| hook(&text))
+}
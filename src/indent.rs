@@ -0,0 +1,157 @@
+use std::ops::Range;
+
+use toml_edit::{Item, Table, Value as TomlValue};
+
+use crate::format::{Format, IndentStyle};
+use crate::minify::{Lexeme, lex};
+
+/// Rewrites the indentation of every line of `text` with `style`, a tab
+/// standing for `tab_width` columns.
+///
+/// Leading whitespace is measured in columns and written back as tabs
+/// followed by whatever does not fill a whole tab, so alignment inside
+/// block comments (`/*` ... ` * `) and continuation lines survives the
+/// trip. Only the start of lines changes: whitespace after the first
+/// non-blank character and the lines of multi-line strings (TOML `"""`,
+/// JSON5 line continuations) are kept as they are, as are blank lines.
+/// YAML forbids tabs in indentation, so converting YAML to tabs returns
+/// `text` unchanged.
+///
+/// ```
+/// use c12_parser::{Format, IndentStyle, reindent};
+///
+/// let text = "{\n    \"a\": {\n        \"b\": 1\n    },\n    /* one\n     * two */\n}\n";
+/// let tabs = reindent(text, Format::Jsonc, IndentStyle::Tabs, 4);
+/// assert_eq!(tabs, "{\n\t\"a\": {\n\t\t\"b\": 1\n\t},\n\t/* one\n\t * two */\n}\n");
+/// assert_eq!(reindent(&tabs, Format::Jsonc, IndentStyle::Spaces, 4), text);
+/// ```
+pub fn reindent(text: &str, format: Format, style: IndentStyle, tab_width: usize) -> String {
+    if format == Format::Yaml && style == IndentStyle::Tabs {
+        return text.to_string();
+    }
+    let width = tab_width.max(1);
+    let strings = match format {
+        Format::Json | Format::Jsonc | Format::Json5 => json_strings(text),
+        Format::Toml => toml_strings(text),
+        Format::Yaml | Format::Ini => Vec::new(),
+    };
+    let mut out = String::with_capacity(text.len());
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let content = line.trim_start_matches([' ', '\t']);
+        let in_string = strings.iter().any(|s| s.start < start && start < s.end);
+        if in_string || content.trim().is_empty() {
+            out.push_str(line);
+            continue;
+        }
+        let columns = line[..line.len() - content.len()]
+            .chars()
+            .fold(0, |column, c| match c {
+                '\t' => (column / width + 1) * width,
+                _ => column + 1,
+            });
+        match style {
+            IndentStyle::Spaces => out.push_str(&" ".repeat(columns)),
+            IndentStyle::Tabs => {
+                out.push_str(&"\t".repeat(columns / width));
+                out.push_str(&" ".repeat(columns % width));
+            }
+        }
+        out.push_str(content);
+    }
+    out
+}
+
+/// The byte ranges of the string literals in JSON-like text, or none
+/// when it does not lex.
+fn json_strings(text: &str) -> Vec<Range<usize>> {
+    let Ok(lexemes) = lex(text) else {
+        return Vec::new();
+    };
+    let mut strings = Vec::new();
+    for (i, (start, lexeme)) in lexemes.iter().enumerate() {
+        if let Lexeme::Str(_) = lexeme {
+            let next = lexemes.get(i + 1).map_or(text.len(), |(next, _)| *next);
+            strings.push(*start..start + text[*start..next].trim_end().len());
+        }
+    }
+    strings
+}
+
+/// The byte ranges of the string values in TOML text, or none when it
+/// does not parse. Keys cannot span lines, so they are left out.
+fn toml_strings(text: &str) -> Vec<Range<usize>> {
+    let mut strings = Vec::new();
+    if let Ok(doc) = toml_edit::Document::parse(text) {
+        toml_table(doc.as_table(), &mut strings);
+    }
+    strings
+}
+
+fn toml_table(table: &Table, strings: &mut Vec<Range<usize>>) {
+    for (_, item) in table.iter() {
+        match item {
+            Item::Value(value) => toml_value(value, strings),
+            Item::Table(table) => toml_table(table, strings),
+            Item::ArrayOfTables(tables) => {
+                for table in tables.iter() {
+                    toml_table(table, strings);
+                }
+            }
+            Item::None => {}
+        }
+    }
+}
+
+fn toml_value(value: &TomlValue, strings: &mut Vec<Range<usize>>) {
+    match value {
+        TomlValue::String(s) => strings.extend(s.span()),
+        TomlValue::Array(array) => {
+            for value in array.iter() {
+                toml_value(value, strings);
+            }
+        }
+        TomlValue::InlineTable(table) => {
+            for (_, value) in table.iter() {
+                toml_value(value, strings);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_multi_line_strings_and_alignment() {
+        let toml = "a = [\n    1,\n      2,\n]\ns = \"\"\"\n    kept\n\"\"\"\n";
+        assert_eq!(
+            reindent(toml, Format::Toml, IndentStyle::Tabs, 4),
+            "a = [\n\t1,\n\t  2,\n]\ns = \"\"\"\n    kept\n\"\"\"\n"
+        );
+        let json5 = "{\n  s: 'a\\\n  b',\n  n: 1,\n}";
+        assert_eq!(
+            reindent(json5, Format::Json5, IndentStyle::Tabs, 2),
+            "{\n\ts: 'a\\\n  b',\n\tn: 1,\n}"
+        );
+    }
+
+    #[test]
+    fn maps_tab_stops_to_spaces() {
+        let text = "[a]\n\tx = 1\n  \ty = 2\n\t \n";
+        assert_eq!(
+            reindent(text, Format::Ini, IndentStyle::Spaces, 4),
+            "[a]\n    x = 1\n    y = 2\n\t \n"
+        );
+        let yaml = "a:\n  b: 1\n";
+        assert_eq!(reindent(yaml, Format::Yaml, IndentStyle::Tabs, 2), yaml);
+        assert_eq!(
+            reindent("a:\n\tb: 1\n", Format::Yaml, IndentStyle::Spaces, 2),
+            "a:\n  b: 1\n"
+        );
+    }
+}
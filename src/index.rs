@@ -0,0 +1,548 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path as FsPath, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as JsonValue};
+
+use crate::batch::ItemDiagnostic;
+use crate::error::C12Error;
+use crate::flatten::{DottedKeys, flatten, unflatten};
+use crate::format::{Format, FormatOptions};
+use crate::locate::{Location, locate};
+use crate::path::{Path, PathParseError};
+use crate::progress::Progress;
+use crate::query::Query;
+use crate::registry::parse_as;
+use crate::schema_diff::infer_schema;
+use crate::walk::{Walker, file_glob, slash_path};
+
+/// What a [`ConfigIndex`] knows about one file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub format: Format,
+    /// Modification time in nanoseconds since the epoch; with `size`, it
+    /// tells whether the file changed since it was indexed.
+    pub modified: u64,
+    pub size: u64,
+    /// A hash of the [inferred schema](crate::infer_schema), equal for
+    /// files with the same keys and value types. 0 when parsing failed.
+    pub schema_hash: u64,
+    /// The leaf values by [flattened](crate::flatten) key.
+    pub values: Map<String, JsonValue>,
+    /// The diagnostics of the last parse, empty when it succeeded.
+    pub diagnostics: Vec<String>,
+}
+
+impl IndexEntry {
+    /// The flattened keys the file sets.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.values.keys().map(String::as_str)
+    }
+}
+
+/// How an [`update`](ConfigIndex::update) changed the index.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IndexUpdate {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+    pub unchanged: usize,
+}
+
+impl fmt::Display for IndexUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} added, {} updated, {} removed, {} unchanged",
+            self.added, self.updated, self.removed, self.unchanged
+        )
+    }
+}
+
+/// A value found by [`ConfigIndex::query_workspace`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueryMatch {
+    /// The file, relative to the index root.
+    pub file: PathBuf,
+    pub path: Path,
+    pub value: JsonValue,
+    /// Where `path` is written in the file; `None` when the file changed
+    /// since it was indexed.
+    pub location: Option<Location>,
+}
+
+impl fmt::Display for QueryMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", slash_path(&self.file))?;
+        if let Some(at) = self.location {
+            write!(f, ":{}:{}", at.line, at.column)?;
+        }
+        write!(f, ": {} = {}", self.path, self.value)
+    }
+}
+
+/// Returned when a [`ConfigIndex`] cannot read or write its store.
+#[derive(Debug)]
+pub enum IndexError {
+    Io(io::Error),
+    Store(sled::Error),
+    /// The index directory is open elsewhere, in this process or another.
+    Locked(PathBuf),
+    /// A stored entry does not decode, e.g. after a format change; delete
+    /// the index directory to rebuild it.
+    Corrupt(serde_json::Error),
+    /// The expression given to [`ConfigIndex::query_workspace`] is invalid.
+    Query(PathParseError),
+}
+
+impl fmt::Display for IndexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexError::Io(e) => write!(f, "index I/O error: {e}"),
+            IndexError::Store(e) => write!(f, "index store error: {e}"),
+            IndexError::Locked(dir) => write!(f, "index at {} is open elsewhere", dir.display()),
+            IndexError::Corrupt(e) => write!(f, "index entry is corrupt: {e}"),
+            IndexError::Query(e) => write!(f, "invalid query: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for IndexError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            IndexError::Io(e) => Some(e),
+            IndexError::Store(e) => Some(e),
+            IndexError::Locked(_) => None,
+            IndexError::Corrupt(e) => Some(e),
+            IndexError::Query(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for IndexError {
+    fn from(e: io::Error) -> Self {
+        IndexError::Io(e)
+    }
+}
+
+impl From<sled::Error> for IndexError {
+    fn from(e: sled::Error) -> Self {
+        IndexError::Store(e)
+    }
+}
+
+impl From<serde_json::Error> for IndexError {
+    fn from(e: serde_json::Error) -> Self {
+        IndexError::Corrupt(e)
+    }
+}
+
+impl From<PathParseError> for IndexError {
+    fn from(e: PathParseError) -> Self {
+        IndexError::Query(e)
+    }
+}
+
+/// A persistent index of the config files under a directory, for fast
+/// repo-wide queries in large monorepos. Stored in a `sled` database and
+/// kept current incrementally: [`update`](Self::update) re-parses only
+/// files whose size or modification time changed, and watchers can call
+/// [`update_file`](Self::update_file) per change event.
+///
+/// ```
+/// use c12_parser::ConfigIndex;
+/// # let dir = std::env::temp_dir().join(format!("c12-index-doc-{}", std::process::id()));
+/// # let _ = std::fs::remove_dir_all(&dir);
+/// # std::fs::create_dir_all(dir.join("repo/billing")).unwrap();
+/// # std::fs::write(dir.join("repo/billing/service.yaml"), "http:\n  retries: 8\n").unwrap();
+/// # let (db, repo) = (dir.join("index"), dir.join("repo"));
+///
+/// let index = ConfigIndex::open(&db, &repo)?;
+/// index.update()?;
+/// let noisy = index.find("retries", |v| v.as_i64() > Some(5))?;
+/// assert_eq!(noisy[0].0, std::path::Path::new("billing/service.yaml"));
+/// assert_eq!(noisy[0].1, 8);
+/// # drop(index);
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// # Ok::<(), c12_parser::IndexError>(())
+/// ```
+#[derive(Debug)]
+pub struct ConfigIndex {
+    db: sled::Db,
+    root: PathBuf,
+    walker: Walker,
+}
+
+impl ConfigIndex {
+    /// Opens or creates the index stored in `db_dir`, covering the files
+    /// under `root`.
+    pub fn open(db_dir: impl AsRef<FsPath>, root: impl Into<PathBuf>) -> Result<Self, IndexError> {
+        let db_dir = db_dir.as_ref();
+        // Updates flush explicitly, so sled's background flusher is off.
+        let config = sled::Config::new().path(db_dir).flush_every_ms(None);
+        // Jobs of a dropped index can hold its lock for a few milliseconds,
+        // and sled cannot wait for them, so a held lock is retried for a
+        // while. Other errors fail at once.
+        let mut attempts = 0;
+        let db = loop {
+            match config.open() {
+                Err(e) if is_locked(&e) => {
+                    if attempts == 50 {
+                        return Err(IndexError::Locked(db_dir.to_path_buf()));
+                    }
+                    attempts += 1;
+                    std::thread::sleep(std::time::Duration::from_millis(10));
+                }
+                result => break result?,
+            }
+        };
+        Ok(Self {
+            db,
+            root: root.into(),
+            walker: Walker::new(),
+        })
+    }
+
+    /// Lists files with `walker` instead of the default one, e.g. to
+    /// exclude vendored directories.
+    pub fn walker(mut self, walker: Walker) -> Self {
+        self.walker = walker;
+        self
+    }
+
+    /// Brings the index up to date with the directory.
+    pub fn update(&self) -> Result<IndexUpdate, IndexError> {
+        self.update_with_progress(&())
+    }
+
+    /// Like [`update`](Self::update), reporting each config file to
+    /// `progress`.
+    pub fn update_with_progress(&self, progress: &dyn Progress) -> Result<IndexUpdate, IndexError> {
+        let files: Vec<_> = self
+            .walker
+            .files(&self.root)?
+            .into_iter()
+            .filter(|file| format_of(file).is_some())
+            .collect();
+        progress.discovered(files.len());
+
+        let mut update = IndexUpdate::default();
+        for file in &files {
+            match self.refresh(file)? {
+                Refresh::Added => update.added += 1,
+                Refresh::Updated => update.updated += 1,
+                Refresh::Unchanged => update.unchanged += 1,
+                Refresh::Removed => update.removed += 1,
+            }
+            progress.processed(file);
+        }
+        let listed: Vec<_> = files.iter().map(|f| slash_path(f)).collect();
+        for key in self.db.iter().keys() {
+            let key = key?;
+            if !listed.iter().any(|f| f.as_bytes() == &*key) {
+                self.db.remove(key)?;
+                update.removed += 1;
+            }
+        }
+        self.db.flush()?;
+        Ok(update)
+    }
+
+    /// Re-indexes one file, given relative to the root, or drops it when
+    /// it no longer exists. Returns whether the entry changed.
+    pub fn update_file(&self, file: &FsPath) -> Result<bool, IndexError> {
+        let changed = self.refresh(file)? != Refresh::Unchanged;
+        self.db.flush()?;
+        Ok(changed)
+    }
+
+    /// The entry for `file`, relative to the root.
+    pub fn get(&self, file: &FsPath) -> Result<Option<IndexEntry>, IndexError> {
+        match self.db.get(slash_path(file))? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Every entry, in path order.
+    pub fn entries(&self) -> Result<Vec<(PathBuf, IndexEntry)>, IndexError> {
+        self.db
+            .iter()
+            .map(|item| {
+                let (key, bytes) = item?;
+                let path = PathBuf::from(String::from_utf8_lossy(&key).into_owned());
+                Ok((path, serde_json::from_slice(&bytes)?))
+            })
+            .collect()
+    }
+
+    /// The files setting `key` to a value `predicate` accepts, with that
+    /// value. `key` is a flattened key matched at any depth, so `retries`
+    /// finds `retries` and `http.retries` alike.
+    pub fn find(
+        &self,
+        key: &str,
+        predicate: impl Fn(&JsonValue) -> bool,
+    ) -> Result<Vec<(PathBuf, JsonValue)>, IndexError> {
+        let suffix = format!(".{key}");
+        let mut found = Vec::new();
+        for (path, entry) in self.entries()? {
+            found.extend(
+                entry
+                    .values
+                    .iter()
+                    .filter(|(flat, value)| {
+                        (*flat == key || flat.ends_with(&suffix)) && predicate(value)
+                    })
+                    .map(|(_, value)| (path.clone(), value.clone())),
+            );
+        }
+        Ok(found)
+    }
+
+    /// Evaluates the [`Query`] `expression` against every indexed file
+    /// matching `glob` (such as `services/**/*.yaml`; `**` for all), the
+    /// library side of `c12 grep`. Matches come in path order, each located
+    /// in the file as it is on disk now.
+    pub fn query_workspace(
+        &self,
+        glob: &str,
+        expression: &str,
+    ) -> Result<Vec<QueryMatch>, IndexError> {
+        let query: Query = expression.parse()?;
+        let glob = file_glob(glob);
+        let mut found = Vec::new();
+        for (file, entry) in self.entries()? {
+            if !glob.is_match(&slash_path(&file)) || !entry.diagnostics.is_empty() {
+                continue;
+            }
+            let Ok(value) = unflatten(&entry.values, DottedKeys::Nested) else {
+                continue;
+            };
+            let matches = query.matches(&value);
+            if matches.is_empty() {
+                continue;
+            }
+            let text = fs::read_to_string(self.root.join(&file)).ok();
+            found.extend(matches.into_iter().map(|(path, value)| {
+                QueryMatch {
+                    location: text
+                        .as_deref()
+                        .and_then(|text| locate(text, entry.format, &path)),
+                    file: file.clone(),
+                    path,
+                    value: value.clone(),
+                }
+            }));
+        }
+        Ok(found)
+    }
+
+    fn refresh(&self, file: &FsPath) -> Result<Refresh, IndexError> {
+        let key = slash_path(file);
+        let Some(format) = format_of(file) else {
+            return Ok(Refresh::Unchanged);
+        };
+        let old = self.get(file)?;
+        let metadata = match fs::metadata(self.root.join(file)) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                self.db.remove(&key)?;
+                return Ok(if old.is_some() {
+                    Refresh::Removed
+                } else {
+                    Refresh::Unchanged
+                });
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        if let Some(old) = &old
+            && old.modified == modified
+            && old.size == metadata.len()
+        {
+            return Ok(Refresh::Unchanged);
+        }
+
+        let text = fs::read_to_string(self.root.join(file))?;
+        let mut entry = IndexEntry {
+            format,
+            modified,
+            size: metadata.len(),
+            schema_hash: 0,
+            values: Map::new(),
+            diagnostics: Vec::new(),
+        };
+        match parse_as(format, &text, FormatOptions::default()) {
+            Ok(parsed) => {
+                entry.schema_hash = fnv1a(infer_schema(&parsed.value).to_string().as_bytes());
+                entry.values = flatten(&parsed.value);
+            }
+            Err(e) => {
+                let error = C12Error::new(format, &text, &*e);
+                entry.diagnostics = vec![ItemDiagnostic::from_error(&error).to_string()];
+            }
+        }
+        self.db.insert(key, serde_json::to_vec(&entry)?)?;
+        Ok(if old.is_some() {
+            Refresh::Updated
+        } else {
+            Refresh::Added
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Refresh {
+    Added,
+    Updated,
+    Unchanged,
+    Removed,
+}
+
+fn format_of(file: &FsPath) -> Option<Format> {
+    Format::from_name(&file.extension()?.to_string_lossy())
+}
+
+/// 64-bit FNV-1a, stable across builds unlike `DefaultHasher`, which
+/// matters for hashes kept on disk.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Whether `error` is sled failing to take the lock on its directory,
+/// which it reports only through the message of an `Other` I/O error.
+fn is_locked(error: &sled::Error) -> bool {
+    matches!(error, sled::Error::Io(e)
+        if e.kind() == io::ErrorKind::Other
+            && e.to_string().starts_with("could not acquire lock on"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("c12-index-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("repo")).unwrap();
+        dir
+    }
+
+    fn write(root: &FsPath, path: &str, text: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, text).unwrap();
+    }
+
+    #[test]
+    fn indexes_incrementally_and_persists() {
+        let dir = scratch("incremental");
+        let repo = dir.join("repo");
+        write(&repo, "a/service.toml", "[http]\nretries = 3\n");
+        write(&repo, "b/service.json", "{ \"http\": { \"retries\": 7 } }");
+        write(&repo, "c/service.yaml", "http: [\n");
+        write(&repo, "README.md", "# not config\n");
+
+        let index = ConfigIndex::open(dir.join("db"), &repo).unwrap();
+        let update = index.update().unwrap();
+        assert_eq!(
+            update.to_string(),
+            "3 added, 0 updated, 0 removed, 0 unchanged"
+        );
+        let a = index.get("a/service.toml".as_ref()).unwrap().unwrap();
+        let b = index.get("b/service.json".as_ref()).unwrap().unwrap();
+        assert_eq!(a.keys().collect::<Vec<_>>(), ["http.retries"]);
+        assert_eq!(a.schema_hash, b.schema_hash);
+        let broken = index.get("c/service.yaml".as_ref()).unwrap().unwrap();
+        assert!(broken.diagnostics[0].starts_with("error[C12E021]: yaml error"));
+
+        write(&repo, "a/service.toml", "[http]\nretries = 10\n");
+        fs::remove_file(repo.join("c/service.yaml")).unwrap();
+        let update = index.update().unwrap();
+        assert_eq!(
+            (update.updated, update.removed, update.unchanged),
+            (1, 1, 1)
+        );
+        drop(index);
+
+        let index = ConfigIndex::open(dir.join("db"), &repo).unwrap();
+        let found = index.find("retries", |v| v.as_i64() > Some(5)).unwrap();
+        assert_eq!(
+            found,
+            [
+                (PathBuf::from("a/service.toml"), json!(10)),
+                (PathBuf::from("b/service.json"), json!(7)),
+            ]
+        );
+        let err = ConfigIndex::open(dir.join("db"), &repo).unwrap_err();
+        assert!(matches!(err, IndexError::Locked(_)), "{err}");
+        drop(index);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn single_files_update_for_watchers() {
+        let dir = scratch("single");
+        let repo = dir.join("repo");
+        let index = ConfigIndex::open(dir.join("db"), &repo).unwrap();
+        let file = FsPath::new("app.json");
+        assert!(!index.update_file(file).unwrap());
+
+        write(&repo, "app.json", "{ \"port\": 1 }");
+        assert!(index.update_file(file).unwrap());
+        assert!(!index.update_file(file).unwrap());
+        fs::remove_file(repo.join(file)).unwrap();
+        assert!(index.update_file(file).unwrap());
+        assert!(index.entries().unwrap().is_empty());
+        drop(index);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn queries_report_file_and_line() {
+        let dir = scratch("query");
+        let repo = dir.join("repo");
+        write(&repo, "api/app.yaml", "name: api\nhttp:\n  retries: 8\n");
+        write(
+            &repo,
+            "web/app.toml",
+            "name = \"web\"\n\n[http]\nretries = 9\n",
+        );
+        write(
+            &repo,
+            "web/app.json",
+            "{\n  \"http\": { \"retries\": 1 }\n}",
+        );
+        let index = ConfigIndex::open(dir.join("db"), &repo).unwrap();
+        index.update().unwrap();
+
+        let found = index.query_workspace("**", "..retries > 5").unwrap();
+        let lines: Vec<_> = found.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            lines,
+            [
+                "api/app.yaml:3:3: http.retries = 8",
+                "web/app.toml:4:1: http.retries = 9"
+            ]
+        );
+        let found = index.query_workspace("web/*.json", "http.retries").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].location.map(|at| at.line), Some(2));
+        assert!(matches!(
+            index.query_workspace("**", "http..").unwrap_err(),
+            IndexError::Query(_)
+        ));
+        drop(index);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,21 +1,104 @@
 use std::collections::HashMap;
 use std::fmt::Write as _;
 
+use indexmap::IndexMap;
+use serde_json::{Map, Value as JsonValue};
+
+use crate::scalar::{ScalarOptions, typed_scalar};
+
+/// The keys of an INI section and their values, in order. A key without
+/// `=` has no value.
+pub type IniSection = IndexMap<String, Option<String>>;
+
+/// The sections of an INI file, in order. Keys before the first header
+/// belong to the `default` section.
+pub type IniMap = IndexMap<String, IniSection>;
+
 /// Parses an INI string into a simple nested map structure:
-/// `HashMap<section, HashMap<key, Option<value>>>`.
+/// `IndexMap<section, IndexMap<key, Option<value>>>`.
 ///
+/// Sections and keys keep their order in `text`, the `default` section
+/// first, so iterating, hashing or diffing the result is reproducible.
 /// Style/indentation are not preserved.
-pub fn parse_ini(text: &str) -> HashMap<String, HashMap<String, Option<String>>> {
-    ini::inistr!(text)
+pub fn parse_ini(text: &str) -> IniMap {
+    let mut parsed: HashMap<String, HashMap<_, _>> = ini::inistr!(text);
+    let mut order: Vec<(String, Vec<String>)> = vec![("default".into(), Vec::new())];
+    // The parser lowercases names, so the scan does too.
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with([';', '#']) {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            let name = name.trim().to_lowercase();
+            if !order.iter().any(|(section, _)| *section == name) {
+                order.push((name, Vec::new()));
+            }
+            continue;
+        }
+        let key = line.split_once('=').map_or(line, |(key, _)| key);
+        let key = key.trim().to_lowercase();
+        let (_, keys) = order.last_mut().expect("default section");
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+
+    let mut sections = IniMap::new();
+    for (name, keys) in order {
+        let Some(mut entries) = parsed.remove(&name) else {
+            continue;
+        };
+        let mut section: IniSection = keys
+            .into_iter()
+            .filter_map(|key| entries.remove_entry(&key))
+            .collect();
+        // Anything the scan missed still comes out, in a stable order.
+        let mut rest: Vec<_> = entries.into_iter().collect();
+        rest.sort_by(|a, b| a.0.cmp(&b.0));
+        section.extend(rest);
+        sections.insert(name, section);
+    }
+    let mut rest: Vec<_> = parsed.into_iter().collect();
+    rest.sort_by(|a, b| a.0.cmp(&b.0));
+    for (name, entries) in rest {
+        let mut entries: Vec<_> = entries.into_iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        sections.insert(name, entries.into_iter().collect());
+    }
+    sections
+}
+
+/// Parses an INI string into an object of sections, reading booleans,
+/// `null` and numbers as typed values (see [`typed_scalar`]). Keys without
+/// a value become `null`. Sections and keys keep their order in `text`.
+pub fn parse_ini_typed(text: &str, options: Option<ScalarOptions>) -> JsonValue {
+    let opts = options.unwrap_or_default();
+    to_value(parse_ini(text), |value| {
+        value.map_or(JsonValue::Null, |v| typed_scalar(&v, &opts))
+    })
 }
 
 /// Stringifies an INI-like nested map back into INI text.
 ///
+/// Sections and keys are written in the map's order, except that the
+/// `default` section always comes first, without a header, as its keys
+/// would otherwise land in the section before it.
+///
 /// Note: This does **not** preserve exact original formatting.
-pub fn stringify_ini(map: &HashMap<String, HashMap<String, Option<String>>>) -> String {
+///
+/// ```
+/// use c12_parser::{parse_ini, stringify_ini};
+///
+/// let text = "name = app\n[server]\nport = 80\nhost = a\n[db]\nurl\n";
+/// assert_eq!(stringify_ini(&parse_ini(text)), text);
+/// ```
+pub fn stringify_ini(map: &IniMap) -> String {
     let mut out = String::new();
-    for (section, kv) in map {
-        if section.to_lowercase() != "default" {
+    let (defaults, sections): (Vec<_>, Vec<_>) = map
+        .iter()
+        .partition(|(section, _)| section.eq_ignore_ascii_case("default"));
+    for (section, kv) in defaults.into_iter().chain(sections) {
+        if !section.eq_ignore_ascii_case("default") {
             let _ = writeln!(&mut out, "[{}]", section);
         }
         for (key, value) in kv {
@@ -32,9 +115,75 @@ pub fn stringify_ini(map: &HashMap<String, HashMap<String, Option<String>>>) ->
     out
 }
 
+/// Parses INI text into an object of objects of strings, in source order.
+pub(crate) fn ini_to_value(text: &str) -> JsonValue {
+    ini_map_to_value(parse_ini(text))
+}
+
+/// `map` as an object of objects of strings, with valueless keys `null`.
+pub(crate) fn ini_map_to_value(map: IniMap) -> JsonValue {
+    to_value(map, |value| value.map_or(JsonValue::Null, Into::into))
+}
+
+fn to_value(map: IniMap, convert: impl Fn(Option<String>) -> JsonValue) -> JsonValue {
+    let sections: Map<String, JsonValue> = map
+        .into_iter()
+        .map(|(name, entries)| {
+            let object = entries
+                .into_iter()
+                .map(|(key, value)| (key, convert(value)))
+                .collect();
+            (name, JsonValue::Object(object))
+        })
+        .collect();
+    JsonValue::Object(sections)
+}
+
+/// Converts an object of sections into INI sections.
+///
+/// Top-level scalars go to the `default` section, which is written without
+/// a header: that is where [`parse_ini`] puts the keys before the first
+/// header, so a flat config such as a scaffolded `name`/`debug` struct
+/// can be written at all, and reads back as `{ "default": { ... } }`.
+/// They are merged with the entries of an explicit `default` object.
+pub(crate) fn value_to_ini(value: &JsonValue) -> Result<IniMap, String> {
+    let sections = value
+        .as_object()
+        .ok_or("INI output requires an object of sections")?;
+    let mut map = IniMap::new();
+    for (section, entries) in sections {
+        match entries {
+            JsonValue::Object(entries) => {
+                let entries = entries
+                    .iter()
+                    .map(|(key, value)| (key.clone(), ini_scalar(value)));
+                map.entry(section.clone()).or_default().extend(entries);
+            }
+            JsonValue::Array(_) => {
+                return Err(format!("INI section `{section}` must be an object"));
+            }
+            scalar => {
+                map.entry("default".to_string())
+                    .or_default()
+                    .insert(section.clone(), ini_scalar(scalar));
+            }
+        }
+    }
+    Ok(map)
+}
+
+fn ini_scalar(value: &JsonValue) -> Option<String> {
+    match value {
+        JsonValue::Null => None,
+        JsonValue::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fixtures::FLOAT;
 
     const INI_FIXTURE: &str = r#"
 [types]
@@ -76,6 +225,25 @@ date = 1979-05-27T15:32:00.000Z
         assert_eq!(reparsed, map);
     }
 
+    #[test]
+    fn ini_parse_typed_reads_scalars() {
+        let value = parse_ini_typed(INI_FIXTURE, None);
+        let types = &value["types"];
+        assert_eq!(types["boolean"], true);
+        assert_eq!(types["integer"], 1);
+        assert_eq!(types["float"], FLOAT);
+        assert_eq!(types["string"], "hello");
+        assert_eq!(types["null"], JsonValue::Null);
+
+        let lenient = parse_ini_typed(
+            "ratio = 0,75",
+            Some(ScalarOptions {
+                comma_decimal: true,
+            }),
+        );
+        assert_eq!(lenient["default"]["ratio"], 0.75);
+    }
+
     #[test]
     fn ini_handles_default_section_without_header() {
         let ini = r#"
@@ -94,4 +262,32 @@ key2 = value2
             Some("value1")
         );
     }
+
+    #[test]
+    fn ini_keeps_source_order_and_writes_default_first() {
+        let text = "[zeta]\nb = 2\na = 1\n[alpha]\nflag\n";
+        let map = parse_ini(text);
+        let sections: Vec<_> = map.keys().collect();
+        assert_eq!(sections, ["zeta", "alpha"]);
+        assert_eq!(stringify_ini(&map), text);
+
+        let value = serde_json::json!({ "server": { "port": 80 }, "name": "app", "debug": true });
+        let map = value_to_ini(&value).unwrap();
+        let out = stringify_ini(&map);
+        assert_eq!(out, "name = app\ndebug = true\n[server]\nport = 80\n");
+        for _ in 0..8 {
+            assert_eq!(stringify_ini(&value_to_ini(&value).unwrap()), out);
+        }
+    }
+
+    #[test]
+    fn top_level_scalars_read_back_from_the_default_section() {
+        let value = serde_json::json!({ "name": "app", "default": { "debug": "true" }, "db": { "url": "x" } });
+        let out = stringify_ini(&value_to_ini(&value).unwrap());
+        assert_eq!(out, "name = app\ndebug = true\n[db]\nurl = x\n");
+        assert_eq!(
+            ini_to_value(&out),
+            serde_json::json!({ "default": { "name": "app", "debug": "true" }, "db": { "url": "x" } })
+        );
+    }
 }
@@ -0,0 +1,210 @@
+use std::fmt;
+
+use serde::de::{self, DeserializeOwned};
+use serde_json::Value as JsonValue;
+
+use crate::format::FormatOptions;
+use crate::limits::limited;
+use crate::path::{Path, shown};
+
+/// A string [`interpolate_env`] could not expand.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InterpolationError {
+    /// Path of the string, e.g. `server.url`.
+    pub path: Path,
+    pub message: String,
+}
+
+impl fmt::Display for InterpolationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "string at `{}`: {}", shown(&self.path), self.message)
+    }
+}
+
+impl std::error::Error for InterpolationError {}
+
+/// Expands environment variables in every string of `value`, looking
+/// them up with `lookup`.
+///
+/// `${NAME}` is replaced by the variable and fails when it is not set;
+/// `${NAME:-default}` falls back to `default` when the variable is unset
+/// or empty. Defaults are taken literally, without nested `${`. Write
+/// `$${` for a literal `${`. Keys and non-string values are left alone,
+/// and expanded strings stay strings.
+///
+/// ```
+/// use c12_parser::interpolate_env;
+/// use serde_json::json;
+///
+/// let mut config = json!({
+///     "url": "http://${HOST}:${PORT:-8080}/",
+///     "template": "$${HOST} stays",
+/// });
+/// interpolate_env(&mut config, |name| (name == "HOST").then(|| "db".to_string()))?;
+/// assert_eq!(config, json!({ "url": "http://db:8080/", "template": "${HOST} stays" }));
+/// # Ok::<(), c12_parser::InterpolationError>(())
+/// ```
+pub fn interpolate_env(
+    value: &mut JsonValue,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<(), InterpolationError> {
+    visit(value, &lookup, &Path::root())
+}
+
+/// Reads a variable from the process environment, for parsing without a
+/// [`C12Context`](crate::C12Context), whose
+/// [`env_var`](crate::C12Context::env_var) falls back to it.
+pub(crate) fn process_env(name: &str) -> Option<String> {
+    std::env::var(name).ok()
+}
+
+/// Applies the value passes of `opts` to a parsed value, interpolation
+/// and then limits, and deserializes the result. Limits are checked here
+/// even for backends that checked them while parsing, since expanded
+/// strings can be longer.
+pub(crate) fn prepared<T, E>(mut value: JsonValue, opts: &FormatOptions) -> Result<T, E>
+where
+    T: DeserializeOwned,
+    E: de::Error,
+{
+    if opts.interpolate_env {
+        interpolate_env(&mut value, process_env).map_err(E::custom)?;
+    }
+    limited(value, &opts.limits)
+}
+
+fn visit(
+    value: &mut JsonValue,
+    lookup: &dyn Fn(&str) -> Option<String>,
+    path: &Path,
+) -> Result<(), InterpolationError> {
+    match value {
+        JsonValue::String(s) if s.contains("${") => {
+            *s = expand(s, lookup).map_err(|message| InterpolationError {
+                path: path.clone(),
+                message,
+            })?;
+        }
+        JsonValue::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                visit(item, lookup, &path.clone().index(i))?;
+            }
+        }
+        JsonValue::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                visit(child, lookup, &path.clone().key(key.as_str()))?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn expand(text: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(after) = rest.strip_prefix("$${") {
+            out.push_str("${");
+            rest = after;
+            continue;
+        }
+        let Some(inner) = rest.strip_prefix("${") else {
+            out.push('$');
+            rest = &rest[1..];
+            continue;
+        };
+        let Some(end) = inner.find('}') else {
+            return Err("`${` is never closed".into());
+        };
+        let (name, default) = match inner[..end].split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (&inner[..end], None),
+        };
+        let valid = name
+            .chars()
+            .next()
+            .is_some_and(|c| c == '_' || c.is_ascii_alphabetic())
+            && name.chars().all(|c| c == '_' || c.is_ascii_alphanumeric());
+        if !valid {
+            return Err(format!("`${{{}}}` is not a variable name", &inner[..end]));
+        }
+        match (lookup(name), default) {
+            (Some(value), Some(default)) if value.is_empty() => out.push_str(default),
+            (Some(value), _) => out.push_str(&value),
+            (None, Some(default)) => out.push_str(default),
+            (None, None) => return Err(format!("environment variable `{name}` is not set")),
+        }
+        rest = &inner[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn env(name: &str) -> Option<String> {
+        match name {
+            "USER" => Some("ada".into()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn expands_variables_defaults_and_escapes() {
+        let mut value = json!({
+            "home": "/home/${USER}",
+            "list": ["${EMPTY:-fallback}", "${EMPTY}", "$5 and $${USER}", 3],
+            "${USER}": "keys stay"
+        });
+        interpolate_env(&mut value, env).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "home": "/home/ada",
+                "list": ["fallback", "", "$5 and ${USER}", 3],
+                "${USER}": "keys stay"
+            })
+        );
+    }
+
+    #[test]
+    fn reports_unset_and_malformed_references() {
+        let message = |text: &str| {
+            interpolate_env(&mut json!({ "a": [text] }), env)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            message("${MISSING}"),
+            "string at `a[0]`: environment variable `MISSING` is not set"
+        );
+        assert_eq!(
+            message("x ${USER"),
+            "string at `a[0]`: `${` is never closed"
+        );
+        assert_eq!(
+            message("${1X:-y}"),
+            "string at `a[0]`: `${1X:-y}` is not a variable name"
+        );
+    }
+
+    #[test]
+    fn parse_functions_interpolate_when_asked() {
+        let opts = FormatOptions {
+            interpolate_env: true,
+            ..FormatOptions::default()
+        };
+        let text = "dir = \"${C12_PARSER_TEST_UNSET:-/tmp}/cache\"\n";
+        let parsed = crate::parse_toml::<JsonValue>(text, Some(opts)).unwrap();
+        assert_eq!(parsed.value, json!({ "dir": "/tmp/cache" }));
+        let raw = crate::parse_toml::<JsonValue>(text, None).unwrap();
+        assert_eq!(raw.value["dir"], "${C12_PARSER_TEST_UNSET:-/tmp}/cache");
+    }
+}
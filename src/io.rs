@@ -0,0 +1,301 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::path::{Path as FsPath, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// File access used by [`C12Context::load`](crate::C12Context::load) and
+/// [`C12Context::save`](crate::C12Context::save), so embedders can serve
+/// configs from memory, archives or a sandbox.
+pub trait FileSystem: fmt::Debug + Send + Sync {
+    fn read_to_string(&self, path: &FsPath) -> io::Result<String>;
+    fn write(&self, path: &FsPath, contents: &str) -> io::Result<()>;
+    fn exists(&self, path: &FsPath) -> bool;
+
+    /// Writes `contents` so readers see either the old file or the whole
+    /// new one, never a partial write. The default just calls
+    /// [`write`](Self::write), for file systems where writes are atomic
+    /// anyway.
+    fn write_atomic(&self, path: &FsPath, contents: &str) -> io::Result<()> {
+        self.write(path, contents)
+    }
+}
+
+/// The real file system, through `std::fs`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OsFileSystem;
+
+impl FileSystem for OsFileSystem {
+    fn read_to_string(&self, path: &FsPath) -> io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+
+    fn write(&self, path: &FsPath, contents: &str) -> io::Result<()> {
+        std::fs::write(path, contents)
+    }
+
+    fn exists(&self, path: &FsPath) -> bool {
+        path.exists()
+    }
+
+    /// Writes a temporary file next to `path` and renames it over `path`.
+    fn write_atomic(&self, path: &FsPath, contents: &str) -> io::Result<()> {
+        let name = path.file_name().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a file path", path.display()),
+            )
+        })?;
+        let temp = path.with_file_name(format!(".{}.c12-tmp", name.to_string_lossy()));
+        std::fs::write(&temp, contents)?;
+        std::fs::rename(&temp, path).inspect_err(|_| {
+            let _ = std::fs::remove_file(&temp);
+        })
+    }
+}
+
+/// An in-memory file system, for tests and for configs that never touch
+/// the disk.
+#[derive(Debug, Default)]
+pub struct MemoryFileSystem {
+    files: Mutex<BTreeMap<PathBuf, String>>,
+}
+
+impl MemoryFileSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or replaces a file.
+    pub fn with_file(self, path: impl Into<PathBuf>, contents: impl Into<String>) -> Self {
+        self.lock().insert(path.into(), contents.into());
+        self
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<PathBuf, String>> {
+        // A panic while holding the lock cannot leave the map half-updated.
+        self.files.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn read_to_string(&self, path: &FsPath) -> io::Result<String> {
+        self.lock().get(path).cloned().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} does not exist", path.display()),
+            )
+        })
+    }
+
+    fn write(&self, path: &FsPath, contents: &str) -> io::Result<()> {
+        self.lock().insert(path.to_path_buf(), contents.to_string());
+        Ok(())
+    }
+
+    fn exists(&self, path: &FsPath) -> bool {
+        self.lock().contains_key(path)
+    }
+}
+
+/// Fetches remote configs, such as the URLs a config `extends`.
+pub trait Fetcher: fmt::Debug + Send + Sync {
+    /// The body of `url`, failing on anything but a successful response.
+    fn fetch(&self, url: &str) -> io::Result<String>;
+}
+
+/// Fetches with the `curl` command, following redirects to `https://`
+/// only, so the crate carries no HTTP or TLS stack of its own. Never
+/// used unless passed to [`C12Context::fetcher`](crate::C12Context::fetcher);
+/// the `http` feature provides `HttpFetcher` instead.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CurlFetcher;
+
+impl Fetcher for CurlFetcher {
+    fn fetch(&self, url: &str) -> io::Result<String> {
+        let output = Command::new("curl")
+            .args(["--fail", "--silent", "--show-error", "--location"])
+            .args(["--proto", "=https,http", "--proto-redir", "=https", "--"])
+            .arg(url)
+            .output()
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::NotFound => io::Error::new(
+                    e.kind(),
+                    format!("fetching {url} needs `curl`, which is not installed"),
+                ),
+                _ => e,
+            })?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(io::Error::other(format!(
+                "fetching {url} failed: {}",
+                stderr.trim()
+            )));
+        }
+        String::from_utf8(output.stdout)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("{url} is not UTF-8")))
+    }
+}
+
+/// Fetches with the `ureq` HTTP client, following redirects to `https://`
+/// only. The default fetcher of a [`C12Context`](crate::C12Context) when
+/// the `http` feature is enabled.
+#[cfg(feature = "http")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HttpFetcher;
+
+#[cfg(feature = "http")]
+impl Fetcher for HttpFetcher {
+    fn fetch(&self, url: &str) -> io::Result<String> {
+        let config = ureq::Agent::config_builder()
+            .https_only(url.starts_with("https://"))
+            .build();
+        let failed = |e: ureq::Error| io::Error::other(format!("fetching {url} failed: {e}"));
+        let mut response = ureq::Agent::new_with_config(config)
+            .get(url)
+            .call()
+            .map_err(failed)?;
+        response.body_mut().read_to_string().map_err(failed)
+    }
+}
+
+/// The fetcher of a context without the `http` feature, which fetches
+/// nothing: remote configs are opt-in.
+#[cfg(not(feature = "http"))]
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct NoFetcher;
+
+#[cfg(not(feature = "http"))]
+impl Fetcher for NoFetcher {
+    fn fetch(&self, url: &str) -> io::Result<String> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!(
+                "cannot fetch {url}: enable the `http` feature or pass a fetcher, \
+                 such as `CurlFetcher`, to `C12Context::fetcher`"
+            ),
+        ))
+    }
+}
+
+/// `HttpFetcher` with the `http` feature, a fetcher refusing every URL
+/// without.
+pub(crate) fn default_fetcher() -> Arc<dyn Fetcher> {
+    #[cfg(feature = "http")]
+    return Arc::new(HttpFetcher);
+    #[cfg(not(feature = "http"))]
+    return Arc::new(NoFetcher);
+}
+
+/// Canned responses, for tests. Records every URL fetched; clones share
+/// the record, so a test can keep one after handing the fetcher over.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryFetcher {
+    responses: BTreeMap<String, String>,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MemoryFetcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or replaces the body served for `url`.
+    pub fn with_response(mut self, url: impl Into<String>, body: impl Into<String>) -> Self {
+        self.responses.insert(url.into(), body.into());
+        self
+    }
+
+    /// The URLs fetched so far, in order.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+impl Fetcher for MemoryFetcher {
+    fn fetch(&self, url: &str) -> io::Result<String> {
+        self.requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(url.to_string());
+        self.responses
+            .get(url)
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{url} returned 404")))
+    }
+}
+
+/// The time source behind [`C12Context::today`](crate::C12Context::today).
+pub trait Clock: fmt::Debug + Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+/// The system clock.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock stopped at one instant.
+#[derive(Clone, Copy, Debug)]
+pub struct FixedClock(pub SystemTime);
+
+impl Clock for FixedClock {
+    fn now(&self) -> SystemTime {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_file_system_round_trips() {
+        let fs = MemoryFileSystem::new().with_file("/a.json", "{}");
+        assert!(fs.exists(FsPath::new("/a.json")));
+        assert_eq!(fs.read_to_string(FsPath::new("/a.json")).unwrap(), "{}");
+
+        fs.write(FsPath::new("/b.toml"), "a = 1\n").unwrap();
+        assert_eq!(
+            fs.read_to_string(FsPath::new("/b.toml")).unwrap(),
+            "a = 1\n"
+        );
+
+        let err = fs.read_to_string(FsPath::new("/missing")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn atomic_writes_leave_no_temporary_file() {
+        let dir = std::env::temp_dir().join(format!("c12-io-atomic-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.json");
+        OsFileSystem.write(&path, "{}").unwrap();
+        OsFileSystem.write_atomic(&path, "{ \"a\": 1 }").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{ \"a\": 1 }");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        assert!(OsFileSystem.write_atomic(&dir.join(".."), "").is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(not(feature = "http"))]
+    #[test]
+    fn remote_configs_are_opt_in_without_http() {
+        let err = crate::C12Context::new()
+            .fetch("https://example.com/base.json")
+            .unwrap_err();
+        let err = err.downcast::<io::Error>().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        assert!(err.to_string().contains("`http` feature"));
+    }
+}
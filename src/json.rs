@@ -1,14 +1,49 @@
-use serde::{Serialize, de::DeserializeOwned};
+use serde::Serialize;
+use serde::de::{DeserializeOwned, DeserializeSeed};
+use serde_json::Serializer;
+use serde_json::ser::PrettyFormatter;
 
-use crate::format::{FormatOptions, Formatted, compute_indent};
+use crate::emit::{Dialect, EmitStyle, to_json_string};
+use crate::empty::{empty_value, is_empty_document};
+use crate::format::{
+    Format, FormatOptions, Formatted, IndentStyle, StyleProfile, compute_indent, indent_style,
+};
+use crate::indent::reindent;
+use crate::interpolate::prepared;
+use crate::limits::{Limited, parse_limited};
+use crate::roundtrip::guard;
 
 /// Parses a JSON string into a value, capturing its formatting.
 pub fn parse_json<T>(text: &str, options: Option<FormatOptions>) -> serde_json::Result<Formatted<T>>
+where
+    T: DeserializeOwned,
+{
+    parse_json_limited(text, options).map_err(Limited::into_backend)
+}
+
+/// Like [`parse_json`], keeping a [`LimitExceeded`](crate::LimitExceeded)
+/// typed.
+pub(crate) fn parse_json_limited<T>(
+    text: &str,
+    options: Option<FormatOptions>,
+) -> Result<Formatted<T>, Limited<serde_json::Error>>
 where
     T: DeserializeOwned,
 {
     let opts = options.unwrap_or_default();
-    let value = serde_json::from_str(text)?;
+    let value = if is_empty_document(text, Format::Json) {
+        empty_value::<_, serde_json::Error>(opts.on_empty)?
+    } else if opts.parses_directly() {
+        serde_json::from_str(text)?
+    } else {
+        let value = parse_limited(&opts.limits, |seed| {
+            let mut de = serde_json::Deserializer::from_str(text);
+            let value = seed.deserialize(&mut de)?;
+            de.end()?;
+            Ok(value)
+        })?;
+        prepared::<_, serde_json::Error>(value, &opts)?
+    };
     Ok(Formatted::new(text, value, &opts))
 }
 
@@ -22,33 +57,52 @@ where
 {
     let opts = options.unwrap_or_default();
     let indent = compute_indent(&formatted.format, &opts);
-    let json = serde_json::to_string_pretty(&formatted.value)?;
     let indent_str = " ".repeat(indent);
 
-    let indented = json
-        .lines()
-        .map(|line| {
-            if line.is_empty() {
-                line.to_string()
-            } else {
-                let trimmed = line.trim_start();
-                let mut s = String::with_capacity(indent_str.len() + trimmed.len());
-                s.push_str(&indent_str);
-                s.push_str(trimmed);
-                s
-            }
-        })
-        .collect::<Vec<_>>()
-        .join("\n");
-    Ok(format!(
+    let js_compat = opts.profile == StyleProfile::Confbox;
+    let mut body = if js_compat || opts.line_width.is_some() || opts.escaping != Default::default()
+    {
+        let value = serde_json::to_value(&formatted.value)?;
+        to_json_string(
+            &value,
+            &EmitStyle::for_options(Dialect::Json, indent, &opts),
+        )
+    } else {
+        pretty(&formatted.value, &indent_str)?
+    };
+    if indent_style(&formatted.format, &opts) == IndentStyle::Tabs {
+        body = reindent(&body, Format::Json, IndentStyle::Tabs, indent);
+    }
+
+    let out = format!(
         "{}{}{}",
-        formatted.format.whitespace_start, indented, formatted.format.whitespace_end
-    ))
+        formatted.format.whitespace_start, body, formatted.format.whitespace_end
+    );
+    if opts.verify_round_trip {
+        guard::<_, serde_json::Error>(&formatted.value, &out, |text| {
+            serde_json::from_str(text).map_err(|e| e.to_string())
+        })?;
+    }
+    Ok(out)
+}
+
+/// Pretty-prints `value` with `indent` per nesting level, or on one line
+/// when `indent` is empty, as `JSON.stringify` does for an indent of 0.
+fn pretty<T: Serialize>(value: &T, indent: &str) -> serde_json::Result<String> {
+    if indent.is_empty() {
+        return serde_json::to_string(value);
+    }
+    let mut out = Vec::new();
+    let formatter = PrettyFormatter::with_indent(indent.as_bytes());
+    value.serialize(&mut Serializer::with_formatter(&mut out, formatter))?;
+    // serde_json only writes valid UTF-8.
+    Ok(String::from_utf8(out).expect("serde_json output is UTF-8"))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fixtures::FLOAT;
     use crate::format::{FormatInfo, Formatted};
     use serde_json::Value as JsonValue;
 
@@ -95,7 +149,7 @@ mod tests {
         let formatted = parse_json::<Root>(JSON_FIXTURE, None).unwrap();
         assert!(formatted.value.types.boolean);
         assert_eq!(formatted.value.types.integer, 1);
-        assert!((formatted.value.types.float - 3.14).abs() < f64::EPSILON);
+        assert!((formatted.value.types.float - FLOAT).abs() < f64::EPSILON);
         assert_eq!(formatted.value.types.string, "hello");
         assert_eq!(formatted.value.types.array, vec![1, 2, 3]);
         assert_eq!(formatted.value.types.object["key"].as_str(), Some("value"));
@@ -124,6 +178,7 @@ mod tests {
                 sample: None,
                 whitespace_start: String::new(),
                 whitespace_end: String::new(),
+                yaml_anchors: Vec::new(),
             },
         };
         let out = stringify_json(&formatted, None).unwrap();
@@ -140,17 +195,105 @@ mod tests {
 
         let out = stringify_json(&formatted, Some(opts)).unwrap();
 
-        // 第一行是空行（前导换行），第二行应为带 4 个空格缩进的 "{".
+        // 第一行是空行（前导换行），之后每层嵌套缩进 4 个空格。
         let mut lines = out.lines();
         assert_eq!(lines.next(), Some(""));
-        if let Some(second) = lines.next() {
-            let prefix = &second[..4.min(second.len())];
-            assert_eq!(prefix, "    ");
-        } else {
-            panic!("expected at least two lines in JSON output");
+        assert_eq!(lines.next(), Some("{"));
+        assert_eq!(lines.next(), Some("    \"types\": {"));
+        assert_eq!(lines.next(), Some("        \"boolean\": true,"));
+    }
+
+    #[test]
+    fn json_stringify_keeps_nested_structure() {
+        let text = "{\n\t\"a\": {\n\t\t\"b\": [1]\n\t}\n}";
+        let formatted = parse_json::<JsonValue>(text, None).unwrap();
+        for (indent, expected) in [
+            (
+                3,
+                "{\n   \"a\": {\n      \"b\": [\n         1\n      ]\n   }\n}",
+            ),
+            (0, "{\"a\":{\"b\":[1]}}"),
+        ] {
+            let opts = FormatOptions {
+                indent: Some(indent),
+                ..Default::default()
+            };
+            assert_eq!(stringify_json(&formatted, Some(opts)).unwrap(), expected);
         }
     }
 
+    #[test]
+    fn json_stringify_keeps_or_converts_tab_indentation() {
+        let text = "{\n\t\"a\": {\n\t\t\"b\": [1]\n\t}\n}";
+        let formatted = parse_json::<JsonValue>(text, None).unwrap();
+        assert_eq!(
+            stringify_json(&formatted, None).unwrap(),
+            "{\n\t\"a\": {\n\t\t\"b\": [\n\t\t\t1\n\t\t]\n\t}\n}"
+        );
+
+        let opts = FormatOptions {
+            indent_style: Some(IndentStyle::Spaces),
+            tab_width: 2,
+            ..Default::default()
+        };
+        assert_eq!(
+            stringify_json(&formatted, Some(opts)).unwrap(),
+            "{\n  \"a\": {\n    \"b\": [\n      1\n    ]\n  }\n}"
+        );
+        let spaced = parse_json::<JsonValue>("{\n  \"a\": 1\n}", None).unwrap();
+        let opts = FormatOptions {
+            indent_style: Some(IndentStyle::Tabs),
+            ..Default::default()
+        };
+        assert_eq!(
+            stringify_json(&spaced, Some(opts)).unwrap(),
+            "{\n\t\"a\": 1\n}"
+        );
+    }
+
+    #[test]
+    fn json_stringify_wraps_at_line_width() {
+        let text = "{\n  \"long\": [\"aaaaaaaaaa\", \"bbbbbbbbbb\"],\n  \"short\": [\n    1,\n    2\n  ]\n}";
+        let formatted = parse_json::<JsonValue>(text, None).unwrap();
+        let opts = FormatOptions {
+            line_width: Some(24),
+            ..Default::default()
+        };
+
+        let out = stringify_json(&formatted, Some(opts)).unwrap();
+        assert_eq!(
+            out,
+            "{\n  \"long\": [\n    \"aaaaaaaaaa\",\n    \"bbbbbbbbbb\"\n  ],\n  \"short\": [1, 2]\n}"
+        );
+    }
+
+    #[test]
+    fn json_stringify_applies_escaping_options() {
+        let formatted = parse_json::<JsonValue>(r#"{"url": "https://é.example/"}"#, None).unwrap();
+        let mut opts = FormatOptions::default();
+        opts.escaping.escape_unicode = true;
+        opts.escaping.escape_slash = true;
+
+        let out = stringify_json(&formatted, Some(opts)).unwrap();
+        assert!(out.contains(r#""https:\/\/\u00e9.example\/""#));
+    }
+
+    #[test]
+    fn json_confbox_profile_matches_json_stringify() {
+        let text = "\n{\n    \"a\": [1, 2.0],\n    \"b\": {}\n}\n";
+        let formatted = parse_json::<JsonValue>(text, None).unwrap();
+        let opts = FormatOptions {
+            profile: crate::StyleProfile::Confbox,
+            ..Default::default()
+        };
+
+        let out = stringify_json(&formatted, Some(opts)).unwrap();
+        assert_eq!(
+            out,
+            "\n{\n    \"a\": [\n        1,\n        2\n    ],\n    \"b\": {}\n}\n"
+        );
+    }
+
     #[test]
     fn json_preserves_outer_whitespace() {
         let text = " \n{ \"a\": 1 }\n\t";
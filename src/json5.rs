@@ -1,18 +1,49 @@
 use json5 as json5_crate;
-use serde::{Serialize, de::DeserializeOwned};
+use serde::Serialize;
+use serde::de::{DeserializeOwned, DeserializeSeed, IgnoredAny};
 
-use crate::format::{FormatOptions, Formatted, compute_indent};
+use crate::emit::{Dialect, EmitStyle, to_json_string};
+use crate::empty::{empty_value, is_empty_document};
+use crate::format::{Format, FormatOptions, Formatted, IndentStyle, compute_indent, indent_style};
+use crate::indent::reindent;
+use crate::interpolate::prepared;
+use crate::limits::{Limited, parse_limited};
+use crate::roundtrip::guard;
 
 /// Parses a JSON5 string into a value, capturing its formatting.
 pub fn parse_json5<T>(
     text: &str,
     options: Option<FormatOptions>,
 ) -> Result<Formatted<T>, json5_crate::Error>
+where
+    T: DeserializeOwned,
+{
+    parse_json5_limited(text, options).map_err(Limited::into_backend)
+}
+
+/// Like [`parse_json5`], keeping a [`LimitExceeded`](crate::LimitExceeded)
+/// typed.
+pub(crate) fn parse_json5_limited<T>(
+    text: &str,
+    options: Option<FormatOptions>,
+) -> Result<Formatted<T>, Limited<json5_crate::Error>>
 where
     T: DeserializeOwned,
 {
     let opts = options.unwrap_or_default();
-    let value = json5_crate::from_str(text)?;
+    let value = if is_empty_document(text, Format::Json5) {
+        empty_value::<_, json5_crate::Error>(opts.on_empty)?
+    } else if opts.parses_directly() {
+        json5_crate::from_str(text)?
+    } else {
+        // `json5` keeps its check for trailing text private, so the text is
+        // validated first, without building anything.
+        json5_crate::from_str::<IgnoredAny>(text)?;
+        let value = parse_limited(&opts.limits, |seed| {
+            seed.deserialize(&mut json5_crate::Deserializer::from_str(text))
+        })?;
+        prepared::<_, json5_crate::Error>(value, &opts)?
+    };
     Ok(Formatted::new(text, value, &opts))
 }
 
@@ -25,20 +56,34 @@ where
     T: Serialize,
 {
     let opts = options.unwrap_or_default();
-    let _indent = compute_indent(&formatted.format, &opts);
-    // json5 crate does not currently expose a configurable pretty printer
-    // in the same way as the JS version. We fall back to its default
-    // serialization behavior and only preserve outer whitespace.
-    let json5 = json5_crate::to_string(&formatted.value)?;
-    Ok(format!(
+    let indent = compute_indent(&formatted.format, &opts);
+
+    let value = serde_json::to_value(&formatted.value)
+        .map_err(<json5_crate::Error as serde::ser::Error>::custom)?;
+    let mut body = to_json_string(
+        &value,
+        &EmitStyle::for_options(Dialect::Json5, indent, &opts),
+    );
+    if indent_style(&formatted.format, &opts) == IndentStyle::Tabs {
+        body = reindent(&body, Format::Json5, IndentStyle::Tabs, indent);
+    }
+
+    let out = format!(
         "{}{}{}",
-        formatted.format.whitespace_start, json5, formatted.format.whitespace_end
-    ))
+        formatted.format.whitespace_start, body, formatted.format.whitespace_end
+    );
+    if opts.verify_round_trip {
+        guard::<_, json5_crate::Error>(&formatted.value, &out, |text| {
+            json5_crate::from_str(text).map_err(|e| e.to_string())
+        })?;
+    }
+    Ok(out)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fixtures::FLOAT;
     use serde_json::Value as JsonValue;
 
     const JSON5_FIXTURE: &str = r#"
@@ -84,7 +129,7 @@ mod tests {
         let formatted = parse_json5::<Root>(JSON5_FIXTURE, None).unwrap();
         assert!(formatted.value.types.boolean);
         assert_eq!(formatted.value.types.integer, 1);
-        assert!((formatted.value.types.float - 3.14).abs() < f64::EPSILON);
+        assert!((formatted.value.types.float - FLOAT).abs() < f64::EPSILON);
         assert_eq!(formatted.value.types.string, "hello");
         assert_eq!(formatted.value.types.array, vec![1, 2, 3]);
         assert_eq!(formatted.value.types.object["key"].as_str(), Some("value"));
@@ -96,13 +141,53 @@ mod tests {
     }
 
     #[test]
-    fn json5_stringify_exact_normalized() {
+    fn json5_stringify_exact_fixture() {
         let formatted = parse_json5::<JsonValue>(JSON5_FIXTURE, None).unwrap();
         let out = stringify_json5(&formatted, None).unwrap();
-        let expected: JsonValue = ::json5::from_str(JSON5_FIXTURE).unwrap();
-        let expected_str = ::json5::to_string(&expected).unwrap();
-        let expected_str = format!("\n{}", expected_str);
-        assert_eq!(out.trim(), expected_str.trim());
+        assert_eq!(out, JSON5_FIXTURE);
+    }
+
+    #[test]
+    fn json5_stringify_honors_indent_and_trailing_commas() {
+        let formatted = parse_json5::<JsonValue>("{a: {b: [1]}}", None).unwrap();
+        let opts = FormatOptions {
+            indent: Some(4),
+            trailing_commas: Some(false),
+            ..Default::default()
+        };
+        let out = stringify_json5(&formatted, Some(opts)).unwrap();
+        assert_eq!(
+            out,
+            "{\n    a: {\n        b: [\n            1\n        ]\n    }\n}"
+        );
+    }
+
+    #[test]
+    fn json5_stringify_uses_single_quotes_when_requested() {
+        let formatted = parse_json5::<JsonValue>("{ greeting: \"it's\" }", None).unwrap();
+        let mut opts = FormatOptions::default();
+        opts.escaping.quote_style = Some(crate::QuoteStyle::Single);
+
+        let out = stringify_json5(&formatted, Some(opts)).unwrap();
+        assert_eq!(out, "{\n  greeting: 'it\\'s',\n}");
+        let reparsed: JsonValue = ::json5::from_str(&out).unwrap();
+        assert_eq!(reparsed, formatted.value);
+    }
+
+    #[test]
+    fn json5_confbox_profile_matches_json5_stringify() {
+        let text = "{\n  list: [1, 'two'],\n  quote: \"it's\",\n}";
+        let formatted = parse_json5::<JsonValue>(text, None).unwrap();
+        let opts = FormatOptions {
+            profile: crate::StyleProfile::Confbox,
+            ..Default::default()
+        };
+
+        let out = stringify_json5(&formatted, Some(opts)).unwrap();
+        assert_eq!(
+            out,
+            "{\n  list: [\n    1,\n    'two'\n  ],\n  quote: \"it's\"\n}"
+        );
     }
 
     #[test]
@@ -0,0 +1,421 @@
+use std::fmt;
+use std::str::FromStr;
+
+use jsonc_parser::ParseOptions;
+use jsonc_parser::cst::{CstArray, CstInputValue, CstNode, CstObject, CstRootNode};
+use jsonc_parser::errors::ParseError;
+use serde_json::Value as JsonValue;
+
+use crate::path::{Path, PathSegment};
+
+/// A JSON, JSONC or JSON5 document that keeps comments, whitespace, key
+/// order and quoting through edits, the JSON counterpart of
+/// [`TomlDocument`](crate::TomlDocument).
+///
+/// Edits change only the nodes they touch: a replaced string keeps its
+/// quote character and new entries follow the indentation and trailing
+/// comma style around them, though adding a key to a one-line object
+/// spreads it over several lines. The syntax accepted is JSONC plus the JSON5
+/// extensions `jsonc-parser` understands: unquoted keys other than
+/// `true`, `false` and `null`, single-quoted strings, hexadecimal and `+`
+/// numbers. [`parse_document`](crate::parse_document) refuses JSON5 beyond
+/// that instead of misreading it.
+///
+/// ```
+/// use c12_parser::{Path, parse_json_document};
+///
+/// let text = "{\n  // port to bind\n  \"port\": 8080,\n}\n";
+/// let mut doc = parse_json_document(text)?;
+/// doc.set(&Path::root().key("port"), 9090.into())?;
+/// assert_eq!(doc.to_string(), "{\n  // port to bind\n  \"port\": 9090,\n}\n");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct JsonDocument {
+    // The `jsonc-parser` tree shares its nodes by reference counting, so
+    // cloning it would alias the original. Keep the text and value
+    // instead and build a tree for each edit.
+    text: String,
+    value: JsonValue,
+}
+
+/// Parses JSON, JSONC or JSON5 text into an editable [`JsonDocument`].
+pub fn parse_json_document(text: &str) -> Result<JsonDocument, ParseError> {
+    text.parse()
+}
+
+/// Returned when a [`JsonDocument`] edit cannot be applied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JsonEditError {
+    pub path: Path,
+    pub message: String,
+}
+
+impl fmt::Display for JsonEditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot edit `{}`: {}", self.path, self.message)
+    }
+}
+
+impl std::error::Error for JsonEditError {}
+
+impl FromStr for JsonDocument {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from_root(&CstRootNode::parse(
+            text,
+            &ParseOptions::default(),
+        )?))
+    }
+}
+
+impl fmt::Display for JsonDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl JsonDocument {
+    fn from_root(root: &CstRootNode) -> Self {
+        Self {
+            text: root.to_string(),
+            value: root.to_serde_value().unwrap_or(JsonValue::Null),
+        }
+    }
+
+    /// A fresh tree of the document to edit, whose result
+    /// [`commit`](Self::commit) stores. The text came from a tree, so it
+    /// parses again.
+    fn tree(&self) -> Result<CstRootNode, String> {
+        CstRootNode::parse(&self.text, &ParseOptions::default()).map_err(|e| e.to_string())
+    }
+
+    fn commit(&mut self, root: &CstRootNode) {
+        *self = Self::from_root(root);
+    }
+
+    /// The whole document in the shared value model; `Null` when empty.
+    pub fn to_value(&self) -> JsonValue {
+        self.value.clone()
+    }
+
+    /// The value at `path`, if present.
+    pub fn get(&self, path: &Path) -> Option<JsonValue> {
+        path.lookup(&self.to_value()).cloned()
+    }
+
+    /// Sets the value at `path`, creating missing objects on the way. A
+    /// replaced string keeps its quote character, and an array index may
+    /// point one past the end to append.
+    pub fn set(&mut self, path: &Path, value: JsonValue) -> Result<(), JsonEditError> {
+        let error = |message: String| JsonEditError {
+            path: path.clone(),
+            message,
+        };
+        let root = self.tree().map_err(error)?;
+        let Some((last, parents)) = path.segments().split_last() else {
+            root.set_value(to_input(&value));
+            self.commit(&root);
+            return Ok(());
+        };
+        let mut node = root
+            .value()
+            .ok_or_else(|| error("the document is empty".into()))?;
+        for segment in parents {
+            node = child(&node, segment, true).map_err(error)?;
+        }
+        match last {
+            PathSegment::Key(key) => {
+                let object = node
+                    .as_object()
+                    .ok_or_else(|| error("parent is not an object".into()))?;
+                match object.get(key).and_then(|prop| prop.value()) {
+                    Some(old) => replace(old, &value),
+                    None => {
+                        object.append(key, to_input(&value));
+                    }
+                }
+            }
+            PathSegment::Index(index) => {
+                let array = node
+                    .as_array()
+                    .ok_or_else(|| error("parent is not an array".into()))?;
+                let elements = array.elements();
+                if *index == elements.len() {
+                    array.append(to_input(&value));
+                } else {
+                    let old = elements
+                        .into_iter()
+                        .nth(*index)
+                        .ok_or_else(|| error(format!("index {index} is out of bounds")))?;
+                    replace(old, &value);
+                }
+            }
+        }
+        self.commit(&root);
+        Ok(())
+    }
+
+    /// Inserts `key` into the object holding `after`, directly after it.
+    /// Fails if `after` is missing or not an object key, or `key` is
+    /// already present.
+    pub fn insert_after(
+        &mut self,
+        after: &Path,
+        key: &str,
+        value: JsonValue,
+    ) -> Result<(), JsonEditError> {
+        let error = |message: String| JsonEditError {
+            path: after.clone(),
+            message,
+        };
+        let root = self.tree().map_err(error)?;
+        let (object, prop) = property(&root, after).map_err(error)?;
+        if object.get(key).is_some() {
+            return Err(error(format!("`{key}` already exists")));
+        }
+        object.insert(prop + 1, key, to_input(&value));
+        self.commit(&root);
+        Ok(())
+    }
+
+    /// Removes the value at `path`, returning it. Comments on lines of
+    /// their own are kept.
+    pub fn remove(&mut self, path: &Path) -> Option<JsonValue> {
+        let (last, parents) = path.segments().split_last()?;
+        let old = self.get(path)?;
+        let root = self.tree().ok()?;
+        let mut node = root.value()?;
+        for segment in parents {
+            node = child(&node, segment, false).ok()?;
+        }
+        match last {
+            PathSegment::Key(key) => node.as_object()?.get(key)?.remove(),
+            PathSegment::Index(index) => node
+                .as_array()?
+                .elements()
+                .into_iter()
+                .nth(*index)?
+                .remove(),
+        }
+        self.commit(&root);
+        Some(old)
+    }
+
+    /// Rewrites the number literal at `path` through `edit`, which gets
+    /// and returns its source text, such as `0x1F`.
+    pub(crate) fn rewrite_number(
+        &mut self,
+        path: &Path,
+        edit: impl FnOnce(&str) -> Result<String, String>,
+    ) -> Result<(), JsonEditError> {
+        let error = |message: String| JsonEditError {
+            path: path.clone(),
+            message,
+        };
+        let root = self.tree().map_err(error)?;
+        let mut node = root
+            .value()
+            .ok_or_else(|| error("the document is empty".into()))?;
+        for segment in path.segments() {
+            node = child(&node, segment, false).map_err(error)?;
+        }
+        let number = node
+            .as_number_lit()
+            .ok_or_else(|| error("not a number literal".into()))?;
+        number.set_raw_value(edit(&number.to_string()).map_err(error)?);
+        self.commit(&root);
+        Ok(())
+    }
+
+    /// Runs `edit` on the underlying `jsonc-parser` tree, for edits this
+    /// API does not cover, such as rewriting comments, and keeps the
+    /// result.
+    pub fn edit_root(&mut self, edit: impl FnOnce(&CstRootNode)) -> Result<(), ParseError> {
+        let root = CstRootNode::parse(&self.text, &ParseOptions::default())?;
+        edit(&root);
+        self.commit(&root);
+        Ok(())
+    }
+}
+
+/// The object holding the key `path` ends in, and the key's position.
+fn property(root: &CstRootNode, path: &Path) -> Result<(CstObject, usize), String> {
+    let (PathSegment::Key(key), parents) = path
+        .segments()
+        .split_last()
+        .ok_or("the document root is not a key")?
+    else {
+        return Err("not an object key".into());
+    };
+    let mut node = root.value().ok_or("the document is empty")?;
+    for segment in parents {
+        node = child(&node, segment, false)?;
+    }
+    let object = node.as_object().ok_or("parent is not an object")?;
+    let prop = object
+        .get(key)
+        .ok_or_else(|| format!("`{key}` does not exist"))?
+        .property_index();
+    Ok((object, prop))
+}
+
+fn child(node: &CstNode, segment: &PathSegment, create: bool) -> Result<CstNode, String> {
+    match segment {
+        PathSegment::Key(key) => {
+            let object = node
+                .as_object()
+                .ok_or_else(|| format!("`{key}` is not inside an object"))?;
+            match object.get(key) {
+                Some(prop) => prop.value().ok_or_else(|| format!("`{key}` has no value")),
+                None if create => Ok(object
+                    .append(key, CstInputValue::Object(Vec::new()))
+                    .object_value_or_set()
+                    .into()),
+                None => Err(format!("`{key}` does not exist")),
+            }
+        }
+        PathSegment::Index(index) => node
+            .as_array()
+            .and_then(|array: CstArray| array.elements().into_iter().nth(*index))
+            .ok_or_else(|| format!("index {index} does not exist")),
+    }
+}
+
+/// Replaces `node` with `value`, keeping single quotes on strings.
+fn replace(node: CstNode, value: &JsonValue) {
+    if let (Some(old), JsonValue::String(s)) = (node.as_string_lit(), value)
+        && old.raw_value().starts_with('\'')
+    {
+        old.set_raw_value(single_quoted(s));
+        return;
+    }
+    let value = to_input(value);
+    if let Some(n) = node.as_object() {
+        n.replace_with(value);
+    } else if let Some(n) = node.as_array() {
+        n.replace_with(value);
+    } else if let Some(n) = node.as_string_lit() {
+        n.replace_with(value);
+    } else if let Some(n) = node.as_number_lit() {
+        n.replace_with(value);
+    } else if let Some(n) = node.as_boolean_lit() {
+        n.replace_with(value);
+    } else if let Some(n) = node.as_null_keyword() {
+        n.replace_with(value);
+    } else if let Some(n) = node.as_word_lit() {
+        n.replace_with(value);
+    }
+}
+
+/// A JSON5 single-quoted string literal for `s`.
+fn single_quoted(s: &str) -> String {
+    let double = JsonValue::from(s).to_string();
+    let inner = &double[1..double.len() - 1];
+    format!("'{}'", inner.replace("\\\"", "\"").replace('\'', "\\'"))
+}
+
+fn to_input(value: &JsonValue) -> CstInputValue {
+    match value {
+        JsonValue::Null => CstInputValue::Null,
+        JsonValue::Bool(b) => CstInputValue::Bool(*b),
+        JsonValue::Number(n) => CstInputValue::Number(n.to_string()),
+        JsonValue::String(s) => CstInputValue::String(s.clone()),
+        JsonValue::Array(items) => CstInputValue::Array(items.iter().map(to_input).collect()),
+        JsonValue::Object(map) => CstInputValue::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), to_input(value)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const TSCONFIG: &str = r#"{
+  // Compiler settings
+  "compilerOptions": {
+    "target": "es2020", // runtime
+    "strict": true,
+  },
+  "include": ["src"],
+}
+"#;
+
+    fn path(expr: &str) -> Path {
+        expr.parse().unwrap()
+    }
+
+    #[test]
+    fn edits_touch_only_their_nodes() {
+        let mut doc = parse_json_document(TSCONFIG).unwrap();
+        assert_eq!(doc.to_string(), TSCONFIG);
+        doc.set(&path("compilerOptions.target"), json!("es2022"))
+            .unwrap();
+        doc.set(&path("include[1]"), json!("test")).unwrap();
+        doc.insert_after(&path("compilerOptions.target"), "module", json!("nodenext"))
+            .unwrap();
+        assert_eq!(
+            doc.remove(&path("compilerOptions.strict")),
+            Some(json!(true))
+        );
+        assert_eq!(
+            doc.to_string(),
+            r#"{
+  // Compiler settings
+  "compilerOptions": {
+    "target": "es2022", // runtime
+    "module": "nodenext",
+  },
+  "include": ["src", "test"],
+}
+"#
+        );
+    }
+
+    #[test]
+    fn json5_strings_keep_single_quotes() {
+        let mut doc = parse_json_document("{ name: 'demo', tags: [] }").unwrap();
+        doc.set(&path("name"), json!("it's")).unwrap();
+        doc.set(&path("owner.team"), json!("core")).unwrap();
+        assert_eq!(doc.get(&path("name")), Some(json!("it's")));
+        assert_eq!(doc.get(&path("owner")), Some(json!({ "team": "core" })));
+        assert_eq!(
+            doc.to_string(),
+            "{\n  name: 'it\\'s',\n  tags: [],\n  \"owner\": {\n    \"team\": \"core\"\n  }\n}"
+        );
+        let copy = doc.clone();
+        doc.remove(&path("tags"));
+        assert_eq!(copy.get(&path("tags")), Some(json!([])));
+        assert_eq!(doc.get(&path("tags")), None);
+
+        doc.edit_root(|root| {
+            let owner = root.object_value().unwrap().get("owner").unwrap();
+            owner.remove();
+        })
+        .unwrap();
+        assert_eq!(doc.to_value(), json!({ "name": "it's" }));
+        assert_eq!(copy.get(&path("owner.team")), Some(json!("core")));
+    }
+
+    #[test]
+    fn rejects_impossible_edits() {
+        let mut doc = parse_json_document(TSCONFIG).unwrap();
+        let err = doc.set(&path("include[5]"), json!("x")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot edit `include[5]`: index 5 is out of bounds"
+        );
+        assert!(doc.set(&path("include.first"), json!(1)).is_err());
+        assert!(
+            doc.insert_after(&path("include"), "compilerOptions", json!({}))
+                .is_err()
+        );
+        assert!(doc.insert_after(&path("missing"), "x", json!(1)).is_err());
+        assert_eq!(doc.to_string(), TSCONFIG);
+    }
+}
@@ -1,8 +1,12 @@
 use jsonc_parser::{ParseOptions as JsoncParseOptions, parse_to_serde_value};
 use serde_json::Value as JsonValue;
 
+use crate::empty::empty_value;
 use crate::format::{FormatOptions, Formatted};
+use crate::interpolate::{interpolate_env, process_env};
 use crate::json::stringify_json;
+use crate::limits::enforce_limits;
+use crate::loss::{DataLoss, find_jsonc_comments};
 
 /// Extra options for JSONC parsing.
 #[derive(Clone, Debug, Default)]
@@ -27,7 +31,17 @@ pub fn parse_jsonc(
     };
 
     let value_opt = parse_to_serde_value(text, &parse_opts)?;
-    let value = value_opt.unwrap_or(JsonValue::Null);
+    if fmt_opts.fail_on_loss {
+        DataLoss::check(find_jsonc_comments(text))?;
+    }
+    let mut value = match value_opt {
+        Some(value) => value,
+        None => empty_value::<_, serde_json::Error>(fmt_opts.on_empty)?,
+    };
+    if fmt_opts.interpolate_env {
+        interpolate_env(&mut value, process_env)?;
+    }
+    enforce_limits(&mut value, &fmt_opts.limits)?;
     Ok(Formatted::new(text, value, &fmt_opts))
 }
 
@@ -49,6 +63,7 @@ pub fn stringify_jsonc(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fixtures::FLOAT;
     use serde_json::Value as JsonValue;
 
     const JSONC_FIXTURE: &str = r#"
@@ -93,7 +108,7 @@ mod tests {
         let types = &formatted["types"];
         assert_eq!(types["boolean"], JsonValue::Bool(true));
         assert_eq!(types["integer"], JsonValue::from(1));
-        assert!((types["float"].as_f64().unwrap() - 3.14).abs() < f64::EPSILON);
+        assert!((types["float"].as_f64().unwrap() - FLOAT).abs() < f64::EPSILON);
         assert_eq!(types["string"], JsonValue::from("hello"));
         assert_eq!(types["array"], JsonValue::from(vec![1, 2, 3]));
         assert_eq!(types["object"]["key"], JsonValue::from("value"));
@@ -145,4 +160,18 @@ mod tests {
         let res_ok = parse_jsonc(TRAILING_COMMA, None, Some(opts));
         assert!(res_ok.is_ok());
     }
+
+    #[test]
+    fn jsonc_fail_on_loss_reports_comments() {
+        let opts = FormatOptions {
+            fail_on_loss: true,
+            ..Default::default()
+        };
+        let err = parse_jsonc(JSONC_FIXTURE, Some(opts.clone()), None).unwrap_err();
+        let loss = err.downcast_ref::<DataLoss>().expect("a DataLoss error");
+        assert_eq!(loss.losses.len(), 1);
+        assert_eq!(loss.losses[0].line, Some(3));
+
+        assert!(parse_jsonc(r#"{ "a": 1 }"#, Some(opts), None).is_ok());
+    }
 }
@@ -1,15 +1,249 @@
+// The original tests set options field by field.
+#![cfg_attr(
+    test,
+    allow(
+        clippy::bool_assert_comparison,
+        clippy::field_reassign_with_default,
+        clippy::needless_borrows_for_generic_args
+    )
+)]
+
+mod batch;
+mod beautify;
+#[cfg(any(feature = "node", feature = "python", test))]
+mod binding;
+mod capabilities;
+mod completion;
+mod config_dir;
+mod conflict;
+mod context;
+mod convert;
+mod convert_tree;
+mod debounce;
+mod diagnostic;
+mod diff_render;
+mod doctor;
+mod document;
+mod emit;
+mod emit_toml;
+mod emit_yaml;
+mod empty;
+mod encryption;
+mod env_format;
+mod equivalence;
+mod error;
+mod export;
+pub mod fixtures;
+mod flatten;
 mod format;
+mod front_matter;
+#[cfg(feature = "hcl")]
+mod hcl_format;
+mod history;
+mod hjson;
+mod hooks;
+mod indent;
+#[cfg(feature = "index")]
+mod index;
 mod ini_format;
+mod interpolate;
+mod io;
 mod json;
 mod json5;
+mod json_document;
 mod jsonc;
+mod limits;
+mod lint;
+mod literal;
+mod loader;
+mod locate;
+mod loss;
+mod merge;
+mod minify;
+mod mixed_arrays;
+mod ndjson;
+#[cfg(feature = "node")]
+pub mod node;
+mod normalize;
+mod outline;
+mod ownership;
+mod patch;
+mod path;
+#[cfg(feature = "plist")]
+mod plist_format;
+mod progress;
+mod provenance;
+#[cfg(feature = "python")]
+mod python;
+mod query;
+mod registry;
+mod remote;
+mod repro;
+mod roundtrip;
+mod scaffold;
+mod scalar;
+mod schema;
+mod schema_diff;
+mod sniff;
+mod split;
+mod support_bundle;
+#[cfg(feature = "templates")]
+mod template;
+mod toml_document;
 mod toml_format;
+mod typed_config;
+mod value_diff;
+mod version;
+mod walk;
+mod yaml_aliases;
+mod yaml_document;
 mod yaml_format;
 
-pub use format::{FormatInfo, FormatOptions, Formatted};
-pub use ini_format::{parse_ini, stringify_ini};
+pub use batch::{BatchItem, BatchReport, BatchStats, ItemDiagnostic, ItemOutcome};
+pub use beautify::{BeautifyOptions, Layout, beautify};
+pub use capabilities::{FormatCapabilities, formats};
+pub use completion::{Completion, CompletionModel, completion_model};
+pub use config_dir::{ConfigScope, default_config_path, scoped_config_path};
+pub use conflict::{ConflictRegion, ConflictedConfig, KeyConflict, parse_conflicts};
+pub use context::{C12Context, DeterminismError, Nondeterminism};
+pub use convert::{
+    ConversionReport, ConvertOptions, DateConversion, DateNormalization, DatetimePolicy,
+    NullPolicy, convert, convert_with_report,
+};
+pub use convert_tree::{
+    ConvertFilters, ConvertReport, Converted, convert_tree, convert_tree_with_progress,
+};
+pub use debounce::{DebouncedSaver, SaveError};
+pub use diagnostic::{Code, Diagnostic, MessageArgs, MessageFormatter, fill_template};
+pub use diff_render::{DiffStyle, RenderDiffOptions, render_diff};
+pub use doctor::{Check, DoctorReport, Finding, Severity, doctor, doctor_with_context};
+pub use document::{Document, DocumentEditError, parse_document};
+pub use empty::EmptyDocument;
+pub use encryption::{
+    ENC_PREFIX, EncryptionError, KeyProvider, decrypt_hook, decrypt_values, encrypt_path,
+    is_encrypted,
+};
+pub use env_format::{EnvError, parse_env, stringify_env};
+pub use equivalence::{EquivalenceError, Tolerance, assert_equivalent, assert_equivalent_with};
+pub use error::{C12Error, C12ErrorKind, parse_format};
+pub use export::{ExportOptions, export_effective, render_effective};
+pub use flatten::{DottedKeys, FlattenConflict, escape_key, flatten, split_key, unflatten};
+pub use format::{
+    EscapeOptions, Format, FormatInfo, FormatOptions, Formatted, IndentStyle, QuoteStyle,
+    StyleProfile,
+};
+pub use front_matter::{FrontMatter, parse_front_matter, stringify_front_matter};
+#[cfg(feature = "hcl")]
+pub use hcl_format::parse_hcl;
+pub use history::{DocumentEdit, DocumentHistory};
+pub use hjson::{HjsonError, HjsonOutput, parse_hjson, stringify_hjson};
+pub use hooks::HookResult;
+pub use indent::reindent;
+#[cfg(feature = "index")]
+pub use index::{ConfigIndex, IndexEntry, IndexError, IndexUpdate, QueryMatch};
+pub use ini_format::{IniMap, IniSection, parse_ini, parse_ini_typed, stringify_ini};
+pub use interpolate::{InterpolationError, interpolate_env};
+#[cfg(feature = "http")]
+pub use io::HttpFetcher;
+pub use io::{
+    Clock, CurlFetcher, Fetcher, FileSystem, FixedClock, MemoryFetcher, MemoryFileSystem,
+    OsFileSystem, SystemClock,
+};
 pub use json::{parse_json, stringify_json};
+pub use json_document::{JsonDocument, JsonEditError, parse_json_document};
 pub use json5::{parse_json5, stringify_json5};
 pub use jsonc::{JsoncExtraOptions, parse_jsonc, stringify_jsonc};
+pub use limits::{LimitExceeded, LimitKind, Limits, enforce_limits};
+pub use lint::{KeyIssue, KeyLint, lint_keys, lint_text};
+pub use loader::{ConfigLayer, LayerKind, LoadError, LoadOptions, LoadedConfig, load_config};
+pub use locate::{Location, locate};
+pub use loss::{
+    DataLoss, Loss, LossKind, find_ini_comments, find_jsonc_comments, find_non_finite_numbers,
+    find_toml_comments, find_toml_nulls, find_yaml_comments, find_yaml_tags,
+};
+pub use merge::{ArrayMerge, MergeOptions, deep_merge};
+pub use minify::minify;
+pub use mixed_arrays::{
+    MixedArray, MixedArrayError, MixedArrayStrategy, find_mixed_arrays, normalize_mixed_arrays,
+};
+pub use ndjson::{
+    JsonStream, JsonStreamValue, NdjsonError, NdjsonRecords, parse_json_stream, parse_ndjson,
+    stringify_ndjson,
+};
+pub use normalize::{Normalizer, Normalizers};
+pub use outline::{OutlineItem, OutlineKind, folding_ranges, outline};
+pub use ownership::{ForeignEdit, OwnerRule, OwnershipError, OwnershipParseError, OwnershipPolicy};
+pub use patch::{Patch, PatchError, PatchOp, apply_patch, diff, merge_patch};
+pub use path::{
+    Path, PathError, PathParseError, PathSegment, delete_path, get_path, parse_path, set_path,
+};
+#[cfg(feature = "plist")]
+pub use plist_format::{PlistFormat, parse_plist, stringify_plist};
+pub use progress::{Progress, ProgressCounter};
+pub use provenance::Origin;
+pub use query::{Comparison, Query, QuerySegment};
+pub use registry::{FormatRegistry, ReservedFormatName};
+pub use roundtrip::{RoundTripMismatch, verify_round_trip};
+pub use scaffold::scaffold;
+pub use scalar::{Date, ScalarOptions, parse_date, parse_number, typed_scalar};
+pub use schema::{Schema, Violation, apply_defaults, validate, validate_text};
+pub use schema_diff::{SchemaChange, SchemaChangeKind, diff_schemas, infer_schema};
+pub use sniff::{parse_auto, sniff_format};
+pub use split::{SplitConfig, SplitPart, join, split};
+pub use support_bundle::{REDACTED, SupportBundle, SupportBundleOptions, export_support_bundle};
+#[cfg(feature = "templates")]
+pub use template::{render_template, template_hook};
+pub use toml_document::{TomlDocument, TomlEditError, parse_toml_document};
+pub use toml_edit;
 pub use toml_format::{parse_toml, stringify_toml};
+pub use typed_config::C12Config;
+pub use value_diff::{Change, diff_values, semantic_changed};
+pub use version::{BumpError, BumpLevel, BumpOptions, VersionBump, bump_version};
+pub use walk::{IGNORE_FILES, Walker};
+pub use yaml_aliases::{
+    AliasIssue, AliasIssueKind, AliasLimits, YamlAlias, YamlAliasError, YamlAnchor,
+    check_yaml_aliases, yaml_anchors,
+};
+pub use yaml_document::{YamlDocument, YamlEditError, parse_yaml_document};
 pub use yaml_format::{parse_yaml, stringify_yaml};
+
+/// Derives [`C12Config`](trait@C12Config) from attributes: the config
+/// name, environment prefix, schema file and validation function on the
+/// struct, and defaults on its fields. See [the `c12-derive`
+/// crate](https://docs.rs/c12-derive) for the full list.
+///
+/// ```
+/// use c12_parser::{C12Config, C12Context, LoadOptions, MemoryFileSystem};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize, C12Config)]
+/// #[c12(name = "app", env_prefix = "APP_", validate = "Config::check")]
+/// struct Config {
+///     #[c12(default = 8080)]
+///     port: u16,
+///     #[c12(default = "localhost")]
+///     host: String,
+/// }
+///
+/// impl Config {
+///     fn check(&self) -> Result<(), &'static str> {
+///         if self.port == 0 { Err("port must not be 0") } else { Ok(()) }
+///     }
+/// }
+///
+/// let fs = MemoryFileSystem::new().with_file("app/app.config.yaml", "host: example.com\n");
+/// let config = Config::load_with(LoadOptions {
+///     cwd: "app".into(),
+///     context: C12Context::new().file_system(fs).env([("APP_PORT", "3000")]),
+///     ..Config::load_options()
+/// })?;
+/// assert_eq!((config.host.as_str(), config.port), ("example.com", 3000));
+/// # Ok::<(), c12_parser::LoadError>(())
+/// ```
+#[cfg(feature = "derive")]
+pub use c12_derive::C12Config;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use serde_json::{Map, Value, from_str, to_value};
+}
@@ -0,0 +1,453 @@
+use std::cell::RefCell;
+use std::error::Error;
+use std::fmt;
+
+use serde::Deserialize;
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor,
+};
+use serde_json::{Map, Value as JsonValue};
+
+use crate::path::{Path, shown};
+
+/// Caps on individual values, for parsing untrusted config snippets.
+///
+/// Set through [`FormatOptions::limits`](crate::FormatOptions::limits);
+/// the default imposes no limits.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum length of a string, in characters.
+    pub max_string_len: Option<usize>,
+
+    /// Maximum number of elements in an array.
+    pub max_array_len: Option<usize>,
+
+    /// Cut oversized strings and arrays down to the limit instead of
+    /// failing with a [`LimitExceeded`].
+    pub truncate: bool,
+}
+
+impl Limits {
+    pub(crate) fn is_unbounded(&self) -> bool {
+        self.max_string_len.is_none() && self.max_array_len.is_none()
+    }
+}
+
+/// Which limit a value exceeded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LimitKind {
+    StringLength,
+    ArrayLength,
+}
+
+/// A value over one of the configured [`Limits`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LimitExceeded {
+    /// Path of the value, e.g. `users[3].name`.
+    pub path: Path,
+    pub kind: LimitKind,
+    pub limit: usize,
+    pub actual: usize,
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (what, unit) = match self.kind {
+            LimitKind::StringLength => ("string", "characters"),
+            LimitKind::ArrayLength => ("array", "elements"),
+        };
+        write!(
+            f,
+            "{what} at `{}` has {} {unit}, over the limit of {}",
+            shown(&self.path),
+            self.actual,
+            self.limit
+        )
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+/// Checks (or truncates) every string and array in `value`.
+pub fn enforce_limits(value: &mut JsonValue, limits: &Limits) -> Result<(), LimitExceeded> {
+    visit(value, limits, &Path::root())
+}
+
+/// A backend error, or the [`LimitExceeded`] that stopped the backend
+/// while it was parsing.
+#[derive(Debug)]
+pub(crate) enum Limited<E> {
+    Backend(E),
+    Exceeded(LimitExceeded),
+}
+
+impl<E> From<E> for Limited<E> {
+    fn from(e: E) -> Self {
+        Limited::Backend(e)
+    }
+}
+
+impl<E: de::Error> Limited<E> {
+    /// The error as the backend's own type, for functions that can only
+    /// return that.
+    pub(crate) fn into_backend(self) -> E {
+        match self {
+            Limited::Backend(e) => e,
+            Limited::Exceeded(e) => E::custom(e),
+        }
+    }
+}
+
+impl<E: Error + 'static> From<Limited<E>> for Box<dyn Error> {
+    fn from(e: Limited<E>) -> Self {
+        match e {
+            Limited::Backend(e) => Box::new(e),
+            Limited::Exceeded(e) => Box::new(e),
+        }
+    }
+}
+
+/// Runs `parse` with a seed that builds the value while checking `limits`,
+/// so an oversized array is not built before it is refused.
+///
+/// Backends only pass on the message of an error raised inside them; the
+/// seed keeps the [`LimitExceeded`] aside so it comes back typed.
+pub(crate) fn parse_limited<E>(
+    limits: &Limits,
+    parse: impl FnOnce(LimitedValue<'_>) -> Result<JsonValue, E>,
+) -> Result<JsonValue, Limited<E>> {
+    let exceeded = RefCell::new(None);
+    let root = Path::root();
+    let seed = LimitedValue {
+        limits,
+        exceeded: &exceeded,
+        path: &root,
+    };
+    parse(seed).map_err(|e| match exceeded.take() {
+        Some(exceeded) => Limited::Exceeded(exceeded),
+        None => Limited::Backend(e),
+    })
+}
+
+/// The seed [`parse_limited`] hands to a backend.
+#[derive(Clone, Copy)]
+pub(crate) struct LimitedValue<'a> {
+    limits: &'a Limits,
+    exceeded: &'a RefCell<Option<LimitExceeded>>,
+    path: &'a Path,
+}
+
+impl<'a> LimitedValue<'a> {
+    fn at<'b>(&self, path: &'b Path) -> LimitedValue<'b>
+    where
+        'a: 'b,
+    {
+        LimitedValue { path, ..*self }
+    }
+
+    fn exceeded<E: de::Error>(&self, kind: LimitKind, limit: usize, actual: usize) -> E {
+        let e = LimitExceeded {
+            path: self.path.clone(),
+            kind,
+            limit,
+            actual,
+        };
+        let message = e.to_string();
+        *self.exceeded.borrow_mut() = Some(e);
+        E::custom(message)
+    }
+
+    fn string<E: de::Error>(&self, mut s: String) -> Result<JsonValue, E> {
+        if let Some(limit) = self.limits.max_string_len {
+            let actual = s.chars().count();
+            if actual > limit {
+                if !self.limits.truncate {
+                    return Err(self.exceeded(LimitKind::StringLength, limit, actual));
+                }
+                let end = s.char_indices().nth(limit).map_or(s.len(), |(i, _)| i);
+                s.truncate(end);
+            }
+        }
+        Ok(JsonValue::String(s))
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for LimitedValue<'_> {
+    type Value = JsonValue;
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<JsonValue, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+// Mirrors the `Deserialize` impl of `serde_json::Value`.
+impl<'de> Visitor<'de> for LimitedValue<'_> {
+    type Value = JsonValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("any valid JSON value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<JsonValue, E> {
+        Ok(JsonValue::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<JsonValue, E> {
+        Ok(v.into())
+    }
+
+    fn visit_i128<E: de::Error>(self, v: i128) -> Result<JsonValue, E> {
+        JsonValue::deserialize(de::value::I128Deserializer::new(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<JsonValue, E> {
+        Ok(v.into())
+    }
+
+    fn visit_u128<E: de::Error>(self, v: u128) -> Result<JsonValue, E> {
+        JsonValue::deserialize(de::value::U128Deserializer::new(v))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<JsonValue, E> {
+        Ok(v.into())
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<JsonValue, E> {
+        self.string(v.to_string())
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<JsonValue, E> {
+        self.string(v)
+    }
+
+    fn visit_none<E>(self) -> Result<JsonValue, E> {
+        Ok(JsonValue::Null)
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<JsonValue, D::Error> {
+        self.deserialize(deserializer)
+    }
+
+    fn visit_unit<E>(self) -> Result<JsonValue, E> {
+        Ok(JsonValue::Null)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<JsonValue, A::Error> {
+        let mut items = Vec::new();
+        loop {
+            if self.limits.max_array_len == Some(items.len()) {
+                // Skip the rest without building it, counting for the error.
+                let mut actual = items.len();
+                while seq.next_element::<IgnoredAny>()?.is_some() {
+                    actual += 1;
+                }
+                if actual > items.len() && !self.limits.truncate {
+                    return Err(self.exceeded(LimitKind::ArrayLength, items.len(), actual));
+                }
+                break;
+            }
+            let path = self.path.clone().index(items.len());
+            match seq.next_element_seed(self.at(&path))? {
+                Some(item) => items.push(item),
+                None => break,
+            }
+        }
+        Ok(JsonValue::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<JsonValue, A::Error> {
+        let mut object = Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            let path = self.path.clone().key(key.as_str());
+            let value = map.next_value_seed(self.at(&path))?;
+            object.insert(key, value);
+        }
+        Ok(JsonValue::Object(object))
+    }
+}
+
+/// Applies `limits` to a parsed value and deserializes the result.
+pub(crate) fn limited<T, E>(mut value: JsonValue, limits: &Limits) -> Result<T, E>
+where
+    T: DeserializeOwned,
+    E: de::Error,
+{
+    enforce_limits(&mut value, limits).map_err(E::custom)?;
+    serde_json::from_value(value).map_err(E::custom)
+}
+
+fn visit(value: &mut JsonValue, limits: &Limits, path: &Path) -> Result<(), LimitExceeded> {
+    match value {
+        JsonValue::String(s) => {
+            if let Some(limit) = limits.max_string_len {
+                let actual = s.chars().count();
+                if actual > limit {
+                    if !limits.truncate {
+                        return Err(LimitExceeded {
+                            path: path.clone(),
+                            kind: LimitKind::StringLength,
+                            limit,
+                            actual,
+                        });
+                    }
+                    let end = s.char_indices().nth(limit).map_or(s.len(), |(i, _)| i);
+                    s.truncate(end);
+                }
+            }
+        }
+        JsonValue::Array(items) => {
+            if let Some(limit) = limits.max_array_len
+                && items.len() > limit
+            {
+                if !limits.truncate {
+                    return Err(LimitExceeded {
+                        path: path.clone(),
+                        kind: LimitKind::ArrayLength,
+                        limit,
+                        actual: items.len(),
+                    });
+                }
+                items.truncate(limit);
+            }
+            for (i, item) in items.iter_mut().enumerate() {
+                visit(item, limits, &path.clone().index(i))?;
+            }
+        }
+        JsonValue::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                visit(child, limits, &path.clone().key(key.as_str()))?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::FormatOptions;
+    use serde_json::json;
+
+    fn limits(truncate: bool) -> Limits {
+        Limits {
+            max_string_len: Some(3),
+            max_array_len: Some(2),
+            truncate,
+        }
+    }
+
+    #[test]
+    fn rejects_oversized_values_by_path() {
+        let mut value = json!({ "users": [{ "name": "al" }, { "name": "bobby" }] });
+        let err = enforce_limits(&mut value, &limits(false)).unwrap_err();
+        assert_eq!(
+            err,
+            LimitExceeded {
+                path: "users[1].name".parse().unwrap(),
+                kind: LimitKind::StringLength,
+                limit: 3,
+                actual: 5,
+            }
+        );
+
+        let mut value = json!([1, 2, 3]);
+        let err = enforce_limits(&mut value, &limits(false)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "array at `<root>` has 3 elements, over the limit of 2"
+        );
+    }
+
+    #[test]
+    fn truncates_on_char_boundaries() {
+        let mut value = json!({ "s": "héllo", "a": ["añb", 2, 3] });
+        enforce_limits(&mut value, &limits(true)).unwrap();
+        assert_eq!(value, json!({ "s": "hél", "a": ["añb", 2] }));
+    }
+
+    #[test]
+    fn parse_functions_apply_limits() {
+        let opts = FormatOptions {
+            limits: limits(false),
+            ..Default::default()
+        };
+        let err =
+            crate::parse_json::<JsonValue>(r#"{ "k": "long" }"#, Some(opts.clone())).unwrap_err();
+        assert!(err.to_string().contains("string at `k` has 4 characters"));
+        let err = crate::parse_yaml::<JsonValue>("k: [1, 2, 3]\n", Some(opts.clone())).unwrap_err();
+        assert!(err.to_string().contains("array at `k`"));
+
+        let opts = FormatOptions {
+            limits: limits(true),
+            ..Default::default()
+        };
+        let parsed = crate::parse_toml::<JsonValue>("k = [1, 2, 3]\n", Some(opts)).unwrap();
+        assert_eq!(parsed.value, json!({ "k": [1, 2] }));
+    }
+
+    #[test]
+    fn limit_errors_stay_typed_and_located() {
+        use crate::{C12ErrorKind, Format, parse_env, parse_format};
+
+        let opts = FormatOptions {
+            limits: limits(false),
+            ..Default::default()
+        };
+        let cases = [
+            (
+                Format::Json,
+                "{\n  \"a\": 1,\n  \"k\": [1, 2, 3]\n}",
+                "k",
+                3,
+            ),
+            (Format::Json5, "{\n  a: 1,\n  k: [1, 2, 3],\n}", "k", 3),
+            (Format::Jsonc, "{\n  // c\n  \"k\": [1, 2, 3]\n}", "k", 3),
+            (Format::Toml, "a = 1\nk = [1, 2, 3]\n", "k", 2),
+            (Format::Yaml, "a: 1\nk: [1, 2, 3]\n", "k", 2),
+            (Format::Ini, "[s]\nk = long\n", "s.k", 2),
+        ];
+        for (format, text, path, line) in cases {
+            let err = parse_format::<JsonValue>(text, format, Some(opts.clone())).unwrap_err();
+            let C12ErrorKind::LimitExceeded(exceeded) = &err.kind else {
+                panic!("{format}: {err}");
+            };
+            assert_eq!(exceeded.path.to_string(), path, "{format}");
+            assert_eq!(err.line, line, "{format}");
+
+            let err = crate::registry::parse_as(format, text, opts.clone()).unwrap_err();
+            assert!(err.downcast_ref::<LimitExceeded>().is_some(), "{format}");
+        }
+
+        let err = parse_env::<JsonValue>("A=long\n", Some(opts)).unwrap_err();
+        assert_eq!(err.exceeded.unwrap().kind, LimitKind::StringLength);
+    }
+
+    #[test]
+    fn parsing_with_limits_builds_the_same_values() {
+        let opts = FormatOptions {
+            limits: Limits {
+                max_string_len: Some(100),
+                max_array_len: Some(100),
+                truncate: false,
+            },
+            ..Default::default()
+        };
+        let text =
+            "d = 1979-05-27T07:32:00Z\nn = 18446744073709551615\nf = 1.5\n[t]\na = [true, \"x\"]\n";
+        assert_eq!(
+            crate::parse_toml::<JsonValue>(text, Some(opts.clone()))
+                .unwrap()
+                .value,
+            crate::parse_toml::<JsonValue>(text, None).unwrap().value
+        );
+        let text = "a: ~\nb: [1, -2, 0.5, yes]\nc: {d: e}\n";
+        assert_eq!(
+            crate::parse_yaml::<JsonValue>(text, Some(opts.clone()))
+                .unwrap()
+                .value,
+            crate::parse_yaml::<JsonValue>(text, None).unwrap().value
+        );
+        assert!(crate::parse_json5::<JsonValue>("{a: 1} x", Some(opts)).is_err());
+    }
+}
@@ -0,0 +1,226 @@
+use std::fmt;
+
+use serde_json::{Map, Value as JsonValue};
+
+use crate::error::{C12Error, parse_format};
+use crate::format::Format;
+use crate::locate::{Location, locate};
+use crate::path::Path;
+
+/// Why a key is likely to cause trouble downstream.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeyIssue {
+    /// The key is empty.
+    Empty,
+    /// The key contains whitespace, which breaks unquoted keys in TOML,
+    /// INI and env-var mappings.
+    Whitespace,
+    /// The key contains control characters.
+    ControlCharacter,
+    /// Unquoted, the key reads as a YAML 1.1 boolean, null or number
+    /// (`no`, `on`, `~`, `1e3`, ...).
+    AmbiguousYamlScalar,
+    /// Another key in the same object is equal after case normalization,
+    /// so the two collide in case-insensitive consumers (env vars, INI).
+    CaseCollision { other: String },
+}
+
+/// A key flagged by [`lint_keys`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyLint {
+    /// Path to the flagged key, e.g. `server["bind address"]`.
+    pub path: Path,
+    pub key: String,
+    pub issue: KeyIssue,
+    /// A safer spelling of the key, when one can be derived.
+    pub suggestion: Option<String>,
+    /// Where the key is written, when linted from text with
+    /// [`lint_text`].
+    pub location: Option<Location>,
+}
+
+impl fmt::Display for KeyLint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let key = self.key.escape_debug();
+        match &self.issue {
+            KeyIssue::Empty => write!(f, "the key is empty")?,
+            KeyIssue::Whitespace => write!(f, "the key `{key}` contains whitespace")?,
+            KeyIssue::ControlCharacter => {
+                write!(f, "the key `{key}` contains control characters")?;
+            }
+            KeyIssue::AmbiguousYamlScalar => write!(
+                f,
+                "the key `{key}` reads as a YAML boolean, null or number unless quoted"
+            )?,
+            KeyIssue::CaseCollision { other } => write!(
+                f,
+                "the key `{key}` differs from `{}` only in case",
+                other.escape_debug()
+            )?,
+        }
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, ", consider `{suggestion}`")?;
+        }
+        Ok(())
+    }
+}
+
+const YAML_AMBIGUOUS: &[&str] = &[
+    "y", "n", "yes", "no", "on", "off", "true", "false", "null", "~",
+];
+
+/// Flags keys that are valid but likely to be misread by some consumer.
+pub fn lint_keys(value: &JsonValue) -> Vec<KeyLint> {
+    let mut lints = Vec::new();
+    visit(value, &Path::root(), &mut lints);
+    lints
+}
+
+/// Parses `text` and [`lint_keys`] it, giving each finding the
+/// [`location`](KeyLint::location) of its key.
+///
+/// ```
+/// use c12_parser::{Format, lint_text};
+///
+/// let lints = lint_text("[server]\nport = 80\n\"bind address\" = \"::\"\n", Format::Toml)?;
+/// let at = lints[0].location.unwrap();
+/// assert_eq!((at.line, at.column), (3, 1));
+/// # Ok::<(), c12_parser::C12Error>(())
+/// ```
+pub fn lint_text(text: &str, format: Format) -> Result<Vec<KeyLint>, C12Error> {
+    let parsed = parse_format::<JsonValue>(text, format, None)?;
+    let mut lints = lint_keys(&parsed.value);
+    for lint in &mut lints {
+        lint.location = locate(text, format, &lint.path);
+    }
+    Ok(lints)
+}
+
+fn visit(value: &JsonValue, path: &Path, lints: &mut Vec<KeyLint>) {
+    match value {
+        JsonValue::Object(map) => {
+            lint_object(map, path, lints);
+            for (key, child) in map {
+                visit(child, &path.clone().key(key.as_str()), lints);
+            }
+        }
+        JsonValue::Array(items) => {
+            for (i, child) in items.iter().enumerate() {
+                visit(child, &path.clone().index(i), lints);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn lint_object(map: &Map<String, JsonValue>, path: &Path, lints: &mut Vec<KeyLint>) {
+    let mut push = |key: &str, issue: KeyIssue, suggestion: Option<String>| {
+        lints.push(KeyLint {
+            path: path.clone().key(key),
+            key: key.to_string(),
+            issue,
+            suggestion,
+            location: None,
+        });
+    };
+
+    let keys: Vec<&String> = map.keys().collect();
+    for (i, key) in keys.iter().enumerate() {
+        if key.is_empty() {
+            push(key, KeyIssue::Empty, None);
+            continue;
+        }
+        if key.chars().any(char::is_control) {
+            let cleaned: String = key.chars().filter(|c| !c.is_control()).collect();
+            push(key, KeyIssue::ControlCharacter, Some(cleaned));
+        }
+        if key.chars().any(char::is_whitespace) {
+            let cleaned = key.split_whitespace().collect::<Vec<_>>().join("_");
+            push(key, KeyIssue::Whitespace, Some(cleaned));
+        }
+        if is_ambiguous_yaml_scalar(key) {
+            push(key, KeyIssue::AmbiguousYamlScalar, Some(format!("'{key}'")));
+        }
+        if let Some(other) = keys[..i]
+            .iter()
+            .find(|other| other.to_lowercase() == key.to_lowercase())
+        {
+            push(
+                key,
+                KeyIssue::CaseCollision {
+                    other: other.to_string(),
+                },
+                None,
+            );
+        }
+    }
+}
+
+fn is_ambiguous_yaml_scalar(key: &str) -> bool {
+    let numeric = key.starts_with(|c: char| c.is_ascii_digit() || "+-.".contains(c))
+        && key.parse::<f64>().is_ok();
+    numeric || YAML_AMBIGUOUS.contains(&key.to_lowercase().as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn flags_whitespace_and_control_characters() {
+        let lints = lint_keys(&json!({ "bind address": 1, "tab\u{7}": 2 }));
+        assert_eq!(lints.len(), 2);
+        assert_eq!(lints[0].issue, KeyIssue::Whitespace);
+        assert_eq!(lints[0].suggestion.as_deref(), Some("bind_address"));
+        assert_eq!(lints[0].path.to_string(), r#"["bind address"]"#);
+        assert_eq!(lints[1].issue, KeyIssue::ControlCharacter);
+        assert_eq!(lints[1].suggestion.as_deref(), Some("tab"));
+    }
+
+    #[test]
+    fn flags_yaml_ambiguous_keys() {
+        let lints = lint_keys(&json!({ "features": { "no": true, "On": 1, "1e3": 2, "name": 3 } }));
+        let keys: Vec<_> = lints.iter().map(|l| l.key.as_str()).collect();
+        assert_eq!(keys, ["no", "On", "1e3"]);
+        assert!(
+            lints
+                .iter()
+                .all(|l| l.issue == KeyIssue::AmbiguousYamlScalar)
+        );
+        assert_eq!(lints[0].path.to_string(), "features.no");
+    }
+
+    #[test]
+    fn messages_are_sentences() {
+        let messages: Vec<_> = lint_keys(&json!({ "": 1, "a b": 2, "Port": 3, "port": 4 }))
+            .iter()
+            .map(KeyLint::to_string)
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "the key is empty",
+                "the key `a b` contains whitespace, consider `a_b`",
+                "the key `port` differs from `Port` only in case",
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_case_collisions_inside_arrays() {
+        let lints = lint_keys(&json!({ "servers": [{ "Port": 1, "port": 2 }] }));
+        assert_eq!(
+            lints,
+            vec![KeyLint {
+                path: "servers[0].port".parse().unwrap(),
+                key: "port".into(),
+                issue: KeyIssue::CaseCollision {
+                    other: "Port".into()
+                },
+                suggestion: None,
+                location: None,
+            }]
+        );
+    }
+}
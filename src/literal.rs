@@ -0,0 +1,93 @@
+/// Adds `by` to the integer literal `raw`, written the way `raw` was: its
+/// radix prefix, hex digit case, zero padding, digit grouping with `_` and
+/// explicit `+` sign carry over. Returns the new literal and its value.
+pub(crate) fn incremented(raw: &str, by: i64) -> Result<(String, i64), String> {
+    let not_integer = || format!("`{raw}` is not an integer literal");
+    let (sign, unsigned) = match raw.as_bytes().first() {
+        Some(b'+') => ("+", &raw[1..]),
+        Some(b'-') => ("-", &raw[1..]),
+        _ => ("", raw),
+    };
+    let (prefix, radix) = match unsigned.get(..2) {
+        Some("0x" | "0X") => (&unsigned[..2], 16),
+        Some("0o" | "0O") => (&unsigned[..2], 8),
+        Some("0b" | "0B") => (&unsigned[..2], 2),
+        _ => ("", 10),
+    };
+    let digits = &unsigned[prefix.len()..];
+    let plain: String = digits.chars().filter(|&c| c != '_').collect();
+    if plain.is_empty() || digits.starts_with('_') || digits.ends_with('_') {
+        return Err(not_integer());
+    }
+    let magnitude = i64::from_str_radix(&plain, radix).map_err(|_| not_integer())?;
+    let old = if sign == "-" { -magnitude } else { magnitude };
+    let new = old
+        .checked_add(by)
+        .ok_or_else(|| format!("`{raw}` plus {by} does not fit in 64 bits"))?;
+    if new < 0 && radix != 10 {
+        return Err(format!(
+            "`{raw}` plus {by} is negative, which {prefix} literals cannot hold"
+        ));
+    }
+
+    let mut body = match radix {
+        16 if plain.chars().any(|c| c.is_ascii_uppercase()) => format!("{:X}", new.unsigned_abs()),
+        16 => format!("{:x}", new.unsigned_abs()),
+        8 => format!("{:o}", new.unsigned_abs()),
+        2 => format!("{:b}", new.unsigned_abs()),
+        _ => new.unsigned_abs().to_string(),
+    };
+    // Leading zeros only pad to a fixed width, as in `0x00ff`.
+    if plain.len() > 1 && plain.starts_with('0') && body.len() < plain.len() {
+        body = "0".repeat(plain.len() - body.len()) + &body;
+    }
+    if let Some(group) = digits.rsplit('_').next().filter(|_| digits.contains('_')) {
+        body = grouped(&body, group.len());
+    }
+    let sign = match (new < 0, sign) {
+        (true, _) => "-",
+        (false, "+") => "+",
+        _ => "",
+    };
+    Ok((format!("{sign}{prefix}{body}"), new))
+}
+
+/// `digits` with `_` between groups of `size`, counted from the right.
+fn grouped(digits: &str, size: usize) -> String {
+    let mut out = String::with_capacity(digits.len() * 2);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(size) {
+            out.push('_');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_way_literals_are_written() {
+        let bump = |raw: &str, by: i64| incremented(raw, by).map(|(text, _)| text);
+        assert_eq!(bump("41", 1), Ok("42".into()));
+        assert_eq!(bump("0xFF", 1), Ok("0x100".into()));
+        assert_eq!(bump("0x00ff", 1), Ok("0x0100".into()));
+        assert_eq!(bump("0o17", 1), Ok("0o20".into()));
+        assert_eq!(bump("0b0111", 1), Ok("0b1000".into()));
+        assert_eq!(bump("+5", 2), Ok("+7".into()));
+        assert_eq!(bump("+1", -3), Ok("-2".into()));
+        assert_eq!(bump("-1", 1), Ok("0".into()));
+        assert_eq!(bump("999_999", 1), Ok("1_000_000".into()));
+        assert_eq!(
+            bump("1.5", 1),
+            Err("`1.5` is not an integer literal".into())
+        );
+        assert_eq!(
+            bump("0x0", -1),
+            Err("`0x0` plus -1 is negative, which 0x literals cannot hold".into())
+        );
+        assert!(bump("9223372036854775807", 1).is_err());
+    }
+}
@@ -0,0 +1,1322 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Component, Path as FsPath, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value as JsonValue};
+
+use crate::config_dir::ConfigScope;
+use crate::context::C12Context;
+use crate::env_format::parse_env;
+use crate::flatten::{DottedKeys, split_key, unflatten};
+use crate::interpolate::interpolate_env;
+use crate::merge::{MergeOptions, deep_merge};
+use crate::path::Path;
+use crate::provenance::{Origin, Sources, trace_origins};
+use crate::remote::{RemoteSource, is_url};
+use crate::scalar::{ScalarOptions, typed_scalar};
+use crate::split::EXTENDS;
+
+/// Extensions tried for `<name>.config.<ext>`, in order.
+const CONFIG_EXTENSIONS: [&str; 7] = ["json", "json5", "jsonc", "toml", "yaml", "yml", "ini"];
+
+/// Keys of per-environment blocks, as in c12.
+const ENV_KEYS: [&str; 4] = ["$development", "$production", "$test", "$env"];
+
+/// Options for [`load_config`].
+#[derive(Clone, Debug)]
+pub struct LoadOptions {
+    /// The config name: files are looked up as `<name>.config.<ext>` and
+    /// `.<name>rc`, and under the `<name>` key of `package.json`.
+    pub name: String,
+    /// The directory searched for config files.
+    pub cwd: PathBuf,
+    /// A config file to load instead of searching, relative to `cwd`. A
+    /// missing file is an error.
+    pub config_file: Option<PathBuf>,
+    /// Read `.<name>rc` in `cwd`.
+    pub rc_file: bool,
+    /// Files to look for instead of c12's own search, in order, e.g.
+    /// `["package.json", ".toolrc", ".toolrc.yaml", "tool.config.json"]`.
+    /// See [`load_config`] for how they are searched. Empty, the default,
+    /// keeps c12's search.
+    pub search_places: Vec<String>,
+    /// Read `.<name>rc` in the home directory, below the local one.
+    pub global_rc: bool,
+    /// Read the `<name>` key of `package.json` in `cwd`.
+    pub package_json: bool,
+    /// Read the `package.metadata.<name>` table of `Cargo.toml` in `cwd`.
+    pub cargo_toml: bool,
+    /// Read `config.<ext>` in the user's config directory for `<name>`,
+    /// as [`default_config_path`](crate::default_config_path) finds it,
+    /// below every project file.
+    pub user_config: bool,
+    /// Load the files listed under `extends` in config and rc files.
+    pub extends: bool,
+    /// An existing directory keeping the remote configs `extends` fetches,
+    /// so pinned ones are fetched once and unpinned ones survive going
+    /// offline.
+    pub remote_cache: Option<PathBuf>,
+    /// The active environment, whose `$<env_name>` block is merged over
+    /// the rest of each file. `None` reads `NODE_ENV` through the context,
+    /// as c12 does.
+    pub env_name: Option<String>,
+    /// Expand `${NAME}` and `${NAME:-default}` in the merged config's
+    /// strings from environment variables read through the context, as
+    /// [`interpolate_env`] does.
+    pub interpolate_env: bool,
+    /// Read `.env`, `.env.local`, `.env.<env_name>` and
+    /// `.env.<env_name>.local` in `cwd` before anything reads the
+    /// environment, each file over the ones before it and variables
+    /// already set over them all, as Vite does. The process environment
+    /// itself is left alone.
+    pub dotenv: bool,
+    /// Read environment variables starting with this prefix, e.g.
+    /// `MYAPP_`, as a layer over every file.
+    pub env_prefix: Option<String>,
+    /// Separates nesting levels in environment variable names after the
+    /// prefix, as in `MYAPP_SERVER__PORT`.
+    pub env_separator: String,
+    /// How dots in rc file keys and environment variable names are read.
+    /// [`DottedKeys::Nested`], the default, makes `server.port=80` in an
+    /// rc file and `MYAPP_SERVER.PORT` set `server.port`, as c12 does;
+    /// [`DottedKeys::Literal`] keeps `server.port` as one key.
+    pub dotted_keys: DottedKeys,
+    /// Lowest-priority values, under every file.
+    pub defaults: Option<JsonValue>,
+    /// Highest-priority values, over every file.
+    pub overrides: Option<JsonValue>,
+    /// How layers are merged.
+    pub merge: MergeOptions,
+    /// The file system, hooks and format defaults used to read files.
+    pub context: C12Context,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        Self {
+            name: "config".into(),
+            cwd: PathBuf::from("."),
+            config_file: None,
+            rc_file: true,
+            search_places: Vec::new(),
+            global_rc: false,
+            package_json: true,
+            cargo_toml: true,
+            user_config: false,
+            extends: true,
+            remote_cache: None,
+            env_name: None,
+            interpolate_env: false,
+            dotenv: false,
+            env_prefix: None,
+            env_separator: "__".into(),
+            dotted_keys: DottedKeys::Nested,
+            defaults: None,
+            overrides: None,
+            merge: MergeOptions::default(),
+            context: C12Context::default(),
+        }
+    }
+}
+
+/// Where a [`ConfigLayer`] came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LayerKind {
+    Overrides,
+    /// Environment variables under [`env_prefix`](LoadOptions::env_prefix).
+    Env,
+    ConfigFile,
+    /// A file listed under `extends` by the layer before it.
+    Extended,
+    RcFile,
+    GlobalRc,
+    PackageJson,
+    /// The `tool.<name>` table of `pyproject.toml`, found through
+    /// [`search_places`](LoadOptions::search_places).
+    PyprojectToml,
+    CargoToml,
+    /// `config.<ext>` in the user's config directory, such as
+    /// `$XDG_CONFIG_HOME/<name>`.
+    UserConfig,
+    Defaults,
+}
+
+/// One source merged into a loaded config.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigLayer {
+    pub kind: LayerKind,
+    /// The file read, for layers that come from one.
+    pub path: Option<PathBuf>,
+    pub config: JsonValue,
+}
+
+/// A config resolved by [`load_config`].
+#[derive(Clone, Debug)]
+pub struct LoadedConfig<T> {
+    pub config: T,
+    /// The `<name>.config.<ext>` file found, or the one given.
+    pub config_file: Option<PathBuf>,
+    pub cwd: PathBuf,
+    /// Every source that contributed, highest priority first.
+    pub layers: Vec<ConfigLayer>,
+    origins: HashMap<Path, Origin>,
+}
+
+impl<T> LoadedConfig<T> {
+    /// Where the value at `path` came from: the file and line, the
+    /// environment variable or the programmatic layer that supplied it.
+    /// `None` for paths that are not leaves of the merged config, such
+    /// as non-empty objects.
+    ///
+    /// ```
+    /// use c12_parser::{C12Context, LoadOptions, MemoryFileSystem, load_config};
+    /// use serde_json::{Value, json};
+    ///
+    /// let fs = MemoryFileSystem::new()
+    ///     .with_file("app/app.config.yaml", "server:\n  host: a\n  port: 80\n")
+    ///     .with_file("app/.apprc", "server.port=8080\nserver.tls=true\n");
+    /// let loaded = load_config::<Value>(LoadOptions {
+    ///     name: "app".into(),
+    ///     cwd: "app".into(),
+    ///     defaults: Some(json!({ "debug": false })),
+    ///     context: C12Context::new().file_system(fs),
+    ///     ..LoadOptions::default()
+    /// })?;
+    /// let origin = |path: &str| loaded.origin(&path.parse().unwrap()).unwrap().to_string();
+    /// assert_eq!(origin("server.host"), "app/app.config.yaml:2");
+    /// assert_eq!(origin("server.port"), "app/app.config.yaml:3");
+    /// assert_eq!(origin("server.tls"), "app/.apprc:2");
+    /// assert_eq!(origin("debug"), "defaults");
+    /// # Ok::<(), c12_parser::LoadError>(())
+    /// ```
+    pub fn origin(&self, path: &Path) -> Option<&Origin> {
+        self.origins.get(path)
+    }
+}
+
+/// Returned by [`load_config`] when a source cannot be read or parsed, or
+/// the merged config does not fit the target type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoadError {
+    /// The file at fault, if the error is about one.
+    pub path: Option<PathBuf>,
+    pub message: String,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.path {
+            Some(path) => write!(f, "{}: {}", path.display(), self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl LoadError {
+    fn at(path: &FsPath, message: impl fmt::Display) -> Self {
+        Self {
+            path: Some(path.to_path_buf()),
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Finds, parses and merges a config the way c12 does.
+///
+/// Sources, from highest to lowest priority: `overrides`, environment
+/// variables under `env_prefix`, the first
+/// `<name>.config.{json,json5,jsonc,toml,yaml,yml,ini}` in `cwd` (or
+/// `config_file`), `.<name>rc` in `cwd`, `.<name>rc` in the home directory
+/// (with `global_rc`), the `<name>` key of `package.json`, the
+/// `package.metadata.<name>` table of `Cargo.toml`, `config.<ext>` in the
+/// user's config directory (with `user_config`), and `defaults`. Config
+/// files are read through the context, so its hooks and format defaults
+/// apply. Rc files hold `key=value` lines, with dotted keys nesting and
+/// values read as JSON, booleans or numbers where they can be.
+///
+/// A config or rc file may list files it builds on under `extends`, a
+/// path or an array of paths relative to the file; a path without its
+/// extension is tried with each config extension. Each extended file, and
+/// the files it extends in turn, sits right below the file extending it,
+/// earlier entries over later ones, with the `extends` key itself
+/// dropped. A file extending itself, directly or not, is an error.
+///
+/// `extends` entries may also be remote: an `https://` or `http://` URL,
+/// or `github:owner/repo/path` with an optional `@ref`, fetched through
+/// the context's [`Fetcher`](crate::Fetcher) and read in the format named
+/// by the URL's extension. Paths in a remote config resolve against its
+/// URL. Appending `#sha256=<hex>` pins the body's checksum, and a fetched
+/// body that does not match is an error; plain `http://` URLs must be
+/// pinned. See
+/// [`remote_cache`](LoadOptions::remote_cache) for caching.
+///
+/// With [`search_places`](LoadOptions::search_places), the first place
+/// found is the only file source besides the global rc file, in the
+/// manner of cosmiconfig. Each place is tried in `cwd`, then in each
+/// parent directory up to the first one holding `.git` or
+/// `pnpm-workspace.yaml`, or the root; a relative `cwd` is walked up only
+/// as far as it names. `package.json`, `pyproject.toml` and `Cargo.toml`
+/// count only when they have a `<name>` key, `tool.<name>` table or
+/// `package.metadata.<name>` table. Any place may name the key to read,
+/// as in `deno.json#tool.config`. Files named like `.toolrc`, without an
+/// extension, are rc files.
+///
+/// With [`dotenv`](LoadOptions::dotenv), variables from `.env` files are
+/// read as if they were set, below the ones that are.
+///
+/// Environment variables are read through the context. After the prefix,
+/// each `env_separator` nests a level, as does each dot unless
+/// [`dotted_keys`](LoadOptions::dotted_keys) is literal. Each name part
+/// matches an existing key regardless of case or is lowercased, so
+/// `MYAPP_SERVER__LOGLEVEL` sets `server.logLevel` when the files have it.
+/// Values are read like rc file values.
+///
+/// Files may carry blocks for particular environments: `$development`,
+/// `$production` and `$test`, or any name under `$env`. The block for the
+/// active [`env_name`](LoadOptions::env_name) is merged over the rest of
+/// its file, and every such block is dropped.
+///
+/// ```
+/// use c12_parser::{C12Context, LayerKind, LoadOptions, MemoryFileSystem, load_config};
+/// use serde::Deserialize;
+/// use serde_json::json;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Config {
+///     port: u16,
+///     host: String,
+///     debug: bool,
+/// }
+///
+/// let fs = MemoryFileSystem::new()
+///     .with_file("app/app.config.toml", "port = 8080\n")
+///     .with_file("app/.apprc", "host=example.com\nport=1\n");
+/// let loaded = load_config::<Config>(LoadOptions {
+///     name: "app".into(),
+///     cwd: "app".into(),
+///     defaults: Some(json!({ "debug": false })),
+///     context: C12Context::new().file_system(fs),
+///     ..LoadOptions::default()
+/// })?;
+/// assert_eq!(loaded.config.port, 8080);
+/// assert_eq!(loaded.config.host, "example.com");
+/// assert!(!loaded.config.debug);
+/// assert_eq!(loaded.config_file, Some("app/app.config.toml".into()));
+/// let kinds: Vec<_> = loaded.layers.iter().map(|l| l.kind).collect();
+/// assert_eq!(kinds, [LayerKind::ConfigFile, LayerKind::RcFile, LayerKind::Defaults]);
+/// # Ok::<(), c12_parser::LoadError>(())
+/// ```
+pub fn load_config<T>(mut options: LoadOptions) -> Result<LoadedConfig<T>, LoadError>
+where
+    T: DeserializeOwned,
+{
+    if options.dotenv {
+        options.context = with_dotenv(&options)?;
+    }
+    let context = &options.context;
+    let fs = context.fs();
+    let cwd = options.cwd.clone();
+    let mut layers = Vec::new();
+
+    if let Some(overrides) = &options.overrides {
+        layers.push(ConfigLayer {
+            kind: LayerKind::Overrides,
+            path: None,
+            config: overrides.clone(),
+        });
+    }
+
+    let searching = !options.search_places.is_empty();
+    let mut config_file = match &options.config_file {
+        Some(file) => {
+            let path = cwd.join(file);
+            if !fs.exists(&path) {
+                return Err(LoadError::at(&path, "config file not found"));
+            }
+            Some(path)
+        }
+        None if searching => None,
+        None => CONFIG_EXTENSIONS
+            .iter()
+            .map(|ext| cwd.join(format!("{}.config.{ext}", options.name)))
+            .find(|path| fs.exists(path)),
+    };
+    if let Some(path) = &config_file {
+        let parsed = context.load(path).map_err(|e| LoadError::at(path, e))?;
+        let layer = file_layer(LayerKind::ConfigFile, path, parsed.value, &options)?;
+        push_layer(&mut layers, layer, &options, &mut Vec::new())?;
+    } else if searching && let Some(layer) = search_places(&options)? {
+        config_file = layer.path.clone();
+        push_layer(&mut layers, layer, &options, &mut Vec::new())?;
+    }
+
+    let rc_name = format!(".{}rc", options.name);
+    let home = context
+        .env_var("HOME")
+        .or_else(|| context.env_var("USERPROFILE"))
+        .map(PathBuf::from);
+    let rc_files = [
+        (
+            options.rc_file && !searching,
+            LayerKind::RcFile,
+            Some(cwd.join(&rc_name)),
+        ),
+        (
+            options.global_rc,
+            LayerKind::GlobalRc,
+            home.map(|home| home.join(&rc_name)),
+        ),
+    ];
+    for (enabled, kind, path) in rc_files {
+        if let Some(path) = path.filter(|path| enabled && fs.exists(path)) {
+            let text = fs
+                .read_to_string(&path)
+                .map_err(|e| LoadError::at(&path, e))?;
+            let config = parse_rc(&text, &path, options.dotted_keys)?;
+            let layer = file_layer(kind, &path, config, &options)?;
+            push_layer(&mut layers, layer, &options, &mut Vec::new())?;
+        }
+    }
+
+    let manifests = [
+        (
+            options.package_json,
+            LayerKind::PackageJson,
+            "package.json",
+            "json",
+        ),
+        (
+            options.cargo_toml,
+            LayerKind::CargoToml,
+            "Cargo.toml",
+            "toml",
+        ),
+    ];
+    for (enabled, kind, file, format) in manifests {
+        let path = cwd.join(file);
+        if !enabled || searching || !fs.exists(&path) {
+            continue;
+        }
+        let text = fs
+            .read_to_string(&path)
+            .map_err(|e| LoadError::at(&path, e))?;
+        let manifest = context
+            .parse(format, &text)
+            .map_err(|e| LoadError::at(&path, e))?
+            .value;
+        let section = match kind {
+            LayerKind::CargoToml => manifest.pointer(&format!(
+                "/package/metadata/{}",
+                options.name.replace('~', "~0").replace('/', "~1")
+            )),
+            _ => manifest.get(&options.name),
+        };
+        if let Some(section) = section {
+            let layer = file_layer(kind, &path, section.clone(), &options)?;
+            push_layer(&mut layers, layer, &options, &mut Vec::new())?;
+        }
+    }
+
+    let user_dir = options
+        .user_config
+        .then(|| context.config_path(&options.name, ConfigScope::User));
+    if let Some(path) = user_dir.flatten().and_then(|dir| {
+        CONFIG_EXTENSIONS
+            .iter()
+            .map(|ext| dir.join(format!("config.{ext}")))
+            .find(|path| fs.exists(path))
+    }) {
+        let parsed = context.load(&path).map_err(|e| LoadError::at(&path, e))?;
+        let layer = file_layer(LayerKind::UserConfig, &path, parsed.value, &options)?;
+        push_layer(&mut layers, layer, &options, &mut Vec::new())?;
+    }
+
+    if let Some(defaults) = &options.defaults {
+        layers.push(ConfigLayer {
+            kind: LayerKind::Defaults,
+            path: None,
+            config: defaults.clone(),
+        });
+    }
+
+    let merge = |layers: &[ConfigLayer]| {
+        layers
+            .iter()
+            .rev()
+            .fold(JsonValue::Object(Map::new()), |merged, layer| {
+                deep_merge(&merged, &layer.config, options.merge.clone())
+            })
+    };
+    let mut env_vars = HashMap::new();
+    if let Some(prefix) = &options.env_prefix {
+        let at = usize::from(options.overrides.is_some());
+        let config = env_layer(
+            &context.env_vars(),
+            prefix,
+            &options.env_separator,
+            options.dotted_keys,
+            &merge(&layers[at..]),
+            &mut env_vars,
+        );
+        if config.as_object().is_some_and(|map| !map.is_empty()) {
+            let layer = ConfigLayer {
+                kind: LayerKind::Env,
+                path: None,
+                config,
+            };
+            layers.insert(at, layer);
+        }
+    }
+    let mut merged = merge(&layers);
+    let env_name = active_env(&options);
+    let sources = Sources {
+        texts: layers
+            .iter()
+            .filter_map(|layer| layer.path.as_ref())
+            .filter_map(|path| Some((path.clone(), fs.read_to_string(path).ok()?)))
+            .collect(),
+        env_vars,
+        env_name: env_name.as_deref(),
+        name: &options.name,
+    };
+    let origins = trace_origins(&layers, &merged, &sources);
+    if options.interpolate_env {
+        interpolate_env(&mut merged, |name| context.env_var(name)).map_err(|e| LoadError {
+            path: None,
+            message: e.to_string(),
+        })?;
+    }
+    let config = serde_json::from_value(merged).map_err(|e| LoadError {
+        path: config_file.clone(),
+        message: e.to_string(),
+    })?;
+    Ok(LoadedConfig {
+        config,
+        config_file,
+        cwd,
+        layers,
+        origins,
+    })
+}
+
+/// Files marking the root of a workspace, where searching stops.
+const WORKSPACE_MARKERS: [&str; 2] = [".git", "pnpm-workspace.yaml"];
+
+/// The first of the search places found, walking up from `cwd`.
+fn search_places(options: &LoadOptions) -> Result<Option<ConfigLayer>, LoadError> {
+    let context = &options.context;
+    let fs = context.fs();
+    let mut dir = Some(normalize(&options.cwd));
+    while let Some(current) = dir {
+        for place in &options.search_places {
+            let (file, key) = match place.split_once('#') {
+                Some((file, key)) => (file, Some(key)),
+                None => (place.as_str(), None),
+            };
+            let path = current.join(file);
+            if !fs.exists(&path) {
+                continue;
+            }
+            let name = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or_default();
+            let (kind, section) = match name {
+                "package.json" => (LayerKind::PackageJson, Path::root().key(&options.name)),
+                "pyproject.toml" => (
+                    LayerKind::PyprojectToml,
+                    Path::root().key("tool").key(&options.name),
+                ),
+                "Cargo.toml" => (
+                    LayerKind::CargoToml,
+                    Path::root()
+                        .key("package")
+                        .key("metadata")
+                        .key(&options.name),
+                ),
+                _ => (LayerKind::ConfigFile, Path::root()),
+            };
+            let section = match key {
+                Some(key) => key
+                    .parse::<Path>()
+                    .map_err(|e| LoadError::at(&path, format!("search place `{place}`: {e}")))?,
+                None => section,
+            };
+            let rc = FsPath::new(name).extension().is_none();
+            let value = if rc {
+                let text = fs
+                    .read_to_string(&path)
+                    .map_err(|e| LoadError::at(&path, e))?;
+                parse_rc(&text, &path, options.dotted_keys)?
+            } else {
+                context
+                    .load(&path)
+                    .map_err(|e| LoadError::at(&path, e))?
+                    .value
+            };
+            let kind = if rc { LayerKind::RcFile } else { kind };
+            if let Some(config) = section.lookup(&value) {
+                return file_layer(kind, &path, config.clone(), options).map(Some);
+            }
+        }
+        let root = WORKSPACE_MARKERS
+            .iter()
+            .any(|marker| fs.exists(&current.join(marker)));
+        dir = current
+            .parent()
+            .filter(|parent| !root && *parent != FsPath::new(""))
+            .map(FsPath::to_path_buf);
+    }
+    Ok(None)
+}
+
+/// The context with the variables of the dotenv files in `cwd` laid
+/// under its environment.
+fn with_dotenv(options: &LoadOptions) -> Result<C12Context, LoadError> {
+    let context = &options.context;
+    let fs = context.fs();
+    let mut names = vec![".env".to_string(), ".env.local".to_string()];
+    if let Some(env) = active_env(options) {
+        names.push(format!(".env.{env}"));
+        names.push(format!(".env.{env}.local"));
+    }
+    let mut vars = BTreeMap::new();
+    for name in names {
+        let path = options.cwd.join(name);
+        if !fs.exists(&path) {
+            continue;
+        }
+        let text = fs
+            .read_to_string(&path)
+            .map_err(|e| LoadError::at(&path, e))?;
+        let file = parse_env::<Map<String, JsonValue>>(&text, None)
+            .map_err(|e| LoadError::at(&path, e))?
+            .value;
+        for (key, value) in file {
+            vars.insert(key, value.as_str().unwrap_or_default().to_string());
+        }
+    }
+    vars.extend(context.env_vars());
+    Ok(context.clone().env(vars))
+}
+
+/// The environment whose `$<env>` blocks apply.
+fn active_env(options: &LoadOptions) -> Option<String> {
+    options
+        .env_name
+        .clone()
+        .or_else(|| options.context.env_var("NODE_ENV"))
+}
+
+/// A layer read from `path`, which must hold an object, with the block
+/// for the active environment applied.
+fn file_layer(
+    kind: LayerKind,
+    path: &FsPath,
+    config: JsonValue,
+    options: &LoadOptions,
+) -> Result<ConfigLayer, LoadError> {
+    let JsonValue::Object(mut map) = config else {
+        return Err(LoadError::at(path, "a config must be an object"));
+    };
+    let env_name = active_env(options);
+    let mut blocks = Vec::new();
+    for key in ENV_KEYS {
+        if let Some(block) = map.remove(key) {
+            blocks.push((key, block));
+        }
+    }
+    let mut config = JsonValue::Object(map);
+    if let Some(env) = env_name {
+        for (key, block) in blocks {
+            let block = match key {
+                "$env" => block.get(&env).cloned(),
+                _ => (key[1..] == env).then_some(block),
+            };
+            match block {
+                Some(block @ JsonValue::Object(_)) => {
+                    config = deep_merge(&config, &block, options.merge.clone());
+                }
+                Some(_) => {
+                    return Err(LoadError::at(
+                        path,
+                        format!("the `{key}` block for `{env}` must be an object"),
+                    ));
+                }
+                None => {}
+            }
+        }
+    }
+    Ok(ConfigLayer {
+        kind,
+        path: Some(path.to_path_buf()),
+        config,
+    })
+}
+
+/// Pushes a file layer followed by the files it extends, recursively.
+/// `chain` holds the files extending this one, to catch cycles.
+fn push_layer(
+    layers: &mut Vec<ConfigLayer>,
+    mut layer: ConfigLayer,
+    options: &LoadOptions,
+    chain: &mut Vec<PathBuf>,
+) -> Result<(), LoadError> {
+    let raw_path = layer.path.clone().unwrap_or_default();
+    let remote_base = raw_path.to_str().filter(|path| is_url(path));
+    let path = match remote_base {
+        Some(_) => raw_path.clone(),
+        None => normalize(&raw_path),
+    };
+    let extends = match layer.config.as_object_mut() {
+        Some(map) if options.extends => map.remove(EXTENDS),
+        _ => None,
+    };
+    let files = match extends {
+        None => Vec::new(),
+        Some(JsonValue::String(file)) => vec![file],
+        Some(JsonValue::Array(files)) => files
+            .into_iter()
+            .map(|file| match file {
+                JsonValue::String(file) => Ok(file),
+                _ => Err(LoadError::at(
+                    &path,
+                    format!("`{EXTENDS}` entries must be strings"),
+                )),
+            })
+            .collect::<Result<_, _>>()?,
+        Some(_) => {
+            return Err(LoadError::at(
+                &path,
+                format!("`{EXTENDS}` must be a string or an array"),
+            ));
+        }
+    };
+    layers.push(layer);
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    chain.push(path.clone());
+    let fs = options.context.fs();
+    let dir = path.parent().unwrap_or(FsPath::new(""));
+    for file in files {
+        let remote = RemoteSource::parse(&file, remote_base)
+            .transpose()
+            .map_err(|e| LoadError::at(&path, e))?;
+        let extended = match &remote {
+            Some(remote) => PathBuf::from(&remote.url),
+            None => {
+                let base = normalize(&dir.join(&file));
+                std::iter::once(base.clone())
+                    .chain(CONFIG_EXTENSIONS.iter().map(|ext| {
+                        let mut with_ext = base.clone().into_os_string();
+                        with_ext.push(format!(".{ext}"));
+                        PathBuf::from(with_ext)
+                    }))
+                    .find(|candidate| fs.exists(candidate))
+                    .ok_or_else(|| {
+                        LoadError::at(&path, format!("extended config `{file}` not found"))
+                    })?
+            }
+        };
+        if chain.contains(&extended) {
+            let cycle: Vec<_> = chain
+                .iter()
+                .skip_while(|p| **p != extended)
+                .chain([&extended])
+                .map(|p| p.display().to_string())
+                .collect();
+            return Err(LoadError::at(
+                &path,
+                format!("`{EXTENDS}` cycle: {}", cycle.join(" -> ")),
+            ));
+        }
+        let parsed = match &remote {
+            Some(remote) => {
+                let format = remote
+                    .extension()
+                    .filter(|ext| CONFIG_EXTENSIONS.contains(ext))
+                    .ok_or_else(|| {
+                        LoadError::at(&path, format!("cannot tell the format of `{file}`"))
+                    })?;
+                let text = remote
+                    .fetch(&options.context, options.remote_cache.as_deref())
+                    .map_err(|e| LoadError::at(&path, e))?;
+                options.context.parse(format, &text)
+            }
+            None => options.context.load(&extended),
+        }
+        .map_err(|e| LoadError::at(&extended, e))?;
+        let layer = file_layer(LayerKind::Extended, &extended, parsed.value, options)?;
+        push_layer(layers, layer, options, chain)?;
+    }
+    chain.pop();
+    Ok(())
+}
+
+/// `path` with `.` and `..` components resolved without touching the file
+/// system, so the same file is always spelled the same way.
+fn normalize(path: &FsPath) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(out.components().next_back(), Some(Component::Normal(_))) =>
+            {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// Reads an rc file: `key=value` lines, with dots in keys read by
+/// `dotted_keys` and values read by [`loose_value`].
+fn parse_rc(text: &str, path: &FsPath, dotted_keys: DottedKeys) -> Result<JsonValue, LoadError> {
+    let flat = parse_env::<Map<String, JsonValue>>(text, None)
+        .map_err(|e| LoadError::at(path, e))?
+        .value;
+    let typed = flat
+        .into_iter()
+        .map(|(key, value)| {
+            let value = loose_value(value.as_str().unwrap_or_default());
+            (key, value)
+        })
+        .collect();
+    unflatten(&typed, dotted_keys).map_err(|e| LoadError::at(path, e))
+}
+
+/// A value from an rc file or environment variable: a JSON array or
+/// object, a boolean, `null` or a number where it reads as one, and a
+/// string otherwise.
+fn loose_value(text: &str) -> JsonValue {
+    match text.trim_start().chars().next() {
+        Some('[' | '{') => {
+            serde_json::from_str(text).unwrap_or_else(|_| JsonValue::String(text.to_string()))
+        }
+        _ => typed_scalar(text, &ScalarOptions::default()),
+    }
+}
+
+/// The variables starting with `prefix` as a config, nested at each
+/// `separator`, with name parts matched against the keys of `below`.
+fn env_layer(
+    vars: &[(String, String)],
+    prefix: &str,
+    separator: &str,
+    dotted_keys: DottedKeys,
+    below: &JsonValue,
+    names: &mut HashMap<Path, String>,
+) -> JsonValue {
+    let mut config = JsonValue::Object(Map::new());
+    for (name, value) in vars {
+        let Some(rest) = name.strip_prefix(prefix) else {
+            continue;
+        };
+        let parts: Vec<String> = match dotted_keys {
+            DottedKeys::Literal => rest.split(separator).map(str::to_string).collect(),
+            DottedKeys::Nested => rest.split(separator).flat_map(split_key).collect(),
+        };
+        if parts.iter().any(|part| part.is_empty()) {
+            continue;
+        }
+        let mut existing = Some(below);
+        let mut target = &mut config;
+        let mut path = Path::root();
+        for (i, part) in parts.iter().enumerate() {
+            let lower = part.to_lowercase();
+            let key = existing
+                .and_then(JsonValue::as_object)
+                .and_then(|map| map.keys().find(|key| key.to_lowercase() == lower))
+                .cloned()
+                .unwrap_or(lower);
+            existing = existing.and_then(|value| value.get(&key));
+            path.push(key.as_str());
+            if !target.is_object() {
+                *target = JsonValue::Object(Map::new());
+            }
+            let map = target.as_object_mut().expect("just made an object");
+            if i + 1 == parts.len() {
+                map.insert(key, loose_value(value));
+                names.insert(path, name.clone());
+                break;
+            }
+            target = map
+                .entry(key)
+                .or_insert_with(|| JsonValue::Object(Map::new()));
+        }
+    }
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{MemoryFetcher, MemoryFileSystem};
+    use crate::remote::sha256_hex;
+    use serde_json::json;
+
+    fn options(fs: MemoryFileSystem) -> LoadOptions {
+        LoadOptions {
+            name: "tool".into(),
+            cwd: "/work".into(),
+            context: C12Context::new().file_system(fs),
+            ..LoadOptions::default()
+        }
+    }
+
+    #[test]
+    fn merges_layers_in_c12_priority() {
+        let fs = MemoryFileSystem::new()
+            .with_file("/work/tool.config.yaml", "server:\n  port: 80\n")
+            .with_file(
+                "/work/tool.config.json",
+                "{ \"server\": { \"host\": \"a\" } }",
+            )
+            .with_file(
+                "/work/.toolrc",
+                "server.port=1\nserver.tags=[\"x\"]\nlevel=3\n",
+            )
+            .with_file(
+                "/work/package.json",
+                "{ \"tool\": { \"level\": 0, \"pkg\": true } }",
+            )
+            .with_file(
+                "/work/Cargo.toml",
+                "[package]\nname = \"x\"\n[package.metadata.tool]\ncargo = true\n",
+            );
+        let loaded = load_config::<JsonValue>(LoadOptions {
+            overrides: Some(json!({ "level": 9 })),
+            ..options(fs)
+        })
+        .unwrap();
+        assert_eq!(loaded.config_file, Some("/work/tool.config.json".into()));
+        assert_eq!(
+            loaded.config,
+            json!({
+                "cargo": true,
+                "level": 9,
+                "pkg": true,
+                "server": { "port": 1, "tags": ["x"], "host": "a" }
+            })
+        );
+        let layers: Vec<_> = loaded.layers.iter().map(|l| l.kind).collect();
+        assert_eq!(
+            layers,
+            [
+                LayerKind::Overrides,
+                LayerKind::ConfigFile,
+                LayerKind::RcFile,
+                LayerKind::PackageJson,
+                LayerKind::CargoToml,
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_missing_and_malformed_files() {
+        let fs = MemoryFileSystem::new().with_file("/work/tool.config.toml", "a = [\n");
+        let err = load_config::<JsonValue>(options(fs)).unwrap_err();
+        assert_eq!(err.path, Some("/work/tool.config.toml".into()));
+
+        let err = load_config::<JsonValue>(LoadOptions {
+            config_file: Some("custom.yaml".into()),
+            ..options(MemoryFileSystem::new())
+        })
+        .unwrap_err();
+        assert_eq!(err.to_string(), "/work/custom.yaml: config file not found");
+
+        let empty = load_config::<JsonValue>(options(MemoryFileSystem::new())).unwrap();
+        assert_eq!(empty.config, json!({}));
+        assert!(empty.layers.is_empty() && empty.config_file.is_none());
+    }
+
+    #[test]
+    fn follows_extends_relative_to_each_file() {
+        let fs = MemoryFileSystem::new()
+            .with_file(
+                "/work/tool.config.json",
+                "{ \"extends\": [\"./presets/strict\", \"../shared/base.yaml\"], \"a\": 1 }",
+            )
+            .with_file(
+                "/work/presets/strict.toml",
+                "extends = \"./common.yaml\"\na = 2\nb = 2\n",
+            )
+            .with_file("/work/presets/common.yaml", "b: 3\nc: 3\n")
+            .with_file("/shared/base.yaml", "c: 4\nd: 4\n");
+        let loaded = load_config::<JsonValue>(options(fs)).unwrap();
+        assert_eq!(loaded.config, json!({ "a": 1, "b": 2, "c": 3, "d": 4 }));
+        let paths: Vec<_> = loaded
+            .layers
+            .iter()
+            .map(|l| (l.kind, l.path.clone().unwrap()))
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                (LayerKind::ConfigFile, "/work/tool.config.json".into()),
+                (LayerKind::Extended, "/work/presets/strict.toml".into()),
+                (LayerKind::Extended, "/work/presets/common.yaml".into()),
+                (LayerKind::Extended, "/shared/base.yaml".into()),
+            ]
+        );
+
+        let cyclic = MemoryFileSystem::new()
+            .with_file("/work/.toolrc", "extends=./a.yaml\n")
+            .with_file("/work/a.yaml", "extends: ./b.yaml\n")
+            .with_file("/work/b.yaml", "extends: [./a.yaml]\n");
+        let err = load_config::<JsonValue>(options(cyclic)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "/work/b.yaml: `extends` cycle: /work/a.yaml -> /work/b.yaml -> /work/a.yaml"
+        );
+    }
+
+    #[test]
+    fn manifest_sections_follow_extends() {
+        let fs = MemoryFileSystem::new()
+            .with_file(
+                "/work/package.json",
+                "{ \"tool\": { \"extends\": \"./presets/node.yaml\", \"a\": 1 } }",
+            )
+            .with_file(
+                "/work/Cargo.toml",
+                "[package.metadata.tool]\nextends = [\"./presets/rust\"]\nb = 1\n",
+            )
+            .with_file("/work/presets/node.yaml", "a: 2\nc: 2\n")
+            .with_file("/work/presets/rust.toml", "b = 2\nd = 2\n");
+        let loaded = load_config::<JsonValue>(options(fs)).unwrap();
+        assert_eq!(loaded.config, json!({ "a": 1, "b": 1, "c": 2, "d": 2 }));
+        let kinds: Vec<_> = loaded.layers.iter().map(|l| l.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                LayerKind::PackageJson,
+                LayerKind::Extended,
+                LayerKind::CargoToml,
+                LayerKind::Extended,
+            ]
+        );
+    }
+
+    #[test]
+    fn fetches_pinned_remote_extends_once() {
+        let base = "https://example.com/presets/base.yaml";
+        let body = "extends: ./common.json\nport: 80\n";
+        let pin = sha256_hex(body.as_bytes());
+        let fetcher = MemoryFetcher::new()
+            .with_response(base, body)
+            .with_response(
+                "https://example.com/presets/common.json",
+                "{ \"host\": \"a\" }",
+            );
+        let text = format!("extends = \"{base}#sha256={pin}\"\nport = 8080\n");
+        let fs = MemoryFileSystem::new()
+            .with_file("/work/tool.config.toml", text)
+            .with_file("/cache/.keep", "");
+        let mut opts = LoadOptions {
+            remote_cache: Some("/cache".into()),
+            ..options(fs)
+        };
+        opts.context = opts.context.fetcher(fetcher.clone());
+        let loaded = load_config::<JsonValue>(opts.clone()).unwrap();
+        assert_eq!(loaded.config, json!({ "port": 8080, "host": "a" }));
+        assert_eq!(loaded.layers[1].path, Some(base.into()));
+
+        // The pinned body now comes from the cache, even offline.
+        opts.context = opts.context.fetcher(MemoryFetcher::new());
+        assert!(load_config::<JsonValue>(opts).is_ok_and(|l| l.config["host"] == "a"));
+        assert_eq!(fetcher.requests().len(), 2);
+
+        let tampered = MemoryFetcher::new().with_response(base, "port: 1\n");
+        let fs = MemoryFileSystem::new().with_file(
+            "/work/tool.config.yaml",
+            format!("extends: \"{base}#sha256={pin}\"\n"),
+        );
+        let mut opts = options(fs);
+        opts.context = opts.context.fetcher(tampered);
+        let err = load_config::<JsonValue>(opts).unwrap_err();
+        assert!(err.message.starts_with("checksum mismatch for "), "{err}");
+    }
+
+    #[test]
+    fn applies_the_active_environment_block() {
+        let text = "log: info\ndb: {host: a, port: 1}\n$test: {log: debug}\n$production: {log: warn}\n$env:\n  staging: {db: {host: b}}\n";
+        let load = |env: &str| {
+            let fs = MemoryFileSystem::new().with_file("/work/tool.config.yaml", text);
+            load_config::<JsonValue>(LoadOptions {
+                env_name: Some(env.into()),
+                ..options(fs)
+            })
+            .unwrap()
+            .config
+        };
+        assert_eq!(
+            load("test"),
+            json!({ "log": "debug", "db": { "host": "a", "port": 1 } })
+        );
+        assert_eq!(
+            load("staging"),
+            json!({ "log": "info", "db": { "host": "b", "port": 1 } })
+        );
+        assert_eq!(
+            load("local"),
+            json!({ "log": "info", "db": { "host": "a", "port": 1 } })
+        );
+    }
+
+    #[test]
+    fn interpolates_the_merged_config() {
+        let fs = MemoryFileSystem::new().with_file(
+            "/work/tool.config.json",
+            "{ \"cache\": \"${C12_PARSER_TEST_UNSET:-/tmp}/tool\" }",
+        );
+        let loaded = load_config::<JsonValue>(LoadOptions {
+            interpolate_env: true,
+            defaults: Some(json!({ "home": "${C12_PARSER_TEST_UNSET}" })),
+            overrides: Some(json!({ "home": "$${HOME}" })),
+            ..options(fs)
+        })
+        .unwrap();
+        assert_eq!(
+            loaded.config,
+            json!({ "home": "${HOME}", "cache": "/tmp/tool" })
+        );
+        assert_eq!(
+            loaded.layers[1].config["cache"],
+            "${C12_PARSER_TEST_UNSET:-/tmp}/tool"
+        );
+    }
+
+    #[test]
+    fn reads_prefixed_environment_variables_over_files() {
+        let fs = MemoryFileSystem::new().with_file(
+            "/work/tool.config.yaml",
+            "server:\n  logLevel: info\n  port: 80\nname: app\n",
+        );
+        let context = C12Context::new().file_system(fs).env([
+            ("TOOL_SERVER__PORT", "8080"),
+            ("TOOL_SERVER__LOGLEVEL", "debug"),
+            ("TOOL_FEATURES", "[\"a\", \"b\"]"),
+            ("TOOL_NAME", "api"),
+            ("TOOL_DEBUG", "true"),
+            ("TOOL_", "ignored"),
+            ("OTHER_NAME", "ignored"),
+        ]);
+        let loaded = load_config::<JsonValue>(LoadOptions {
+            env_prefix: Some("TOOL_".into()),
+            overrides: Some(json!({ "name": "forced" })),
+            context,
+            ..options(MemoryFileSystem::new())
+        })
+        .unwrap();
+        assert_eq!(
+            loaded.config,
+            json!({
+                "server": { "logLevel": "debug", "port": 8080 },
+                "name": "forced",
+                "debug": true,
+                "features": ["a", "b"]
+            })
+        );
+        let kinds: Vec<_> = loaded.layers.iter().map(|l| l.kind).collect();
+        assert_eq!(
+            kinds,
+            [LayerKind::Overrides, LayerKind::Env, LayerKind::ConfigFile]
+        );
+
+        let deterministic = C12Context::new().deterministic(true).env([("TOOL_A", "1")]);
+        let loaded = load_config::<JsonValue>(LoadOptions {
+            env_prefix: Some("TOOL_".into()),
+            context: deterministic,
+            ..options(MemoryFileSystem::new())
+        })
+        .unwrap();
+        assert!(loaded.layers.is_empty());
+    }
+
+    #[test]
+    fn reads_dotted_keys_as_chosen() {
+        let load = |dotted_keys| {
+            let fs = MemoryFileSystem::new().with_file("/work/.toolrc", "server.port=80\n");
+            let context = C12Context::new()
+                .file_system(fs)
+                .env([("TOOL_LOG.LEVEL", "debug")]);
+            load_config::<JsonValue>(LoadOptions {
+                env_prefix: Some("TOOL_".into()),
+                dotted_keys,
+                context,
+                ..options(MemoryFileSystem::new())
+            })
+            .unwrap()
+            .config
+        };
+        assert_eq!(
+            load(DottedKeys::Nested),
+            json!({ "server": { "port": 80 }, "log": { "level": "debug" } })
+        );
+        assert_eq!(
+            load(DottedKeys::Literal),
+            json!({ "server.port": 80, "log.level": "debug" })
+        );
+    }
+
+    #[test]
+    fn traces_each_value_to_its_source() {
+        let fs = MemoryFileSystem::new()
+            .with_file(
+                "/work/tool.config.yaml",
+                "extends: ./base.toml\nlog: info\n$production:\n  log: warn\nhosts: [a, b]\n",
+            )
+            .with_file("/work/base.toml", "[db]\nhost = \"x\"\npool = 4\n")
+            .with_file(
+                "/work/package.json",
+                "{\n  \"tool\": {\n    \"color\": true\n  }\n}",
+            );
+        let context = C12Context::new()
+            .file_system(fs)
+            .env([("TOOL_DB__POOL", "8")]);
+        let loaded = load_config::<JsonValue>(LoadOptions {
+            env_name: Some("production".into()),
+            env_prefix: Some("TOOL_".into()),
+            overrides: Some(json!({ "db": { "host": "y" } })),
+            context,
+            ..options(MemoryFileSystem::new())
+        })
+        .unwrap();
+        let origin = |path: &str| {
+            loaded
+                .origin(&path.parse().unwrap())
+                .map(ToString::to_string)
+        };
+        assert_eq!(origin("log").as_deref(), Some("/work/tool.config.yaml:4"));
+        assert_eq!(
+            origin("hosts[1]").as_deref(),
+            Some("/work/tool.config.yaml:5")
+        );
+        assert_eq!(origin("db.host").as_deref(), Some("overrides"));
+        assert_eq!(
+            origin("db.pool").as_deref(),
+            Some("environment variable `TOOL_DB__POOL`")
+        );
+        assert_eq!(origin("color").as_deref(), Some("/work/package.json:3"));
+        assert_eq!(origin("db"), None);
+        assert_eq!(
+            loaded.origin(&"color".parse().unwrap()),
+            Some(&Origin::File {
+                kind: LayerKind::PackageJson,
+                path: "/work/package.json".into(),
+                line: Some(3),
+            })
+        );
+    }
+
+    #[test]
+    fn reads_dotenv_files_under_the_environment() {
+        let fs = MemoryFileSystem::new()
+            .with_file(
+                "/work/tool.config.json",
+                "{ \"url\": \"${DB_HOST}:${DB_PORT}\" }",
+            )
+            .with_file("/work/.env", "DB_HOST=a\nDB_PORT=1\nTOOL_LEVEL=1\n")
+            .with_file("/work/.env.local", "DB_HOST=b\n")
+            .with_file("/work/.env.test", "DB_PORT=2\nTOOL_LEVEL=2\n")
+            .with_file("/work/.env.test.local", "TOOL_LEVEL=3\n")
+            .with_file("/work/.env.production", "DB_HOST=prod\n");
+        let context = C12Context::new()
+            .file_system(fs)
+            .env([("NODE_ENV", "test"), ("DB_PORT", "5432")]);
+        let loaded = load_config::<JsonValue>(LoadOptions {
+            dotenv: true,
+            interpolate_env: true,
+            env_prefix: Some("TOOL_".into()),
+            context,
+            ..options(MemoryFileSystem::new())
+        })
+        .unwrap();
+        assert_eq!(loaded.config, json!({ "url": "b:5432", "level": 3 }));
+    }
+
+    #[test]
+    fn searches_places_up_to_the_workspace_root() {
+        let fs = || {
+            MemoryFileSystem::new()
+                .with_file("/tool.json", "{ \"outside\": true }")
+                .with_file("/repo/.git", "")
+                .with_file("/repo/pyproject.toml", "[tool.tool]\nlevel = 1\n")
+                .with_file("/repo/app/package.json", "{ \"name\": \"app\" }")
+                .with_file("/repo/app/.toolrc", "level=2\n")
+                .with_file(
+                    "/repo/app/deno.json",
+                    "{ \"tasks\": { \"tool\": { \"level\": 3 } } }",
+                )
+        };
+        let load = |places: &[&str]| {
+            load_config::<JsonValue>(LoadOptions {
+                cwd: "/repo/app".into(),
+                search_places: places.iter().map(|p| p.to_string()).collect(),
+                ..options(fs())
+            })
+            .unwrap()
+        };
+        let loaded = load(&["package.json", "pyproject.toml"]);
+        assert_eq!(loaded.config, json!({ "level": 1 }));
+        assert_eq!(loaded.config_file, Some("/repo/pyproject.toml".into()));
+        assert_eq!(loaded.layers[0].kind, LayerKind::PyprojectToml);
+        assert_eq!(
+            loaded
+                .origin(&"level".parse().unwrap())
+                .unwrap()
+                .to_string(),
+            "/repo/pyproject.toml:2"
+        );
+
+        let loaded = load(&[".toolrc", "pyproject.toml"]);
+        assert_eq!(loaded.config, json!({ "level": 2 }));
+        assert_eq!(loaded.layers[0].kind, LayerKind::RcFile);
+        assert_eq!(
+            load(&["deno.json#tasks.tool"]).config,
+            json!({ "level": 3 })
+        );
+        // The repository root stops the search before `/tool.json`.
+        assert_eq!(load(&["tool.json"]).config, json!({}));
+    }
+
+    #[test]
+    fn reads_the_user_config_below_project_files() {
+        let vars = [
+            ("XDG_CONFIG_HOME", "/xdg"),
+            ("HOME", "/home/me"),
+            ("APPDATA", "/appdata"),
+        ];
+        let dir = C12Context::new()
+            .env(vars)
+            .config_path("tool", ConfigScope::User)
+            .unwrap();
+        let fs = || {
+            MemoryFileSystem::new()
+                .with_file("/work/tool.config.json", "{ \"port\": 1 }")
+                .with_file(dir.join("config.toml"), "port = 2\ntheme = \"dark\"\n")
+                .with_file(dir.join("config.yaml"), "ignored: true\n")
+        };
+        let load = |user_config| {
+            load_config::<JsonValue>(LoadOptions {
+                user_config,
+                context: C12Context::new().file_system(fs()).env(vars),
+                ..options(MemoryFileSystem::new())
+            })
+            .unwrap()
+        };
+        let loaded = load(true);
+        assert_eq!(loaded.config, json!({ "port": 1, "theme": "dark" }));
+        assert_eq!(loaded.layers[1].kind, LayerKind::UserConfig);
+        assert_eq!(loaded.layers[1].path, Some(dir.join("config.toml")));
+        assert_eq!(load(false).config, json!({ "port": 1 }));
+    }
+}
@@ -0,0 +1,428 @@
+use std::ops::Range;
+
+use jsonc_parser::ast::{ObjectPropName, Value as AstValue};
+use jsonc_parser::common::Ranged;
+use jsonc_parser::{CollectOptions, ParseOptions, parse_to_ast};
+use serde::de::IgnoredAny;
+use toml_edit::{Item, Value as TomlValue};
+
+use crate::format::Format;
+use crate::path::{Path, PathSegment};
+use crate::yaml_document::YamlDocument;
+
+/// A position in a config text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Location {
+    /// Byte offset in the text.
+    pub offset: usize,
+    /// 1-based line.
+    pub line: usize,
+    /// 1-based column, counted in characters.
+    pub column: usize,
+}
+
+impl Location {
+    /// The location of byte `offset` in `text`.
+    pub fn of_offset(text: &str, offset: usize) -> Self {
+        let offset = offset.min(text.len());
+        let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+        Self {
+            offset,
+            line: text[..offset].matches('\n').count() + 1,
+            column: text[line_start..offset].chars().count() + 1,
+        }
+    }
+}
+
+/// Finds where the value at `path` is written in `text`: the start of its
+/// key inside an object, of the value itself inside an array. `None` when
+/// the text does not parse or has no such path.
+///
+/// ```
+/// use c12_parser::{Format, locate};
+///
+/// let text = "[server]\nhost = \"a\"\nports = [80, 443]\n";
+/// let at = locate(text, Format::Toml, &"server.ports[1]".parse()?).unwrap();
+/// assert_eq!((at.line, at.column), (3, 14));
+/// # Ok::<(), c12_parser::PathParseError>(())
+/// ```
+pub fn locate(text: &str, format: Format, path: &Path) -> Option<Location> {
+    let offset = match format {
+        Format::Json | Format::Jsonc => json_offset(text, path),
+        Format::Json5 => json5_spans(text)?
+            .into_iter()
+            .find_map(|(found, span)| (found == *path).then_some(span.start)),
+        Format::Toml => toml_offset(text, path),
+        Format::Yaml => text.parse::<YamlDocument>().ok()?.entry_offset(path),
+        Format::Ini => ini_offset(text, path),
+    }?;
+    Some(Location::of_offset(text, offset))
+}
+
+fn json_offset(text: &str, path: &Path) -> Option<usize> {
+    let options = ParseOptions::default();
+    let ast = parse_to_ast(text, &CollectOptions::default(), &options).ok()?;
+    let mut value = ast.value?;
+    let mut offset = value.range().start;
+    for segment in path {
+        value = match (segment, value) {
+            (PathSegment::Key(key), AstValue::Object(object)) => {
+                let prop = object.properties.into_iter().find(|prop| {
+                    let name = match &prop.name {
+                        ObjectPropName::String(s) => &s.value,
+                        ObjectPropName::Word(w) => w.value,
+                    };
+                    name == key
+                })?;
+                offset = prop.range.start;
+                prop.value
+            }
+            (PathSegment::Index(index), AstValue::Array(array)) => {
+                let element = array.elements.into_iter().nth(*index)?;
+                offset = element.range().start;
+                element
+            }
+            _ => return None,
+        };
+    }
+    Some(offset)
+}
+
+/// The byte range of every value in JSON5 `text`, the root first and the
+/// rest in document order, starting where [`locate`] reports it and ending
+/// with the value. `jsonc-parser` reads only part of JSON5, so this walks
+/// the text once it is known to be valid.
+pub(crate) fn json5_spans(text: &str) -> Option<Vec<(Path, Range<usize>)>> {
+    json5::from_str::<IgnoredAny>(text).ok()?;
+    let mut scanner = Json5Scanner {
+        text,
+        pos: 0,
+        found: Vec::new(),
+    };
+    scanner.trivia();
+    scanner.entry(Path::root(), scanner.pos)?;
+    Some(scanner.found)
+}
+
+struct Json5Scanner<'a> {
+    text: &'a str,
+    pos: usize,
+    found: Vec<(Path, Range<usize>)>,
+}
+
+impl Json5Scanner<'_> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    /// Skips whitespace and comments.
+    fn trivia(&mut self) {
+        loop {
+            let rest = &self.text[self.pos..];
+            if rest.starts_with("//") {
+                self.pos += rest.find(['\n', '\r']).unwrap_or(rest.len());
+            } else if let Some(comment) = rest.strip_prefix("/*") {
+                self.pos += comment.find("*/").map_or(rest.len(), |end| end + 4);
+            } else if let Some(c) = self
+                .peek()
+                .filter(|c| c.is_whitespace() || *c == '\u{feff}')
+            {
+                self.pos += c.len_utf8();
+            } else {
+                return;
+            }
+        }
+    }
+
+    /// Records the entry at `path`, which starts at `start`, and skips its
+    /// value at the current position.
+    fn entry(&mut self, path: Path, start: usize) -> Option<()> {
+        let index = self.found.len();
+        self.found.push((path.clone(), start..start));
+        self.value(&path)?;
+        self.found[index].1.end = self.pos;
+        Some(())
+    }
+
+    /// Skips the value at the current position, recording the keys and
+    /// elements inside it.
+    fn value(&mut self, path: &Path) -> Option<()> {
+        match self.peek()? {
+            '{' => {
+                self.bump();
+                self.trivia();
+                while self.peek()? != '}' {
+                    let start = self.pos;
+                    let key = match self.peek()? {
+                        quote @ ('"' | '\'') => self.string(quote)?,
+                        _ => self.identifier()?,
+                    };
+                    self.trivia();
+                    (self.bump()? == ':').then_some(())?;
+                    self.trivia();
+                    self.entry(path.clone().key(key), start)?;
+                    self.separator('}')?;
+                }
+                self.bump();
+            }
+            '[' => {
+                self.bump();
+                self.trivia();
+                let mut index = 0;
+                while self.peek()? != ']' {
+                    self.entry(path.clone().index(index), self.pos)?;
+                    self.separator(']')?;
+                    index += 1;
+                }
+                self.bump();
+            }
+            quote @ ('"' | '\'') => {
+                self.string(quote)?;
+            }
+            _ => {
+                while self
+                    .peek()
+                    .is_some_and(|c| !c.is_whitespace() && !matches!(c, ',' | ']' | '}' | '/'))
+                {
+                    self.bump();
+                }
+            }
+        }
+        Some(())
+    }
+
+    /// Skips the comma after an entry, if any, up to `close` or the next
+    /// entry.
+    fn separator(&mut self, close: char) -> Option<()> {
+        self.trivia();
+        if self.peek()? == ',' {
+            self.bump();
+            self.trivia();
+        } else if self.peek()? != close {
+            return None;
+        }
+        Some(())
+    }
+
+    /// An unquoted key, with `\uXXXX` escapes applied.
+    fn identifier(&mut self) -> Option<String> {
+        let mut key = String::new();
+        while let Some(c) = self.peek() {
+            match c {
+                '\\' => {
+                    self.bump();
+                    (self.bump()? == 'u').then_some(())?;
+                    key.push(self.unicode_escape()?);
+                }
+                c if c.is_alphanumeric() || matches!(c, '_' | '$') => {
+                    self.bump();
+                    key.push(c);
+                }
+                _ => break,
+            }
+        }
+        Some(key)
+    }
+
+    /// The string opened by `quote` at the current position, with escapes
+    /// and line continuations applied.
+    fn string(&mut self, quote: char) -> Option<String> {
+        self.bump();
+        let mut out = String::new();
+        loop {
+            let c = self.bump()?;
+            if c == quote {
+                return Some(out);
+            }
+            if c != '\\' {
+                out.push(c);
+                continue;
+            }
+            match self.bump()? {
+                'b' => out.push('\u{8}'),
+                'f' => out.push('\u{c}'),
+                'n' => out.push('\n'),
+                'r' => out.push('\r'),
+                't' => out.push('\t'),
+                'v' => out.push('\u{b}'),
+                '0' => out.push('\0'),
+                'x' => out.push(char::from_u32(self.hex(2)?)?),
+                'u' => out.push(self.unicode_escape()?),
+                '\r' => {
+                    if self.peek() == Some('\n') {
+                        self.bump();
+                    }
+                }
+                '\n' | '\u{2028}' | '\u{2029}' => {}
+                other => out.push(other),
+            }
+        }
+    }
+
+    /// The character of a `\u` escape whose `u` was just read, joining a
+    /// surrogate pair written as two escapes.
+    fn unicode_escape(&mut self) -> Option<char> {
+        let high = self.hex(4)? as u16;
+        let mut units = vec![high];
+        if (0xD800..0xDC00).contains(&high) && self.text[self.pos..].starts_with("\\u") {
+            self.pos += 2;
+            units.push(self.hex(4)? as u16);
+        }
+        char::decode_utf16(units).next()?.ok()
+    }
+
+    fn hex(&mut self, digits: usize) -> Option<u32> {
+        let hex = self.text.get(self.pos..self.pos + digits)?;
+        let value = u32::from_str_radix(hex, 16).ok()?;
+        self.pos += digits;
+        Some(value)
+    }
+}
+
+fn toml_offset(text: &str, path: &Path) -> Option<usize> {
+    let doc = toml_edit::Document::parse(text).ok()?;
+    let mut offset = 0;
+    let mut segments = path.segments().iter();
+    let mut table = doc.as_table();
+    // Tables and arrays of tables are items; anything inside an inline value
+    // continues in `toml_value`.
+    while let Some(segment) = segments.next() {
+        let PathSegment::Key(key) = segment else {
+            return None;
+        };
+        let (key, item) = table.get_key_value(key)?;
+        offset = key
+            .span()
+            .or_else(|| item.span())
+            .map_or(offset, |s| s.start);
+        match item {
+            Item::Table(next) => table = next,
+            Item::ArrayOfTables(tables) => match segments.next() {
+                Some(PathSegment::Index(index)) => {
+                    let next = tables.get(*index)?;
+                    offset = next.span().map_or(offset, |s| s.start);
+                    table = next;
+                }
+                Some(PathSegment::Key(_)) => return None,
+                None => return Some(offset),
+            },
+            Item::Value(value) => return toml_value(value, segments.as_slice(), offset),
+            Item::None => return None,
+        }
+    }
+    Some(offset)
+}
+
+fn toml_value(mut value: &TomlValue, segments: &[PathSegment], mut offset: usize) -> Option<usize> {
+    for segment in segments {
+        value = match (segment, value) {
+            (PathSegment::Key(key), TomlValue::InlineTable(table)) => {
+                let (key, next) = table.get_key_value(key)?;
+                offset = key
+                    .span()
+                    .or_else(|| next.span())
+                    .map_or(offset, |s| s.start);
+                next.as_value()?
+            }
+            (PathSegment::Index(index), TomlValue::Array(array)) => {
+                let next = array.get(*index)?;
+                offset = next.span().map_or(offset, |s| s.start);
+                next
+            }
+            _ => return None,
+        };
+    }
+    Some(offset)
+}
+
+/// Sections are the top-level keys and `default` holds the keys before the
+/// first section, matched case-insensitively like the INI parser.
+fn ini_offset(text: &str, path: &Path) -> Option<usize> {
+    let (section, key) = match path.segments() {
+        [PathSegment::Key(section)] => (section.to_lowercase(), None),
+        [PathSegment::Key(section), PathSegment::Key(key)] => {
+            (section.to_lowercase(), Some(key.to_lowercase()))
+        }
+        _ => return None,
+    };
+    let mut current = String::from("default");
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim();
+        let indent = line.len() - line.trim_start().len();
+        if let Some(name) = trimmed.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            current = name.trim().to_lowercase();
+            if key.is_none() && current == section {
+                return Some(offset + indent);
+            }
+        } else if !trimmed.is_empty() && !trimmed.starts_with([';', '#']) && current == section {
+            let name = trimmed.split_once('=').map_or(trimmed, |(name, _)| name);
+            if key.as_deref() == Some(&name.trim().to_lowercase()) {
+                return Some(offset + indent);
+            }
+        }
+        offset += line.len();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line_column(text: &str, format: Format, path: &str) -> Option<(usize, usize)> {
+        locate(text, format, &path.parse().unwrap()).map(|at| (at.line, at.column))
+    }
+
+    #[test]
+    fn locates_keys_in_every_format() {
+        let json = "{\n  // db\n  \"db\": { \"hosts\": [\"a\", \"b\"] }\n}";
+        assert_eq!(line_column(json, Format::Jsonc, "db"), Some((3, 3)));
+        assert_eq!(
+            line_column(json, Format::Jsonc, "db.hosts[1]"),
+            Some((3, 26))
+        );
+        assert_eq!(line_column(json, Format::Jsonc, "db.port"), None);
+
+        let yaml = "db:\n  hosts:\n    - a\n    - b\n";
+        assert_eq!(line_column(yaml, Format::Yaml, "db.hosts"), Some((2, 3)));
+        assert_eq!(line_column(yaml, Format::Yaml, "db.hosts[1]"), Some((4, 7)));
+
+        let ini = "top = 1\n[DB]\n  host = a\n";
+        assert_eq!(line_column(ini, Format::Ini, "default.top"), Some((1, 1)));
+        assert_eq!(line_column(ini, Format::Ini, "db"), Some((2, 1)));
+        assert_eq!(line_column(ini, Format::Ini, "db.host"), Some((3, 3)));
+    }
+
+    #[test]
+    fn locates_keys_in_full_json5() {
+        let json5 = "// app\n{\n  null: .5,\n  'a\\\nb': [+1, Infinity],\n  \\u0063: 'x',\n}\n";
+        assert_eq!(line_column(json5, Format::Json5, "null"), Some((3, 3)));
+        assert_eq!(
+            locate(json5, Format::Json5, &Path::root().key("ab").index(1)).map(|at| at.line),
+            Some(5)
+        );
+        assert_eq!(line_column(json5, Format::Json5, "c"), Some((6, 3)));
+        assert_eq!(line_column(json5, Format::Json5, ""), Some((2, 1)));
+        assert_eq!(line_column("{ a: }", Format::Json5, "a"), None);
+    }
+
+    #[test]
+    fn locates_toml_tables_and_arrays_of_tables() {
+        let toml =
+            "name = \"x\"\n[db]\nport = 1\n\n[[routes]]\nto = \"a\"\n[[routes]]\nto = \"b\"\n";
+        assert_eq!(line_column(toml, Format::Toml, "name"), Some((1, 1)));
+        assert_eq!(line_column(toml, Format::Toml, "db.port"), Some((3, 1)));
+        assert_eq!(
+            line_column(toml, Format::Toml, "routes[1].to"),
+            Some((8, 1))
+        );
+        assert_eq!(line_column(toml, Format::Toml, "inline"), None);
+    }
+}
@@ -0,0 +1,538 @@
+use std::fmt;
+
+use std::ops::Range;
+
+use jsonc_parser::tokens::Token;
+use jsonc_parser::{ParseOptions, Scanner, ScannerOptions, parse_to_serde_value};
+use serde_json::Value as JsonValue;
+use serde_yaml::Value as YamlValue;
+use toml_edit::{Decor, Item, RawString, Table, Value as TomlValue};
+use yaml_rust2::scanner::{Scanner as YamlScanner, TScalarStyle, Token as YamlToken, TokenType};
+
+use crate::format::Format;
+use crate::path::Path;
+
+/// Information a parse or stringify step cannot carry over.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LossKind {
+    /// A JSONC comment, dropped when the text is parsed into a value.
+    Comment(String),
+    /// A `null`, which TOML cannot represent.
+    Null,
+    /// A YAML tag such as `!secret`, which the JSON data model cannot
+    /// represent.
+    Tag(String),
+    /// A NaN or infinite float, such as TOML's `nan` or YAML's `.inf`,
+    /// which the JSON data model cannot represent. Holds the value as
+    /// Rust prints it: `NaN`, `inf` or `-inf`.
+    NonFiniteNumber(String),
+}
+
+/// One piece of information that would be lost.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Loss {
+    /// Path to the affected value, the root when the loss is not tied to
+    /// one (comments).
+    pub path: Path,
+    /// 1-based line in the source text, when known.
+    pub line: Option<usize>,
+    pub kind: LossKind,
+}
+
+/// Returned instead of silently dropping data when
+/// `FormatOptions::fail_on_loss` is set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DataLoss {
+    pub losses: Vec<Loss>,
+}
+
+impl fmt::Display for Loss {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            LossKind::Comment(text) => write!(f, "comment `{}`", text.trim())?,
+            LossKind::Null => write!(f, "null")?,
+            LossKind::Tag(tag) => write!(f, "tag `{tag}`")?,
+            LossKind::NonFiniteNumber(number) => write!(f, "number `{number}`")?,
+        }
+        if !self.path.is_root() {
+            write!(f, " at `{}`", self.path)?;
+        }
+        if let Some(line) = self.line {
+            write!(f, " on line {line}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DataLoss {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "operation would lose data:")?;
+        for loss in &self.losses {
+            write!(f, " {loss};")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for DataLoss {}
+
+impl DataLoss {
+    /// `Ok` when nothing is lost, otherwise the losses as an error.
+    pub(crate) fn check(losses: Vec<Loss>) -> Result<(), DataLoss> {
+        if losses.is_empty() {
+            Ok(())
+        } else {
+            Err(DataLoss { losses })
+        }
+    }
+}
+
+/// Lists the comments in a JSONC text. Unscannable text yields the
+/// comments found before the error; parsing reports the error itself.
+pub fn find_jsonc_comments(text: &str) -> Vec<Loss> {
+    let mut scanner = Scanner::new(text, &ScannerOptions::default());
+    let mut losses = Vec::new();
+    while let Ok(Some(token)) = scanner.scan() {
+        if let Token::CommentLine(comment) | Token::CommentBlock(comment) = token {
+            losses.push(Loss {
+                path: Path::root(),
+                line: Some(text[..scanner.token_start()].matches('\n').count() + 1),
+                kind: LossKind::Comment(comment.to_string()),
+            });
+        }
+    }
+    losses
+}
+
+/// Lists the comments in a YAML text. Unscannable text yields the
+/// comments found before the error; parsing reports the error itself.
+pub fn find_yaml_comments(text: &str) -> Vec<Loss> {
+    let chars: Vec<char> = text.chars().collect();
+    // Quoted and block scalars may hold a `#` that starts no comment;
+    // anywhere else, a `#` at the start of a line or after whitespace does.
+    let mut scalars = Vec::new();
+    let mut scanner = YamlScanner::new(text.chars());
+    for YamlToken(mark, token) in &mut scanner {
+        if let TokenType::Scalar(style, _) = token
+            && style != TScalarStyle::Plain
+        {
+            scalars.push(scalar_span(&chars, mark.index(), style));
+        }
+    }
+    let end = scanner
+        .get_error()
+        .map_or(chars.len(), |e| e.marker().index().min(chars.len()));
+
+    let mut losses = Vec::new();
+    let mut scalars = scalars.into_iter().peekable();
+    let mut i = 0;
+    while i < end {
+        if let Some(&(start, stop)) = scalars.peek()
+            && i >= start
+        {
+            i = i.max(stop);
+            scalars.next();
+            continue;
+        }
+        let after_space = i == 0 || chars[i - 1].is_whitespace();
+        if chars[i] == '#' && after_space {
+            let line_end = (i..chars.len())
+                .find(|&j| chars[j] == '\n')
+                .unwrap_or(chars.len());
+            let comment: String = chars[i + 1..line_end].iter().collect();
+            losses.push(Loss {
+                path: Path::root(),
+                line: Some(chars[..i].iter().filter(|&&c| c == '\n').count() + 1),
+                kind: LossKind::Comment(comment.trim_end_matches('\r').to_string()),
+            });
+            i = line_end;
+        }
+        i += 1;
+    }
+    losses
+}
+
+/// Lists the comments in a TOML text, read from the whitespace the parser
+/// keeps around keys and values, so a `#` inside a string is not one.
+/// Text that does not parse yields nothing.
+pub fn find_toml_comments(text: &str) -> Vec<Loss> {
+    let Ok(doc) = toml_edit::Document::parse(text) else {
+        return Vec::new();
+    };
+    let mut spans: Vec<_> = doc.trailing().span().into_iter().collect();
+    table_decor_spans(doc.as_table(), &mut spans);
+    spans.sort_by_key(|span| span.start);
+    spans.dedup();
+
+    let mut losses = Vec::new();
+    for span in spans {
+        let mut offset = span.start;
+        for line in text[span].split_inclusive('\n') {
+            if let Some(i) = line.find('#') {
+                losses.push(Loss {
+                    path: Path::root(),
+                    line: Some(text[..offset + i].matches('\n').count() + 1),
+                    kind: LossKind::Comment(line[i + 1..].trim_end_matches(['\n', '\r']).into()),
+                });
+            }
+            offset += line.len();
+        }
+    }
+    losses
+}
+
+fn decor_spans(decor: &Decor, spans: &mut Vec<Range<usize>>) {
+    spans.extend(decor.prefix().and_then(RawString::span));
+    spans.extend(decor.suffix().and_then(RawString::span));
+}
+
+fn table_decor_spans(table: &Table, spans: &mut Vec<Range<usize>>) {
+    decor_spans(table.decor(), spans);
+    for (name, _) in table.iter() {
+        let Some((key, item)) = table.get_key_value(name) else {
+            continue;
+        };
+        decor_spans(key.leaf_decor(), spans);
+        decor_spans(key.dotted_decor(), spans);
+        match item {
+            Item::Value(value) => value_decor_spans(value, spans),
+            Item::Table(table) => table_decor_spans(table, spans),
+            Item::ArrayOfTables(tables) => {
+                for table in tables {
+                    table_decor_spans(table, spans);
+                }
+            }
+            Item::None => {}
+        }
+    }
+}
+
+fn value_decor_spans(value: &TomlValue, spans: &mut Vec<Range<usize>>) {
+    decor_spans(value.decor(), spans);
+    match value {
+        TomlValue::Array(array) => {
+            spans.extend(array.trailing().span());
+            for value in array {
+                value_decor_spans(value, spans);
+            }
+        }
+        TomlValue::InlineTable(table) => {
+            spans.extend(table.trailing().span());
+            for (name, _) in table {
+                if let Some((key, Item::Value(value))) = table.get_key_value(name) {
+                    decor_spans(key.leaf_decor(), spans);
+                    decor_spans(key.dotted_decor(), spans);
+                    value_decor_spans(value, spans);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Lists the comments in an INI text. The INI parser ends a line at the
+/// first `;` or `#`, quoted or not, so each one starts a comment.
+pub fn find_ini_comments(text: &str) -> Vec<Loss> {
+    text.lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let start = line.find([';', '#'])?;
+            Some(Loss {
+                path: Path::root(),
+                line: Some(i + 1),
+                kind: LossKind::Comment(line[start + 1..].to_string()),
+            })
+        })
+        .collect()
+}
+
+/// The characters of the quoted or block scalar the scanner marked at
+/// `start`, where no comment can start. A block scalar's header line can
+/// hold one, so its span starts on the next line.
+fn scalar_span(chars: &[char], start: usize, style: TScalarStyle) -> (usize, usize) {
+    let mut i = start + 1;
+    match style {
+        TScalarStyle::SingleQuoted => {
+            while i < chars.len() {
+                match (chars[i], chars.get(i + 1)) {
+                    ('\'', Some('\'')) => i += 2,
+                    ('\'', _) => return (start, i + 1),
+                    _ => i += 1,
+                }
+            }
+        }
+        TScalarStyle::DoubleQuoted => {
+            while i < chars.len() {
+                match chars[i] {
+                    '\\' => i += 2,
+                    '"' => return (start, i + 1),
+                    _ => i += 1,
+                }
+            }
+        }
+        _ => {
+            // The scanner marks a block scalar at its first content
+            // character, and content runs while lines are blank or
+            // indented as far.
+            let body = chars[..start]
+                .iter()
+                .rposition(|&c| c == '\n')
+                .map_or(0, |j| j + 1);
+            let indent = start - body;
+            if indent == 0 {
+                return (start, start);
+            }
+            let mut line = body;
+            while line < chars.len() {
+                let next = (line..chars.len())
+                    .find(|&j| chars[j] == '\n')
+                    .map_or(chars.len(), |j| j + 1);
+                let text = &chars[line..next];
+                let blank = text.iter().all(|c| c.is_whitespace());
+                if !blank && text.iter().take_while(|&&c| c == ' ').count() < indent {
+                    break;
+                }
+                line = next;
+            }
+            return (body, line);
+        }
+    }
+    (start, chars.len())
+}
+
+/// What `fail_on_loss` reports when parsing `text` as `format`: comments
+/// in JSONC and YAML, and YAML tags. Text that does not parse yields
+/// nothing, leaving the error to the parser.
+pub(crate) fn parse_losses(text: &str, format: Format) -> Vec<Loss> {
+    match format {
+        Format::Jsonc if parse_to_serde_value(text, &ParseOptions::default()).is_ok() => {
+            find_jsonc_comments(text)
+        }
+        Format::Yaml => match find_yaml_tags(text) {
+            Ok(tags) => {
+                let mut losses = find_yaml_comments(text);
+                losses.extend(tags);
+                losses
+            }
+            Err(_) => Vec::new(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+/// Lists the `null` values TOML output would have to drop.
+pub fn find_toml_nulls(value: &JsonValue) -> Vec<Loss> {
+    let mut losses = Vec::new();
+    visit_nulls(value, &Path::root(), &mut losses);
+    losses
+}
+
+fn visit_nulls(value: &JsonValue, path: &Path, losses: &mut Vec<Loss>) {
+    match value {
+        JsonValue::Null => losses.push(Loss {
+            path: path.clone(),
+            line: None,
+            kind: LossKind::Null,
+        }),
+        JsonValue::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                visit_nulls(item, &path.clone().index(i), losses);
+            }
+        }
+        JsonValue::Object(map) => {
+            for (key, item) in map {
+                visit_nulls(item, &path.clone().key(key.as_str()), losses);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Lists the NaN and infinite floats in a TOML, YAML or JSON5 document,
+/// which become `null` when read into the JSON data model. Other formats
+/// cannot write them, and unparsable text yields none.
+pub fn find_non_finite_numbers(text: &str, format: Format) -> Vec<Loss> {
+    let lower = text.to_ascii_lowercase();
+    if !lower.contains("nan") && !lower.contains("inf") {
+        return Vec::new();
+    }
+    // YAML values keep floats as they are, whatever the source format.
+    let value: Option<YamlValue> = match format {
+        Format::Toml => toml::from_str(text).ok(),
+        Format::Yaml => serde_yaml::from_str(text).ok(),
+        Format::Json5 => json5::from_str(text).ok(),
+        _ => None,
+    };
+    let mut losses = Vec::new();
+    if let Some(value) = value {
+        visit_floats(&value, &Path::root(), &mut losses);
+    }
+    losses
+}
+
+fn visit_floats(value: &YamlValue, path: &Path, losses: &mut Vec<Loss>) {
+    match value {
+        YamlValue::Number(n) if n.as_f64().is_some_and(|f| !f.is_finite()) => {
+            let number = n.as_f64().unwrap_or_default().to_string();
+            losses.push(Loss {
+                path: path.clone(),
+                line: None,
+                kind: LossKind::NonFiniteNumber(number),
+            });
+        }
+        YamlValue::Tagged(tagged) => visit_floats(&tagged.value, path, losses),
+        YamlValue::Sequence(items) => {
+            for (i, item) in items.iter().enumerate() {
+                visit_floats(item, &path.clone().index(i), losses);
+            }
+        }
+        YamlValue::Mapping(map) => {
+            for (key, item) in map {
+                if let Some(key) = key.as_str() {
+                    visit_floats(item, &path.clone().key(key), losses);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Lists the tagged values in a YAML document.
+pub fn find_yaml_tags(text: &str) -> Result<Vec<Loss>, serde_yaml::Error> {
+    let value: YamlValue = serde_yaml::from_str(text)?;
+    let mut losses = Vec::new();
+    visit_tags(&value, &Path::root(), &mut losses);
+    Ok(losses)
+}
+
+fn visit_tags(value: &YamlValue, path: &Path, losses: &mut Vec<Loss>) {
+    match value {
+        YamlValue::Tagged(tagged) => {
+            losses.push(Loss {
+                path: path.clone(),
+                line: None,
+                kind: LossKind::Tag(tagged.tag.to_string()),
+            });
+            visit_tags(&tagged.value, path, losses);
+        }
+        YamlValue::Sequence(items) => {
+            for (i, item) in items.iter().enumerate() {
+                visit_tags(item, &path.clone().index(i), losses);
+            }
+        }
+        YamlValue::Mapping(map) => {
+            for (key, item) in map {
+                let key = match key {
+                    YamlValue::String(s) => s.clone(),
+                    other => serde_yaml::to_string(other)
+                        .map(|s| s.trim_end().to_string())
+                        .unwrap_or_default(),
+                };
+                visit_tags(item, &path.clone().key(key.as_str()), losses);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn finds_jsonc_comments_with_lines() {
+        let losses = find_jsonc_comments("{\n  // port\n  \"a\": 1 /* inline */\n}");
+        assert_eq!(
+            losses,
+            vec![
+                Loss {
+                    path: Path::root(),
+                    line: Some(2),
+                    kind: LossKind::Comment(" port".into()),
+                },
+                Loss {
+                    path: Path::root(),
+                    line: Some(3),
+                    kind: LossKind::Comment(" inline ".into()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_nulls_and_tags_by_path() {
+        let nulls = find_toml_nulls(&json!({ "a": null, "b": [1, null] }));
+        let paths: Vec<_> = nulls.iter().map(|l| l.path.to_string()).collect();
+        assert_eq!(paths, ["a", "b[1]"]);
+
+        let tags = find_yaml_tags("db:\n  password: !secret abc\n").unwrap();
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].path.to_string(), "db.password");
+        assert_eq!(tags[0].kind, LossKind::Tag("!secret".into()));
+    }
+
+    #[test]
+    fn finds_toml_and_ini_comments_the_parsers_skip() {
+        let text = "# top\na = \"x # not\" # after\n[t] # header\nb = [\n  1, # one\n  'y#z',\n]\nc = { d = 1 }\n# end\n";
+        let found: Vec<_> = find_toml_comments(text)
+            .into_iter()
+            .map(|l| (l.line.unwrap(), l.kind))
+            .collect();
+        let comment = |line, text: &str| (line, LossKind::Comment(text.into()));
+        assert_eq!(
+            found,
+            [
+                comment(1, " top"),
+                comment(2, " after"),
+                comment(3, " header"),
+                comment(5, " one"),
+                comment(9, " end"),
+            ]
+        );
+        assert!(find_toml_comments("a = \"#\"\nb = '''\n#\n'''\n").is_empty());
+
+        let found: Vec<_> = find_ini_comments("; top\n[s]\na = \"x;y\"\nb = 1 # c\nc = 2\n")
+            .into_iter()
+            .map(|l| (l.line.unwrap(), l.kind))
+            .collect();
+        assert_eq!(
+            found,
+            [comment(1, " top"), comment(3, "y\""), comment(4, " c")]
+        );
+    }
+
+    #[test]
+    fn finds_yaml_comments_outside_scalars() {
+        let text = "# top\nurl: http://x#frag # link\nquote: 'a # b' # q\ntext: |\n  # kept\n  body\n# after\nlist: [\"#\", 1] #end\n";
+        let found: Vec<_> = find_yaml_comments(text)
+            .into_iter()
+            .map(|loss| (loss.line.unwrap(), loss.kind))
+            .collect();
+        let comment = |line, text: &str| (line, LossKind::Comment(text.into()));
+        assert_eq!(
+            found,
+            [
+                comment(1, " top"),
+                comment(2, " link"),
+                comment(3, " q"),
+                comment(7, " after"),
+                comment(8, "end"),
+            ]
+        );
+        assert_eq!(
+            find_yaml_comments("a: 1 # ok\nb: [\n"),
+            [Loss {
+                path: Path::root(),
+                line: Some(1),
+                kind: LossKind::Comment(" ok".into()),
+            }]
+        );
+    }
+
+    #[test]
+    fn data_loss_lists_every_loss() {
+        let err = DataLoss::check(find_toml_nulls(&json!({ "a": null }))).unwrap_err();
+        assert_eq!(err.to_string(), "operation would lose data: null at `a`;");
+    }
+}
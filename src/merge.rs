@@ -0,0 +1,187 @@
+use std::collections::BTreeMap;
+
+use serde_json::{Map, Value as JsonValue};
+
+use crate::path::Path;
+
+/// How [`deep_merge`] combines an array in the base with one in the
+/// overlay.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ArrayMerge {
+    /// The overlay's array replaces the base's.
+    #[default]
+    Replace,
+    /// The overlay's items follow the base's.
+    Append,
+    /// Object items with the same value at this key are merged, in the
+    /// base's order; other overlay items are appended.
+    MergeByKey(String),
+}
+
+/// Options for [`deep_merge`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MergeOptions {
+    pub arrays: ArrayMerge,
+    /// Strategies for particular arrays, by path such as `servers` or
+    /// `build.plugins`, taking precedence over `arrays`.
+    pub array_paths: BTreeMap<Path, ArrayMerge>,
+    /// A `null` in the overlay removes the key instead of setting it to
+    /// `null`.
+    pub null_deletes: bool,
+}
+
+impl MergeOptions {
+    /// Uses `strategy` for the array at `path`.
+    pub fn array_at(mut self, path: Path, strategy: ArrayMerge) -> Self {
+        self.array_paths.insert(path, strategy);
+        self
+    }
+}
+
+/// Merges `overlay` onto `base`, for layered config loading: objects merge
+/// key by key, arrays by the configured [`ArrayMerge`], and any other
+/// overlay value wins.
+///
+/// ```
+/// use c12_parser::{ArrayMerge, MergeOptions, Path, deep_merge};
+/// use serde_json::json;
+///
+/// let base = json!({ "servers": [{ "name": "a", "port": 1 }], "debug": true });
+/// let overlay = json!({ "servers": [{ "name": "a", "port": 2 }, { "name": "b" }], "debug": null });
+/// let options = MergeOptions {
+///     null_deletes: true,
+///     ..MergeOptions::default()
+/// }
+/// .array_at(Path::root().key("servers"), ArrayMerge::MergeByKey("name".into()));
+/// assert_eq!(
+///     deep_merge(&base, &overlay, options),
+///     json!({ "servers": [{ "name": "a", "port": 2 }, { "name": "b" }] })
+/// );
+/// ```
+pub fn deep_merge(base: &JsonValue, overlay: &JsonValue, options: MergeOptions) -> JsonValue {
+    let mut merged = base.clone();
+    merge_into(&mut merged, overlay, &Path::root(), &options);
+    merged
+}
+
+fn merge_into(base: &mut JsonValue, overlay: &JsonValue, path: &Path, options: &MergeOptions) {
+    match (base, overlay) {
+        (JsonValue::Object(base), JsonValue::Object(overlay)) => {
+            merge_objects(base, overlay, path, options)
+        }
+        (JsonValue::Array(base), JsonValue::Array(overlay)) => {
+            let strategy = options.array_paths.get(path).unwrap_or(&options.arrays);
+            match strategy {
+                ArrayMerge::Replace => *base = overlay.clone(),
+                ArrayMerge::Append => base.extend(overlay.iter().cloned()),
+                ArrayMerge::MergeByKey(key) => {
+                    for item in overlay {
+                        let id = item.get(key).filter(|id| !id.is_null());
+                        let existing = id.and_then(|id| {
+                            base.iter()
+                                .position(|candidate| candidate.get(key) == Some(id))
+                        });
+                        match existing {
+                            Some(i) => {
+                                merge_into(&mut base[i], item, &path.clone().index(i), options)
+                            }
+                            None => base.push(item.clone()),
+                        }
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
+fn merge_objects(
+    base: &mut Map<String, JsonValue>,
+    overlay: &Map<String, JsonValue>,
+    path: &Path,
+    options: &MergeOptions,
+) {
+    for (key, value) in overlay {
+        if value.is_null() && options.null_deletes {
+            base.shift_remove(key);
+            continue;
+        }
+        match base.get_mut(key) {
+            Some(existing) => merge_into(existing, value, &path.clone().key(key.as_str()), options),
+            None => {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn arrays_follow_the_strategy() {
+        let base = json!({ "tags": ["a"], "nested": { "tags": ["b"] } });
+        let overlay = json!({ "tags": ["c"], "nested": { "tags": ["d"] } });
+        assert_eq!(
+            deep_merge(&base, &overlay, MergeOptions::default()),
+            overlay
+        );
+        let append = MergeOptions {
+            arrays: ArrayMerge::Append,
+            ..MergeOptions::default()
+        };
+        assert_eq!(
+            deep_merge(&base, &overlay, append.clone()),
+            json!({ "tags": ["a", "c"], "nested": { "tags": ["b", "d"] } })
+        );
+        assert_eq!(
+            deep_merge(
+                &base,
+                &overlay,
+                append.array_at("nested.tags".parse().unwrap(), ArrayMerge::Replace)
+            ),
+            json!({ "tags": ["a", "c"], "nested": { "tags": ["d"] } })
+        );
+    }
+
+    #[test]
+    fn merges_by_key_and_handles_nulls() {
+        let base = json!({
+            "routes": [{ "id": 1, "to": "a", "opts": { "x": 1 } }, { "to": "anon" }],
+            "keep": null
+        });
+        let overlay = json!({
+            "routes": [{ "id": 1, "opts": { "y": 2 } }, { "id": 3 }, { "to": "other" }],
+            "keep": null,
+            "added": { "deep": true }
+        });
+        let options = MergeOptions {
+            arrays: ArrayMerge::MergeByKey("id".into()),
+            ..MergeOptions::default()
+        };
+        assert_eq!(
+            deep_merge(&base, &overlay, options.clone()),
+            json!({
+                "routes": [
+                    { "id": 1, "to": "a", "opts": { "x": 1, "y": 2 } },
+                    { "to": "anon" },
+                    { "id": 3 },
+                    { "to": "other" }
+                ],
+                "keep": null,
+                "added": { "deep": true }
+            })
+        );
+        let deletes = MergeOptions {
+            null_deletes: true,
+            ..options
+        };
+        assert!(deep_merge(&base, &overlay, deletes).get("keep").is_none());
+        assert_eq!(
+            deep_merge(&base, &json!(5), MergeOptions::default()),
+            json!(5)
+        );
+    }
+}
@@ -0,0 +1,332 @@
+use std::error::Error;
+use std::fmt;
+
+use jsonc_parser::{ParseOptions, parse_to_value};
+
+use crate::emit::{Dialect, EmitStyle, is_identifier, write_string};
+use crate::format::Format;
+
+/// Rewrites JSON, JSONC or JSON5 text in its smallest form: no whitespace,
+/// strings re-escaped with the fewest escapes, trailing commas dropped and,
+/// for JSON5, identifier keys unquoted. Numbers and other bare words keep
+/// their spelling.
+///
+/// The text is validated as `format` first. JSONC may use single-quoted
+/// strings and trailing commas, which become plain JSON, but not JSON5's
+/// bare keys and numbers.
+///
+/// With `keep_comments`, comments stay in place (line comments keep their
+/// terminating newline), e.g. to retain license headers in bundles.
+///
+/// ```
+/// use c12_parser::{Format, minify};
+///
+/// let text = "/*! MIT */\n{\n  // port\n  \"port\": 8080,\n  'name': \"app\",\n}\n";
+/// assert_eq!(minify(text, Format::Json5, false)?, "{port:8080,name:'app'}");
+/// assert_eq!(minify(text, Format::Jsonc, true)?, "/*! MIT */{// port\n\"port\":8080,\"name\":\"app\"}");
+/// assert!(minify("{ port: 8080 }", Format::Jsonc, false).is_err());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn minify(text: &str, format: Format, keep_comments: bool) -> Result<String, Box<dyn Error>> {
+    let dialect = match format {
+        Format::Json => {
+            // Comments and JSON5 syntax are not valid JSON; reject them
+            // rather than minify them into something that still isn't.
+            serde_json::from_str::<serde::de::IgnoredAny>(text)?;
+            Dialect::Json
+        }
+        Format::Jsonc => {
+            let options = ParseOptions {
+                allow_loose_object_property_names: false,
+                allow_missing_commas: false,
+                allow_hexadecimal_numbers: false,
+                allow_unary_plus_numbers: false,
+                ..Default::default()
+            };
+            parse_to_value(text, &options)?;
+            // The parser only enforces commas between object members.
+            if let Some(offset) = missing_array_comma(&lex(text)?) {
+                return Err(LexError {
+                    offset,
+                    message: "expected a comma",
+                }
+                .into());
+            }
+            Dialect::Json
+        }
+        Format::Json5 => {
+            json5::from_str::<serde::de::IgnoredAny>(text)?;
+            Dialect::Json5
+        }
+        other => return Err(format!("cannot minify {other}, only JSON-like formats").into()),
+    };
+    let style = EmitStyle {
+        dialect,
+        indent: String::new(),
+        line_width: None,
+        escaping: Default::default(),
+        js_compat: true,
+        trailing_commas: false,
+    };
+
+    let lexemes = lex(text)?;
+    let mut out = String::with_capacity(text.len());
+    for (i, (_, lexeme)) in lexemes.iter().enumerate() {
+        match lexeme {
+            Lexeme::LineComment(raw) if keep_comments => {
+                out.push_str(raw);
+                out.push('\n');
+            }
+            Lexeme::BlockComment(raw) if keep_comments => out.push_str(raw),
+            Lexeme::LineComment(_) | Lexeme::BlockComment(_) => {}
+            Lexeme::Str(s) => {
+                let is_key = lexemes[i + 1..]
+                    .iter()
+                    .find(|(_, l)| !l.is_comment())
+                    .is_some_and(|(_, l)| matches!(l, Lexeme::Punct(':')));
+                if dialect == Dialect::Json5 && is_key && is_identifier(s) {
+                    out.push_str(s);
+                } else {
+                    write_string(&mut out, s, &style);
+                }
+            }
+            Lexeme::Punct(',')
+                if lexemes[i + 1..]
+                    .iter()
+                    .find(|(_, l)| !l.is_comment())
+                    .is_some_and(|(_, l)| matches!(l, Lexeme::Punct('}' | ']'))) => {}
+            Lexeme::Punct(c) => out.push(*c),
+            Lexeme::Atom(raw) => out.push_str(raw),
+        }
+    }
+    Ok(out)
+}
+
+/// A token of JSON-family text, with strings already unescaped.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Lexeme<'a> {
+    /// One of `{}[],:`.
+    Punct(char),
+    Str(String),
+    /// A number, literal or unquoted key, as written.
+    Atom(&'a str),
+    /// A `//` comment, without its line break.
+    LineComment(&'a str),
+    BlockComment(&'a str),
+}
+
+impl Lexeme<'_> {
+    pub(crate) fn is_comment(&self) -> bool {
+        matches!(self, Lexeme::LineComment(_) | Lexeme::BlockComment(_))
+    }
+}
+
+/// A lexing failure at a byte offset.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct LexError {
+    pub offset: usize,
+    pub message: &'static str,
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+impl Error for LexError {}
+
+/// Splits JSON, JSONC or JSON5 text into lexemes with their byte offsets.
+/// Structure is not validated; this only needs to be good enough to
+/// re-space text.
+pub(crate) fn lex(text: &str) -> Result<Vec<(usize, Lexeme<'_>)>, LexError> {
+    let mut lexemes = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        match c {
+            c if c.is_whitespace() || c == '\u{feff}' => {
+                chars.next();
+            }
+            '{' | '}' | '[' | ']' | ',' | ':' => {
+                chars.next();
+                lexemes.push((start, Lexeme::Punct(c)));
+            }
+            '"' | '\'' => {
+                chars.next();
+                lexemes.push((start, Lexeme::Str(lex_string(&mut chars, c, start)?)));
+            }
+            '/' if text[start..].starts_with("//") => {
+                let end = text[start..]
+                    .find(['\n', '\r'])
+                    .map_or(text.len(), |i| start + i);
+                lexemes.push((start, Lexeme::LineComment(&text[start..end])));
+                while chars.next_if(|&(i, _)| i < end).is_some() {}
+            }
+            '/' if text[start..].starts_with("/*") => {
+                let end = text[start + 2..].find("*/").ok_or(LexError {
+                    offset: start,
+                    message: "unterminated block comment",
+                })? + start
+                    + 4;
+                lexemes.push((start, Lexeme::BlockComment(&text[start..end])));
+                while chars.next_if(|&(i, _)| i < end).is_some() {}
+            }
+            _ => {
+                let mut end = start;
+                while let Some((i, c)) = chars.next_if(|&(_, c)| !is_delimiter(c)) {
+                    end = i + c.len_utf8();
+                }
+                if end == start {
+                    return Err(LexError {
+                        offset: start,
+                        message: "unexpected character",
+                    });
+                }
+                lexemes.push((start, Lexeme::Atom(&text[start..end])));
+            }
+        }
+    }
+    Ok(lexemes)
+}
+
+/// The offset of the first array element not preceded by a comma.
+fn missing_array_comma(lexemes: &[(usize, Lexeme<'_>)]) -> Option<usize> {
+    let mut previous: Option<&Lexeme<'_>> = None;
+    for (offset, lexeme) in lexemes.iter().filter(|(_, l)| !l.is_comment()) {
+        let ends_value = matches!(
+            previous,
+            Some(Lexeme::Str(_) | Lexeme::Atom(_) | Lexeme::Punct('}' | ']'))
+        );
+        let starts_value = matches!(
+            lexeme,
+            Lexeme::Str(_) | Lexeme::Atom(_) | Lexeme::Punct('{' | '[')
+        );
+        if ends_value && starts_value {
+            return Some(*offset);
+        }
+        previous = Some(lexeme);
+    }
+    None
+}
+
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '{' | '}' | '[' | ']' | ',' | ':' | '"' | '\'' | '/')
+}
+
+fn lex_string(
+    chars: &mut std::iter::Peekable<std::str::CharIndices<'_>>,
+    quote: char,
+    start: usize,
+) -> Result<String, LexError> {
+    let error = |message| LexError {
+        offset: start,
+        message,
+    };
+    let mut out = String::new();
+    loop {
+        let (_, c) = chars.next().ok_or(error("unterminated string"))?;
+        match c {
+            c if c == quote => return Ok(out),
+            '\\' => {
+                let (_, escaped) = chars.next().ok_or(error("unterminated string"))?;
+                match escaped {
+                    'b' => out.push('\u{8}'),
+                    'f' => out.push('\u{c}'),
+                    'n' => out.push('\n'),
+                    'r' => out.push('\r'),
+                    't' => out.push('\t'),
+                    'v' => out.push('\u{b}'),
+                    '0' => out.push('\0'),
+                    // JSON5 line continuations.
+                    '\n' | '\u{2028}' | '\u{2029}' => {}
+                    '\r' => {
+                        chars.next_if(|&(_, c)| c == '\n');
+                    }
+                    'x' => out.push(hex_escape(chars, 2).ok_or(error("invalid \\x escape"))?),
+                    'u' => {
+                        let high = hex_code(chars, 4).ok_or(error("invalid \\u escape"))?;
+                        let code = if (0xd800..0xdc00).contains(&high) {
+                            let low = (chars.next_if(|&(_, c)| c == '\\').is_some()
+                                && chars.next_if(|&(_, c)| c == 'u').is_some())
+                            .then(|| hex_code(chars, 4))
+                            .flatten()
+                            .filter(|low| (0xdc00..0xe000).contains(low))
+                            .ok_or(error("unpaired surrogate"))?;
+                            0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+                        } else {
+                            high
+                        };
+                        out.push(char::from_u32(code).ok_or(error("unpaired surrogate"))?);
+                    }
+                    other => out.push(other),
+                }
+            }
+            c => out.push(c),
+        }
+    }
+}
+
+fn hex_code(chars: &mut impl Iterator<Item = (usize, char)>, digits: usize) -> Option<u32> {
+    (0..digits).try_fold(0, |code, _| Some(code * 16 + chars.next()?.1.to_digit(16)?))
+}
+
+fn hex_escape(chars: &mut impl Iterator<Item = (usize, char)>, digits: usize) -> Option<char> {
+    char::from_u32(hex_code(chars, digits)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minifies_json_with_minimal_escapes() {
+        let text =
+            "{\n  \"a\\/b\": \"\\u0041\\u00e9\",\n  \"list\": [1, 2.50, -3e2, true, null]\n}\n";
+        assert_eq!(
+            minify(text, Format::Json, false).unwrap(),
+            r#"{"a/b":"Aé","list":[1,2.50,-3e2,true,null]}"#
+        );
+        assert!(minify("{ /* c */ }", Format::Json, true).is_err());
+        assert!(minify("a = 1", Format::Toml, false).is_err());
+    }
+
+    #[test]
+    fn json5_picks_the_cheaper_quote_and_keeps_words() {
+        assert!(minify("{ 'open: 1 }", Format::Json5, false).is_err());
+        assert!(minify("{ a: 1 b: 2 }", Format::Json5, false).is_err());
+        assert_eq!(
+            minify("[1, [2,], /* end */ ]", Format::Json5, true).unwrap(),
+            "[1,[2]/* end */]"
+        );
+
+        let text = "{ key: \"it's\", \"two words\": Infinity, $n: 0x1F, s: 'say \\\"hi\\\"', e: '\\x41\\uD83D\\uDE00' }";
+        assert_eq!(
+            minify(text, Format::Json5, false).unwrap(),
+            r#"{key:"it's",'two words':Infinity,$n:0x1F,s:'say "hi"',e:'A😀'}"#
+        );
+    }
+
+    #[test]
+    fn jsonc_is_validated_and_minifies_to_json() {
+        for invalid in ["[1 2]", "{ a: 1 }", "[0x1F]", "[+1]", "{ \"a\": 1"] {
+            assert!(minify(invalid, Format::Jsonc, false).is_err(), "{invalid}");
+        }
+        assert_eq!(
+            minify("{ 'a': [1, 2,], }", Format::Jsonc, false).unwrap(),
+            r#"{"a":[1,2]}"#
+        );
+    }
+
+    #[test]
+    fn minified_jsonc_parses_to_the_same_value() {
+        let text =
+            "// header\n{\n  \"a\": [1, 2], // trailing\n  /* b */ \"b\": { \"c\": \"x y\" }\n}\n";
+        for keep_comments in [false, true] {
+            let minified = minify(text, Format::Jsonc, keep_comments).unwrap();
+            assert_eq!(
+                crate::parse_jsonc(&minified, None, None).unwrap().value,
+                crate::parse_jsonc(text, None, None).unwrap().value
+            );
+        }
+    }
+}
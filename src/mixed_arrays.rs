@@ -0,0 +1,256 @@
+use std::fmt;
+use std::ops::Range;
+
+use serde_json::Value as JsonValue;
+use toml::Value as TomlValue;
+
+use crate::format::Format;
+use crate::outline::spans;
+use crate::path::Path;
+
+/// How arrays mixing element types are handled when emitting TOML.
+///
+/// TOML 1.0 allows mixed-type arrays, but TOML 0.5 and several
+/// implementations still reject them, and converting from YAML or JSON
+/// produces them easily.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MixedArrayStrategy {
+    /// Leave mixed arrays untouched.
+    #[default]
+    Keep,
+    /// Convert every element other than strings to its JSON text, e.g.
+    /// `1`, `true` or `{"three":3}`.
+    Stringify,
+    /// Turn the array into an array of tables, wrapping non-table
+    /// elements as `{ value = ... }`.
+    SplitTables,
+    /// Refuse to emit mixed arrays.
+    Error,
+}
+
+/// An array whose elements do not all share the same TOML type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MixedArray {
+    /// Path to the array, e.g. `types.array` or `servers[0].ports`.
+    pub path: Path,
+    /// The distinct element types, in order of first appearance.
+    pub kinds: Vec<&'static str>,
+    /// The array's byte range in the source, when it was found while
+    /// [converting](crate::convert) text.
+    pub span: Option<Range<usize>>,
+}
+
+/// Returned by the [`MixedArrayStrategy::Error`] strategy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MixedArrayError {
+    pub arrays: Vec<MixedArray>,
+}
+
+impl fmt::Display for MixedArray {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` ({})", self.path, self.kinds.join(", "))
+    }
+}
+
+impl fmt::Display for MixedArrayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "mixed-type arrays are not allowed:")?;
+        for array in &self.arrays {
+            write!(f, " {array}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for MixedArrayError {}
+
+/// Lists every array in `value` whose elements mix TOML types.
+pub fn find_mixed_arrays(value: &TomlValue) -> Vec<MixedArray> {
+    let mut found = Vec::new();
+    visit(value, Path::root(), &mut found);
+    found
+}
+
+/// Applies `strategy` to every mixed array in `value`, returning the
+/// arrays that were found (and, unless the strategy is `Keep`, rewritten).
+pub fn normalize_mixed_arrays(
+    value: &mut TomlValue,
+    strategy: MixedArrayStrategy,
+) -> Result<Vec<MixedArray>, MixedArrayError> {
+    let found = find_mixed_arrays(value);
+    match strategy {
+        MixedArrayStrategy::Keep => {}
+        MixedArrayStrategy::Error if !found.is_empty() => {
+            return Err(MixedArrayError { arrays: found });
+        }
+        MixedArrayStrategy::Error => {}
+        MixedArrayStrategy::Stringify | MixedArrayStrategy::SplitTables => {
+            rewrite(value, strategy);
+        }
+    }
+    Ok(found)
+}
+
+/// Fills in each array's [`span`](MixedArray::span) from `text`.
+pub(crate) fn locate_mixed_arrays(arrays: &mut [MixedArray], text: &str, format: Format) {
+    if arrays.is_empty() {
+        return;
+    }
+    let spans = spans(text, format);
+    for array in arrays {
+        array.span = spans.get(&array.path).cloned();
+    }
+}
+
+fn element_kinds(items: &[TomlValue]) -> Vec<&'static str> {
+    let mut kinds = Vec::new();
+    for item in items {
+        let kind = item.type_str();
+        if !kinds.contains(&kind) {
+            kinds.push(kind);
+        }
+    }
+    kinds
+}
+
+fn visit(value: &TomlValue, path: Path, found: &mut Vec<MixedArray>) {
+    match value {
+        TomlValue::Table(table) => {
+            for (key, child) in table {
+                visit(child, path.clone().key(key.as_str()), found);
+            }
+        }
+        TomlValue::Array(items) => {
+            for (i, child) in items.iter().enumerate() {
+                visit(child, path.clone().index(i), found);
+            }
+            let kinds = element_kinds(items);
+            if kinds.len() > 1 {
+                found.push(MixedArray {
+                    path,
+                    kinds,
+                    span: None,
+                });
+            }
+        }
+        _ => {}
+    }
+}
+
+fn rewrite(value: &mut TomlValue, strategy: MixedArrayStrategy) {
+    match value {
+        TomlValue::Table(table) => {
+            for (_, child) in table.iter_mut() {
+                rewrite(child, strategy);
+            }
+        }
+        TomlValue::Array(items) => {
+            for child in items.iter_mut() {
+                rewrite(child, strategy);
+            }
+            if element_kinds(items).len() > 1 {
+                for item in items.iter_mut() {
+                    *item = normalize_element(item.clone(), strategy);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn normalize_element(item: TomlValue, strategy: MixedArrayStrategy) -> TomlValue {
+    match (strategy, item) {
+        (MixedArrayStrategy::Stringify, TomlValue::String(s)) => TomlValue::String(s),
+        (MixedArrayStrategy::Stringify, other) => TomlValue::String(to_json(other).to_string()),
+        (MixedArrayStrategy::SplitTables, TomlValue::Table(table)) => TomlValue::Table(table),
+        (MixedArrayStrategy::SplitTables, other) => {
+            let mut table = toml::Table::new();
+            table.insert("value".into(), other);
+            TomlValue::Table(table)
+        }
+        (_, other) => other,
+    }
+}
+
+/// `item` as JSON, with datetimes as their RFC 3339 text.
+fn to_json(item: TomlValue) -> JsonValue {
+    match item {
+        TomlValue::String(s) => s.into(),
+        TomlValue::Integer(i) => i.into(),
+        TomlValue::Float(f) => f.into(),
+        TomlValue::Boolean(b) => b.into(),
+        TomlValue::Datetime(datetime) => datetime.to_string().into(),
+        TomlValue::Array(items) => items.into_iter().map(to_json).collect(),
+        TomlValue::Table(table) => table
+            .into_iter()
+            .map(|(key, value)| (key, to_json(value)))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> TomlValue {
+        toml::from_str(
+            r#"
+uniform = [1, 2, 3]
+mixed = [1, "two", { three = 3 }]
+nested = { inner = [true, 1.5] }
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn finds_mixed_arrays_with_paths() {
+        let found = find_mixed_arrays(&fixture());
+        assert_eq!(
+            found,
+            vec![
+                MixedArray {
+                    path: Path::root().key("mixed"),
+                    kinds: vec!["integer", "string", "table"],
+                    span: None,
+                },
+                MixedArray {
+                    path: Path::root().key("nested").key("inner"),
+                    kinds: vec!["boolean", "float"],
+                    span: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn stringify_strategy_converts_elements_to_strings() {
+        let mut value = fixture();
+        normalize_mixed_arrays(&mut value, MixedArrayStrategy::Stringify).unwrap();
+
+        let mixed = value["mixed"].as_array().unwrap();
+        assert!(mixed.iter().all(TomlValue::is_str));
+        assert_eq!(mixed[0].as_str(), Some("1"));
+        assert_eq!(mixed[2].as_str(), Some(r#"{"three":3}"#));
+        assert_eq!(value["uniform"], fixture()["uniform"]);
+        assert!(find_mixed_arrays(&value).is_empty());
+    }
+
+    #[test]
+    fn split_tables_strategy_wraps_non_tables() {
+        let mut value = fixture();
+        normalize_mixed_arrays(&mut value, MixedArrayStrategy::SplitTables).unwrap();
+
+        let mixed = value["mixed"].as_array().unwrap();
+        assert_eq!(mixed[1]["value"].as_str(), Some("two"));
+        assert_eq!(mixed[2]["three"].as_integer(), Some(3));
+    }
+
+    #[test]
+    fn error_strategy_reports_all_arrays() {
+        let mut value = fixture();
+        let err = normalize_mixed_arrays(&mut value, MixedArrayStrategy::Error).unwrap_err();
+        assert_eq!(err.arrays.len(), 2);
+        assert!(err.to_string().contains("`nested.inner`"));
+    }
+}
@@ -0,0 +1,325 @@
+use std::fmt;
+use std::iter::Enumerate;
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::str::Lines;
+
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value as JsonValue;
+
+use crate::emit::{Dialect, EmitStyle, to_json_string};
+use crate::error::C12Error;
+use crate::format::{Format, FormatOptions, Formatted, StyleProfile};
+use crate::interpolate::prepared;
+use crate::roundtrip::guard;
+
+/// A record of NDJSON input that failed to parse.
+#[derive(Debug)]
+pub struct NdjsonError {
+    /// 1-based line of the record.
+    pub line: usize,
+    pub error: serde_json::Error,
+}
+
+impl fmt::Display for NdjsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+impl std::error::Error for NdjsonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// The records of NDJSON text, parsed one line at a time by
+/// [`parse_ndjson`].
+pub struct NdjsonRecords<'a, T> {
+    lines: Enumerate<Lines<'a>>,
+    options: FormatOptions,
+    record: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> Iterator for NdjsonRecords<'_, T> {
+    type Item = Result<Formatted<T>, NdjsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (index, line) = self.lines.find(|(_, line)| !line.trim().is_empty())?;
+        let parse = || {
+            let value = if self.options.parses_directly() {
+                serde_json::from_str(line)?
+            } else {
+                prepared(serde_json::from_str(line)?, &self.options)?
+            };
+            Ok(Formatted::new(line, value, &self.options))
+        };
+        Some(parse().map_err(|error| NdjsonError {
+            line: index + 1,
+            error,
+        }))
+    }
+}
+
+/// Parses NDJSON (JSON Lines) text lazily, one record per non-blank line,
+/// for log-style and batch inputs.
+///
+/// Each record is parsed when the iterator reaches it, so a bad line
+/// fails only its own item and iteration can go on past it. Each item
+/// captures the formatting of its own line.
+///
+/// ```
+/// use c12_parser::parse_ndjson;
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Event {
+///     level: String,
+/// }
+///
+/// let text = "{\"level\": \"info\"}\n\n{\"level\": \"warn\"}\n{oops}\n";
+/// let records: Vec<_> = parse_ndjson::<Event>(text, None).collect();
+/// assert_eq!(records[0].as_ref().unwrap().value.level, "info");
+/// assert_eq!(records[1].as_ref().unwrap().value.level, "warn");
+/// assert_eq!(records[2].as_ref().unwrap_err().line, 4);
+/// ```
+pub fn parse_ndjson<T>(text: &str, options: Option<FormatOptions>) -> NdjsonRecords<'_, T>
+where
+    T: DeserializeOwned,
+{
+    NdjsonRecords {
+        lines: text.lines().enumerate(),
+        options: options.unwrap_or_default(),
+        record: PhantomData,
+    }
+}
+
+/// A value read by [`parse_json_stream`], with where it sits in the text.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JsonStreamValue<T> {
+    pub value: T,
+    /// Byte range of the value, without the whitespace around it.
+    pub span: Range<usize>,
+}
+
+/// The values of concatenated JSON text, parsed one at a time by
+/// [`parse_json_stream`].
+pub struct JsonStream<'a, T> {
+    text: &'a str,
+    position: usize,
+    options: FormatOptions,
+    value: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> Iterator for JsonStream<'_, T> {
+    type Item = Result<JsonStreamValue<T>, C12Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = &self.text[self.position..];
+        let start = self.position + (rest.len() - rest.trim_start().len());
+        if start == self.text.len() {
+            self.position = start;
+            return None;
+        }
+        let source = &self.text[start..];
+        let mut values = serde_json::Deserializer::from_str(source).into_iter::<JsonValue>();
+        let parsed = values.next()?;
+        let end = start + values.byte_offset();
+        let value = match parsed {
+            Ok(value) => {
+                self.position = end;
+                value
+            }
+            Err(e) => {
+                let local = C12Error::new(Format::Json, source, &e);
+                let error = C12Error::at_offset(
+                    Format::Json,
+                    self.text,
+                    start + local.offset,
+                    local.message,
+                );
+                // Pick up again on the line after the syntax error.
+                self.position = self.text[error.offset..]
+                    .find('\n')
+                    .map_or(self.text.len(), |i| error.offset + i + 1);
+                return Some(Err(error));
+            }
+        };
+        let value = if self.options.parses_directly() {
+            serde_json::from_value(value)
+        } else {
+            prepared(value, &self.options)
+        };
+        Some(
+            value
+                .map(|value| JsonStreamValue {
+                    value,
+                    span: start..end,
+                })
+                .map_err(|e| C12Error::at_offset(Format::Json, self.text, start, e.to_string())),
+        )
+    }
+}
+
+/// Parses concatenated JSON values, written back to back as in
+/// `{..}{..}` or separated by any whitespace, as log pipelines and some
+/// tools emit them.
+///
+/// Each value is parsed when the iterator reaches it and comes with its
+/// byte span. A value that fails to parse yields an error located in
+/// `text`, and reading picks up again on the next line, so one bad record
+/// does not end the stream.
+///
+/// ```
+/// use c12_parser::parse_json_stream;
+/// use serde_json::{Value, json};
+///
+/// let text = "{\"a\":1}{\"b\":2} [3]\n{oops}\n\"done\"";
+/// let values: Vec<_> = parse_json_stream::<Value>(text, None).collect();
+/// assert_eq!(values[0].as_ref().unwrap().value, json!({ "a": 1 }));
+/// assert_eq!(values[1].as_ref().unwrap().span, 7..14);
+/// assert_eq!(values[2].as_ref().unwrap().value, json!([3]));
+/// assert_eq!(values[3].as_ref().unwrap_err().line, 2);
+/// assert_eq!(values[4].as_ref().unwrap().value, "done");
+/// ```
+pub fn parse_json_stream<T>(text: &str, options: Option<FormatOptions>) -> JsonStream<'_, T>
+where
+    T: DeserializeOwned,
+{
+    JsonStream {
+        text,
+        position: 0,
+        options: options.unwrap_or_default(),
+        value: PhantomData,
+    }
+}
+
+/// Writes each record as compact JSON on a line of its own, each line
+/// ending with `\n`.
+///
+/// Escaping options and the [`Confbox`](StyleProfile::Confbox) profile
+/// apply as in `stringify_json`; indentation and line width do not, as a
+/// record must fit on one line.
+///
+/// ```
+/// use c12_parser::stringify_ndjson;
+/// use serde_json::json;
+///
+/// let records = [json!({ "id": 1, "tags": ["a"] }), json!({ "id": 2, "tags": [] })];
+/// assert_eq!(
+///     stringify_ndjson(&records, None)?,
+///     "{\"id\":1,\"tags\":[\"a\"]}\n{\"id\":2,\"tags\":[]}\n"
+/// );
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+pub fn stringify_ndjson<'a, T>(
+    records: impl IntoIterator<Item = &'a T>,
+    options: Option<FormatOptions>,
+) -> serde_json::Result<String>
+where
+    T: Serialize + 'a,
+{
+    let opts = options.unwrap_or_default();
+    let plain = opts.profile == StyleProfile::Native && opts.escaping == Default::default();
+    let style = EmitStyle {
+        line_width: None,
+        ..EmitStyle::for_options(Dialect::Json, 0, &opts)
+    };
+    let mut out = String::new();
+    for record in records {
+        let line = if plain {
+            serde_json::to_string(record)?
+        } else {
+            to_json_string(&serde_json::to_value(record)?, &style)
+        };
+        if opts.verify_round_trip {
+            guard::<_, serde_json::Error>(record, &line, |text| {
+                serde_json::from_str(text).map_err(|e| e.to_string())
+            })?;
+        }
+        out.push_str(&line);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::EscapeOptions;
+    use crate::limits::Limits;
+    use serde_json::json;
+
+    #[test]
+    fn parses_records_lazily_and_keeps_going_after_errors() {
+        let text = "{\"a\": [1, 2, 3]}\r\n  {\"b\": 2}  \n[1\n\"x\"\n";
+        let opts = FormatOptions {
+            limits: Limits {
+                max_array_len: Some(2),
+                ..Limits::default()
+            },
+            ..FormatOptions::default()
+        };
+        let records: Vec<_> = parse_ndjson::<JsonValue>(text, Some(opts)).collect();
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[0].as_ref().unwrap_err().line, 1);
+        let second = records[1].as_ref().unwrap();
+        assert_eq!(second.value, json!({ "b": 2 }));
+        assert_eq!(second.format.whitespace_start, "  ");
+        assert_eq!(records[2].as_ref().unwrap_err().line, 3);
+        assert_eq!(records[3].as_ref().unwrap().value, json!("x"));
+
+        assert_eq!(parse_ndjson::<JsonValue>("\n \n", None).count(), 0);
+    }
+
+    #[test]
+    fn stringify_applies_escaping_on_single_lines() {
+        let records = vec![json!({ "path": "a/b", "name": "é" }), json!(null)];
+        let opts = FormatOptions {
+            indent: Some(4),
+            line_width: Some(10),
+            escaping: EscapeOptions {
+                escape_unicode: true,
+                escape_slash: true,
+                ..EscapeOptions::default()
+            },
+            verify_round_trip: true,
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            stringify_ndjson(&records, Some(opts)).unwrap(),
+            "{\"path\":\"a\\/b\",\"name\":\"\\u00e9\"}\nnull\n"
+        );
+        assert_eq!(stringify_ndjson::<JsonValue>([], None).unwrap(), "");
+    }
+
+    #[test]
+    fn splits_concatenated_values_and_resumes_after_errors() {
+        let text = "  {\"n\": 1}{\"n\": 2}\n[1, 2, 3] 4\n{\"n\": }\n{\"n\": 5}";
+        let opts = FormatOptions {
+            limits: Limits {
+                max_array_len: Some(2),
+                ..Limits::default()
+            },
+            ..FormatOptions::default()
+        };
+        let values: Vec<_> = parse_json_stream::<JsonValue>(text, Some(opts)).collect();
+        assert_eq!(values.len(), 6);
+        let first = values[0].as_ref().unwrap();
+        assert_eq!(
+            (first.span.clone(), &first.value),
+            (2..10, &json!({ "n": 1 }))
+        );
+        assert_eq!(
+            &text[values[1].as_ref().unwrap().span.clone()],
+            "{\"n\": 2}"
+        );
+        assert!(values[2].as_ref().unwrap_err().message.contains("array"));
+        assert_eq!(values[3].as_ref().unwrap().value, json!(4));
+        let bad = values[4].as_ref().unwrap_err();
+        assert_eq!((bad.line, bad.column), (3, 7));
+        assert_eq!(values[5].as_ref().unwrap().value, json!({ "n": 5 }));
+
+        assert_eq!(parse_json_stream::<JsonValue>(" \n ", None).count(), 0);
+    }
+}
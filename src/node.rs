@@ -0,0 +1,103 @@
+use napi::{Error, Result};
+use napi_derive::napi;
+use serde_json::Value as JsonValue;
+
+use crate::binding;
+use crate::format::{FormatInfo, FormatOptions, Formatted, StyleProfile};
+
+/// Formatting options, mirroring confbox's `FormatOptions`.
+#[napi(object)]
+#[derive(Default)]
+pub struct NodeFormatOptions {
+    pub indent: Option<u32>,
+    pub preserve_indentation: Option<bool>,
+    pub preserve_whitespace: Option<bool>,
+    pub sample_size: Option<u32>,
+}
+
+/// Formatting captured by `parse`, to be handed back to `stringify`. The
+/// JS shim stores it where confbox keeps its format symbol.
+#[napi(object)]
+pub struct NodeFormatInfo {
+    pub sample: Option<String>,
+    pub whitespace_start: String,
+    pub whitespace_end: String,
+}
+
+#[napi(object)]
+pub struct NodeParsed {
+    pub value: JsonValue,
+    pub format: NodeFormatInfo,
+}
+
+impl From<NodeFormatOptions> for FormatOptions {
+    fn from(options: NodeFormatOptions) -> Self {
+        let defaults = FormatOptions::default();
+        FormatOptions {
+            indent: options.indent.map(|i| i as usize),
+            preserve_indentation: options
+                .preserve_indentation
+                .unwrap_or(defaults.preserve_indentation),
+            preserve_whitespace: options
+                .preserve_whitespace
+                .unwrap_or(defaults.preserve_whitespace),
+            sample_size: options
+                .sample_size
+                .map_or(defaults.sample_size, |s| s as usize),
+            // Node callers expect byte-identical output to the JS packages.
+            profile: StyleProfile::Confbox,
+            ..defaults
+        }
+    }
+}
+
+/// Parses `text` as `format` (`json`, `json5`, `jsonc`, `yaml`, `toml` or
+/// `ini`).
+#[napi]
+pub fn parse(
+    format: String,
+    text: String,
+    options: Option<NodeFormatOptions>,
+) -> Result<NodeParsed> {
+    let formatted = binding::parse(&format, &text, options.unwrap_or_default().into())
+        .map_err(Error::from_reason)?;
+    Ok(NodeParsed {
+        value: formatted.value,
+        format: NodeFormatInfo {
+            sample: formatted.format.sample,
+            whitespace_start: formatted.format.whitespace_start,
+            whitespace_end: formatted.format.whitespace_end,
+        },
+    })
+}
+
+/// Stringifies `value` as `format`, reusing the formatting captured by a
+/// previous `parse` when given.
+#[napi]
+pub fn stringify(
+    format: String,
+    value: JsonValue,
+    format_info: Option<NodeFormatInfo>,
+    options: Option<NodeFormatOptions>,
+) -> Result<String> {
+    let format_info = format_info.map_or(
+        FormatInfo {
+            sample: None,
+            whitespace_start: String::new(),
+            whitespace_end: String::new(),
+            yaml_anchors: Vec::new(),
+        },
+        |info| FormatInfo {
+            sample: info.sample,
+            whitespace_start: info.whitespace_start,
+            whitespace_end: info.whitespace_end,
+            yaml_anchors: Vec::new(),
+        },
+    );
+    let formatted = Formatted {
+        value,
+        format: format_info,
+    };
+    binding::stringify(&format, &formatted, options.unwrap_or_default().into())
+        .map_err(Error::from_reason)
+}
@@ -0,0 +1,253 @@
+use std::cmp::Ordering;
+
+use regex::Regex;
+use serde_json::{Number, Value as JsonValue};
+
+use crate::hooks::HookResult;
+use crate::path::{join_index, join_key};
+
+/// A cleanup step applied to values at matching paths.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Normalizer {
+    /// Trim surrounding whitespace from strings.
+    Trim,
+    /// Lowercase strings, for enum-like fields such as `log_level`.
+    Lowercase,
+    /// Drop repeated elements from arrays, keeping the first occurrence.
+    Dedupe,
+    /// Sort arrays of strings or of numbers; mixed arrays are left as is.
+    Sort,
+}
+
+/// Normalizers declared per path glob, applied to freshly parsed values.
+///
+/// Globs use the path syntax of [`parse_path`](crate::parse_path) with
+/// wildcards: `*` matches one key, `[*]` any index and `**` any number of
+/// segments (`**.name`, `servers[*].host`). String normalizers given an
+/// array path apply to the strings inside it. Children are normalized
+/// before their parent, and normalizers for the same path run in the order
+/// they were added.
+///
+/// ```
+/// use c12_parser::{Normalizer, Normalizers};
+/// use serde_json::json;
+///
+/// let normalizers = Normalizers::new()
+///     .add("tags", Normalizer::Trim)
+///     .add("tags", Normalizer::Dedupe)
+///     .add("**.level", Normalizer::Lowercase);
+/// let mut value = json!({ "tags": [" a", "a "], "log": { "level": "DEBUG" } });
+/// normalizers.apply(&mut value);
+/// assert_eq!(value, json!({ "tags": ["a"], "log": { "level": "debug" } }));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Normalizers {
+    rules: Vec<(Regex, Normalizer)>,
+}
+
+impl Normalizers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies `normalizer` at paths matching `glob`, after any previously
+    /// added normalizers for the same path.
+    pub fn add(mut self, glob: &str, normalizer: Normalizer) -> Self {
+        self.rules.push((glob_regex(glob), normalizer));
+        self
+    }
+
+    /// Normalizes `value` in place.
+    pub fn apply(&self, value: &mut JsonValue) {
+        self.visit(value, "");
+    }
+
+    /// Wraps the normalizers as a
+    /// [`C12Context::post_parse`](crate::C12Context::post_parse) hook.
+    pub fn into_hook(self) -> impl Fn(JsonValue) -> HookResult<JsonValue> + Send + Sync + 'static {
+        move |mut value| {
+            self.apply(&mut value);
+            Ok(value)
+        }
+    }
+
+    fn visit(&self, value: &mut JsonValue, path: &str) {
+        match value {
+            JsonValue::Object(map) => {
+                for (key, child) in map.iter_mut() {
+                    self.visit(child, &join_key(path, key));
+                }
+            }
+            JsonValue::Array(items) => {
+                for (i, item) in items.iter_mut().enumerate() {
+                    self.visit(item, &join_index(path, i));
+                }
+            }
+            _ => {}
+        }
+
+        for (glob, normalizer) in &self.rules {
+            if glob.is_match(path) {
+                normalize(value, *normalizer);
+            }
+        }
+    }
+}
+
+fn normalize(value: &mut JsonValue, normalizer: Normalizer) {
+    match (normalizer, value) {
+        (Normalizer::Trim, JsonValue::String(s)) => {
+            let trimmed = s.trim();
+            if trimmed.len() != s.len() {
+                *s = trimmed.to_string();
+            }
+        }
+        (Normalizer::Lowercase, JsonValue::String(s)) => *s = s.to_lowercase(),
+        (Normalizer::Trim | Normalizer::Lowercase, JsonValue::Array(items)) => {
+            for item in items.iter_mut().filter(|item| item.is_string()) {
+                normalize(item, normalizer);
+            }
+        }
+        (Normalizer::Dedupe, JsonValue::Array(items)) => {
+            let mut seen = Vec::with_capacity(items.len());
+            items.retain(|item| {
+                let fresh = !seen.contains(item);
+                if fresh {
+                    seen.push(item.clone());
+                }
+                fresh
+            });
+        }
+        (Normalizer::Sort, JsonValue::Array(items)) => {
+            if items.iter().all(JsonValue::is_string) {
+                items.sort_by(|a, b| a.as_str().cmp(&b.as_str()));
+            } else if items.iter().all(JsonValue::is_number) {
+                items.sort_by(|a, b| match (a, b) {
+                    (JsonValue::Number(a), JsonValue::Number(b)) => compare_numbers(a, b),
+                    _ => Ordering::Equal,
+                });
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Orders integers exactly, so ids past 2^53 keep their order; only
+/// comparisons involving a float go through `f64`.
+fn compare_numbers(a: &Number, b: &Number) -> Ordering {
+    match (a.as_i64(), b.as_i64(), a.as_u64(), b.as_u64()) {
+        (Some(a), Some(b), _, _) => a.cmp(&b),
+        (_, _, Some(a), Some(b)) => a.cmp(&b),
+        // A negative integer against one above `i64::MAX`.
+        (Some(_), None, _, Some(_)) => Ordering::Less,
+        (None, Some(_), Some(_), _) => Ordering::Greater,
+        _ => {
+            let (a, b) = (a.as_f64().unwrap_or(0.0), b.as_f64().unwrap_or(0.0));
+            a.total_cmp(&b)
+        }
+    }
+}
+
+/// Translates a path glob into an anchored regex over formatted paths.
+pub(crate) fn glob_regex(glob: &str) -> Regex {
+    let mut pattern = String::from("^");
+    let mut rest = glob;
+    while !rest.is_empty() {
+        if let Some(tail) = rest.strip_prefix("**.") {
+            pattern.push_str(r"(?:.*\.)?");
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix("**") {
+            pattern.push_str(".*");
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix("[*]") {
+            pattern.push_str(r"\[\d+\]");
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix('*') {
+            pattern.push_str(r"[^.\[\]]*");
+            rest = tail;
+        } else {
+            let c = rest.chars().next().unwrap();
+            pattern.push_str(&regex::escape(c.encode_utf8(&mut [0; 4])));
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern).expect("escaped glob is a valid regex")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn globs_match_formatted_paths() {
+        let matches = |glob, path| glob_regex(glob).is_match(path);
+        assert!(matches("servers[*].host", "servers[3].host"));
+        assert!(!matches("servers[*].host", "servers.host"));
+        assert!(matches("*.level", "log.level"));
+        assert!(!matches("*.level", "a.log.level"));
+        assert!(matches("**.level", "level"));
+        assert!(matches("**.level", "a[0].log.level"));
+        assert!(matches("db.**", "db.pool.size"));
+        assert!(matches(r#"["a.b"].c"#, r#"["a.b"].c"#));
+    }
+
+    #[test]
+    fn normalizes_in_declared_order() {
+        let normalizers = Normalizers::new()
+            .add("hosts", Normalizer::Lowercase)
+            .add("hosts", Normalizer::Dedupe)
+            .add("hosts", Normalizer::Sort)
+            .add("ports", Normalizer::Sort)
+            .add("servers[*].name", Normalizer::Trim);
+        let mut value = json!({
+            "hosts": ["B.example", "a.example", "b.example"],
+            "ports": [443, 80, 8080.5],
+            "servers": [{ "name": "  web " }, { "name": 1 }],
+            "name": "  untouched ",
+        });
+        normalizers.apply(&mut value);
+        assert_eq!(
+            value,
+            json!({
+                "hosts": ["a.example", "b.example"],
+                "ports": [80, 443, 8080.5],
+                "servers": [{ "name": "web" }, { "name": 1 }],
+                "name": "  untouched ",
+            })
+        );
+    }
+
+    #[test]
+    fn sorts_large_integers_exactly() {
+        let normalizers = Normalizers::new().add("ids", Normalizer::Sort);
+        let mut value = json!({
+            "ids": [9007199254740993_u64, u64::MAX, 9007199254740992_u64, -1, 0.5, i64::MIN]
+        });
+        normalizers.apply(&mut value);
+        assert_eq!(
+            value["ids"],
+            json!([
+                i64::MIN,
+                -1,
+                0.5,
+                9007199254740992_u64,
+                9007199254740993_u64,
+                u64::MAX
+            ])
+        );
+    }
+
+    #[test]
+    fn runs_as_a_post_parse_hook() {
+        let ctx = crate::C12Context::new()
+            .post_parse(Normalizers::new().add("mode", Normalizer::Trim).into_hook());
+        let config = ctx
+            .parse_with(r#"{ "mode": " fast " }"#, |text| {
+                crate::parse_json(text, None)
+            })
+            .unwrap();
+        assert_eq!(config.value["mode"], "fast");
+    }
+}
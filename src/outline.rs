@@ -0,0 +1,385 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use jsonc_parser::ast::{ObjectPropName, Value as AstValue};
+use jsonc_parser::common::Ranged;
+use jsonc_parser::{CollectOptions, ParseOptions, parse_to_ast};
+use serde_json::Value as JsonValue;
+use toml_edit::{Item, Table, Value as TomlValue};
+
+use crate::error::{C12Error, parse_format};
+use crate::format::Format;
+use crate::locate::{Location, json5_spans};
+use crate::path::Path;
+use crate::yaml_document::YamlDocument;
+
+/// What an [`OutlineItem`] holds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutlineKind {
+    /// An object, which is a section in INI and a table in TOML.
+    Object,
+    Array,
+    Value,
+}
+
+/// A key or array item in an [`outline`], with where it is written.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutlineItem {
+    /// The key, or `[i]` for the `i`th array item.
+    pub name: String,
+    pub path: Path,
+    pub kind: OutlineKind,
+    /// Byte range from the start of the key, or of the item, to the end
+    /// of the value. TOML tables run from their header to their last key.
+    pub span: Range<usize>,
+    pub start: Location,
+    pub end: Location,
+    pub children: Vec<OutlineItem>,
+}
+
+/// The keys of a config as a tree with their spans, in document order,
+/// for editor outlines and folding.
+///
+/// Everything the format's document model can place is included; an entry
+/// it cannot, such as a TOML key only implied by a dotted key, is left
+/// out and its children take its place. INI names are lowercased, as the
+/// INI parser reads them. Fails with the parse error when `text` does not
+/// parse.
+///
+/// ```
+/// use c12_parser::{Format, OutlineKind, outline};
+///
+/// let text = "[server]\nhost = \"a\"\nports = [80, 443]\n\n[log]\nlevel = \"info\"\n";
+/// let items = outline(text, Format::Toml)?;
+/// let names: Vec<_> = items.iter().map(|item| item.name.as_str()).collect();
+/// assert_eq!(names, ["server", "log"]);
+/// assert_eq!(items[0].kind, OutlineKind::Object);
+/// assert_eq!((items[0].start.line, items[0].end.line), (1, 3));
+/// assert_eq!(&text[items[0].children[1].span.clone()], "ports = [80, 443]");
+/// # Ok::<(), c12_parser::C12Error>(())
+/// ```
+pub fn outline(text: &str, format: Format) -> Result<Vec<OutlineItem>, C12Error> {
+    let value = parse_format::<JsonValue>(text, format, None)?.value;
+    Ok(items(text, &value, &Path::root(), &spans(text, format)))
+}
+
+/// The byte range of every entry of `text` its format's document model can
+/// place, as [`OutlineItem::span`] describes.
+pub(crate) fn spans(text: &str, format: Format) -> HashMap<Path, Range<usize>> {
+    match format {
+        Format::Json | Format::Jsonc => json_spans(text),
+        Format::Json5 => json5_spans(text).map(|spans| {
+            spans
+                .into_iter()
+                .filter(|(path, _)| !path.is_root())
+                .collect()
+        }),
+        Format::Toml => toml_spans(text),
+        Format::Yaml => text
+            .parse::<YamlDocument>()
+            .ok()
+            .and_then(|doc| doc.entry_spans()),
+        Format::Ini => Some(ini_spans(text)),
+    }
+    .unwrap_or_default()
+}
+
+/// The line ranges, 1-based and inclusive, that an editor can fold: every
+/// item in `items` or below spanning more than one line.
+pub fn folding_ranges(items: &[OutlineItem]) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut pending: Vec<&OutlineItem> = items.iter().rev().collect();
+    while let Some(item) = pending.pop() {
+        if item.end.line > item.start.line {
+            ranges.push((item.start.line, item.end.line));
+        }
+        pending.extend(item.children.iter().rev());
+    }
+    ranges
+}
+
+fn items(
+    text: &str,
+    value: &JsonValue,
+    path: &Path,
+    spans: &HashMap<Path, Range<usize>>,
+) -> Vec<OutlineItem> {
+    let children: Vec<(String, Path, &JsonValue)> = match value {
+        JsonValue::Object(map) => map
+            .iter()
+            .map(|(key, child)| (key.clone(), path.clone().key(key.as_str()), child))
+            .collect(),
+        JsonValue::Array(values) => values
+            .iter()
+            .enumerate()
+            .map(|(i, child)| (format!("[{i}]"), path.clone().index(i), child))
+            .collect(),
+        _ => return Vec::new(),
+    };
+    let mut found = Vec::new();
+    for (name, path, child) in children {
+        let nested = items(text, child, &path, spans);
+        let Some(span) = spans.get(&path) else {
+            found.extend(nested);
+            continue;
+        };
+        let kind = match child {
+            JsonValue::Object(_) => OutlineKind::Object,
+            JsonValue::Array(_) => OutlineKind::Array,
+            _ => OutlineKind::Value,
+        };
+        found.push(OutlineItem {
+            name,
+            path,
+            kind,
+            span: span.clone(),
+            start: Location::of_offset(text, span.start),
+            end: Location::of_offset(text, span.end),
+            children: nested,
+        });
+    }
+    found
+}
+
+fn json_spans(text: &str) -> Option<HashMap<Path, Range<usize>>> {
+    let ast = parse_to_ast(text, &CollectOptions::default(), &ParseOptions::default()).ok()?;
+    let mut spans = HashMap::new();
+    json_value(&ast.value?, Path::root(), &mut spans);
+    Some(spans)
+}
+
+fn json_value(value: &AstValue, path: Path, spans: &mut HashMap<Path, Range<usize>>) {
+    match value {
+        AstValue::Object(object) => {
+            for prop in &object.properties {
+                let name = match &prop.name {
+                    ObjectPropName::String(s) => s.value.to_string(),
+                    ObjectPropName::Word(w) => w.value.to_string(),
+                };
+                let child = path.clone().key(name);
+                spans.insert(child.clone(), prop.range.start..prop.range.end);
+                json_value(&prop.value, child, spans);
+            }
+        }
+        AstValue::Array(array) => {
+            for (i, element) in array.elements.iter().enumerate() {
+                let child = path.clone().index(i);
+                let range = element.range();
+                spans.insert(child.clone(), range.start..range.end);
+                json_value(element, child, spans);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn toml_spans(text: &str) -> Option<HashMap<Path, Range<usize>>> {
+    let doc = toml_edit::Document::parse(text).ok()?;
+    let mut spans = HashMap::new();
+    toml_table(doc.as_table(), &Path::root(), &mut spans);
+    Some(spans)
+}
+
+/// The smallest range covering both.
+fn join(a: Option<Range<usize>>, b: Option<Range<usize>>) -> Option<Range<usize>> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.start.min(b.start)..a.end.max(b.end)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Records the entries of `table` and returns the range they and its
+/// header cover.
+fn toml_table(
+    table: &Table,
+    path: &Path,
+    spans: &mut HashMap<Path, Range<usize>>,
+) -> Option<Range<usize>> {
+    let mut covered = table.span();
+    for (name, _) in table.iter() {
+        let Some((key, item)) = table.get_key_value(name) else {
+            continue;
+        };
+        let child = path.clone().key(name);
+        let span = match item {
+            Item::Value(value) => join(key.span(), toml_value(value, &child, spans)),
+            Item::Table(table) => join(key.span(), toml_table(table, &child, spans)),
+            Item::ArrayOfTables(tables) => {
+                tables.iter().enumerate().fold(None, |covered, (i, table)| {
+                    let item = child.clone().index(i);
+                    let span = toml_table(table, &item, spans);
+                    if let Some(span) = &span {
+                        spans.insert(item, span.clone());
+                    }
+                    join(covered, span)
+                })
+            }
+            Item::None => None,
+        };
+        if let Some(span) = span {
+            covered = join(covered, Some(span.clone()));
+            spans.insert(child, span);
+        }
+    }
+    covered
+}
+
+fn toml_value(
+    value: &TomlValue,
+    path: &Path,
+    spans: &mut HashMap<Path, Range<usize>>,
+) -> Option<Range<usize>> {
+    match value {
+        TomlValue::InlineTable(table) => {
+            for (name, _) in table.iter() {
+                let Some((key, item)) = table.get_key_value(name) else {
+                    continue;
+                };
+                let child = path.clone().key(name);
+                let inner = item.as_value().and_then(|v| toml_value(v, &child, spans));
+                if let Some(span) = join(key.span(), inner) {
+                    spans.insert(child, span);
+                }
+            }
+        }
+        TomlValue::Array(array) => {
+            for (i, item) in array.iter().enumerate() {
+                let child = path.clone().index(i);
+                if let Some(span) = toml_value(item, &child, spans) {
+                    spans.insert(child, span);
+                }
+            }
+        }
+        _ => {}
+    }
+    value.span()
+}
+
+/// Sections run from their header, or their first key for the keys
+/// before any header, to their last key.
+fn ini_spans(text: &str) -> HashMap<Path, Range<usize>> {
+    let mut spans = HashMap::new();
+    let mut section = Path::root().key("default");
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end();
+        let start = offset + (line.len() - line.trim_start().len());
+        let end = offset + content.len();
+        offset += line.len();
+        let trimmed = content.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with([';', '#']) {
+            continue;
+        }
+        if let Some(name) = trimmed.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = Path::root().key(name.trim().to_lowercase());
+            spans.insert(section.clone(), start..end);
+            continue;
+        }
+        let name = trimmed.split_once('=').map_or(trimmed, |(name, _)| name);
+        spans.insert(section.clone().key(name.trim().to_lowercase()), start..end);
+        spans
+            .entry(section.clone())
+            .and_modify(|span| span.end = end)
+            .or_insert(start..end);
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(items: &[OutlineItem]) -> Vec<String> {
+        let mut out = Vec::new();
+        for item in items {
+            out.push(format!(
+                "{}@{}-{}",
+                item.path, item.start.line, item.end.line
+            ));
+            out.extend(names(&item.children));
+        }
+        out
+    }
+
+    #[test]
+    fn outlines_every_format() {
+        let json = "{\n  \"db\": {\n    \"hosts\": [\"a\", \"b\"]\n  },\n  \"debug\": true\n}\n";
+        let yaml = "db:\n  hosts:\n    - a\n    - b\ndebug: true\n";
+        let toml = "debug = true\n\n[db]\nhosts = [\n  \"a\",\n  \"b\",\n]\n";
+        let ini = "debug = true\n\n[db]\nhost = a\nport = 1\n";
+        assert_eq!(
+            names(&outline(json, Format::Json).unwrap()),
+            [
+                "db@2-4",
+                "db.hosts@3-3",
+                "db.hosts[0]@3-3",
+                "db.hosts[1]@3-3",
+                "debug@5-5"
+            ]
+        );
+        assert_eq!(
+            names(&outline(yaml, Format::Yaml).unwrap()),
+            [
+                "db@1-4",
+                "db.hosts@2-4",
+                "db.hosts[0]@3-3",
+                "db.hosts[1]@4-4",
+                "debug@5-5"
+            ]
+        );
+        assert_eq!(
+            names(&outline(toml, Format::Toml).unwrap()),
+            [
+                "debug@1-1",
+                "db@3-7",
+                "db.hosts@4-7",
+                "db.hosts[0]@5-5",
+                "db.hosts[1]@6-6"
+            ]
+        );
+        assert_eq!(
+            names(&outline(ini, Format::Ini).unwrap()),
+            [
+                "default@1-1",
+                "default.debug@1-1",
+                "db@3-5",
+                "db.host@4-4",
+                "db.port@5-5"
+            ]
+        );
+        let json5 = "{\n  db: {\n    hosts: [.5, Infinity],\n  },\n  null: 'a\\\nb',\n}\n";
+        assert_eq!(
+            names(&outline(json5, Format::Json5).unwrap()),
+            [
+                "db@2-4",
+                "db.hosts@3-3",
+                "db.hosts[0]@3-3",
+                "db.hosts[1]@3-3",
+                "null@5-6"
+            ]
+        );
+    }
+
+    #[test]
+    fn folds_multi_line_items_and_reports_parse_errors() {
+        let toml = "a.b = 1\n\n[[srv]]\nname = \"x\"\n\n[[srv]]\nname = \"y\"\nport = 2\n";
+        let items = outline(toml, Format::Toml).unwrap();
+        assert_eq!(
+            names(&items),
+            [
+                "a@1-1",
+                "a.b@1-1",
+                "srv@3-8",
+                "srv[0]@3-4",
+                "srv[0].name@4-4",
+                "srv[1]@6-8",
+                "srv[1].name@7-7",
+                "srv[1].port@8-8"
+            ]
+        );
+        assert_eq!(folding_ranges(&items), [(3, 8), (3, 4), (6, 8)]);
+
+        let err = outline("{\n  \"a\": [1,\n", Format::Json).unwrap_err();
+        assert_eq!(err.format, Format::Json);
+    }
+}
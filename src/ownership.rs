@@ -0,0 +1,313 @@
+use std::fmt;
+use std::path::{Path as FsPath, PathBuf};
+use std::str::FromStr;
+
+use regex::Regex;
+
+use crate::path::{Path, PathSegment};
+use crate::query::{Query, QuerySegment};
+use crate::walk::{file_glob, slash_path};
+
+/// One line of an [`OwnershipPolicy`].
+#[derive(Clone, Debug)]
+pub struct OwnerRule {
+    /// The file glob, relative to the policy root.
+    pub files: String,
+    /// The keys the rule covers, or `None` for the whole file.
+    pub keys: Option<Query>,
+    /// Users or teams, e.g. `@org/payments`. Empty to leave the match
+    /// unowned.
+    pub owners: Vec<String>,
+    glob: Regex,
+}
+
+/// Who owns which configs, in a CODEOWNERS-style file: each line is a file
+/// glob, optionally followed by `:` and a [`Query`] over keys, then the
+/// owners. As in CODEOWNERS, the last matching line wins, and `#` starts
+/// a comment.
+///
+/// ```text
+/// **/*.yaml                     @org/platform
+/// services/billing/**           @org/payments
+/// services/**:database          @org/dba
+/// services/**:..tls             @org/security @alice
+/// ```
+///
+/// A key rule covers the keys below the ones it names, so `database` owns
+/// `database.pool.size` as well.
+#[derive(Clone, Debug, Default)]
+pub struct OwnershipPolicy {
+    pub rules: Vec<OwnerRule>,
+    root: PathBuf,
+}
+
+/// Returned when an ownership policy has a malformed line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OwnershipParseError {
+    /// 1-based line of the policy.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for OwnershipParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ownership policy line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for OwnershipParseError {}
+
+impl FromStr for OwnershipPolicy {
+    type Err = OwnershipParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut rules = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let error = |message: String| OwnershipParseError {
+                line: i + 1,
+                message,
+            };
+            let line = line.split_once('#').map_or(line, |(line, _)| line);
+            let mut words = line.split_whitespace();
+            let Some(pattern) = words.next() else {
+                continue;
+            };
+            let (files, keys) = match pattern.split_once(':') {
+                Some((files, keys)) => {
+                    let query = keys.parse::<Query>().map_err(|e| error(e.to_string()))?;
+                    (files, Some(query))
+                }
+                None => (pattern, None),
+            };
+            rules.push(OwnerRule {
+                files: files.to_string(),
+                keys,
+                owners: words.map(str::to_string).collect(),
+                glob: file_glob(files),
+            });
+        }
+        Ok(Self {
+            rules,
+            root: PathBuf::new(),
+        })
+    }
+}
+
+impl OwnershipPolicy {
+    /// Matches file globs against paths relative to `root`, e.g. the
+    /// repository holding the policy. Files outside it match no rule.
+    pub fn root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    /// The owners of the key at `path` in `file`; empty when no rule
+    /// matches.
+    pub fn owners(&self, file: &FsPath, path: &Path) -> &[String] {
+        let Ok(relative) = file.strip_prefix(&self.root) else {
+            return &[];
+        };
+        let file = slash_path(relative);
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| {
+                rule.glob.is_match(&file)
+                    && rule
+                        .keys
+                        .as_ref()
+                        .is_none_or(|keys| covers(&keys.segments, path.segments()))
+            })
+            .map_or(&[], |rule| &rule.owners)
+    }
+
+    /// The edits among `changed` that `actor` (a user and the teams they
+    /// belong to) makes to keys owned by someone else. Unowned keys are
+    /// anyone's to edit.
+    pub fn foreign_edits(
+        &self,
+        file: &FsPath,
+        actor: &[impl AsRef<str>],
+        changed: impl IntoIterator<Item = Path>,
+    ) -> Vec<ForeignEdit> {
+        changed
+            .into_iter()
+            .filter_map(|path| {
+                let owners = self.owners(file, &path);
+                let own = owners
+                    .iter()
+                    .any(|owner| actor.iter().any(|a| a.as_ref() == owner));
+                (!owners.is_empty() && !own).then(|| ForeignEdit {
+                    file: file.to_path_buf(),
+                    owners: owners.to_vec(),
+                    path,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Whether a key pattern covers `path`: the pattern matches `path` or one
+/// of its parents, or `path` is a parent of keys it matches.
+fn covers(pattern: &[QuerySegment], path: &[PathSegment]) -> bool {
+    let Some((first, rest)) = pattern.split_first() else {
+        return true;
+    };
+    let Some((segment, path_rest)) = path.split_first() else {
+        return true;
+    };
+    match (first, segment) {
+        (QuerySegment::Descendants, _) => {
+            covers(rest, path) || (!path_rest.is_empty() && covers(pattern, path_rest))
+        }
+        (QuerySegment::Key(a), PathSegment::Key(b)) if a == b => covers(rest, path_rest),
+        (QuerySegment::Index(a), PathSegment::Index(b)) if a == b => covers(rest, path_rest),
+        (QuerySegment::AnyKey, PathSegment::Key(_))
+        | (QuerySegment::AnyIndex, PathSegment::Index(_)) => covers(rest, path_rest),
+        _ => false,
+    }
+}
+
+/// An edit to a key owned by someone other than the editor, reported as
+/// a warning or, where acknowledgment is required, an error.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ForeignEdit {
+    pub file: PathBuf,
+    pub path: Path,
+    pub owners: Vec<String>,
+}
+
+impl fmt::Display for ForeignEdit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.segments().is_empty() {
+            "the whole file".to_string()
+        } else {
+            format!("`{}`", self.path)
+        };
+        write!(
+            f,
+            "{path} in {} is owned by {}",
+            self.file.display(),
+            self.owners.join(", ")
+        )
+    }
+}
+
+/// Returned when saving edits keys owned by other teams without
+/// acknowledging them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OwnershipError {
+    pub edits: Vec<ForeignEdit>,
+}
+
+impl fmt::Display for OwnershipError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unacknowledged edits to keys owned by other teams: ")?;
+        for (i, edit) in self.edits.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{edit}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for OwnershipError {}
+
+/// The policy a [`C12Context`](crate::C12Context) enforces on save.
+#[derive(Clone, Debug)]
+pub(crate) struct Ownership {
+    pub(crate) policy: OwnershipPolicy,
+    pub(crate) actor: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value_diff::diff_values;
+    use serde_json::json;
+
+    const POLICY: &str = "\
+# platform owns everything by default
+**                      @org/platform
+services/billing/**     @org/payments
+services/**:database    @org/dba   # schema changes go through DBAs
+services/**:..tls       @org/security @alice
+services/**:scratch
+";
+
+    fn path(expr: &str) -> Path {
+        expr.parse().unwrap()
+    }
+
+    #[test]
+    fn the_last_matching_rule_wins() {
+        let policy: OwnershipPolicy = POLICY.parse().unwrap();
+        let billing = FsPath::new("services/billing/app.yaml");
+        assert_eq!(
+            policy.owners(billing, &path("http.port")),
+            ["@org/payments"]
+        );
+        assert_eq!(
+            policy.owners(billing, &path("database.pool.size")),
+            ["@org/dba"]
+        );
+        assert_eq!(
+            policy.owners(billing, &path("edge[0].tls.cert")),
+            ["@org/security", "@alice"]
+        );
+        assert!(policy.owners(billing, &path("scratch.notes")).is_empty());
+        assert_eq!(
+            policy.owners("tools/lint.toml".as_ref(), &path("database")),
+            ["@org/platform"]
+        );
+
+        let rooted = policy.root("/repo");
+        assert_eq!(
+            rooted.owners("/repo/services/x.json".as_ref(), &path("database")),
+            ["@org/dba"]
+        );
+        assert!(
+            rooted
+                .owners("/elsewhere/x.json".as_ref(), &path("a"))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn foreign_edits_skip_the_actors_own_keys() {
+        let policy: OwnershipPolicy = POLICY.parse().unwrap();
+        let file = FsPath::new("services/web/app.json");
+        let old = json!({ "database": { "pool": 4 }, "http": { "tls": true }, "scratch": 1 });
+        let new = json!({ "database": { "pool": 8 }, "http": { "tls": false }, "scratch": 2 });
+        let changed: Vec<_> = diff_values(&old, &new)
+            .into_iter()
+            .map(|c| c.path)
+            .collect();
+        assert_eq!(
+            changed,
+            [path("database.pool"), path("http.tls"), path("scratch")]
+        );
+
+        let edits = policy.foreign_edits(file, &["@alice", "@org/web"], changed);
+        assert_eq!(edits.len(), 1);
+        assert_eq!(
+            edits[0].to_string(),
+            "`database.pool` in services/web/app.json is owned by @org/dba"
+        );
+    }
+
+    #[test]
+    fn malformed_lines_are_reported() {
+        let error = "a/**  @x\nb/**:a[ @y\n"
+            .parse::<OwnershipPolicy>()
+            .unwrap_err();
+        assert_eq!(error.line, 2);
+        let error = "b/**:a>1 @y".parse::<OwnershipPolicy>().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "ownership policy line 1: invalid path at byte 1: expected `.`, `..` or `[`"
+        );
+    }
+}
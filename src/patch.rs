@@ -0,0 +1,478 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::format::Formatted;
+use crate::path::{Path, PathSegment};
+
+/// One JSON Patch operation. Paths are JSON Pointers (RFC 6901), e.g.
+/// `/servers/0/port`, with `~1` for `/` and `~0` for `~` inside keys.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOp {
+    /// Adds a key, replaces an existing key, or inserts into an array;
+    /// `-` as the last token appends.
+    Add {
+        path: String,
+        value: JsonValue,
+    },
+    Remove {
+        path: String,
+    },
+    Replace {
+        path: String,
+        value: JsonValue,
+    },
+    Move {
+        from: String,
+        path: String,
+    },
+    Copy {
+        from: String,
+        path: String,
+    },
+    /// Fails the patch unless the value at `path` equals `value`.
+    Test {
+        path: String,
+        value: JsonValue,
+    },
+}
+
+impl PatchOp {
+    /// The pointer the operation writes to, or tests.
+    pub fn path(&self) -> &str {
+        match self {
+            PatchOp::Add { path, .. }
+            | PatchOp::Remove { path }
+            | PatchOp::Replace { path, .. }
+            | PatchOp::Move { path, .. }
+            | PatchOp::Copy { path, .. }
+            | PatchOp::Test { path, .. } => path,
+        }
+    }
+}
+
+/// A JSON Patch (RFC 6902): operations applied in order, all or nothing.
+/// Serializes as the RFC's JSON array, so patches can be stored as
+/// migration files and read back with `serde_json`.
+///
+/// ```
+/// use c12_parser::{Patch, apply_patch, diff, parse_jsonc, stringify_jsonc};
+///
+/// let mut config = parse_jsonc("{\n    \"port\": 8080\n}", None, None)?;
+/// let patch: Patch = serde_json::from_str(
+///     r#"[{ "op": "replace", "path": "/port", "value": 9090 },
+///         { "op": "add", "path": "/host", "value": "0.0.0.0" }]"#,
+/// )?;
+/// let before = config.value.clone();
+/// apply_patch(&mut config, &patch)?;
+/// assert_eq!(
+///     stringify_jsonc(&config, None)?,
+///     "{\n    \"port\": 9090,\n    \"host\": \"0.0.0.0\"\n}"
+/// );
+/// assert_eq!(diff(&before, &config.value), patch);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Patch {
+    pub ops: Vec<PatchOp>,
+}
+
+impl Patch {
+    /// Applies every operation to `value`, leaving it untouched if one
+    /// fails.
+    pub fn apply(&self, value: &mut JsonValue) -> Result<(), PatchError> {
+        let mut patched = value.clone();
+        for (index, op) in self.ops.iter().enumerate() {
+            apply_op(&mut patched, op).map_err(|message| PatchError {
+                index,
+                pointer: op.path().to_string(),
+                path: pointer_path(&patched, op.path()),
+                message,
+            })?;
+        }
+        *value = patched;
+        Ok(())
+    }
+}
+
+/// Returned when a [`Patch`] operation cannot be applied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PatchError {
+    /// Position of the failing operation in the patch.
+    pub index: usize,
+    /// The operation's JSON Pointer, as written in the patch.
+    pub pointer: String,
+    /// The same location as a [`Path`], `None` when the pointer is
+    /// malformed.
+    pub path: Option<Path>,
+    pub message: String,
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "patch operation {} at `{}` failed: {}",
+            self.index, self.pointer, self.message
+        )
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+/// Applies `patch` to a parsed document. The document's formatting
+/// metadata is kept, so it stringifies with its original indentation.
+pub fn apply_patch(formatted: &mut Formatted<JsonValue>, patch: &Patch) -> Result<(), PatchError> {
+    patch.apply(&mut formatted.value)
+}
+
+/// Applies a JSON Merge Patch (RFC 7386) to a parsed document, for
+/// layering an override file on a base config: objects merge key by key,
+/// `null` deletes a key, and anything else replaces the target value
+/// whole, arrays included. The base document's formatting metadata is
+/// kept.
+///
+/// ```
+/// use c12_parser::{merge_patch, parse_yaml};
+/// use serde_json::json;
+///
+/// let mut base = parse_yaml("server:\n  port: 8080\n  debug: true\n", None)?;
+/// let overrides = parse_yaml("server:\n  port: 9090\n  debug: null\n", None)?;
+/// merge_patch(&mut base, &overrides.value);
+/// assert_eq!(base.value, json!({ "server": { "port": 9090 } }));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn merge_patch(target: &mut Formatted<JsonValue>, patch: &JsonValue) {
+    merge_value(&mut target.value, patch);
+}
+
+fn merge_value(target: &mut JsonValue, patch: &JsonValue) {
+    let JsonValue::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = JsonValue::Object(Default::default());
+    }
+    let JsonValue::Object(map) = target else {
+        unreachable!("target was just made an object");
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            map.shift_remove(key);
+        } else {
+            merge_value(map.entry(key.clone()).or_insert(JsonValue::Null), value);
+        }
+    }
+}
+
+/// A patch turning `a` into `b`: objects are compared key by key and
+/// arrays index by index, and anything else that differs is replaced
+/// whole. Removals come before additions at each level.
+pub fn diff(a: &JsonValue, b: &JsonValue) -> Patch {
+    let mut ops = Vec::new();
+    diff_into(a, b, &mut String::new(), &mut ops);
+    Patch { ops }
+}
+
+fn diff_into(a: &JsonValue, b: &JsonValue, pointer: &mut String, ops: &mut Vec<PatchOp>) {
+    let len = pointer.len();
+    match (a, b) {
+        (JsonValue::Object(a), JsonValue::Object(b)) => {
+            for key in a.keys().filter(|key| !b.contains_key(*key)) {
+                push_token(pointer, key);
+                ops.push(PatchOp::Remove {
+                    path: pointer.clone(),
+                });
+                pointer.truncate(len);
+            }
+            for (key, new) in b {
+                push_token(pointer, key);
+                match a.get(key) {
+                    Some(old) => diff_into(old, new, pointer, ops),
+                    None => ops.push(PatchOp::Add {
+                        path: pointer.clone(),
+                        value: new.clone(),
+                    }),
+                }
+                pointer.truncate(len);
+            }
+        }
+        (JsonValue::Array(a), JsonValue::Array(b)) => {
+            for (i, (old, new)) in a.iter().zip(b).enumerate() {
+                push_token(pointer, &i.to_string());
+                diff_into(old, new, pointer, ops);
+                pointer.truncate(len);
+            }
+            for i in (b.len()..a.len()).rev() {
+                ops.push(PatchOp::Remove {
+                    path: format!("{pointer}/{i}"),
+                });
+            }
+            for (i, new) in b.iter().enumerate().skip(a.len()) {
+                ops.push(PatchOp::Add {
+                    path: format!("{pointer}/{i}"),
+                    value: new.clone(),
+                });
+            }
+        }
+        _ if a != b => ops.push(PatchOp::Replace {
+            path: pointer.clone(),
+            value: b.clone(),
+        }),
+        _ => {}
+    }
+}
+
+fn push_token(pointer: &mut String, key: &str) {
+    pointer.push('/');
+    pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+}
+
+fn apply_op(value: &mut JsonValue, op: &PatchOp) -> Result<(), String> {
+    match op {
+        PatchOp::Add { path, value: new } => add(value, path, new.clone()),
+        PatchOp::Remove { path } => remove(value, path).map(drop),
+        PatchOp::Replace { path, value: new } => {
+            *pointee_mut(value, &tokens(path)?)? = new.clone();
+            Ok(())
+        }
+        PatchOp::Move { from, path } => {
+            if path.starts_with(from.as_str()) && path[from.len()..].starts_with('/') {
+                return Err(format!("cannot move `{from}` into itself"));
+            }
+            let moved = remove(value, from)?;
+            add(value, path, moved)
+        }
+        PatchOp::Copy { from, path } => {
+            let copied = pointee_mut(value, &tokens(from)?)?.clone();
+            add(value, path, copied)
+        }
+        PatchOp::Test {
+            path,
+            value: expected,
+        } => {
+            let actual = pointee_mut(value, &tokens(path)?)?;
+            if actual == expected {
+                Ok(())
+            } else {
+                Err(format!("expected {expected}, found {actual}"))
+            }
+        }
+    }
+}
+
+/// The unescaped reference tokens of a JSON Pointer.
+fn tokens(pointer: &str) -> Result<Vec<String>, String> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    let rest = pointer
+        .strip_prefix('/')
+        .ok_or_else(|| format!("`{pointer}` is not a JSON Pointer"))?;
+    Ok(rest
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+/// The [`Path`] `pointer` names in `value`: a token is an index where it
+/// points into an array, and a key anywhere else.
+fn pointer_path(value: &JsonValue, pointer: &str) -> Option<Path> {
+    let mut node = Some(value);
+    let mut path = Path::root();
+    for token in tokens(pointer).ok()? {
+        let segment = match (node, token.parse::<usize>()) {
+            (Some(JsonValue::Array(_)), Ok(i)) => PathSegment::Index(i),
+            _ => PathSegment::Key(token),
+        };
+        node = node.and_then(|node| match &segment {
+            PathSegment::Index(i) => node.get(i),
+            PathSegment::Key(key) => node.get(key),
+        });
+        path.push(segment);
+    }
+    Some(path)
+}
+
+/// An array index token: digits without leading zeros.
+fn index(token: &str, len: usize) -> Result<usize, String> {
+    let valid = !token.is_empty()
+        && token.bytes().all(|b| b.is_ascii_digit())
+        && (token == "0" || !token.starts_with('0'));
+    match token.parse::<usize>() {
+        Ok(i) if valid && i < len => Ok(i),
+        _ => Err(format!("index `{token}` is out of bounds")),
+    }
+}
+
+fn pointee_mut<'a>(
+    value: &'a mut JsonValue,
+    tokens: &[String],
+) -> Result<&'a mut JsonValue, String> {
+    tokens.iter().try_fold(value, |node, token| match node {
+        JsonValue::Object(map) => map
+            .get_mut(token)
+            .ok_or_else(|| format!("`{token}` does not exist")),
+        JsonValue::Array(items) => {
+            let i = index(token, items.len())?;
+            Ok(&mut items[i])
+        }
+        _ => Err(format!("`{token}` is not inside an object or array")),
+    })
+}
+
+fn add(value: &mut JsonValue, pointer: &str, new: JsonValue) -> Result<(), String> {
+    let tokens = tokens(pointer)?;
+    let Some((last, parents)) = tokens.split_last() else {
+        *value = new;
+        return Ok(());
+    };
+    match pointee_mut(value, parents)? {
+        JsonValue::Object(map) => {
+            map.insert(last.clone(), new);
+        }
+        JsonValue::Array(items) if last == "-" => items.push(new),
+        JsonValue::Array(items) => {
+            let i = index(last, items.len() + 1)?;
+            items.insert(i, new);
+        }
+        _ => return Err("parent is not an object or array".into()),
+    }
+    Ok(())
+}
+
+fn remove(value: &mut JsonValue, pointer: &str) -> Result<JsonValue, String> {
+    let tokens = tokens(pointer)?;
+    let Some((last, parents)) = tokens.split_last() else {
+        return Ok(value.take());
+    };
+    match pointee_mut(value, parents)? {
+        JsonValue::Object(map) => map
+            .shift_remove(last)
+            .ok_or_else(|| format!("`{last}` does not exist")),
+        JsonValue::Array(items) => {
+            let i = index(last, items.len())?;
+            Ok(items.remove(i))
+        }
+        _ => Err("parent is not an object or array".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn patch(ops: JsonValue) -> Patch {
+        serde_json::from_value(ops).unwrap()
+    }
+
+    #[test]
+    fn applies_every_rfc_operation() {
+        let mut value = json!({ "a/b": 1, "list": [1, 2], "old": { "x": true } });
+        patch(json!([
+            { "op": "test", "path": "/a~1b", "value": 1 },
+            { "op": "add", "path": "/list/1", "value": 9 },
+            { "op": "add", "path": "/list/-", "value": 3 },
+            { "op": "remove", "path": "/list/0" },
+            { "op": "move", "from": "/old", "path": "/new" },
+            { "op": "copy", "from": "/new/x", "path": "/flag" },
+            { "op": "replace", "path": "/a~1b", "value": "one" },
+        ]))
+        .apply(&mut value)
+        .unwrap();
+        assert_eq!(
+            value,
+            json!({ "a/b": "one", "list": [9, 2, 3], "new": { "x": true }, "flag": true })
+        );
+    }
+
+    #[test]
+    fn a_failing_operation_leaves_the_value_alone() {
+        let mut value = json!({ "port": 8080, "list": [] });
+        let err = patch(json!([
+            { "op": "replace", "path": "/port", "value": 9090 },
+            { "op": "test", "path": "/port", "value": 8080 },
+        ]))
+        .apply(&mut value)
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "patch operation 1 at `/port` failed: expected 8080, found 9090"
+        );
+        assert_eq!(value, json!({ "port": 8080, "list": [] }));
+        assert_eq!(err.path, Some("port".parse().unwrap()));
+        let err = patch(json!([{ "op": "remove", "path": "/list/0" }]))
+            .apply(&mut value)
+            .unwrap_err();
+        assert_eq!(err.path, Some(Path::root().key("list").index(0)));
+
+        for bad in [
+            json!({ "op": "remove", "path": "/missing" }),
+            json!({ "op": "add", "path": "/list/01", "value": 1 }),
+            json!({ "op": "add", "path": "port", "value": 1 }),
+            json!({ "op": "move", "from": "/list", "path": "/list/0" }),
+        ] {
+            assert!(patch(json!([bad])).apply(&mut value).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn merge_patches_follow_the_rfc_examples() {
+        let cases = [
+            (
+                json!({ "a": "b" }),
+                json!({ "a": "c" }),
+                json!({ "a": "c" }),
+            ),
+            (json!({ "a": "b" }), json!({ "a": null }), json!({})),
+            (
+                json!({ "a": [{ "b": "c" }] }),
+                json!({ "a": [1] }),
+                json!({ "a": [1] }),
+            ),
+            (json!(["a", "b"]), json!({ "a": "c" }), json!({ "a": "c" })),
+            (json!({ "a": "foo" }), json!(null), json!(null)),
+            (
+                json!({ "e": null }),
+                json!({ "a": 1 }),
+                json!({ "e": null, "a": 1 }),
+            ),
+            (
+                json!({}),
+                json!({ "a": { "bb": { "ccc": null } } }),
+                json!({ "a": { "bb": {} } }),
+            ),
+        ];
+        for (target, patch, expected) in cases {
+            let mut formatted = Formatted::new("{}", target, &Default::default());
+            merge_patch(&mut formatted, &patch);
+            assert_eq!(formatted.value, expected, "{patch}");
+        }
+    }
+
+    #[test]
+    fn diffs_apply_back() {
+        let a = json!({ "keep": 1, "drop": 2, "list": [1, 2, 3], "nested": { "v": "a" } });
+        let b = json!({ "keep": 1, "list": [1, 5], "nested": { "v": "b", "w/~": [] } });
+        let patch = diff(&a, &b);
+        assert_eq!(
+            serde_json::to_value(&patch).unwrap(),
+            json!([
+                { "op": "remove", "path": "/drop" },
+                { "op": "replace", "path": "/list/1", "value": 5 },
+                { "op": "remove", "path": "/list/2" },
+                { "op": "replace", "path": "/nested/v", "value": "b" },
+                { "op": "add", "path": "/nested/w~1~0", "value": [] },
+            ])
+        );
+        let mut patched = a.clone();
+        patch.apply(&mut patched).unwrap();
+        assert_eq!(patched, b);
+        assert!(diff(&b, &b).ops.is_empty());
+    }
+}
@@ -0,0 +1,645 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde_json::Value as JsonValue;
+
+/// One step of a path expression.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+impl From<&str> for PathSegment {
+    fn from(key: &str) -> Self {
+        PathSegment::Key(key.to_string())
+    }
+}
+
+impl From<String> for PathSegment {
+    fn from(key: String) -> Self {
+        PathSegment::Key(key)
+    }
+}
+
+impl From<usize> for PathSegment {
+    fn from(index: usize) -> Self {
+        PathSegment::Index(index)
+    }
+}
+
+/// A location inside a config value, as a list of key and index segments.
+///
+/// Build one programmatically instead of escaping a string by hand, or
+/// parse one with [`str::parse`]; `Display` writes the expression form
+/// read by [`parse_path`].
+///
+/// ```
+/// use c12_parser::Path;
+///
+/// let path = Path::root().key("servers").key("10.0.0.1").key("port");
+/// assert_eq!(path.to_string(), r#"servers["10.0.0.1"].port"#);
+/// assert_eq!(path.to_string().parse::<Path>()?, path);
+/// # Ok::<(), c12_parser::PathParseError>(())
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Path {
+    segments: Vec<PathSegment>,
+}
+
+impl Path {
+    /// The empty path, pointing at the whole value.
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    /// Returns the path extended with an object key.
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.segments.push(PathSegment::Key(key.into()));
+        self
+    }
+
+    /// Returns the path extended with an array index.
+    pub fn index(mut self, index: usize) -> Self {
+        self.segments.push(PathSegment::Index(index));
+        self
+    }
+
+    pub fn push(&mut self, segment: impl Into<PathSegment>) {
+        self.segments.push(segment.into());
+    }
+
+    pub fn pop(&mut self) -> Option<PathSegment> {
+        self.segments.pop()
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// The path without its last segment, `None` for the root.
+    pub fn parent(&self) -> Option<Path> {
+        let (_, parent) = self.segments.split_last()?;
+        Some(Path {
+            segments: parent.to_vec(),
+        })
+    }
+
+    /// The path as a JSON Pointer (RFC 6901), such as `/servers/0/host`;
+    /// the root is the empty string.
+    pub fn to_pointer(&self) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                PathSegment::Key(key) => format!("/{}", key.replace('~', "~0").replace('/', "~1")),
+                PathSegment::Index(index) => format!("/{index}"),
+            })
+            .collect()
+    }
+
+    /// Looks the path up in `value`.
+    pub fn lookup<'a>(&self, value: &'a JsonValue) -> Option<&'a JsonValue> {
+        self.segments
+            .iter()
+            .try_fold(value, |value, segment| match segment {
+                PathSegment::Key(key) => value.as_object()?.get(key),
+                PathSegment::Index(index) => value.as_array()?.get(*index),
+            })
+    }
+
+    /// Looks the path up in `value` for mutation.
+    pub fn lookup_mut<'a>(&self, value: &'a mut JsonValue) -> Option<&'a mut JsonValue> {
+        self.segments
+            .iter()
+            .try_fold(value, |value, segment| match segment {
+                PathSegment::Key(key) => value.as_object_mut()?.get_mut(key),
+                PathSegment::Index(index) => value.as_array_mut()?.get_mut(*index),
+            })
+    }
+
+    /// Stores `new` at the path in `value`, returning what it replaced.
+    ///
+    /// Missing keys are created along the way, as objects or, before an
+    /// index, arrays; nulls count as missing. An index may point one past
+    /// the end of an array to append. Fails when the path runs through
+    /// a scalar or further past the end of an array.
+    pub fn set(
+        &self,
+        value: &mut JsonValue,
+        new: JsonValue,
+    ) -> Result<Option<JsonValue>, PathError> {
+        let Some((last, parents)) = self.segments.split_last() else {
+            return Ok(Some(std::mem::replace(value, new)));
+        };
+        let conflict = |depth: usize, message: &str| {
+            let at = match depth {
+                0 => "the root".to_string(),
+                _ => format!("`{}`", format_path(&self.segments[..depth])),
+            };
+            PathError::Conflict {
+                path: self.clone(),
+                message: format!("{at} {message}"),
+            }
+        };
+        let mut current = value;
+        for (depth, segment) in parents.iter().enumerate() {
+            let next = &self.segments[depth + 1];
+            current = child_or_insert(current, segment, next).map_err(|m| conflict(depth, m))?;
+        }
+        match last {
+            PathSegment::Key(key) => {
+                let map = container(current, last)
+                    .as_object_mut()
+                    .ok_or_else(|| conflict(parents.len(), "is not an object"))?;
+                Ok(map.insert(key.clone(), new))
+            }
+            PathSegment::Index(index) => {
+                let items = container(current, last)
+                    .as_array_mut()
+                    .ok_or_else(|| conflict(parents.len(), "is not an array"))?;
+                if *index == items.len() {
+                    items.push(new);
+                    return Ok(None);
+                }
+                let slot = items
+                    .get_mut(*index)
+                    .ok_or_else(|| conflict(parents.len(), "is too short"))?;
+                Ok(Some(std::mem::replace(slot, new)))
+            }
+        }
+    }
+
+    /// Removes the value at the path from `value`, returning it. Later
+    /// array items shift down; removing the root leaves null.
+    pub fn remove(&self, value: &mut JsonValue) -> Option<JsonValue> {
+        let Some((last, parents)) = self.segments.split_last() else {
+            return Some(value.take());
+        };
+        let parent = Path::from(parents.to_vec()).lookup_mut(value)?;
+        match last {
+            PathSegment::Key(key) => parent.as_object_mut()?.shift_remove(key),
+            PathSegment::Index(index) => {
+                let items = parent.as_array_mut()?;
+                (*index < items.len()).then(|| items.remove(*index))
+            }
+        }
+    }
+}
+
+/// Turns a null into the empty container `segment` indexes.
+fn container<'a>(value: &'a mut JsonValue, segment: &PathSegment) -> &'a mut JsonValue {
+    if value.is_null() {
+        *value = match segment {
+            PathSegment::Key(_) => JsonValue::Object(Default::default()),
+            PathSegment::Index(_) => JsonValue::Array(Vec::new()),
+        };
+    }
+    value
+}
+
+/// The child of `value` at `segment`, created empty to suit `next` when
+/// missing.
+fn child_or_insert<'a>(
+    value: &'a mut JsonValue,
+    segment: &PathSegment,
+    next: &PathSegment,
+) -> Result<&'a mut JsonValue, &'static str> {
+    let empty = || container(&mut JsonValue::Null, next).take();
+    match segment {
+        PathSegment::Key(key) => Ok(container(value, segment)
+            .as_object_mut()
+            .ok_or("is not an object")?
+            .entry(key.clone())
+            .or_insert_with(empty)),
+        PathSegment::Index(index) => {
+            let items = container(value, segment)
+                .as_array_mut()
+                .ok_or("is not an array")?;
+            if *index == items.len() {
+                items.push(empty());
+            }
+            items.get_mut(*index).ok_or("is too short")
+        }
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format_path(&self.segments))
+    }
+}
+
+impl FromStr for Path {
+    type Err = PathParseError;
+
+    fn from_str(expr: &str) -> Result<Self, Self::Err> {
+        parse_path(expr)
+    }
+}
+
+impl<S: Into<PathSegment>> FromIterator<S> for Path {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        Path {
+            segments: iter.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<Vec<PathSegment>> for Path {
+    fn from(segments: Vec<PathSegment>) -> Self {
+        Path { segments }
+    }
+}
+
+impl<'a> IntoIterator for &'a Path {
+    type Item = &'a PathSegment;
+    type IntoIter = std::slice::Iter<'a, PathSegment>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.segments.iter()
+    }
+}
+
+/// Returned by [`parse_path`] for a malformed expression.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathParseError {
+    /// Byte offset of the problem in the expression.
+    pub offset: usize,
+    pub message: String,
+}
+
+impl fmt::Display for PathParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid path at byte {}: {}", self.offset, self.message)
+    }
+}
+
+impl std::error::Error for PathParseError {}
+
+/// Returned by [`set_path`] and [`Path::set`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PathError {
+    /// The path expression is malformed.
+    Parse(PathParseError),
+    /// The value's shape does not allow the path, e.g. it runs through a
+    /// string.
+    Conflict { path: Path, message: String },
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathError::Parse(e) => e.fmt(f),
+            PathError::Conflict { path, message } => write!(f, "cannot set `{path}`: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for PathError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PathError::Parse(e) => Some(e),
+            PathError::Conflict { .. } => None,
+        }
+    }
+}
+
+impl From<PathParseError> for PathError {
+    fn from(e: PathParseError) -> Self {
+        PathError::Parse(e)
+    }
+}
+
+/// The value at the path expression `path` (see [`parse_path`]) in
+/// `value`, if present.
+///
+/// ```
+/// use c12_parser::{delete_path, get_path, set_path};
+/// use serde_json::json;
+///
+/// let mut config = json!({ "db": { "servers": [{ "host": "a" }] } });
+/// assert_eq!(get_path(&config, "db.servers[0].host")?, Some(&json!("a")));
+///
+/// set_path(&mut config, "db.servers[1].host", json!("b"))?;
+/// set_path(&mut config, "db.pool.size", json!(4))?;
+/// assert_eq!(delete_path(&mut config, "db.servers[0]")?, Some(json!({ "host": "a" })));
+/// assert_eq!(
+///     config,
+///     json!({ "db": { "servers": [{ "host": "b" }], "pool": { "size": 4 } } })
+/// );
+/// # Ok::<(), c12_parser::PathError>(())
+/// ```
+pub fn get_path<'a>(
+    value: &'a JsonValue,
+    path: &str,
+) -> Result<Option<&'a JsonValue>, PathParseError> {
+    Ok(parse_path(path)?.lookup(value))
+}
+
+/// Stores `new` at the path expression `path` in `value`, returning what
+/// it replaced; see [`Path::set`].
+pub fn set_path(
+    value: &mut JsonValue,
+    path: &str,
+    new: JsonValue,
+) -> Result<Option<JsonValue>, PathError> {
+    parse_path(path)?.set(value, new)
+}
+
+/// Removes the value at the path expression `path` from `value`,
+/// returning it; see [`Path::remove`].
+pub fn delete_path(value: &mut JsonValue, path: &str) -> Result<Option<JsonValue>, PathParseError> {
+    Ok(parse_path(path)?.remove(value))
+}
+
+/// Parses a path expression such as `servers["10.0.0.1"].port` or
+/// `paths['C:\\tools'][0]`.
+///
+/// Plain keys (letters, digits, `_` and `-`) are separated by `.`. Any
+/// other key, including ones containing `.` or `:` like IP addresses and
+/// Windows paths, is written in brackets as a double-quoted JSON string or
+/// a single-quoted string where `\'` and `\\` are the only escapes.
+/// `[n]` is an array index. The empty expression is the root.
+pub fn parse_path(expr: &str) -> Result<Path, PathParseError> {
+    let mut parser = Parser { expr, pos: 0 };
+    let mut segments = Vec::new();
+
+    while parser.pos < expr.len() {
+        let start = parser.pos;
+        match parser.peek() {
+            Some('[') => segments.push(parser.bracket()?),
+            Some('.') if !segments.is_empty() => {
+                parser.pos += 1;
+                segments.push(PathSegment::Key(parser.plain_key()?));
+            }
+            Some(_) if start == 0 => segments.push(PathSegment::Key(parser.plain_key()?)),
+            _ => return Err(parser.error("expected `.` or `[`")),
+        }
+    }
+    Ok(Path { segments })
+}
+
+/// Formats segments as the expression [`parse_path`] reads back.
+pub(crate) fn format_path(segments: &[PathSegment]) -> String {
+    segments
+        .iter()
+        .fold(String::new(), |path, segment| match segment {
+            PathSegment::Key(key) => join_key(&path, key),
+            PathSegment::Index(index) => join_index(&path, *index),
+        })
+}
+
+pub(crate) struct Parser<'a> {
+    pub(crate) expr: &'a str,
+    pub(crate) pos: usize,
+}
+
+impl Parser<'_> {
+    pub(crate) fn peek(&self) -> Option<char> {
+        self.expr[self.pos..].chars().next()
+    }
+
+    pub(crate) fn error(&self, message: &str) -> PathParseError {
+        PathParseError {
+            offset: self.pos,
+            message: message.to_string(),
+        }
+    }
+
+    pub(crate) fn plain_key(&mut self) -> Result<String, PathParseError> {
+        let rest = &self.expr[self.pos..];
+        let len = rest.find(|c: char| !is_plain(c)).unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error("expected a key; quote keys with other characters as [\"...\"]"));
+        }
+        self.pos += len;
+        Ok(rest[..len].to_string())
+    }
+
+    pub(crate) fn bracket(&mut self) -> Result<PathSegment, PathParseError> {
+        self.pos += 1;
+        let segment = match self.peek() {
+            Some('"') => PathSegment::Key(self.double_quoted()?),
+            Some('\'') => PathSegment::Key(self.single_quoted()?),
+            Some(c) if c.is_ascii_digit() => {
+                let rest = &self.expr[self.pos..];
+                let len = rest
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(rest.len());
+                let index = rest[..len]
+                    .parse()
+                    .map_err(|_| self.error("array index is too large"))?;
+                self.pos += len;
+                PathSegment::Index(index)
+            }
+            _ => return Err(self.error("expected an index or a quoted key")),
+        };
+        if self.peek() != Some(']') {
+            return Err(self.error("expected `]`"));
+        }
+        self.pos += 1;
+        Ok(segment)
+    }
+
+    fn double_quoted(&mut self) -> Result<String, PathParseError> {
+        let rest = &self.expr[self.pos..];
+        let mut escaped = false;
+        for (i, c) in rest.char_indices().skip(1) {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => {
+                    let key = serde_json::from_str(&rest[..=i])
+                        .map_err(|e| self.error(&format!("invalid quoted key: {e}")))?;
+                    self.pos += i + 1;
+                    return Ok(key);
+                }
+                _ => {}
+            }
+        }
+        Err(self.error("unterminated quoted key"))
+    }
+
+    fn single_quoted(&mut self) -> Result<String, PathParseError> {
+        let rest = &self.expr[self.pos..];
+        let mut key = String::new();
+        let mut chars = rest.char_indices().skip(1);
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some((_, c @ ('\'' | '\\'))) => key.push(c),
+                    Some((_, c)) => {
+                        key.push('\\');
+                        key.push(c);
+                    }
+                    None => break,
+                },
+                '\'' => {
+                    self.pos += i + 1;
+                    return Ok(key);
+                }
+                c => key.push(c),
+            }
+        }
+        Err(self.error("unterminated quoted key"))
+    }
+}
+
+fn is_plain(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+/// `path` as messages show it, `<root>` for the whole value.
+pub(crate) fn shown(path: &Path) -> String {
+    if path.is_root() {
+        "<root>".to_string()
+    } else {
+        path.to_string()
+    }
+}
+
+/// Appends an object key to a dotted path, falling back to the quoted
+/// bracket form (`["a.b"]`) when the key is not a plain identifier.
+pub(crate) fn join_key(parent: &str, key: &str) -> String {
+    let plain = !key.is_empty() && key.chars().all(is_plain);
+
+    if plain && parent.is_empty() {
+        key.to_string()
+    } else if plain {
+        format!("{parent}.{key}")
+    } else {
+        format!("{parent}[{}]", serde_json::Value::from(key))
+    }
+}
+
+/// Appends an array index to a path.
+pub(crate) fn join_index(parent: &str, index: usize) -> String {
+    format!("{parent}[{index}]")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(k: &str) -> PathSegment {
+        PathSegment::Key(k.to_string())
+    }
+
+    #[test]
+    fn joins_plain_and_quoted_keys() {
+        let path = join_key("", "servers");
+        let path = join_key(&path, "10.0.0.1");
+        let path = join_index(&path, 2);
+        let path = join_key(&path, "port");
+        assert_eq!(path, r#"servers["10.0.0.1"][2].port"#);
+    }
+
+    #[test]
+    fn parses_quoted_keys_with_dots_and_colons() {
+        assert_eq!(
+            parse_path(r#"servers["10.0.0.1"].port"#)
+                .unwrap()
+                .segments(),
+            [key("servers"), key("10.0.0.1"), key("port")]
+        );
+        assert_eq!(
+            parse_path(r"paths['C:\\tools\\bin'][0]")
+                .unwrap()
+                .segments(),
+            [key("paths"), key(r"C:\tools\bin"), PathSegment::Index(0)]
+        );
+        assert_eq!(
+            parse_path(r#"["a \"b\""]['it\'s']"#).unwrap().segments(),
+            [key("a \"b\""), key("it's")]
+        );
+        assert!(parse_path("").unwrap().is_root());
+    }
+
+    #[test]
+    fn format_round_trips_through_parse() {
+        let path = Path::root()
+            .key("drives")
+            .key("D:")
+            .index(3)
+            .key("")
+            .key("a.b");
+        let expr = path.to_string();
+        assert_eq!(expr, r#"drives["D:"][3][""]["a.b"]"#);
+        assert_eq!(expr.parse::<Path>().unwrap(), path);
+    }
+
+    #[test]
+    fn looks_up_values_and_parents() {
+        let mut value = serde_json::json!({ "servers": { "10.0.0.1": { "ports": [80, 443] } } });
+        let path: Path = ["servers", "10.0.0.1", "ports"]
+            .into_iter()
+            .map(PathSegment::from)
+            .chain([PathSegment::Index(1)])
+            .collect();
+
+        assert_eq!(path.lookup(&value), Some(&serde_json::json!(443)));
+        *path.lookup_mut(&mut value).unwrap() = 8443.into();
+        assert_eq!(value["servers"]["10.0.0.1"]["ports"][1], 8443);
+        assert_eq!(
+            path.parent().unwrap().to_string(),
+            r#"servers["10.0.0.1"].ports"#
+        );
+        assert_eq!(Path::root().parent(), None);
+        assert_eq!(Path::root().key("missing").lookup(&value), None);
+    }
+
+    #[test]
+    fn set_creates_parents_and_reports_conflicts() {
+        let mut value = serde_json::json!({ "name": "app", "tags": null });
+        assert_eq!(set_path(&mut value, "tags[0]", "a".into()), Ok(None));
+        assert_eq!(set_path(&mut value, "matrix[0][0]", 1.into()), Ok(None));
+        assert_eq!(
+            set_path(&mut value, "name", "api".into()),
+            Ok(Some("app".into()))
+        );
+        assert_eq!(
+            value,
+            serde_json::json!({ "name": "api", "tags": ["a"], "matrix": [[1]] })
+        );
+
+        let err = set_path(&mut value, "name.first", 1.into()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot set `name.first`: `name` is not an object"
+        );
+        let err = set_path(&mut value, "tags[3]", 1.into()).unwrap_err();
+        assert_eq!(err.to_string(), "cannot set `tags[3]`: `tags` is too short");
+        assert!(matches!(
+            set_path(&mut value, "a..b", 1.into()),
+            Err(PathError::Parse(_))
+        ));
+        let mut scalar = serde_json::json!(1);
+        let err = set_path(&mut scalar, "a", 1.into()).unwrap_err();
+        assert_eq!(err.to_string(), "cannot set `a`: the root is not an object");
+    }
+
+    #[test]
+    fn delete_removes_keys_and_shifts_items() {
+        let mut value = serde_json::json!({ "a": 1, "b": [1, 2, 3], "c": 3 });
+        assert_eq!(delete_path(&mut value, "a"), Ok(Some(1.into())));
+        assert_eq!(delete_path(&mut value, "b[0]"), Ok(Some(1.into())));
+        assert_eq!(delete_path(&mut value, "b[5]"), Ok(None));
+        assert_eq!(delete_path(&mut value, "missing.x"), Ok(None));
+        assert_eq!(value.to_string(), r#"{"b":[2,3],"c":3}"#);
+        assert_eq!(get_path(&value, "b[1]"), Ok(Some(&3.into())));
+    }
+
+    #[test]
+    fn reports_offsets_of_errors() {
+        let err = parse_path("a.C:").unwrap_err();
+        assert_eq!(err.offset, 3);
+        assert_eq!(parse_path("a[").unwrap_err().offset, 2);
+        assert_eq!(parse_path(r#"a["x"#).unwrap_err().offset, 2);
+        assert_eq!(parse_path("a..b").unwrap_err().offset, 2);
+        assert_eq!(parse_path("a[1x]").unwrap_err().offset, 3);
+    }
+}
@@ -0,0 +1,262 @@
+use std::io::Cursor;
+
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use plist::{Dictionary, Value as PlistValue};
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::{Map, Number, Value as JsonValue};
+
+use crate::format::{FormatOptions, Formatted};
+use crate::interpolate::prepared;
+use crate::loss::{DataLoss, find_toml_nulls};
+use crate::roundtrip::guard;
+
+/// Which property list encoding [`stringify_plist`] writes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlistFormat {
+    /// The XML encoding, as written by `plutil -convert xml1`.
+    #[default]
+    Xml,
+    /// The `bplist00` binary encoding.
+    Binary,
+}
+
+/// Parses an XML or binary property list, telling them apart by content.
+///
+/// Dictionaries keep their key order. Dates become RFC 3339 strings,
+/// data becomes base64 strings and UIDs become numbers, so none of them
+/// read back as their own plist type. Only outer whitespace of XML input
+/// is captured.
+///
+/// ```
+/// use c12_parser::parse_plist;
+///
+/// let xml = br#"<?xml version="1.0" encoding="UTF-8"?>
+/// <plist version="1.0">
+/// <dict>
+///     <key>Label</key>
+///     <string>com.example.agent</string>
+///     <key>RunAtLoad</key>
+///     <true/>
+///     <key>StartInterval</key>
+///     <integer>300</integer>
+/// </dict>
+/// </plist>
+/// "#;
+/// let parsed = parse_plist::<serde_json::Value>(xml, None)?;
+/// assert_eq!(parsed.value["Label"], "com.example.agent");
+/// assert_eq!(parsed.value["RunAtLoad"], true);
+/// assert_eq!(parsed.value["StartInterval"], 300);
+/// # Ok::<(), plist::Error>(())
+/// ```
+pub fn parse_plist<T>(
+    bytes: &[u8],
+    options: Option<FormatOptions>,
+) -> Result<Formatted<T>, plist::Error>
+where
+    T: DeserializeOwned,
+{
+    let mut opts = options.unwrap_or_default();
+    opts.preserve_indentation = false;
+    let value = plist_to_json(PlistValue::from_reader(Cursor::new(bytes))?);
+    let value = if opts.parses_directly() {
+        serde_json::from_value(value).map_err(<plist::Error as serde::de::Error>::custom)?
+    } else {
+        prepared::<_, plist::Error>(value, &opts)?
+    };
+    // Binary plists have no whitespace to keep.
+    let text = std::str::from_utf8(bytes).unwrap_or_default();
+    Ok(Formatted::new(text, value, &opts))
+}
+
+/// Stringifies a value as an XML or binary property list.
+///
+/// A property list has no `null`: `null` members of objects are left out,
+/// as in TOML, and `null` array items are an error. With
+/// [`fail_on_loss`](FormatOptions::fail_on_loss) any `null` is an error.
+/// Integers outside the `i64` and `u64` ranges are written as reals. XML
+/// output ends with the trailing whitespace of the original text; leading
+/// whitespace would come before the XML declaration and is dropped.
+///
+/// ```
+/// use c12_parser::{PlistFormat, parse_plist, stringify_plist};
+/// use serde_json::json;
+///
+/// let value = json!({ "Label": "agent", "Args": ["run", "--fast"], "Nice": -5 });
+/// let formatted = c12_parser::Formatted::new("", value.clone(), &Default::default());
+/// for format in [PlistFormat::Xml, PlistFormat::Binary] {
+///     let bytes = stringify_plist(&formatted, None, format)?;
+///     assert_eq!(parse_plist::<serde_json::Value>(&bytes, None)?.value, value);
+/// }
+/// # Ok::<(), plist::Error>(())
+/// ```
+pub fn stringify_plist<T>(
+    formatted: &Formatted<T>,
+    options: Option<FormatOptions>,
+    format: PlistFormat,
+) -> Result<Vec<u8>, plist::Error>
+where
+    T: Serialize,
+{
+    let opts = options.unwrap_or_default();
+    let value = serde_json::to_value(&formatted.value)
+        .map_err(<plist::Error as serde::ser::Error>::custom)?;
+    if opts.fail_on_loss {
+        DataLoss::check(find_toml_nulls(&value))
+            .map_err(<plist::Error as serde::ser::Error>::custom)?;
+    }
+    let plist = json_to_plist(&value)?.unwrap_or_else(|| PlistValue::Dictionary(Dictionary::new()));
+
+    let mut out = Vec::new();
+    match format {
+        PlistFormat::Xml => {
+            plist.to_writer_xml(&mut out)?;
+            let end = formatted.format.whitespace_end.as_str();
+            out.extend_from_slice(if end.is_empty() { "\n" } else { end }.as_bytes());
+        }
+        PlistFormat::Binary => plist.to_writer_binary(&mut out)?,
+    }
+    if opts.verify_round_trip {
+        // Binary output is not text, so the check reparses the bytes.
+        guard::<_, plist::Error>(&formatted.value, "", |_| {
+            PlistValue::from_reader(Cursor::new(&out))
+                .map(plist_to_json)
+                .map_err(|e| e.to_string())
+        })?;
+    }
+    Ok(out)
+}
+
+fn plist_to_json(value: PlistValue) -> JsonValue {
+    match value {
+        PlistValue::Dictionary(dict) => JsonValue::Object(
+            dict.into_iter()
+                .map(|(key, value)| (key, plist_to_json(value)))
+                .collect::<Map<_, _>>(),
+        ),
+        PlistValue::Array(values) => {
+            JsonValue::Array(values.into_iter().map(plist_to_json).collect())
+        }
+        PlistValue::Boolean(b) => JsonValue::Bool(b),
+        PlistValue::Integer(n) => match (n.as_signed(), n.as_unsigned()) {
+            (Some(n), _) => n.into(),
+            (None, Some(n)) => n.into(),
+            (None, None) => JsonValue::Null,
+        },
+        PlistValue::Real(f) => Number::from_f64(f).map_or(JsonValue::Null, JsonValue::Number),
+        PlistValue::String(s) => JsonValue::String(s),
+        PlistValue::Date(date) => JsonValue::String(date.to_xml_format()),
+        PlistValue::Data(bytes) => JsonValue::String(BASE64.encode(bytes)),
+        PlistValue::Uid(uid) => uid.get().into(),
+        _ => JsonValue::Null,
+    }
+}
+
+/// Converts `value`, returning `None` for a `null` that its parent object
+/// should leave out.
+fn json_to_plist(value: &JsonValue) -> Result<Option<PlistValue>, plist::Error> {
+    Ok(Some(match value {
+        JsonValue::Null => return Ok(None),
+        JsonValue::Bool(b) => PlistValue::Boolean(*b),
+        JsonValue::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(n), _) => PlistValue::Integer(n.into()),
+            (None, Some(n)) => PlistValue::Integer(n.into()),
+            _ => PlistValue::Real(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        JsonValue::String(s) => PlistValue::String(s.clone()),
+        JsonValue::Array(values) => PlistValue::Array(
+            values
+                .iter()
+                .map(|value| {
+                    json_to_plist(value)?.ok_or_else(|| {
+                        <plist::Error as serde::ser::Error>::custom(
+                            "a property list array cannot hold null",
+                        )
+                    })
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        JsonValue::Object(map) => {
+            let mut dict = Dictionary::new();
+            for (key, value) in map {
+                if let Some(value) = json_to_plist(value)? {
+                    dict.insert(key.clone(), value);
+                }
+            }
+            PlistValue::Dictionary(dict)
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const INFO_PLIST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>CFBundleName</key>
+	<string>Example</string>
+	<key>LSMinimumSystemVersion</key>
+	<string>13.0</string>
+	<key>Scale</key>
+	<real>1.5</real>
+	<key>Built</key>
+	<date>2024-05-01T12:00:00Z</date>
+	<key>Icon</key>
+	<data>AAEC</data>
+	<key>Schemes</key>
+	<array>
+		<string>https</string>
+		<string>example</string>
+	</array>
+</dict>
+</plist>
+"#;
+
+    #[test]
+    fn maps_plist_types_into_the_value_model() {
+        let parsed = parse_plist::<JsonValue>(INFO_PLIST.as_bytes(), None).unwrap();
+        assert_eq!(
+            parsed.value,
+            json!({
+                "CFBundleName": "Example",
+                "LSMinimumSystemVersion": "13.0",
+                "Scale": 1.5,
+                "Built": "2024-05-01T12:00:00Z",
+                "Icon": "AAEC",
+                "Schemes": ["https", "example"]
+            })
+        );
+
+        let binary = stringify_plist(&parsed, None, PlistFormat::Binary).unwrap();
+        assert!(binary.starts_with(b"bplist00"));
+        let reparsed = parse_plist::<JsonValue>(&binary, None).unwrap();
+        assert_eq!(reparsed.value, parsed.value);
+        assert!(
+            parse_plist::<JsonValue>(b"<plist><dict><key>a</key></dict></plist>", None).is_err()
+        );
+    }
+
+    #[test]
+    fn drops_null_members_and_rejects_null_items() {
+        let formatted = Formatted::new("", json!({ "a": 1, "b": null }), &FormatOptions::default());
+        let xml = stringify_plist(&formatted, None, PlistFormat::Xml).unwrap();
+        let xml = String::from_utf8(xml).unwrap();
+        assert!(
+            xml.contains("<key>a</key>") && !xml.contains("<key>b</key>"),
+            "{xml}"
+        );
+        assert!(xml.ends_with("</plist>\n"));
+
+        let strict = FormatOptions {
+            fail_on_loss: true,
+            ..FormatOptions::default()
+        };
+        assert!(stringify_plist(&formatted, Some(strict), PlistFormat::Xml).is_err());
+        let array = Formatted::new("", json!([1, null]), &FormatOptions::default());
+        assert!(stringify_plist(&array, None, PlistFormat::Binary).is_err());
+    }
+}
@@ -0,0 +1,113 @@
+use std::fmt;
+use std::path::Path as FsPath;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Receives progress from batch operations such as
+/// [`convert_tree_with_progress`](crate::convert_tree_with_progress), to
+/// drive a progress bar or log how far a large run has got.
+///
+/// Operations may call the methods from several threads at once, so
+/// implementations take `&self` and use interior mutability. Every
+/// method does nothing by default; `()` reports nowhere.
+pub trait Progress: Send + Sync {
+    /// `count` more files were found and will be processed. Operations
+    /// that list their files up front call this once with the total.
+    fn discovered(&self, count: usize) {
+        let _ = count;
+    }
+
+    /// `path` is done, whatever the outcome. Called once per discovered
+    /// file.
+    fn processed(&self, path: &FsPath) {
+        let _ = path;
+    }
+
+    /// Processing `path` failed with `message`; called before
+    /// [`processed`](Self::processed) for the same file.
+    fn error(&self, path: &FsPath, message: &str) {
+        let _ = (path, message);
+    }
+}
+
+impl Progress for () {}
+
+impl<P: Progress + ?Sized> Progress for &P {
+    fn discovered(&self, count: usize) {
+        (**self).discovered(count)
+    }
+
+    fn processed(&self, path: &FsPath) {
+        (**self).processed(path)
+    }
+
+    fn error(&self, path: &FsPath, message: &str) {
+        (**self).error(path, message)
+    }
+}
+
+/// A [`Progress`] that counts, for polling from another thread or for a
+/// summary at the end.
+///
+/// ```
+/// use c12_parser::{Progress, ProgressCounter};
+///
+/// let counter = ProgressCounter::new();
+/// counter.discovered(2);
+/// counter.error("a.json".as_ref(), "bad");
+/// counter.processed("a.json".as_ref());
+/// assert_eq!((counter.done(), counter.total()), (1, 2));
+/// assert_eq!(counter.to_string(), "1/2 processed, 1 error");
+/// ```
+#[derive(Debug, Default)]
+pub struct ProgressCounter {
+    discovered: AtomicUsize,
+    processed: AtomicUsize,
+    errors: AtomicUsize,
+}
+
+impl ProgressCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Files discovered so far.
+    pub fn total(&self) -> usize {
+        self.discovered.load(Ordering::Relaxed)
+    }
+
+    /// Files processed so far.
+    pub fn done(&self) -> usize {
+        self.processed.load(Ordering::Relaxed)
+    }
+
+    pub fn errors(&self) -> usize {
+        self.errors.load(Ordering::Relaxed)
+    }
+}
+
+impl Progress for ProgressCounter {
+    fn discovered(&self, count: usize) {
+        self.discovered.fetch_add(count, Ordering::Relaxed);
+    }
+
+    fn processed(&self, _: &FsPath) {
+        self.processed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn error(&self, _: &FsPath, _: &str) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl fmt::Display for ProgressCounter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let errors = self.errors();
+        write!(
+            f,
+            "{}/{} processed, {errors} error{}",
+            self.done(),
+            self.total(),
+            if errors == 1 { "" } else { "s" }
+        )
+    }
+}
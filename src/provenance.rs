@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path as FsPath, PathBuf};
+
+use serde_json::Value as JsonValue;
+
+use crate::format::Format;
+use crate::loader::{ConfigLayer, LayerKind};
+use crate::locate::locate;
+use crate::path::Path;
+
+/// Where a loaded value came from, as told by
+/// [`LoadedConfig::origin`](crate::LoadedConfig::origin).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Origin {
+    /// A config file, rc file, manifest or extended config, with the
+    /// 1-based line its value is written on when it can be found.
+    File {
+        kind: LayerKind,
+        path: PathBuf,
+        line: Option<usize>,
+    },
+    /// An environment variable under
+    /// [`env_prefix`](crate::LoadOptions::env_prefix).
+    EnvVar(String),
+    Overrides,
+    Defaults,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::File {
+                path,
+                line: Some(line),
+                ..
+            } => write!(f, "{}:{line}", path.display()),
+            Origin::File { path, .. } => write!(f, "{}", path.display()),
+            Origin::EnvVar(name) => write!(f, "environment variable `{name}`"),
+            Origin::Overrides => f.write_str("overrides"),
+            Origin::Defaults => f.write_str("defaults"),
+        }
+    }
+}
+
+/// What [`trace_origins`] needs besides the layers.
+pub(crate) struct Sources<'a> {
+    /// The text of each file layer that could be read again.
+    pub(crate) texts: HashMap<PathBuf, String>,
+    /// The variable behind each value of the environment layer.
+    pub(crate) env_vars: HashMap<Path, String>,
+    /// The active environment, whose `$<env>` blocks may hold a value.
+    pub(crate) env_name: Option<&'a str>,
+    /// The config name, the key of the manifest sections.
+    pub(crate) name: &'a str,
+}
+
+/// The origin of every leaf of `merged`: scalars, empty objects and empty
+/// arrays. A leaf comes from the highest-priority layer holding the same
+/// value at the same path, or, for values an array merge moved, the
+/// highest one holding the path or its closest ancestor.
+pub(crate) fn trace_origins(
+    layers: &[ConfigLayer],
+    merged: &JsonValue,
+    sources: &Sources<'_>,
+) -> HashMap<Path, Origin> {
+    let mut leaves = Vec::new();
+    collect_leaves(merged, &mut Path::root(), &mut leaves);
+    leaves
+        .into_iter()
+        .filter_map(|(path, value)| {
+            let layer = layers
+                .iter()
+                .find(|layer| path.lookup(&layer.config) == Some(value))
+                .or_else(|| {
+                    let mut ancestor = Some(path.clone());
+                    while let Some(candidate) = ancestor {
+                        let found = layers
+                            .iter()
+                            .find(|layer| candidate.lookup(&layer.config).is_some());
+                        if found.is_some() {
+                            return found;
+                        }
+                        ancestor = candidate.parent();
+                    }
+                    None
+                })?;
+            let origin = origin_in(layer, &path, sources);
+            Some((path, origin))
+        })
+        .collect()
+}
+
+fn collect_leaves<'a>(value: &'a JsonValue, path: &mut Path, out: &mut Vec<(Path, &'a JsonValue)>) {
+    match value {
+        JsonValue::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                path.push(key.as_str());
+                collect_leaves(child, path, out);
+                path.pop();
+            }
+        }
+        JsonValue::Array(items) if !items.is_empty() => {
+            for (index, child) in items.iter().enumerate() {
+                path.push(index);
+                collect_leaves(child, path, out);
+                path.pop();
+            }
+        }
+        _ => out.push((path.clone(), value)),
+    }
+}
+
+fn origin_in(layer: &ConfigLayer, path: &Path, sources: &Sources<'_>) -> Origin {
+    let file = match (layer.kind, &layer.path) {
+        (LayerKind::Overrides, _) => return Origin::Overrides,
+        (LayerKind::Defaults, _) => return Origin::Defaults,
+        (LayerKind::Env, _) => {
+            let name = sources.env_vars.get(path).cloned().unwrap_or_default();
+            return Origin::EnvVar(name);
+        }
+        (_, Some(file)) => file,
+        (_, None) => return Origin::Defaults,
+    };
+    let line = sources
+        .texts
+        .get(file)
+        .and_then(|text| line_of(layer.kind, file, text, path, sources));
+    Origin::File {
+        kind: layer.kind,
+        path: file.clone(),
+        line,
+    }
+}
+
+/// The line `path` is written on in `text`, or its closest ancestor when
+/// the value itself has no line of its own, as in an inline array.
+fn line_of(
+    kind: LayerKind,
+    file: &FsPath,
+    text: &str,
+    path: &Path,
+    sources: &Sources<'_>,
+) -> Option<usize> {
+    let (format, section) = match kind {
+        LayerKind::RcFile | LayerKind::GlobalRc => (None, Path::root()),
+        LayerKind::PackageJson => (Some(Format::Json), Path::root().key(sources.name)),
+        LayerKind::PyprojectToml => (
+            Some(Format::Toml),
+            Path::root().key("tool").key(sources.name),
+        ),
+        LayerKind::CargoToml => (
+            Some(Format::Toml),
+            Path::root()
+                .key("package")
+                .key("metadata")
+                .key(sources.name),
+        ),
+        _ => {
+            let ext = file.extension().and_then(|ext| ext.to_str())?;
+            (Some(Format::from_name(ext)?), Path::root())
+        }
+    };
+    // A value from the active environment's block is written inside it.
+    let mut prefixes = Vec::new();
+    if let Some(env) = sources.env_name {
+        prefixes.push(section.clone().key(format!("${env}")));
+        prefixes.push(section.clone().key("$env").key(env));
+    }
+    prefixes.push(section);
+
+    let mut target = Some(path.clone());
+    while let Some(candidate) = target.filter(|candidate| !candidate.is_root()) {
+        for prefix in &prefixes {
+            let mut full = prefix.clone();
+            for segment in candidate.segments() {
+                full.push(segment.clone());
+            }
+            let line = match format {
+                Some(format) => locate(text, format, &full).map(|at| at.line),
+                None => rc_line(text, &full),
+            };
+            if line.is_some() {
+                return line;
+            }
+        }
+        target = candidate.parent();
+    }
+    None
+}
+
+/// The line of an rc file setting the dotted key `path`.
+fn rc_line(text: &str, path: &Path) -> Option<usize> {
+    let key = path.to_string();
+    text.lines()
+        .position(|line| {
+            line.split_once('=')
+                .is_some_and(|(name, _)| name.trim() == key)
+        })
+        .map(|index| index + 1)
+}
@@ -0,0 +1,144 @@
+use pyo3::exceptions::{PyKeyError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple};
+use serde_json::{Map, Value as JsonValue};
+
+use crate::binding;
+use crate::format::{FormatOptions, Formatted};
+
+fn options(indent: Option<usize>) -> FormatOptions {
+    FormatOptions {
+        indent,
+        ..Default::default()
+    }
+}
+
+/// Parses `text` written in `format` into plain Python objects.
+#[pyfunction]
+fn parse(py: Python<'_>, format: &str, text: &str) -> PyResult<Py<PyAny>> {
+    let formatted =
+        binding::parse(format, text, FormatOptions::default()).map_err(PyValueError::new_err)?;
+    to_python(py, &formatted.value)
+}
+
+/// Stringifies `value` as `format`.
+#[pyfunction]
+#[pyo3(signature = (format, value, indent = None))]
+fn stringify(format: &str, value: &Bound<'_, PyAny>, indent: Option<usize>) -> PyResult<String> {
+    let value = from_python(value)?;
+    let formatted = Formatted::new("", value, &FormatOptions::default());
+    binding::stringify(format, &formatted, options(indent)).map_err(PyValueError::new_err)
+}
+
+/// Converts `text` from one format to another, keeping the outer
+/// whitespace of the input.
+#[pyfunction]
+#[pyo3(signature = (text, from_format, to_format, indent = None))]
+fn convert(
+    text: &str,
+    from_format: &str,
+    to_format: &str,
+    indent: Option<usize>,
+) -> PyResult<String> {
+    let formatted = binding::parse(from_format, text, FormatOptions::default())
+        .map_err(PyValueError::new_err)?;
+    binding::stringify(to_format, &formatted, options(indent)).map_err(PyValueError::new_err)
+}
+
+/// Returns the value at a JSON Pointer (`/server/port`) inside `text`.
+#[pyfunction]
+fn query(py: Python<'_>, format: &str, text: &str, pointer: &str) -> PyResult<Py<PyAny>> {
+    let formatted =
+        binding::parse(format, text, FormatOptions::default()).map_err(PyValueError::new_err)?;
+    let found = formatted
+        .value
+        .pointer(pointer)
+        .ok_or_else(|| PyKeyError::new_err(pointer.to_string()))?;
+    to_python(py, found)
+}
+
+fn to_python(py: Python<'_>, value: &JsonValue) -> PyResult<Py<PyAny>> {
+    Ok(match value {
+        JsonValue::Null => py.None(),
+        JsonValue::Bool(b) => PyBool::new(py, *b).to_owned().into_any().unbind(),
+        JsonValue::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => i.into_pyobject(py)?.into_any().unbind(),
+            (None, Some(u)) => u.into_pyobject(py)?.into_any().unbind(),
+            _ => PyFloat::new(py, n.as_f64().unwrap_or(f64::NAN))
+                .into_any()
+                .unbind(),
+        },
+        JsonValue::String(s) => PyString::new(py, s).into_any().unbind(),
+        JsonValue::Array(items) => {
+            let list = PyList::empty(py);
+            for item in items {
+                list.append(to_python(py, item)?)?;
+            }
+            list.into_any().unbind()
+        }
+        JsonValue::Object(map) => {
+            let dict = PyDict::new(py);
+            for (key, item) in map {
+                dict.set_item(key, to_python(py, item)?)?;
+            }
+            dict.into_any().unbind()
+        }
+    })
+}
+
+fn from_python(value: &Bound<'_, PyAny>) -> PyResult<JsonValue> {
+    if value.is_none() {
+        return Ok(JsonValue::Null);
+    }
+    // `bool` is a subclass of `int`, so it has to be checked first.
+    if let Ok(b) = value.cast::<PyBool>() {
+        return Ok(JsonValue::Bool(b.is_true()));
+    }
+    if value.cast::<PyInt>().is_ok() {
+        if let Ok(i) = value.extract::<i64>() {
+            return Ok(i.into());
+        }
+        return Ok(value.extract::<u64>()?.into());
+    }
+    if let Ok(f) = value.cast::<PyFloat>() {
+        return serde_json::Number::from_f64(f.value())
+            .map(JsonValue::Number)
+            .ok_or_else(|| {
+                PyValueError::new_err(format!("`{}` cannot be written to a config", f.value()))
+            });
+    }
+    if let Ok(s) = value.cast::<PyString>() {
+        return Ok(JsonValue::String(s.to_str()?.to_string()));
+    }
+    if let Ok(dict) = value.cast::<PyDict>() {
+        let mut map = Map::new();
+        for (key, item) in dict.iter() {
+            let key = key
+                .cast::<PyString>()
+                .map_err(|_| PyTypeError::new_err("config keys must be strings"))?;
+            map.insert(key.to_str()?.to_string(), from_python(&item)?);
+        }
+        return Ok(JsonValue::Object(map));
+    }
+    if value.cast::<PyList>().is_ok() || value.cast::<PyTuple>().is_ok() {
+        return value
+            .try_iter()?
+            .map(|item| from_python(&item?))
+            .collect::<PyResult<_>>()
+            .map(JsonValue::Array);
+    }
+    Err(PyTypeError::new_err(format!(
+        "unsupported config value of type `{}`",
+        value.get_type().name()?
+    )))
+}
+
+/// The `c12_parser` Python module.
+#[pymodule]
+fn c12_parser(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(parse, m)?)?;
+    m.add_function(wrap_pyfunction!(stringify, m)?)?;
+    m.add_function(wrap_pyfunction!(convert, m)?)?;
+    m.add_function(wrap_pyfunction!(query, m)?)?;
+    Ok(())
+}
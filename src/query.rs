@@ -0,0 +1,299 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use serde_json::Value as JsonValue;
+
+use crate::path::{Parser, Path, PathParseError, PathSegment};
+
+/// One step of a [`Query`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QuerySegment {
+    Key(String),
+    Index(usize),
+    /// `*`: every value of an object.
+    AnyKey,
+    /// `[*]`: every item of an array.
+    AnyIndex,
+    /// `..`: the current value and everything below it.
+    Descendants,
+}
+
+/// A comparison a [`Query`] applies to the values it selects.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    const ALL: [(&'static str, Comparison); 6] = [
+        ("==", Comparison::Eq),
+        ("!=", Comparison::Ne),
+        ("<=", Comparison::Le),
+        (">=", Comparison::Ge),
+        ("<", Comparison::Lt),
+        (">", Comparison::Gt),
+    ];
+
+    /// Numbers compare by value and strings lexically; ordering any other
+    /// pair of values is false.
+    pub fn holds(self, value: &JsonValue, operand: &JsonValue) -> bool {
+        let ordering = match (value, operand) {
+            (JsonValue::Number(a), JsonValue::Number(b)) => a
+                .as_f64()
+                .zip(b.as_f64())
+                .and_then(|(a, b)| a.partial_cmp(&b)),
+            (JsonValue::String(a), JsonValue::String(b)) => Some(a.cmp(b)),
+            _ => None,
+        };
+        match self {
+            Comparison::Eq => ordering.map_or(value == operand, Ordering::is_eq),
+            Comparison::Ne => ordering.map_or(value != operand, Ordering::is_ne),
+            Comparison::Lt => ordering.is_some_and(Ordering::is_lt),
+            Comparison::Le => ordering.is_some_and(Ordering::is_le),
+            Comparison::Gt => ordering.is_some_and(Ordering::is_gt),
+            Comparison::Ge => ordering.is_some_and(Ordering::is_ge),
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (op, _) = Comparison::ALL.iter().find(|(_, c)| c == self).unwrap();
+        f.write_str(op)
+    }
+}
+
+/// A JSONPath-style expression selecting values by shape, such as
+/// `services[*].http.retries > 5` or `..image`.
+///
+/// It extends [path expressions](crate::parse_path) with `*` for every
+/// value of an object, `[*]` for every item of an array and `..` for any
+/// depth, optionally after a leading `$`. A trailing comparison with a
+/// JSON literal keeps only the values it holds for.
+///
+/// ```
+/// use c12_parser::Query;
+/// use serde_json::json;
+///
+/// let config = json!({
+///     "api": { "http": { "retries": 8 } },
+///     "web": { "http": { "retries": 2 } }
+/// });
+/// let query: Query = "$..retries > 5".parse()?;
+/// let matches = query.matches(&config);
+/// assert_eq!(matches.len(), 1);
+/// assert_eq!(matches[0].0.to_string(), "api.http.retries");
+/// assert_eq!(matches[0].1, &json!(8));
+/// # Ok::<(), c12_parser::PathParseError>(())
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Query {
+    pub segments: Vec<QuerySegment>,
+    pub filter: Option<(Comparison, JsonValue)>,
+}
+
+impl Query {
+    /// The values the query selects in `value`, with their paths, in
+    /// document order.
+    pub fn matches<'a>(&self, value: &'a JsonValue) -> Vec<(Path, &'a JsonValue)> {
+        let mut found = Vec::new();
+        select(value, &self.segments, Path::root(), &mut found);
+        if let Some((comparison, operand)) = &self.filter {
+            found.retain(|(_, value)| comparison.holds(value, operand));
+        }
+        found
+    }
+}
+
+fn select<'a>(
+    value: &'a JsonValue,
+    segments: &[QuerySegment],
+    path: Path,
+    found: &mut Vec<(Path, &'a JsonValue)>,
+) {
+    let Some((segment, rest)) = segments.split_first() else {
+        found.push((path, value));
+        return;
+    };
+    match (segment, value) {
+        (QuerySegment::Key(key), JsonValue::Object(map)) => {
+            if let Some(child) = map.get(key) {
+                select(child, rest, path.key(key.as_str()), found);
+            }
+        }
+        (QuerySegment::Index(index), JsonValue::Array(items)) => {
+            if let Some(child) = items.get(*index) {
+                select(child, rest, path.index(*index), found);
+            }
+        }
+        (QuerySegment::AnyKey, JsonValue::Object(map)) => {
+            for (key, child) in map {
+                select(child, rest, path.clone().key(key.as_str()), found);
+            }
+        }
+        (QuerySegment::AnyIndex, JsonValue::Array(items)) => {
+            for (index, child) in items.iter().enumerate() {
+                select(child, rest, path.clone().index(index), found);
+            }
+        }
+        (QuerySegment::Descendants, _) => {
+            select(value, rest, path.clone(), found);
+            match value {
+                JsonValue::Object(map) => {
+                    for (key, child) in map {
+                        select(child, segments, path.clone().key(key.as_str()), found);
+                    }
+                }
+                JsonValue::Array(items) => {
+                    for (index, child) in items.iter().enumerate() {
+                        select(child, segments, path.clone().index(index), found);
+                    }
+                }
+                _ => {}
+            }
+        }
+        _ => {}
+    }
+}
+
+impl FromStr for Query {
+    type Err = PathParseError;
+
+    fn from_str(expr: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { expr, pos: 0 };
+        if parser.peek() == Some('$') {
+            parser.pos += 1;
+        }
+        let start = parser.pos;
+        let mut segments = Vec::new();
+        while let Some(c) = parser.peek()
+            && !c.is_whitespace()
+        {
+            let segment = if parser.expr[parser.pos..].starts_with("..") {
+                parser.pos += 2;
+                segments.push(QuerySegment::Descendants);
+                match parser.peek() {
+                    Some('[') => bracket(&mut parser)?,
+                    _ => key(&mut parser)?,
+                }
+            } else {
+                match c {
+                    '[' => bracket(&mut parser)?,
+                    '.' if parser.pos > start => {
+                        parser.pos += 1;
+                        key(&mut parser)?
+                    }
+                    _ if parser.pos == start => key(&mut parser)?,
+                    _ => return Err(parser.error("expected `.`, `..` or `[`")),
+                }
+            };
+            segments.push(segment);
+        }
+
+        let rest = parser.expr[parser.pos..].trim_start();
+        parser.pos = expr.len() - rest.len();
+        if rest.is_empty() {
+            return Ok(Query {
+                segments,
+                filter: None,
+            });
+        }
+        let Some(&(op, comparison)) = Comparison::ALL.iter().find(|(op, _)| rest.starts_with(op))
+        else {
+            return Err(parser.error("expected a comparison such as `==` or `>`"));
+        };
+        parser.pos += op.len();
+        let operand = serde_json::from_str(&rest[op.len()..])
+            .map_err(|e| parser.error(&format!("invalid JSON operand: {e}")))?;
+        Ok(Query {
+            segments,
+            filter: Some((comparison, operand)),
+        })
+    }
+}
+
+fn key(parser: &mut Parser<'_>) -> Result<QuerySegment, PathParseError> {
+    if parser.peek() == Some('*') {
+        parser.pos += 1;
+        return Ok(QuerySegment::AnyKey);
+    }
+    Ok(QuerySegment::Key(parser.plain_key()?))
+}
+
+fn bracket(parser: &mut Parser<'_>) -> Result<QuerySegment, PathParseError> {
+    if parser.expr[parser.pos..].starts_with("[*]") {
+        parser.pos += 3;
+        return Ok(QuerySegment::AnyIndex);
+    }
+    Ok(match parser.bracket()? {
+        PathSegment::Key(key) => QuerySegment::Key(key),
+        PathSegment::Index(index) => QuerySegment::Index(index),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn paths(expr: &str, value: &JsonValue) -> Vec<String> {
+        let query: Query = expr.parse().unwrap();
+        query
+            .matches(value)
+            .into_iter()
+            .map(|(path, _)| path.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn wildcards_and_descendants() {
+        let config = json!({
+            "services": [
+                { "name": "api", "image": "api:1", "sidecars": [{ "image": "proxy:2" }] },
+                { "name": "web", "image": "web:3" }
+            ],
+            "a.b": { "image": "x" }
+        });
+        assert_eq!(
+            paths("services[*].name", &config),
+            ["services[0].name", "services[1].name"]
+        );
+        assert_eq!(
+            paths("$..image", &config),
+            [
+                "services[0].image",
+                "services[0].sidecars[0].image",
+                "services[1].image",
+                "[\"a.b\"].image"
+            ]
+        );
+        assert_eq!(paths("*.image", &config), ["[\"a.b\"].image"]);
+        assert_eq!(paths("services[1]", &config), ["services[1]"]);
+        assert_eq!(paths("services.name", &config), Vec::<String>::new());
+    }
+
+    #[test]
+    fn filters_compare_with_json_literals() {
+        let config = json!({ "a": { "port": 80 }, "b": { "port": 8080.0 }, "c": { "port": "80" } });
+        assert_eq!(paths("*.port >= 1024", &config), ["b.port"]);
+        assert_eq!(paths("*.port == 80", &config), ["a.port"]);
+        assert_eq!(paths("*.port != 80", &config), ["b.port", "c.port"]);
+        assert_eq!(paths("*.port < \"9\"", &config), ["c.port"]);
+    }
+
+    #[test]
+    fn reports_where_parsing_failed() {
+        let error = "a.b ~ 1".parse::<Query>().unwrap_err();
+        assert_eq!(error.offset, 4);
+        let error = "a > nope".parse::<Query>().unwrap_err();
+        assert_eq!(error.offset, 3);
+        assert!(error.message.starts_with("invalid JSON operand"));
+        assert!("a[".parse::<Query>().is_err());
+    }
+}
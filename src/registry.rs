@@ -0,0 +1,356 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Arc;
+
+use serde_json::Value as JsonValue;
+
+use crate::capabilities::{FormatCapabilities, custom_capabilities, formats};
+use crate::empty::{empty_value, is_empty_document};
+use crate::format::{Format, FormatOptions, Formatted};
+use crate::hooks::HookResult;
+use crate::ini_format::{ini_to_value, value_to_ini};
+use crate::interpolate::{interpolate_env, process_env};
+use crate::json::parse_json_limited;
+use crate::json5::parse_json5_limited;
+use crate::limits::{Limits, enforce_limits};
+use crate::loss::{DataLoss, find_toml_nulls, parse_losses};
+use crate::roundtrip::check_output;
+use crate::toml_format::parse_toml_limited;
+use crate::yaml_format::parse_yaml_limited;
+use crate::{
+    parse_jsonc, stringify_ini, stringify_json, stringify_json5, stringify_jsonc, stringify_toml,
+    stringify_yaml,
+};
+
+type ParseFn = Arc<dyn Fn(&str) -> HookResult<JsonValue> + Send + Sync>;
+type StringifyFn = Arc<dyn Fn(&JsonValue) -> HookResult<String> + Send + Sync>;
+
+/// A custom format name [`FormatRegistry::register`] refused because a
+/// built-in format answers to it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReservedFormatName {
+    pub name: String,
+}
+
+impl fmt::Display for ReservedFormatName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` is the name of a built-in format and cannot be registered",
+            self.name
+        )
+    }
+}
+
+impl std::error::Error for ReservedFormatName {}
+
+/// Formats known to a [`C12Context`](crate::C12Context): the built-in
+/// ones with per-format default options, plus any custom formats.
+///
+/// A registry is a plain value owned by its context, never a global, so
+/// two libraries embedding this crate each keep their own settings.
+#[derive(Clone, Default)]
+pub struct FormatRegistry {
+    base: FormatOptions,
+    defaults: HashMap<Format, FormatOptions>,
+    custom: BTreeMap<String, (ParseFn, StringifyFn)>,
+}
+
+impl fmt::Debug for FormatRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FormatRegistry")
+            .field("base", &self.base)
+            .field("defaults", &self.defaults)
+            .field("custom", &self.custom.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl FormatRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the options used for every format without its own defaults.
+    pub fn set_base(&mut self, options: FormatOptions) {
+        self.base = options;
+    }
+
+    /// Sets the options used for `format` when none are given.
+    pub fn set_defaults(&mut self, format: Format, options: FormatOptions) {
+        self.defaults.insert(format, options);
+    }
+
+    /// Sets [`FormatOptions::limits`] in the base options and in every
+    /// format's defaults set so far. Custom formats are held to the base
+    /// limits once they have parsed.
+    pub fn set_limits(&mut self, limits: Limits) {
+        for options in std::iter::once(&mut self.base).chain(self.defaults.values_mut()) {
+            options.limits = limits.clone();
+        }
+    }
+
+    /// The default options for `format`.
+    pub fn defaults(&self, format: Format) -> FormatOptions {
+        self.defaults.get(&format).unwrap_or(&self.base).clone()
+    }
+
+    /// Registers a custom format under `name` (compared case-insensitively).
+    /// Built-in format names, `yml` included, cannot be overridden and fail
+    /// with [`ReservedFormatName`].
+    pub fn register(
+        &mut self,
+        name: &str,
+        parse: impl Fn(&str) -> HookResult<JsonValue> + Send + Sync + 'static,
+        stringify: impl Fn(&JsonValue) -> HookResult<String> + Send + Sync + 'static,
+    ) -> Result<(), ReservedFormatName> {
+        if Format::from_name(name).is_some() {
+            return Err(ReservedFormatName {
+                name: name.to_string(),
+            });
+        }
+        self.custom.insert(
+            name.to_ascii_lowercase(),
+            (Arc::new(parse), Arc::new(stringify)),
+        );
+        Ok(())
+    }
+
+    /// Names of all formats, built-in first, then custom ones.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        Format::ALL
+            .iter()
+            .map(|f| f.name())
+            .chain(self.custom.keys().map(String::as_str))
+    }
+
+    /// Capabilities of all formats, built-in first, then custom ones,
+    /// which only report their name.
+    pub fn formats(&self) -> Vec<FormatCapabilities> {
+        let custom = self.custom.keys().map(|name| custom_capabilities(name));
+        formats().into_iter().chain(custom).collect()
+    }
+
+    /// Parses `text` in the named format.
+    pub fn parse(&self, format: &str, text: &str) -> HookResult<Formatted<JsonValue>> {
+        self.parse_in(format, text, &process_env)
+    }
+
+    /// Like [`parse`](Self::parse), expanding variables from `env` when
+    /// the format's options ask for
+    /// [`interpolate_env`](FormatOptions::interpolate_env).
+    pub(crate) fn parse_in(
+        &self,
+        format: &str,
+        text: &str,
+        env: &dyn Fn(&str) -> Option<String>,
+    ) -> HookResult<Formatted<JsonValue>> {
+        if let Some(builtin) = Format::from_name(format) {
+            let mut options = self.defaults(builtin);
+            let interpolate = std::mem::take(&mut options.interpolate_env);
+            let mut formatted = parse_as(builtin, text, options.clone())?;
+            if interpolate {
+                interpolate_env(&mut formatted.value, env)?;
+                enforce_limits(&mut formatted.value, &options.limits)?;
+            }
+            return Ok(formatted);
+        }
+        let (parse, _) = self.custom(format)?;
+        let mut value = parse(text)?;
+        enforce_limits(&mut value, &self.base.limits)?;
+        Ok(Formatted::new(text, value, &FormatOptions::default()))
+    }
+
+    /// Stringifies `formatted` in the named format.
+    pub fn stringify(&self, format: &str, formatted: &Formatted<JsonValue>) -> HookResult<String> {
+        if let Some(builtin) = Format::from_name(format) {
+            return stringify_as(builtin, formatted, self.defaults(builtin));
+        }
+        let (_, stringify) = self.custom(format)?;
+        stringify(&formatted.value)
+    }
+
+    fn custom(&self, name: &str) -> HookResult<&(ParseFn, StringifyFn)> {
+        self.custom
+            .get(&name.to_ascii_lowercase())
+            .ok_or_else(|| format!("unsupported format `{name}`").into())
+    }
+}
+
+/// Parses `text` as `format` into the shared value model. INI values stay
+/// strings, as in [`parse_ini`]. A [`LimitExceeded`](crate::LimitExceeded)
+/// is returned as it is.
+pub(crate) fn parse_as(
+    format: Format,
+    text: &str,
+    options: FormatOptions,
+) -> HookResult<Formatted<JsonValue>> {
+    // Losses are checked here, before the backend, so they keep their type.
+    if options.fail_on_loss {
+        DataLoss::check(parse_losses(text, format))?;
+    }
+    let opts = Some(options.clone());
+    let parsed = match format {
+        Format::Json => parse_json_limited(text, opts)?,
+        Format::Json5 => parse_json5_limited(text, opts)?,
+        Format::Jsonc => parse_jsonc(text, opts, None)?,
+        Format::Toml => parse_toml_limited(text, opts)?,
+        Format::Yaml => parse_yaml_limited(text, opts)?,
+        Format::Ini if is_empty_document(text, format) => {
+            let value = empty_value::<_, serde_json::Error>(options.on_empty)?;
+            Formatted::new(text, value, &options)
+        }
+        Format::Ini => {
+            let mut value = ini_to_value(text);
+            enforce_limits(&mut value, &options.limits)?;
+            Formatted::new(text, value, &options)
+        }
+    };
+    Ok(parsed)
+}
+
+/// Stringifies `formatted` as `format`. With [`verify_round_trip`](FormatOptions::verify_round_trip), a
+/// mismatch fails as a typed [`RoundTripMismatch`](crate::RoundTripMismatch).
+pub(crate) fn stringify_as(
+    format: Format,
+    formatted: &Formatted<JsonValue>,
+    mut options: FormatOptions,
+) -> HookResult<String> {
+    let verify = std::mem::take(&mut options.verify_round_trip);
+    if options.fail_on_loss && format == Format::Toml {
+        DataLoss::check(find_toml_nulls(&formatted.value))?;
+    }
+    let opts = Some(options.clone());
+    let out = match format {
+        Format::Json => stringify_json(formatted, opts)?,
+        Format::Json5 => stringify_json5(formatted, opts)?,
+        Format::Jsonc => stringify_jsonc(formatted, opts)?,
+        Format::Toml => stringify_toml(formatted, opts)?,
+        Format::Yaml => stringify_yaml(formatted, opts)?,
+        Format::Ini => stringify_ini(&value_to_ini(&formatted.value)?),
+    };
+    if verify {
+        check_output(format, &formatted.value, &out, &options)?;
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::StyleProfile;
+    use crate::path::Path;
+    use serde_json::json;
+
+    #[test]
+    fn registries_keep_their_own_defaults() {
+        let mut wide = FormatRegistry::new();
+        wide.set_defaults(
+            Format::Json,
+            FormatOptions {
+                indent: Some(4),
+                profile: StyleProfile::Confbox,
+                ..Default::default()
+            },
+        );
+        let narrow = FormatRegistry::new();
+
+        let parsed = narrow.parse("json", r#"{"a":[1]}"#).unwrap();
+        assert_eq!(
+            wide.stringify("json", &parsed).unwrap(),
+            "{\n    \"a\": [\n        1\n    ]\n}"
+        );
+        assert_eq!(
+            narrow.stringify("JSON", &parsed).unwrap(),
+            crate::stringify_json(&parsed, None).unwrap()
+        );
+    }
+
+    #[test]
+    fn every_format_keeps_source_key_order() {
+        let sources = [
+            (Format::Json, r#"{"zeta": 1, "alpha": {"b": 2, "a": 3}}"#),
+            (Format::Json5, "{zeta: 1, alpha: {b: 2, a: 3}}"),
+            (
+                Format::Jsonc,
+                "{\"zeta\": 1, /* c */ \"alpha\": {\"b\": 2, \"a\": 3}}",
+            ),
+            (Format::Toml, "zeta = 1\n[alpha]\nb = 2\na = 3\n"),
+            (Format::Yaml, "zeta: 1\nalpha:\n  b: 2\n  a: 3\n"),
+            (Format::Ini, "zeta = 1\n[alpha]\nb = 2\na = 3\n"),
+        ];
+        for (format, text) in sources {
+            let parsed = parse_as(format, text, FormatOptions::default()).unwrap();
+            let value = match format {
+                Format::Ini => parsed.value["default"].as_object().unwrap().clone(),
+                _ => parsed.value.as_object().unwrap().clone(),
+            };
+            assert_eq!(value.keys().next().unwrap(), "zeta", "{format}");
+            let alpha: Vec<_> = parsed.value["alpha"].as_object().unwrap().keys().collect();
+            assert_eq!(alpha, ["b", "a"], "{format}");
+
+            let out = stringify_as(format, &parsed, FormatOptions::default()).unwrap();
+            let zeta = out.find("zeta").unwrap();
+            assert!(zeta < out.find("alpha").unwrap(), "{format}: {out}");
+        }
+    }
+
+    #[test]
+    fn data_loss_is_returned_as_is() {
+        let mut registry = FormatRegistry::new();
+        registry.set_base(FormatOptions {
+            fail_on_loss: true,
+            ..FormatOptions::default()
+        });
+        let err = registry.parse("jsonc", "{ // c\n}").unwrap_err();
+        assert!(err.downcast_ref::<DataLoss>().is_some(), "{err}");
+        let parsed = registry.parse("json", "{ \"a\": null }").unwrap();
+        let err = registry.stringify("toml", &parsed).unwrap_err();
+        let loss = err.downcast_ref::<DataLoss>().unwrap();
+        assert_eq!(loss.losses[0].path.to_string(), "a");
+    }
+
+    #[test]
+    fn custom_formats_are_scoped_to_their_registry() {
+        let mut registry = FormatRegistry::new();
+        registry
+            .register(
+                "kv",
+                |text| {
+                    let map = text
+                        .lines()
+                        .filter_map(|line| line.split_once('='))
+                        .map(|(k, v)| (k.trim().to_string(), JsonValue::from(v.trim())))
+                        .collect();
+                    Ok(JsonValue::Object(map))
+                },
+                |value| Ok(format!("{value}")),
+            )
+            .unwrap();
+        let parsed = registry.parse("KV", "a = 1\nb = two\n").unwrap();
+        assert_eq!(parsed.value, json!({ "a": "1", "b": "two" }));
+        assert_eq!(
+            registry.names().collect::<Vec<_>>(),
+            ["json", "json5", "jsonc", "toml", "yaml", "ini", "kv"]
+        );
+
+        let err = FormatRegistry::new().parse("kv", "").unwrap_err();
+        assert_eq!(err.to_string(), "unsupported format `kv`");
+
+        let err = registry
+            .register("YML", |_| Ok(JsonValue::Null), |_| Ok(String::new()))
+            .unwrap_err();
+        assert_eq!(err.name, "YML");
+        assert_eq!(registry.names().count(), 7);
+
+        registry.set_limits(crate::Limits {
+            max_string_len: Some(2),
+            ..Default::default()
+        });
+        let err = registry.parse("kv", "b = two\n").unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<crate::LimitExceeded>().unwrap().path,
+            Path::root().key("b")
+        );
+    }
+}
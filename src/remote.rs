@@ -0,0 +1,234 @@
+use std::path::Path as FsPath;
+
+use sha2::{Digest, Sha256};
+
+use crate::context::C12Context;
+
+/// Marks a pinned checksum at the end of a remote `extends` entry.
+const PIN: &str = "#sha256=";
+
+/// A remote config listed under `extends`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct RemoteSource {
+    pub(crate) url: String,
+    /// The SHA-256 the body must have, in lowercase hex.
+    pub(crate) sha256: Option<String>,
+}
+
+/// Whether `path` names a remote config rather than a file.
+pub(crate) fn is_url(path: &str) -> bool {
+    path.starts_with("https://") || path.starts_with("http://")
+}
+
+impl RemoteSource {
+    /// Reads an `extends` entry as a remote source: an `http(s)://` URL,
+    /// `github:owner/repo/path[@ref]`, or any path when the extending
+    /// config, `base`, is itself remote. Either may end in
+    /// `#sha256=<hex>`, which a plain `http://` URL must have. `None` for
+    /// local files.
+    pub(crate) fn parse(entry: &str, base: Option<&str>) -> Option<Result<Self, String>> {
+        let (location, sha256) = match entry.rsplit_once(PIN) {
+            Some((location, hex)) => (location, Some(hex)),
+            None => (entry, None),
+        };
+        let url = if is_url(location) {
+            Ok(location.to_string())
+        } else if let Some(shorthand) = location.strip_prefix("github:") {
+            github_url(shorthand)
+        } else {
+            Ok(join_url(base?, location))
+        };
+        Some(url.and_then(|url| {
+            let sha256 = sha256
+                .map(|hex| {
+                    if hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                        Ok(hex.to_ascii_lowercase())
+                    } else {
+                        Err(format!("`{hex}` is not a SHA-256 checksum"))
+                    }
+                })
+                .transpose()?;
+            if url.starts_with("http://") && sha256.is_none() {
+                return Err(format!(
+                    "`{url}` is plain HTTP; use https:// or pin it with `{PIN}<hex>`"
+                ));
+            }
+            Ok(Self { url, sha256 })
+        }))
+    }
+
+    /// The extension of the URL's path, which names the config's format.
+    pub(crate) fn extension(&self) -> Option<&str> {
+        let path = self.url.split(['?', '#']).next().unwrap_or_default();
+        let file = path.rsplit('/').next().unwrap_or_default();
+        file.rsplit_once('.').map(|(_, ext)| ext)
+    }
+
+    /// The config's text. With `cache`, a directory that must exist, each
+    /// body fetched is kept there: pinned sources are read from it
+    /// without fetching while the checksum still matches, and unpinned
+    /// ones fall back to it when fetching fails.
+    pub(crate) fn fetch(
+        &self,
+        context: &C12Context,
+        cache: Option<&FsPath>,
+    ) -> Result<String, String> {
+        let fs = context.fs();
+        let cached = cache.map(|dir| dir.join(sha256_hex(self.url.as_bytes())));
+        let cached_text = cached
+            .as_deref()
+            .filter(|path| fs.exists(path))
+            .and_then(|path| fs.read_to_string(path).ok());
+        if let (Some(pin), Some(text)) = (&self.sha256, &cached_text)
+            && sha256_hex(text.as_bytes()) == *pin
+        {
+            return Ok(text.clone());
+        }
+
+        let text = match context.fetch(&self.url) {
+            Ok(text) => text,
+            Err(_)
+                if self.sha256.is_none()
+                    && !context.is_deterministic()
+                    && cached_text.is_some() =>
+            {
+                return Ok(cached_text.unwrap_or_default());
+            }
+            Err(e) => return Err(e.to_string()),
+        };
+        if let Some(pin) = &self.sha256 {
+            let actual = sha256_hex(text.as_bytes());
+            if actual != *pin {
+                return Err(format!(
+                    "checksum mismatch for {}: expected sha256 {pin}, got {actual}",
+                    self.url
+                ));
+            }
+        }
+        if let Some(path) = cached {
+            fs.write(&path, &text)
+                .map_err(|e| format!("cannot cache {}: {e}", self.url))?;
+        }
+        Ok(text)
+    }
+}
+
+/// The SHA-256 digest of `data` in lowercase hex, for pinning remote
+/// configs.
+pub(crate) fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// The raw file URL for `owner/repo/path[@ref]`, at `HEAD` without a ref.
+fn github_url(shorthand: &str) -> Result<String, String> {
+    let (location, reference) = match shorthand.rsplit_once('@') {
+        Some((location, reference)) => (location, reference),
+        None => (shorthand, "HEAD"),
+    };
+    match location.splitn(3, '/').collect::<Vec<_>>()[..] {
+        [owner, repo, path] if !owner.is_empty() && !repo.is_empty() && !path.is_empty() => Ok(
+            format!("https://raw.githubusercontent.com/{owner}/{repo}/{reference}/{path}"),
+        ),
+        _ => Err(format!(
+            "`github:{shorthand}` is not `github:owner/repo/path`"
+        )),
+    }
+}
+
+/// `path` resolved against the URL `base`, with `.` and `..` segments
+/// applied.
+fn join_url(base: &str, path: &str) -> String {
+    let scheme_end = base.find("://").map_or(0, |i| i + 3);
+    let origin_end = base[scheme_end..]
+        .find('/')
+        .map_or(base.len(), |i| scheme_end + i);
+    let origin = &base[..origin_end];
+    let joined = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        let dir = base[origin_end..]
+            .rsplit_once('/')
+            .map_or("", |(dir, _)| dir);
+        format!("{dir}/{path}")
+    };
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in joined.split('/').skip(1) {
+        match segment {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    format!("{origin}/{}", segments.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_urls_shorthands_and_pins() {
+        let parse = |entry: &str, base: Option<&str>| RemoteSource::parse(entry, base);
+        assert_eq!(parse("./base.json", None), None);
+        let pin = "AB".repeat(32);
+        assert_eq!(
+            parse(
+                &format!("github:unjs/c12/presets/base.json@v1{PIN}{pin}"),
+                None
+            ),
+            Some(Ok(RemoteSource {
+                url: "https://raw.githubusercontent.com/unjs/c12/v1/presets/base.json".into(),
+                sha256: Some(pin.to_ascii_lowercase()),
+            }))
+        );
+        let base = Some("https://example.com/configs/app/base.yaml");
+        let relative = parse("../shared/./common.toml", base).unwrap().unwrap();
+        assert_eq!(
+            relative.url,
+            "https://example.com/configs/shared/common.toml"
+        );
+        assert_eq!(relative.extension(), Some("toml"));
+        assert_eq!(
+            parse("/root.json", base).unwrap().unwrap().url,
+            "https://example.com/root.json"
+        );
+        assert_eq!(
+            parse("github:unjs/c12", None),
+            Some(Err(
+                "`github:unjs/c12` is not `github:owner/repo/path`".into()
+            ))
+        );
+        assert_eq!(
+            parse(&format!("https://a.dev/x.json{PIN}123"), None),
+            Some(Err("`123` is not a SHA-256 checksum".into()))
+        );
+
+        assert_eq!(
+            parse("http://a.dev/x.json", None),
+            Some(Err(format!(
+                "`http://a.dev/x.json` is plain HTTP; use https:// or pin it with `{PIN}<hex>`"
+            )))
+        );
+        assert!(
+            parse("./y.json", Some("http://a.dev/x.json"))
+                .unwrap()
+                .is_err()
+        );
+        let pinned = parse(&format!("http://a.dev/x.json{PIN}{pin}"), None);
+        assert!(pinned.unwrap().is_ok());
+    }
+
+    #[test]
+    fn hashes_with_sha256() {
+        assert_eq!(
+            sha256_hex(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
@@ -0,0 +1,299 @@
+use crate::error::C12Error;
+use crate::format::Format;
+
+/// Lines kept before the error line, besides the ones that make the
+/// snippet self-contained.
+const CONTEXT_LINES: usize = 2;
+
+/// Cuts the excerpt behind [`C12Error::repro`] out of `text`.
+pub(crate) fn minimal_repro(text: &str, error: &C12Error) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    if lines.is_empty() {
+        return String::new();
+    }
+    let mut line = (error.line.max(1) - 1).min(lines.len() - 1);
+    while line > 0 && lines[line].trim().is_empty() {
+        line -= 1;
+    }
+    let kept = match error.format {
+        Format::Json | Format::Json5 | Format::Jsonc => {
+            return json_repro(text, &lines, line, error.format);
+        }
+        Format::Toml => header_repro(text, &lines, line, Syntax::TOML),
+        Format::Ini => header_repro(text, &lines, line, Syntax::INI),
+        Format::Yaml => yaml_repro(&lines, line),
+    };
+    let mut out = String::new();
+    for index in kept {
+        out.push_str(lines[index]);
+        out.push('\n');
+    }
+    out
+}
+
+/// How a format quotes strings and writes comments, enough to tell
+/// brackets in values from brackets in strings and comments.
+struct Syntax {
+    quotes: &'static [char],
+    line_comments: &'static [&'static str],
+    block_comments: bool,
+}
+
+impl Syntax {
+    const JSON: Syntax = Syntax {
+        quotes: &['"'],
+        line_comments: &[],
+        block_comments: false,
+    };
+    const JSON5: Syntax = Syntax {
+        quotes: &['"', '\''],
+        line_comments: &["//"],
+        block_comments: true,
+    };
+    const TOML: Syntax = Syntax {
+        quotes: &['"', '\''],
+        line_comments: &["#"],
+        block_comments: false,
+    };
+    const INI: Syntax = Syntax {
+        quotes: &[],
+        line_comments: &["#", ";"],
+        block_comments: false,
+    };
+}
+
+/// Open brackets, innermost last, as `(offset, bracket)` pairs.
+type Open = Vec<(usize, char)>;
+
+/// The brackets open at the start of each line up to the end of line
+/// `last`, and at its end.
+fn open_brackets(text: &str, last: usize, syntax: &Syntax) -> (Vec<Open>, Open) {
+    let mut at_line_start = vec![Vec::new()];
+    let mut stack = Open::new();
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    let mut in_line_comment = false;
+    let mut in_block_comment = false;
+    let mut chars = text.char_indices().peekable();
+    while let Some((offset, c)) = chars.next() {
+        if c == '\n' {
+            if at_line_start.len() > last {
+                break;
+            }
+            // Strings spanning lines are rare enough in configs that an
+            // unclosed quote is better taken as the error itself.
+            in_line_comment = false;
+            quote = None;
+            at_line_start.push(stack.clone());
+            continue;
+        }
+        if in_line_comment {
+            continue;
+        }
+        if in_block_comment {
+            if c == '*' && chars.peek().is_some_and(|&(_, next)| next == '/') {
+                chars.next();
+                in_block_comment = false;
+            }
+            continue;
+        }
+        if let Some(open) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' && open == '"' {
+                escaped = true;
+            } else if c == open {
+                quote = None;
+            }
+            continue;
+        }
+        let rest = &text[offset..];
+        if syntax.line_comments.iter().any(|m| rest.starts_with(m)) {
+            in_line_comment = true;
+        } else if syntax.block_comments && rest.starts_with("/*") {
+            chars.next();
+            in_block_comment = true;
+        } else if syntax.quotes.contains(&c) {
+            quote = Some(c);
+        } else if c == '{' || c == '[' {
+            stack.push((offset, c));
+        } else if (c == '}' || c == ']') && !stack.is_empty() {
+            stack.pop();
+        }
+    }
+    (at_line_start, stack)
+}
+
+fn closer(open: char) -> char {
+    if open == '{' { '}' } else { ']' }
+}
+
+fn json_repro(text: &str, lines: &[&str], line: usize, format: Format) -> String {
+    let syntax = if format == Format::Json {
+        Syntax::JSON
+    } else {
+        Syntax::JSON5
+    };
+    let (at_line_start, open_at_end) = open_brackets(text, line, &syntax);
+    let enclosing = &at_line_start[line];
+    let Some(&(container, bracket)) = enclosing.last() else {
+        // At the top level there is nothing to balance.
+        let first = line.saturating_sub(CONTEXT_LINES);
+        return lines[first..=line]
+            .iter()
+            .map(|l| format!("{l}\n"))
+            .collect();
+    };
+    let depth = enclosing.len();
+    let container_line = text[..container].matches('\n').count();
+
+    // Start at the latest line at member level that still leaves some
+    // context, so a cut never lands inside a sibling's value.
+    let start = (container_line + 1..=line.saturating_sub(CONTEXT_LINES))
+        .rev()
+        .find(|&l| at_line_start[l].len() == depth);
+    let mut out = String::new();
+    match start {
+        Some(start) if start > container_line + 1 => {
+            out.push(bracket);
+            out.push('\n');
+            for l in &lines[start..=line] {
+                out.push_str(l);
+                out.push('\n');
+            }
+        }
+        _ => {
+            out.push_str(
+                text[container..]
+                    .split_inclusive('\n')
+                    .next()
+                    .unwrap_or_default(),
+            );
+            if !out.ends_with('\n') {
+                out.push('\n');
+            }
+            for l in &lines[container_line + 1..=line] {
+                out.push_str(l);
+                out.push('\n');
+            }
+        }
+    }
+    // Close what the snippet opened and left open, innermost first.
+    let opened = open_at_end
+        .iter()
+        .filter(|&&(offset, _)| offset >= container);
+    for (level, &(_, open)) in opened.enumerate().collect::<Vec<_>>().into_iter().rev() {
+        out.push_str(&"  ".repeat(level));
+        out.push(closer(open));
+        out.push('\n');
+    }
+    out
+}
+
+/// TOML and INI: the enclosing header, then the context lines, starting
+/// outside any multi-line array.
+fn header_repro(text: &str, lines: &[&str], line: usize, syntax: Syntax) -> Vec<usize> {
+    let (at_line_start, _) = open_brackets(text, line, &syntax);
+    let header = (0..=line)
+        .rev()
+        .find(|&l| at_line_start[l].is_empty() && lines[l].trim_start().starts_with('['));
+    let first_body = header.map_or(0, |h| h + 1);
+    let start = (first_body..=line)
+        .filter(|&l| at_line_start[l].is_empty())
+        .find(|&l| l + CONTEXT_LINES >= line)
+        .unwrap_or(line);
+    let mut kept: Vec<usize> = header.filter(|&h| h < start).into_iter().collect();
+    kept.extend(start..=line);
+    kept
+}
+
+/// YAML: the error line, the line before it, as errors such as an
+/// unclosed flow collection are reported on the line after, a few
+/// siblings of that line and the keys of every block enclosing it.
+fn yaml_repro(lines: &[&str], line: usize) -> Vec<usize> {
+    let indent = |l: &str| l.len() - l.trim_start_matches([' ', '-']).len();
+    let significant = |l: &str| {
+        let trimmed = l.trim_start();
+        !(trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with("---"))
+    };
+    let mut kept = vec![line];
+    let Some(anchor) = (0..line).rev().find(|&l| significant(lines[l])) else {
+        return kept;
+    };
+    kept.push(anchor);
+    let mut level = indent(lines[anchor]);
+    let mut siblings = 0;
+    let mut in_parents = false;
+    for l in (0..anchor).rev().filter(|&l| significant(lines[l])) {
+        if level == 0 {
+            break;
+        }
+        let own = indent(lines[l]);
+        if own < level {
+            kept.push(l);
+            level = own;
+            in_parents = true;
+        } else if own == level && !in_parents && siblings < CONTEXT_LINES {
+            kept.push(l);
+            siblings += 1;
+        }
+    }
+    kept.reverse();
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Format, parse_format};
+    use serde_json::Value as JsonValue;
+
+    fn repro(text: &str, format: Format) -> String {
+        let err = parse_format::<JsonValue>(text, format, None).unwrap_err();
+        let snippet = err.repro(text);
+        let again = parse_format::<JsonValue>(&snippet, format, None).unwrap_err();
+        // Messages may mention other positions, which moved with the cut.
+        let shape = |message: &str| message.replace(|c: char| c.is_ascii_digit(), "");
+        assert_eq!(
+            shape(&again.message),
+            shape(&err.message),
+            "{format}:\n{snippet}"
+        );
+        snippet
+    }
+
+    #[test]
+    fn cuts_json_down_to_the_enclosing_object() {
+        let mut text = String::from("{\n  \"name\": \"app\",\n  \"server\": {\n");
+        for i in 0..50 {
+            text.push_str(&format!("    \"key{i}\": {{ \"n\": {i} }},\n"));
+        }
+        text.push_str("    \"port\": 80,\n    \"host\": ?\n  }\n}\n");
+        assert_eq!(
+            repro(&text, Format::Json),
+            "{\n    \"key49\": { \"n\": 49 },\n    \"port\": 80,\n    \"host\": ?\n}\n"
+        );
+        assert_eq!(
+            repro("{\n  // c\n  \"a\": [1, 2,\n    ?]\n}\n", Format::Jsonc),
+            "[1, 2,\n    ?]\n"
+        );
+    }
+
+    #[test]
+    fn keeps_the_enclosing_table_or_block() {
+        let mut toml = String::from("[package]\nname = \"x\"\n\n[server]\n");
+        for i in 0..20 {
+            toml.push_str(&format!("k{i} = {i}\n"));
+        }
+        toml.push_str("port = ?\n\n[other]\na = 1\n");
+        assert_eq!(
+            repro(&toml, Format::Toml),
+            "[server]\nk18 = 18\nk19 = 19\nport = ?\n"
+        );
+
+        let yaml = "app:\n  name: x\n  db:\n    user: a\n    pool: 1\n    host: [1\nother: 2\n";
+        assert_eq!(
+            repro(yaml, Format::Yaml),
+            "app:\n  db:\n    user: a\n    pool: 1\n    host: [1\nother: 2\n"
+        );
+    }
+}
@@ -0,0 +1,230 @@
+use std::fmt;
+
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+use crate::error::C12Error;
+use crate::format::{Format, FormatOptions};
+use crate::hooks::HookResult;
+use crate::ini_format::{ini_map_to_value, ini_to_value, value_to_ini};
+use crate::mixed_arrays::{MixedArrayStrategy, normalize_mixed_arrays};
+use crate::path::{Path, shown};
+use crate::registry::parse_as;
+
+/// Returned when stringified output does not parse back to the value it
+/// was produced from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoundTripMismatch {
+    /// Path to the first differing value.
+    pub path: Path,
+    pub expected: Option<JsonValue>,
+    /// What the output parsed back to, `None` if the key went missing.
+    pub found: Option<JsonValue>,
+}
+
+impl fmt::Display for RoundTripMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |v: &Option<JsonValue>| v.as_ref().map_or("nothing".into(), |v| v.to_string());
+        write!(
+            f,
+            "round-trip mismatch at `{}`: expected {}, found {}",
+            shown(&self.path),
+            show(&self.expected),
+            show(&self.found)
+        )
+    }
+}
+
+impl std::error::Error for RoundTripMismatch {}
+
+/// Compares a value with what its stringified output parsed back to,
+/// reporting the first difference in key order. Numbers compare by value,
+/// so `1` and `1.0` are equal.
+// The mismatch carries both values by design; it is only built on failure.
+#[allow(clippy::result_large_err)]
+pub fn verify_round_trip(
+    expected: &JsonValue,
+    reparsed: &JsonValue,
+) -> Result<(), RoundTripMismatch> {
+    match first_difference(expected, reparsed, &Path::root(), &|a, b| a == b) {
+        Some(mismatch) => Err(mismatch),
+        None => Ok(()),
+    }
+}
+
+/// The first difference between two values in key order, comparing
+/// scalars with `same` after numbers that are equal by value.
+pub(crate) fn first_difference(
+    expected: &JsonValue,
+    found: &JsonValue,
+    path: &Path,
+    same: &dyn Fn(&JsonValue, &JsonValue) -> bool,
+) -> Option<RoundTripMismatch> {
+    let mismatch = || RoundTripMismatch {
+        path: path.clone(),
+        expected: Some(expected.clone()),
+        found: Some(found.clone()),
+    };
+    match (expected, found) {
+        (JsonValue::Object(a), JsonValue::Object(b)) => {
+            for (key, value) in a {
+                let child = path.clone().key(key.as_str());
+                match b.get(key) {
+                    Some(other) => {
+                        if let Some(m) = first_difference(value, other, &child, same) {
+                            return Some(m);
+                        }
+                    }
+                    None => {
+                        return Some(RoundTripMismatch {
+                            path: child,
+                            expected: Some(value.clone()),
+                            found: None,
+                        });
+                    }
+                }
+            }
+            b.iter()
+                .find(|(key, _)| !a.contains_key(*key))
+                .map(|(key, value)| RoundTripMismatch {
+                    path: path.clone().key(key.as_str()),
+                    expected: None,
+                    found: Some(value.clone()),
+                })
+        }
+        (JsonValue::Array(a), JsonValue::Array(b)) => {
+            let nested = a
+                .iter()
+                .zip(b)
+                .enumerate()
+                .find_map(|(i, (x, y))| first_difference(x, y, &path.clone().index(i), same));
+            nested.or_else(|| (a.len() != b.len()).then(mismatch))
+        }
+        (JsonValue::Number(a), JsonValue::Number(b)) if a.as_f64() == b.as_f64() => None,
+        _ if same(expected, found) => None,
+        _ => Some(mismatch()),
+    }
+}
+
+/// The round-trip check for `out`, the text `value` was stringified to as
+/// a built-in `format` with `options`. A mismatch stays a typed
+/// [`RoundTripMismatch`] and output that does not parse back is a
+/// [`C12Error`] located in it. INI output is compared with the sections
+/// it was written from, and TOML output with the value after mixed
+/// arrays are normalized, as both are rewritten on purpose.
+pub(crate) fn check_output(
+    format: Format,
+    value: &JsonValue,
+    out: &str,
+    options: &FormatOptions,
+) -> HookResult<()> {
+    let (expected, reparsed) = match format {
+        Format::Ini => (ini_map_to_value(value_to_ini(value)?), ini_to_value(out)),
+        _ => {
+            let expected = match options.mixed_arrays {
+                MixedArrayStrategy::Keep => value.clone(),
+                _ if format != Format::Toml => value.clone(),
+                strategy => {
+                    let mut value = toml::Value::try_from(value)?;
+                    normalize_mixed_arrays(&mut value, strategy)?;
+                    serde_json::to_value(value)?
+                }
+            };
+            let reparsed = parse_as(format, out, FormatOptions::default())
+                .map_err(|e| C12Error::new(format, out, &*e))?;
+            (expected, reparsed.value)
+        }
+    };
+    Ok(verify_round_trip(&expected, &reparsed)?)
+}
+
+/// Runs the round-trip check for a `stringify_*` function when
+/// `FormatOptions::verify_round_trip` is set. Only the message of a
+/// mismatch fits the backend's error type; the registry, which stringifies
+/// through [`check_output`] instead, keeps it typed.
+pub(crate) fn guard<T, E>(
+    value: &T,
+    text: &str,
+    reparse: impl FnOnce(&str) -> Result<JsonValue, String>,
+) -> Result<(), E>
+where
+    T: Serialize,
+    E: serde::ser::Error,
+{
+    let expected = serde_json::to_value(value).map_err(E::custom)?;
+    let reparsed = reparse(text)
+        .map_err(|e| E::custom(format!("round-trip failed, output does not parse: {e}")))?;
+    verify_round_trip(&expected, &reparsed).map_err(E::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reports_first_differing_path() {
+        let expected = json!({ "a": { "b": [1, 2, 3] }, "c": true });
+        let found = json!({ "a": { "b": [1, 5, 3] }, "c": false });
+        let err = verify_round_trip(&expected, &found).unwrap_err();
+        assert_eq!(err.path, Path::root().key("a").key("b").index(1));
+        assert_eq!(
+            err.to_string(),
+            "round-trip mismatch at `a.b[1]`: expected 2, found 5"
+        );
+    }
+
+    #[test]
+    fn reports_missing_and_extra_keys() {
+        let err = verify_round_trip(&json!({ "a": 1 }), &json!({})).unwrap_err();
+        assert_eq!((err.path, err.found), (Path::root().key("a"), None));
+
+        let err = verify_round_trip(&json!({}), &json!({ "b": 1 })).unwrap_err();
+        assert_eq!((err.path, err.expected), (Path::root().key("b"), None));
+    }
+
+    #[test]
+    fn numbers_compare_by_value() {
+        assert!(verify_round_trip(&json!({ "n": 3.0 }), &json!({ "n": 3 })).is_ok());
+    }
+
+    #[test]
+    fn registry_mismatches_are_typed_for_every_format() {
+        let formatted = crate::format::Formatted::new(
+            "",
+            json!({ "DB": { "Host": "a" } }),
+            &Default::default(),
+        );
+        let options = FormatOptions {
+            verify_round_trip: true,
+            ..Default::default()
+        };
+        let err =
+            crate::registry::stringify_as(Format::Ini, &formatted, options.clone()).unwrap_err();
+        let mismatch = err.downcast_ref::<RoundTripMismatch>().unwrap();
+        assert_eq!(mismatch.path, Path::root().key("DB"));
+        assert_eq!(mismatch.found, None);
+
+        for format in Format::ALL {
+            let value = json!({ "db": { "host": "a", "port": 1 } });
+            let formatted = crate::format::Formatted::new("", value, &Default::default());
+            let out = crate::registry::stringify_as(format, &formatted, options.clone());
+            assert!(out.is_ok(), "{format}: {out:?}");
+        }
+        let err =
+            check_output(Format::Jsonc, &json!({ "a": 1 }), "{ \"a\": ", &options).unwrap_err();
+        assert!(err.downcast_ref::<C12Error>().is_some(), "{err}");
+    }
+
+    #[test]
+    fn guard_surfaces_mismatch_through_backend_error() {
+        let err = guard::<_, serde_json::Error>(&json!({ "a": 1 }), r#"{ "a": 2 }"#, |text| {
+            serde_json::from_str(text).map_err(|e| e.to_string())
+        })
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "round-trip mismatch at `a`: expected 1, found 2"
+        );
+    }
+}
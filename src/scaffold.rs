@@ -0,0 +1,128 @@
+use std::error::Error;
+
+use serde::Serialize;
+
+use crate::format::{Format, FormatOptions, Formatted, StyleProfile};
+use crate::ini_format::{stringify_ini, value_to_ini};
+use crate::{stringify_json, stringify_json5, stringify_jsonc, stringify_toml, stringify_yaml};
+
+/// Writes `T::default()` as a new config file in `format`, for `init`
+/// commands.
+///
+/// Output uses the [`StyleProfile::Confbox`] conventions with two-space
+/// indentation and ends with a newline. Keys follow the field order of
+/// `T`. `None` fields are left out of TOML, which has no null, and written
+/// as bare keys in INI.
+///
+/// ```
+/// use c12_parser::{Format, scaffold};
+///
+/// #[derive(Default, serde::Serialize)]
+/// struct Config {
+///     port: u16,
+///     name: String,
+/// }
+///
+/// assert_eq!(scaffold::<Config>(Format::Toml)?, "port = 0\nname = \"\"\n");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn scaffold<T>(format: Format) -> Result<String, Box<dyn Error>>
+where
+    T: Default + Serialize,
+{
+    let opts = FormatOptions {
+        indent: Some(2),
+        profile: StyleProfile::Confbox,
+        ..Default::default()
+    };
+    let formatted = Formatted::new("", T::default(), &opts);
+
+    let mut out = match format {
+        Format::Json => stringify_json(&formatted, Some(opts))?,
+        Format::Json5 => stringify_json5(&formatted, Some(opts))?,
+        Format::Jsonc => {
+            let value = serde_json::to_value(&formatted.value)?;
+            stringify_jsonc(&Formatted::new("", value, &opts), Some(opts))?
+        }
+        Format::Toml => stringify_toml(&formatted, Some(opts))?,
+        Format::Yaml => stringify_yaml(&formatted, Some(opts))?,
+        Format::Ini => stringify_ini(&value_to_ini(&serde_json::to_value(&formatted.value)?)?),
+    };
+    if !out.ends_with('\n') {
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Config {
+        name: String,
+        debug: bool,
+        log_file: Option<String>,
+        server: Server,
+    }
+
+    impl Default for Config {
+        fn default() -> Self {
+            Self {
+                name: "app".into(),
+                debug: false,
+                log_file: None,
+                server: Server {
+                    host: "localhost".into(),
+                    port: 8080,
+                },
+            }
+        }
+    }
+
+    #[test]
+    fn scaffolds_every_format_with_a_trailing_newline() {
+        for format in Format::ALL {
+            let out = scaffold::<Config>(format).unwrap();
+            assert!(out.ends_with('\n'), "{format}");
+            assert!(!out.ends_with("\n\n"), "{format}");
+        }
+    }
+
+    #[test]
+    fn scaffolded_files_parse_back_to_the_default() {
+        let json = scaffold::<Config>(Format::Json).unwrap();
+        assert_eq!(
+            json,
+            "{\n  \"name\": \"app\",\n  \"debug\": false,\n  \"log_file\": null,\n  \"server\": {\n    \"host\": \"localhost\",\n    \"port\": 8080\n  }\n}\n"
+        );
+        let yaml = scaffold::<Config>(Format::Yaml).unwrap();
+        assert_eq!(
+            crate::parse_yaml::<Config>(&yaml, None).unwrap().value,
+            Config::default()
+        );
+        let toml = scaffold::<Config>(Format::Toml).unwrap();
+        assert_eq!(
+            crate::parse_toml::<Config>(&toml, None).unwrap().value,
+            Config::default()
+        );
+        assert!(!toml.contains("log_file"));
+    }
+
+    #[test]
+    fn ini_scaffold_rejects_arrays() {
+        #[derive(Default, Serialize)]
+        struct Tagged {
+            tags: Vec<String>,
+        }
+        let err = scaffold::<Tagged>(Format::Ini).unwrap_err();
+        assert_eq!(err.to_string(), "INI section `tags` must be an object");
+    }
+}
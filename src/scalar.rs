@@ -0,0 +1,190 @@
+use std::fmt;
+
+use serde_json::{Number, Value as JsonValue};
+
+/// Options for reading untyped scalars from string-only formats (INI).
+///
+/// Parsing never consults the system locale: `.` is the decimal separator
+/// and dates are ISO 8601 on every platform, unless `comma_decimal`
+/// explicitly opts into `,`.
+#[derive(Clone, Debug, Default)]
+pub struct ScalarOptions {
+    /// Accept `,` as the decimal separator (`3,14`), as written by tools
+    /// running under European locales. The value is normalized to a plain
+    /// number. Thousands separators are never accepted, so a comma
+    /// followed by exactly three digits (`1,234`, which may mean either)
+    /// is not a number, and neither is a second comma.
+    pub comma_decimal: bool,
+}
+
+/// A calendar date read by [`parse_date`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// Parses a decimal number: an optional sign, digits, an optional
+/// fraction and an optional exponent. Hex, `_` separators, `inf` and `nan`
+/// are rejected.
+pub fn parse_number(text: &str, options: &ScalarOptions) -> Option<Number> {
+    let normalized;
+    let mut text = text.trim();
+    if options.comma_decimal
+        && let Some((_, frac)) = text.split_once(',')
+        && !text.contains('.')
+    {
+        let frac = frac.split(['e', 'E']).next().unwrap_or(frac);
+        if frac.contains(',') || frac.len() == 3 {
+            return None;
+        }
+        normalized = text.replace(',', ".");
+        text = &normalized;
+    }
+
+    let unsigned = text.strip_prefix(['-', '+']).unwrap_or(text);
+    let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+        Some(i) => (&unsigned[..i], Some(&unsigned[i + 1..])),
+        None => (unsigned, None),
+    };
+    let (int, frac) = match mantissa.split_once('.') {
+        Some((int, frac)) => (int, Some(frac)),
+        None => (mantissa, None),
+    };
+    let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let valid = digits(int)
+        && frac.is_none_or(digits)
+        && exponent.is_none_or(|e| digits(e.strip_prefix(['-', '+']).unwrap_or(e)));
+    if !valid {
+        return None;
+    }
+
+    let text = text.strip_prefix('+').unwrap_or(text);
+    if frac.is_none() && exponent.is_none() {
+        if let Ok(i) = text.parse::<i64>() {
+            return Some(i.into());
+        }
+        if let Ok(u) = text.parse::<u64>() {
+            return Some(u.into());
+        }
+    }
+    text.parse::<f64>().ok().and_then(Number::from_f64)
+}
+
+/// Parses an ISO 8601 calendar date (`YYYY-MM-DD`), optionally followed
+/// by a time part, which is ignored. Locale-dependent spellings such as
+/// `03/04/2024` are rejected rather than guessed.
+pub fn parse_date(text: &str) -> Option<Date> {
+    let text = text.trim();
+    let date = text.get(..10)?;
+    let rest = &text[10..];
+    if !(rest.is_empty() || rest.starts_with(['T', 't', ' '])) {
+        return None;
+    }
+
+    let bytes = date.as_bytes();
+    if bytes[4] != b'-' || bytes[7] != b'-' {
+        return None;
+    }
+    let field = |range: std::ops::Range<usize>| -> Option<u16> {
+        let s = &date[range];
+        s.bytes()
+            .all(|b| b.is_ascii_digit())
+            .then(|| s.parse().ok())
+            .flatten()
+    };
+    let year = field(0..4)?;
+    let month = field(5..7)? as u8;
+    let day = field(8..10)? as u8;
+
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return None,
+    };
+    (1..=days_in_month)
+        .contains(&day)
+        .then_some(Date { year, month, day })
+}
+
+/// Reads an untyped scalar: `true`/`false`, `null`, numbers per
+/// [`parse_number`], and anything else as a string.
+pub fn typed_scalar(text: &str, options: &ScalarOptions) -> JsonValue {
+    match text.trim() {
+        "true" => JsonValue::Bool(true),
+        "false" => JsonValue::Bool(false),
+        "null" => JsonValue::Null,
+        trimmed => parse_number(trimmed, options)
+            .map_or_else(|| JsonValue::String(text.to_string()), JsonValue::Number),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_numbers_without_locale() {
+        let opts = ScalarOptions::default();
+        let parse = |s| parse_number(s, &opts).map(JsonValue::Number);
+        assert_eq!(parse("42"), Some(json!(42)));
+        assert_eq!(parse("-1.5e3"), Some(json!(-1500.0)));
+        assert_eq!(parse("+7"), Some(json!(7)));
+        assert_eq!(parse("18446744073709551615"), Some(json!(u64::MAX)));
+        for rejected in ["3,14", "1_000", "0x10", "inf", "NaN", ".5", "1.", "1e", ""] {
+            assert_eq!(parse(rejected), None, "{rejected}");
+        }
+    }
+
+    #[test]
+    fn comma_decimal_is_opt_in_and_normalized() {
+        let opts = ScalarOptions {
+            comma_decimal: true,
+        };
+        assert_eq!(typed_scalar("2,75", &opts), json!(2.75));
+        assert_eq!(typed_scalar("-0,5", &opts), json!(-0.5));
+        // Two commas, or three digits after one, may be thousands
+        // separators, which stay strings.
+        assert_eq!(typed_scalar("1,234,567", &opts), json!("1,234,567"));
+        assert_eq!(typed_scalar("1,234", &opts), json!("1,234"));
+        assert_eq!(typed_scalar("1,2345", &opts), json!(1.2345));
+        assert_eq!(
+            typed_scalar("3,14", &ScalarOptions::default()),
+            json!("3,14")
+        );
+    }
+
+    #[test]
+    fn parses_iso_dates_only() {
+        let date = parse_date("2024-02-29T10:00:00Z").unwrap();
+        assert_eq!(date.to_string(), "2024-02-29");
+        assert_eq!(
+            parse_date("1979-05-27"),
+            Some(Date {
+                year: 1979,
+                month: 5,
+                day: 27
+            })
+        );
+        for rejected in [
+            "2023-02-29",
+            "2024-13-01",
+            "03/04/2024",
+            "2024-1-01",
+            "2024-01-01x",
+        ] {
+            assert_eq!(parse_date(rejected), None, "{rejected}");
+        }
+    }
+}
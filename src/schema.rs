@@ -0,0 +1,586 @@
+use std::fmt;
+use std::ops::Range;
+
+use regex::Regex;
+use serde_json::{Map, Value as JsonValue};
+
+use crate::diagnostic::MessageArgs;
+use crate::error::{C12Error, parse_format};
+use crate::format::{Format, Formatted};
+use crate::outline::spans;
+use crate::path::Path;
+
+/// How many `$ref`s in a row are followed before giving up on a cycle.
+const MAX_REFS: usize = 32;
+
+/// A JSON Schema to [`validate`] configs against.
+///
+/// The keywords checked are `type`, `enum`, `const`, `required`,
+/// `properties`, `additionalProperties`, `items` (a schema, or one per
+/// index), `minimum`, `maximum`, `exclusiveMinimum`, `exclusiveMaximum`,
+/// `minLength`, `maxLength`, `pattern`, `minItems`, `maxItems`,
+/// `uniqueItems`, `allOf`, `anyOf` and `oneOf`. A `$ref` to a pointer in
+/// the same schema, such as `#/$defs/port`, stands for its target, as in
+/// draft 7. Other keywords, annotations included, are ignored.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Schema {
+    root: JsonValue,
+}
+
+impl Schema {
+    pub fn new(schema: JsonValue) -> Self {
+        Self { root: schema }
+    }
+
+    pub fn as_value(&self) -> &JsonValue {
+        &self.root
+    }
+}
+
+impl From<JsonValue> for Schema {
+    fn from(schema: JsonValue) -> Self {
+        Self::new(schema)
+    }
+}
+
+/// A value breaking a keyword of a [`Schema`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    /// Where the value is, as a JSON Pointer; empty for the whole config.
+    /// A missing required key is reported on its object.
+    pub pointer: String,
+    pub path: Path,
+    /// The keyword broken, such as `type` or `maximum`.
+    pub keyword: String,
+    pub message: String,
+    /// The values behind `message` besides the path, for
+    /// [localized](crate::Diagnostic::localized) rendering.
+    pub args: MessageArgs,
+    /// Byte range of the value's entry in the source, from its key to the
+    /// end of the value, when validated with [`validate_text`].
+    pub span: Option<Range<usize>>,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.pointer.is_empty() {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "`{}`: {}", self.pointer, self.message)
+        }
+    }
+}
+
+/// Checks a parsed config against `schema`, returning every violation in
+/// document order; none when it is valid. The violations carry no span,
+/// as a [`Formatted`] value no longer has its text; see [`validate_text`].
+///
+/// ```
+/// use c12_parser::{Schema, parse_yaml, validate};
+/// use serde_json::json;
+///
+/// let schema = Schema::new(json!({
+///     "properties": { "port": { "type": "integer", "maximum": 65535 } },
+///     "required": ["name"]
+/// }));
+/// let config = parse_yaml("port: 80000\n", None)?;
+/// let messages: Vec<_> = validate(&config, &schema).iter().map(|v| v.to_string()).collect();
+/// assert_eq!(messages, ["missing required key `name`", "`/port`: expected at most 65535, found 80000"]);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn validate(config: &Formatted<JsonValue>, schema: &Schema) -> Vec<Violation> {
+    let mut validator = Validator {
+        root: &schema.root,
+        violations: Vec::new(),
+    };
+    validator.check(&config.value, &schema.root, &Path::root());
+    validator.violations
+}
+
+/// Parses `text` and [`validate`]s it, giving each violation the span of
+/// its entry, or of the closest enclosing one the format's document model
+/// places. Fails with the parse error when `text` does not parse.
+///
+/// ```
+/// use c12_parser::{Format, Schema, validate_text};
+/// use serde_json::json;
+///
+/// let schema = Schema::new(json!({ "properties": { "tags": { "uniqueItems": true } } }));
+/// let text = "name = \"web\"\ntags = [\"a\", \"a\"]\n";
+/// let violations = validate_text(text, Format::Toml, &schema)?;
+/// assert_eq!(violations[0].keyword, "uniqueItems");
+/// assert_eq!(&text[violations[0].span.clone().unwrap()], "tags = [\"a\", \"a\"]");
+/// # Ok::<(), c12_parser::C12Error>(())
+/// ```
+pub fn validate_text(
+    text: &str,
+    format: Format,
+    schema: &Schema,
+) -> Result<Vec<Violation>, C12Error> {
+    let config = parse_format::<JsonValue>(text, format, None)?;
+    let spans = spans(text, format);
+    let mut violations = validate(&config, schema);
+    for violation in &mut violations {
+        let mut path = Some(violation.path.clone());
+        while let Some(candidate) = path {
+            if candidate.is_root() {
+                violation.span = Some(0..text.len());
+                break;
+            }
+            if let Some(span) = spans.get(&candidate) {
+                violation.span = Some(span.clone());
+                break;
+            }
+            path = candidate.parent();
+        }
+    }
+    Ok(violations)
+}
+
+/// Fills in the `default` of every key `schema` declares that `value`
+/// lacks, so that deserializing into a struct needs no `Option` for
+/// fields with defaults.
+///
+/// Defaults are looked up in `properties`, following `$ref`s and
+/// `allOf`, at every depth: inside each object present, including one
+/// just filled in, and each item of arrays with an `items` schema. A
+/// missing object is not created for the defaults inside it unless it
+/// has a `default` of its own, such as `{}`. Keys set to `null` are
+/// left alone.
+///
+/// ```
+/// use c12_parser::{Schema, apply_defaults};
+/// use serde_json::json;
+///
+/// let schema = Schema::new(json!({
+///     "properties": {
+///         "port": { "default": 8080 },
+///         "log": {
+///             "default": {},
+///             "properties": { "level": { "default": "info" }, "color": { "default": true } }
+///         }
+///     }
+/// }));
+/// let mut config = json!({ "log": { "color": false } });
+/// apply_defaults(&mut config, &schema);
+/// assert_eq!(config, json!({ "log": { "color": false, "level": "info" }, "port": 8080 }));
+/// ```
+pub fn apply_defaults(value: &mut JsonValue, schema: &Schema) {
+    fill(value, &schema.root, &schema.root);
+}
+
+fn fill(value: &mut JsonValue, schema: &JsonValue, root: &JsonValue) {
+    let schema = resolve(root, schema);
+    if let Some(JsonValue::Array(all)) = schema.get("allOf") {
+        for each in all {
+            fill(value, each, root);
+        }
+    }
+    match value {
+        JsonValue::Object(map) => {
+            let Some(properties) = schema.get("properties").and_then(JsonValue::as_object) else {
+                return;
+            };
+            for (key, property) in properties {
+                if !map.contains_key(key)
+                    && let Some(default) = resolve(root, property).get("default")
+                {
+                    map.insert(key.clone(), default.clone());
+                }
+                if let Some(child) = map.get_mut(key) {
+                    fill(child, property, root);
+                }
+            }
+        }
+        JsonValue::Array(items) => match schema.get("items") {
+            Some(JsonValue::Array(tuple)) => {
+                for (item, each) in items.iter_mut().zip(tuple) {
+                    fill(item, each, root);
+                }
+            }
+            Some(each) => items.iter_mut().for_each(|item| fill(item, each, root)),
+            None => {}
+        },
+        _ => {}
+    }
+}
+
+/// `schema` with its `$ref`s to pointers in `root` followed.
+pub(crate) fn resolve<'s>(root: &'s JsonValue, mut schema: &'s JsonValue) -> &'s JsonValue {
+    for _ in 0..MAX_REFS {
+        let target = schema
+            .get("$ref")
+            .and_then(JsonValue::as_str)
+            .and_then(|reference| reference.strip_prefix('#'))
+            .and_then(|pointer| root.pointer(pointer));
+        match target {
+            Some(target) => schema = target,
+            None => break,
+        }
+    }
+    schema
+}
+
+fn matches_type(value: &JsonValue, expected: &JsonValue) -> bool {
+    match expected {
+        JsonValue::String(name) => match name.as_str() {
+            "integer" => value.as_i64().is_some() || value.as_u64().is_some(),
+            "number" => value.is_number(),
+            other => type_name(value) == other,
+        },
+        JsonValue::Array(names) => names.iter().any(|name| matches_type(value, name)),
+        _ => true,
+    }
+}
+
+/// A schema `type`, as `integer` or `integer or string`.
+fn type_names(expected: &JsonValue) -> String {
+    match expected {
+        JsonValue::String(name) => name.clone(),
+        JsonValue::Array(names) => names
+            .iter()
+            .map(type_names)
+            .collect::<Vec<_>>()
+            .join(" or "),
+        other => other.to_string(),
+    }
+}
+
+fn type_name(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Null => "null",
+        JsonValue::Bool(_) => "boolean",
+        JsonValue::Number(_) => "number",
+        JsonValue::String(_) => "string",
+        JsonValue::Array(_) => "array",
+        JsonValue::Object(_) => "object",
+    }
+}
+
+/// Whether a number meets a bound.
+type Holds = fn(f64, f64) -> bool;
+
+struct Validator<'s> {
+    root: &'s JsonValue,
+    violations: Vec<Violation>,
+}
+
+impl<'s> Validator<'s> {
+    fn report(&mut self, path: &Path, keyword: &str, message: String) {
+        self.report_with(path, keyword, message, Vec::new());
+    }
+
+    fn report_with(&mut self, path: &Path, keyword: &str, message: String, args: MessageArgs) {
+        self.violations.push(Violation {
+            pointer: path.to_pointer(),
+            path: path.clone(),
+            keyword: keyword.to_string(),
+            message,
+            args,
+            span: None,
+        });
+    }
+
+    /// Whether `value` meets `schema`, without reporting anything.
+    fn passes(&self, value: &JsonValue, schema: &'s JsonValue) -> bool {
+        let mut validator = Validator {
+            root: self.root,
+            violations: Vec::new(),
+        };
+        validator.check(value, schema, &Path::root());
+        validator.violations.is_empty()
+    }
+
+    fn check(&mut self, value: &JsonValue, schema: &'s JsonValue, path: &Path) {
+        let schema = match resolve(self.root, schema) {
+            JsonValue::Bool(false) => {
+                self.report(path, "false", "no value is allowed here".into());
+                return;
+            }
+            schema @ JsonValue::Object(_) => schema,
+            _ => return,
+        };
+        if let Some(expected) = schema.get("type")
+            && !matches_type(value, expected)
+        {
+            let message = format!(
+                "expected {}, found {}",
+                type_names(expected),
+                type_name(value)
+            );
+            let args = vec![
+                ("expected", type_names(expected)),
+                ("found", type_name(value).to_string()),
+            ];
+            self.report_with(path, "type", message, args);
+            return;
+        }
+        if let Some(JsonValue::Array(allowed)) = schema.get("enum")
+            && !allowed.contains(value)
+        {
+            let message = format!("{value} is not one of the allowed values");
+            self.report_with(path, "enum", message, vec![("value", value.to_string())]);
+        }
+        if let Some(expected) = schema.get("const")
+            && value != expected
+        {
+            self.report(path, "const", format!("expected {expected}, found {value}"));
+        }
+        self.check_combinators(value, schema, path);
+
+        match value {
+            JsonValue::Number(number) => self.check_number(number.as_f64(), value, schema, path),
+            JsonValue::String(text) => self.check_string(text, value, schema, path),
+            JsonValue::Array(items) => self.check_array(items, schema, path),
+            JsonValue::Object(map) => self.check_object(map, schema, path),
+            _ => {}
+        }
+    }
+
+    fn check_combinators(&mut self, value: &JsonValue, schema: &'s JsonValue, path: &Path) {
+        if let Some(JsonValue::Array(all)) = schema.get("allOf") {
+            for each in all {
+                self.check(value, each, path);
+            }
+        }
+        if let Some(JsonValue::Array(any)) = schema.get("anyOf")
+            && !any.iter().any(|each| self.passes(value, each))
+        {
+            let message = "matches none of the `anyOf` schemas".to_string();
+            self.report(path, "anyOf", message);
+        }
+        if let Some(JsonValue::Array(one)) = schema.get("oneOf") {
+            let matched = one.iter().filter(|each| self.passes(value, each)).count();
+            if matched != 1 {
+                let message = format!("matches {matched} of the `oneOf` schemas, not exactly one");
+                self.report(path, "oneOf", message);
+            }
+        }
+    }
+
+    fn check_number(
+        &mut self,
+        number: Option<f64>,
+        value: &JsonValue,
+        schema: &JsonValue,
+        path: &Path,
+    ) {
+        let Some(number) = number else {
+            return;
+        };
+        let bounds: [(&str, Holds, &str); 4] = [
+            ("minimum", |n, bound| n >= bound, "at least"),
+            ("exclusiveMinimum", |n, bound| n > bound, "more than"),
+            ("maximum", |n, bound| n <= bound, "at most"),
+            ("exclusiveMaximum", |n, bound| n < bound, "less than"),
+        ];
+        for (keyword, holds, expected) in bounds {
+            if let Some(bound) = schema.get(keyword)
+                && let Some(limit) = bound.as_f64()
+                && !holds(number, limit)
+            {
+                self.report(
+                    path,
+                    keyword,
+                    format!("expected {expected} {bound}, found {value}"),
+                );
+            }
+        }
+    }
+
+    fn check_string(&mut self, text: &str, value: &JsonValue, schema: &JsonValue, path: &Path) {
+        let length = text.chars().count();
+        self.check_count(length, schema, path, "minLength", "maxLength", "characters");
+        if let Some(pattern) = schema.get("pattern").and_then(JsonValue::as_str)
+            && let Ok(regex) = Regex::new(pattern)
+            && !regex.is_match(text)
+        {
+            let message = format!("{value} does not match the pattern `{pattern}`");
+            self.report(path, "pattern", message);
+        }
+    }
+
+    fn check_array(&mut self, items: &[JsonValue], schema: &'s JsonValue, path: &Path) {
+        self.check_count(items.len(), schema, path, "minItems", "maxItems", "items");
+        if schema.get("uniqueItems") == Some(&JsonValue::Bool(true))
+            && let Some((index, item)) = items
+                .iter()
+                .enumerate()
+                .find(|(index, item)| items[..*index].contains(item))
+        {
+            let message = format!("expected unique items, found {item} again at index {index}");
+            self.report(path, "uniqueItems", message);
+        }
+        match schema.get("items") {
+            Some(JsonValue::Array(tuple)) => {
+                for (index, (item, each)) in items.iter().zip(tuple).enumerate() {
+                    self.check(item, each, &path.clone().index(index));
+                }
+            }
+            Some(each) => {
+                for (index, item) in items.iter().enumerate() {
+                    self.check(item, each, &path.clone().index(index));
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn check_object(&mut self, map: &Map<String, JsonValue>, schema: &'s JsonValue, path: &Path) {
+        if let Some(JsonValue::Array(required)) = schema.get("required") {
+            for key in required.iter().filter_map(JsonValue::as_str) {
+                if !map.contains_key(key) {
+                    let message = format!("missing required key `{key}`");
+                    self.report_with(path, "required", message, vec![("key", key.to_string())]);
+                }
+            }
+        }
+        let properties = schema.get("properties").and_then(JsonValue::as_object);
+        for (key, child) in map {
+            let child_path = path.clone().key(key.as_str());
+            match (
+                properties.and_then(|p| p.get(key)),
+                schema.get("additionalProperties"),
+            ) {
+                (Some(property), _) => self.check(child, property, &child_path),
+                (None, Some(JsonValue::Bool(false))) => {
+                    let message = format!("unknown key `{key}` is not allowed");
+                    let args = vec![("key", key.clone())];
+                    self.report_with(&child_path, "additionalProperties", message, args);
+                }
+                (None, Some(additional)) => self.check(child, additional, &child_path),
+                (None, None) => {}
+            }
+        }
+    }
+
+    /// Checks a length against the `min` and `max` keywords.
+    fn check_count(
+        &mut self,
+        count: usize,
+        schema: &JsonValue,
+        path: &Path,
+        min: &str,
+        max: &str,
+        unit: &str,
+    ) {
+        if let Some(limit) = schema.get(min).and_then(JsonValue::as_u64)
+            && (count as u64) < limit
+        {
+            let message = format!("expected at least {limit} {unit}, found {count}");
+            self.report(path, min, message);
+        }
+        if let Some(limit) = schema.get(max).and_then(JsonValue::as_u64)
+            && (count as u64) > limit
+        {
+            let message = format!("expected at most {limit} {unit}, found {count}");
+            self.report(path, max, message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::FormatOptions;
+    use serde_json::json;
+
+    fn check(value: JsonValue, schema: JsonValue) -> Vec<(String, String)> {
+        let config = Formatted::new("", value, &FormatOptions::default());
+        validate(&config, &Schema::new(schema))
+            .into_iter()
+            .map(|v| (v.pointer, v.keyword))
+            .collect()
+    }
+
+    #[test]
+    fn checks_keywords_through_refs_and_combinators() {
+        let schema = json!({
+            "$defs": {
+                "port": { "type": "integer", "minimum": 1, "exclusiveMaximum": 65536 },
+                "name": { "type": "string", "pattern": "^[a-z]+$", "maxLength": 8 }
+            },
+            "properties": {
+                "servers": {
+                    "type": "array",
+                    "minItems": 1,
+                    "items": {
+                        "properties": {
+                            "name": { "$ref": "#/$defs/name" },
+                            "port": { "$ref": "#/$defs/port" }
+                        },
+                        "additionalProperties": false
+                    }
+                },
+                "mode": { "oneOf": [{ "const": "dev" }, { "const": "prod" }] },
+                "retries": { "anyOf": [{ "type": "integer" }, { "enum": ["never"] }] }
+            }
+        });
+        let config = json!({
+            "servers": [
+                { "name": "web", "port": 443 },
+                { "name": "Web-Admin", "port": 65536, "tls": true }
+            ],
+            "mode": "test",
+            "retries": "often"
+        });
+        assert_eq!(
+            check(config, schema),
+            [
+                ("/servers/1/name", "maxLength"),
+                ("/servers/1/name", "pattern"),
+                ("/servers/1/port", "exclusiveMaximum"),
+                ("/servers/1/tls", "additionalProperties"),
+                ("/mode", "oneOf"),
+                ("/retries", "anyOf"),
+            ]
+            .map(|(pointer, keyword)| (pointer.to_string(), keyword.to_string()))
+        );
+        assert_eq!(
+            check(
+                json!({ "a/b": 1 }),
+                json!({ "additionalProperties": false })
+            )[0]
+            .0,
+            "/a~1b"
+        );
+    }
+
+    #[test]
+    fn fills_defaults_through_refs_and_arrays() {
+        let schema = Schema::new(json!({
+            "$defs": { "server": { "properties": { "port": { "default": 80 } } } },
+            "allOf": [{ "properties": { "debug": { "default": false } } }],
+            "properties": {
+                "servers": { "items": { "$ref": "#/$defs/server" } },
+                "cache": { "properties": { "ttl": { "default": 60 } } },
+                "name": { "default": "app" }
+            }
+        }));
+        let mut config = json!({ "servers": [{ "host": "a" }, { "port": 8080 }], "name": null });
+        apply_defaults(&mut config, &schema);
+        assert_eq!(
+            config,
+            json!({
+                "servers": [{ "host": "a", "port": 80 }, { "port": 8080 }],
+                "name": null,
+                "debug": false
+            })
+        );
+    }
+
+    #[test]
+    fn spans_point_into_the_source() {
+        let schema = Schema::new(json!({
+            "properties": { "server": { "required": ["host"], "properties": { "port": { "type": "integer" } } } }
+        }));
+        let text = "# app\nserver:\n  port: '80'\nlog: info\n";
+        let violations = validate_text(text, Format::Yaml, &schema).unwrap();
+        let spans: Vec<_> = violations
+            .iter()
+            .map(|v| &text[v.span.clone().unwrap()])
+            .collect();
+        assert_eq!(spans, ["server:\n  port: '80'", "port: '80'"]);
+        assert!(validate_text("a: [", Format::Yaml, &schema).is_err());
+    }
+}
@@ -0,0 +1,276 @@
+use std::collections::BTreeSet;
+
+use serde_json::{Map, Value as JsonValue, json};
+
+use crate::path::join_key;
+
+/// A difference between two JSON Schemas.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SchemaChangeKind {
+    /// A property was removed, so configs setting it become invalid or
+    /// silently ignored.
+    RemovedKey,
+    /// A property was added.
+    AddedKey,
+    /// Some previously accepted types are no longer accepted.
+    NarrowedType { old: Vec<String>, new: Vec<String> },
+    /// More types are accepted than before.
+    WidenedType { old: Vec<String>, new: Vec<String> },
+    /// Previously allowed `enum` values were removed.
+    NarrowedEnum { removed: Vec<JsonValue> },
+    /// A property became required.
+    NewRequired,
+    /// A property is no longer required.
+    NoLongerRequired,
+}
+
+/// One change reported by [`diff_schemas`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SchemaChange {
+    /// Path to the property, with `[*]` standing for array items.
+    pub path: String,
+    pub kind: SchemaChangeKind,
+}
+
+impl SchemaChange {
+    /// Whether a config valid under the old schema can be rejected (or
+    /// misread) under the new one.
+    pub fn is_breaking(&self) -> bool {
+        matches!(
+            self.kind,
+            SchemaChangeKind::RemovedKey
+                | SchemaChangeKind::NarrowedType { .. }
+                | SchemaChangeKind::NarrowedEnum { .. }
+                | SchemaChangeKind::NewRequired
+        )
+    }
+}
+
+/// Compares two JSON Schemas, following `properties` and `items`, and lists
+/// the changes in path order. Filter with [`SchemaChange::is_breaking`] to
+/// gate schema evolution in CI.
+pub fn diff_schemas(old: &JsonValue, new: &JsonValue) -> Vec<SchemaChange> {
+    let mut changes = Vec::new();
+    diff(old, new, "", &mut changes);
+    changes
+}
+
+/// Infers a minimal schema (`type`, `properties`, `required`, `items`)
+/// from a sample config, so two config versions can be compared with
+/// [`diff_schemas`] when no schema is maintained.
+pub fn infer_schema(value: &JsonValue) -> JsonValue {
+    match value {
+        JsonValue::Null => json!({ "type": "null" }),
+        JsonValue::Bool(_) => json!({ "type": "boolean" }),
+        JsonValue::Number(n) if n.is_f64() => json!({ "type": "number" }),
+        JsonValue::Number(_) => json!({ "type": "integer" }),
+        JsonValue::String(_) => json!({ "type": "string" }),
+        JsonValue::Array(items) => match items.first() {
+            Some(first) => json!({ "type": "array", "items": infer_schema(first) }),
+            None => json!({ "type": "array" }),
+        },
+        JsonValue::Object(map) => {
+            let properties: Map<_, _> = map
+                .iter()
+                .map(|(key, value)| (key.clone(), infer_schema(value)))
+                .collect();
+            json!({
+                "type": "object",
+                "properties": properties,
+                "required": map.keys().collect::<Vec<_>>(),
+            })
+        }
+    }
+}
+
+fn diff(old: &JsonValue, new: &JsonValue, path: &str, changes: &mut Vec<SchemaChange>) {
+    let mut push = |kind| {
+        changes.push(SchemaChange {
+            path: path.to_string(),
+            kind,
+        })
+    };
+
+    let (old_types, new_types) = (types(old), types(new));
+    if old_types != new_types {
+        let accepts = |set: &Option<BTreeSet<String>>, ty: &str| match set {
+            None => true,
+            Some(set) => set.contains(ty) || (ty == "integer" && set.contains("number")),
+        };
+        let list = |set: &Option<BTreeSet<String>>| {
+            set.as_ref()
+                .map_or_else(Vec::new, |s| s.iter().cloned().collect())
+        };
+        let narrowed = match &old_types {
+            None => new_types.is_some(),
+            Some(old_set) => old_set.iter().any(|ty| !accepts(&new_types, ty)),
+        };
+        let (old_list, new_list) = (list(&old_types), list(&new_types));
+        push(if narrowed {
+            SchemaChangeKind::NarrowedType {
+                old: old_list,
+                new: new_list,
+            }
+        } else {
+            SchemaChangeKind::WidenedType {
+                old: old_list,
+                new: new_list,
+            }
+        });
+    }
+
+    if let Some(JsonValue::Array(new_enum)) = new.get("enum") {
+        match old.get("enum") {
+            Some(JsonValue::Array(old_enum)) => {
+                let removed: Vec<_> = old_enum
+                    .iter()
+                    .filter(|v| !new_enum.contains(v))
+                    .cloned()
+                    .collect();
+                if !removed.is_empty() {
+                    push(SchemaChangeKind::NarrowedEnum { removed });
+                }
+            }
+            // Introducing an enum restricts a previously open value.
+            _ => push(SchemaChangeKind::NarrowedEnum { removed: vec![] }),
+        }
+    }
+
+    let old_required = required(old);
+    let new_required = required(new);
+    let empty = Map::new();
+    let old_props = old
+        .get("properties")
+        .and_then(JsonValue::as_object)
+        .unwrap_or(&empty);
+    let new_props = new
+        .get("properties")
+        .and_then(JsonValue::as_object)
+        .unwrap_or(&empty);
+
+    let keys: BTreeSet<&String> = old_props.keys().chain(new_props.keys()).collect();
+    for key in keys {
+        let child = join_key(path, key);
+        let mut push = |kind| {
+            changes.push(SchemaChange {
+                path: child.clone(),
+                kind,
+            })
+        };
+        match (old_props.get(key), new_props.get(key)) {
+            (Some(_), None) => push(SchemaChangeKind::RemovedKey),
+            (None, Some(_)) => push(SchemaChangeKind::AddedKey),
+            _ => {}
+        }
+        match (old_required.contains(key), new_required.contains(key)) {
+            (false, true) => push(SchemaChangeKind::NewRequired),
+            (true, false) => push(SchemaChangeKind::NoLongerRequired),
+            _ => {}
+        }
+        if let (Some(old_child), Some(new_child)) = (old_props.get(key), new_props.get(key)) {
+            diff(old_child, new_child, &child, changes);
+        }
+    }
+
+    if let (Some(old_items), Some(new_items)) = (old.get("items"), new.get("items")) {
+        diff(old_items, new_items, &format!("{path}[*]"), changes);
+    }
+}
+
+/// The accepted types, `None` when unconstrained.
+pub(crate) fn types(schema: &JsonValue) -> Option<BTreeSet<String>> {
+    match schema.get("type")? {
+        JsonValue::String(ty) => Some(BTreeSet::from([ty.clone()])),
+        JsonValue::Array(tys) => Some(
+            tys.iter()
+                .filter_map(JsonValue::as_str)
+                .map(str::to_string)
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+pub(crate) fn required(schema: &JsonValue) -> BTreeSet<String> {
+    schema
+        .get("required")
+        .and_then(JsonValue::as_array)
+        .map(|keys| {
+            keys.iter()
+                .filter_map(JsonValue::as_str)
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_breaking_and_compatible_changes() {
+        let old = json!({
+            "type": "object",
+            "required": ["name"],
+            "properties": {
+                "name": { "type": "string" },
+                "port": { "type": ["integer", "string"] },
+                "legacy": { "type": "boolean" },
+                "level": { "enum": ["debug", "info", "warn"] },
+            },
+        });
+        let new = json!({
+            "type": "object",
+            "required": ["port"],
+            "properties": {
+                "name": { "type": ["string", "null"] },
+                "port": { "type": "integer" },
+                "level": { "enum": ["info", "warn"] },
+                "tags": { "type": "array" },
+            },
+        });
+
+        let changes = diff_schemas(&old, &new);
+        let summary: Vec<_> = changes
+            .iter()
+            .map(|c| (c.path.as_str(), c.is_breaking()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("legacy", true),
+                ("level", true),
+                ("name", false),
+                ("name", false),
+                ("port", true),
+                ("port", true),
+                ("tags", false),
+            ]
+        );
+        assert_eq!(
+            changes[4].kind,
+            SchemaChangeKind::NewRequired,
+            "required changes are reported before nested ones"
+        );
+    }
+
+    #[test]
+    fn integer_to_number_is_widening() {
+        let changes = diff_schemas(&json!({ "type": "integer" }), &json!({ "type": "number" }));
+        assert_eq!(changes.len(), 1);
+        assert!(!changes[0].is_breaking());
+    }
+
+    #[test]
+    fn diffs_inferred_schemas() {
+        let old = infer_schema(&json!({ "port": 80, "hosts": ["a"] }));
+        let new = infer_schema(&json!({ "port": "80", "hosts": [1] }));
+        let paths: Vec<_> = diff_schemas(&old, &new)
+            .into_iter()
+            .filter(SchemaChange::is_breaking)
+            .map(|c| c.path)
+            .collect();
+        assert_eq!(paths, ["hosts[*]", "port"]);
+    }
+}
@@ -0,0 +1,177 @@
+use std::error::Error;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::de::DeserializeOwned;
+
+use crate::error::parse_format;
+use crate::format::{Format, Formatted};
+use crate::minify::{Lexeme, lex};
+
+static JSON_NUMBER: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^-?(?:0|[1-9]\d*)(?:\.\d+)?(?:[eE][+-]?\d+)?$").unwrap());
+static SECTION_HEADER: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\[[^\[\],{}]+\]\s*$").unwrap());
+static KEY_VALUE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[^=:\s][^=:]*=").unwrap());
+
+/// Guesses the format of config `text` from its content, or `None` when
+/// it is empty or looks like none of them.
+///
+/// JSON-like text is JSON unless it uses comments (JSONC) or JSON5-only
+/// syntax such as single quotes, unquoted keys or trailing commas.
+/// `key = value` and `[section]` text is TOML when it parses as TOML and
+/// INI otherwise; `key: value`, `- item` and `---` are YAML.
+pub fn sniff_format(text: &str) -> Option<Format> {
+    let trimmed = text.trim_start_matches('\u{feff}').trim_start();
+    if trimmed.starts_with('{') || trimmed.starts_with("//") || trimmed.starts_with("/*") {
+        return Some(sniff_json_family(trimmed));
+    }
+    if trimmed.starts_with('[') && !SECTION_HEADER.is_match(trimmed.lines().next()?) {
+        return Some(sniff_json_family(trimmed));
+    }
+
+    let first = trimmed
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))?;
+    if first.starts_with(';') {
+        return Some(Format::Ini);
+    }
+    if first == "---" || first.starts_with("%YAML") || first.starts_with("- ") || first == "-" {
+        return Some(Format::Yaml);
+    }
+    if SECTION_HEADER.is_match(first) || KEY_VALUE.is_match(first) {
+        return Some(match toml::from_str::<toml::Table>(trimmed) {
+            Ok(_) => Format::Toml,
+            Err(_) => Format::Ini,
+        });
+    }
+    if first.contains(':') {
+        return Some(Format::Yaml);
+    }
+    None
+}
+
+fn sniff_json_family(text: &str) -> Format {
+    let Ok(lexemes) = lex(text) else {
+        return Format::Json5;
+    };
+    let mut format = Format::Json;
+    let mut previous: Option<&Lexeme> = None;
+    let mut json5 = false;
+    for (offset, lexeme) in &lexemes {
+        if lexeme.is_comment() {
+            format = Format::Jsonc;
+            continue;
+        }
+        json5 |= match lexeme {
+            Lexeme::Str(_) => text[*offset..].starts_with('\''),
+            Lexeme::Atom(raw) => {
+                !matches!(*raw, "true" | "false" | "null") && !JSON_NUMBER.is_match(raw)
+            }
+            Lexeme::Punct('}' | ']') => matches!(previous, Some(Lexeme::Punct(','))),
+            _ => false,
+        };
+        previous = Some(lexeme);
+    }
+    if json5 { Format::Json5 } else { format }
+}
+
+/// Parses `text` as `format`, or as the format [`sniff_format`] detects
+/// when `format` is `None`, so one call handles any supported config.
+///
+/// INI values are read as typed scalars (see
+/// [`parse_ini_typed`](crate::parse_ini_typed)) so they deserialize into
+/// numeric and boolean fields. Parse failures are
+/// [`C12Error`](crate::C12Error)s.
+///
+/// ```
+/// use c12_parser::parse_auto;
+/// use serde_json::{Value, json};
+///
+/// let toml = parse_auto::<Value>("port = 8080\n", None)?;
+/// let yaml = parse_auto::<Value>("port: 8080\n", None)?;
+/// assert_eq!(toml.value, json!({ "port": 8080 }));
+/// assert_eq!(yaml.value, toml.value);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn parse_auto<T>(text: &str, format: Option<Format>) -> Result<Formatted<T>, Box<dyn Error>>
+where
+    T: DeserializeOwned,
+{
+    let format = format
+        .or_else(|| sniff_format(text))
+        .ok_or("cannot detect the config format")?;
+    Ok(parse_format(text, format, None)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[test]
+    fn sniffs_each_format() {
+        let cases = [
+            (r#"{ "a": [1, 2.5, true, null] }"#, Some(Format::Json)),
+            ("[1, 2]", Some(Format::Json)),
+            ("// settings\n{ \"a\": 1 }", Some(Format::Jsonc)),
+            ("{ a: 1 }", Some(Format::Json5)),
+            ("{ \"a\": 'x' }", Some(Format::Json5)),
+            ("{ \"a\": [1, 2,] }", Some(Format::Json5)),
+            ("{ \"a\": Infinity }", Some(Format::Json5)),
+            ("# app\nname = \"demo\"\n", Some(Format::Toml)),
+            ("[server]\nport = 8080\n", Some(Format::Toml)),
+            ("[server]\nhost = localhost\n", Some(Format::Ini)),
+            ("; app\nname = demo\n", Some(Format::Ini)),
+            ("name: demo\n", Some(Format::Yaml)),
+            ("url: http://x/?a=b\nport: 1\n", Some(Format::Yaml)),
+            ("name: a=b\n", Some(Format::Yaml)),
+            ("---\n- a\n", Some(Format::Yaml)),
+            ("  \n", None),
+            ("just words", None),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(sniff_format(text), expected, "{text:?}");
+        }
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Server {
+        port: u16,
+        debug: bool,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Config {
+        server: Server,
+    }
+
+    #[test]
+    fn parse_auto_deserializes_every_format() {
+        let expected = Config {
+            server: Server {
+                port: 8080,
+                debug: true,
+            },
+        };
+        let texts = [
+            r#"{ "server": { "port": 8080, "debug": true } }"#,
+            "/* c */ { \"server\": { \"port\": 8080, \"debug\": true } }",
+            "{ server: { port: 8080, debug: true, }, }",
+            "[server]\nport = 8080\ndebug = true\n",
+            "server:\n  port: 8080\n  debug: true\n",
+            "; c\n[server]\nport = 8080\ndebug = true\n",
+        ];
+        for text in texts {
+            let parsed = parse_auto::<Config>(text, None).unwrap();
+            assert_eq!(parsed.value, expected, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn explicit_format_skips_sniffing() {
+        let parsed = parse_auto::<serde_json::Value>("a = 1\n", Some(Format::Ini)).unwrap();
+        assert_eq!(parsed.value, serde_json::json!({ "default": { "a": 1 } }));
+        assert!(parse_auto::<serde_json::Value>("", None).is_err());
+    }
+}
@@ -0,0 +1,301 @@
+use std::error::Error;
+
+use serde_json::{Map, Value as JsonValue};
+
+use crate::document::{Document, parse_document};
+use crate::format::Format;
+use crate::path::{Path, PathSegment, parse_path};
+
+/// The key listing the files a config extends, as in c12.
+pub(crate) const EXTENDS: &str = "extends";
+
+/// A config file split by [`split`] into a main file and parts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SplitConfig {
+    /// The original file without the extracted subtrees, extending the
+    /// parts.
+    pub main: String,
+    pub parts: Vec<SplitPart>,
+}
+
+/// A subtree extracted into its own file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SplitPart {
+    /// The file name, as written in the main file's `extends`.
+    pub file: String,
+    /// Where the subtree sits, in both files.
+    pub path: Path,
+    /// The part file: the subtree nested under its path, so extending it
+    /// puts it back in place.
+    pub text: String,
+}
+
+/// Extracts subtrees of a config into separate files of the same format,
+/// for breaking up a monolithic config.
+///
+/// `parts` pairs [path expressions](crate::parse_path) of object members
+/// with file names. Each part file keeps only its subtree and the keys
+/// leading to it, cut out of the original with minimal edits, so the
+/// subtree's comments and layout move with it; comments on lines of
+/// their own stay in both files, as removing a key leaves the comments
+/// above it in place. The main file loses the subtrees, and any object
+/// emptied by that, and lists the files in its `extends` key, after files
+/// it already extends. INI files cannot be split.
+///
+/// ```
+/// use c12_parser::{Format, join, split};
+///
+/// let text = "name: app\ndatabase: # primary\n  host: db.local # internal\n  port: 5432\n";
+/// let config = split(text, Format::Yaml, &[("database", "./database.yaml")])?;
+/// assert_eq!(config.main, "name: app\nextends: [./database.yaml]\n");
+/// assert_eq!(
+///     config.parts[0].text,
+///     "database: # primary\n  host: db.local # internal\n  port: 5432\n"
+/// );
+/// assert_eq!(join(&config, Format::Yaml)?, "name: app\ndatabase: {host: db.local, port: 5432}\n");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn split(
+    text: &str,
+    format: Format,
+    parts: &[(&str, &str)],
+) -> Result<SplitConfig, Box<dyn Error>> {
+    let source = parse_document(text, format)?;
+    let mut targets = Vec::with_capacity(parts.len());
+    for (expression, file) in parts {
+        let path = parse_path(expression)?;
+        if path.is_root()
+            || path
+                .segments()
+                .iter()
+                .any(|s| matches!(s, PathSegment::Index(_)))
+        {
+            return Err(
+                format!("`{path}` is not an object member, only those can be split out").into(),
+            );
+        }
+        if path.segments()[0] == PathSegment::Key(EXTENDS.into()) {
+            return Err(format!("`{EXTENDS}` cannot be split out").into());
+        }
+        if source.get(&path).is_none() {
+            return Err(format!("`{path}` is not in the config").into());
+        }
+        if let Some((other, _)) = targets.iter().find(|(other, _): &&(Path, &str)| {
+            let (a, b) = (other.segments(), path.segments());
+            a.starts_with(b) || b.starts_with(a)
+        }) {
+            return Err(format!("`{path}` overlaps `{other}`").into());
+        }
+        targets.push((path, *file));
+    }
+
+    let mut main = source.clone();
+    let mut split_parts = Vec::with_capacity(targets.len());
+    for (path, file) in targets {
+        let mut part = source.clone();
+        keep_only(&mut part, &path);
+        main.remove(&path);
+        let mut emptied = path.parent();
+        while let Some(parent) = emptied.filter(|p| !p.is_root()) {
+            if main
+                .get(&parent)
+                .is_none_or(|v| v.as_object().is_none_or(Map::is_empty))
+            {
+                main.remove(&parent);
+                emptied = parent.parent();
+            } else {
+                break;
+            }
+        }
+        split_parts.push(SplitPart {
+            file: file.to_string(),
+            path,
+            text: part.to_string(),
+        });
+    }
+
+    let extends = Path::root().key(EXTENDS);
+    let mut files = match main.get(&extends) {
+        None => Vec::new(),
+        Some(JsonValue::String(file)) => vec![JsonValue::String(file)],
+        Some(JsonValue::Array(files)) => files,
+        Some(_) => return Err(format!("`{EXTENDS}` must be a string or an array").into()),
+    };
+    files.extend(
+        split_parts
+            .iter()
+            .map(|part| JsonValue::String(part.file.clone())),
+    );
+    main.set(&extends, JsonValue::Array(files))?;
+
+    Ok(SplitConfig {
+        main: main.to_string(),
+        parts: split_parts,
+    })
+}
+
+/// Puts the parts of a [`SplitConfig`] back into its main file and drops
+/// them from its `extends`, undoing [`split`].
+///
+/// Subtrees go back into the main file as values, added at the end of
+/// the object holding them and written in the main file's style; their
+/// comments stay behind in the part files. Where the main file already
+/// has a value it is kept, as it would override the extended file. A
+/// single file left in `extends` is written as a string and an empty
+/// `extends` is removed. Fails when a part is not listed in `extends`.
+pub fn join(config: &SplitConfig, format: Format) -> Result<String, Box<dyn Error>> {
+    let mut main = parse_document(&config.main, format)?;
+    let extends = Path::root().key(EXTENDS);
+    let mut files = match main.get(&extends) {
+        None => Vec::new(),
+        Some(JsonValue::String(file)) => vec![JsonValue::String(file)],
+        Some(JsonValue::Array(files)) => files,
+        Some(_) => return Err(format!("`{EXTENDS}` must be a string or an array").into()),
+    };
+
+    for part in &config.parts {
+        let Some(position) = files.iter().position(|f| f.as_str() == Some(&part.file)) else {
+            return Err(format!("`{}` is not in `{EXTENDS}`", part.file).into());
+        };
+        files.remove(position);
+        let mut value = parse_document(&part.text, format)?.to_value();
+        if let Some(map) = value.as_object_mut() {
+            map.remove(EXTENDS);
+            graft(&mut main, &Path::root(), map)?;
+        }
+    }
+
+    match files.len() {
+        0 => {
+            main.remove(&extends);
+        }
+        1 => main.set(&extends, files.remove(0))?,
+        _ => main.set(&extends, JsonValue::Array(files))?,
+    }
+    Ok(main.to_string())
+}
+
+/// Removes everything from `doc` that is not on `path` or under it.
+fn keep_only(doc: &mut Document, path: &Path) {
+    let mut ancestor = Path::root();
+    for segment in path.segments() {
+        let PathSegment::Key(keep) = segment else {
+            return;
+        };
+        let siblings: Vec<String> = doc
+            .get(&ancestor)
+            .and_then(|value| value.as_object().map(|map| map.keys().cloned().collect()))
+            .unwrap_or_default();
+        for key in siblings.into_iter().filter(|key| key != keep) {
+            doc.remove(&ancestor.clone().key(key));
+        }
+        ancestor = ancestor.key(keep.as_str());
+    }
+}
+
+/// Adds the members of `map` missing from the object at `path`, merging
+/// into objects both have.
+fn graft(
+    doc: &mut Document,
+    path: &Path,
+    map: &Map<String, JsonValue>,
+) -> Result<(), Box<dyn Error>> {
+    for (key, value) in map {
+        let child = path.clone().key(key.as_str());
+        match (doc.get(&child), value) {
+            (None, _) => doc.set(&child, value.clone())?,
+            (Some(JsonValue::Object(_)), JsonValue::Object(inner)) => graft(doc, &child, inner)?,
+            (Some(_), _) => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const CONFIG: &str = r#"{
+  // Service settings
+  "name": "api",
+  "extends": "./base.jsonc",
+  "server": {
+    "http": {
+      /* public listener */
+      "port": 8080
+    },
+    "workers": 4
+  },
+  "logging": { "level": "info" }
+}
+"#;
+
+    #[test]
+    fn splits_nested_members_and_joins_them_back() {
+        let config = split(
+            CONFIG,
+            Format::Jsonc,
+            &[
+                ("server.http", "./http.jsonc"),
+                ("logging", "./logging.jsonc"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            config.parts[0].text,
+            "{\n  // Service settings\n  \"server\": {\n    \"http\": {\n      /* public listener */\n      \"port\": 8080\n    }\n  }\n}\n"
+        );
+        let main = parse_document(&config.main, Format::Jsonc)
+            .unwrap()
+            .to_value();
+        assert_eq!(
+            main,
+            json!({
+                "name": "api",
+                "extends": ["./base.jsonc", "./http.jsonc", "./logging.jsonc"],
+                "server": { "workers": 4 }
+            })
+        );
+        assert!(config.main.contains("// Service settings"));
+
+        let joined = join(&config, Format::Jsonc).unwrap();
+        let original = parse_document(CONFIG, Format::Jsonc).unwrap().to_value();
+        assert_eq!(
+            parse_document(&joined, Format::Jsonc).unwrap().to_value(),
+            original
+        );
+    }
+
+    #[test]
+    fn rejects_paths_that_cannot_be_split() {
+        let toml = "[a]\nb = [1, 2]\n[c]\nd = 1\n";
+        let message =
+            |parts: &[(&str, &str)]| split(toml, Format::Toml, parts).unwrap_err().to_string();
+        assert_eq!(
+            message(&[("a.b[0]", "x.toml")]),
+            "`a.b[0]` is not an object member, only those can be split out"
+        );
+        assert_eq!(
+            message(&[("missing", "x.toml")]),
+            "`missing` is not in the config"
+        );
+        assert_eq!(
+            message(&[("a", "x.toml"), ("a.b", "y.toml")]),
+            "`a.b` overlaps `a`"
+        );
+        assert!(split("a = 1\n", Format::Ini, &[("a", "x.ini")]).is_err());
+
+        let config = split(toml, Format::Toml, &[("c", "c.toml")]).unwrap();
+        assert_eq!(config.main, "extends = [\"c.toml\"]\n[a]\nb = [1, 2]\n");
+        assert_eq!(config.parts[0].text, "[c]\nd = 1\n");
+        let orphan = SplitConfig {
+            main: "[a]\nb = 1\n".into(),
+            parts: config.parts,
+        };
+        assert_eq!(
+            join(&orphan, Format::Toml).unwrap_err().to_string(),
+            "`c.toml` is not in `extends`"
+        );
+    }
+}
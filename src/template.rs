@@ -0,0 +1,63 @@
+use minijinja::{Environment, UndefinedBehavior};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+use crate::hooks::HookResult;
+
+/// Renders `text` as a minijinja template with the variables in
+/// `context`. Undefined variables are an error rather than an empty string.
+///
+/// Values are inserted verbatim: nothing is escaped for the target format.
+/// A value containing `:` or `#` can break YAML, and a quote can break a
+/// JSON or TOML string. Pipe anything that is not a trusted plain word
+/// through `tojson`, whose output is a valid JSON, JSON5, YAML flow and
+/// TOML basic string:
+///
+/// ```yaml
+/// name: {{ name | tojson }}
+/// ```
+pub fn render_template(text: &str, context: &impl Serialize) -> Result<String, minijinja::Error> {
+    let mut env = Environment::new();
+    env.set_undefined_behavior(UndefinedBehavior::Strict);
+    // Config files usually end with a newline that must survive rendering.
+    env.set_keep_trailing_newline(true);
+    env.render_str(text, context)
+}
+
+/// A `pre_parse` hook for [`C12Context`](crate::C12Context) rendering the
+/// text with `context`.
+///
+/// ```
+/// use c12_parser::{C12Context, parse_yaml, template_hook};
+///
+/// let ctx = C12Context::new().pre_parse(template_hook(serde_json::json!({ "env": "prod" })));
+/// let config = ctx.parse_with("env: {{ env | tojson }}\n", |text| parse_yaml(text, None))?;
+/// assert_eq!(config.value["env"], "prod");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn template_hook(
+    context: JsonValue,
+) -> impl Fn(&str) -> HookResult<String> + Send + Sync + 'static {
+    move |text| Ok(render_template(text, &context)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn renders_and_keeps_trailing_newline() {
+        let out = render_template(
+            "port: {{ port }}\nname: {{ name | tojson }}\n",
+            &json!({ "port": 8080, "name": "a: b" }),
+        )
+        .unwrap();
+        assert_eq!(out, "port: 8080\nname: \"a: b\"\n");
+    }
+
+    #[test]
+    fn undefined_variables_are_errors() {
+        assert!(render_template("port: {{ missing }}", &json!({})).is_err());
+    }
+}
@@ -0,0 +1,400 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde_json::Value as JsonValue;
+use toml_edit::{Array, DocumentMut, InlineTable, Item, Table, Value};
+
+use crate::path::{Path, PathSegment};
+
+/// A TOML document that keeps comments, key order, whitespace and table
+/// layout through edits, for programmatic changes to hand-written files
+/// such as `Cargo.toml`.
+///
+/// Unlike [`parse_toml`](crate::parse_toml), nothing is normalized:
+/// `to_string()` on an unedited document gives back the input exactly.
+///
+/// ```
+/// use c12_parser::{Path, parse_toml_document};
+///
+/// let text = "[package]\nname = \"demo\" # crate name\nversion = \"0.1.0\"\n";
+/// let mut doc = parse_toml_document(text)?;
+/// doc.set(&Path::root().key("package").key("version"), "0.2.0".into())?;
+/// assert_eq!(
+///     doc.to_string(),
+///     "[package]\nname = \"demo\" # crate name\nversion = \"0.2.0\"\n"
+/// );
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TomlDocument {
+    doc: DocumentMut,
+}
+
+/// Parses TOML text into an editable [`TomlDocument`].
+pub fn parse_toml_document(text: &str) -> Result<TomlDocument, toml_edit::TomlError> {
+    text.parse()
+}
+
+/// Returned when a [`TomlDocument`] edit cannot be applied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TomlEditError {
+    pub path: Path,
+    pub message: String,
+}
+
+impl fmt::Display for TomlEditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot edit `{}`: {}", self.path, self.message)
+    }
+}
+
+impl std::error::Error for TomlEditError {}
+
+impl FromStr for TomlDocument {
+    type Err = toml_edit::TomlError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Ok(Self { doc: text.parse()? })
+    }
+}
+
+impl fmt::Display for TomlDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.doc.fmt(f)
+    }
+}
+
+impl TomlDocument {
+    /// The whole document in the shared value model.
+    pub fn to_value(&self) -> JsonValue {
+        // The document was valid TOML when parsed and edits only insert
+        // valid values, so re-reading it cannot fail.
+        toml::from_str(&self.doc.to_string()).unwrap_or(JsonValue::Null)
+    }
+
+    /// The value at `path`, if present.
+    pub fn get(&self, path: &Path) -> Option<JsonValue> {
+        path.lookup(&self.to_value()).cloned()
+    }
+
+    /// Sets the value at `path`, creating missing tables on the way. A
+    /// replaced value keeps its surrounding comments and whitespace. An
+    /// array index may point one past the end to append.
+    pub fn set(&mut self, path: &Path, value: JsonValue) -> Result<(), TomlEditError> {
+        let error = |message: String| TomlEditError {
+            path: path.clone(),
+            message,
+        };
+        let (last, parents) = path
+            .segments()
+            .split_last()
+            .ok_or_else(|| error("the document root cannot be replaced".into()))?;
+        let value = to_toml_value(&value).map_err(error)?;
+        let mut item = self.doc.as_item_mut();
+        for segment in parents {
+            item = child_mut(item, segment, true).map_err(error)?;
+        }
+        match last {
+            PathSegment::Key(key) => {
+                let inline = item.is_inline_table();
+                let table = item
+                    .as_table_like_mut()
+                    .ok_or_else(|| error("parent is not a table".into()))?;
+                match table.get_mut(key) {
+                    Some(slot) => assign(slot, value),
+                    None if inline => {
+                        table.insert(key, Item::Value(value));
+                    }
+                    None => {
+                        let mut slot = Item::None;
+                        assign(&mut slot, value);
+                        table.insert(key, slot);
+                    }
+                }
+            }
+            PathSegment::Index(index) => {
+                if let Some(array) = item.as_array_mut()
+                    && *index == array.len()
+                {
+                    array.push(value);
+                    return Ok(());
+                }
+                let slot = item
+                    .get_mut(*index)
+                    .ok_or_else(|| error(format!("index {index} is out of bounds")))?;
+                assign(slot, value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Inserts `key` into the table holding `after`, directly after it.
+    /// Objects become their own `[table]`, which TOML places after the
+    /// plain values wherever the key goes.
+    pub fn insert_after(
+        &mut self,
+        after: &Path,
+        key: &str,
+        value: JsonValue,
+    ) -> Result<(), TomlEditError> {
+        let error = |message: String| TomlEditError {
+            path: after.clone(),
+            message,
+        };
+        let Some((PathSegment::Key(after_key), parents)) = after.segments().split_last() else {
+            return Err(error("not a table key".into()));
+        };
+        let value = to_toml_value(&value).map_err(error)?;
+        let mut item = self.doc.as_item_mut();
+        for segment in parents {
+            item = child_mut(item, segment, false).map_err(error)?;
+        }
+        let table = item
+            .as_table_like_mut()
+            .ok_or_else(|| error("parent is not a table".into()))?;
+        if !table.contains_key(after_key) {
+            return Err(error(format!("`{after_key}` does not exist")));
+        }
+        if table.contains_key(key) {
+            return Err(error(format!("`{key}` already exists")));
+        }
+        let mut order: Vec<String> = table.iter().map(|(k, _)| k.to_string()).collect();
+        let at = order
+            .iter()
+            .position(|k| k == after_key)
+            .map_or(0, |i| i + 1);
+        order.insert(at, key.to_string());
+        let rank = |k: &str| order.iter().position(|o| o == k).unwrap_or(usize::MAX);
+        match item {
+            Item::Table(table) => {
+                let mut slot = Item::None;
+                assign(&mut slot, value);
+                table.insert(key, slot);
+                table.sort_values_by(|a, _, b, _| rank(a).cmp(&rank(b)));
+            }
+            Item::Value(Value::InlineTable(table)) => {
+                table.insert(key, value);
+                table.sort_values_by(|a, _, b, _| rank(a).cmp(&rank(b)));
+            }
+            _ => unreachable!("checked to be a table above"),
+        }
+        Ok(())
+    }
+
+    /// Removes the value at `path`, returning it.
+    pub fn remove(&mut self, path: &Path) -> Option<JsonValue> {
+        let (last, parents) = path.segments().split_last()?;
+        let old = self.get(path)?;
+        let mut item = self.doc.as_item_mut();
+        for segment in parents {
+            item = child_mut(item, segment, false).ok()?;
+        }
+        match last {
+            PathSegment::Key(key) => item.as_table_like_mut()?.remove(key)?,
+            PathSegment::Index(index) => match item {
+                Item::Value(Value::Array(array)) => Item::Value(array.remove(*index)),
+                Item::ArrayOfTables(tables) => Item::Table(tables.remove(*index)),
+                _ => return None,
+            },
+        };
+        Some(old)
+    }
+
+    /// Rewrites the integer at `path` through `edit`, which gets and
+    /// returns its source text, such as `0x1F` or `1_000`.
+    pub(crate) fn rewrite_number(
+        &mut self,
+        path: &Path,
+        edit: impl FnOnce(&str) -> Result<String, String>,
+    ) -> Result<(), TomlEditError> {
+        let error = |message: String| TomlEditError {
+            path: path.clone(),
+            message,
+        };
+        let mut item = self.doc.as_item_mut();
+        for segment in path.segments() {
+            item = child_mut(item, segment, false).map_err(error)?;
+        }
+        let Some(Value::Integer(old)) = item.as_value() else {
+            return Err(error("not an integer".into()));
+        };
+        let raw = edit(&old.display_repr()).map_err(error)?;
+        let new = raw
+            .parse::<Value>()
+            .map_err(|e| error(format!("`{raw}` is not a TOML value: {e}")))?;
+        assign(item, new);
+        Ok(())
+    }
+
+    pub fn as_document(&self) -> &DocumentMut {
+        &self.doc
+    }
+
+    /// The underlying `toml_edit` document, for edits this API does not
+    /// cover, such as rewriting comments.
+    pub fn as_document_mut(&mut self) -> &mut DocumentMut {
+        &mut self.doc
+    }
+}
+
+fn child_mut<'a>(
+    item: &'a mut Item,
+    segment: &PathSegment,
+    create: bool,
+) -> Result<&'a mut Item, String> {
+    match segment {
+        PathSegment::Key(key) => {
+            let inline = item.is_inline_table();
+            let table = item
+                .as_table_like_mut()
+                .ok_or_else(|| format!("`{key}` is not inside a table"))?;
+            if create && table.get(key).is_none() {
+                let child = if inline {
+                    Item::Value(Value::InlineTable(InlineTable::new()))
+                } else {
+                    let mut table = Table::new();
+                    table.set_implicit(true);
+                    Item::Table(table)
+                };
+                table.insert(key, child);
+            }
+            table
+                .get_mut(key)
+                .ok_or_else(|| format!("`{key}` does not exist"))
+        }
+        PathSegment::Index(index) => item
+            .get_mut(*index)
+            .ok_or_else(|| format!("index {index} does not exist")),
+    }
+}
+
+/// Stores `value` in `slot`, keeping the decoration of a replaced value.
+/// Objects stored where a table or nothing was become standard tables.
+fn assign(slot: &mut Item, value: Value) {
+    *slot = match (std::mem::take(slot), value) {
+        (Item::Value(old), mut new) => {
+            *new.decor_mut() = old.decor().clone();
+            Item::Value(new)
+        }
+        (Item::Table(_) | Item::None, Value::InlineTable(table)) => Item::Table(table.into_table()),
+        (_, new) => Item::Value(new),
+    };
+}
+
+fn to_toml_value(value: &JsonValue) -> Result<Value, String> {
+    Ok(match value {
+        JsonValue::Null => return Err("TOML has no null".into()),
+        JsonValue::Bool(b) => (*b).into(),
+        JsonValue::Number(n) => match n.as_i64() {
+            Some(i) => i.into(),
+            None => n.as_f64().unwrap_or(f64::NAN).into(),
+        },
+        JsonValue::String(s) => s.as_str().into(),
+        JsonValue::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(to_toml_value)
+                .collect::<Result<Array, _>>()?,
+        ),
+        JsonValue::Object(map) => {
+            let mut table = InlineTable::new();
+            for (key, value) in map {
+                table.insert(key, to_toml_value(value)?);
+            }
+            Value::InlineTable(table)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const MANIFEST: &str = "\
+# The package
+[package]
+name = \"demo\"   # keep me
+version = \"0.1.0\"
+
+[dependencies]
+serde = { version = \"1\", features = [\"derive\"] }
+";
+
+    #[test]
+    fn untouched_documents_round_trip_exactly() {
+        let doc = parse_toml_document(MANIFEST).unwrap();
+        assert_eq!(doc.to_string(), MANIFEST);
+        assert_eq!(
+            doc.get(&"dependencies.serde.features[0]".parse().unwrap()),
+            Some(json!("derive"))
+        );
+    }
+
+    #[test]
+    fn edits_keep_comments_and_layout() {
+        let mut doc = parse_toml_document(MANIFEST).unwrap();
+        let name = Path::root().key("package").key("name");
+        doc.set(&name, json!("renamed")).unwrap();
+        doc.set(
+            &"dependencies.serde.features[1]".parse().unwrap(),
+            json!("rc"),
+        )
+        .unwrap();
+        doc.set(&"dependencies.regex".parse().unwrap(), json!("1.10"))
+            .unwrap();
+        doc.set(&"profile.release.lto".parse().unwrap(), json!(true))
+            .unwrap();
+        doc.insert_after(&name, "edition", json!("2024")).unwrap();
+        doc.insert_after(
+            &"dependencies.serde.version".parse().unwrap(),
+            "default-features",
+            json!(false),
+        )
+        .unwrap();
+        assert_eq!(
+            doc.remove(&"package.version".parse().unwrap()),
+            Some(json!("0.1.0"))
+        );
+        assert_eq!(
+            doc.to_string(),
+            "\
+# The package
+[package]
+name = \"renamed\"   # keep me
+edition = \"2024\"
+
+[dependencies]
+serde = { version = \"1\", default-features = false, features = [\"derive\", \"rc\"] }
+regex = \"1.10\"
+
+[profile.release]
+lto = true
+"
+        );
+    }
+
+    #[test]
+    fn rejects_impossible_edits() {
+        let mut doc = parse_toml_document(MANIFEST).unwrap();
+        let err = doc
+            .set(&"package.name".parse().unwrap(), JsonValue::Null)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot edit `package.name`: TOML has no null"
+        );
+        assert!(doc.set(&Path::root(), json!({})).is_err());
+        assert!(
+            doc.set(&"package.name.first".parse().unwrap(), json!(1))
+                .is_err()
+        );
+        let name = "package.name".parse().unwrap();
+        assert!(doc.insert_after(&name, "version", json!("1")).is_err());
+        assert!(
+            doc.insert_after(&"package.nope".parse().unwrap(), "x", json!(1))
+                .is_err()
+        );
+        assert_eq!(doc.to_string(), MANIFEST);
+    }
+}
@@ -1,40 +1,239 @@
-use serde::{Serialize, de::DeserializeOwned};
+use serde::Serialize;
+use serde::de::{DeserializeOwned, DeserializeSeed};
 
-use crate::format::{FormatOptions, Formatted};
+use crate::emit_toml::to_toml_string;
+use crate::empty::{empty_value, is_empty_document};
+use crate::format::{Format, FormatOptions, Formatted, IndentStyle, StyleProfile, indent_style};
+use crate::interpolate::prepared;
+use crate::limits::{Limited, parse_limited};
+use crate::loss::{DataLoss, find_toml_nulls};
+use crate::mixed_arrays::{MixedArrayStrategy, normalize_mixed_arrays};
+use crate::roundtrip::guard;
 
 /// Parses a TOML string into a value, capturing outer whitespace only.
 pub fn parse_toml<T>(
     text: &str,
     options: Option<FormatOptions>,
 ) -> Result<Formatted<T>, toml::de::Error>
+where
+    T: DeserializeOwned,
+{
+    parse_toml_limited(text, options).map_err(Limited::into_backend)
+}
+
+/// Like [`parse_toml`], keeping a [`LimitExceeded`](crate::LimitExceeded)
+/// typed.
+pub(crate) fn parse_toml_limited<T>(
+    text: &str,
+    options: Option<FormatOptions>,
+) -> Result<Formatted<T>, Limited<toml::de::Error>>
 where
     T: DeserializeOwned,
 {
     let mut opts = options.unwrap_or_default();
     // Match JS version: comments/indentation are not preserved, but whitespace is.
     opts.preserve_indentation = false;
-    let value = toml::from_str(text)?;
+    let value = if is_empty_document(text, Format::Toml) {
+        empty_value::<_, toml::de::Error>(opts.on_empty)?
+    } else if opts.parses_directly() {
+        toml::from_str(text)?
+    } else {
+        let value = parse_limited(&opts.limits, |seed| {
+            seed.deserialize(toml::de::Deserializer::parse(text)?)
+        })?;
+        prepared::<_, toml::de::Error>(value, &opts)?
+    };
     Ok(Formatted::new(text, value, &opts))
 }
 
 /// Stringifies a TOML value with preserved outer whitespace.
 pub fn stringify_toml<T>(
     formatted: &Formatted<T>,
-    _options: Option<FormatOptions>,
+    options: Option<FormatOptions>,
 ) -> Result<String, toml::ser::Error>
 where
     T: Serialize,
 {
-    let toml_str = toml::to_string(&formatted.value)?;
-    Ok(format!(
+    let opts = options.unwrap_or_default();
+    let confbox = opts.profile == StyleProfile::Confbox;
+    if opts.fail_on_loss {
+        let value = serde_json::to_value(&formatted.value)
+            .map_err(<toml::ser::Error as serde::ser::Error>::custom)?;
+        DataLoss::check(find_toml_nulls(&value))
+            .map_err(<toml::ser::Error as serde::ser::Error>::custom)?;
+    }
+    // Mixed-array normalization rewrites the value on purpose, so the
+    // round-trip check compares against the rewritten one.
+    let mut normalized = None;
+    let mut toml_str = if opts.mixed_arrays == MixedArrayStrategy::Keep && !confbox {
+        toml::to_string(&formatted.value)?
+    } else {
+        let mut value = toml::Value::try_from(&formatted.value)?;
+        normalize_mixed_arrays(&mut value, opts.mixed_arrays)
+            .map_err(<toml::ser::Error as serde::ser::Error>::custom)?;
+        let toml_str = match &value {
+            toml::Value::Table(table) if confbox => to_toml_string(table),
+            _ => toml::to_string(&value)?,
+        };
+        normalized = Some(value);
+        toml_str
+    };
+    if opts.escaping.prefer_literal_strings {
+        toml_str = prefer_literal_strings(&toml_str);
+    }
+    if let Some(width) = opts.line_width {
+        let indent = match indent_style(&formatted.format, &opts) {
+            IndentStyle::Tabs => "\t".to_string(),
+            IndentStyle::Spaces => " ".repeat(opts.indent.unwrap_or(2)),
+        };
+        toml_str = wrap_arrays(&toml_str, width, &indent);
+    }
+
+    let out = format!(
         "{}{}{}",
         formatted.format.whitespace_start, toml_str, formatted.format.whitespace_end
-    ))
+    );
+    if opts.verify_round_trip {
+        let reparse =
+            |text: &str| toml::from_str::<serde_json::Value>(text).map_err(|e| e.to_string());
+        match &normalized {
+            Some(value) => guard::<_, toml::ser::Error>(value, &out, reparse)?,
+            None => guard::<_, toml::ser::Error>(&formatted.value, &out, reparse)?,
+        }
+    }
+    Ok(out)
+}
+
+/// Puts the elements of each array whose `key = [...]` line would reach
+/// `width` on lines of their own. Nested arrays stay inline, and inline
+/// tables cannot span lines in TOML 1.0.
+fn wrap_arrays(toml: &str, width: usize, indent: &str) -> String {
+    match toml.parse::<toml_edit::DocumentMut>() {
+        Ok(mut doc) => {
+            wrap_table_arrays(doc.as_table_mut(), width, indent);
+            doc.to_string()
+        }
+        Err(_) => toml.to_string(),
+    }
+}
+
+fn wrap_table_arrays(table: &mut toml_edit::Table, width: usize, indent: &str) {
+    for (key, item) in table.iter_mut() {
+        match item {
+            toml_edit::Item::Value(toml_edit::Value::Array(array)) => {
+                let line = format!("{} = {}", key.display_repr(), array.to_string().trim());
+                if array.is_empty() || line.chars().count() < width {
+                    continue;
+                }
+                for value in array.iter_mut() {
+                    value.decor_mut().set_prefix(format!("\n{indent}"));
+                    value.decor_mut().set_suffix("");
+                }
+                array.set_trailing_comma(true);
+                array.set_trailing("\n");
+            }
+            toml_edit::Item::Table(table) => wrap_table_arrays(table, width, indent),
+            toml_edit::Item::ArrayOfTables(tables) => {
+                for table in tables.iter_mut() {
+                    wrap_table_arrays(table, width, indent);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Rewrites single-line basic strings (`"..."`) as literal strings
+/// (`'...'`) wherever the decoded content can be written literally, i.e.
+/// it contains no `'` and no control characters other than tab.
+fn prefer_literal_strings(toml: &str) -> String {
+    let mut out = String::with_capacity(toml.len());
+    let mut rest = toml;
+
+    while let Some(pos) = rest.find(['"', '\'', '#']) {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+
+        let end = if rest.starts_with("\"\"\"") || rest.starts_with("'''") {
+            // Multi-line strings are copied verbatim.
+            rest[3..].find(&rest[..3]).map_or(rest.len(), |i| i + 6)
+        } else if let Some(literal) = rest.strip_prefix('\'') {
+            literal.find('\'').map_or(rest.len(), |i| i + 2)
+        } else if rest.starts_with('#') {
+            rest.find('\n').unwrap_or(rest.len())
+        } else {
+            let end = basic_string_end(rest);
+            match decode_basic(&rest[1..end - 1]) {
+                Some(decoded)
+                    if !decoded.contains('\'')
+                        && !decoded.chars().any(|c| c.is_control() && c != '\t') =>
+                {
+                    out.push('\'');
+                    out.push_str(&decoded);
+                    out.push('\'');
+                    rest = &rest[end..];
+                    continue;
+                }
+                _ => end,
+            }
+        };
+
+        out.push_str(&rest[..end]);
+        rest = &rest[end..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Returns the byte offset just past the closing quote of the basic
+/// string at the start of `text`.
+fn basic_string_end(text: &str) -> usize {
+    let mut escaped = false;
+    for (i, c) in text.char_indices().skip(1) {
+        match c {
+            '"' if !escaped => return i + 1,
+            '\\' => escaped = !escaped,
+            _ => escaped = false,
+        }
+    }
+    text.len()
+}
+
+fn decode_basic(body: &str) -> Option<String> {
+    let mut out = String::with_capacity(body.len());
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        let decoded = match chars.next()? {
+            'b' => '\u{8}',
+            't' => '\t',
+            'n' => '\n',
+            'f' => '\u{c}',
+            'r' => '\r',
+            'e' => '\u{1b}',
+            '"' => '"',
+            '\\' => '\\',
+            'u' => char::from_u32(
+                u32::from_str_radix(&chars.by_ref().take(4).collect::<String>(), 16).ok()?,
+            )?,
+            'U' => char::from_u32(
+                u32::from_str_radix(&chars.by_ref().take(8).collect::<String>(), 16).ok()?,
+            )?,
+            _ => return None,
+        };
+        out.push(decoded);
+    }
+    Some(out)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fixtures::FLOAT;
 
     const TOML_FIXTURE: &str = r#"
 [types]
@@ -90,7 +289,7 @@ key = "value"
         let formatted = parse_toml::<Root>(TOML_FIXTURE, None).unwrap();
         assert_eq!(formatted.value.types.boolean, true);
         assert_eq!(formatted.value.types.integer, 1);
-        assert!((formatted.value.types.float - 3.14).abs() < f64::EPSILON);
+        assert!((formatted.value.types.float - FLOAT).abs() < f64::EPSILON);
         assert_eq!(formatted.value.types.string, "hello");
         assert_eq!(formatted.value.types.array, vec![1, 2, 3]);
         assert_eq!(formatted.value.types.null, "null");
@@ -115,6 +314,57 @@ key = "value"
         assert_eq!(out_val, expected_val);
     }
 
+    #[test]
+    fn toml_stringify_prefers_literal_strings() {
+        let text = "path = \"C:\\\\Users\"\nquote = \"it's\"\n\"key with space\" = \"value\"\n";
+        let formatted = parse_toml::<toml::Table>(text, None).unwrap();
+        let mut opts = FormatOptions::default();
+        opts.escaping.prefer_literal_strings = true;
+
+        let out = stringify_toml(&formatted, Some(opts)).unwrap();
+        assert!(out.contains("'key with space' = 'value'"));
+        assert!(out.contains("path = 'C:\\Users'"));
+        assert!(out.contains("quote = \"it's\""));
+        let reparsed: toml::Table = toml::from_str(&out).unwrap();
+        assert_eq!(reparsed, formatted.value);
+    }
+
+    #[test]
+    fn toml_stringify_applies_mixed_array_strategy() {
+        let value: serde_json::Value = serde_json::json!({ "ports": [80, "443"] });
+        let formatted = Formatted::new("", value, &FormatOptions::default());
+
+        let opts = FormatOptions {
+            mixed_arrays: MixedArrayStrategy::Stringify,
+            ..Default::default()
+        };
+        let out = stringify_toml(&formatted, Some(opts)).unwrap();
+        assert_eq!(out.trim(), r#"ports = ["80", "443"]"#);
+
+        let opts = FormatOptions {
+            mixed_arrays: MixedArrayStrategy::Error,
+            ..Default::default()
+        };
+        let err = stringify_toml(&formatted, Some(opts)).unwrap_err();
+        assert!(err.to_string().contains("`ports`"));
+    }
+
+    #[test]
+    fn toml_confbox_profile_matches_smol_toml() {
+        let text = "[types]\narray = [1, 2, 3]\nboolean = true\nfloat = 3.14\n\n[types.object]\nkey = \"value\"\n";
+        let formatted = parse_toml::<toml::Table>(text, None).unwrap();
+        let opts = FormatOptions {
+            profile: StyleProfile::Confbox,
+            ..Default::default()
+        };
+
+        let out = stringify_toml(&formatted, Some(opts)).unwrap();
+        assert_eq!(
+            out,
+            "[types]\narray = [ 1, 2, 3 ]\nboolean = true\nfloat = 3.14\n\n[types.object]\nkey = \"value\"\n"
+        );
+    }
+
     #[test]
     fn toml_preserves_outer_whitespace() {
         let text = " \n[section]\nkey = 1\n\n";
@@ -129,4 +379,50 @@ key = "value"
         assert!(out.starts_with(" \n"));
         assert!(out.ends_with("\n\n"));
     }
+
+    #[test]
+    fn toml_fail_on_loss_lists_nulls() {
+        let formatted = Formatted::new(
+            "",
+            serde_json::json!({ "a": 1, "b": null, "c": [null] }),
+            &FormatOptions::default(),
+        );
+        let opts = FormatOptions {
+            fail_on_loss: true,
+            ..Default::default()
+        };
+        let err = stringify_toml(&formatted, Some(opts)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "operation would lose data: null at `b`; null at `c[0]`;"
+        );
+    }
+
+    #[test]
+    fn wraps_arrays_at_line_width() {
+        let formatted = Formatted::new(
+            "",
+            serde_json::json!({
+                "short": [1, 2],
+                "hosts": ["alpha.example.com", "beta.example.com"],
+                "server": { "ports": [8080, 8081, [1, 2]], "meta": { "k": "v" } },
+            }),
+            &FormatOptions::default(),
+        );
+        let opts = FormatOptions {
+            line_width: Some(24),
+            ..Default::default()
+        };
+        let out = stringify_toml(&formatted, Some(opts)).unwrap();
+        assert_eq!(
+            out,
+            "short = [1, 2]\nhosts = [\n  \"alpha.example.com\",\n  \"beta.example.com\",\n]\n\n\
+             [server]\nports = [\n  8080,\n  8081,\n  [1, 2],\n]\n\n[server.meta]\nk = \"v\"\n"
+        );
+        let unwrapped = stringify_toml(&formatted, None).unwrap();
+        assert_eq!(
+            toml::from_str::<toml::Value>(&out).unwrap(),
+            toml::from_str::<toml::Value>(&unwrapped).unwrap()
+        );
+    }
 }
@@ -0,0 +1,159 @@
+use serde::de::DeserializeOwned;
+use serde_json::Value as JsonValue;
+
+use crate::format::{FormatOptions, Formatted};
+use crate::loader::{LoadError, LoadOptions, load_config};
+use crate::schema::{Schema, apply_defaults, validate};
+
+/// A config struct that knows how to load itself: where to look, which
+/// environment variables override it, its defaults and how to check it.
+///
+/// `#[derive(C12Config)]`, with the `derive` feature, implements it from
+/// attributes. By hand, only [`load_options`](Self::load_options) is
+/// required.
+///
+/// ```
+/// use c12_parser::{C12Config, C12Context, LoadOptions, MemoryFileSystem};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     port: u16,
+///     host: String,
+/// }
+///
+/// impl C12Config for Config {
+///     fn load_options() -> LoadOptions {
+///         LoadOptions {
+///             name: "app".into(),
+///             defaults: Some(serde_json::json!({ "host": "localhost" })),
+///             ..LoadOptions::default()
+///         }
+///     }
+///
+///     fn validate(&self) -> Result<(), String> {
+///         match self.port {
+///             0 => Err("port must not be 0".into()),
+///             _ => Ok(()),
+///         }
+///     }
+/// }
+///
+/// let fs = MemoryFileSystem::new().with_file("app/app.config.toml", "port = 8080\n");
+/// let config = Config::load_with(LoadOptions {
+///     cwd: "app".into(),
+///     context: C12Context::new().file_system(fs),
+///     ..Config::load_options()
+/// })?;
+/// assert_eq!((config.port, config.host.as_str()), (8080, "localhost"));
+/// # Ok::<(), c12_parser::LoadError>(())
+/// ```
+pub trait C12Config: DeserializeOwned {
+    /// The options [`load`](Self::load) starts from: the config name and
+    /// environment prefix, with the field defaults as `defaults`.
+    fn load_options() -> LoadOptions;
+
+    /// A JSON Schema whose `default`s fill in missing keys, as
+    /// [`apply_defaults`] does, and that the merged config must meet.
+    fn schema() -> Option<Schema> {
+        None
+    }
+
+    /// Further checks on the loaded config.
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Finds, merges and checks the config from
+    /// [`load_options`](Self::load_options).
+    fn load() -> Result<Self, LoadError> {
+        Self::load_with(Self::load_options())
+    }
+
+    /// Like [`load`](Self::load) with other options, usually
+    /// [`load_options`](Self::load_options) with some fields changed.
+    /// Schema violations and [`validate`](Self::validate) failures are
+    /// [`LoadError`]s about the config file found.
+    fn load_with(options: LoadOptions) -> Result<Self, LoadError> {
+        let loaded = load_config::<JsonValue>(options)?;
+        let invalid = |message| LoadError {
+            path: loaded.config_file.clone(),
+            message,
+        };
+        let mut value = loaded.config.clone();
+        if let Some(schema) = Self::schema() {
+            apply_defaults(&mut value, &schema);
+            let config = Formatted::new("", value, &FormatOptions::default());
+            let violations = validate(&config, &schema);
+            if !violations.is_empty() {
+                let messages: Vec<_> = violations.iter().map(|v| v.to_string()).collect();
+                return Err(invalid(messages.join("; ")));
+            }
+            value = config.value;
+        }
+        let config: Self = serde_json::from_value(value).map_err(|e| invalid(e.to_string()))?;
+        config.validate().map_err(invalid)?;
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::C12Context;
+    use crate::io::MemoryFileSystem;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Server {
+        port: u16,
+        host: String,
+    }
+
+    impl C12Config for Server {
+        fn load_options() -> LoadOptions {
+            LoadOptions {
+                name: "server".into(),
+                env_prefix: Some("SERVER_".into()),
+                ..LoadOptions::default()
+            }
+        }
+
+        fn schema() -> Option<Schema> {
+            Some(Schema::new(json!({
+                "properties": {
+                    "port": { "type": "integer", "maximum": 9000 },
+                    "host": { "default": "0.0.0.0" }
+                }
+            })))
+        }
+    }
+
+    #[test]
+    fn fills_schema_defaults_and_reports_violations() {
+        let load = |port: &str| {
+            let fs =
+                MemoryFileSystem::new().with_file("/srv/server.config.json", "{ \"port\": 80 }");
+            Server::load_with(LoadOptions {
+                cwd: "/srv".into(),
+                context: C12Context::new()
+                    .file_system(fs)
+                    .env([("SERVER_PORT", port)]),
+                ..Server::load_options()
+            })
+        };
+        assert_eq!(
+            load("8080").unwrap(),
+            Server {
+                port: 8080,
+                host: "0.0.0.0".into()
+            }
+        );
+        let err = load("9090").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "/srv/server.config.json: `/port`: expected at most 9000, found 9090"
+        );
+    }
+}
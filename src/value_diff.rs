@@ -0,0 +1,216 @@
+use std::fmt;
+
+use serde_json::Value as JsonValue;
+
+use crate::empty::EmptyDocument;
+use crate::format::{Format, FormatOptions};
+use crate::path::Path;
+use crate::registry::parse_as;
+
+/// One difference found by [`diff_values`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Change {
+    pub path: Path,
+    /// The value before, `None` when the key or item was added.
+    pub old: Option<JsonValue>,
+    /// The value after, `None` when the key or item was removed.
+    pub new: Option<JsonValue>,
+}
+
+impl Change {
+    pub fn is_addition(&self) -> bool {
+        self.old.is_none()
+    }
+
+    pub fn is_removal(&self) -> bool {
+        self.new.is_none()
+    }
+}
+
+/// `+ path = new`, `- path = old` or `~ path: old -> new`, with values as
+/// compact JSON.
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.segments().is_empty() {
+            "(root)".to_string()
+        } else {
+            self.path.to_string()
+        };
+        match (&self.old, &self.new) {
+            (None, Some(new)) => write!(f, "+ {path} = {new}"),
+            (Some(old), None) => write!(f, "- {path} = {old}"),
+            (Some(old), Some(new)) => write!(f, "~ {path}: {old} -> {new}"),
+            (None, None) => write!(f, "  {path}"),
+        }
+    }
+}
+
+/// The differences between two configs, in the order of `a` followed by
+/// keys only `b` has. Objects are compared key by key and arrays index by
+/// index, so a change deep inside reports just that leaf; a value whose
+/// type changed is reported whole.
+///
+/// Both sides are in the shared value model, so a config migrated from
+/// one format to another compares directly with its original.
+///
+/// ```
+/// use c12_parser::{diff_values, parse_toml, parse_yaml};
+///
+/// let before = parse_yaml("port: 80\nhosts: [a, b]\ndebug: true\n", None)?;
+/// let after = parse_toml("port = 8080\nhosts = [\"a\"]\nname = \"web\"\n", None)?;
+/// let changes: Vec<String> = diff_values(&before.value, &after.value)
+///     .iter()
+///     .map(ToString::to_string)
+///     .collect();
+/// assert_eq!(
+///     changes,
+///     [
+///         "~ port: 80 -> 8080",
+///         "- hosts[1] = \"b\"",
+///         "- debug = true",
+///         "+ name = \"web\"",
+///     ]
+/// );
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn diff_values(a: &JsonValue, b: &JsonValue) -> Vec<Change> {
+    let mut changes = Vec::new();
+    collect(Some(a), Some(b), Path::root(), &mut changes);
+    changes
+}
+
+/// Whether `new_text` holds different data than `old_text`, so watchers
+/// and CI checks can skip edits that only touch comments, whitespace or
+/// formatting.
+///
+/// Both texts are read with the comment-aware parsers (JSON as JSONC) and
+/// compared as values, so reordering object keys is not a change either.
+/// Comment-only documents read as `null`. A text that fails to parse
+/// counts as changed, unless both texts are identical.
+///
+/// ```
+/// use c12_parser::{Format, semantic_changed};
+///
+/// let old = "# server\nport = 80 # http\n";
+/// assert!(!semantic_changed(old, "# Server settings\nport   = 80\n", Format::Toml));
+/// assert!(semantic_changed(old, "port = 8080\n", Format::Toml));
+/// ```
+pub fn semantic_changed(old_text: &str, new_text: &str, format: Format) -> bool {
+    if old_text == new_text {
+        return false;
+    }
+    let format = match format {
+        Format::Json => Format::Jsonc,
+        format => format,
+    };
+    let read = |text| {
+        let opts = FormatOptions {
+            on_empty: EmptyDocument::Null,
+            ..FormatOptions::default()
+        };
+        parse_as(format, text, opts).ok().map(|parsed| parsed.value)
+    };
+    match (read(old_text), read(new_text)) {
+        (Some(old), Some(new)) => old != new,
+        _ => true,
+    }
+}
+
+fn collect(
+    old: Option<&JsonValue>,
+    new: Option<&JsonValue>,
+    path: Path,
+    changes: &mut Vec<Change>,
+) {
+    match (old, new) {
+        (Some(JsonValue::Object(old)), Some(JsonValue::Object(new))) => {
+            for (key, value) in old {
+                collect(
+                    Some(value),
+                    new.get(key),
+                    path.clone().key(key.as_str()),
+                    changes,
+                );
+            }
+            for (key, value) in new.iter().filter(|(key, _)| !old.contains_key(*key)) {
+                collect(None, Some(value), path.clone().key(key.as_str()), changes);
+            }
+        }
+        (Some(JsonValue::Array(old)), Some(JsonValue::Array(new))) => {
+            for i in 0..old.len().max(new.len()) {
+                collect(old.get(i), new.get(i), path.clone().index(i), changes);
+            }
+        }
+        (old, new) if old != new => changes.push(Change {
+            path,
+            old: old.cloned(),
+            new: new.cloned(),
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reports_leaf_changes_with_paths() {
+        let a = json!({ "db": { "host": "a", "pool": [1, 2] }, "tags": { "x.y": 1 } });
+        let b = json!({ "db": { "host": "b", "pool": [1, 3, 4] }, "tags": "none" });
+        let changes = diff_values(&a, &b);
+        assert_eq!(
+            changes[0],
+            Change {
+                path: "db.host".parse().unwrap(),
+                old: Some(json!("a")),
+                new: Some(json!("b")),
+            }
+        );
+        let lines: Vec<_> = changes.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            lines,
+            [
+                "~ db.host: \"a\" -> \"b\"",
+                "~ db.pool[1]: 2 -> 3",
+                "+ db.pool[2] = 4",
+                "~ tags: {\"x.y\":1} -> \"none\"",
+            ]
+        );
+        assert!(changes[2].is_addition() && !changes[2].is_removal());
+    }
+
+    #[test]
+    fn equal_values_have_no_changes() {
+        let value = json!({ "a": [1, { "b": null }] });
+        assert!(diff_values(&value, &value).is_empty());
+        assert_eq!(
+            diff_values(&json!(1), &json!(2))[0].to_string(),
+            "~ (root): 1 -> 2"
+        );
+    }
+
+    #[test]
+    fn ignores_comments_whitespace_and_key_order() {
+        let old = "{\n  // port\n  \"port\": 80,\n  \"host\": \"a\"\n}\n";
+        let new = "{ \"host\": \"a\", /* moved */ \"port\": 80 }";
+        assert!(!semantic_changed(old, new, Format::Json));
+        assert!(semantic_changed(old, "{ \"port\": 80 }", Format::Json));
+
+        let yaml = "# only a comment\n";
+        assert!(!semantic_changed(yaml, "\n# another\n", Format::Yaml));
+        assert!(semantic_changed(yaml, "a: 1\n", Format::Yaml));
+        assert!(!semantic_changed(
+            "[a]\nx = 1\n",
+            "; note\n[a]\nx=1\n",
+            Format::Ini
+        ));
+    }
+
+    #[test]
+    fn unparsable_text_counts_as_changed() {
+        assert!(semantic_changed("a = 1\n", "a = \n", Format::Toml));
+        assert!(!semantic_changed("a = \n", "a = \n", Format::Toml));
+    }
+}
@@ -0,0 +1,281 @@
+use std::fmt;
+
+use crate::document::{DocumentEditError, parse_document};
+use crate::error::C12Error;
+use crate::format::Format;
+use crate::path::Path;
+
+/// Which part of a semantic version [`bump_version`] increments.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BumpLevel {
+    Major,
+    Minor,
+    #[default]
+    Patch,
+}
+
+/// Options for [`bump_version`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BumpOptions {
+    /// Where the version string is, `version` by default; Cargo manifests
+    /// keep it at `package.version`.
+    pub path: Path,
+    pub level: BumpLevel,
+}
+
+impl Default for BumpOptions {
+    fn default() -> Self {
+        Self {
+            path: Path::root().key("version"),
+            level: BumpLevel::default(),
+        }
+    }
+}
+
+/// The result of [`bump_version`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VersionBump {
+    /// The config with only the version changed.
+    pub text: String,
+    pub old: String,
+    pub new: String,
+}
+
+/// Returned when [`bump_version`] cannot bump a config's version.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BumpError {
+    /// The config does not parse, or cannot be edited in place.
+    Parse(C12Error),
+    /// Nothing is stored at `path`.
+    Missing { path: Path },
+    /// The value at `path` is not a string.
+    NotAString { path: Path },
+    /// The string at `path` is not a semantic version.
+    Invalid { path: Path, version: String },
+    /// The part being bumped is already `u64::MAX`.
+    Overflow { path: Path, version: String },
+    /// The new version cannot be written back.
+    Edit(DocumentEditError),
+}
+
+impl BumpError {
+    /// The path of the version the error is about, if it got that far.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            BumpError::Missing { path }
+            | BumpError::NotAString { path }
+            | BumpError::Invalid { path, .. }
+            | BumpError::Overflow { path, .. } => Some(path),
+            BumpError::Parse(_) | BumpError::Edit(_) => None,
+        }
+    }
+
+    /// The message without the path, e.g. `is not a string`.
+    pub(crate) fn message(&self) -> String {
+        match self {
+            BumpError::Parse(e) => e.to_string(),
+            BumpError::Missing { .. } => "is not in the document".into(),
+            BumpError::NotAString { .. } => "is not a string".into(),
+            BumpError::Invalid { version, .. } => {
+                format!("holds `{version}`, which is not a semantic version")
+            }
+            BumpError::Overflow { version, .. } => {
+                format!("holds `{version}`, which cannot be bumped any further")
+            }
+            BumpError::Edit(e) => e.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for BumpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.path() {
+            Some(path) => write!(f, "`{path}` {}", self.message()),
+            None => f.write_str(&self.message()),
+        }
+    }
+}
+
+impl std::error::Error for BumpError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BumpError::Parse(e) => Some(e),
+            BumpError::Edit(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<C12Error> for BumpError {
+    fn from(e: C12Error) -> Self {
+        BumpError::Parse(e)
+    }
+}
+
+impl From<DocumentEditError> for BumpError {
+    fn from(e: DocumentEditError) -> Self {
+        BumpError::Edit(e)
+    }
+}
+
+/// Bumps the semantic version string in a config, leaving the rest of the
+/// file byte for byte as it was, quotes around the version included.
+///
+/// Bumping a level resets the ones below it and drops build metadata. A
+/// prerelease is released instead when it already sits at the target, as
+/// npm does: a patch bump turns `1.3.0-rc.1` into `1.3.0`, and so does a
+/// minor one. A leading `v` is kept.
+///
+/// ```
+/// use c12_parser::{BumpLevel, BumpOptions, Format, Path, bump_version};
+///
+/// let bump = bump_version("{\n  // release\n  \"version\": \"1.4.2\"\n}", Format::Jsonc, None)?;
+/// assert_eq!(bump.text, "{\n  // release\n  \"version\": \"1.4.3\"\n}");
+///
+/// let manifest = "[package]\nname = \"app\"\nversion = \"0.9.1\" # bumped by CI\n";
+/// let options = BumpOptions {
+///     path: Path::root().key("package").key("version"),
+///     level: BumpLevel::Minor,
+/// };
+/// let bump = bump_version(manifest, Format::Toml, Some(options))?;
+/// assert_eq!((bump.old.as_str(), bump.new.as_str()), ("0.9.1", "0.10.0"));
+/// assert_eq!(bump.text, "[package]\nname = \"app\"\nversion = \"0.10.0\" # bumped by CI\n");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn bump_version(
+    text: &str,
+    format: Format,
+    options: Option<BumpOptions>,
+) -> Result<VersionBump, BumpError> {
+    let options = options.unwrap_or_default();
+    let mut doc = parse_document(text, format)?;
+    let path = options.path;
+    let old = match doc.get(&path) {
+        Some(serde_json::Value::String(old)) => old,
+        Some(_) => return Err(BumpError::NotAString { path }),
+        None => return Err(BumpError::Missing { path }),
+    };
+    let new = bumped(&old, options.level).map_err(|kind| kind.at(path.clone(), &old))?;
+    doc.set(&path, new.clone().into())?;
+    Ok(VersionBump {
+        text: doc.to_string(),
+        old,
+        new,
+    })
+}
+
+/// Why [`bumped`] failed, before the path is known.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum BumpFailure {
+    Invalid,
+    Overflow,
+}
+
+impl BumpFailure {
+    fn at(self, path: Path, version: &str) -> BumpError {
+        let version = version.to_string();
+        match self {
+            BumpFailure::Invalid => BumpError::Invalid { path, version },
+            BumpFailure::Overflow => BumpError::Overflow { path, version },
+        }
+    }
+}
+
+/// `version` bumped at `level`.
+pub(crate) fn bumped(version: &str, level: BumpLevel) -> Result<String, BumpFailure> {
+    let invalid = || BumpFailure::Invalid;
+    let (prefix, rest) = match version.strip_prefix('v') {
+        Some(rest) => ("v", rest),
+        None => ("", version),
+    };
+    let core = rest.split('+').next().unwrap_or_default();
+    let (core, prerelease) = match core.split_once('-') {
+        Some((core, pre)) if !pre.is_empty() => (core, true),
+        Some(_) => return Err(invalid()),
+        None => (core, false),
+    };
+    let numbers = core
+        .split('.')
+        .map(|part| {
+            let leading_zero = part.len() > 1 && part.starts_with('0');
+            match part.parse::<u64>() {
+                Ok(n) if !leading_zero && part.bytes().all(|b| b.is_ascii_digit()) => Ok(n),
+                _ => Err(invalid()),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let [major, minor, patch] = numbers[..] else {
+        return Err(invalid());
+    };
+    let overflow = || BumpFailure::Overflow;
+    let (major, minor, patch) = match level {
+        BumpLevel::Major if prerelease && minor == 0 && patch == 0 => (major, 0, 0),
+        BumpLevel::Major => (major.checked_add(1).ok_or_else(overflow)?, 0, 0),
+        BumpLevel::Minor if prerelease && patch == 0 => (major, minor, 0),
+        BumpLevel::Minor => (major, minor.checked_add(1).ok_or_else(overflow)?, 0),
+        BumpLevel::Patch if prerelease => (major, minor, patch),
+        BumpLevel::Patch => (major, minor, patch.checked_add(1).ok_or_else(overflow)?),
+    };
+    Ok(format!("{prefix}{major}.{minor}.{patch}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_semver_and_npm_prerelease_rules() {
+        let bump = |version: &str, level| bumped(version, level);
+        assert_eq!(bump("1.2.3", BumpLevel::Patch), Ok("1.2.4".into()));
+        assert_eq!(bump("1.2.3+build.5", BumpLevel::Minor), Ok("1.3.0".into()));
+        assert_eq!(bump("v1.2.3", BumpLevel::Major), Ok("v2.0.0".into()));
+        assert_eq!(bump("1.3.0-rc.1", BumpLevel::Minor), Ok("1.3.0".into()));
+        assert_eq!(bump("1.3.1-rc.1", BumpLevel::Minor), Ok("1.4.0".into()));
+        assert_eq!(bump("2.0.0-beta", BumpLevel::Major), Ok("2.0.0".into()));
+        assert_eq!(bump("1.2.3-beta", BumpLevel::Patch), Ok("1.2.3".into()));
+        for invalid in ["1.2", "01.2.3", "1.2.x", "1.2.3-", ""] {
+            assert_eq!(bump(invalid, BumpLevel::Patch), Err(BumpFailure::Invalid));
+        }
+        let max = format!("{}.0.0", u64::MAX);
+        assert_eq!(bump(&max, BumpLevel::Major), Err(BumpFailure::Overflow));
+    }
+
+    #[test]
+    fn keeps_the_rest_of_each_format() {
+        let yaml = "name: app # the app\nversion: '1.0.0'\n";
+        let bump = bump_version(yaml, Format::Yaml, None).unwrap();
+        assert_eq!(bump.text, "name: app # the app\nversion: '1.0.1'\n");
+
+        let json5 = "{ version: '3.1.4', }";
+        let bump = bump_version(json5, Format::Json5, None).unwrap();
+        assert_eq!(bump.text, "{ version: '3.1.5', }");
+
+        let err = bump_version("{ \"version\": 1 }", Format::Json, None).unwrap_err();
+        assert_eq!(err.to_string(), "`version` is not a string");
+    }
+
+    #[test]
+    fn reports_typed_errors() {
+        let version = Path::root().key("version");
+        let err = |text: &str| bump_version(text, Format::Json, None).unwrap_err();
+        assert_eq!(
+            err("{}"),
+            BumpError::Missing {
+                path: version.clone()
+            }
+        );
+        assert_eq!(
+            err("{ \"version\": \"1.x\" }"),
+            BumpError::Invalid {
+                path: version.clone(),
+                version: "1.x".into()
+            }
+        );
+        assert_eq!(
+            err("{ \"version\": \"1.x\" }").to_string(),
+            "`version` holds `1.x`, which is not a semantic version"
+        );
+        assert!(matches!(err("{"), BumpError::Parse(e) if e.line == 1));
+        assert_eq!(err("{").path(), None);
+    }
+}
@@ -0,0 +1,307 @@
+use std::fs;
+use std::io;
+use std::path::{Path as FsPath, PathBuf};
+
+use regex::Regex;
+
+/// Ignore files read in every directory by default.
+pub const IGNORE_FILES: [&str; 2] = [".gitignore", ".c12ignore"];
+
+/// Lists the files of a directory tree in path order, skipping what
+/// `.gitignore` and `.c12ignore` files exclude. Shared by the directory
+/// operations such as [`convert_tree`](crate::convert_tree).
+///
+/// Ignore files use gitignore syntax: `#` comments, `!` to re-include,
+/// a trailing `/` for directories only, and patterns containing a `/`
+/// anchored to the directory of the ignore file. Rules from deeper ignore
+/// files and later lines win. As in git, nothing inside an ignored
+/// directory can be re-included, and `.git` itself is always skipped.
+///
+/// ```
+/// use c12_parser::Walker;
+/// # let root = std::env::temp_dir().join(format!("c12-walker-doc-{}", std::process::id()));
+/// # std::fs::create_dir_all(root.join("build")).unwrap();
+/// # std::fs::write(root.join(".gitignore"), "build/\n").unwrap();
+/// # std::fs::write(root.join("app.json"), "{}").unwrap();
+/// # std::fs::write(root.join("build/app.json"), "{}").unwrap();
+///
+/// let files = Walker::new().files(&root)?;
+/// assert_eq!(files, [".gitignore", "app.json"].map(std::path::PathBuf::from));
+///
+/// let all = Walker::new().include_ignored(true).files(&root)?;
+/// assert_eq!(all.len(), 3);
+/// # std::fs::remove_dir_all(&root).unwrap();
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct Walker {
+    ignore_files: Vec<String>,
+    include_ignored: bool,
+    exclude: Vec<Regex>,
+}
+
+impl Default for Walker {
+    fn default() -> Self {
+        Self {
+            ignore_files: IGNORE_FILES.map(String::from).to_vec(),
+            include_ignored: false,
+            exclude: Vec::new(),
+        }
+    }
+}
+
+/// One line of an ignore file.
+#[derive(Clone, Debug)]
+struct Rule {
+    /// Directory of the ignore file, relative to the walk root.
+    base: String,
+    pattern: Regex,
+    negated: bool,
+    dir_only: bool,
+}
+
+impl Walker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also list files the ignore files exclude.
+    pub fn include_ignored(mut self, include: bool) -> Self {
+        self.include_ignored = include;
+        self
+    }
+
+    /// Reads ignore rules from files called `name` instead of the
+    /// defaults in [`IGNORE_FILES`]; call repeatedly for several names.
+    pub fn ignore_file(mut self, name: impl Into<String>) -> Self {
+        if self.ignore_files == IGNORE_FILES {
+            self.ignore_files.clear();
+        }
+        self.ignore_files.push(name.into());
+        self
+    }
+
+    /// Skips paths matching `glob`, even with
+    /// [`include_ignored`](Self::include_ignored). See
+    /// [`ConvertFilters`](crate::ConvertFilters) for the glob syntax.
+    pub fn exclude(mut self, glob: &str) -> Self {
+        self.exclude.push(file_glob(glob));
+        self
+    }
+
+    /// The files under `root`, relative to it and sorted by path.
+    pub fn files(&self, root: &FsPath) -> io::Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        self.walk(root, PathBuf::new(), Vec::new(), &mut files)?;
+        Ok(files)
+    }
+
+    fn walk(
+        &self,
+        root: &FsPath,
+        dir: PathBuf,
+        mut rules: Vec<Rule>,
+        files: &mut Vec<PathBuf>,
+    ) -> io::Result<()> {
+        if !self.include_ignored {
+            let base = slash_path(&dir);
+            for name in &self.ignore_files {
+                match fs::read_to_string(root.join(&dir).join(name)) {
+                    Ok(text) => rules.extend(parse_rules(&text, &base)),
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        let mut entries = fs::read_dir(root.join(&dir))?.collect::<io::Result<Vec<_>>>()?;
+        entries.sort_by_key(|e| e.file_name());
+        for entry in entries {
+            let relative = dir.join(entry.file_name());
+            let path = slash_path(&relative);
+            let is_dir = entry.file_type()?.is_dir();
+            if self.exclude.iter().any(|g| g.is_match(&path))
+                || (is_dir && entry.file_name() == ".git")
+                || (!self.include_ignored && is_ignored(&rules, &path, is_dir))
+            {
+                continue;
+            }
+            if is_dir {
+                self.walk(root, relative, rules.clone(), files)?;
+            } else {
+                files.push(relative);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_rules(text: &str, base: &str) -> Vec<Rule> {
+    text.lines()
+        .filter_map(|line| {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                return None;
+            }
+            let (negated, line) = match line.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, line.strip_prefix('\\').unwrap_or(line)),
+            };
+            let (dir_only, line) = match line.strip_suffix('/') {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+            let anchored = line.contains('/');
+            let line = line.strip_prefix('/').unwrap_or(line);
+            let glob = if anchored {
+                line.to_string()
+            } else {
+                format!("**/{line}")
+            };
+            Some(Rule {
+                base: base.to_string(),
+                pattern: file_glob(&glob),
+                negated,
+                dir_only,
+            })
+        })
+        .collect()
+}
+
+/// Whether the last rule matching `path` ignores it.
+fn is_ignored(rules: &[Rule], path: &str, is_dir: bool) -> bool {
+    rules
+        .iter()
+        .rev()
+        .find(|rule| {
+            let relative = if rule.base.is_empty() {
+                Some(path)
+            } else {
+                path.strip_prefix(&rule.base)
+                    .and_then(|rest| rest.strip_prefix('/'))
+            };
+            (is_dir || !rule.dir_only) && relative.is_some_and(|p| rule.pattern.is_match(p))
+        })
+        .is_some_and(|rule| !rule.negated)
+}
+
+pub(crate) fn slash_path(path: &FsPath) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Translates a file glob into an anchored regex over `/`-separated
+/// relative paths.
+pub(crate) fn file_glob(glob: &str) -> Regex {
+    let mut pattern = String::from("^");
+    let mut rest = glob;
+    while let Some(c) = rest.chars().next() {
+        if let Some(tail) = rest.strip_prefix("**/") {
+            pattern.push_str("(?:.*/)?");
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix("**") {
+            pattern.push_str(".*");
+            rest = tail;
+        } else {
+            match c {
+                '*' => pattern.push_str("[^/]*"),
+                '?' => pattern.push_str("[^/]"),
+                c => pattern.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+            }
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern).expect("escaped glob is a valid regex")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("c12-walk-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for (path, text) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, text).unwrap();
+        }
+        root
+    }
+
+    fn listed(walker: &Walker, root: &FsPath) -> Vec<String> {
+        let files = walker.files(root).unwrap();
+        files.iter().map(|p| slash_path(p)).collect()
+    }
+
+    #[test]
+    fn honors_nested_ignore_files_and_negation() {
+        let root = tree(
+            "nested",
+            &[
+                (".gitignore", "*.log\n/dist/\n# comment\n"),
+                ("app.json", "{}"),
+                ("debug.log", ""),
+                ("dist/app.json", "{}"),
+                ("pkg/.c12ignore", "*.yaml\n!keep.yaml\n"),
+                ("pkg/dist/x.json", "{}"),
+                ("pkg/drop.yaml", ""),
+                ("pkg/keep.yaml", ""),
+                ("pkg/trace.log", ""),
+                (".git/config", ""),
+            ],
+        );
+        assert_eq!(
+            listed(&Walker::new(), &root),
+            [
+                ".gitignore",
+                "app.json",
+                "pkg/.c12ignore",
+                "pkg/dist/x.json",
+                "pkg/keep.yaml"
+            ]
+        );
+        assert_eq!(
+            listed(
+                &Walker::new().include_ignored(true).exclude("**/*.log"),
+                &root
+            )
+            .len(),
+            7
+        );
+        assert_eq!(
+            listed(&Walker::new().ignore_file(".c12ignore"), &root),
+            [
+                ".gitignore",
+                "app.json",
+                "debug.log",
+                "dist/app.json",
+                "pkg/.c12ignore",
+                "pkg/dist/x.json",
+                "pkg/keep.yaml",
+                "pkg/trace.log"
+            ]
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn directory_rules_do_not_match_files() {
+        let rules = parse_rules("cache/\nsub/*.json\n", "");
+        assert!(is_ignored(&rules, "a/cache", true));
+        assert!(!is_ignored(&rules, "a/cache", false));
+        assert!(is_ignored(&rules, "sub/x.json", false));
+        assert!(!is_ignored(&rules, "a/sub/x.json", false));
+    }
+
+    #[test]
+    fn file_globs_respect_separators() {
+        assert!(file_glob("**/*.yaml").is_match("a/b/c.yaml"));
+        assert!(file_glob("**/*.yaml").is_match("c.yaml"));
+        assert!(!file_glob("*.yaml").is_match("a/c.yaml"));
+        assert!(file_glob("conf?.json").is_match("conf1.json"));
+    }
+}
@@ -0,0 +1,540 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use yaml_rust2::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust2::scanner::{Marker, Scanner, TokenType};
+
+use crate::path::Path;
+
+/// Bounds on YAML anchors and aliases, checked by [`check_yaml_aliases`]
+/// and, when set in
+/// [`FormatOptions::yaml_aliases`](crate::FormatOptions::yaml_aliases),
+/// by [`parse_yaml`](crate::parse_yaml).
+///
+/// These are enforced on the event stream before serde_yaml sees the
+/// document, so the outcome does not depend on serde_yaml's internal
+/// recursion and repetition limits.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AliasLimits {
+    /// How many times a single anchor may be referenced.
+    pub max_alias_uses: usize,
+
+    /// How many nodes a document may expand to once every alias is
+    /// replaced by its anchored node. This is what stops "billion laughs"
+    /// documents, where a few lines of nested aliases expand to gigabytes.
+    pub max_expanded_nodes: usize,
+
+    /// Accept an anchor name being defined again later in the same
+    /// document. YAML allows it, but the second definition silently
+    /// changes what later aliases mean.
+    pub allow_duplicate_anchors: bool,
+}
+
+impl Default for AliasLimits {
+    fn default() -> Self {
+        Self {
+            max_alias_uses: 32,
+            max_expanded_nodes: 10_000,
+            allow_duplicate_anchors: false,
+        }
+    }
+}
+
+/// What is wrong with an anchor or alias.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AliasIssueKind {
+    /// The anchor is defined a second time in the same document.
+    DuplicateAnchor(String),
+    /// The alias refers to a node that contains it.
+    RecursiveAlias(String),
+    /// The anchor is referenced more than `max_alias_uses` times.
+    ExcessiveReuse(String),
+    /// The document expands to more than `max_expanded_nodes` nodes.
+    ExpansionTooLarge { nodes: usize },
+}
+
+/// An anchor or alias problem, located by 1-based line and column.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AliasIssue {
+    pub line: usize,
+    pub column: usize,
+    pub kind: AliasIssueKind,
+}
+
+impl fmt::Display for AliasIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: ", self.line, self.column)?;
+        match &self.kind {
+            AliasIssueKind::DuplicateAnchor(name) => write!(f, "anchor `&{name}` is defined twice"),
+            AliasIssueKind::RecursiveAlias(name) => {
+                write!(f, "alias `*{name}` refers to a node containing it")
+            }
+            AliasIssueKind::ExcessiveReuse(name) => {
+                write!(f, "anchor `&{name}` is referenced too many times")
+            }
+            AliasIssueKind::ExpansionTooLarge { nodes } => {
+                write!(f, "document expands to at least {nodes} nodes")
+            }
+        }
+    }
+}
+
+/// The issues that made [`parse_yaml`](crate::parse_yaml) reject a
+/// document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct YamlAliasError {
+    pub issues: Vec<AliasIssue>,
+}
+
+impl fmt::Display for YamlAliasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unsafe YAML aliases:")?;
+        for issue in &self.issues {
+            write!(f, " {issue};")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for YamlAliasError {}
+
+impl YamlAliasError {
+    pub(crate) fn check(issues: Vec<AliasIssue>) -> Result<(), YamlAliasError> {
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(YamlAliasError { issues })
+        }
+    }
+}
+
+/// Lists anchor and alias problems in `text` without rejecting it, for
+/// callers that prefer to warn.
+pub fn check_yaml_aliases(
+    text: &str,
+    limits: &AliasLimits,
+) -> Result<Vec<AliasIssue>, serde_yaml::Error> {
+    let to_error = <serde_yaml::Error as serde::de::Error>::custom;
+
+    // Events only carry anchor ids, assigned in definition order across
+    // the stream, so the names come from a separate pass over the tokens.
+    let mut names = Vec::new();
+    let mut issues = Vec::new();
+    let mut defined = HashSet::new();
+    let mut scanner = Scanner::new(text.chars());
+    for token in &mut scanner {
+        match token.1 {
+            TokenType::DocumentStart => defined.clear(),
+            TokenType::Anchor(name) => {
+                if !defined.insert(name.clone()) && !limits.allow_duplicate_anchors {
+                    issues.push(issue(
+                        token.0,
+                        AliasIssueKind::DuplicateAnchor(name.clone()),
+                    ));
+                }
+                names.push(name);
+            }
+            _ => {}
+        }
+    }
+    if let Some(err) = scanner.get_error() {
+        return Err(to_error(err));
+    }
+
+    let mut checker = Checker {
+        limits,
+        names: &names,
+        issues,
+        frames: vec![Frame::default()],
+        sizes: HashMap::new(),
+        uses: HashMap::new(),
+    };
+    Parser::new_from_str(text)
+        .load(&mut checker, true)
+        .map_err(to_error)?;
+    let mut issues = checker.issues;
+    issues.sort_by_key(|i| (i.line, i.column));
+    Ok(issues)
+}
+
+fn issue(mark: Marker, kind: AliasIssueKind) -> AliasIssue {
+    let (line, column) = position(mark);
+    AliasIssue { line, column, kind }
+}
+
+/// The 1-based line and column of `mark`.
+fn position(mark: Marker) -> (usize, usize) {
+    // yaml-rust2 lines are 1-based but columns are 0-based.
+    (mark.line(), mark.col() + 1)
+}
+
+#[derive(Default)]
+struct Frame {
+    anchor: usize,
+    nodes: usize,
+}
+
+struct Checker<'a> {
+    limits: &'a AliasLimits,
+    names: &'a [String],
+    issues: Vec<AliasIssue>,
+    frames: Vec<Frame>,
+    /// Expanded node count of each closed anchored node.
+    sizes: HashMap<usize, usize>,
+    uses: HashMap<usize, usize>,
+}
+
+impl Checker<'_> {
+    fn name(&self, id: usize) -> String {
+        self.names.get(id - 1).cloned().unwrap_or_default()
+    }
+
+    fn add_nodes(&mut self, nodes: usize) {
+        let frame = self.frames.last_mut().expect("root frame");
+        frame.nodes = frame.nodes.saturating_add(nodes);
+    }
+}
+
+impl MarkedEventReceiver for Checker<'_> {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        match event {
+            Event::DocumentStart => {
+                self.frames = vec![Frame::default()];
+                self.uses.clear();
+            }
+            Event::DocumentEnd => {
+                let nodes = self.frames[0].nodes;
+                if nodes > self.limits.max_expanded_nodes {
+                    let kind = AliasIssueKind::ExpansionTooLarge { nodes };
+                    self.issues.push(issue(mark, kind));
+                }
+            }
+            Event::SequenceStart(anchor, _) | Event::MappingStart(anchor, _) => {
+                self.frames.push(Frame { anchor, nodes: 1 });
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                let frame = self.frames.pop().expect("balanced events");
+                if frame.anchor > 0 {
+                    self.sizes.insert(frame.anchor, frame.nodes);
+                }
+                self.add_nodes(frame.nodes);
+            }
+            Event::Scalar(_, _, anchor, _) => {
+                if anchor > 0 {
+                    self.sizes.insert(anchor, 1);
+                }
+                self.add_nodes(1);
+            }
+            Event::Alias(id) => {
+                if self.frames.iter().any(|f| f.anchor == id) {
+                    let kind = AliasIssueKind::RecursiveAlias(self.name(id));
+                    self.issues.push(issue(mark, kind));
+                }
+                let uses = self.uses.entry(id).or_default();
+                *uses += 1;
+                if *uses == self.limits.max_alias_uses + 1 {
+                    let kind = AliasIssueKind::ExcessiveReuse(self.name(id));
+                    self.issues.push(issue(mark, kind));
+                }
+                let size = self.sizes.get(&id).copied().unwrap_or(1);
+                self.add_nodes(size);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// An anchor in a YAML document and the aliases referring to it, found by
+/// [`yaml_anchors`]. Positions are 1-based and point at the start of the
+/// anchored node or of the alias.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct YamlAnchor {
+    pub name: String,
+    /// The anchored node.
+    pub path: Path,
+    pub line: usize,
+    pub column: usize,
+    pub aliases: Vec<YamlAlias>,
+}
+
+/// A `*name` alias, located by the path of the node it stands for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct YamlAlias {
+    pub path: Path,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Lists the anchors of the first document in `text`, in definition
+/// order, with the aliases that refer to each, so callers can tell which
+/// values were shared before parsing expanded them.
+///
+/// Anchors and aliases on mapping keys have no path and are left out. Set
+/// [`FormatOptions::keep_yaml_anchors`](crate::FormatOptions::keep_yaml_anchors)
+/// to have them written back by [`stringify_yaml`](crate::stringify_yaml).
+///
+/// ```
+/// use c12_parser::{Path, yaml_anchors};
+///
+/// let text = "defaults: &defaults\n  retries: 3\nprod:\n  http: *defaults\n";
+/// let anchors = yaml_anchors(text)?;
+/// assert_eq!(anchors[0].name, "defaults");
+/// assert_eq!(anchors[0].path, Path::root().key("defaults"));
+/// assert_eq!(anchors[0].aliases[0].path, Path::root().key("prod").key("http"));
+/// assert_eq!(anchors[0].aliases[0].line, 4);
+/// # Ok::<(), serde_yaml::Error>(())
+/// ```
+pub fn yaml_anchors(text: &str) -> Result<Vec<YamlAnchor>, serde_yaml::Error> {
+    let to_error = <serde_yaml::Error as serde::de::Error>::custom;
+    let mut scanner = Scanner::new(text.chars());
+    let names: Vec<String> = (&mut scanner)
+        .filter_map(|token| match token.1 {
+            TokenType::Anchor(name) => Some(name),
+            _ => None,
+        })
+        .collect();
+    if let Some(err) = scanner.get_error() {
+        return Err(to_error(err));
+    }
+
+    let mut recorder = Recorder {
+        names: &names,
+        stack: Vec::new(),
+        anchors: Vec::new(),
+        by_id: HashMap::new(),
+        done: false,
+    };
+    Parser::new_from_str(text)
+        .load(&mut recorder, false)
+        .map_err(to_error)?;
+    Ok(recorder.anchors)
+}
+
+/// Where the next node of a collection goes.
+enum Slot {
+    Key,
+    Value(Option<Path>),
+}
+
+struct Collection {
+    /// `None` inside a mapping key.
+    path: Option<Path>,
+    /// For mappings, whether a key comes next and the last scalar key.
+    mapping: Option<(bool, Option<String>)>,
+    next_index: usize,
+}
+
+struct Recorder<'a> {
+    names: &'a [String],
+    stack: Vec<Collection>,
+    anchors: Vec<YamlAnchor>,
+    by_id: HashMap<usize, usize>,
+    done: bool,
+}
+
+impl Recorder<'_> {
+    fn slot(&mut self) -> Slot {
+        let Some(top) = self.stack.last_mut() else {
+            return Slot::Value(Some(Path::root()));
+        };
+        match &mut top.mapping {
+            Some((expect_key, key)) if *expect_key => {
+                *expect_key = false;
+                *key = None;
+                Slot::Key
+            }
+            Some((expect_key, key)) => {
+                *expect_key = true;
+                let key = key.take();
+                Slot::Value(top.path.clone().zip(key).map(|(path, key)| path.key(key)))
+            }
+            None => {
+                top.next_index += 1;
+                Slot::Value(top.path.clone().map(|path| path.index(top.next_index - 1)))
+            }
+        }
+    }
+
+    fn anchor(&mut self, id: usize, path: Option<Path>, mark: Marker) {
+        let (Some(path), Some(name)) = (path, self.names.get(id.wrapping_sub(1))) else {
+            return;
+        };
+        let (line, column) = position(mark);
+        self.by_id.insert(id, self.anchors.len());
+        self.anchors.push(YamlAnchor {
+            name: name.clone(),
+            path,
+            line,
+            column,
+            aliases: Vec::new(),
+        });
+    }
+
+    fn open(&mut self, anchor: usize, mapping: bool, mark: Marker) {
+        let path = match self.slot() {
+            Slot::Key => None,
+            Slot::Value(path) => path,
+        };
+        if anchor > 0 {
+            self.anchor(anchor, path.clone(), mark);
+        }
+        self.stack.push(Collection {
+            path,
+            mapping: mapping.then_some((true, None)),
+            next_index: 0,
+        });
+    }
+}
+
+impl MarkedEventReceiver for Recorder<'_> {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        if self.done {
+            return;
+        }
+        match event {
+            Event::DocumentEnd => self.done = true,
+            Event::MappingStart(anchor, _) => self.open(anchor, true, mark),
+            Event::SequenceStart(anchor, _) => self.open(anchor, false, mark),
+            Event::MappingEnd | Event::SequenceEnd => {
+                self.stack.pop();
+            }
+            Event::Scalar(value, _, anchor, _) => match self.slot() {
+                Slot::Key => {
+                    if let Some(Collection {
+                        mapping: Some((_, key)),
+                        ..
+                    }) = self.stack.last_mut()
+                    {
+                        *key = Some(value);
+                    }
+                }
+                Slot::Value(path) if anchor > 0 => self.anchor(anchor, path, mark),
+                Slot::Value(_) => {}
+            },
+            Event::Alias(id) => {
+                if let Slot::Value(Some(path)) = self.slot()
+                    && let Some(&index) = self.by_id.get(&id)
+                {
+                    let (line, column) = position(mark);
+                    self.anchors[index]
+                        .aliases
+                        .push(YamlAlias { path, line, column });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::FormatOptions;
+    use serde_json::Value as JsonValue;
+
+    fn kinds(text: &str, limits: &AliasLimits) -> Vec<AliasIssueKind> {
+        check_yaml_aliases(text, limits)
+            .unwrap()
+            .into_iter()
+            .map(|i| i.kind)
+            .collect()
+    }
+
+    #[test]
+    fn plain_aliases_pass() {
+        let text = "base: &base { a: 1 }\none: *base\ntwo: *base\n";
+        assert!(kinds(text, &AliasLimits::default()).is_empty());
+    }
+
+    #[test]
+    fn reports_recursion_duplicates_and_reuse_with_spans() {
+        let issues = check_yaml_aliases("a: &x [1, *x]\n", &AliasLimits::default()).unwrap();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(
+            issues[0].to_string(),
+            "1:11: alias `*x` refers to a node containing it"
+        );
+
+        let text = "a: &x 1\nb: &x 2\nc: *x\n";
+        assert_eq!(
+            kinds(text, &AliasLimits::default()),
+            [AliasIssueKind::DuplicateAnchor("x".into())]
+        );
+        let lenient = AliasLimits {
+            allow_duplicate_anchors: true,
+            ..Default::default()
+        };
+        assert!(kinds(text, &lenient).is_empty());
+
+        let strict = AliasLimits {
+            max_alias_uses: 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            kinds("a: &x 1\nb: *x\nc: *x\nd: *x\n", &strict),
+            [AliasIssueKind::ExcessiveReuse("x".into())]
+        );
+    }
+
+    #[test]
+    fn lists_anchors_with_their_aliases() {
+        let text = "\
+base: &base
+  tags: &tags [a, b]
+jobs:
+  - &first { name: one, tags: *tags }
+  - *first
+? &k key
+: *base
+---
+other: &later 1
+";
+        let anchors = yaml_anchors(text).unwrap();
+        let summary: Vec<_> = anchors
+            .iter()
+            .map(|a| {
+                let aliases: Vec<_> = a.aliases.iter().map(|x| x.path.to_string()).collect();
+                (a.name.as_str(), a.path.to_string(), aliases)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("base", "base".to_string(), vec!["key".to_string()]),
+                (
+                    "tags",
+                    "base.tags".to_string(),
+                    vec!["jobs[0].tags".to_string()]
+                ),
+                ("first", "jobs[0]".to_string(), vec!["jobs[1]".to_string()]),
+            ]
+        );
+        assert_eq!((anchors[1].line, anchors[1].column), (2, 15));
+        assert_eq!(
+            (anchors[2].aliases[0].line, anchors[2].aliases[0].column),
+            (5, 5)
+        );
+    }
+
+    #[test]
+    fn rejects_billion_laughs_in_parse_yaml() {
+        let text = "\
+a: &a [x, x, x, x, x, x, x, x, x, x]
+b: &b [*a, *a, *a, *a, *a, *a, *a, *a, *a, *a]
+c: &c [*b, *b, *b, *b, *b, *b, *b, *b, *b, *b]
+d: &d [*c, *c, *c, *c, *c, *c, *c, *c, *c, *c]
+e: [*d, *d, *d, *d, *d, *d, *d, *d, *d, *d]
+";
+        assert!(matches!(
+            kinds(text, &AliasLimits::default())[..],
+            [AliasIssueKind::ExpansionTooLarge { nodes }] if nodes > 100_000
+        ));
+
+        let opts = FormatOptions {
+            yaml_aliases: Some(AliasLimits::default()),
+            ..Default::default()
+        };
+        let err = crate::parse_yaml::<JsonValue>(text, Some(opts)).unwrap_err();
+        assert!(err.to_string().starts_with("unsafe YAML aliases: "));
+    }
+}
@@ -0,0 +1,668 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+use serde_json::Value as JsonValue;
+use yaml_rust2::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust2::scanner::{Marker, TScalarStyle};
+
+use crate::path::{Path, PathSegment};
+
+/// A YAML document edited in place, so comments, blank lines, key order
+/// and quoting survive everywhere except the values that change.
+///
+/// Edits splice new text into the original instead of re-serializing the
+/// whole file: replacing a scalar keeps its quote style and trailing
+/// comment, and new keys are appended to their mapping at its
+/// indentation. New collections are written in flow style (`[a, b]`).
+///
+/// ```
+/// use c12_parser::{Path, parse_yaml_document};
+///
+/// let text = "# CI\nimage: node:20 # runtime\nversion: \"1.2.3\"\n";
+/// let mut doc = parse_yaml_document(text)?;
+/// doc.set(&Path::root().key("version"), "1.3.0".into())?;
+/// assert_eq!(doc.to_string(), "# CI\nimage: node:20 # runtime\nversion: \"1.3.0\"\n");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct YamlDocument {
+    text: String,
+}
+
+/// Parses YAML text into an editable [`YamlDocument`].
+pub fn parse_yaml_document(text: &str) -> Result<YamlDocument, serde_yaml::Error> {
+    text.parse()
+}
+
+/// Returned when a [`YamlDocument`] edit cannot be applied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct YamlEditError {
+    pub path: Path,
+    pub message: String,
+}
+
+impl fmt::Display for YamlEditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot edit `{}`: {}", self.path, self.message)
+    }
+}
+
+impl std::error::Error for YamlEditError {}
+
+impl FromStr for YamlDocument {
+    type Err = serde_yaml::Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        serde_yaml::from_str::<serde::de::IgnoredAny>(text)?;
+        Ok(Self {
+            text: text.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for YamlDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl YamlDocument {
+    /// The whole document in the shared value model.
+    pub fn to_value(&self) -> JsonValue {
+        // Validated on parse and kept valid by every edit.
+        serde_yaml::from_str(&self.text).unwrap_or(JsonValue::Null)
+    }
+
+    /// The value at `path`, if present.
+    pub fn get(&self, path: &Path) -> Option<JsonValue> {
+        path.lookup(&self.to_value()).cloned()
+    }
+
+    /// Sets the value at `path`. Missing keys are appended to their
+    /// mapping, creating missing parent mappings in flow style, and an
+    /// array index one past the end appends to the sequence.
+    pub fn set(&mut self, path: &Path, value: JsonValue) -> Result<(), YamlEditError> {
+        let error = |message: &str| YamlEditError {
+            path: path.clone(),
+            message: message.to_string(),
+        };
+        let nodes = self.index().map_err(|e| error(&e))?;
+        if let Some(node) = nodes.get(path) {
+            let style = match node.kind {
+                Kind::Scalar(TScalarStyle::Literal | TScalarStyle::Folded) | Kind::Opaque => {
+                    return Err(error("block scalars and aliases cannot be replaced"));
+                }
+                Kind::Scalar(style) => style,
+                Kind::Flow { .. } => TScalarStyle::Plain,
+                Kind::BlockMapping { .. } | Kind::BlockSequence { .. } => {
+                    return Err(error("block collections cannot be replaced"));
+                }
+            };
+            self.text
+                .replace_range(node.start..node.end, &render(&value, style));
+            return Ok(());
+        }
+
+        let (last, parent_path) = match (path.segments().last(), path.parent()) {
+            (Some(last), Some(parent)) => (last, parent),
+            _ => return Err(error("the document is empty")),
+        };
+        let Some(parent) = nodes.get(&parent_path) else {
+            // Create the missing parent as a flow mapping holding the value.
+            return match last {
+                PathSegment::Key(key) => {
+                    let mut map = serde_json::Map::new();
+                    map.insert(key.clone(), value);
+                    self.set(&parent_path, JsonValue::Object(map))
+                        .map_err(|_| error("parent does not exist"))
+                }
+                PathSegment::Index(_) => Err(error("parent does not exist")),
+            };
+        };
+        let entry = match (last, &parent.kind) {
+            (PathSegment::Key(key), Kind::BlockMapping { .. } | Kind::Flow { open: '{', .. }) => {
+                format!(
+                    "{}: {}",
+                    render_key(key),
+                    render(&value, TScalarStyle::Plain)
+                )
+            }
+            (
+                PathSegment::Index(index),
+                Kind::BlockSequence { len, .. } | Kind::Flow { open: '[', len },
+            ) if index == len => render(&value, TScalarStyle::Plain),
+            (PathSegment::Index(_), _) => return Err(error("index is out of bounds")),
+            _ => return Err(error("parent has the wrong type")),
+        };
+        match parent.kind {
+            Kind::BlockMapping { column } => {
+                self.insert_line(parent.end, &format!("{}{entry}", " ".repeat(column)));
+            }
+            Kind::BlockSequence { column, .. } => {
+                self.insert_line(parent.end, &format!("{}- {entry}", " ".repeat(column)));
+            }
+            Kind::Flow { len, .. } => {
+                let at = parent.end - 1;
+                let sep = if len == 0 { "" } else { ", " };
+                self.text.insert_str(at, &format!("{sep}{entry}"));
+            }
+            _ => unreachable!("checked above"),
+        }
+        Ok(())
+    }
+
+    /// Inserts `key` into the mapping holding `after`, on the line after
+    /// its entry in a block mapping or right after it in a flow mapping.
+    pub fn insert_after(
+        &mut self,
+        after: &Path,
+        key: &str,
+        value: JsonValue,
+    ) -> Result<(), YamlEditError> {
+        let error = |message: &str| YamlEditError {
+            path: after.clone(),
+            message: message.to_string(),
+        };
+        let parent_path = match (after.segments().last(), after.parent()) {
+            (Some(PathSegment::Key(_)), Some(parent)) => parent,
+            _ => return Err(error("not a mapping key")),
+        };
+        let nodes = self.index().map_err(|e| error(&e))?;
+        let node = nodes
+            .get(after)
+            .ok_or_else(|| error("key does not exist"))?;
+        if nodes.contains_key(&parent_path.clone().key(key)) {
+            return Err(error(&format!("`{key}` already exists")));
+        }
+        let entry = format!(
+            "{}: {}",
+            render_key(key),
+            render(&value, TScalarStyle::Plain)
+        );
+        match (nodes.get(&parent_path).map(|p| p.kind), node.kind) {
+            (_, Kind::Opaque) => {
+                return Err(error("cannot insert after aliases and multi-line scalars"));
+            }
+            (Some(Kind::BlockMapping { column }), _) => {
+                self.insert_line(node.end, &format!("{}{entry}", " ".repeat(column)));
+            }
+            (Some(Kind::Flow { open: '{', .. }), _) => {
+                self.text.insert_str(node.end, &format!(", {entry}"));
+            }
+            _ => return Err(error("parent is not a mapping")),
+        }
+        Ok(())
+    }
+
+    /// Removes the value at `path` from a block mapping or sequence,
+    /// returning it. The entry's lines go with it; comments on the lines
+    /// above are kept.
+    pub fn remove(&mut self, path: &Path) -> Option<JsonValue> {
+        let old = self.get(path)?;
+        let nodes = self.index().ok()?;
+        let node = nodes.get(path)?;
+        let parent = nodes.get(&path.parent()?)?;
+        if !matches!(
+            parent.kind,
+            Kind::BlockMapping { .. } | Kind::BlockSequence { .. }
+        ) {
+            return None;
+        }
+        let start = self.text[..node.entry_start]
+            .rfind('\n')
+            .map_or(0, |i| i + 1);
+        let end = self.text[node.end..]
+            .find('\n')
+            .map_or(self.text.len(), |i| node.end + i + 1);
+        self.text.replace_range(start..end, "");
+        Some(old)
+    }
+
+    /// Rewrites the plain scalar at `path` through `edit`, which gets and
+    /// returns its source text, such as `0x1F`.
+    pub(crate) fn rewrite_number(
+        &mut self,
+        path: &Path,
+        edit: impl FnOnce(&str) -> Result<String, String>,
+    ) -> Result<(), YamlEditError> {
+        let error = |message: String| YamlEditError {
+            path: path.clone(),
+            message,
+        };
+        let nodes = self.index().map_err(error)?;
+        let node = nodes
+            .get(path)
+            .ok_or_else(|| error("does not exist".into()))?;
+        if !matches!(node.kind, Kind::Scalar(TScalarStyle::Plain)) {
+            return Err(error("not a plain scalar".into()));
+        }
+        let raw = edit(&self.text[node.start..node.end]).map_err(error)?;
+        self.text.replace_range(node.start..node.end, &raw);
+        Ok(())
+    }
+
+    /// Inserts `line` on a new line after the one containing `after`.
+    fn insert_line(&mut self, after: usize, line: &str) {
+        match self.text[after..].find('\n') {
+            Some(i) => self.text.insert_str(after + i + 1, &format!("{line}\n")),
+            None => self.text.push_str(&format!("\n{line}")),
+        }
+    }
+
+    /// Byte offset where the entry at `path` starts: its key inside a
+    /// mapping, the node itself elsewhere.
+    pub(crate) fn entry_offset(&self, path: &Path) -> Option<usize> {
+        Some(self.index().ok()?.get(path)?.entry_start)
+    }
+
+    /// Byte range of each entry below the root, from its key to the end
+    /// of its value.
+    pub(crate) fn entry_spans(&self) -> Option<HashMap<Path, Range<usize>>> {
+        let nodes = self.index().ok()?;
+        Some(
+            nodes
+                .into_iter()
+                .filter(|(path, _)| !path.is_root())
+                .map(|(path, node)| (path, node.entry_start..node.end))
+                .collect(),
+        )
+    }
+
+    fn index(&self) -> Result<HashMap<Path, Node>, String> {
+        let mut indexer = Indexer {
+            text: &self.text,
+            offsets: self
+                .text
+                .char_indices()
+                .map(|(i, _)| i)
+                .chain([self.text.len()])
+                .collect(),
+            frames: Vec::new(),
+            nodes: HashMap::new(),
+            last_end: 0,
+            open_block_scalar: None,
+        };
+        Parser::new_from_str(&self.text)
+            .load(&mut indexer, false)
+            .map_err(|e| e.to_string())?;
+        Ok(indexer.nodes)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Kind {
+    Scalar(TScalarStyle),
+    /// An alias or multi-line plain scalar, which edits leave alone.
+    Opaque,
+    BlockMapping {
+        column: usize,
+    },
+    BlockSequence {
+        column: usize,
+        len: usize,
+    },
+    Flow {
+        open: char,
+        len: usize,
+    },
+}
+
+/// Where a node sits in the text, as byte offsets. `entry_start` is the
+/// start of its key for mapping values, of the node itself otherwise.
+#[derive(Clone, Copy, Debug)]
+struct Node {
+    entry_start: usize,
+    start: usize,
+    end: usize,
+    kind: Kind,
+}
+
+enum Frame {
+    Map {
+        path: Path,
+        key: Option<(String, usize)>,
+        len: usize,
+    },
+    Seq {
+        path: Path,
+        len: usize,
+    },
+}
+
+struct Indexer<'a> {
+    text: &'a str,
+    /// Byte offset of each char index, since markers count chars.
+    offsets: Vec<usize>,
+    frames: Vec<Frame>,
+    nodes: HashMap<Path, Node>,
+    /// End of the most recently finished node.
+    last_end: usize,
+    /// A literal or folded scalar whose end is only known from the next
+    /// event's position.
+    open_block_scalar: Option<Path>,
+}
+
+impl Indexer<'_> {
+    /// Claims the next slot in the current collection. Returns `None` for
+    /// a mapping key, which is recorded on the frame instead.
+    fn next_path(&mut self, key: Option<(&str, usize)>) -> Option<(Path, usize)> {
+        match self.frames.last_mut() {
+            None => Some((Path::root(), usize::MAX)),
+            Some(Frame::Map {
+                key: slot @ None, ..
+            }) => {
+                *slot = Some(key.map_or_else(Default::default, |(k, at)| (k.to_string(), at)));
+                None
+            }
+            Some(Frame::Map { path, key, len }) => {
+                let (key, at) = key.take().expect("matched above");
+                *len += 1;
+                Some((path.clone().key(key), at))
+            }
+            Some(Frame::Seq { path, len }) => {
+                *len += 1;
+                Some((path.clone().index(*len - 1), usize::MAX))
+            }
+        }
+    }
+
+    fn scalar_end(&self, start: usize, value: &str, style: TScalarStyle) -> Option<usize> {
+        let rest = &self.text[start..];
+        match style {
+            TScalarStyle::Plain => rest.starts_with(value).then(|| start + value.len()),
+            TScalarStyle::DoubleQuoted => {
+                let mut escaped = false;
+                rest.char_indices().skip(1).find_map(|(i, c)| {
+                    match (escaped, c) {
+                        (false, '"') => return Some(start + i + 1),
+                        (false, '\\') => escaped = true,
+                        _ => escaped = false,
+                    }
+                    None
+                })
+            }
+            TScalarStyle::SingleQuoted => {
+                let bytes = rest.as_bytes();
+                let mut i = 1;
+                while i < bytes.len() {
+                    if bytes[i] == b'\'' {
+                        if bytes.get(i + 1) == Some(&b'\'') {
+                            i += 2;
+                            continue;
+                        }
+                        return Some(start + i + 1);
+                    }
+                    i += 1;
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+impl MarkedEventReceiver for Indexer<'_> {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        let at = self.offsets[mark.index().min(self.offsets.len() - 1)];
+        if let Some(path) = self.open_block_scalar.take() {
+            let end = self.text[..at].trim_end().len();
+            if let Some(node) = self.nodes.get_mut(&path) {
+                node.end = end;
+            }
+            self.last_end = end;
+        }
+        match event {
+            Event::Scalar(value, style, _, _) => {
+                let Some((path, key_at)) = self.next_path(Some((&value, at))) else {
+                    return;
+                };
+                let end = self.scalar_end(at, &value, style);
+                let (kind, end) = match (style, end) {
+                    (TScalarStyle::Literal | TScalarStyle::Folded, _) => {
+                        self.open_block_scalar = Some(path.clone());
+                        (Kind::Scalar(style), at)
+                    }
+                    (_, Some(end)) => (Kind::Scalar(style), end),
+                    (_, None) => (Kind::Opaque, at),
+                };
+                self.last_end = end;
+                self.nodes.insert(
+                    path,
+                    Node {
+                        entry_start: key_at.min(at),
+                        start: at,
+                        end,
+                        kind,
+                    },
+                );
+            }
+            Event::Alias(_) => {
+                if let Some((path, key_at)) = self.next_path(None) {
+                    let node = Node {
+                        entry_start: key_at.min(at),
+                        start: at,
+                        end: at,
+                        kind: Kind::Opaque,
+                    };
+                    self.nodes.insert(path, node);
+                }
+            }
+            Event::MappingStart(..) | Event::SequenceStart(..) => {
+                let is_map = matches!(event, Event::MappingStart(..));
+                let Some((path, key_at)) = self.next_path(None) else {
+                    return;
+                };
+                let open = self.text[at..].chars().next().unwrap_or(' ');
+                let kind = match (is_map, open) {
+                    (true, '{') | (false, '[') => Kind::Flow { open, len: 0 },
+                    // Block mapping columns come from the first key.
+                    (true, _) => Kind::BlockMapping { column: 0 },
+                    (false, _) => Kind::BlockSequence {
+                        column: mark.col(),
+                        len: 0,
+                    },
+                };
+                self.nodes.insert(
+                    path.clone(),
+                    Node {
+                        entry_start: key_at.min(at),
+                        start: at,
+                        end: at,
+                        kind,
+                    },
+                );
+                self.frames.push(if is_map {
+                    Frame::Map {
+                        path,
+                        key: None,
+                        len: 0,
+                    }
+                } else {
+                    Frame::Seq { path, len: 0 }
+                });
+            }
+            Event::MappingEnd | Event::SequenceEnd => {
+                let (path, count) = match self.frames.pop() {
+                    Some(Frame::Map { path, len, .. }) | Some(Frame::Seq { path, len }) => {
+                        (path, len)
+                    }
+                    None => return,
+                };
+                let first_key_column = self.first_key_column(&path);
+                let Some(node) = self.nodes.get_mut(&path) else {
+                    return;
+                };
+                match &mut node.kind {
+                    Kind::Flow { len, .. } => {
+                        *len = count;
+                        node.end = at + 1;
+                    }
+                    Kind::BlockSequence { len, .. } => {
+                        *len = count;
+                        node.end = self.last_end;
+                    }
+                    Kind::BlockMapping { column } => {
+                        *column = first_key_column;
+                        node.end = self.last_end;
+                    }
+                    _ => {}
+                }
+                self.last_end = node.end;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Indexer<'_> {
+    /// The column of the first key of the mapping at `path`, from the
+    /// `entry_start` of its first value.
+    fn first_key_column(&self, path: &Path) -> usize {
+        self.nodes
+            .iter()
+            .filter(|(p, _)| p.parent().as_ref() == Some(path))
+            .map(|(_, node)| node.entry_start)
+            .min()
+            .map_or(0, |at| {
+                let line_start = self.text[..at].rfind('\n').map_or(0, |i| i + 1);
+                self.text[line_start..at].chars().count()
+            })
+    }
+}
+
+/// Writes a key, quoting it only when it would not read back as itself.
+fn render_key(key: &str) -> String {
+    render(&JsonValue::String(key.to_string()), TScalarStyle::Plain)
+}
+
+/// Writes `value` on one line, strings in `style` where possible and
+/// collections in flow style.
+fn render(value: &JsonValue, style: TScalarStyle) -> String {
+    match value {
+        JsonValue::String(s) if s.contains('\n') => JsonValue::from(s.as_str()).to_string(),
+        JsonValue::String(s) => match style {
+            TScalarStyle::DoubleQuoted => value.to_string(),
+            TScalarStyle::SingleQuoted => format!("'{}'", s.replace('\'', "''")),
+            _ => {
+                let plain = serde_yaml::to_string(s).unwrap_or_default();
+                let plain = plain.trim_end_matches('\n');
+                // Flow indicators would end the scalar inside `[..]`/`{..}`.
+                if plain.contains(['[', ']', '{', '}', ',']) && !plain.starts_with(['\'', '"']) {
+                    value.to_string()
+                } else {
+                    plain.to_string()
+                }
+            }
+        },
+        JsonValue::Array(items) => {
+            let items: Vec<_> = items
+                .iter()
+                .map(|v| render(v, TScalarStyle::Plain))
+                .collect();
+            format!("[{}]", items.join(", "))
+        }
+        JsonValue::Object(map) => {
+            let entries: Vec<_> = map
+                .iter()
+                .map(|(k, v)| format!("{}: {}", render_key(k), render(v, TScalarStyle::Plain)))
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        }
+        _ => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const WORKFLOW: &str = "\
+# Release pipeline
+name: release   # shown in the UI
+
+env:
+  VERSION: '1.2.3'
+  NODE: \"20\"
+
+steps:
+  - run: npm ci
+  - run: npm test # keep
+";
+
+    fn path(expr: &str) -> Path {
+        expr.parse().unwrap()
+    }
+
+    #[test]
+    fn replaces_scalars_in_place() {
+        let mut doc = parse_yaml_document(WORKFLOW).unwrap();
+        doc.set(&path("env.VERSION"), json!("1.3.0")).unwrap();
+        doc.set(&path("env.NODE"), json!("22")).unwrap();
+        doc.set(&path("steps[1].run"), json!("npm run test:ci"))
+            .unwrap();
+        doc.set(&path("name"), json!(["a", "b, c"])).unwrap();
+        assert_eq!(
+            doc.to_string(),
+            WORKFLOW
+                .replace("'1.2.3'", "'1.3.0'")
+                .replace("\"20\"", "\"22\"")
+                .replace("npm test", "npm run test:ci")
+                .replace("release   #", "[a, \"b, c\"]   #")
+        );
+        assert_eq!(doc.get(&path("name[1]")), Some(json!("b, c")));
+    }
+
+    #[test]
+    fn appends_and_removes_entries() {
+        let mut doc = parse_yaml_document(WORKFLOW).unwrap();
+        doc.set(&path("env.CI"), json!(true)).unwrap();
+        doc.set(&path("steps[2]"), json!({ "run": "npm publish" }))
+            .unwrap();
+        doc.set(&path("cache.key"), json!("deps")).unwrap();
+        doc.insert_after(&path("env.VERSION"), "TAG", json!("latest"))
+            .unwrap();
+        doc.insert_after(&path("cache.key"), "paths", json!(["~/.npm"]))
+            .unwrap();
+        assert_eq!(
+            doc.remove(&path("steps[0]")),
+            Some(json!({ "run": "npm ci" }))
+        );
+        assert_eq!(
+            doc.to_string(),
+            "\
+# Release pipeline
+name: release   # shown in the UI
+
+env:
+  VERSION: '1.2.3'
+  TAG: latest
+  NODE: \"20\"
+  CI: true
+
+steps:
+  - run: npm test # keep
+  - {run: npm publish}
+cache: {key: deps, paths: [~/.npm]}
+"
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_edits() {
+        let mut doc = parse_yaml_document("a: |\n  text\nb:\n  - 1\n").unwrap();
+        assert!(doc.set(&path("a"), json!("x")).is_err());
+        assert!(doc.set(&path("b"), json!([2])).is_err());
+        let err = doc.set(&path("b[5]"), json!(2)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot edit `b[5]`: index is out of bounds"
+        );
+        assert!(doc.insert_after(&path("b[0]"), "x", json!(1)).is_err());
+        assert!(doc.insert_after(&path("a"), "b", json!(1)).is_err());
+        doc.set(&path("c"), json!(1)).unwrap();
+        assert_eq!(doc.to_string(), "a: |\n  text\nb:\n  - 1\nc: 1\n");
+    }
+}
@@ -1,23 +1,70 @@
-use serde::{Serialize, de::DeserializeOwned};
+use serde::Serialize;
+use serde::de::{DeserializeOwned, DeserializeSeed};
 
-use crate::format::{FormatOptions, Formatted};
+use crate::emit_yaml::{to_yaml_string, to_yaml_string_with_anchors};
+use crate::empty::{empty_value, is_empty_document};
+use crate::format::{Format, FormatOptions, Formatted, QuoteStyle, StyleProfile, compute_indent};
+use crate::interpolate::prepared;
+use crate::limits::{Limited, parse_limited};
+use crate::loss::{DataLoss, parse_losses};
+use crate::roundtrip::guard;
+use crate::yaml_aliases::{YamlAliasError, check_yaml_aliases, yaml_anchors};
 
 /// Parses a YAML string into a value, capturing outer whitespace only.
 pub fn parse_yaml<T>(
     text: &str,
     options: Option<FormatOptions>,
 ) -> Result<Formatted<T>, serde_yaml::Error>
+where
+    T: DeserializeOwned,
+{
+    parse_yaml_limited(text, options).map_err(Limited::into_backend)
+}
+
+/// Like [`parse_yaml`], keeping a [`LimitExceeded`](crate::LimitExceeded)
+/// typed.
+pub(crate) fn parse_yaml_limited<T>(
+    text: &str,
+    options: Option<FormatOptions>,
+) -> Result<Formatted<T>, Limited<serde_yaml::Error>>
 where
     T: DeserializeOwned,
 {
     let mut opts = options.unwrap_or_default();
     // Comments are not preserved; indentation is not preserved in the JS version.
     opts.preserve_indentation = false;
-    let value = serde_yaml::from_str(text)?;
-    Ok(Formatted::new(text, value, &opts))
+    if opts.fail_on_loss {
+        DataLoss::check(parse_losses(text, Format::Yaml))
+            .map_err(<serde_yaml::Error as serde::de::Error>::custom)?;
+    }
+    if let Some(limits) = &opts.yaml_aliases {
+        YamlAliasError::check(check_yaml_aliases(text, limits)?)
+            .map_err(<serde_yaml::Error as serde::de::Error>::custom)?;
+    }
+    let value = if is_empty_document(text, Format::Yaml) {
+        empty_value::<_, serde_yaml::Error>(opts.on_empty)?
+    } else if opts.parses_directly() {
+        serde_yaml::from_str(text)?
+    } else {
+        let value = parse_limited(&opts.limits, |seed| {
+            seed.deserialize(serde_yaml::Deserializer::from_str(text))
+        })?;
+        prepared::<_, serde_yaml::Error>(value, &opts)?
+    };
+    let mut formatted = Formatted::new(text, value, &opts);
+    if opts.keep_yaml_anchors {
+        formatted.format.yaml_anchors = yaml_anchors(text)?;
+    }
+    Ok(formatted)
 }
 
 /// Stringifies a YAML value with preserved outer whitespace.
+///
+/// Anchors recorded by `parse_yaml` (see
+/// [`keep_yaml_anchors`](FormatOptions::keep_yaml_anchors)) are written
+/// back where the anchored value is still present, and each alias whose
+/// value is unchanged is written as `*name` again; edited aliases are
+/// written out in full.
 pub fn stringify_yaml<T>(
     formatted: &Formatted<T>,
     options: Option<FormatOptions>,
@@ -25,21 +72,148 @@ pub fn stringify_yaml<T>(
 where
     T: Serialize,
 {
-    let _opts = options.unwrap_or_default();
+    let opts = options.unwrap_or_default();
 
-    // We let serde_yaml handle inner indentation and only restore the
-    // outer whitespace captured during parsing.
-    let yaml_str = serde_yaml::to_string(&formatted.value)?;
+    let yaml_str = if !formatted.format.yaml_anchors.is_empty() {
+        let value = serde_json::to_value(&formatted.value)
+            .map_err(<serde_yaml::Error as serde::ser::Error>::custom)?;
+        let indent = compute_indent(&formatted.format, &opts);
+        to_yaml_string_with_anchors(&value, indent, &formatted.format.yaml_anchors)
+    } else if opts.profile == StyleProfile::Confbox {
+        let value = serde_json::to_value(&formatted.value)
+            .map_err(<serde_yaml::Error as serde::ser::Error>::custom)?;
+        let indent = compute_indent(&formatted.format, &opts);
+        to_yaml_string(&value, indent)
+    } else {
+        // We let serde_yaml handle inner indentation and only restore the
+        // outer whitespace captured during parsing.
+        let yaml_str = serde_yaml::to_string(&formatted.value)?;
+        if opts.escaping.quote_style == Some(QuoteStyle::Double) {
+            requote_double(&yaml_str)
+        } else {
+            yaml_str
+        }
+    };
 
-    Ok(format!(
+    let out = format!(
         "{}{}{}",
         formatted.format.whitespace_start, yaml_str, formatted.format.whitespace_end
-    ))
+    );
+    if opts.verify_round_trip {
+        guard::<_, serde_yaml::Error>(&formatted.value, &out, |text| {
+            serde_yaml::from_str(text).map_err(|e| e.to_string())
+        })?;
+    }
+    Ok(out)
+}
+
+/// Rewrites the single-quoted scalars produced by serde_yaml as
+/// double-quoted ones. serde_yaml only emits double quotes when escapes
+/// are required, so the reverse direction never needs rewriting.
+///
+/// Lines in serde_yaml output have the shape `indent ("- ")* [key ":"] [value]`,
+/// with block scalar bodies (after `|` or `>`) copied verbatim.
+fn requote_double(yaml: &str) -> String {
+    let mut out = String::with_capacity(yaml.len());
+    let mut block_indent: Option<usize> = None;
+
+    for line in yaml.split_inclusive('\n') {
+        let content = line.trim_start_matches(' ');
+        let indent = line.len() - content.len();
+
+        if let Some(parent) = block_indent {
+            if content.trim().is_empty() || indent > parent {
+                out.push_str(line);
+                continue;
+            }
+            block_indent = None;
+        }
+
+        out.push_str(&line[..indent]);
+        let mut rest = content;
+        while let Some(tail) = rest.strip_prefix("- ") {
+            out.push_str("- ");
+            rest = tail;
+        }
+
+        let (first, tail) = split_scalar(rest);
+        out.push_str(&requote_scalar(first));
+        let value = if let Some(value) = tail.strip_prefix(": ") {
+            out.push_str(": ");
+            let (value, after) = split_scalar(value);
+            out.push_str(&requote_scalar(value));
+            out.push_str(after);
+            value
+        } else {
+            out.push_str(tail);
+            first
+        };
+
+        if value.starts_with('|') || value.starts_with('>') {
+            block_indent = Some(indent);
+        }
+    }
+
+    out
+}
+
+/// Splits a leading scalar token (quoted, or plain up to `": "`) from the
+/// rest of a line.
+fn split_scalar(text: &str) -> (&str, &str) {
+    let end = if text.starts_with('\'') {
+        let mut chars = text.char_indices().skip(1).peekable();
+        let mut end = text.len();
+        while let Some((i, c)) = chars.next() {
+            if c == '\'' {
+                if chars.peek().is_some_and(|&(_, next)| next == '\'') {
+                    chars.next();
+                } else {
+                    end = i + 1;
+                    break;
+                }
+            }
+        }
+        end
+    } else if text.starts_with('"') {
+        let mut escaped = false;
+        text.char_indices()
+            .skip(1)
+            .find(|&(_, c)| {
+                let closes = c == '"' && !escaped;
+                escaped = c == '\\' && !escaped;
+                closes
+            })
+            .map_or(text.len(), |(i, _)| i + 1)
+    } else {
+        text.find(": ")
+            .or_else(|| text.trim_end().strip_suffix(':').map(str::len))
+            .unwrap_or_else(|| text.trim_end_matches(['\r', '\n']).len())
+    };
+    text.split_at(end)
+}
+
+fn requote_scalar(token: &str) -> String {
+    let Some(inner) = token.strip_prefix('\'').and_then(|t| t.strip_suffix('\'')) else {
+        return token.to_string();
+    };
+
+    let mut out = String::with_capacity(token.len() + 2);
+    out.push('"');
+    for c in inner.replace("''", "'").chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fixtures::FLOAT;
     use serde_json::Value as JsonValue;
 
     const YAML_FIXTURE: &str = r#"
@@ -92,7 +266,7 @@ types:
         );
         assert_eq!(
             types.get(&serde_yaml::Value::String("float".into())),
-            Some(&serde_yaml::Value::Number(serde_yaml::Number::from(3.14)))
+            Some(&serde_yaml::Value::Number(serde_yaml::Number::from(FLOAT)))
         );
         assert_eq!(
             types.get(&serde_yaml::Value::String("string".into())),
@@ -131,6 +305,38 @@ types:
         assert_eq!(out_val, expected_val);
     }
 
+    #[test]
+    fn yaml_stringify_uses_double_quotes_when_requested() {
+        let text = "'null': 'true'\nlist:\n- '123'\n- it's\nblock: |-\n  'kept'\n  as is\npath: 'C: \\dir'";
+        let formatted = parse_yaml::<serde_yaml::Value>(text, None).unwrap();
+        let mut opts = FormatOptions::default();
+        opts.escaping.quote_style = Some(QuoteStyle::Double);
+
+        let out = stringify_yaml(&formatted, Some(opts)).unwrap();
+        assert_eq!(
+            out,
+            "\"null\": \"true\"\nlist:\n- \"123\"\n- it's\nblock: |-\n  'kept'\n  as is\npath: \"C: \\\\dir\"\n"
+        );
+        let reparsed: serde_yaml::Value = serde_yaml::from_str(&out).unwrap();
+        assert_eq!(reparsed, formatted.value);
+    }
+
+    #[test]
+    fn yaml_confbox_profile_matches_js_yaml() {
+        let text = "types:\n  array:\n  - 1\n  - 2\n  date: '1979-05-27T15:32:00.000Z'\n  'null': null\n  object:\n    key: value\n";
+        let formatted = parse_yaml::<JsonValue>(text, None).unwrap();
+        let opts = FormatOptions {
+            profile: StyleProfile::Confbox,
+            ..Default::default()
+        };
+
+        let out = stringify_yaml(&formatted, Some(opts)).unwrap();
+        assert_eq!(
+            out,
+            "types:\n  array:\n    - 1\n    - 2\n  date: '1979-05-27T15:32:00.000Z'\n  'null': null\n  object:\n    key: value\n\n"
+        );
+    }
+
     #[test]
     fn yaml_preserves_outer_whitespace() {
         let text = " \ntypes:\n  key: value\n\n";
@@ -140,4 +346,44 @@ types:
         assert!(out.starts_with(" \n"));
         assert!(out.ends_with("\n\n"));
     }
+
+    #[test]
+    fn yaml_verify_round_trip_accepts_faithful_output() {
+        let text = "list:\n  - 'yes'\n  - |\n    multi\n    line\n";
+        let formatted = parse_yaml::<JsonValue>(text, None).unwrap();
+        for profile in [StyleProfile::Native, StyleProfile::Confbox] {
+            let opts = FormatOptions {
+                profile,
+                verify_round_trip: true,
+                ..Default::default()
+            };
+            assert!(stringify_yaml(&formatted, Some(opts)).is_ok());
+        }
+    }
+
+    #[test]
+    fn yaml_keeps_anchors_and_unchanged_aliases() {
+        let text = "defaults: &defaults\n  retries: 3\n  hosts: &hosts\n    - a\n    - b\nprod:\n  http: *defaults\n  grpc: *defaults\n  mirrors: *hosts\n";
+        let opts = FormatOptions {
+            keep_yaml_anchors: true,
+            verify_round_trip: true,
+            ..Default::default()
+        };
+        let mut formatted = parse_yaml::<JsonValue>(text, Some(opts.clone())).unwrap();
+        // The emitter ends with a newline of its own before the captured
+        // trailing whitespace.
+        let out = stringify_yaml(&formatted, Some(opts.clone())).unwrap();
+        assert_eq!(out, format!("{text}\n"));
+
+        formatted.value["prod"]["grpc"]["retries"] = 5.into();
+        let out = stringify_yaml(&formatted, Some(opts)).unwrap();
+        assert_eq!(
+            out,
+            "defaults: &defaults\n  retries: 3\n  hosts: &hosts\n    - a\n    - b\nprod:\n  http: *defaults\n  grpc:\n    retries: 5\n    hosts:\n      - a\n      - b\n  mirrors: *hosts\n\n"
+        );
+
+        let plain = parse_yaml::<JsonValue>(text, None).unwrap();
+        assert!(plain.format.yaml_anchors.is_empty());
+        assert!(!stringify_yaml(&plain, None).unwrap().contains('*'));
+    }
 }